@@ -0,0 +1,131 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checkpoint benchmarks.
+//!
+//! Keys share long prefixes, so that the benchmarks measure writing and loading B+tree nodes of
+//! which the keys are compressed. The number of records can be set through the
+//! `SAP_TSF_BENCH_RECORDS` environment variable, e.g.,
+//! `SAP_TSF_BENCH_RECORDS=65536 cargo bench --bench checkpoint`.
+
+use criterion::async_executor::FuturesExecutor;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use sap_tsf::{Database, Durability, IndexType, Metadata, OpenOptions};
+use std::env;
+use std::fs::remove_dir_all;
+use std::num::NonZeroU32;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// The number of records inserted by a single transaction when populating a database.
+const BATCH_SIZE: usize = 64;
+
+fn num_records() -> usize {
+    env::var("SAP_TSF_BENCH_RECORDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|v| *v != 0)
+        .unwrap_or(4096)
+}
+
+/// Returns a key sharing a long prefix with adjacent keys.
+fn key(r: usize) -> Vec<u8> {
+    format!("tenant/{:04}/user/{r:016}/profile", r / 1024).into_bytes()
+}
+
+async fn open(path: &Path) -> Database {
+    let options = OpenOptions::new()
+        .with_truncate(true)
+        .with_durability(Durability::OsBuffered);
+    Database::with_options(path, &options).await.unwrap()
+}
+
+async fn populate(database: &Database, index_type: IndexType, records: usize) {
+    let transaction = database.transaction();
+    let mut journal = transaction.journal();
+    let container = database
+        .create_container(
+            "bench".to_string(),
+            Metadata::default().with_index_type(index_type),
+            &mut journal,
+            None,
+        )
+        .await
+        .unwrap();
+    let _: NonZeroU32 = journal.submit();
+    assert!(transaction.commit().await.is_ok());
+
+    for batch in 0..records.div_ceil(BATCH_SIZE) {
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        for r in (batch * BATCH_SIZE)..((batch + 1) * BATCH_SIZE).min(records) {
+            let key = key(r);
+            assert!(container
+                .insert(&key, &key, &mut journal, None)
+                .await
+                .is_ok());
+        }
+        let _: NonZeroU32 = journal.submit();
+        assert!(transaction.commit().await.is_ok());
+    }
+}
+
+async fn write_check(index_type: IndexType, records: usize, iters: u64) -> Duration {
+    let path = Path::new("bench_checkpoint_write");
+    let database = open(path).await;
+    populate(&database, index_type, records).await;
+    let start = Instant::now();
+    for _ in 0..iters {
+        assert!(database.checkpoint(None).await.is_ok());
+    }
+    let elapsed = start.elapsed();
+    drop(database);
+    assert!(remove_dir_all(path).is_ok());
+    elapsed
+}
+
+async fn load_check(index_type: IndexType, records: usize, iters: u64) -> Duration {
+    let path = Path::new("bench_checkpoint_load");
+    let database = open(path).await;
+    populate(&database, index_type, records).await;
+    assert!(database.checkpoint(None).await.is_ok());
+    drop(database);
+    let start = Instant::now();
+    for _ in 0..iters {
+        drop(Database::with_path(path).await.unwrap());
+    }
+    let elapsed = start.elapsed();
+    assert!(remove_dir_all(path).is_ok());
+    elapsed
+}
+
+fn write(c: &mut Criterion) {
+    let records = num_records();
+    let mut group = c.benchmark_group("Checkpoint: write");
+    group.throughput(Throughput::Elements(u64::try_from(records).unwrap()));
+    for (name, index_type) in [("ordered", IndexType::Ordered), ("hash", IndexType::Hash)] {
+        group.bench_with_input(BenchmarkId::new(name, records), &records, |b, &r| {
+            b.to_async(FuturesExecutor)
+                .iter_custom(|iters| write_check(index_type, r, iters));
+        });
+    }
+    group.finish();
+}
+
+fn load(c: &mut Criterion) {
+    let records = num_records();
+    let mut group = c.benchmark_group("Checkpoint: load");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(u64::try_from(records).unwrap()));
+    for (name, index_type) in [("ordered", IndexType::Ordered), ("hash", IndexType::Hash)] {
+        group.bench_with_input(BenchmarkId::new(name, records), &records, |b, &r| {
+            b.to_async(FuturesExecutor)
+                .iter_custom(|iters| load_check(index_type, r, iters));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(checkpoint, write, load);
+criterion_main!(checkpoint);
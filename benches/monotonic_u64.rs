@@ -4,8 +4,10 @@
 
 use criterion::{criterion_group, criterion_main, Criterion};
 use sap_tsf::sequencer::ToInstant;
-use sap_tsf::{MonotonicU64, Sequencer};
-use std::sync::atomic::Ordering::Relaxed;
+use sap_tsf::{MonotonicU64, Sequencer, ShardedU64};
+use std::sync::atomic::Ordering::{Relaxed, Release};
+use std::thread;
+use std::time::{Duration, Instant};
 
 fn track_empty(c: &mut Criterion) {
     let mu = MonotonicU64::default();
@@ -29,5 +31,34 @@ fn track_non_empty(c: &mut Criterion) {
     });
 }
 
-criterion_group!(monotonic_u64, track_empty, track_non_empty);
+fn advance_check<S: Sequencer>(iters: u64) -> Duration {
+    let sequencer = S::default();
+    let start = Instant::now();
+    thread::scope(|s| {
+        for _ in 0..thread::available_parallelism().map_or(4, usize::from) {
+            s.spawn(|| {
+                for _ in 0..iters {
+                    sequencer.advance(Release);
+                }
+            });
+        }
+    });
+    start.elapsed()
+}
+
+fn advance_parallel(c: &mut Criterion) {
+    c.bench_function("MonotonicU64: parallel advance", |b| {
+        b.iter_custom(advance_check::<MonotonicU64>);
+    });
+    c.bench_function("ShardedU64: parallel advance", |b| {
+        b.iter_custom(advance_check::<ShardedU64>);
+    });
+}
+
+criterion_group!(
+    monotonic_u64,
+    track_empty,
+    track_non_empty,
+    advance_parallel
+);
 criterion_main!(monotonic_u64);
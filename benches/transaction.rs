@@ -0,0 +1,228 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Transaction benchmarks.
+//!
+//! The number of records and threads can be set through the `SAP_TSF_BENCH_RECORDS` and
+//! `SAP_TSF_BENCH_THREADS` environment variables, e.g.,
+//! `SAP_TSF_BENCH_RECORDS=65536 SAP_TSF_BENCH_THREADS=16 cargo bench --bench transaction`.
+
+use criterion::async_executor::FuturesExecutor;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use futures::executor::block_on;
+use sap_tsf::{Container, Database, Durability, FileIO, Metadata, MonotonicU64, OpenOptions};
+use scc::ebr::Shared;
+use std::env;
+use std::fs::remove_dir_all;
+use std::num::{NonZeroU32, NonZeroUsize};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+type BenchContainer = Container<MonotonicU64, FileIO<MonotonicU64>>;
+
+/// The key that all the threads update in the contention benchmark.
+const HOT_KEY: &[u8] = b"hot";
+
+/// The number of records inserted by a single transaction when populating a database.
+const BATCH_SIZE: usize = 16;
+
+fn num_records() -> usize {
+    knob("SAP_TSF_BENCH_RECORDS", 1024)
+}
+
+fn num_threads() -> usize {
+    let parallelism = thread::available_parallelism().map_or(4, NonZeroUsize::get);
+    knob("SAP_TSF_BENCH_THREADS", parallelism.min(8))
+}
+
+fn knob(name: &str, default: usize) -> usize {
+    env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|v| *v != 0)
+        .unwrap_or(default)
+}
+
+async fn open(path: &Path) -> Database {
+    let options = OpenOptions::new()
+        .with_truncate(true)
+        .with_durability(Durability::OsBuffered);
+    Database::with_options(path, &options).await.unwrap()
+}
+
+async fn populate(database: &Database, records: usize) -> Shared<BenchContainer> {
+    let transaction = database.transaction();
+    let mut journal = transaction.journal();
+    let container = database
+        .create_container("bench".to_string(), Metadata::default(), &mut journal, None)
+        .await
+        .unwrap();
+    assert!(container
+        .insert(HOT_KEY, HOT_KEY, &mut journal, None)
+        .await
+        .is_ok());
+    let _: NonZeroU32 = journal.submit();
+    assert!(transaction.commit().await.is_ok());
+
+    for batch in 0..records.div_ceil(BATCH_SIZE) {
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        for r in (batch * BATCH_SIZE)..((batch + 1) * BATCH_SIZE).min(records) {
+            let key = u64::try_from(r).unwrap().to_be_bytes();
+            assert!(container
+                .insert(&key, &key, &mut journal, None)
+                .await
+                .is_ok());
+        }
+        let _: NonZeroU32 = journal.submit();
+        assert!(transaction.commit().await.is_ok());
+    }
+    container
+}
+
+async fn commit_check(iters: u64) -> Duration {
+    let path = Path::new("bench_transaction_commit");
+    let database = Database::with_path(path).await.unwrap();
+    let container = populate(&database, 0).await;
+    let start = Instant::now();
+    for i in 0..iters {
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let key = i.to_be_bytes();
+        assert!(container
+            .insert(&key, &key, &mut journal, None)
+            .await
+            .is_ok());
+        let _: NonZeroU32 = journal.submit();
+        assert!(transaction.commit().await.is_ok());
+    }
+    let elapsed = start.elapsed();
+    drop(container);
+    drop(database);
+    assert!(remove_dir_all(path).is_ok());
+    elapsed
+}
+
+async fn update_hot_record(database: &Database, container: &BenchContainer) {
+    loop {
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let deadline = Instant::now() + Duration::from_secs(1);
+        if container
+            .update(HOT_KEY, HOT_KEY, &mut journal, Some(deadline))
+            .await
+            .is_ok()
+        {
+            let _: NonZeroU32 = journal.submit();
+            if transaction.commit().await.is_ok() {
+                return;
+            }
+        } else {
+            drop(journal);
+            transaction.rollback();
+        }
+    }
+}
+
+fn contention_check(threads: usize, iters: u64) -> Duration {
+    let path = Path::new("bench_transaction_contention");
+    let database = block_on(open(path));
+    let container = block_on(populate(&database, 0));
+    let start = Instant::now();
+    thread::scope(|s| {
+        for _ in 0..threads {
+            s.spawn(|| {
+                for _ in 0..iters {
+                    block_on(update_hot_record(&database, &container));
+                }
+            });
+        }
+    });
+    let elapsed = start.elapsed();
+    drop(container);
+    drop(database);
+    assert!(remove_dir_all(path).is_ok());
+    elapsed
+}
+
+async fn scan_check(records: usize, iters: u64) -> Duration {
+    let path = Path::new("bench_transaction_scan");
+    let database = open(path).await;
+    let container = populate(&database, records).await;
+    let start = Instant::now();
+    for _ in 0..iters {
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let pairs = container
+            .read_range::<&[u8], _>(.., &mut journal, None)
+            .await
+            .unwrap();
+        assert_eq!(pairs.len(), records + 1);
+        drop(journal);
+        transaction.rollback();
+    }
+    let elapsed = start.elapsed();
+    drop(container);
+    drop(database);
+    assert!(remove_dir_all(path).is_ok());
+    elapsed
+}
+
+async fn recovery_check(records: usize, iters: u64) -> Duration {
+    let path = Path::new("bench_transaction_recovery");
+    let mut elapsed = Duration::ZERO;
+    for _ in 0..iters {
+        let database = open(path).await;
+        drop(populate(&database, records).await);
+        drop(database);
+        let start = Instant::now();
+        let database = Database::with_path(path).await.unwrap();
+        elapsed += start.elapsed();
+        drop(database);
+    }
+    assert!(remove_dir_all(path).is_ok());
+    elapsed
+}
+
+fn commit(c: &mut Criterion) {
+    c.bench_function("Transaction: commit", |b| {
+        b.to_async(FuturesExecutor).iter_custom(commit_check);
+    });
+}
+
+fn contention(c: &mut Criterion) {
+    let threads = num_threads();
+    let mut group = c.benchmark_group("Transaction: contention");
+    group.throughput(Throughput::Elements(u64::try_from(threads).unwrap()));
+    group.bench_with_input(BenchmarkId::from_parameter(threads), &threads, |b, &t| {
+        b.iter_custom(|iters| contention_check(t, iters));
+    });
+    group.finish();
+}
+
+fn scan(c: &mut Criterion) {
+    let records = num_records();
+    let mut group = c.benchmark_group("Transaction: scan");
+    group.throughput(Throughput::Elements(u64::try_from(records).unwrap()));
+    group.bench_with_input(BenchmarkId::from_parameter(records), &records, |b, &r| {
+        b.to_async(FuturesExecutor)
+            .iter_custom(|iters| scan_check(r, iters));
+    });
+    group.finish();
+}
+
+fn recovery(c: &mut Criterion) {
+    let records = num_records();
+    let mut group = c.benchmark_group("Transaction: recovery");
+    group.sample_size(10);
+    group.bench_with_input(BenchmarkId::from_parameter(records), &records, |b, &r| {
+        b.to_async(FuturesExecutor)
+            .iter_custom(|iters| recovery_check(r, iters));
+    });
+    group.finish();
+}
+
+criterion_group!(transaction, commit, contention, scan, recovery);
+criterion_main!(transaction);
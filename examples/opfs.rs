@@ -0,0 +1,765 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! A [`PersistenceLayer`] that stores the log in a file of the Origin Private File System.
+//!
+//! [`FileIO`](sap_tsf::FileIO) is not available on `wasm32` where the standard library cannot
+//! access a file system; [`OpfsPersistence`] is built only on the public interfaces of
+//! [`PersistenceLayer`], and appends log records to a `FileSystemSyncAccessHandle` that the host
+//! opens in a dedicated worker, e.g., `await (await navigator.storage.getDirectory())
+//! .getFileHandle("db.log", { create: true })).createSyncAccessHandle()`. Databases are
+//! recovered by replaying the log, therefore no checkpoints are taken.
+//!
+//! On other targets, a [`File`](std::fs::File) takes the place of the `FileSystemSyncAccessHandle`,
+//! so that the example can be run with `cargo run --example opfs`.
+
+use sap_tsf::utils::Instant;
+use sap_tsf::{
+    AwaitIO, AwaitRecovery, Counter, Database, Error, JournalID, LogBufferInterface, Metadata,
+    MonotonicU64, PersistedIndexInterface, PersistenceLayer, Playback, RecoveryResult, Sequencer,
+    ShutdownPolicy, Telemetry, TransactionID, VersionRecord,
+};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io::ErrorKind;
+use std::mem::take;
+use std::num::{NonZeroU32, NonZeroU64};
+use std::ops::Bound;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::sync::{Arc, Mutex};
+use std::task::Waker;
+
+/// [`Device`] is a file that log records are written to.
+trait Device: 'static + Debug + Send + Sync {
+    /// Returns the size of the file.
+    fn size(&self) -> Result<u64, Error>;
+
+    /// Reads `buffer.len()` bytes at the offset.
+    fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), Error>;
+
+    /// Writes the data at the offset.
+    fn write_at(&self, offset: u64, data: &[u8]) -> Result<(), Error>;
+
+    /// Makes written data durable.
+    fn flush(&self) -> Result<(), Error>;
+}
+
+/// [`OpfsPersistence`] is a [`PersistenceLayer`] that appends log records to a [`Device`].
+#[derive(Debug)]
+struct OpfsPersistence<D: Device> {
+    /// The file storing the log.
+    device: D,
+
+    /// The offset in the file where the next log record is written.
+    end: Mutex<u64>,
+
+    /// Writing log records failed, and the log has a hole.
+    failed: AtomicBool,
+
+    /// The number of times the file was flushed.
+    flush_epoch: AtomicU64,
+
+    /// The recovered database.
+    recovery_result: Mutex<Option<Result<Database<MonotonicU64, Self>, Error>>>,
+
+    /// Statistics of the persistence layer.
+    telemetry: Telemetry,
+}
+
+/// [`OpfsLogBuffer`] is the log buffer type for [`OpfsPersistence`].
+#[derive(Debug, Default)]
+struct OpfsLogBuffer {
+    /// Encoded log records that have yet to be written to the file.
+    data: Mutex<Vec<u8>>,
+
+    /// The flush epoch since when the log buffer is durable.
+    durable_flush_epoch: AtomicU64,
+}
+
+/// [`OpfsIndex`] is the persisted index type for [`OpfsPersistence`].
+///
+/// Every version is installed in memory when the database is recovered, therefore no instances of
+/// it exist.
+#[derive(Debug)]
+enum OpfsIndex {}
+
+/// [`LogRecord`] is a change to the database stored in the log.
+///
+/// Each log record is stored as the length of the encoded log record followed by a tag and the
+/// fields of the log record in little endian; a log record that was not completely written is
+/// discarded when the log is replayed.
+#[derive(Debug)]
+enum LogRecord<'l> {
+    /// A journal created a database object.
+    JournalCreatedObject(TransactionID, JournalID, u64),
+
+    /// A journal deleted a database object.
+    JournalDeletedObject(TransactionID, JournalID, u64),
+
+    /// A journal wrote a version of a key-value pair identified as
+    /// `(container identifier, record identifier, database object identifier)`.
+    JournalWroteVersion(TransactionID, [u64; 3], &'l [u8], &'l [u8]),
+
+    /// A journal was submitted.
+    JournalSubmitted(TransactionID, JournalID, NonZeroU32),
+
+    /// A journal was discarded.
+    JournalDiscarded(TransactionID, JournalID),
+
+    /// A transaction participated in a distributed transaction.
+    TransactionParticipated(TransactionID, &'l [u8]),
+
+    /// A transaction was rewound.
+    TransactionRewound(TransactionID, Option<NonZeroU32>),
+
+    /// A transaction was prepared.
+    TransactionPrepared(TransactionID, u64),
+
+    /// A transaction was committed.
+    TransactionCommitted(TransactionID, u64),
+
+    /// The database was shut down at the instant.
+    CleanShutdown(u64),
+}
+
+impl<D: Device> OpfsPersistence<D> {
+    /// Creates a new [`OpfsPersistence`] on the [`Device`].
+    fn with_device(device: D) -> Self {
+        Self {
+            device,
+            end: Mutex::new(0),
+            failed: AtomicBool::new(false),
+            flush_epoch: AtomicU64::new(0),
+            recovery_result: Mutex::default(),
+            telemetry: Telemetry::default(),
+        }
+    }
+
+    /// Writes the log records in the log buffer to the file.
+    ///
+    /// Log records written by a failed call are overwritten by the next call, therefore no more
+    /// log records can be made durable after a failure.
+    fn write(&self, log_buffer: &OpfsLogBuffer, log_record: Option<LogRecord<'_>>) {
+        let mut data = log_buffer
+            .data
+            .lock()
+            .map(|mut d| take(&mut *d))
+            .unwrap_or_default();
+        if let Some(log_record) = log_record {
+            log_record.encode(&mut data);
+        }
+        if data.is_empty() {
+            return;
+        }
+        let Ok(mut end) = self.end.lock() else {
+            self.failed.store(true, Release);
+            return;
+        };
+        if self.device.write_at(*end, &data).is_err() {
+            self.failed.store(true, Release);
+            return;
+        }
+        *end += data.len() as u64;
+    }
+
+    /// Writes the log records in the log buffer to the file and flushes the file, and sets the
+    /// durable flush epoch of the log buffer.
+    fn sync(
+        &self,
+        log_buffer: Arc<OpfsLogBuffer>,
+        log_record: LogRecord<'_>,
+        deadline: Option<Instant>,
+    ) -> AwaitIO<'_, MonotonicU64, Self> {
+        self.write(&log_buffer, Some(log_record));
+        if self.failed.load(Acquire) {
+            return AwaitIO::with_error(self, Error::IO(ErrorKind::Other));
+        }
+        if let Err(error) = self.device.flush() {
+            return AwaitIO::with_error(self, error);
+        }
+        let flush_epoch = self.flush_epoch.fetch_add(1, Release) + 1;
+        self.telemetry.add(Counter::Fsyncs, 1);
+        log_buffer.set_durable_flush_epoch(flush_epoch);
+        AwaitIO::with_log_buffer(self, log_buffer, deadline)
+    }
+
+    /// Replays the log records in the file.
+    fn replay(
+        &self,
+        database: &Database<MonotonicU64, Self>,
+        until: Option<u64>,
+    ) -> Result<(), Error> {
+        let size = usize::try_from(self.device.size()?).map_err(|_| Error::OutOfMemory)?;
+        let mut log = vec![0; size];
+        self.device.read_at(0, &mut log)?;
+
+        let mut remaining = log.as_slice();
+        let mut playback_container: HashMap<TransactionID, Playback<MonotonicU64, Self>> =
+            HashMap::new();
+        while let Some(log_record) = LogRecord::decode(&mut remaining) {
+            match log_record {
+                LogRecord::JournalCreatedObject(transaction_id, journal_id, object_id) => {
+                    playback_container
+                        .entry(transaction_id)
+                        .or_insert_with(|| Playback::with_id(database, transaction_id))
+                        .create(journal_id, object_id);
+                }
+                LogRecord::JournalDeletedObject(transaction_id, journal_id, object_id) => {
+                    playback_container
+                        .entry(transaction_id)
+                        .or_insert_with(|| Playback::with_id(database, transaction_id))
+                        .delete(journal_id, object_id);
+                }
+                LogRecord::JournalWroteVersion(transaction_id, [c, r, o], key, value) => {
+                    playback_container
+                        .entry(transaction_id)
+                        .or_insert_with(|| Playback::with_id(database, transaction_id))
+                        .write(&VersionRecord::new(c, r, o, key, value));
+                }
+                LogRecord::JournalSubmitted(transaction_id, journal_id, submit_instant) => {
+                    if let Some(playback) = playback_container.get_mut(&transaction_id) {
+                        playback.submit_journal(journal_id, submit_instant.get());
+                    }
+                }
+                LogRecord::JournalDiscarded(transaction_id, journal_id) => {
+                    if let Some(playback) = playback_container.get_mut(&transaction_id) {
+                        playback.discard_journal(journal_id);
+                    }
+                }
+                LogRecord::TransactionParticipated(transaction_id, xid) => {
+                    playback_container
+                        .entry(transaction_id)
+                        .or_insert_with(|| Playback::with_id(database, transaction_id))
+                        .participate(xid);
+                }
+                LogRecord::TransactionRewound(transaction_id, rewind_to) => {
+                    if rewind_to.is_none() {
+                        if let Some(playback) = playback_container.remove(&transaction_id) {
+                            playback.rollback();
+                        }
+                    } else if let Some(playback) = playback_container.get_mut(&transaction_id) {
+                        playback.rewind(rewind_to);
+                    }
+                }
+                LogRecord::TransactionPrepared(transaction_id, prepare_instant) => {
+                    playback_container
+                        .entry(transaction_id)
+                        .or_insert_with(|| Playback::with_id(database, transaction_id))
+                        .prepare(prepare_instant);
+                }
+                LogRecord::TransactionCommitted(transaction_id, commit_instant) => {
+                    if until.is_some_and(|until| commit_instant > until) {
+                        break;
+                    }
+                    playback_container
+                        .remove(&transaction_id)
+                        .unwrap_or_else(|| Playback::new(database))
+                        .commit(commit_instant);
+                }
+                LogRecord::CleanShutdown(clock) => {
+                    // Clock values issued without leaving log records behind must not be reused.
+                    let _: Result<u64, u64> = database.sequencer().update(clock, Release);
+                }
+            }
+        }
+        playback_container.clear();
+
+        // A log record that was not completely written is overwritten.
+        let Ok(mut end) = self.end.lock() else {
+            return Err(Error::UnexpectedState);
+        };
+        *end = (log.len() - remaining.len()) as u64;
+        Ok(())
+    }
+}
+
+impl<'l> LogRecord<'l> {
+    /// Appends the encoded log record to the buffer.
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        let start = buffer.len();
+        buffer.extend_from_slice(&0_u32.to_le_bytes());
+        match self {
+            Self::JournalCreatedObject(t, j, o) => put(buffer, 1, &[*t, *j, *o]),
+            Self::JournalDeletedObject(t, j, o) => put(buffer, 2, &[*t, *j, *o]),
+            Self::JournalWroteVersion(t, [c, r, o], k, v) => {
+                put(buffer, 3, &[*t, *c, *r, *o]);
+                put_bytes(buffer, k);
+                put_bytes(buffer, v);
+            }
+            Self::JournalSubmitted(t, j, i) => put(buffer, 4, &[*t, *j, u64::from(i.get())]),
+            Self::JournalDiscarded(t, j) => put(buffer, 5, &[*t, *j]),
+            Self::TransactionParticipated(t, x) => {
+                put(buffer, 6, &[*t]);
+                put_bytes(buffer, x);
+            }
+            Self::TransactionRewound(t, i) => {
+                put(buffer, 7, &[*t, u64::from(i.map_or(0, NonZeroU32::get))]);
+            }
+            Self::TransactionPrepared(t, i) => put(buffer, 8, &[*t, *i]),
+            Self::TransactionCommitted(t, i) => put(buffer, 9, &[*t, *i]),
+            Self::CleanShutdown(i) => put(buffer, 10, &[*i]),
+        }
+        let len = u32::try_from(buffer.len() - start - 4).unwrap_or(u32::MAX);
+        buffer[start..start + 4].copy_from_slice(&len.to_le_bytes());
+    }
+
+    /// Decodes a log record at the beginning of the data, and advances the data past it.
+    ///
+    /// Returns `None` if the data does not start with a completely written log record.
+    fn decode(data: &mut &'l [u8]) -> Option<LogRecord<'l>> {
+        let len = usize::try_from(get_u32(data)?).ok()?;
+        let (mut fields, rest) = data.split_at_checked(len)?;
+        let fields = &mut fields;
+        let log_record = match fields.split_off_first()? {
+            1 => Self::JournalCreatedObject(get(fields)?, get(fields)?, get(fields)?),
+            2 => Self::JournalDeletedObject(get(fields)?, get(fields)?, get(fields)?),
+            3 => Self::JournalWroteVersion(
+                get(fields)?,
+                [get(fields)?, get(fields)?, get(fields)?],
+                get_bytes(fields)?,
+                get_bytes(fields)?,
+            ),
+            4 => Self::JournalSubmitted(
+                get(fields)?,
+                get(fields)?,
+                NonZeroU32::new(u32::try_from(get(fields)?).ok()?)?,
+            ),
+            5 => Self::JournalDiscarded(get(fields)?, get(fields)?),
+            6 => Self::TransactionParticipated(get(fields)?, get_bytes(fields)?),
+            7 => Self::TransactionRewound(
+                get(fields)?,
+                NonZeroU32::new(u32::try_from(get(fields)?).ok()?),
+            ),
+            8 => Self::TransactionPrepared(get(fields)?, get(fields)?),
+            9 => Self::TransactionCommitted(get(fields)?, get(fields)?),
+            10 => Self::CleanShutdown(get(fields)?),
+            _ => return None,
+        };
+        *data = rest;
+        Some(log_record)
+    }
+}
+
+impl<D: Device> PersistenceLayer<MonotonicU64> for OpfsPersistence<D> {
+    type LogBuffer = OpfsLogBuffer;
+    type PersistedIndex = OpfsIndex;
+
+    fn wait_prepare_logging() -> bool {
+        true
+    }
+
+    fn recover(
+        &self,
+        database: Database<MonotonicU64, Self>,
+        until: Option<u64>,
+        deadline: Option<Instant>,
+    ) -> Result<AwaitRecovery<'_, MonotonicU64, Self>, Error> {
+        self.replay(&database, until)?;
+        let Ok(mut recovery_result) = self.recovery_result.lock() else {
+            return Err(Error::UnexpectedState);
+        };
+        recovery_result.replace(Ok(database));
+        Ok(AwaitRecovery::new(self, deadline))
+    }
+
+    fn backup(
+        &self,
+        _database: &Database<MonotonicU64, Self>,
+        _catalog_only: bool,
+        _path: Option<&str>,
+        _deadline: Option<Instant>,
+    ) -> AwaitIO<'_, MonotonicU64, Self> {
+        AwaitIO::with_error(self, Error::Generic("backup is not supported"))
+    }
+
+    fn shutdown(
+        &self,
+        database: &Database<MonotonicU64, Self>,
+        deadline: Option<Instant>,
+    ) -> AwaitIO<'_, MonotonicU64, Self> {
+        let clock = database.sequencer().now(Acquire);
+        self.sync(Arc::default(), LogRecord::CleanShutdown(clock), deadline)
+    }
+
+    fn participate(
+        &self,
+        transaction_id: TransactionID,
+        xid: &[u8],
+        deadline: Option<Instant>,
+    ) -> AwaitIO<'_, MonotonicU64, Self> {
+        let log_record = LogRecord::TransactionParticipated(transaction_id, xid);
+        self.sync(Arc::default(), log_record, deadline)
+    }
+
+    fn create(
+        &self,
+        log_buffer: Arc<Self::LogBuffer>,
+        transaction_id: TransactionID,
+        journal_id: JournalID,
+        object_ids: &[u64],
+    ) -> Result<Arc<Self::LogBuffer>, Error> {
+        let Ok(mut data) = log_buffer.data.lock() else {
+            return Err(Error::UnexpectedState);
+        };
+        for object_id in object_ids {
+            LogRecord::JournalCreatedObject(transaction_id, journal_id, *object_id)
+                .encode(&mut data);
+        }
+        drop(data);
+        Ok(log_buffer)
+    }
+
+    fn write(
+        &self,
+        log_buffer: Arc<Self::LogBuffer>,
+        transaction_id: TransactionID,
+        _journal_id: JournalID,
+        version: &VersionRecord<'_>,
+    ) -> Result<Arc<Self::LogBuffer>, Error> {
+        let Ok(mut data) = log_buffer.data.lock() else {
+            return Err(Error::UnexpectedState);
+        };
+        LogRecord::JournalWroteVersion(
+            transaction_id,
+            [
+                version.container_id(),
+                version.record_id(),
+                version.object_id(),
+            ],
+            version.key(),
+            version.value(),
+        )
+        .encode(&mut data);
+        drop(data);
+        Ok(log_buffer)
+    }
+
+    fn delete(
+        &self,
+        log_buffer: Arc<Self::LogBuffer>,
+        transaction_id: TransactionID,
+        journal_id: JournalID,
+        object_ids: &[u64],
+    ) -> Result<Arc<Self::LogBuffer>, Error> {
+        let Ok(mut data) = log_buffer.data.lock() else {
+            return Err(Error::UnexpectedState);
+        };
+        for object_id in object_ids {
+            LogRecord::JournalDeletedObject(transaction_id, journal_id, *object_id)
+                .encode(&mut data);
+        }
+        drop(data);
+        Ok(log_buffer)
+    }
+
+    fn submit(
+        &self,
+        log_buffer: Arc<Self::LogBuffer>,
+        transaction_id: TransactionID,
+        journal_id: JournalID,
+        transaction_instant: Option<NonZeroU32>,
+        _deadline: Option<Instant>,
+    ) {
+        let log_record = transaction_instant
+            .map(|instant| LogRecord::JournalSubmitted(transaction_id, journal_id, instant));
+        self.write(&log_buffer, log_record);
+    }
+
+    fn discard(
+        &self,
+        log_buffer: Arc<Self::LogBuffer>,
+        transaction_id: TransactionID,
+        journal_id: JournalID,
+        _deadline: Option<Instant>,
+    ) {
+        let log_record = LogRecord::JournalDiscarded(transaction_id, journal_id);
+        self.write(&log_buffer, Some(log_record));
+    }
+
+    fn rewind(
+        &self,
+        log_buffer: Arc<Self::LogBuffer>,
+        transaction_id: TransactionID,
+        transaction_instant: Option<NonZeroU32>,
+        _deadline: Option<Instant>,
+    ) {
+        let log_record = LogRecord::TransactionRewound(transaction_id, transaction_instant);
+        self.write(&log_buffer, Some(log_record));
+    }
+
+    fn prepare(
+        &self,
+        log_buffer: Arc<Self::LogBuffer>,
+        transaction_id: TransactionID,
+        prepare_instant: u64,
+        deadline: Option<Instant>,
+    ) -> AwaitIO<'_, MonotonicU64, Self> {
+        let log_record = LogRecord::TransactionPrepared(transaction_id, prepare_instant);
+        self.sync(log_buffer, log_record, deadline)
+    }
+
+    fn commit(
+        &self,
+        log_buffer: Arc<Self::LogBuffer>,
+        transaction_id: TransactionID,
+        commit_instant: u64,
+        deadline: Option<Instant>,
+    ) -> AwaitIO<'_, MonotonicU64, Self> {
+        let log_record = LogRecord::TransactionCommitted(transaction_id, commit_instant);
+        self.sync(log_buffer, log_record, deadline)
+    }
+
+    fn current_flush_epoch(&self) -> Option<NonZeroU64> {
+        NonZeroU64::new(self.flush_epoch.load(Relaxed))
+    }
+
+    fn check_io_completion(
+        &self,
+        expected_flush_epoch: Option<NonZeroU64>,
+        _waker: &Waker,
+    ) -> Result<bool, Error> {
+        // Log buffers are flushed before being awaited.
+        Ok(expected_flush_epoch.is_some_and(|e| self.current_flush_epoch() >= Some(e)))
+    }
+
+    fn check_recovery(&self, _waker: &Waker) -> Result<RecoveryResult<MonotonicU64, Self>, Error> {
+        if let Ok(mut recovery_result) = self.recovery_result.try_lock() {
+            if let Some(database) = recovery_result.take() {
+                return Ok(RecoveryResult::Recovered(database?));
+            }
+        }
+        Ok(RecoveryResult::Unknown)
+    }
+
+    fn cancel_recovery(&self) {
+        // Recovery is synchronous.
+    }
+
+    fn telemetry(&self) -> &Telemetry {
+        &self.telemetry
+    }
+}
+
+impl LogBufferInterface for OpfsLogBuffer {
+    fn set_durable_flush_epoch(&self, flush_epoch: u64) {
+        self.durable_flush_epoch.store(flush_epoch, Relaxed);
+    }
+
+    fn get_durable_flush_epoch(&self) -> Option<NonZeroU64> {
+        NonZeroU64::new(self.durable_flush_epoch.load(Acquire))
+    }
+}
+
+impl PersistedIndexInterface for OpfsIndex {
+    async fn scan<F: FnMut(&VersionRecord<'_>) + Send>(
+        &self,
+        _start: Bound<&[u8]>,
+        _end: Bound<&[u8]>,
+        _limit: usize,
+        _readahead: usize,
+        _visitor: F,
+    ) -> Result<(), Error> {
+        match *self {}
+    }
+
+    fn may_contain_sync(&self, _key: &[u8]) -> bool {
+        match *self {}
+    }
+}
+
+/// Appends the tag and the fields to the buffer.
+fn put(buffer: &mut Vec<u8>, tag: u8, fields: &[u64]) {
+    buffer.push(tag);
+    for field in fields {
+        buffer.extend_from_slice(&field.to_le_bytes());
+    }
+}
+
+/// Appends the length of the bytes and the bytes to the buffer.
+fn put_bytes(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    buffer.extend_from_slice(&u32::try_from(bytes.len()).unwrap_or(u32::MAX).to_le_bytes());
+    buffer.extend_from_slice(bytes);
+}
+
+/// Reads a `u32` at the beginning of the data.
+fn get_u32(data: &mut &[u8]) -> Option<u32> {
+    let (bytes, rest) = data.split_first_chunk()?;
+    *data = rest;
+    Some(u32::from_le_bytes(*bytes))
+}
+
+/// Reads a `u64` at the beginning of the data.
+fn get(data: &mut &[u8]) -> Option<u64> {
+    let (bytes, rest) = data.split_first_chunk()?;
+    *data = rest;
+    Some(u64::from_le_bytes(*bytes))
+}
+
+/// Reads bytes prefixed with their length at the beginning of the data.
+fn get_bytes<'l>(data: &mut &'l [u8]) -> Option<&'l [u8]> {
+    let len = usize::try_from(get_u32(data)?).ok()?;
+    data.split_off(..len)
+}
+
+/// Opens the database stored in the [`Device`], increments the number of visits, and returns the
+/// number of visits.
+async fn visit<D: Device>(device: D) -> Result<u64, Error> {
+    let database =
+        Database::with_persistence_layer(OpfsPersistence::with_device(device), None, None).await?;
+    let transaction = database.transaction();
+    let mut journal = transaction.journal();
+    let snapshot = database.snapshot();
+    let visits = if let Some(container) = database.get_container("visits", &snapshot).await {
+        let visits = container
+            .get(b"visits", &snapshot, None)
+            .await?
+            .and_then(|v| v.try_into().ok())
+            .map_or(0, u64::from_le_bytes)
+            + 1;
+        container
+            .update(b"visits", &visits.to_le_bytes(), &mut journal, None)
+            .await?;
+        visits
+    } else {
+        let container = database
+            .create_container("visits".into(), Metadata::default(), &mut journal, None)
+            .await?;
+        container
+            .insert(b"visits", &1_u64.to_le_bytes(), &mut journal, None)
+            .await?;
+        1
+    };
+    drop(snapshot);
+    let _: NonZeroU32 = journal.submit();
+    transaction.commit().await?;
+
+    // No background threads can be spawned on `wasm32`.
+    #[cfg(target_arch = "wasm32")]
+    let _: Option<std::time::Duration> = database.process_tasks();
+
+    database.shutdown(ShutdownPolicy::Wait, None).await?;
+    Ok(visits)
+}
+
+#[cfg(target_arch = "wasm32")]
+mod opfs {
+    use super::{Device, Error};
+    use std::io::ErrorKind;
+    use wasm_bindgen::prelude::*;
+    use web_sys::{FileSystemReadWriteOptions, FileSystemSyncAccessHandle};
+
+    /// [`OpfsFile`] is a file of the Origin Private File System.
+    #[derive(Debug)]
+    pub struct OpfsFile(FileSystemSyncAccessHandle);
+
+    // SAFETY: `wasm32` without the `atomics` target feature cannot spawn threads, therefore the
+    // JavaScript object is only accessed by the thread that created it.
+    #[cfg(not(target_feature = "atomics"))]
+    unsafe impl Send for OpfsFile {}
+
+    // SAFETY: see `Send`.
+    #[cfg(not(target_feature = "atomics"))]
+    unsafe impl Sync for OpfsFile {}
+
+    /// Increments the number of visits stored in the file, and returns the number of visits.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database could not be opened or updated.
+    #[wasm_bindgen]
+    pub fn visit(handle: FileSystemSyncAccessHandle) -> Result<u64, JsError> {
+        // Log records are written synchronously, therefore no futures wait for the event loop.
+        futures::executor::block_on(super::visit(OpfsFile(handle)))
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// Converts a JavaScript exception into an [`Error`].
+    #[allow(clippy::needless_pass_by_value)]
+    fn io_error(_: JsValue) -> Error {
+        Error::IO(ErrorKind::Other)
+    }
+
+    impl Device for OpfsFile {
+        fn size(&self) -> Result<u64, Error> {
+            self.0.get_size().map(|s| s as u64).map_err(io_error)
+        }
+
+        fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), Error> {
+            let options = FileSystemReadWriteOptions::new();
+            options.set_at(offset as f64);
+            let read = self
+                .0
+                .read_with_u8_array_and_options(buffer, &options)
+                .map_err(io_error)?;
+            if read as usize == buffer.len() {
+                Ok(())
+            } else {
+                Err(Error::IO(ErrorKind::UnexpectedEof))
+            }
+        }
+
+        fn write_at(&self, offset: u64, data: &[u8]) -> Result<(), Error> {
+            let options = FileSystemReadWriteOptions::new();
+            options.set_at(offset as f64);
+            let written = self
+                .0
+                .write_with_u8_array_and_options(data, &options)
+                .map_err(io_error)?;
+            if written as usize == data.len() {
+                Ok(())
+            } else {
+                Err(Error::DiskFull)
+            }
+        }
+
+        fn flush(&self) -> Result<(), Error> {
+            self.0.flush().map_err(io_error)
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Device for std::fs::File {
+    fn size(&self) -> Result<u64, Error> {
+        Ok(self.metadata()?.len())
+    }
+
+    fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), Error> {
+        Ok(std::os::unix::fs::FileExt::read_exact_at(
+            self, buffer, offset,
+        )?)
+    }
+
+    fn write_at(&self, offset: u64, data: &[u8]) -> Result<(), Error> {
+        Ok(std::os::unix::fs::FileExt::write_all_at(
+            self, data, offset,
+        )?)
+    }
+
+    fn flush(&self) -> Result<(), Error> {
+        Ok(self.sync_data()?)
+    }
+}
+
+fn main() {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let path = std::env::temp_dir().join("sap_tsf_opfs_example.log");
+        let _: std::io::Result<()> = std::fs::remove_file(&path);
+        for expected in 1..=3 {
+            let file = std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&path)
+                .unwrap();
+            let visits = futures::executor::block_on(visit(file)).unwrap();
+            assert_eq!(visits, expected);
+            println!("visits: {visits}");
+        }
+        assert!(std::fs::remove_file(&path).is_ok());
+    }
+}
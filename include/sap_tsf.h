@@ -0,0 +1,104 @@
+/*
+ * SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+/*
+ * C interface of sap-tsf enabled by the `capi` feature.
+ *
+ * Every function returns TSF_OK if successful, or a negative error code otherwise. The functions
+ * block the calling thread until the operation completes. All the transactions of a database
+ * must be committed or rolled back before the database is closed.
+ */
+
+#ifndef SAP_TSF_H
+#define SAP_TSF_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define TSF_OK 0
+#define TSF_ERROR_ALREADY_IN_USE -1
+#define TSF_ERROR_CANCELLED -2
+#define TSF_ERROR_CONFLICT -3
+#define TSF_ERROR_CORRUPT_DATABASE -4
+#define TSF_ERROR_CORRUPT_PAGE -5
+#define TSF_ERROR_DEADLOCK -6
+#define TSF_ERROR_DISK_FULL -7
+#define TSF_ERROR_GENERIC -8
+#define TSF_ERROR_IO -9
+#define TSF_ERROR_NOT_FOUND -10
+#define TSF_ERROR_OUT_OF_MEMORY -11
+#define TSF_ERROR_OUT_OF_MEMORY_BUDGET -12
+#define TSF_ERROR_OVERLOADED -13
+#define TSF_ERROR_READ_ONLY -14
+#define TSF_ERROR_SERIALIZATION_FAILURE -15
+#define TSF_ERROR_SHUT_DOWN -16
+#define TSF_ERROR_TIMEOUT -17
+#define TSF_ERROR_UNEXPECTED_STATE -18
+#define TSF_ERROR_UNIQUENESS_VIOLATION -19
+#define TSF_ERROR_WRONG_PARAMETER -20
+#define TSF_ERROR_ABORTED -21
+#define TSF_ERROR_VERSION_CHAIN_TOO_LONG -22
+
+/* An opaque handle of a database. */
+typedef struct TsfDatabase TsfDatabase;
+
+/* An opaque handle of a transaction. */
+typedef struct TsfTransaction TsfTransaction;
+
+/* Receives each key-value pair visited by tsf_scan; returning a non-zero value stops the scan. */
+typedef int (*TsfScanCallback)(void *context, const uint8_t *key, size_t key_len,
+                               const uint8_t *value, size_t value_len);
+
+/* Opens the database in the directory. */
+int tsf_open(const char *path, TsfDatabase **database);
+
+/* Shuts down and closes the database, and releases the handle. */
+int tsf_close(TsfDatabase *database);
+
+/* Starts a transaction. */
+int tsf_begin(TsfDatabase *database, TsfTransaction **transaction);
+
+/* Commits the transaction, and releases the handle; instant may be NULL. */
+int tsf_commit(TsfTransaction *transaction, uint64_t *instant);
+
+/* Rolls back the transaction, and releases the handle. */
+int tsf_rollback(TsfTransaction *transaction);
+
+/* Creates a container with the transaction. */
+int tsf_create_container(TsfTransaction *transaction, const char *name);
+
+/* Inserts or updates the key-value pair in the container. */
+int tsf_put(TsfTransaction *transaction, const char *container, const uint8_t *key,
+            size_t key_len, const uint8_t *value, size_t value_len);
+
+/* Deletes the key-value pair from the container. */
+int tsf_delete(TsfTransaction *transaction, const char *container, const uint8_t *key,
+               size_t key_len);
+
+/* Reads the value associated with the key; the value has to be released by tsf_free. */
+int tsf_get(TsfTransaction *transaction, const char *container, const uint8_t *key,
+            size_t key_len, uint8_t **value, size_t *value_len);
+
+/* Visits key-value pairs in [start, end) in ascending key order; NULL means unbounded. */
+int tsf_scan(TsfTransaction *transaction, const char *container, const uint8_t *start,
+             size_t start_len, const uint8_t *end, size_t end_len, TsfScanCallback callback,
+             void *context);
+
+/* Releases a value returned by tsf_get. */
+void tsf_free(uint8_t *value, size_t value_len);
+
+/* Returns a static string describing the error code. */
+const char *tsf_error_message(int code);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SAP_TSF_H */
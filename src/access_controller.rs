@@ -2,18 +2,29 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+use super::deadlock::{self, DeadlockReport, VictimPolicy, WaitsFor};
+use super::dependency_graph::{Access, AccessType, ObjectDependency, ObjectHolder};
 use super::journal::AccessRequestResult;
 use super::journal::Anchor as JournalAnchor;
 use super::journal::{AwaitResponse, Relationship};
-use super::{Error, Journal, PersistenceLayer, Sequencer, Snapshot};
+use super::transaction::Anchor as TransactionAnchor;
+use super::utils::Instant;
+use super::{
+    DependencyGraph, Error, Journal, PersistenceLayer, Sequencer, Snapshot, TransactionID,
+    TransactionState, VersionState,
+};
 use scc::hash_map::Entry as MapEntry;
-use scc::{ebr, HashMap};
+use scc::{ebr, HashMap, TreeIndex};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::cmp;
-use std::collections::{BTreeSet, VecDeque};
-use std::mem::take;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::mem::{replace, take};
 use std::ops::{Deref, DerefMut};
-use std::sync::Arc;
-use std::time::Instant;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicU8, AtomicUsize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 /// [`AccessController`] grants or rejects access to a database object identified as a [`usize`]
 /// value.
@@ -69,12 +80,12 @@ use std::time::Instant;
 ///
 ///     let transaction_fail = database.transaction();
 ///     let mut journal_fail = transaction_fail.journal();
-
+///
 ///     // Another transaction fails to take ownership of the database object.
 ///     assert!(access_controller.lock(1, &mut journal_fail, None).await.is_err());
 ///
 ///     let mut journal_delete = transaction_succ.journal();
-//
+///
 ///     // The transaction will delete the database object.
 ///     assert!(access_controller.delete(1, &mut journal_delete, None).await.is_ok());
 ///     assert_eq!(Some(journal_delete.submit()), NonZeroU32::new(2));
@@ -87,9 +98,103 @@ use std::time::Instant;
 ///     assert!(access_controller.share(1, &mut journal_fail, None).await.is_err());
 /// };
 /// ```
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct AccessController<S: Sequencer> {
     table: HashMap<u64, ObjectState<S>>,
+
+    /// The [`ConflictPolicy`] of the [`AccessController`].
+    conflict_policy: AtomicU8,
+
+    /// The maximum number of requests waiting for a database object.
+    ///
+    /// `usize::MAX` means no limit.
+    max_waiters: AtomicUsize,
+
+    /// The [`WaitPolicy`] of the [`AccessController`].
+    ///
+    /// `0` represents [`WaitPolicy::Fifo`], and other values represent [`WaitPolicy::Priority`]
+    /// with the aging interval in nanoseconds plus one.
+    wait_policy: AtomicU64,
+
+    /// The maximum time in nanoseconds that a request can wait for a database object.
+    ///
+    /// `u64::MAX` means no limit.
+    lock_timeout: AtomicU64,
+
+    /// The maximum number of rounds that a waiting request spins before being parked.
+    spin_limit: AtomicU32,
+
+    /// The number of spin rounds adapted to how often spinning succeeded recently.
+    spin_rounds: AtomicU32,
+
+    /// Committing transactions release their locks before their commit log records are
+    /// persisted.
+    early_lock_release: AtomicBool,
+
+    /// The [`VictimPolicy`] of deadlock detection.
+    ///
+    /// `0` means that deadlock detection is disabled.
+    victim_policy: AtomicU8,
+
+    /// The logical clock of transactions.
+    ///
+    /// Each transaction is assigned a distinct value when it starts, and the value is used to
+    /// determine which transaction is older when a conflict is resolved.
+    start_clock: AtomicU64,
+
+    /// The start clock values of active serializable transactions.
+    serializable_transactions: TreeIndex<u64, ()>,
+
+    /// Database objects created or deleted by recently committed transactions along with the
+    /// commit instants, waiting to be consolidated in the background.
+    consolidation_queue: Mutex<VecDeque<(S::Instant, Vec<u64>)>>,
+}
+
+/// [`ConflictPolicy`] determines how a transaction requesting access to a database object owned
+/// by other transactions is handled.
+///
+/// Transactions are ordered by their start time; a transaction is older than another one if it
+/// started earlier.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum ConflictPolicy {
+    /// The requester waits for the owners until the deadline is reached.
+    #[default]
+    Wait,
+
+    /// The requester waits for the owners only if it is older than all of them, otherwise
+    /// [`Error::Deadlock`] is returned.
+    WaitDie,
+
+    /// The requester wounds the owners that are younger than it, and waits for them.
+    ///
+    /// A wounded transaction fails to gain access to database objects with [`Error::Deadlock`],
+    /// and it cannot be committed. Transactions that have started to commit are not wounded.
+    WoundWait,
+
+    /// The requester never waits, and [`Error::SerializationFailure`] is returned as if no
+    /// deadline was specified.
+    NoWait,
+}
+
+/// [`WaitPolicy`] determines the order in which waiting transactions gain access to a database
+/// object.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum WaitPolicy {
+    /// Waiting transactions gain access in the order of their requests.
+    #[default]
+    Fifo,
+
+    /// Transactions of higher [`Transaction::priority`](super::Transaction::priority) gain access
+    /// first, and requests of the same priority are ordered by the time when they were made.
+    ///
+    /// The priority of a waiting request is raised by one every `aging` interval in order to
+    /// prevent low-priority transactions from starving; [`Duration::ZERO`] disables aging.
+    Priority {
+        /// The interval at which the priority of a waiting request is raised.
+        aging: Duration,
+    },
 }
 
 /// An owner of a database object.
@@ -163,6 +268,23 @@ pub(super) enum Request<S: Sequencer> {
     Delete(Instant, Owner<S>, Arc<AccessRequestResult>),
 }
 
+impl<S: Sequencer> Request<S> {
+    /// Returns the priority of the request raised by one every `aging` interval since the request
+    /// was made.
+    fn effective_priority(&self, aging: Duration, now: Instant) -> u64 {
+        let (Request::Create(instant, owner, _)
+        | Request::Protect(instant, owner, _)
+        | Request::Lock(instant, owner, _)
+        | Request::Delete(instant, owner, _)) = self;
+        let priority = u64::from(owner.transaction_anchor().priority());
+        if aging.is_zero() {
+            return priority;
+        }
+        let raised = now.saturating_duration_since(*instant).as_nanos() / aging.as_nanos();
+        priority.saturating_add(u64::try_from(raised).unwrap_or(u64::MAX))
+    }
+}
+
 /// [`SharedAwaitable`] contains multiple owners and a wait queue.
 #[derive(Debug)]
 pub(super) struct SharedAwaitable<S: Sequencer> {
@@ -191,13 +313,31 @@ pub(super) struct ExclusiveAwaitable<S: Sequencer> {
 
     /// The previous ownership before the current owner took the database object.
     ///
-    /// This field is only used when ownership is promoted within the same transaction.
+    /// This field is only used when ownership is promoted within the same transaction, or when a
+    /// database object created by a transaction that released its locks before being committed
+    /// is deleted by another transaction.
     prior_ownership: Option<Box<Ownership<S>>>,
 
     /// The wait queue of the database object.
     wait_queue: WaitQueue<S>,
 }
 
+/// The default maximum number of rounds that a waiting request spins before being parked.
+const DEFAULT_SPIN_LIMIT: u32 = 10;
+
+/// The largest configurable number of spin rounds.
+const MAX_SPIN_LIMIT: u32 = 16;
+
+/// The maximum number of committed transactions waiting for their database objects to be
+/// consolidated.
+///
+/// Database objects of transactions committed while the queue is full are left to garbage
+/// collection and subsequent accesses.
+const MAX_PENDING_CONSOLIDATIONS: usize = 1 << 16;
+
+/// The maximum number of database objects consolidated at once.
+const CONSOLIDATION_BATCH_SIZE: usize = 1024;
+
 /// The access request wait queue for a database object.
 #[derive(Debug, Default)]
 struct WaitQueue<S: Sequencer>(VecDeque<Request<S>>);
@@ -289,6 +429,14 @@ impl<S: Sequencer> AccessController<S> {
                                 owner.grant_read_access(snapshot, deadline).map(|r| !r)
                             }
                             Ownership::DeletedAwaitable(exclusive_awaitable) => {
+                                if let Some(creator) = exclusive_awaitable.released_creator() {
+                                    // The database object was created by a transaction that
+                                    // released its locks before being committed.
+                                    match creator.grant_read_access(snapshot, deadline) {
+                                        Ok(true) => (),
+                                        result => return result,
+                                    }
+                                }
                                 if *snapshot >= exclusive_awaitable.creation_instant {
                                     // The database object is being deleted.
                                     exclusive_awaitable
@@ -373,6 +521,8 @@ impl<S: Sequencer> AccessController<S> {
         journal: &mut Journal<'_, '_, S, P>,
         deadline: Option<Instant>,
     ) -> Result<bool, Error> {
+        let deadline = self.admit(journal.anchor(), deadline)?;
+        journal.record_owned_object(object_id);
         let mut entry = match self.table.entry_async(object_id).await {
             MapEntry::Occupied(entry) => entry,
             MapEntry::Vacant(entry) => {
@@ -389,7 +539,11 @@ impl<S: Sequencer> AccessController<S> {
             ObjectState::Owned(Ownership::CreatedAwaitable(exclusive_awaitable)),
         ) = (deadline, entry.get_mut())
         {
+            self.check_wait_queue(&exclusive_awaitable.wait_queue)?;
+            self.resolve_conflict(journal.anchor(), [&exclusive_awaitable.owner])?;
+            let transaction_anchor = journal.anchor().transaction_anchor().clone();
             let task_processor = journal.task_processor();
+            let telemetry = journal.database().telemetry();
             let result_placeholder = Arc::new(AccessRequestResult::default());
             let request = Request::Create(
                 Instant::now(),
@@ -397,7 +551,16 @@ impl<S: Sequencer> AccessController<S> {
                 result_placeholder.clone(),
             );
             exclusive_awaitable.push_request(request);
-            return AwaitResponse::new(entry, task_processor, deadline, result_placeholder).await;
+            return AwaitResponse::new(
+                self,
+                entry,
+                task_processor,
+                telemetry,
+                deadline,
+                result_placeholder,
+                transaction_anchor,
+            )
+            .await;
         }
 
         // The database object has been created, deleted, or invisible.
@@ -441,6 +604,7 @@ impl<S: Sequencer> AccessController<S> {
         journal: &mut Journal<'_, '_, S, P>,
         deadline: Option<Instant>,
     ) -> Result<bool, Error> {
+        let deadline = self.admit(journal.anchor(), deadline)?;
         let mut entry = match self.table.entry_async(object_id).await {
             MapEntry::Occupied(entry) => entry,
             MapEntry::Vacant(entry) => {
@@ -460,7 +624,11 @@ impl<S: Sequencer> AccessController<S> {
                 | Ownership::LockedAwaitable(exclusive_awaitable)
                 | Ownership::DeletedAwaitable(exclusive_awaitable) => {
                     if let Some(deadline) = deadline {
+                        self.check_wait_queue(&exclusive_awaitable.wait_queue)?;
+                        self.resolve_conflict(journal.anchor(), [&exclusive_awaitable.owner])?;
+                        let transaction_anchor = journal.anchor().transaction_anchor().clone();
                         let task_processor = journal.task_processor();
+                        let telemetry = journal.database().telemetry();
                         let result_placeholder = Arc::new(AccessRequestResult::default());
                         let request = Request::Protect(
                             Instant::now(),
@@ -469,17 +637,24 @@ impl<S: Sequencer> AccessController<S> {
                         );
                         exclusive_awaitable.push_request(request);
                         return AwaitResponse::new(
+                            self,
                             entry,
                             task_processor,
+                            telemetry,
                             deadline,
                             result_placeholder,
+                            transaction_anchor,
                         )
                         .await;
                     }
                 }
                 Ownership::ProtectedAwaitable(shared_awaitable) => {
                     if let Some(deadline) = deadline {
+                        self.check_wait_queue(&shared_awaitable.wait_queue)?;
+                        self.resolve_conflict(journal.anchor(), &shared_awaitable.owner_set)?;
+                        let transaction_anchor = journal.anchor().transaction_anchor().clone();
                         let task_processor = journal.task_processor();
+                        let telemetry = journal.database().telemetry();
                         let result_placeholder = Arc::new(AccessRequestResult::default());
                         let request = Request::Protect(
                             Instant::now(),
@@ -488,10 +663,13 @@ impl<S: Sequencer> AccessController<S> {
                         );
                         shared_awaitable.push_request(request);
                         return AwaitResponse::new(
+                            self,
                             entry,
                             task_processor,
+                            telemetry,
                             deadline,
                             result_placeholder,
+                            transaction_anchor,
                         )
                         .await;
                     }
@@ -541,6 +719,7 @@ impl<S: Sequencer> AccessController<S> {
         journal: &mut Journal<'_, '_, S, P>,
         deadline: Option<Instant>,
     ) -> Result<bool, Error> {
+        let deadline = self.admit(journal.anchor(), deadline)?;
         let mut entry = match self.table.entry_async(object_id).await {
             MapEntry::Occupied(entry) => entry,
             MapEntry::Vacant(entry) => {
@@ -558,7 +737,11 @@ impl<S: Sequencer> AccessController<S> {
                 | Ownership::LockedAwaitable(exclusive_awaitable)
                 | Ownership::DeletedAwaitable(exclusive_awaitable) => {
                     if let Some(deadline) = deadline {
+                        self.check_wait_queue(&exclusive_awaitable.wait_queue)?;
+                        self.resolve_conflict(journal.anchor(), [&exclusive_awaitable.owner])?;
+                        let transaction_anchor = journal.anchor().transaction_anchor().clone();
                         let task_processor = journal.task_processor();
+                        let telemetry = journal.database().telemetry();
                         let result_placeholder = Arc::new(AccessRequestResult::default());
                         let request = Request::Lock(
                             Instant::now(),
@@ -567,17 +750,25 @@ impl<S: Sequencer> AccessController<S> {
                         );
                         exclusive_awaitable.push_request(request);
                         return AwaitResponse::new(
+                            self,
                             entry,
                             task_processor,
+                            telemetry,
                             deadline,
                             result_placeholder,
+                            transaction_anchor,
                         )
                         .await;
                     }
                 }
                 Ownership::ProtectedAwaitable(shared_awaitable) => {
                     if let Some(deadline) = deadline {
+                        shared_awaitable.check_upgrade(journal.anchor())?;
+                        self.check_wait_queue(&shared_awaitable.wait_queue)?;
+                        self.resolve_conflict(journal.anchor(), &shared_awaitable.owner_set)?;
+                        let transaction_anchor = journal.anchor().transaction_anchor().clone();
                         let task_processor = journal.task_processor();
+                        let telemetry = journal.database().telemetry();
                         let result_placeholder = Arc::new(AccessRequestResult::default());
                         let request = Request::Lock(
                             Instant::now(),
@@ -586,10 +777,13 @@ impl<S: Sequencer> AccessController<S> {
                         );
                         shared_awaitable.push_request(request);
                         return AwaitResponse::new(
+                            self,
                             entry,
                             task_processor,
+                            telemetry,
                             deadline,
                             result_placeholder,
+                            transaction_anchor,
                         )
                         .await;
                     }
@@ -642,6 +836,8 @@ impl<S: Sequencer> AccessController<S> {
         journal: &mut Journal<'_, '_, S, P>,
         deadline: Option<Instant>,
     ) -> Result<bool, Error> {
+        let deadline = self.admit(journal.anchor(), deadline)?;
+        journal.record_owned_object(object_id);
         let mut entry = match self.table.entry_async(object_id).await {
             MapEntry::Occupied(entry) => entry,
             MapEntry::Vacant(entry) => {
@@ -659,7 +855,11 @@ impl<S: Sequencer> AccessController<S> {
                 | Ownership::LockedAwaitable(exclusive_awaitable)
                 | Ownership::DeletedAwaitable(exclusive_awaitable) => {
                     if let Some(deadline) = deadline {
+                        self.check_wait_queue(&exclusive_awaitable.wait_queue)?;
+                        self.resolve_conflict(journal.anchor(), [&exclusive_awaitable.owner])?;
+                        let transaction_anchor = journal.anchor().transaction_anchor().clone();
                         let task_processor = journal.task_processor();
+                        let telemetry = journal.database().telemetry();
                         let result_placeholder = Arc::new(AccessRequestResult::default());
                         let request = Request::Delete(
                             Instant::now(),
@@ -668,18 +868,26 @@ impl<S: Sequencer> AccessController<S> {
                         );
                         exclusive_awaitable.push_request(request);
                         return AwaitResponse::new(
+                            self,
                             entry,
                             task_processor,
+                            telemetry,
                             deadline,
                             result_placeholder,
+                            transaction_anchor,
                         )
                         .await;
                     }
                 }
                 Ownership::ProtectedAwaitable(shared_awaitable) => {
                     if let Some(deadline) = deadline {
+                        shared_awaitable.check_upgrade(journal.anchor())?;
                         // Wait for the database resource to be available to the transaction.
+                        self.check_wait_queue(&shared_awaitable.wait_queue)?;
+                        self.resolve_conflict(journal.anchor(), &shared_awaitable.owner_set)?;
+                        let transaction_anchor = journal.anchor().transaction_anchor().clone();
                         let task_processor = journal.task_processor();
+                        let telemetry = journal.database().telemetry();
                         let result_placeholder = Arc::new(AccessRequestResult::default());
                         let request = Request::Delete(
                             Instant::now(),
@@ -688,10 +896,13 @@ impl<S: Sequencer> AccessController<S> {
                         );
                         shared_awaitable.push_request(request);
                         return AwaitResponse::new(
+                            self,
                             entry,
                             task_processor,
+                            telemetry,
                             deadline,
                             result_placeholder,
+                            transaction_anchor,
                         )
                         .await;
                     }
@@ -704,6 +915,451 @@ impl<S: Sequencer> AccessController<S> {
         Err(Error::SerializationFailure)
     }
 
+    /// Sets the [`ConflictPolicy`].
+    ///
+    /// The policy applies to requests made after the method returns.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{ConflictPolicy, Database};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("conflict_policy")).await.unwrap();
+    ///     let access_controller = database.access_controller();
+    ///     access_controller.set_conflict_policy(ConflictPolicy::WaitDie);
+    ///     assert_eq!(access_controller.conflict_policy(), ConflictPolicy::WaitDie);
+    /// };
+    /// ```
+    #[inline]
+    pub fn set_conflict_policy(&self, conflict_policy: ConflictPolicy) {
+        self.conflict_policy.store(conflict_policy.into(), Relaxed);
+    }
+
+    /// Returns the current [`ConflictPolicy`].
+    #[inline]
+    #[must_use]
+    pub fn conflict_policy(&self) -> ConflictPolicy {
+        ConflictPolicy::from(self.conflict_policy.load(Relaxed))
+    }
+
+    /// Sets the maximum number of requests that can wait for a database object.
+    ///
+    /// Requests that would exceed the limit fail immediately with [`Error::Overloaded`] instead
+    /// of waiting, which gives callers a backpressure signal when many transactions contend for
+    /// the same database object. Timed out requests may take up space in the wait queue until the
+    /// wait queue is cleaned up in the background. `None` means no limit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("max_waiters")).await.unwrap();
+    ///     let access_controller = database.access_controller();
+    ///     assert_eq!(access_controller.max_waiters(), None);
+    ///     access_controller.set_max_waiters(Some(16));
+    ///     assert_eq!(access_controller.max_waiters(), Some(16));
+    /// };
+    /// ```
+    #[inline]
+    pub fn set_max_waiters(&self, max_waiters: Option<usize>) {
+        self.max_waiters
+            .store(max_waiters.unwrap_or(usize::MAX), Relaxed);
+    }
+
+    /// Returns the maximum number of requests that can wait for a database object.
+    #[inline]
+    #[must_use]
+    pub fn max_waiters(&self) -> Option<usize> {
+        let max_waiters = self.max_waiters.load(Relaxed);
+        (max_waiters != usize::MAX).then_some(max_waiters)
+    }
+
+    /// Sets the [`WaitPolicy`].
+    ///
+    /// The policy applies to waiting requests that are processed after the method returns.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, WaitPolicy};
+    /// use std::path::Path;
+    /// use std::time::Duration;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("wait_policy")).await.unwrap();
+    ///     let access_controller = database.access_controller();
+    ///     let wait_policy = WaitPolicy::Priority {
+    ///         aging: Duration::from_millis(100),
+    ///     };
+    ///     access_controller.set_wait_policy(wait_policy);
+    ///     assert_eq!(access_controller.wait_policy(), wait_policy);
+    /// };
+    /// ```
+    #[inline]
+    pub fn set_wait_policy(&self, wait_policy: WaitPolicy) {
+        self.wait_policy.store(wait_policy.into(), Relaxed);
+    }
+
+    /// Returns the current [`WaitPolicy`].
+    #[inline]
+    #[must_use]
+    pub fn wait_policy(&self) -> WaitPolicy {
+        WaitPolicy::from(self.wait_policy.load(Relaxed))
+    }
+
+    /// Sets the maximum time that a request can wait for a database object.
+    ///
+    /// Deadlines of requests that are later than the timeout from the time of the request are
+    /// shortened to the timeout, and requests without a deadline do not wait regardless of the
+    /// timeout. The timeout applies to requests made after the method returns. `None` means no
+    /// limit, which is the default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    /// use std::time::Duration;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("lock_timeout")).await.unwrap();
+    ///     let access_controller = database.access_controller();
+    ///     assert_eq!(access_controller.lock_timeout(), None);
+    ///     access_controller.set_lock_timeout(Some(Duration::from_secs(1)));
+    ///     assert_eq!(access_controller.lock_timeout(), Some(Duration::from_secs(1)));
+    /// };
+    /// ```
+    #[inline]
+    pub fn set_lock_timeout(&self, timeout: Option<Duration>) {
+        let nanos = timeout.map_or(u64::MAX, |t| {
+            u64::try_from(t.as_nanos()).unwrap_or(u64::MAX - 1)
+        });
+        self.lock_timeout.store(nanos, Relaxed);
+    }
+
+    /// Returns the maximum time that a request can wait for a database object.
+    #[inline]
+    #[must_use]
+    pub fn lock_timeout(&self) -> Option<Duration> {
+        let nanos = self.lock_timeout.load(Relaxed);
+        (nanos != u64::MAX).then(|| Duration::from_nanos(nanos))
+    }
+
+    /// Sets the maximum number of rounds that a waiting request spins before being parked.
+    ///
+    /// A request that has to wait for a database object checks whether it was granted access in
+    /// rounds of busy-waiting before it is parked, and the number of busy-wait iterations doubles
+    /// every round, so that conflicts lasting microseconds are resolved without paying for
+    /// wake-ups. The number of rounds is adapted to contention: it grows while spinning succeeds,
+    /// and shrinks while requests end up being parked. `0` disables spinning, and values larger
+    /// than `16` are regarded as `16`; the default is `10`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("spin_limit")).await.unwrap();
+    ///     let access_controller = database.access_controller();
+    ///     assert_eq!(access_controller.spin_limit(), 10);
+    ///     access_controller.set_spin_limit(0);
+    ///     assert_eq!(access_controller.spin_limit(), 0);
+    /// };
+    /// ```
+    #[inline]
+    pub fn set_spin_limit(&self, spin_limit: u32) {
+        let spin_limit = spin_limit.min(MAX_SPIN_LIMIT);
+        self.spin_limit.store(spin_limit, Relaxed);
+        self.spin_rounds.store(spin_limit, Relaxed);
+    }
+
+    /// Returns the maximum number of rounds that a waiting request spins before being parked.
+    #[inline]
+    #[must_use]
+    pub fn spin_limit(&self) -> u32 {
+        self.spin_limit.load(Relaxed)
+    }
+
+    /// Enables or disables early lock release.
+    ///
+    /// If enabled, a transaction releases its exclusive locks once it is given a commit instant
+    /// and its commit log record is queued, so that other transactions can lock the database
+    /// objects without waiting for the commit log record to be persisted. A transaction that
+    /// locks a database object released this way sees the changes made by the previous owner, and
+    /// depends on the commit of the previous owner: it waits for the previous owner to be
+    /// committed when it is prepared for commit, and it fails with
+    /// [`Error::SerializationFailure`] if the previous owner was rolled back, e.g., because the
+    /// commit log record could not be persisted. Other readers still wait for the commit log
+    /// record to be persisted. It is disabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("early_lock_release")).await.unwrap();
+    ///     let access_controller = database.access_controller();
+    ///     assert!(!access_controller.early_lock_release());
+    ///     access_controller.set_early_lock_release(true);
+    ///     assert!(access_controller.early_lock_release());
+    /// };
+    /// ```
+    #[inline]
+    pub fn set_early_lock_release(&self, enabled: bool) {
+        self.early_lock_release.store(enabled, Relaxed);
+    }
+
+    /// Returns `true` if early lock release is enabled.
+    #[inline]
+    #[must_use]
+    pub fn early_lock_release(&self) -> bool {
+        self.early_lock_release.load(Relaxed)
+    }
+
+    /// Enables deadlock detection with the [`VictimPolicy`], or disables it.
+    ///
+    /// Transactions waiting for each other are checked in the background, and the waiting request
+    /// of the victim in each cycle fails with [`Error::DeadlockDetected`] carrying the
+    /// [`DeadlockReport`], which is also counted in the statistics of the database and, with the
+    /// `tracing` feature, emitted as a warning. It is disabled by default, in which case waiting
+    /// requests of a deadlock fail only when they are timed out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, VictimPolicy};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("deadlock_detection")).await.unwrap();
+    ///     let access_controller = database.access_controller();
+    ///     assert_eq!(access_controller.deadlock_detection(), None);
+    ///     access_controller.set_deadlock_detection(Some(VictimPolicy::LeastWork));
+    ///     assert_eq!(
+    ///         access_controller.deadlock_detection(),
+    ///         Some(VictimPolicy::LeastWork)
+    ///     );
+    /// };
+    /// ```
+    #[inline]
+    pub fn set_deadlock_detection(&self, victim_policy: Option<VictimPolicy>) {
+        let victim_policy = match victim_policy {
+            None => 0,
+            Some(VictimPolicy::Youngest) => 1,
+            Some(VictimPolicy::LeastWork) => 2,
+            Some(VictimPolicy::LowestPriority) => 3,
+        };
+        self.victim_policy.store(victim_policy, Relaxed);
+    }
+
+    /// Returns the [`VictimPolicy`] of deadlock detection if it is enabled.
+    #[inline]
+    #[must_use]
+    pub fn deadlock_detection(&self) -> Option<VictimPolicy> {
+        match self.victim_policy.load(Relaxed) {
+            1 => Some(VictimPolicy::Youngest),
+            2 => Some(VictimPolicy::LeastWork),
+            3 => Some(VictimPolicy::LowestPriority),
+            _ => None,
+        }
+    }
+
+    /// Returns the identifiers and states of the transactions owning the database object.
+    ///
+    /// An empty [`Vec`] is returned if no transactions own the database object.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, TransactionState};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("owners")).await.unwrap();
+    ///     let access_controller = database.access_controller();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     assert!(access_controller.create(1, &mut journal, None).await.is_ok());
+    ///     assert_eq!(
+    ///         access_controller.owners(1).await,
+    ///         vec![(transaction.id(), TransactionState::Active)]
+    ///     );
+    /// };
+    /// ```
+    #[inline]
+    pub async fn owners(
+        &self,
+        object_id: u64,
+    ) -> Vec<(TransactionID, TransactionState<S::Instant>)> {
+        self.table
+            .read_async(&object_id, |_, entry| entry.owners())
+            .await
+            .unwrap_or_default()
+    }
+
+    /// Returns the transactions other than the specified one that hold the database object and
+    /// have yet to be ended.
+    ///
+    /// The database object is examined without being waited for.
+    pub(super) fn holders(
+        &self,
+        object_id: u64,
+        transaction_id: TransactionID,
+    ) -> Vec<ObjectHolder<S::Instant>> {
+        self.table
+            .read(&object_id, |_, entry| entry.holders(transaction_id))
+            .unwrap_or_default()
+    }
+
+    /// Returns the [`DependencyGraph`] of the database objects owned by transactions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{AccessType, Database};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("dependency_graph")).await.unwrap();
+    ///     let access_controller = database.access_controller();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     assert!(access_controller.share(1, &mut journal, None).await.is_ok());
+    ///     let graph = access_controller.dependency_graph().await;
+    ///     assert_eq!(graph.transactions[0].0, transaction.id());
+    ///     assert_eq!(graph.objects[0].holders[0].access_type, AccessType::Protect);
+    /// };
+    /// ```
+    #[inline]
+    pub async fn dependency_graph(&self) -> DependencyGraph<S::Instant> {
+        let mut transactions = BTreeMap::new();
+        let mut objects = Vec::new();
+        self.table
+            .scan_async(|object_id, entry| {
+                if let Some(object) = entry.dependency(*object_id, &mut transactions) {
+                    objects.push(object);
+                }
+            })
+            .await;
+        objects.sort_unstable_by_key(|o| o.object_id);
+        DependencyGraph {
+            transactions: transactions.into_iter().collect(),
+            objects,
+        }
+    }
+
+    /// Returns the identifiers of database objects that have access control data and are
+    /// invisible to the [`Snapshot`] in ascending order.
+    ///
+    /// Database objects without access control data are visible to every reader, therefore the
+    /// returned database objects are the only ones of which the visibility has to be persisted.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) async fn invisible_objects(
+        &self,
+        snapshot: &Snapshot<'_, '_, '_, S>,
+        deadline: Option<Instant>,
+    ) -> Result<Vec<u64>, Error> {
+        let mut object_ids = Vec::new();
+        self.table
+            .scan_async(|object_id, _| object_ids.push(*object_id))
+            .await;
+        object_ids.sort_unstable();
+        let mut invisible_objects = Vec::new();
+        for object_id in object_ids {
+            if !self.read(object_id, snapshot, deadline).await? {
+                invisible_objects.push(object_id);
+            }
+        }
+        Ok(invisible_objects)
+    }
+
+    /// Releases the lock on the database object held by the journal.
+    ///
+    /// The lock is not released if other journals are waiting for it. Returns `true` if the lock
+    /// was released.
+    pub(super) async fn release(
+        &self,
+        object_id: u64,
+        journal_anchor: &ebr::Shared<JournalAnchor<S>>,
+    ) -> bool {
+        self.table
+            .remove_if_async(&object_id, |o| {
+                matches!(o, ObjectState::Owned(Ownership::Locked(owner))
+                    if owner.anchor.as_ptr() == journal_anchor.as_ptr())
+            })
+            .await
+            .is_some()
+    }
+
+    /// Returns the [`VersionState`] of the database object.
+    ///
+    /// It is a blocking method.
+    pub(super) fn version_state_sync(&self, object_id: u64) -> VersionState<S::Instant> {
+        self.table
+            .read(&object_id, |_, entry| match entry {
+                ObjectState::Owned(_) => VersionState::Owned(entry.owners()),
+                ObjectState::Created(instant) => VersionState::Created(*instant),
+                ObjectState::Deleted(instant) => VersionState::Deleted(*instant),
+            })
+            .unwrap_or(VersionState::Untracked)
+    }
+
+    /// Returns the number of rounds that a waiting request spins before being parked.
+    pub(super) fn spin_rounds(&self) -> u32 {
+        self.spin_rounds
+            .load(Relaxed)
+            .min(self.spin_limit.load(Relaxed))
+    }
+
+    /// Adapts the number of spin rounds to whether the request was granted access while spinning.
+    ///
+    /// At least one round is kept unless spinning is disabled, so that a request can find out
+    /// that spinning pays off again.
+    pub(super) fn adapt_spin_rounds(&self, granted: bool) {
+        let spin_limit = self.spin_limit.load(Relaxed);
+        let _: Result<u32, u32> = self.spin_rounds.fetch_update(Relaxed, Relaxed, |rounds| {
+            let rounds = if granted {
+                rounds.saturating_add(1)
+            } else {
+                rounds.saturating_sub(1).max(1)
+            };
+            Some(rounds.min(spin_limit))
+        });
+    }
+
+    /// Returns a new start clock value for a transaction.
+    pub(super) fn next_start_clock(&self) -> u64 {
+        self.start_clock.fetch_add(1, Relaxed)
+    }
+
+    /// Registers an active serializable transaction.
+    pub(super) fn begin_serializable(&self, start_clock: u64) {
+        let result = self.serializable_transactions.insert(start_clock, ());
+        debug_assert!(result.is_ok());
+    }
+
+    /// Deregisters a serializable transaction.
+    pub(super) fn end_serializable(&self, start_clock: u64) {
+        self.serializable_transactions.remove(&start_clock);
+    }
+
+    /// Returns the start clock value of the oldest active serializable transaction.
+    pub(super) fn oldest_serializable(&self) -> Option<u64> {
+        self.serializable_transactions
+            .iter(&ebr::Guard::new())
+            .next()
+            .map(|(k, ())| *k)
+    }
+
     /// Creates a new database object during database recovery.
     ///
     /// It is an infallible method.
@@ -726,6 +1382,15 @@ impl<S: Sequencer> AccessController<S> {
         };
     }
 
+    /// Makes a database object invisible to every reader during database recovery.
+    ///
+    /// It is an infallible method.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn playback_invisible_sync(&self, object_id: u64) {
+        self.table
+            .upsert(object_id, ObjectState::Deleted(S::Instant::default()));
+    }
+
     /// Deletes a database object during database recovery.
     ///
     /// It is an infallible method.
@@ -753,6 +1418,7 @@ impl<S: Sequencer> AccessController<S> {
     /// If the database object still need to be monitored, it returns `true`. It is a blocking and
     /// synchronous method, therefore this must be invoked in the background.
     pub(super) fn transfer_ownership_sync(&self, object_id: u64) -> bool {
+        let wait_policy = self.wait_policy();
         self.table
             .update(&object_id, |_, object_state| {
                 object_state.prepare_ownership_transfer();
@@ -766,11 +1432,11 @@ impl<S: Sequencer> AccessController<S> {
                         | Ownership::LockedAwaitable(exclusive_awaitable)
                         | Ownership::DeletedAwaitable(exclusive_awaitable) => {
                             let wait_queue = take(&mut exclusive_awaitable.wait_queue);
-                            Self::process_wait_queue(object_state, wait_queue)
+                            Self::process_wait_queue(object_state, wait_queue, wait_policy)
                         }
                         Ownership::ProtectedAwaitable(shared_awaitable) => {
                             let wait_queue = take(&mut shared_awaitable.wait_queue);
-                            Self::process_wait_queue(object_state, wait_queue)
+                            Self::process_wait_queue(object_state, wait_queue, wait_policy)
                         }
                     }
                 } else {
@@ -778,7 +1444,148 @@ impl<S: Sequencer> AccessController<S> {
                 };
                 Self::post_process_object_state(object_state, wait_queue)
             })
-            .map_or(false, |r| r)
+            .is_some_and(|r| r)
+    }
+
+    /// Detects cycles of transactions waiting for each other for the database objects, and makes
+    /// the waiting request of the victim chosen by the [`VictimPolicy`] in each cycle fail.
+    ///
+    /// Returns the [`DeadlockReport`] of each cycle. It is a blocking and synchronous method,
+    /// therefore this must be invoked in the background.
+    pub(super) fn resolve_deadlocks_sync<'o, I: IntoIterator<Item = &'o u64>>(
+        &self,
+        object_ids: I,
+    ) -> Vec<DeadlockReport> {
+        let Some(victim_policy) = self.deadlock_detection() else {
+            return Vec::new();
+        };
+        let now = Instant::now();
+        let mut edges = Vec::new();
+        let mut waiters = BTreeMap::new();
+        for object_id in object_ids {
+            self.table.read(object_id, |_, object_state| {
+                object_state.waits_for(*object_id, now, &mut edges, &mut waiters);
+            });
+        }
+
+        let mut reports = Vec::new();
+        while let Some(cycle) = deadlock::find_cycle(&edges) {
+            let Some(victim_edge) = cycle.iter().copied().max_by_key(|e| {
+                waiters.get(&e.waiter).map(|a| {
+                    let (num_submitted_journals, memory_usage) = a.work();
+                    victim_policy.rank(
+                        a.start_clock(),
+                        a.priority(),
+                        num_submitted_journals,
+                        memory_usage,
+                    )
+                })
+            }) else {
+                break;
+            };
+            let report = DeadlockReport {
+                victim: victim_edge.waiter,
+                cycle,
+            };
+            self.table.read(&victim_edge.object_id, |_, object_state| {
+                object_state.reject_waiter(victim_edge.waiter, &report);
+            });
+            edges
+                .retain(|e| e.waiter != victim_edge.waiter || e.object_id != victim_edge.object_id);
+            reports.push(report);
+        }
+        reports
+    }
+
+    /// Schedules the database objects created or deleted by a committed transaction to be
+    /// consolidated.
+    pub(super) fn schedule_consolidation(&self, commit_instant: S::Instant, object_ids: Vec<u64>) {
+        if object_ids.is_empty() {
+            return;
+        }
+        if let Ok(mut queue) = self.consolidation_queue.lock() {
+            if queue.len() < MAX_PENDING_CONSOLIDATIONS {
+                queue.push_back((commit_instant, object_ids));
+            }
+        }
+    }
+
+    /// Consolidates the access control data of database objects of committed transactions of
+    /// which the commit instants satisfy the condition.
+    ///
+    /// Returns the number of consolidated database objects; at most [`CONSOLIDATION_BATCH_SIZE`]
+    /// database objects are visited at once. It is a blocking and synchronous method, therefore
+    /// this must be invoked in the background.
+    pub(super) fn consolidate_sync<C: Fn(&S::Instant) -> bool>(&self, condition: &C) -> u64 {
+        let mut num_consolidated = 0;
+        let mut budget = CONSOLIDATION_BATCH_SIZE;
+        while budget != 0 {
+            let Some(object_ids) = self.consolidation_queue.lock().ok().and_then(|mut queue| {
+                let (commit_instant, object_ids) = queue.front_mut()?;
+                if !condition(commit_instant) {
+                    return None;
+                } else if object_ids.len() > budget {
+                    let remaining = object_ids.split_off(budget);
+                    return Some(replace(object_ids, remaining));
+                }
+                queue.pop_front().map(|(_, object_ids)| object_ids)
+            }) else {
+                break;
+            };
+            budget -= object_ids.len();
+            for object_id in object_ids {
+                if self.consolidate_object_sync(object_id, condition) {
+                    num_consolidated += 1;
+                }
+            }
+        }
+        num_consolidated
+    }
+
+    /// Replaces the ownership of a committed transaction with the commit instant, or removes the
+    /// access control data if the database object is globally visible.
+    ///
+    /// Returns `true` if the access control data was modified.
+    fn consolidate_object_sync<C: Fn(&S::Instant) -> bool>(
+        &self,
+        object_id: u64,
+        condition: &C,
+    ) -> bool {
+        let mut consolidated = false;
+        self.table.remove_if(&object_id, |o| match o {
+            ObjectState::Owned(Ownership::Created(owner)) => {
+                // Rolled back creations are left to garbage collection.
+                if let Some(eot_instant) = owner.eot_instant() {
+                    if eot_instant != S::Instant::default() {
+                        consolidated = true;
+                        if condition(&eot_instant) {
+                            return true;
+                        }
+                        *o = ObjectState::Created(eot_instant);
+                    }
+                }
+                false
+            }
+            ObjectState::Owned(Ownership::Deleted(owner)) => {
+                // The access control data of a deleted database object cannot be removed since
+                // database objects without access control data are visible to every reader.
+                if let Some(eot_instant) = owner.eot_instant() {
+                    consolidated = true;
+                    if eot_instant == S::Instant::default() {
+                        // The database object has never been deleted.
+                        return true;
+                    }
+                    *o = ObjectState::Deleted(eot_instant);
+                }
+                false
+            }
+            ObjectState::Created(instant) => {
+                consolidated = condition(instant);
+                consolidated
+            }
+            ObjectState::Owned(_) | ObjectState::Deleted(_) => false,
+        });
+        consolidated
     }
 
     /// Tries to remove the access control data corresponding to the database object.
@@ -855,12 +1662,15 @@ impl<S: Sequencer> AccessController<S> {
         !found || removed
     }
 
-    /// Processes the supplied wait queue.
+    /// Processes the supplied wait queue in the order determined by the [`WaitPolicy`].
     fn process_wait_queue(
         object_state: &mut ObjectState<S>,
         mut wait_queue: WaitQueue<S>,
+        wait_policy: WaitPolicy,
     ) -> Option<WaitQueue<S>> {
-        while let Some(request) = wait_queue.clone_oldest() {
+        wait_queue.reject_aborted();
+        let now = Instant::now();
+        while let Some((index, request)) = wait_queue.clone_next(wait_policy, now) {
             let result_placeholder = match &request {
                 Request::Create(_, _, result_placeholder)
                 | Request::Protect(_, _, result_placeholder)
@@ -870,7 +1680,7 @@ impl<S: Sequencer> AccessController<S> {
             if let Some(mut result_waker) = result_placeholder.lock_sync() {
                 if result_waker.0.is_some() {
                     // The request was timed out.
-                    wait_queue.remove_oldest();
+                    wait_queue.remove(index);
                     continue;
                 }
                 let (result, new_owner) = match &request {
@@ -913,7 +1723,7 @@ impl<S: Sequencer> AccessController<S> {
                 }
             } else {
                 // The `Mutex` was poisoned.
-                wait_queue.remove_oldest();
+                wait_queue.remove(index);
             }
         }
         if wait_queue.is_empty() {
@@ -1032,10 +1842,82 @@ impl<S: Sequencer> AccessController<S> {
                 }
             },
             ObjectState::Created(_) | ObjectState::Deleted(_) => return false,
-        };
+        }
         true
     }
 
+    /// Checks if the requester is allowed to request access to database objects, and returns the
+    /// deadline that the request can wait until.
+    fn admit(
+        &self,
+        requester: &JournalAnchor<S>,
+        deadline: Option<Instant>,
+    ) -> Result<Option<Instant>, Error> {
+        if requester.transaction_anchor().is_aborted() {
+            // The transaction was aborted by another thread.
+            return Err(Error::Aborted);
+        }
+        if requester.transaction_anchor().is_wounded() {
+            // The transaction was wounded by an older transaction.
+            return Err(Error::Deadlock);
+        }
+        if self.conflict_policy() == ConflictPolicy::NoWait {
+            Ok(None)
+        } else if let Some(timeout) = self.lock_timeout() {
+            let limit = Instant::now().checked_add(timeout);
+            Ok(deadline.map(|d| limit.map_or(d, |l| d.min(l))))
+        } else {
+            Ok(deadline)
+        }
+    }
+
+    /// Checks if the wait queue can accept a new request.
+    ///
+    /// Returns [`Error::Overloaded`] if the wait queue is full.
+    fn check_wait_queue(&self, wait_queue: &WaitQueue<S>) -> Result<(), Error> {
+        if wait_queue.len() >= self.max_waiters.load(Relaxed) {
+            return Err(Error::Overloaded);
+        }
+        Ok(())
+    }
+
+    /// Decides whether the requester is allowed to wait for the owners.
+    ///
+    /// Returns [`Error::Deadlock`] if the requester has to give up.
+    fn resolve_conflict<'o, I: IntoIterator<Item = &'o Owner<S>>>(
+        &self,
+        requester: &JournalAnchor<S>,
+        owners: I,
+    ) -> Result<(), Error> {
+        let requester = requester.transaction_anchor();
+        match self.conflict_policy() {
+            ConflictPolicy::Wait | ConflictPolicy::NoWait => Ok(()),
+            ConflictPolicy::WaitDie => {
+                if owners.into_iter().any(|o| {
+                    let owner = o.transaction_anchor();
+                    owner.as_ptr() != requester.as_ptr()
+                        && owner.start_clock() < requester.start_clock()
+                }) {
+                    // The requester is younger than one of the owners.
+                    Err(Error::Deadlock)
+                } else {
+                    Ok(())
+                }
+            }
+            ConflictPolicy::WoundWait => {
+                for o in owners {
+                    let owner = o.transaction_anchor();
+                    if owner.as_ptr() != requester.as_ptr()
+                        && owner.start_clock() > requester.start_clock()
+                    {
+                        owner.wound();
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
     /// Tries to create the database object.
     ///
     /// Returns `Ok(None)` if the result will be out after waiting.
@@ -1395,7 +2277,11 @@ impl<S: Sequencer> AccessController<S> {
                 Err(Error::Deadlock)
             }
             Relationship::Unknown => {
-                if deadline.is_some() {
+                if !is_created && !is_deleted && owner.take_over(new_owner).is_some() {
+                    // The owner released the lock before its commit log record was persisted.
+                    *ownership = Ownership::Protected(Owner::new(new_owner));
+                    Ok(Some(true))
+                } else if deadline.is_some() {
                     *ownership = Self::augment_wait_queue(is_created, is_deleted, owner.clone());
                     Ok(None)
                 } else {
@@ -1460,8 +2346,25 @@ impl<S: Sequencer> AccessController<S> {
                 // deadlock.
                 return Err(Error::Deadlock);
             }
-            Relationship::Unknown => (),
-        };
+            Relationship::Unknown => {
+                if !is_created
+                    && !is_deleted
+                    && exclusive_awaitable.prior_ownership.is_none()
+                    && exclusive_awaitable.wait_queue.is_empty()
+                    && exclusive_awaitable.owner.take_over(new_owner).is_some()
+                {
+                    // The owner released the lock before its commit log record was persisted.
+                    *ownership = Ownership::ProtectedAwaitable(
+                        SharedAwaitable::with_instant_and_owner(
+                            exclusive_awaitable.creation_instant,
+                            Owner::new(new_owner),
+                        )
+                        .into(),
+                    );
+                    return Ok((true, true));
+                }
+            }
+        }
 
         if deadline.is_some() {
             Ok((false, true))
@@ -1521,7 +2424,11 @@ impl<S: Sequencer> AccessController<S> {
                 Err(Error::Deadlock)
             }
             Relationship::Unknown => {
-                if deadline.is_some() {
+                if !is_created && !is_deleted && owner.take_over(new_owner).is_some() {
+                    // The owner released the lock before its commit log record was persisted.
+                    *ownership = Ownership::Locked(Owner::new(new_owner));
+                    Ok(Some(true))
+                } else if deadline.is_some() {
                     *ownership = Self::augment_wait_queue(is_created, is_deleted, owner.clone());
                     Ok(None)
                 } else {
@@ -1586,8 +2493,25 @@ impl<S: Sequencer> AccessController<S> {
                 // deadlock.
                 return Err(Error::Deadlock);
             }
-            Relationship::Unknown => (),
-        };
+            Relationship::Unknown => {
+                if !is_created
+                    && !is_deleted
+                    && exclusive_awaitable.prior_ownership.is_none()
+                    && exclusive_awaitable.wait_queue.is_empty()
+                    && exclusive_awaitable.owner.take_over(new_owner).is_some()
+                {
+                    // The owner released the lock before its commit log record was persisted.
+                    *ownership = Ownership::LockedAwaitable(
+                        ExclusiveAwaitable::with_instant_and_owner(
+                            exclusive_awaitable.creation_instant,
+                            Owner::new(new_owner),
+                        )
+                        .into(),
+                    );
+                    return Ok((true, true));
+                }
+            }
+        }
 
         if deadline.is_some() {
             Ok((false, true))
@@ -1659,7 +2583,19 @@ impl<S: Sequencer> AccessController<S> {
                 Err(Error::Deadlock)
             }
             Relationship::Unknown => {
-                if deadline.is_some() {
+                if let Some(commit_instant) =
+                    is_created.then(|| owner.take_over(new_owner)).flatten()
+                {
+                    // The owner released its locks before its commit log record was persisted,
+                    // and the prior state is restored if the owner is rolled back.
+                    let mut exclusive_awaitable = ExclusiveAwaitable::with_instant_and_owner(
+                        commit_instant,
+                        Owner::new(new_owner),
+                    );
+                    exclusive_awaitable.set_prior_state(Ownership::Created(owner.clone()));
+                    *ownership = Ownership::DeletedAwaitable(exclusive_awaitable.into());
+                    Ok(Some(true))
+                } else if deadline.is_some() {
                     *ownership = Self::augment_wait_queue(is_created, is_deleted, owner.clone());
                     Ok(None)
                 } else {
@@ -1746,8 +2682,30 @@ impl<S: Sequencer> AccessController<S> {
                 // deadlock.
                 return Err(Error::Deadlock);
             }
-            Relationship::Unknown => (),
-        };
+            Relationship::Unknown => {
+                if is_created
+                    && exclusive_awaitable.prior_ownership.is_none()
+                    && exclusive_awaitable.wait_queue.is_empty()
+                {
+                    if let Some(commit_instant) = exclusive_awaitable.owner.take_over(new_owner) {
+                        // The owner released its locks before its commit log record was
+                        // persisted, and the prior state is restored if the owner is rolled back.
+                        let mut new_exclusive_awaitable =
+                            ExclusiveAwaitable::with_instant_and_owner(
+                                commit_instant,
+                                Owner::new(new_owner),
+                            );
+                        let old_exclusive_awaitable =
+                            ExclusiveAwaitable::take_other(exclusive_awaitable);
+                        new_exclusive_awaitable.set_prior_state(Ownership::CreatedAwaitable(
+                            old_exclusive_awaitable.into(),
+                        ));
+                        *ownership = Ownership::DeletedAwaitable(new_exclusive_awaitable.into());
+                        return Ok((true, true));
+                    }
+                }
+            }
+        }
 
         if deadline.is_some() {
             Ok((false, true))
@@ -1784,6 +2742,26 @@ impl<S: Sequencer> AccessController<S> {
     }
 }
 
+impl<S: Sequencer> Default for AccessController<S> {
+    #[inline]
+    fn default() -> Self {
+        AccessController {
+            table: HashMap::default(),
+            conflict_policy: AtomicU8::default(),
+            max_waiters: AtomicUsize::new(usize::MAX),
+            wait_policy: AtomicU64::default(),
+            lock_timeout: AtomicU64::new(u64::MAX),
+            spin_limit: AtomicU32::new(DEFAULT_SPIN_LIMIT),
+            spin_rounds: AtomicU32::new(DEFAULT_SPIN_LIMIT),
+            early_lock_release: AtomicBool::new(false),
+            victim_policy: AtomicU8::default(),
+            start_clock: AtomicU64::default(),
+            serializable_transactions: TreeIndex::default(),
+            consolidation_queue: Mutex::default(),
+        }
+    }
+}
+
 impl<S: Sequencer> Owner<S> {
     /// Creates a new [`Owner`].
     fn new(anchor: &ebr::Shared<JournalAnchor<S>>) -> Owner<S> {
@@ -1791,6 +2769,34 @@ impl<S: Sequencer> Owner<S> {
             anchor: anchor.clone(),
         }
     }
+
+    /// Returns the [`Access`] of the owner.
+    fn access(&self, access_type: AccessType) -> Access {
+        Access {
+            transaction_id: self.anchor.transaction_id(),
+            journal_id: self.anchor.id(),
+            access_type,
+        }
+    }
+
+    /// Returns the [`ObjectHolder`] describing the owner.
+    fn holder(&self, access_type: AccessType) -> ObjectHolder<S::Instant> {
+        let transaction_anchor = self.anchor.transaction_anchor();
+        ObjectHolder {
+            access_type,
+            start_clock: transaction_anchor.start_clock(),
+            tag: transaction_anchor.tag().map(ToString::to_string),
+            state: self.anchor.transaction_state(),
+        }
+    }
+
+    /// Returns the identifier and state of the owner transaction.
+    fn transaction(&self) -> (TransactionID, TransactionState<S::Instant>) {
+        (
+            self.anchor.transaction_id(),
+            self.anchor.transaction_state(),
+        )
+    }
 }
 
 impl<S: Sequencer> Clone for Owner<S> {
@@ -1839,11 +2845,221 @@ impl<S: Sequencer> PartialEq for Owner<S> {
 impl<S: Sequencer> PartialOrd for Owner<S> {
     #[inline]
     fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
-        self.anchor.as_ptr().partial_cmp(&other.anchor.as_ptr())
+        Some(self.cmp(other))
     }
 }
 
 impl<S: Sequencer> ObjectState<S> {
+    /// Returns the transactions owning the database object.
+    fn owners(&self) -> Vec<(TransactionID, TransactionState<S::Instant>)> {
+        let ObjectState::Owned(ownership) = self else {
+            return Vec::new();
+        };
+        match ownership {
+            Ownership::Created(owner)
+            | Ownership::Protected(owner)
+            | Ownership::Locked(owner)
+            | Ownership::Deleted(owner) => vec![owner.transaction()],
+            Ownership::CreatedAwaitable(exclusive_awaitable)
+            | Ownership::LockedAwaitable(exclusive_awaitable)
+            | Ownership::DeletedAwaitable(exclusive_awaitable) => {
+                vec![exclusive_awaitable.owner.transaction()]
+            }
+            Ownership::ProtectedAwaitable(shared_awaitable) => shared_awaitable
+                .owner_set
+                .iter()
+                .map(Owner::transaction)
+                .collect(),
+        }
+    }
+
+    /// Returns the transactions other than the specified one that hold the database object and
+    /// have yet to be ended.
+    fn holders(&self, transaction_id: TransactionID) -> Vec<ObjectHolder<S::Instant>> {
+        let ObjectState::Owned(ownership) = self else {
+            return Vec::new();
+        };
+        let owners = match ownership {
+            Ownership::Created(owner) => vec![(AccessType::Create, owner)],
+            Ownership::Protected(owner) => vec![(AccessType::Protect, owner)],
+            Ownership::Locked(owner) => vec![(AccessType::Lock, owner)],
+            Ownership::Deleted(owner) => vec![(AccessType::Delete, owner)],
+            Ownership::CreatedAwaitable(exclusive_awaitable) => {
+                vec![(AccessType::Create, &exclusive_awaitable.owner)]
+            }
+            Ownership::LockedAwaitable(exclusive_awaitable) => {
+                vec![(AccessType::Lock, &exclusive_awaitable.owner)]
+            }
+            Ownership::DeletedAwaitable(exclusive_awaitable) => {
+                vec![(AccessType::Delete, &exclusive_awaitable.owner)]
+            }
+            Ownership::ProtectedAwaitable(shared_awaitable) => shared_awaitable
+                .owner_set
+                .iter()
+                .map(|o| (AccessType::Protect, o))
+                .collect(),
+        };
+        owners
+            .into_iter()
+            .filter(|(_, o)| o.transaction_id() != transaction_id && !o.is_terminated())
+            .map(|(access_type, o)| o.holder(access_type))
+            .collect()
+    }
+
+    /// Collects the waits-for edges of the database object, and records the anchors of the waiting
+    /// transactions.
+    ///
+    /// Requests that already have a result are not waiting.
+    fn waits_for(
+        &self,
+        object_id: u64,
+        now: Instant,
+        edges: &mut Vec<WaitsFor>,
+        waiters: &mut BTreeMap<TransactionID, ebr::Shared<TransactionAnchor<S>>>,
+    ) {
+        let ObjectState::Owned(ownership) = self else {
+            return;
+        };
+        let (holders, wait_queue): (Vec<&Owner<S>>, _) = match ownership {
+            Ownership::Created(_)
+            | Ownership::Protected(_)
+            | Ownership::Locked(_)
+            | Ownership::Deleted(_) => return,
+            Ownership::CreatedAwaitable(exclusive_awaitable)
+            | Ownership::LockedAwaitable(exclusive_awaitable)
+            | Ownership::DeletedAwaitable(exclusive_awaitable) => (
+                vec![&exclusive_awaitable.owner],
+                &exclusive_awaitable.wait_queue,
+            ),
+            Ownership::ProtectedAwaitable(shared_awaitable) => (
+                shared_awaitable.owner_set.iter().collect(),
+                &shared_awaitable.wait_queue,
+            ),
+        };
+        for request in wait_queue.iter() {
+            let (Request::Create(instant, waiter, result_placeholder)
+            | Request::Protect(instant, waiter, result_placeholder)
+            | Request::Lock(instant, waiter, result_placeholder)
+            | Request::Delete(instant, waiter, result_placeholder)) = request;
+            if result_placeholder
+                .lock_sync()
+                .is_none_or(|result_waker| result_waker.0.is_some())
+            {
+                continue;
+            }
+            let waiter_id = waiter.transaction_id();
+            for holder in &holders {
+                if holder.transaction_id() == waiter_id || holder.is_terminated() {
+                    continue;
+                }
+                waiters
+                    .entry(waiter_id)
+                    .or_insert_with(|| waiter.transaction_anchor().clone());
+                edges.push(WaitsFor {
+                    waiter: waiter_id,
+                    holder: holder.transaction_id(),
+                    object_id,
+                    wait_duration: now.saturating_duration_since(*instant),
+                });
+            }
+        }
+    }
+
+    /// Makes the waiting requests of the transaction fail with [`Error::DeadlockDetected`].
+    fn reject_waiter(&self, transaction_id: TransactionID, report: &DeadlockReport) {
+        let ObjectState::Owned(ownership) = self else {
+            return;
+        };
+        let wait_queue = match ownership {
+            Ownership::Created(_)
+            | Ownership::Protected(_)
+            | Ownership::Locked(_)
+            | Ownership::Deleted(_) => return,
+            Ownership::CreatedAwaitable(exclusive_awaitable)
+            | Ownership::LockedAwaitable(exclusive_awaitable)
+            | Ownership::DeletedAwaitable(exclusive_awaitable) => &exclusive_awaitable.wait_queue,
+            Ownership::ProtectedAwaitable(shared_awaitable) => &shared_awaitable.wait_queue,
+        };
+        for request in wait_queue.iter() {
+            let (Request::Create(_, waiter, result_placeholder)
+            | Request::Protect(_, waiter, result_placeholder)
+            | Request::Lock(_, waiter, result_placeholder)
+            | Request::Delete(_, waiter, result_placeholder)) = request;
+            if waiter.transaction_id() != transaction_id {
+                continue;
+            }
+            if let Some(mut result_waker) = result_placeholder.lock_sync() {
+                if result_waker.0.is_none() {
+                    result_waker
+                        .0
+                        .replace(Err(Error::DeadlockDetected(Box::new(report.clone()))));
+                }
+                if let Some(waker) = result_waker.1.take() {
+                    waker.wake();
+                }
+            }
+        }
+    }
+
+    /// Returns the [`ObjectDependency`] of the database object, and records the transactions
+    /// involved in it.
+    fn dependency(
+        &self,
+        object_id: u64,
+        transactions: &mut BTreeMap<TransactionID, TransactionState<S::Instant>>,
+    ) -> Option<ObjectDependency> {
+        let ObjectState::Owned(ownership) = self else {
+            return None;
+        };
+        let (holders, wait_queue) = match ownership {
+            Ownership::Created(owner) => (vec![owner.access(AccessType::Create)], None),
+            Ownership::Protected(owner) => (vec![owner.access(AccessType::Protect)], None),
+            Ownership::Locked(owner) => (vec![owner.access(AccessType::Lock)], None),
+            Ownership::Deleted(owner) => (vec![owner.access(AccessType::Delete)], None),
+            Ownership::CreatedAwaitable(exclusive_awaitable) => (
+                vec![exclusive_awaitable.owner.access(AccessType::Create)],
+                Some(&exclusive_awaitable.wait_queue),
+            ),
+            Ownership::LockedAwaitable(exclusive_awaitable) => (
+                vec![exclusive_awaitable.owner.access(AccessType::Lock)],
+                Some(&exclusive_awaitable.wait_queue),
+            ),
+            Ownership::DeletedAwaitable(exclusive_awaitable) => (
+                vec![exclusive_awaitable.owner.access(AccessType::Delete)],
+                Some(&exclusive_awaitable.wait_queue),
+            ),
+            Ownership::ProtectedAwaitable(shared_awaitable) => (
+                shared_awaitable
+                    .owner_set
+                    .iter()
+                    .map(|o| o.access(AccessType::Protect))
+                    .collect(),
+                Some(&shared_awaitable.wait_queue),
+            ),
+        };
+        let mut owners = self.owners();
+        let waiters = wait_queue.map_or_else(Vec::new, |w| {
+            w.iter()
+                .map(|request| {
+                    let (access_type, owner) = match request {
+                        Request::Create(_, owner, _) => (AccessType::Create, owner),
+                        Request::Protect(_, owner, _) => (AccessType::Protect, owner),
+                        Request::Lock(_, owner, _) => (AccessType::Lock, owner),
+                        Request::Delete(_, owner, _) => (AccessType::Delete, owner),
+                    };
+                    owners.push(owner.transaction());
+                    owner.access(access_type)
+                })
+                .collect()
+        });
+        transactions.extend(owners);
+        Some(ObjectDependency {
+            object_id,
+            holders,
+            waiters,
+        })
+    }
+
     /// Prepares the [`ObjectState`] for ownership transfer.
     ///
     /// This rolls any promoted ownership back to the previous state if the owner was rolled back,
@@ -1890,6 +3106,18 @@ impl<S: Sequencer> ObjectState<S> {
                                 Ownership::ProtectedAwaitable(shared_awaitable) => {
                                     shared_awaitable.wait_queue = wait_queue;
                                 }
+                                Ownership::Created(owner) => {
+                                    *ownership = Ownership::CreatedAwaitable(
+                                        ExclusiveAwaitable::with_owner_and_wait_queue(
+                                            owner.clone(),
+                                            wait_queue,
+                                        )
+                                        .into(),
+                                    );
+                                }
+                                Ownership::CreatedAwaitable(exclusive_awaitable) => {
+                                    exclusive_awaitable.wait_queue = wait_queue;
+                                }
                                 _ => unreachable!(),
                             }
                         }
@@ -2050,6 +3278,36 @@ impl<S: Sequencer> SharedAwaitable<S> {
         self.owner_set.is_empty() && self.wait_queue.is_empty()
     }
 
+    /// Checks if the requester can wait for the shared owners to release the database object.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Deadlock`] if the transaction of the requester is one of the shared owners,
+    /// and another shared owner is already waiting to upgrade its ownership; they would otherwise
+    /// wait for each other.
+    fn check_upgrade(&self, requester: &JournalAnchor<S>) -> Result<(), Error> {
+        let transaction_id = requester.transaction_id();
+        let is_owner = |owner: &Owner<S>| {
+            owner.anchor.transaction_id() != transaction_id
+                && self
+                    .owner_set
+                    .iter()
+                    .any(|o| o.anchor.transaction_id() == owner.anchor.transaction_id())
+        };
+        if self
+            .owner_set
+            .iter()
+            .any(|o| o.anchor.transaction_id() == transaction_id)
+            && self.wait_queue.iter().any(|r| match r {
+                Request::Lock(_, owner, _) | Request::Delete(_, owner, _) => is_owner(owner),
+                Request::Create(..) | Request::Protect(..) => false,
+            })
+        {
+            return Err(Error::Deadlock);
+        }
+        Ok(())
+    }
+
     /// Pushes a request into the wait queue.
     fn push_request(&mut self, request: Request<S>) {
         self.wait_queue.push_back(request);
@@ -2129,6 +3387,17 @@ impl<S: Sequencer> ExclusiveAwaitable<S> {
         self.prior_ownership.replace(Box::new(old_access_data));
     }
 
+    /// Returns the creator of the database object if the database object was created by another
+    /// transaction that released its locks before being committed.
+    fn released_creator(&self) -> Option<&Owner<S>> {
+        let creator = match self.prior_ownership.as_deref()? {
+            Ownership::Created(owner) => owner,
+            Ownership::CreatedAwaitable(exclusive_awaitable) => &exclusive_awaitable.owner,
+            _ => return None,
+        };
+        (creator.transaction_id() != self.owner.transaction_id()).then_some(creator)
+    }
+
     /// Pushes a request into the wait queue.
     fn push_request(&mut self, request: Request<S>) {
         self.wait_queue.push_back(request);
@@ -2137,14 +3406,40 @@ impl<S: Sequencer> ExclusiveAwaitable<S> {
 }
 
 impl<S: Sequencer> WaitQueue<S> {
-    /// Pops the oldest request.
-    fn clone_oldest(&self) -> Option<Request<S>> {
-        self.front().map(Clone::clone)
+    /// Clones the request to be processed next along with its position in the queue.
+    fn clone_next(&self, wait_policy: WaitPolicy, now: Instant) -> Option<(usize, Request<S>)> {
+        let index = match wait_policy {
+            WaitPolicy::Fifo => 0,
+            WaitPolicy::Priority { aging } => self
+                .iter()
+                .enumerate()
+                .max_by_key(|(i, r)| (r.effective_priority(aging, now), cmp::Reverse(*i)))
+                .map(|(i, _)| i)?,
+        };
+        self.get(index).map(|r| (index, r.clone()))
     }
 
-    /// Removes the oldest request.
-    fn remove_oldest(&mut self) {
-        self.pop_front();
+    /// Removes requests made by aborted transactions, and wakes them up with
+    /// [`Error::Aborted`].
+    fn reject_aborted(&mut self) {
+        self.retain(|r| {
+            let (Request::Create(_, owner, result_placeholder)
+            | Request::Protect(_, owner, result_placeholder)
+            | Request::Lock(_, owner, result_placeholder)
+            | Request::Delete(_, owner, result_placeholder)) = r;
+            if !owner.transaction_anchor().is_aborted() {
+                return true;
+            }
+            if let Some(mut result_waker) = result_placeholder.lock_sync() {
+                if result_waker.0.is_none() {
+                    result_waker.0.replace(Err(Error::Aborted));
+                }
+                if let Some(waker) = result_waker.1.take() {
+                    waker.wake();
+                }
+            }
+            false
+        });
     }
 
     /// Inherits other [`WaitQueue`].
@@ -2208,10 +3503,59 @@ impl<S: Sequencer> Drop for WaitQueue<S> {
     }
 }
 
+impl From<ConflictPolicy> for u8 {
+    #[inline]
+    fn from(v: ConflictPolicy) -> u8 {
+        match v {
+            ConflictPolicy::Wait => 0,
+            ConflictPolicy::WaitDie => 1,
+            ConflictPolicy::WoundWait => 2,
+            ConflictPolicy::NoWait => 3,
+        }
+    }
+}
+
+impl From<WaitPolicy> for u64 {
+    #[inline]
+    fn from(v: WaitPolicy) -> u64 {
+        match v {
+            WaitPolicy::Fifo => 0,
+            WaitPolicy::Priority { aging } => {
+                u64::try_from(aging.as_nanos()).map_or(u64::MAX, |a| a.saturating_add(1))
+            }
+        }
+    }
+}
+
+impl From<u64> for WaitPolicy {
+    #[inline]
+    fn from(v: u64) -> WaitPolicy {
+        if v == 0 {
+            WaitPolicy::Fifo
+        } else {
+            WaitPolicy::Priority {
+                aging: Duration::from_nanos(v - 1),
+            }
+        }
+    }
+}
+
+impl From<u8> for ConflictPolicy {
+    #[inline]
+    fn from(v: u8) -> ConflictPolicy {
+        match v {
+            1 => ConflictPolicy::WaitDie,
+            2 => ConflictPolicy::WoundWait,
+            3 => ConflictPolicy::NoWait,
+            _ => ConflictPolicy::Wait,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Database, MonotonicU64};
+    use crate::{Database, Metadata, MonotonicU64};
     use std::num::NonZeroU32;
     use std::path::Path;
     use std::sync::atomic::AtomicUsize;
@@ -2223,7 +3567,7 @@ mod tests {
 
     static_assertions::assert_eq_size!(ObjectState<MonotonicU64>, [u8; 16]);
 
-    const TIMEOUT_UNEXPECTED: Duration = Duration::from_secs(60);
+    const TIMEOUT_UNEXPECTED: Duration = Duration::from_mins(1);
     const TIMEOUT_EXPECTED: Duration = Duration::from_millis(1);
 
     #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -2495,7 +3839,7 @@ mod tests {
                     }
                     let waiting_transaction = database.transaction();
                     let mut waiting_journal = waiting_transaction.journal();
-                    let (waiting_result, _) = futures::join!(
+                    let (waiting_result, ()) = futures::join!(
                         take_access_action(
                             waiting_action,
                             access_controller,
@@ -2673,7 +4017,7 @@ mod tests {
                         )
                         .await
                     };
-                    let (_, result, result_post) = futures::join!(
+                    let ((), result, result_post) = futures::join!(
                         async { assert!(prepared.await.is_ok()) },
                         action_runner,
                         post_action_runner
@@ -2693,7 +4037,7 @@ mod tests {
                             assert_eq!(result, Err(Error::SerializationFailure));
                             assert_eq!(result_post, Err(Error::SerializationFailure));
                         }
-                    };
+                    }
 
                     assert!(remove_dir_all(path).await.is_ok());
                 }
@@ -2789,6 +4133,542 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn conflict_no_wait() {
+        const DIR: &str = "access_controller_conflict_no_wait_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let access_controller = database.access_controller();
+        access_controller.set_conflict_policy(ConflictPolicy::NoWait);
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert_eq!(
+            access_controller.lock(0, &mut journal, None).await,
+            Ok(true)
+        );
+        assert_eq!(Some(journal.submit()), NonZeroU32::new(1));
+
+        let transaction_other = database.transaction();
+        let mut journal_other = transaction_other.journal();
+        assert_eq!(
+            access_controller
+                .lock(
+                    0,
+                    &mut journal_other,
+                    Some(Instant::now() + TIMEOUT_UNEXPECTED)
+                )
+                .await,
+            Err(Error::SerializationFailure)
+        );
+        drop(journal_other);
+        drop(transaction_other);
+        assert!(transaction.commit().await.is_ok());
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn conflict_wait_die() {
+        const DIR: &str = "access_controller_conflict_wait_die_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let access_controller = database.access_controller();
+        access_controller.set_conflict_policy(ConflictPolicy::WaitDie);
+
+        let transaction_old = database.transaction();
+        let transaction_young = database.transaction();
+        let mut journal_old = transaction_old.journal();
+        let mut journal_young = transaction_young.journal();
+        assert_eq!(
+            access_controller.lock(0, &mut journal_old, None).await,
+            Ok(true)
+        );
+        assert_eq!(
+            access_controller.lock(1, &mut journal_young, None).await,
+            Ok(true)
+        );
+        assert_eq!(Some(journal_old.submit()), NonZeroU32::new(1));
+        assert_eq!(Some(journal_young.submit()), NonZeroU32::new(1));
+
+        // The younger transaction dies.
+        let mut journal_young = transaction_young.journal();
+        assert_eq!(
+            access_controller
+                .lock(
+                    0,
+                    &mut journal_young,
+                    Some(Instant::now() + TIMEOUT_UNEXPECTED)
+                )
+                .await,
+            Err(Error::Deadlock)
+        );
+
+        // The older transaction waits.
+        let mut journal_old = transaction_old.journal();
+        assert_eq!(
+            access_controller
+                .lock(1, &mut journal_old, Some(Instant::now() + TIMEOUT_EXPECTED))
+                .await,
+            Err(Error::Timeout)
+        );
+
+        drop(journal_young);
+        transaction_young.rollback();
+        assert_eq!(
+            access_controller
+                .lock(
+                    1,
+                    &mut journal_old,
+                    Some(Instant::now() + TIMEOUT_UNEXPECTED)
+                )
+                .await,
+            Ok(true)
+        );
+        assert_eq!(Some(journal_old.submit()), NonZeroU32::new(2));
+        assert!(transaction_old.commit().await.is_ok());
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn conflict_wound_wait() {
+        const DIR: &str = "access_controller_conflict_wound_wait_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let access_controller = database.access_controller();
+        access_controller.set_conflict_policy(ConflictPolicy::WoundWait);
+
+        let transaction_old = database.transaction();
+        let transaction_young = database.transaction();
+        let mut journal_old = transaction_old.journal();
+        let mut journal_young = transaction_young.journal();
+        assert_eq!(
+            access_controller.lock(0, &mut journal_young, None).await,
+            Ok(true)
+        );
+        assert_eq!(
+            access_controller.lock(1, &mut journal_old, None).await,
+            Ok(true)
+        );
+        assert_eq!(Some(journal_old.submit()), NonZeroU32::new(1));
+        assert_eq!(Some(journal_young.submit()), NonZeroU32::new(1));
+
+        // The younger transaction waits for the older one, and the older one wounds the younger
+        // one to break the deadlock.
+        let young = async move {
+            let mut journal = transaction_young.journal();
+            let result = access_controller
+                .lock(1, &mut journal, Some(Instant::now() + TIMEOUT_UNEXPECTED))
+                .await;
+            assert_eq!(
+                access_controller.lock(2, &mut journal, None).await,
+                Err(Error::Deadlock)
+            );
+            drop(journal);
+            assert_eq!(transaction_young.commit().await, Err(Error::Deadlock));
+            result
+        };
+        let old = async {
+            let mut journal = transaction_old.journal();
+            let result = access_controller
+                .lock(0, &mut journal, Some(Instant::now() + TIMEOUT_UNEXPECTED))
+                .await;
+            assert_eq!(Some(journal.submit()), NonZeroU32::new(2));
+            result
+        };
+        assert_eq!(tokio::join!(young, old), (Err(Error::Deadlock), Ok(true)));
+        assert!(transaction_old.commit().await.is_ok());
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn deadlock_detection() {
+        const DIR: &str = "access_controller_deadlock_detection_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let access_controller = database.access_controller();
+        access_controller.set_deadlock_detection(Some(VictimPolicy::LowestPriority));
+
+        let transaction_old = database.transaction();
+        let mut transaction_young = database.transaction();
+        transaction_young.set_priority(1);
+        let (id_old, id_young) = (transaction_old.id(), transaction_young.id());
+        let mut journal_old = transaction_old.journal();
+        let mut journal_young = transaction_young.journal();
+        assert_eq!(
+            access_controller.lock(0, &mut journal_old, None).await,
+            Ok(true)
+        );
+        assert_eq!(
+            access_controller.lock(1, &mut journal_young, None).await,
+            Ok(true)
+        );
+        assert_eq!(Some(journal_old.submit()), NonZeroU32::new(1));
+        assert_eq!(Some(journal_young.submit()), NonZeroU32::new(1));
+
+        // The older transaction has the lower priority, therefore it gives up.
+        let old = async move {
+            let mut journal = transaction_old.journal();
+            let result = access_controller
+                .lock(1, &mut journal, Some(Instant::now() + TIMEOUT_UNEXPECTED))
+                .await;
+            drop(journal);
+            transaction_old.rollback();
+            result
+        };
+        let young = async {
+            let mut journal = transaction_young.journal();
+            let result = access_controller
+                .lock(0, &mut journal, Some(Instant::now() + TIMEOUT_UNEXPECTED))
+                .await;
+            assert_eq!(Some(journal.submit()), NonZeroU32::new(2));
+            result
+        };
+        let (result_old, result_young) = tokio::join!(old, young);
+        assert_eq!(result_young, Ok(true));
+        let Err(Error::DeadlockDetected(report)) = result_old else {
+            unreachable!("{result_old:?}");
+        };
+        assert_eq!(report.victim, id_old);
+        assert_eq!(report.cycle.len(), 2);
+        assert!(report
+            .cycle
+            .iter()
+            .any(|e| e.waiter == id_old && e.holder == id_young && e.object_id == 1));
+        assert!(report
+            .cycle
+            .iter()
+            .any(|e| e.waiter == id_young && e.holder == id_old && e.object_id == 0));
+        assert_eq!(database.statistics().deadlocks_detected, 1);
+        assert!(transaction_young.commit().await.is_ok());
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn wait_policy_priority() {
+        const DIR: &str = "access_controller_wait_policy_priority_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let access_controller = database.access_controller();
+
+        // Without aging, the high-priority transaction jumps the queue; the low-priority
+        // transaction has been waiting long enough to be served first with aging.
+        for (aging, expected) in [(Duration::ZERO, [8, 0]), (Duration::from_nanos(1), [0, 8])] {
+            access_controller.set_wait_policy(WaitPolicy::Priority { aging });
+            let transaction = database.transaction();
+            let mut journal = transaction.journal();
+            assert_eq!(
+                access_controller.lock(0, &mut journal, None).await,
+                Ok(true)
+            );
+            assert_eq!(Some(journal.submit()), NonZeroU32::new(1));
+
+            let order = std::sync::Mutex::new(Vec::new());
+            let wait = |priority: u8| {
+                let order = &order;
+                let mut transaction = database.transaction();
+                transaction.set_priority(priority);
+                async move {
+                    let mut journal = transaction.journal();
+                    let result = access_controller
+                        .lock(0, &mut journal, Some(Instant::now() + TIMEOUT_UNEXPECTED))
+                        .await;
+                    order.lock().unwrap().push(priority);
+                    assert_eq!(Some(journal.submit()), NonZeroU32::new(1));
+                    assert!(transaction.commit().await.is_ok());
+                    result
+                }
+            };
+            let owner = async {
+                tokio::time::sleep(TIMEOUT_EXPECTED).await;
+                assert!(transaction.commit().await.is_ok());
+            };
+            assert_eq!(
+                tokio::join!(wait(0), wait(8), owner),
+                (Ok(true), Ok(true), ())
+            );
+            assert_eq!(*order.lock().unwrap(), expected);
+        }
+        access_controller.set_wait_policy(WaitPolicy::Fifo);
+        assert_eq!(access_controller.wait_policy(), WaitPolicy::Fifo);
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn max_waiters() {
+        const DIR: &str = "access_controller_max_waiters_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let access_controller = database.access_controller();
+        access_controller.set_max_waiters(Some(1));
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert_eq!(
+            access_controller.lock(0, &mut journal, None).await,
+            Ok(true)
+        );
+        assert_eq!(Some(journal.submit()), NonZeroU32::new(1));
+
+        // The second waiter is rejected while the first one is waiting.
+        let transaction_waiting = database.transaction();
+        let mut journal_waiting = transaction_waiting.journal();
+        let transaction_rejected = database.transaction();
+        let mut journal_rejected = transaction_rejected.journal();
+        assert_eq!(
+            tokio::join!(
+                access_controller.lock(
+                    0,
+                    &mut journal_waiting,
+                    Some(Instant::now() + TIMEOUT_EXPECTED)
+                ),
+                access_controller.share(
+                    0,
+                    &mut journal_rejected,
+                    Some(Instant::now() + TIMEOUT_UNEXPECTED)
+                )
+            ),
+            (Err(Error::Timeout), Err(Error::Overloaded))
+        );
+        drop(journal_waiting);
+        drop(journal_rejected);
+        transaction_waiting.rollback();
+        transaction_rejected.rollback();
+        assert!(transaction.commit().await.is_ok());
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn adaptive_spinning() {
+        const DIR: &str = "access_controller_adaptive_spinning_test";
+        let path = Path::new(DIR);
+        let database = Arc::new(Database::with_path(path).await.unwrap());
+        let access_controller = database.access_controller();
+        assert_eq!(access_controller.spin_rounds(), DEFAULT_SPIN_LIMIT);
+
+        access_controller.set_spin_limit(u32::MAX);
+        assert_eq!(access_controller.spin_limit(), MAX_SPIN_LIMIT);
+        access_controller.adapt_spin_rounds(true);
+        assert_eq!(access_controller.spin_rounds(), MAX_SPIN_LIMIT);
+        for _ in 0..MAX_SPIN_LIMIT {
+            access_controller.adapt_spin_rounds(false);
+        }
+        assert_eq!(access_controller.spin_rounds(), 1);
+        access_controller.adapt_spin_rounds(true);
+        assert_eq!(access_controller.spin_rounds(), 2);
+
+        access_controller.set_spin_limit(0);
+        access_controller.adapt_spin_rounds(false);
+        access_controller.adapt_spin_rounds(true);
+        assert_eq!(access_controller.spin_rounds(), 0);
+
+        // Waiters are granted access whether or not they spin.
+        for spin_limit in [0, MAX_SPIN_LIMIT] {
+            access_controller.set_spin_limit(spin_limit);
+            let transaction = database.transaction();
+            let mut journal = transaction.journal();
+            assert_eq!(
+                access_controller.lock(0, &mut journal, None).await,
+                Ok(true)
+            );
+            assert_eq!(journal.submit().get(), 1);
+
+            let database_clone = database.clone();
+            let waiter = tokio::spawn(async move {
+                let transaction = database_clone.transaction();
+                let mut journal = transaction.journal();
+                let deadline = Some(Instant::now() + TIMEOUT_UNEXPECTED);
+                let result = database_clone
+                    .access_controller()
+                    .lock(0, &mut journal, deadline)
+                    .await;
+                drop(journal);
+                transaction.rollback();
+                result
+            });
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            assert!(transaction.commit().await.is_ok());
+            assert_eq!(waiter.await.unwrap(), Ok(true));
+            assert!(access_controller.spin_rounds() <= spin_limit);
+        }
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn early_lock_release() {
+        const DIR: &str = "access_controller_early_lock_release_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("elr".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert!(container
+            .insert(b"k", b"0", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        for (value, enabled, rolled_back) in [
+            (b"1", false, false),
+            (b"2", true, false),
+            (b"3", true, true),
+        ] {
+            database.access_controller().set_early_lock_release(enabled);
+            let owner = database.transaction();
+            let mut journal = owner.journal();
+            assert!(container
+                .update(b"k", value, &mut journal, None)
+                .await
+                .is_ok());
+            assert_eq!(journal.submit().get(), 1);
+            let mut committable = Box::pin(owner.prepare().await.unwrap());
+            assert!(futures::poll!(committable.as_mut()).is_pending());
+
+            let dependent = database.transaction();
+            let mut journal = dependent.journal();
+            let mut seen = Vec::new();
+            let result = container
+                .update_with(
+                    b"k",
+                    |v| {
+                        seen.extend_from_slice(v);
+                        [v, b"+"].concat()
+                    },
+                    &mut journal,
+                    None,
+                )
+                .await;
+            assert_eq!(journal.submit().get(), 1);
+            if !enabled {
+                assert!(result.is_err());
+                assert!(committable.await.is_ok());
+                dependent.rollback();
+                continue;
+            }
+
+            // The lock is taken over while the commit log record of the owner is persisted.
+            assert_eq!(result, Ok(()));
+            assert_eq!(seen, value);
+            let mut commit = Box::pin(dependent.commit());
+            assert!(futures::poll!(commit.as_mut()).is_pending());
+            if rolled_back {
+                drop(committable);
+
+                // The changes made by the owner are invisible while the dependent is active.
+                let reader = database.transaction();
+                let mut journal = reader.journal();
+                assert_eq!(
+                    container
+                        .read(b"k", &mut journal, None)
+                        .await
+                        .unwrap()
+                        .as_deref(),
+                    Some(&b"2+"[..])
+                );
+                drop(journal);
+                reader.rollback();
+                assert_eq!(commit.await, Err(Error::SerializationFailure));
+            } else {
+                let owner_commit_instant = committable.await.unwrap();
+                assert!(commit.await.unwrap() > owner_commit_instant);
+            }
+        }
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert_eq!(
+            container
+                .read(b"k", &mut journal, None)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some(&b"2+"[..])
+        );
+        drop(journal);
+        transaction.rollback();
+
+        drop(container);
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn dependency_graph() {
+        const DIR: &str = "access_controller_dependency_graph_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let access_controller = database.access_controller();
+        let holder = database.transaction();
+        let mut holder_journal = holder.journal();
+        assert_eq!(
+            access_controller.lock(1, &mut holder_journal, None).await,
+            Ok(true)
+        );
+        assert_eq!(holder_journal.submit().get(), 1);
+        let waiter = database.transaction();
+        let mut waiter_journal = waiter.journal();
+        assert_eq!(
+            access_controller.share(2, &mut waiter_journal, None).await,
+            Ok(true)
+        );
+
+        let deadline = Instant::now() + TIMEOUT_UNEXPECTED;
+        let (result, ()) = tokio::join!(
+            access_controller.share(1, &mut waiter_journal, Some(deadline)),
+            async {
+                let graph = loop {
+                    let graph = access_controller.dependency_graph().await;
+                    if !graph.waits_for().is_empty() {
+                        break graph;
+                    }
+                    tokio::task::yield_now().await;
+                };
+                assert_eq!(graph.waits_for(), vec![(waiter.id(), holder.id(), 1)]);
+                assert_eq!(graph.transactions.len(), 2);
+                assert!(graph
+                    .transactions
+                    .iter()
+                    .all(|(id, state)| (*id == holder.id() || *id == waiter.id())
+                        && *state == TransactionState::Active));
+                assert_eq!(graph.objects.len(), 2);
+                assert_eq!(graph.objects[0].holders[0].access_type, AccessType::Lock);
+                assert_eq!(graph.objects[0].waiters[0].access_type, AccessType::Protect);
+                assert_eq!(graph.objects[1].waiters, Vec::new());
+                let dot = graph.to_dot();
+                assert!(dot.contains(&format!("\"o1\" -> \"t{}\"", holder.id())));
+                assert!(dot.contains(&format!("\"t{}\" -> \"o1\"", waiter.id())));
+                holder.rollback();
+            }
+        );
+        assert_eq!(result, Ok(true));
+        assert!(access_controller
+            .dependency_graph()
+            .await
+            .waits_for()
+            .is_empty());
+
+        drop(waiter_journal);
+        drop(waiter);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
     #[tokio::test]
     async fn object_lifecycle() {
         for commit in [false, true] {
@@ -2853,6 +4733,57 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn consolidation() {
+        const DIR: &str = "access_controller_consolidation_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let access_controller = database.access_controller();
+        let condition = |i: &u64| *i <= database.sequencer().min(Relaxed);
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(journal.create(&[1, 2], None).await.is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        let mut journal = transaction.journal();
+        assert!(journal.create(&[3], None).await.is_ok());
+        drop(journal);
+        let snapshot = database.snapshot();
+        assert!(transaction.commit().await.is_ok());
+
+        // The database objects are not consolidated while the snapshot is alive.
+        access_controller.consolidate_sync(&condition);
+        assert!(access_controller.table.read(&1, |_, _| ()).is_some());
+        assert!(access_controller.table.read(&2, |_, _| ()).is_some());
+        drop(snapshot);
+        access_controller.consolidate_sync(&condition);
+        assert!(access_controller.table.read(&1, |_, _| ()).is_none());
+        assert!(access_controller.table.read(&2, |_, _| ()).is_none());
+
+        // Rolled back creations are left to garbage collection.
+        assert!(access_controller.table.read(&3, |_, _| ()).is_some());
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(journal.delete(&[1], None).await.is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        access_controller.consolidate_sync(&condition);
+        assert_eq!(
+            access_controller
+                .table
+                .read(&1, |_, o| matches!(o, ObjectState::Deleted(_))),
+            Some(true)
+        );
+        let snapshot = database.snapshot();
+        assert_eq!(access_controller.read(1, &snapshot, None).await, Ok(false));
+        assert_eq!(access_controller.read(2, &snapshot, None).await, Ok(true));
+        drop(snapshot);
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 16)]
     async fn parallel_mutex() {
         const DIR: &str = "access_controller_parallel_mutex_test";
@@ -0,0 +1,138 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The module defines the cancellation interface of blocking operations.
+
+use super::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::{Acquire, Release};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// [`CancellationToken`] aborts operations waiting for other transactions or IO completion.
+///
+/// A [`CancellationToken`] is attached to a [`Transaction`](super::Transaction), and any
+/// [`Journal`](super::Journal) of the transaction waiting for a database object or for its commit
+/// log record to be persisted returns [`Error::Cancelled`] once the token is cancelled. Clones of
+/// a [`CancellationToken`] share the same state, therefore the token can be cancelled from any
+/// thread.
+///
+/// # Examples
+///
+/// ```
+/// use sap_tsf::CancellationToken;
+///
+/// let token = CancellationToken::default();
+/// let cloned = token.clone();
+/// assert!(!cloned.is_cancelled());
+/// token.cancel();
+/// assert!(cloned.is_cancelled());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    /// The state shared among clones.
+    state: Arc<State>,
+}
+
+/// The shared state of [`CancellationToken`].
+#[derive(Debug, Default)]
+struct State {
+    /// The token was cancelled.
+    cancelled: AtomicBool,
+
+    /// Tasks to wake up when the token is cancelled.
+    wakers: Mutex<Vec<Waker>>,
+}
+
+/// [`Cancellable`] makes a [`Future`] return [`Error::Cancelled`] when the associated
+/// [`CancellationToken`] is cancelled.
+#[derive(Debug)]
+pub(super) struct Cancellable<F> {
+    /// The [`Future`] to await.
+    future: F,
+
+    /// The [`CancellationToken`].
+    token: Option<CancellationToken>,
+}
+
+impl CancellationToken {
+    /// Cancels every operation that is waiting for the [`CancellationToken`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::CancellationToken;
+    ///
+    /// let token = CancellationToken::default();
+    /// token.cancel();
+    /// assert!(token.is_cancelled());
+    /// ```
+    #[inline]
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Release);
+        let wakers = self
+            .state
+            .wakers
+            .lock()
+            .map(|mut w| std::mem::take(&mut *w))
+            .unwrap_or_default();
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Returns `true` if the [`CancellationToken`] has been cancelled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::CancellationToken;
+    ///
+    /// let token = CancellationToken::default();
+    /// assert!(!token.is_cancelled());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Acquire)
+    }
+
+    /// Registers the [`Waker`] to be woken up when the token is cancelled.
+    ///
+    /// Returns `true` if the token has been cancelled.
+    pub(super) fn wait(&self, waker: &Waker) -> bool {
+        if self.is_cancelled() {
+            return true;
+        }
+        if let Ok(mut wakers) = self.state.wakers.lock() {
+            if !wakers.iter().any(|w| w.will_wake(waker)) {
+                wakers.push(waker.clone());
+            }
+        }
+
+        // The token may have been cancelled before the waker was pushed.
+        self.is_cancelled()
+    }
+}
+
+impl<F> Cancellable<F> {
+    /// Creates a new [`Cancellable`].
+    pub(super) fn new(future: F, token: Option<CancellationToken>) -> Cancellable<F> {
+        Cancellable { future, token }
+    }
+}
+
+impl<T, F: Future<Output = Result<T, Error>> + Unpin> Future for Cancellable<F> {
+    type Output = Result<T, Error>;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.token.as_ref().is_some_and(|t| t.wait(cx.waker())) {
+            return Poll::Ready(Err(Error::Cancelled));
+        }
+        Pin::new(&mut self.future).poll(cx)
+    }
+}
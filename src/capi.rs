@@ -0,0 +1,828 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! C interface.
+//!
+//! The functions are declared in `include/sap_tsf.h`, and they allow C, C++, or Python
+//! applications to embed a [`Database`] backed by [`FileIO`] through opaque handles; the crate
+//! can be built as a C library with `cargo rustc --release --features capi --crate-type cdylib`.
+//!
+//! Every function returns [`TSF_OK`] if successful, or a negative error code corresponding to an
+//! [`Error`] variant otherwise, and [`tsf_error_message`] describes the error code. The functions
+//! block the calling thread until the operation completes, and a request conflicting with other
+//! transactions waits for them for up to [`LOCK_WAIT`] or the lock timeout of the
+//! [`AccessController`](crate::AccessController). All the transactions of a database must be
+//! committed or rolled back before the database is closed.
+
+use crate::{
+    Database, Error, FileIO, Journal, Metadata, MonotonicU64, ShutdownPolicy, Transaction,
+};
+use std::ffi::{c_char, c_int, c_void, CStr};
+use std::future::Future;
+use std::num::NonZeroU32;
+use std::ops::Bound;
+use std::path::Path;
+use std::pin::pin;
+use std::ptr;
+use std::slice;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+/// [`TsfDatabase`] is an opaque handle of a [`Database`] opened by [`tsf_open`].
+pub struct TsfDatabase(Database<MonotonicU64, FileIO<MonotonicU64>>);
+
+/// [`TsfTransaction`] is an opaque handle of a [`Transaction`] started by [`tsf_begin`].
+pub struct TsfTransaction {
+    /// The database of the transaction.
+    database: &'static Database<MonotonicU64, FileIO<MonotonicU64>>,
+
+    /// The transaction.
+    transaction: Transaction<'static, MonotonicU64, FileIO<MonotonicU64>>,
+}
+
+/// [`TsfScanCallback`] receives each key-value pair visited by [`tsf_scan`].
+///
+/// The key and value are only valid while the callback is running, and returning a non-zero
+/// value stops the scan.
+pub type TsfScanCallback = unsafe extern "C" fn(
+    context: *mut c_void,
+    key: *const u8,
+    key_len: usize,
+    value: *const u8,
+    value_len: usize,
+) -> c_int;
+
+/// The operation was successful.
+pub const TSF_OK: c_int = 0;
+
+/// See [`Error::AlreadyInUse`].
+pub const TSF_ERROR_ALREADY_IN_USE: c_int = -1;
+
+/// See [`Error::Cancelled`].
+pub const TSF_ERROR_CANCELLED: c_int = -2;
+
+/// See [`Error::Conflict`].
+pub const TSF_ERROR_CONFLICT: c_int = -3;
+
+/// See [`Error::CorruptDatabase`] and [`Error::CorruptLog`].
+pub const TSF_ERROR_CORRUPT_DATABASE: c_int = -4;
+
+/// See [`Error::CorruptPage`].
+pub const TSF_ERROR_CORRUPT_PAGE: c_int = -5;
+
+/// See [`Error::Deadlock`] and [`Error::DeadlockDetected`].
+pub const TSF_ERROR_DEADLOCK: c_int = -6;
+
+/// See [`Error::DiskFull`].
+pub const TSF_ERROR_DISK_FULL: c_int = -7;
+
+/// See [`Error::Generic`].
+pub const TSF_ERROR_GENERIC: c_int = -8;
+
+/// See [`Error::IO`] and [`Error::IOAt`].
+pub const TSF_ERROR_IO: c_int = -9;
+
+/// See [`Error::NotFound`].
+pub const TSF_ERROR_NOT_FOUND: c_int = -10;
+
+/// See [`Error::OutOfMemory`].
+pub const TSF_ERROR_OUT_OF_MEMORY: c_int = -11;
+
+/// See [`Error::OutOfMemoryBudget`].
+pub const TSF_ERROR_OUT_OF_MEMORY_BUDGET: c_int = -12;
+
+/// See [`Error::Overloaded`].
+pub const TSF_ERROR_OVERLOADED: c_int = -13;
+
+/// See [`Error::ReadOnly`].
+pub const TSF_ERROR_READ_ONLY: c_int = -14;
+
+/// See [`Error::SerializationFailure`].
+pub const TSF_ERROR_SERIALIZATION_FAILURE: c_int = -15;
+
+/// See [`Error::ShutDown`].
+pub const TSF_ERROR_SHUT_DOWN: c_int = -16;
+
+/// See [`Error::Timeout`].
+pub const TSF_ERROR_TIMEOUT: c_int = -17;
+
+/// See [`Error::UnexpectedState`].
+pub const TSF_ERROR_UNEXPECTED_STATE: c_int = -18;
+
+/// See [`Error::UniquenessViolation`].
+pub const TSF_ERROR_UNIQUENESS_VIOLATION: c_int = -19;
+
+/// See [`Error::WrongParameter`]; it is also returned if a pointer argument is null, or a string
+/// argument is not valid UTF-8.
+pub const TSF_ERROR_WRONG_PARAMETER: c_int = -20;
+
+/// See [`Error::Aborted`].
+pub const TSF_ERROR_ABORTED: c_int = -21;
+
+/// See [`Error::VersionChainTooLong`].
+pub const TSF_ERROR_VERSION_CHAIN_TOO_LONG: c_int = -22;
+
+/// The maximum time that a request waits for conflicting transactions.
+pub const LOCK_WAIT: Duration = Duration::from_mins(1);
+
+/// Opens the database in the directory, and stores the handle in `database`.
+///
+/// # Safety
+///
+/// `path` must be a valid nul-terminated string, and `database` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tsf_open(path: *const c_char, database: *mut *mut TsfDatabase) -> c_int {
+    // SAFETY: the caller guarantees that `path` is a valid nul-terminated string.
+    let Some(path) = (unsafe { to_str(path) }) else {
+        return TSF_ERROR_WRONG_PARAMETER;
+    };
+    if database.is_null() {
+        return TSF_ERROR_WRONG_PARAMETER;
+    }
+    match block_on(Database::with_path(Path::new(path))) {
+        Ok(opened) => {
+            // SAFETY: the caller guarantees that `database` is valid for writes.
+            unsafe { database.write(Box::into_raw(Box::new(TsfDatabase(opened)))) };
+            TSF_OK
+        }
+        Err(error) => error_code(&error),
+    }
+}
+
+/// Shuts down and closes the database, and releases the handle.
+///
+/// # Safety
+///
+/// `database` must be a handle returned by [`tsf_open`] that has not been closed, and all the
+/// transactions of the database must have been committed or rolled back.
+#[no_mangle]
+pub unsafe extern "C" fn tsf_close(database: *mut TsfDatabase) -> c_int {
+    if database.is_null() {
+        return TSF_ERROR_WRONG_PARAMETER;
+    }
+    // SAFETY: the caller guarantees that `database` was created by `tsf_open`.
+    let database = unsafe { Box::from_raw(database) };
+    match block_on(database.0.shutdown(ShutdownPolicy::Abort, None)) {
+        Ok(()) => TSF_OK,
+        Err(error) => error_code(&error),
+    }
+}
+
+/// Starts a transaction, and stores the handle in `transaction`.
+///
+/// # Safety
+///
+/// `database` must be a handle returned by [`tsf_open`] that has not been closed, and
+/// `transaction` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tsf_begin(
+    database: *mut TsfDatabase,
+    transaction: *mut *mut TsfTransaction,
+) -> c_int {
+    if database.is_null() || transaction.is_null() {
+        return TSF_ERROR_WRONG_PARAMETER;
+    }
+    // SAFETY: the caller guarantees that the database outlives the transaction.
+    let database: &'static TsfDatabase = unsafe { &*database };
+    let handle = TsfTransaction {
+        database: &database.0,
+        transaction: database.0.transaction(),
+    };
+    // SAFETY: the caller guarantees that `transaction` is valid for writes.
+    unsafe { transaction.write(Box::into_raw(Box::new(handle))) };
+    TSF_OK
+}
+
+/// Commits the transaction, stores the commit instant in `instant` unless it is null, and
+/// releases the handle.
+///
+/// # Safety
+///
+/// `transaction` must be a handle returned by [`tsf_begin`] that has not been ended, and
+/// `instant` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tsf_commit(transaction: *mut TsfTransaction, instant: *mut u64) -> c_int {
+    if transaction.is_null() {
+        return TSF_ERROR_WRONG_PARAMETER;
+    }
+    // SAFETY: the caller guarantees that `transaction` was created by `tsf_begin`.
+    let transaction = unsafe { Box::from_raw(transaction) };
+    match block_on(transaction.transaction.commit()) {
+        Ok(commit_instant) => {
+            if !instant.is_null() {
+                // SAFETY: the caller guarantees that `instant` is valid for writes.
+                unsafe { instant.write(commit_instant) };
+            }
+            TSF_OK
+        }
+        Err(error) => error_code(&error),
+    }
+}
+
+/// Rolls back the transaction, and releases the handle.
+///
+/// # Safety
+///
+/// `transaction` must be a handle returned by [`tsf_begin`] that has not been ended.
+#[no_mangle]
+pub unsafe extern "C" fn tsf_rollback(transaction: *mut TsfTransaction) -> c_int {
+    if transaction.is_null() {
+        return TSF_ERROR_WRONG_PARAMETER;
+    }
+    // SAFETY: the caller guarantees that `transaction` was created by `tsf_begin`.
+    let transaction = unsafe { Box::from_raw(transaction) };
+    transaction.transaction.rollback();
+    TSF_OK
+}
+
+/// Creates a container with the transaction.
+///
+/// # Safety
+///
+/// `transaction` must be a handle returned by [`tsf_begin`] that has not been ended, and `name`
+/// must be a valid nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn tsf_create_container(
+    transaction: *mut TsfTransaction,
+    name: *const c_char,
+) -> c_int {
+    // SAFETY: the caller guarantees that the arguments are valid.
+    let (Some(transaction), Some(name)) =
+        (unsafe { transaction.as_ref() }, unsafe { to_str(name) })
+    else {
+        return TSF_ERROR_WRONG_PARAMETER;
+    };
+    let mut journal = transaction.transaction.journal();
+    let result = block_on(transaction.database.create_container(
+        name.to_string(),
+        Metadata::default(),
+        &mut journal,
+        deadline(),
+    ));
+    finish(journal, result.map(|_| ()))
+}
+
+/// Inserts or updates the key-value pair in the container with the transaction.
+///
+/// # Safety
+///
+/// `transaction` must be a handle returned by [`tsf_begin`] that has not been ended, `container`
+/// must be a valid nul-terminated string, and `key` and `value` must be valid for reads of
+/// `key_len` and `value_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn tsf_put(
+    transaction: *mut TsfTransaction,
+    container: *const c_char,
+    key: *const u8,
+    key_len: usize,
+    value: *const u8,
+    value_len: usize,
+) -> c_int {
+    // SAFETY: the caller guarantees that the arguments are valid.
+    let (Some(transaction), Some(name), Some(key), Some(value)) = (
+        unsafe { transaction.as_ref() },
+        unsafe { to_str(container) },
+        unsafe { to_bytes(key, key_len) },
+        unsafe { to_bytes(value, value_len) },
+    ) else {
+        return TSF_ERROR_WRONG_PARAMETER;
+    };
+    let snapshot = transaction
+        .transaction
+        .snapshot()
+        .combine(transaction.database.snapshot());
+    let Some(container) = block_on(transaction.database.get_container(name, &snapshot)) else {
+        return TSF_ERROR_NOT_FOUND;
+    };
+    let mut journal = transaction.transaction.journal();
+    let result = block_on(async {
+        if container
+            .read(key, &mut journal, deadline())
+            .await?
+            .is_some()
+        {
+            container.update(key, value, &mut journal, deadline()).await
+        } else {
+            container.insert(key, value, &mut journal, deadline()).await
+        }
+    });
+    finish(journal, result)
+}
+
+/// Deletes the key-value pair from the container with the transaction.
+///
+/// # Safety
+///
+/// `transaction` must be a handle returned by [`tsf_begin`] that has not been ended, `container`
+/// must be a valid nul-terminated string, and `key` must be valid for reads of `key_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn tsf_delete(
+    transaction: *mut TsfTransaction,
+    container: *const c_char,
+    key: *const u8,
+    key_len: usize,
+) -> c_int {
+    // SAFETY: the caller guarantees that the arguments are valid.
+    let (Some(transaction), Some(name), Some(key)) = (
+        unsafe { transaction.as_ref() },
+        unsafe { to_str(container) },
+        unsafe { to_bytes(key, key_len) },
+    ) else {
+        return TSF_ERROR_WRONG_PARAMETER;
+    };
+    let snapshot = transaction
+        .transaction
+        .snapshot()
+        .combine(transaction.database.snapshot());
+    let Some(container) = block_on(transaction.database.get_container(name, &snapshot)) else {
+        return TSF_ERROR_NOT_FOUND;
+    };
+    let mut journal = transaction.transaction.journal();
+    let result = block_on(container.delete(key, &mut journal, deadline()));
+    finish(journal, result)
+}
+
+/// Reads the value associated with the key in the container with the transaction.
+///
+/// The value is stored in `value` and `value_len`, and it has to be released by [`tsf_free`].
+/// [`TSF_ERROR_NOT_FOUND`] is returned if the container or the key does not exist.
+///
+/// # Safety
+///
+/// `transaction` must be a handle returned by [`tsf_begin`] that has not been ended, `container`
+/// must be a valid nul-terminated string, `key` must be valid for reads of `key_len` bytes, and
+/// `value` and `value_len` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tsf_get(
+    transaction: *mut TsfTransaction,
+    container: *const c_char,
+    key: *const u8,
+    key_len: usize,
+    value: *mut *mut u8,
+    value_len: *mut usize,
+) -> c_int {
+    // SAFETY: the caller guarantees that the arguments are valid.
+    let (Some(transaction), Some(name), Some(key)) = (
+        unsafe { transaction.as_ref() },
+        unsafe { to_str(container) },
+        unsafe { to_bytes(key, key_len) },
+    ) else {
+        return TSF_ERROR_WRONG_PARAMETER;
+    };
+    if value.is_null() || value_len.is_null() {
+        return TSF_ERROR_WRONG_PARAMETER;
+    }
+    let snapshot = transaction
+        .transaction
+        .snapshot()
+        .combine(transaction.database.snapshot());
+    let Some(container) = block_on(transaction.database.get_container(name, &snapshot)) else {
+        return TSF_ERROR_NOT_FOUND;
+    };
+    let mut journal = transaction.transaction.journal();
+    match block_on(container.read(key, &mut journal, deadline())) {
+        Ok(Some(read)) => {
+            let read = read.into_boxed_slice();
+            // SAFETY: the caller guarantees that `value` and `value_len` are valid for writes.
+            unsafe {
+                value_len.write(read.len());
+                value.write(Box::into_raw(read).cast::<u8>());
+            }
+            finish(journal, Ok(()))
+        }
+        Ok(None) => finish(journal, Err(Error::NotFound)),
+        Err(error) => finish(journal, Err(error)),
+    }
+}
+
+/// Visits key-value pairs in the container with the transaction in ascending key order.
+///
+/// Keys greater than or equal to `start` and less than `end` are visited where a null `start` or
+/// `end` means that the range is unbounded on that side, and `callback` is called with
+/// `context` and each key-value pair until it returns a non-zero value.
+///
+/// # Safety
+///
+/// `transaction` must be a handle returned by [`tsf_begin`] that has not been ended, `container`
+/// must be a valid nul-terminated string, and `start` and `end` must be null or valid for reads
+/// of `start_len` and `end_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn tsf_scan(
+    transaction: *mut TsfTransaction,
+    container: *const c_char,
+    start: *const u8,
+    start_len: usize,
+    end: *const u8,
+    end_len: usize,
+    callback: Option<TsfScanCallback>,
+    context: *mut c_void,
+) -> c_int {
+    // SAFETY: the caller guarantees that the arguments are valid.
+    let (Some(transaction), Some(name), Some(callback)) = (
+        unsafe { transaction.as_ref() },
+        unsafe { to_str(container) },
+        callback,
+    ) else {
+        return TSF_ERROR_WRONG_PARAMETER;
+    };
+    // SAFETY: the caller guarantees that `start` and `end` are null or valid.
+    let (start, end) = unsafe { (to_key(start, start_len), to_key(end, end_len)) };
+    let range = (
+        start.map_or(Bound::Unbounded, Bound::Included),
+        end.map_or(Bound::Unbounded, Bound::Excluded),
+    );
+    let snapshot = transaction
+        .transaction
+        .snapshot()
+        .combine(transaction.database.snapshot());
+    let Some(container) = block_on(transaction.database.get_container(name, &snapshot)) else {
+        return TSF_ERROR_NOT_FOUND;
+    };
+    let mut journal = transaction.transaction.journal();
+    let pairs = match block_on(container.read_range::<&[u8], _>(range, &mut journal, deadline())) {
+        Ok(pairs) => pairs,
+        Err(error) => return finish(journal, Err(error)),
+    };
+    for (key, value) in pairs {
+        // SAFETY: the key and value are valid while the callback is running.
+        let stop = unsafe {
+            callback(
+                context,
+                key.as_ptr(),
+                key.len(),
+                value.as_ptr(),
+                value.len(),
+            )
+        };
+        if stop != 0 {
+            break;
+        }
+    }
+    finish(journal, Ok(()))
+}
+
+/// Releases a value returned by [`tsf_get`].
+///
+/// # Safety
+///
+/// `value` and `value_len` must have been returned by [`tsf_get`], and the value must not have
+/// been released.
+#[no_mangle]
+pub unsafe extern "C" fn tsf_free(value: *mut u8, value_len: usize) {
+    if !value.is_null() {
+        // SAFETY: the caller guarantees that the value was allocated by `tsf_get`.
+        drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(value, value_len)) });
+    }
+}
+
+/// Returns a static nul-terminated string describing the error code.
+#[no_mangle]
+#[must_use]
+pub extern "C" fn tsf_error_message(code: c_int) -> *const c_char {
+    let message = match code {
+        TSF_OK => c"the operation was successful",
+        TSF_ERROR_ALREADY_IN_USE => c"the database files are in use",
+        TSF_ERROR_CANCELLED => c"the operation was cancelled",
+        TSF_ERROR_CONFLICT => c"the operation conflicts with others",
+        TSF_ERROR_CORRUPT_DATABASE => c"the database is corrupt",
+        TSF_ERROR_CORRUPT_PAGE => c"a page is corrupt",
+        TSF_ERROR_DEADLOCK => c"the operation causes a deadlock",
+        TSF_ERROR_DISK_FULL => c"the storage device has run out of space",
+        TSF_ERROR_GENERIC => c"the operation failed",
+        TSF_ERROR_IO => c"IO error",
+        TSF_ERROR_NOT_FOUND => c"the resource could not be found",
+        TSF_ERROR_OUT_OF_MEMORY => c"memory allocation failed",
+        TSF_ERROR_OUT_OF_MEMORY_BUDGET => c"the transaction exceeded its memory budget",
+        TSF_ERROR_OVERLOADED => c"too many requests are waiting for the resource",
+        TSF_ERROR_READ_ONLY => c"the database is read-only",
+        TSF_ERROR_SERIALIZATION_FAILURE => c"the operation failed to be serialized with others",
+        TSF_ERROR_SHUT_DOWN => c"the database is shut down",
+        TSF_ERROR_TIMEOUT => c"the operation was timed out",
+        TSF_ERROR_UNEXPECTED_STATE => c"the database object is in an unexpected state",
+        TSF_ERROR_UNIQUENESS_VIOLATION => c"the key already exists",
+        TSF_ERROR_WRONG_PARAMETER => c"the parameter value is wrong",
+        TSF_ERROR_ABORTED => c"the transaction was aborted",
+        TSF_ERROR_VERSION_CHAIN_TOO_LONG => c"the key-value pair has too many unreclaimed versions",
+        _ => c"unknown error code",
+    };
+    message.as_ptr()
+}
+
+/// [`ThreadWaker`] unparks the thread blocked in [`block_on`].
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    #[inline]
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Blocks the current thread until the future completes.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+        thread::park();
+    }
+}
+
+/// Returns the deadline of a request.
+fn deadline() -> Option<Instant> {
+    Instant::now().checked_add(LOCK_WAIT)
+}
+
+/// Submits the journal if successful, and returns the error code.
+fn finish(
+    journal: Journal<'_, '_, MonotonicU64, FileIO<MonotonicU64>>,
+    result: Result<(), Error>,
+) -> c_int {
+    match result {
+        Ok(()) => {
+            let _: NonZeroU32 = journal.submit();
+            TSF_OK
+        }
+        Err(error) => error_code(&error),
+    }
+}
+
+/// Converts an [`Error`] into an error code.
+fn error_code(error: &Error) -> c_int {
+    match error {
+        Error::Aborted => TSF_ERROR_ABORTED,
+        Error::AlreadyInUse => TSF_ERROR_ALREADY_IN_USE,
+        Error::Cancelled => TSF_ERROR_CANCELLED,
+        Error::Conflict(_) => TSF_ERROR_CONFLICT,
+        Error::CorruptDatabase | Error::CorruptLog(_) => TSF_ERROR_CORRUPT_DATABASE,
+        Error::CorruptPage(_) => TSF_ERROR_CORRUPT_PAGE,
+        Error::Deadlock | Error::DeadlockDetected(_) => TSF_ERROR_DEADLOCK,
+        Error::DiskFull => TSF_ERROR_DISK_FULL,
+        Error::Generic(_) => TSF_ERROR_GENERIC,
+        Error::IO(_) | Error::IOAt(..) => TSF_ERROR_IO,
+        Error::NotFound => TSF_ERROR_NOT_FOUND,
+        Error::OutOfMemory => TSF_ERROR_OUT_OF_MEMORY,
+        Error::OutOfMemoryBudget => TSF_ERROR_OUT_OF_MEMORY_BUDGET,
+        Error::Overloaded => TSF_ERROR_OVERLOADED,
+        Error::ReadOnly => TSF_ERROR_READ_ONLY,
+        Error::SerializationFailure => TSF_ERROR_SERIALIZATION_FAILURE,
+        Error::ShutDown => TSF_ERROR_SHUT_DOWN,
+        Error::Timeout => TSF_ERROR_TIMEOUT,
+        Error::UnexpectedState => TSF_ERROR_UNEXPECTED_STATE,
+        Error::UniquenessViolation => TSF_ERROR_UNIQUENESS_VIOLATION,
+        Error::VersionChainTooLong => TSF_ERROR_VERSION_CHAIN_TOO_LONG,
+        Error::WrongParameter => TSF_ERROR_WRONG_PARAMETER,
+    }
+}
+
+/// Converts a nul-terminated string into a [`str`].
+///
+/// # Safety
+///
+/// `string` must be null or a valid nul-terminated string.
+unsafe fn to_str<'a>(string: *const c_char) -> Option<&'a str> {
+    if string.is_null() {
+        return None;
+    }
+    // SAFETY: the caller guarantees that `string` is a valid nul-terminated string.
+    unsafe { CStr::from_ptr(string) }.to_str().ok()
+}
+
+/// Converts a pointer and a length into a slice.
+///
+/// # Safety
+///
+/// `bytes` must be null or valid for reads of `len` bytes.
+unsafe fn to_bytes<'a>(bytes: *const u8, len: usize) -> Option<&'a [u8]> {
+    if bytes.is_null() {
+        return (len == 0).then_some(&[]);
+    }
+    // SAFETY: the caller guarantees that `bytes` is valid for reads of `len` bytes.
+    Some(unsafe { slice::from_raw_parts(bytes, len) })
+}
+
+/// Converts a pointer and a length into a key of a range bound where null means unbounded.
+///
+/// # Safety
+///
+/// `bytes` must be null or valid for reads of `len` bytes.
+unsafe fn to_key<'a>(bytes: *const u8, len: usize) -> Option<&'a [u8]> {
+    if bytes.is_null() {
+        return None;
+    }
+    // SAFETY: the caller guarantees that `bytes` is valid for reads of `len` bytes.
+    Some(unsafe { slice::from_raw_parts(bytes, len) })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs::remove_dir_all;
+
+    #[test]
+    fn header() {
+        let header = include_str!("../include/sap_tsf.h");
+        for (name, code) in [
+            ("TSF_OK", TSF_OK),
+            ("TSF_ERROR_ALREADY_IN_USE", TSF_ERROR_ALREADY_IN_USE),
+            ("TSF_ERROR_CANCELLED", TSF_ERROR_CANCELLED),
+            ("TSF_ERROR_CONFLICT", TSF_ERROR_CONFLICT),
+            ("TSF_ERROR_CORRUPT_DATABASE", TSF_ERROR_CORRUPT_DATABASE),
+            ("TSF_ERROR_CORRUPT_PAGE", TSF_ERROR_CORRUPT_PAGE),
+            ("TSF_ERROR_DEADLOCK", TSF_ERROR_DEADLOCK),
+            ("TSF_ERROR_DISK_FULL", TSF_ERROR_DISK_FULL),
+            ("TSF_ERROR_GENERIC", TSF_ERROR_GENERIC),
+            ("TSF_ERROR_IO", TSF_ERROR_IO),
+            ("TSF_ERROR_NOT_FOUND", TSF_ERROR_NOT_FOUND),
+            ("TSF_ERROR_OUT_OF_MEMORY", TSF_ERROR_OUT_OF_MEMORY),
+            (
+                "TSF_ERROR_OUT_OF_MEMORY_BUDGET",
+                TSF_ERROR_OUT_OF_MEMORY_BUDGET,
+            ),
+            ("TSF_ERROR_OVERLOADED", TSF_ERROR_OVERLOADED),
+            ("TSF_ERROR_READ_ONLY", TSF_ERROR_READ_ONLY),
+            (
+                "TSF_ERROR_SERIALIZATION_FAILURE",
+                TSF_ERROR_SERIALIZATION_FAILURE,
+            ),
+            ("TSF_ERROR_SHUT_DOWN", TSF_ERROR_SHUT_DOWN),
+            ("TSF_ERROR_TIMEOUT", TSF_ERROR_TIMEOUT),
+            ("TSF_ERROR_UNEXPECTED_STATE", TSF_ERROR_UNEXPECTED_STATE),
+            (
+                "TSF_ERROR_UNIQUENESS_VIOLATION",
+                TSF_ERROR_UNIQUENESS_VIOLATION,
+            ),
+            ("TSF_ERROR_WRONG_PARAMETER", TSF_ERROR_WRONG_PARAMETER),
+            ("TSF_ERROR_ABORTED", TSF_ERROR_ABORTED),
+            (
+                "TSF_ERROR_VERSION_CHAIN_TOO_LONG",
+                TSF_ERROR_VERSION_CHAIN_TOO_LONG,
+            ),
+        ] {
+            assert!(
+                header.contains(&format!("#define {name} {code}\n")),
+                "{name}"
+            );
+            // SAFETY: `tsf_error_message` returns a static nul-terminated string.
+            let message = unsafe { CStr::from_ptr(tsf_error_message(code)) };
+            assert_ne!(message, c"unknown error code", "{name}");
+        }
+        for function in [
+            "tsf_open(",
+            "tsf_close(",
+            "tsf_begin(",
+            "tsf_commit(",
+            "tsf_rollback(",
+            "tsf_create_container(",
+            "tsf_put(",
+            "tsf_delete(",
+            "tsf_get(",
+            "tsf_scan(",
+            "tsf_free(",
+            "tsf_error_message(",
+        ] {
+            assert!(header.contains(function), "{function}");
+        }
+    }
+
+    unsafe extern "C" fn collect(
+        context: *mut c_void,
+        key: *const u8,
+        key_len: usize,
+        value: *const u8,
+        value_len: usize,
+    ) -> c_int {
+        // SAFETY: `context` points to the `Vec` passed to `tsf_scan`, and the key and value are
+        // valid while the callback is running.
+        unsafe {
+            let pairs = &mut *context.cast::<Vec<(Vec<u8>, Vec<u8>)>>();
+            pairs.push((
+                slice::from_raw_parts(key, key_len).to_vec(),
+                slice::from_raw_parts(value, value_len).to_vec(),
+            ));
+            c_int::from(pairs.len() == 2)
+        }
+    }
+
+    #[test]
+    #[allow(clippy::too_many_lines)]
+    fn basic() {
+        const DIR: &str = "capi_basic_test";
+        let mut database = ptr::null_mut();
+        let mut transaction = ptr::null_mut();
+        let container = c"kv".as_ptr();
+        // SAFETY: the arguments are valid, and the handles are released in order.
+        unsafe {
+            assert_eq!(
+                tsf_open(ptr::null(), &raw mut database),
+                TSF_ERROR_WRONG_PARAMETER
+            );
+            assert_eq!(
+                tsf_open(c"capi_basic_test".as_ptr(), &raw mut database),
+                TSF_OK
+            );
+            assert_eq!(tsf_begin(database, &raw mut transaction), TSF_OK);
+            assert_eq!(
+                tsf_put(transaction, container, b"1".as_ptr(), 1, b"one".as_ptr(), 3),
+                TSF_ERROR_NOT_FOUND
+            );
+            assert_eq!(tsf_create_container(transaction, container), TSF_OK);
+            for (key, value) in [(b"1", b"one"), (b"2", b"two"), (b"3", b"thr")] {
+                assert_eq!(
+                    tsf_put(transaction, container, key.as_ptr(), 1, value.as_ptr(), 3),
+                    TSF_OK
+                );
+            }
+            assert_eq!(
+                tsf_put(
+                    transaction,
+                    container,
+                    b"3".as_ptr(),
+                    1,
+                    b"three".as_ptr(),
+                    5
+                ),
+                TSF_OK
+            );
+            let mut instant = 0;
+            assert_eq!(tsf_commit(transaction, &raw mut instant), TSF_OK);
+            assert_ne!(instant, 0);
+
+            assert_eq!(tsf_begin(database, &raw mut transaction), TSF_OK);
+            let mut value = ptr::null_mut();
+            let mut value_len = 0;
+            assert_eq!(
+                tsf_get(
+                    transaction,
+                    container,
+                    b"3".as_ptr(),
+                    1,
+                    &raw mut value,
+                    &raw mut value_len
+                ),
+                TSF_OK
+            );
+            assert_eq!(slice::from_raw_parts(value, value_len), b"three");
+            tsf_free(value, value_len);
+
+            let mut pairs: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+            assert_eq!(
+                tsf_scan(
+                    transaction,
+                    container,
+                    b"2".as_ptr(),
+                    1,
+                    ptr::null(),
+                    0,
+                    Some(collect),
+                    (&raw mut pairs).cast()
+                ),
+                TSF_OK
+            );
+            assert_eq!(
+                pairs,
+                vec![
+                    (b"2".to_vec(), b"two".to_vec()),
+                    (b"3".to_vec(), b"three".to_vec())
+                ]
+            );
+            pairs.clear();
+            assert_eq!(
+                tsf_scan(
+                    transaction,
+                    container,
+                    ptr::null(),
+                    0,
+                    b"2".as_ptr(),
+                    1,
+                    Some(collect),
+                    (&raw mut pairs).cast()
+                ),
+                TSF_OK
+            );
+            assert_eq!(pairs, vec![(b"1".to_vec(), b"one".to_vec())]);
+
+            assert_eq!(tsf_delete(transaction, container, b"1".as_ptr(), 1), TSF_OK);
+            assert_eq!(
+                tsf_get(
+                    transaction,
+                    container,
+                    b"1".as_ptr(),
+                    1,
+                    &raw mut value,
+                    &raw mut value_len
+                ),
+                TSF_ERROR_NOT_FOUND
+            );
+            assert_eq!(tsf_rollback(transaction), TSF_OK);
+            assert_eq!(tsf_close(database), TSF_OK);
+        }
+        assert!(remove_dir_all(DIR).is_ok());
+    }
+}
@@ -0,0 +1,647 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! [`Catalog`] maps names to [`Container`] instances.
+
+use super::utils::Instant;
+use super::{AccessController, Container, ContainerStatistics, Error, Journal, LockMode};
+use super::{Database, Metadata, PersistenceLayer, Sequencer, Snapshot, VersionRecord};
+use scc::{ebr, HashIndex};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// The identifier of the [`Container`] storing catalog entries.
+pub(super) const CATALOG_ID: u64 = 0;
+
+/// [`Catalog`] maps names to [`Container`] instances.
+///
+/// Catalog entries are key-value pairs in a system [`Container`] mapping the name of each
+/// [`Container`] to its [`CatalogEntry`], therefore creating, dropping, and renaming a
+/// [`Container`] are versioned changes made with a [`Journal`]: they are visible to other
+/// transactions only after the transaction is committed, and concurrent changes to the same name
+/// are serialized by the record lock on the name.
+///
+/// The persistence layer may persist the catalog entries visible to a [`Snapshot`] through
+/// [`Catalog::scan_visible_versions`], and play them back before replaying the log.
+#[derive(Debug)]
+pub(super) struct Catalog<S: Sequencer, P: PersistenceLayer<S>> {
+    /// The [`Container`] storing catalog entries.
+    entries: ebr::Shared<Container<S, P>>,
+
+    /// [`Container`] instances indexed by identifiers, including the catalog itself.
+    ///
+    /// Dropped [`Container`] instances are retained since old snapshots may still see them.
+    containers: HashIndex<u64, ebr::Shared<Container<S, P>>>,
+
+    /// The access controller of the database.
+    access_controller: Arc<AccessController<S>>,
+}
+
+/// [`CatalogEntry`] describes a [`Container`] in the [`Catalog`].
+///
+/// The encoded form is `CONTAINER ID 64-bit|METADATA 8-bit`.
+///
+/// The root pages of the [`Container`] are not part of the entry: they change whenever a
+/// checkpoint persists the [`Container`], and the checkpoint records them along with the
+/// [`Container`] identifier. The creation clock is neither, since the entry is written before the
+/// transaction creating the [`Container`] is committed; the entry becomes visible at the commit
+/// clock of the transaction, which snapshots are already checked against.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+struct CatalogEntry {
+    /// The identifier of the [`Container`].
+    container_id: u64,
+
+    /// The [`Metadata`] of the [`Container`].
+    metadata: Metadata,
+}
+
+/// The length of an encoded [`CatalogEntry`].
+const ENTRY_LEN: usize = 9;
+
+impl<S: Sequencer, P: PersistenceLayer<S>> Catalog<S, P> {
+    /// Creates a new empty [`Catalog`].
+    pub(super) fn new(access_controller: Arc<AccessController<S>>) -> Catalog<S, P> {
+        let entries = ebr::Shared::new(Container::new(
+            CATALOG_ID,
+            "".into(),
+            Metadata::default(),
+            access_controller.clone(),
+        ));
+        let containers = HashIndex::default();
+        let _: Result<(), _> = containers.insert(CATALOG_ID, entries.clone());
+        Catalog {
+            entries,
+            containers,
+            access_controller,
+        }
+    }
+
+    /// Creates a new [`Container`] under the name with the [`Journal`].
+    pub(super) async fn create(
+        &self,
+        name: String,
+        metadata: Metadata,
+        journal: &mut Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<ebr::Shared<Container<S, P>>, Error> {
+        let entry = CatalogEntry {
+            container_id: journal.database().new_object_id(),
+            metadata,
+        };
+        self.entries
+            .insert(name.as_bytes(), &entry.encode(), journal, deadline)
+            .await?;
+        let container = ebr::Shared::new(Container::new(
+            entry.container_id,
+            name.into(),
+            metadata,
+            self.access_controller.clone(),
+        ));
+        let _: Result<(), _> = self
+            .containers
+            .insert_async(entry.container_id, container.clone())
+            .await;
+        Ok(container)
+    }
+
+    /// Renames the [`Container`] with the [`Journal`].
+    pub(super) async fn rename(
+        &self,
+        name: &str,
+        new_name: &str,
+        journal: &mut Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        let Some(entry) = self
+            .entries
+            .read(name.as_bytes(), journal, deadline)
+            .await?
+        else {
+            return Err(Error::NotFound);
+        };
+
+        // The new entry is inserted first so that nothing is changed if the name is taken.
+        self.entries
+            .insert(new_name.as_bytes(), &entry, journal, deadline)
+            .await?;
+        self.entries
+            .delete(name.as_bytes(), journal, deadline)
+            .await?;
+        if let Some(entry) = CatalogEntry::decode(&entry) {
+            journal.record_rename(entry.container_id, new_name.into());
+        }
+        Ok(())
+    }
+
+    /// Drops the [`Container`] with the [`Journal`].
+    ///
+    /// The [`Container`] is locked in [`LockMode::Exclusive`] mode before being dropped.
+    pub(super) async fn drop(
+        &self,
+        name: &str,
+        journal: &mut Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        let Some(container) = self.read(name, journal, deadline).await? else {
+            return Err(Error::NotFound);
+        };
+        container
+            .lock(LockMode::Exclusive, journal, deadline)
+            .await?;
+        self.entries
+            .delete(name.as_bytes(), journal, deadline)
+            .await
+    }
+
+    /// Returns the [`Container`] under the name that is visible to the [`Journal`].
+    pub(super) async fn read(
+        &self,
+        name: &str,
+        journal: &mut Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<Option<ebr::Shared<Container<S, P>>>, Error> {
+        let entry = self
+            .entries
+            .read(name.as_bytes(), journal, deadline)
+            .await?;
+        Ok(entry
+            .and_then(|e| CatalogEntry::decode(&e))
+            .and_then(|e| self.containers.peek_with(&e.container_id, |_, c| c.clone())))
+    }
+
+    /// Returns a reference to the [`Container`] under the name that is visible to the
+    /// [`Snapshot`].
+    pub(super) async fn get<'r>(
+        &self,
+        name: &str,
+        snapshot: &'r Snapshot<'_, '_, '_, S>,
+    ) -> Option<&'r Container<S, P>> {
+        let entry = self
+            .entries
+            .get(name.as_bytes(), snapshot, None)
+            .await
+            .ok()??;
+        let entry = CatalogEntry::decode(&entry)?;
+        self.containers.peek_with(&entry.container_id, |_, c|
+            // Safety: `snapshot` is the proof that the returned reference stays valid at least for
+            // the lifetime of `snapshot`; containers are never removed from `containers` while the
+            // database is alive.
+            unsafe { std::mem::transmute::<&Container<S, P>, &'r Container<S, P>>(&**c) })
+    }
+
+    /// Returns the [`ContainerStatistics`] of the [`Container`] instances visible to the
+    /// [`Snapshot`] indexed by their names.
+    pub(super) async fn statistics(
+        &self,
+        snapshot: &Snapshot<'_, '_, '_, S>,
+        deadline: Option<Instant>,
+    ) -> Result<BTreeMap<String, ContainerStatistics>, Error> {
+        let mut statistics = BTreeMap::new();
+        let mut scanner = self.entries.range::<&[u8], _>(.., snapshot, deadline);
+        while let Some((name, entry)) = scanner.next().await? {
+            let Some(entry) = CatalogEntry::decode(&entry) else {
+                continue;
+            };
+            if let Some(s) = self
+                .containers
+                .peek_with(&entry.container_id, |_, c| c.statistics())
+            {
+                statistics.insert(String::from_utf8_lossy(&name).into_owned(), s);
+            }
+        }
+        Ok(statistics)
+    }
+
+    /// Passes the catalog entries visible to the [`Snapshot`] to `visitor` in ascending name
+    /// order as versions of the catalog [`Container`], so that they can be persisted and played
+    /// back by [`playback_version`](Self::playback_version).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) async fn scan_visible_versions<F: FnMut(&VersionRecord<'_>)>(
+        &self,
+        snapshot: &Snapshot<'_, '_, '_, S>,
+        deadline: Option<Instant>,
+        visitor: F,
+    ) -> Result<(), Error> {
+        self.entries
+            .scan_visible_versions(snapshot, deadline, visitor)
+            .await
+    }
+
+    /// Returns the identifiers and [`Metadata`] of the containers visible to the [`Snapshot`] in
+    /// ascending name order.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) async fn visible_containers(
+        &self,
+        snapshot: &Snapshot<'_, '_, '_, S>,
+        deadline: Option<Instant>,
+    ) -> Result<Vec<(u64, Metadata)>, Error> {
+        let mut containers = Vec::new();
+        let mut scanner = self.entries.range::<&[u8], _>(.., snapshot, deadline);
+        while let Some((_, entry)) = scanner.next().await? {
+            if let Some(entry) = CatalogEntry::decode(&entry) {
+                containers.push((entry.container_id, entry.metadata));
+            }
+        }
+        Ok(containers)
+    }
+
+    /// Passes the versions of the [`Container`] visible to the [`Snapshot`] to `visitor` in
+    /// ascending key order.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) async fn scan_container_versions<F: FnMut(&VersionRecord<'_>)>(
+        &self,
+        container_id: u64,
+        snapshot: &Snapshot<'_, '_, '_, S>,
+        deadline: Option<Instant>,
+        visitor: F,
+    ) -> Result<(), Error> {
+        let Some(container) = self.containers.peek_with(&container_id, |_, c| c.clone()) else {
+            return Ok(());
+        };
+        container
+            .scan_visible_versions(snapshot, deadline, visitor)
+            .await
+    }
+
+    /// Installs a version written to the log while the database is being recovered.
+    ///
+    /// A [`Container`] is created for each [`CatalogEntry`] played back, so that versions of the
+    /// [`Container`] that follow in the log can be installed. Returns the identifier and the new
+    /// name of the [`Container`] if the [`CatalogEntry`] of an existing [`Container`] is played
+    /// back under another name, so that the [`Container`] is renamed once the transaction is
+    /// committed.
+    pub(super) fn playback_version(
+        &self,
+        version: &VersionRecord<'_>,
+        database: &Database<S, P>,
+    ) -> Option<(u64, Arc<str>)> {
+        let container = self
+            .containers
+            .peek_with(&version.container_id(), |_, c| c.clone())?;
+        container.playback_version(version, database);
+        if version.container_id() != CATALOG_ID {
+            return None;
+        }
+        let entry = CatalogEntry::decode(version.value())?;
+        database.reserve_object_id(entry.container_id);
+        let name: Arc<str> = String::from_utf8_lossy(version.key()).into();
+        if self.containers.contains(&entry.container_id) {
+            return Some((entry.container_id, name));
+        }
+        let container = ebr::Shared::new(Container::new(
+            entry.container_id,
+            name,
+            entry.metadata,
+            self.access_controller.clone(),
+        ));
+        let _: Result<(), _> = self.containers.insert(entry.container_id, container);
+        None
+    }
+
+    /// Installs a version of a [`Container`] persisted by a checkpoint while the database is being
+    /// recovered.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) fn playback_persisted_version(
+        &self,
+        version: &VersionRecord<'_>,
+        database: &Database<S, P>,
+    ) {
+        if let Some(container) = self
+            .containers
+            .peek_with(&version.container_id(), |_, c| c.clone())
+        {
+            container.playback_persisted_version(version, database);
+        }
+    }
+
+    /// Passes the index of a [`Container`] persisted by a checkpoint to the [`Container`] while
+    /// the database is being recovered.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) fn playback_persisted_index(&self, container_id: u64, index: P::PersistedIndex) {
+        if let Some(container) = self.containers.peek_with(&container_id, |_, c| c.clone()) {
+            container.playback_persisted_index(index);
+        }
+    }
+
+    /// Quarantines a [`Container`] of which the persisted index is corrupt while the database is
+    /// being recovered.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) fn playback_quarantine(&self, container_id: u64, error: Error) {
+        if let Some(container) = self.containers.peek_with(&container_id, |_, c| c.clone()) {
+            container.playback_quarantine(error);
+        }
+    }
+
+    /// Returns the [`Container`] identified as the identifier.
+    pub(super) fn container<'b>(
+        &self,
+        container_id: u64,
+        barrier: &'b ebr::Guard,
+    ) -> Option<&'b Container<S, P>> {
+        self.containers
+            .peek(&container_id, barrier)
+            .map(std::convert::AsRef::as_ref)
+    }
+
+    /// Returns a shared reference to the [`Container`] identified as the identifier.
+    pub(super) fn shared_container(
+        &self,
+        container_id: u64,
+    ) -> Option<ebr::Shared<Container<S, P>>> {
+        self.containers.peek_with(&container_id, |_, c| c.clone())
+    }
+}
+
+impl CatalogEntry {
+    /// Encodes the [`CatalogEntry`].
+    fn encode(self) -> [u8; ENTRY_LEN] {
+        let mut bytes = [0; ENTRY_LEN];
+        bytes[..8].copy_from_slice(&self.container_id.to_le_bytes());
+        bytes[8] = self.metadata.encode();
+        bytes
+    }
+
+    /// Decodes a [`CatalogEntry`] encoded by [`CatalogEntry::encode`].
+    fn decode(bytes: &[u8]) -> Option<CatalogEntry> {
+        let bytes: &[u8; ENTRY_LEN] = bytes.try_into().ok()?;
+        let container_id = u64::from_le_bytes(bytes[..8].try_into().ok()?);
+        let metadata = Metadata::decode(bytes[8])?;
+        Some(CatalogEntry {
+            container_id,
+            metadata,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Database, IndexType};
+    use std::path::Path;
+    use std::time::Duration;
+    use tokio::fs::remove_dir_all;
+
+    #[test]
+    fn catalog_entry() {
+        let entry = CatalogEntry {
+            container_id: 1 << 63,
+            metadata: Metadata::default().with_index_type(IndexType::Hash),
+        };
+        assert_eq!(CatalogEntry::decode(&entry.encode()), Some(entry));
+        assert!(CatalogEntry::decode(&entry.encode()[..8]).is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let entry = CatalogEntry {
+            container_id: 11,
+            metadata: Metadata::default().with_index_type(IndexType::Hash),
+        };
+        let mut serialized = Vec::new();
+        assert!(ciborium::into_writer(&entry, &mut serialized).is_ok());
+        let deserialized: CatalogEntry = ciborium::from_reader(serialized.as_slice()).unwrap();
+        assert_eq!(deserialized, entry);
+    }
+
+    #[tokio::test]
+    async fn transactional() {
+        const DIR: &str = "catalog_transactional_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+
+        // The container is invisible to others until the transaction is committed.
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("a".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert!(container
+            .insert(b"1", b"1", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(database
+            .get_container("a", &database.snapshot())
+            .await
+            .is_none());
+        assert!(database
+            .get_container("a", &transaction.snapshot())
+            .await
+            .is_some());
+        let other_transaction = database.transaction();
+        let mut other_journal = other_transaction.journal();
+        assert_eq!(
+            database
+                .create_container(
+                    "a".to_string(),
+                    Metadata::default(),
+                    &mut other_journal,
+                    None
+                )
+                .await
+                .map(|_| ()),
+            Err(Error::SerializationFailure)
+        );
+        assert!(transaction.commit().await.is_ok());
+        let deadline = Instant::now() + Duration::from_secs(10);
+        assert_eq!(
+            database
+                .create_container(
+                    "a".to_string(),
+                    Metadata::default(),
+                    &mut other_journal,
+                    Some(deadline)
+                )
+                .await
+                .map(|_| ()),
+            Err(Error::UniquenessViolation)
+        );
+        drop(other_journal);
+        drop(other_transaction);
+
+        // Rolled back changes are discarded.
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(database
+            .create_container("b".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        transaction.rollback();
+        assert!(database
+            .get_container("b", &database.snapshot())
+            .await
+            .is_none());
+
+        drop(container);
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn rename_and_drop() {
+        const DIR: &str = "catalog_rename_and_drop_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("a".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert!(container
+            .insert(b"1", b"1", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        // Renaming and dropping containers are invisible to old snapshots.
+        let old_snapshot = database.snapshot();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(database
+            .rename_container("a", "c".to_string(), &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(
+            database
+                .rename_container("a", "d".to_string(), &mut journal, None)
+                .await,
+            Err(Error::NotFound)
+        );
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        let snapshot = database.snapshot();
+        let renamed = database.get_container("c", &snapshot).await.unwrap();
+        assert_eq!(
+            renamed.get(b"1", &snapshot, None).await,
+            Ok(Some(b"1".to_vec()))
+        );
+        assert!(database.get_container("a", &snapshot).await.is_none());
+        assert!(database.get_container("a", &old_snapshot).await.is_some());
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(database
+            .drop_container("c", &snapshot, &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        assert!(database.get_container("c", &snapshot).await.is_some());
+        assert!(database
+            .get_container("c", &database.snapshot())
+            .await
+            .is_none());
+        drop(snapshot);
+        drop(old_snapshot);
+
+        drop(container);
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn reopen() {
+        const DIR: &str = "catalog_reopen_test";
+        const NUM_CONTAINERS: usize = 64;
+        let path = Path::new(DIR);
+        let truncate_log = || {
+            std::fs::OpenOptions::new()
+                .write(true)
+                .open(path.join("l.log"))
+                .unwrap()
+                .set_len(0)
+                .unwrap();
+        };
+        let metadata = Metadata::default().with_index_type(IndexType::Hash);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        for i in 0..NUM_CONTAINERS {
+            assert!(database
+                .create_container(format!("container_{i}"), metadata, &mut journal, None)
+                .await
+                .is_ok());
+        }
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        assert!(database.checkpoint(None).await.is_ok());
+        drop(database);
+
+        // The catalog is loaded from the checkpoint without the log.
+        truncate_log();
+        let database = Database::with_path(path).await.unwrap();
+        let snapshot = database.snapshot();
+        for i in 0..NUM_CONTAINERS {
+            let container = database
+                .get_container(&format!("container_{i}"), &snapshot)
+                .await
+                .unwrap();
+            assert_eq!(container.metadata(), &metadata);
+        }
+        drop(snapshot);
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(database
+            .rename_container("container_0", "renamed".to_string(), &mut journal, None)
+            .await
+            .is_ok());
+        assert!(database
+            .drop_container("container_1", &database.snapshot(), &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        assert!(database.checkpoint(None).await.is_ok());
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(database
+            .create_container("created".to_string(), metadata, &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        drop(database);
+
+        // Log records following the checkpoint are replayed over it.
+        let database = Database::with_path(path).await.unwrap();
+        let snapshot = database.snapshot();
+        assert!(database.get_container("renamed", &snapshot).await.is_some());
+        assert!(database.get_container("created", &snapshot).await.is_some());
+        assert!(database
+            .get_container("container_0", &snapshot)
+            .await
+            .is_none());
+        assert!(database
+            .get_container("container_1", &snapshot)
+            .await
+            .is_none());
+        drop(snapshot);
+        drop(database);
+
+        truncate_log();
+        let database = Database::with_path(path).await.unwrap();
+        let snapshot = database.snapshot();
+        assert!(database.get_container("renamed", &snapshot).await.is_some());
+        assert!(database.get_container("created", &snapshot).await.is_none());
+        assert!(database
+            .get_container("container_0", &snapshot)
+            .await
+            .is_none());
+        assert!(database
+            .get_container("container_2", &snapshot)
+            .await
+            .is_some());
+        drop(snapshot);
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+}
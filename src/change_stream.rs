@@ -0,0 +1,428 @@
+// SPDX-FileCopyrightText: 2021 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The module defines the change data capture interface of the database.
+
+use super::sequencer::ToInstant;
+use super::task_processor::Task;
+use super::utils::Instant;
+use super::{Database, Error, PersistenceLayer, Sequencer};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::future::poll_fn;
+use std::mem::take;
+use std::sync::atomic::Ordering::Acquire;
+use std::sync::{Arc, Mutex};
+use std::task::{Poll, Waker};
+
+/// [`Change`] describes a modification to a key-value pair in a [`Container`](super::Container).
+///
+/// A [`Change`] with an empty key and neither an old nor a new value describes a truncation of
+/// the whole [`Container`](super::Container) by
+/// [`Container::truncate`](super::Container::truncate).
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Change {
+    /// The name of the [`Container`](super::Container) as of the latest committed rename.
+    pub container: Arc<str>,
+
+    /// The key.
+    pub key: Box<[u8]>,
+
+    /// The value before the change.
+    ///
+    /// `None` if the key-value pair was inserted.
+    pub old_value: Option<Box<[u8]>>,
+
+    /// The value after the change.
+    ///
+    /// `None` if the key-value pair was deleted.
+    pub new_value: Option<Box<[u8]>>,
+}
+
+/// [`ChangeBatch`] is the set of changes committed by a [`Transaction`](super::Transaction).
+///
+/// Enabling the `serde` feature makes [`ChangeBatch`] serializable if the
+/// [`Instant`](Sequencer::Instant) of the [`Sequencer`] is serializable, e.g., `u64` of
+/// [`MonotonicU64`](super::MonotonicU64), so that change batches can be shipped to other
+/// processes.
+#[derive(Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Deserialize, Serialize),
+    serde(bound(
+        serialize = "S::Instant: Serialize",
+        deserialize = "S::Instant: Deserialize<'de>"
+    ))
+)]
+pub struct ChangeBatch<S: Sequencer> {
+    /// The commit instant of the [`Transaction`](super::Transaction).
+    pub commit_instant: S::Instant,
+
+    /// The changes in the order of submission of the [`Journal`](super::Journal) instances.
+    pub changes: Vec<Change>,
+}
+
+/// [`ChangeStream`] receives [`ChangeBatch`] instances in commit order.
+///
+/// A [`ChangeStream`] prevents committed changes that it has yet to receive from being discarded,
+/// therefore a [`ChangeStream`] that is not consumed keeps database resources from being garbage
+/// collected.
+///
+/// Changes made before the [`Database`] was recovered are not captured.
+#[derive(Debug)]
+pub struct ChangeStream<'d, S: Sequencer, P: PersistenceLayer<S>> {
+    /// The [`Database`] that the [`ChangeStream`] watches.
+    database: &'d Database<S, P>,
+
+    /// The commit instant of the last received [`ChangeBatch`].
+    position: S::Instant,
+
+    /// Pins `position` until a newer [`ChangeBatch`] is received.
+    tracker: S::Tracker<'d>,
+}
+
+/// [`ChangeLog`] retains [`ChangeBatch`] instances until no [`ChangeStream`] instances need them.
+#[derive(Debug, Default)]
+pub(super) struct ChangeLog<S: Sequencer> {
+    /// The state of the [`ChangeLog`].
+    state: Mutex<State<S>>,
+}
+
+/// The state of [`ChangeLog`].
+#[derive(Debug, Default)]
+struct State<S: Sequencer> {
+    /// [`ChangeBatch`] slots ordered by commit instant.
+    batches: VecDeque<Slot<S>>,
+
+    /// [`Waker`] instances of [`ChangeStream`] instances waiting for a new [`ChangeBatch`].
+    wakers: Vec<Waker>,
+
+    /// The number of published [`ChangeBatch`] instances.
+    num_published: u64,
+}
+
+/// A commit instant and [`ChangeBatch`] pair.
+///
+/// The [`ChangeBatch`] is `None` if the transaction is being committed.
+type Slot<S> = (<S as Sequencer>::Instant, Option<Arc<ChangeBatch<S>>>);
+
+impl<S: Sequencer> Clone for ChangeBatch<S> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            commit_instant: self.commit_instant,
+            changes: self.changes.clone(),
+        }
+    }
+}
+
+impl<'d, S: Sequencer, P: PersistenceLayer<S>> ChangeStream<'d, S, P> {
+    /// Receives the next [`ChangeBatch`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Timeout`] if no [`ChangeBatch`] was committed until the deadline.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("change_stream_next")).await.unwrap();
+    ///     let mut change_stream = database.watch(None).unwrap();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let container = database
+    ///         .create_container("hello".to_string(), Metadata::default(), &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     assert!(container.insert(b"1", b"one", &mut journal, None).await.is_ok());
+    ///     journal.submit();
+    ///     let commit_instant = transaction.commit().await.unwrap();
+    ///
+    ///     let change_batch = change_stream.next(None).await.unwrap();
+    ///     assert_eq!(change_batch.commit_instant, commit_instant);
+    ///     assert_eq!(change_batch.changes[0].key.as_ref(), b"1");
+    /// };
+    /// ```
+    #[inline]
+    pub async fn next(&mut self, deadline: Option<Instant>) -> Result<Arc<ChangeBatch<S>>, Error> {
+        poll_fn(|cx| {
+            if let Some(change_batch) = self
+                .database
+                .change_log()
+                .next_batch(self.position, cx.waker())
+            {
+                self.position = change_batch.commit_instant;
+                if let Some(tracker) = self.database.sequencer().track_at(self.position, Acquire) {
+                    self.tracker = tracker;
+                }
+                return Poll::Ready(Ok(change_batch));
+            }
+            if let Some(deadline) = deadline {
+                if deadline < Instant::now() {
+                    return Poll::Ready(Err(Error::Timeout));
+                } else if !self
+                    .database
+                    .task_processor()
+                    .send_task(Task::WakeUp(deadline, cx.waker().clone()))
+                {
+                    // The message channel is congested.
+                    cx.waker().wake_by_ref();
+                }
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Creates a new [`ChangeStream`] that receives changes committed after the tracked
+    /// instant.
+    pub(super) fn new(
+        database: &'d Database<S, P>,
+        tracker: S::Tracker<'d>,
+    ) -> ChangeStream<'d, S, P> {
+        ChangeStream {
+            database,
+            position: tracker.to_instant(),
+            tracker,
+        }
+    }
+}
+
+impl<S: Sequencer> ChangeLog<S> {
+    /// Reserves a slot for the [`ChangeBatch`] of a transaction being committed.
+    ///
+    /// `advance` generates the commit instant of the transaction while the [`ChangeLog`] is
+    /// locked, so that slots are ordered by commit instant.
+    pub(super) fn reserve<F: FnOnce() -> S::Instant>(&self, advance: F) -> S::Instant {
+        let Ok(mut state) = self.state.lock() else {
+            return advance();
+        };
+        let commit_instant = advance();
+        state.batches.push_back((commit_instant, None));
+        commit_instant
+    }
+
+    /// Publishes the changes of a committed transaction.
+    pub(super) fn publish(&self, commit_instant: S::Instant, changes: Vec<Change>) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if let Some(slot) = state
+            .batches
+            .iter_mut()
+            .rev()
+            .find(|(i, _)| *i == commit_instant)
+        {
+            slot.1.replace(Arc::new(ChangeBatch {
+                commit_instant,
+                changes,
+            }));
+        }
+        state.num_published += 1;
+        let wakers = take(&mut state.wakers);
+        drop(state);
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    /// Withdraws the reserved slot of a transaction that was not committed.
+    pub(super) fn withdraw(&self, commit_instant: S::Instant) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if let Some(index) = state
+            .batches
+            .iter()
+            .rposition(|(i, _)| *i == commit_instant)
+        {
+            state.batches.remove(index);
+        }
+        let wakers = take(&mut state.wakers);
+        drop(state);
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    /// Discards [`ChangeBatch`] instances that are not newer than `oldest`.
+    ///
+    /// `oldest` must not be newer than the instant of any [`ChangeStream`].
+    pub(super) fn prune(&self, oldest: S::Instant) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        while state
+            .batches
+            .front()
+            .is_some_and(|(i, b)| b.is_some() && *i <= oldest)
+        {
+            state.batches.pop_front();
+        }
+    }
+
+    /// Returns the number of published [`ChangeBatch`] instances.
+    pub(super) fn num_published(&self) -> u64 {
+        self.state.lock().map_or(0, |s| s.num_published)
+    }
+
+    /// Returns `true` if a [`ChangeBatch`] has been published since the number of published
+    /// [`ChangeBatch`] instances was `num_published`.
+    ///
+    /// If none has been published, the [`Waker`] is registered.
+    pub(super) fn has_published_since(&self, num_published: u64, waker: &Waker) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return true;
+        };
+        if state.num_published != num_published {
+            return true;
+        }
+        if !state.wakers.iter().any(|w| w.will_wake(waker)) {
+            state.wakers.push(waker.clone());
+        }
+        false
+    }
+
+    /// Returns the first [`ChangeBatch`] newer than `position`.
+    ///
+    /// If the [`ChangeBatch`] is not available, the [`Waker`] is registered.
+    fn next_batch(&self, position: S::Instant, waker: &Waker) -> Option<Arc<ChangeBatch<S>>> {
+        let mut state = self.state.lock().ok()?;
+        let index = state.batches.partition_point(|(i, _)| *i <= position);
+        if let Some((_, Some(change_batch))) = state.batches.get(index) {
+            return Some(change_batch.clone());
+        }
+        if !state.wakers.iter().any(|w| w.will_wake(waker)) {
+            state.wakers.push(waker.clone());
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Database, Error, Metadata};
+    use std::path::Path;
+    use std::time::{Duration, Instant};
+    use tokio::fs::remove_dir_all;
+
+    #[tokio::test]
+    async fn watch() {
+        const DIR: &str = "change_stream_watch_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let mut change_stream = database.watch(None).unwrap();
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("cdc".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert!(container
+            .insert(b"1", b"1", &mut journal, None)
+            .await
+            .is_ok());
+        assert!(container
+            .insert(b"2", b"2", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        let first_instant = transaction.commit().await.unwrap();
+        let pinned = database.snapshot_at(first_instant).unwrap();
+
+        // Rolled back changes are not captured.
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container.delete(b"2", &mut journal, None).await.is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        transaction.rollback();
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .update(b"1", b"3", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        let mut journal = transaction.journal();
+        assert!(container.delete(b"2", &mut journal, None).await.is_ok());
+        drop(journal);
+        let second_instant = transaction.commit().await.unwrap();
+
+        let change_batch = change_stream.next(None).await.unwrap();
+        assert_eq!(change_batch.commit_instant, first_instant);
+        assert_eq!(change_batch.changes.len(), 2);
+        assert_eq!(change_batch.changes[0].container.as_ref(), "cdc");
+        assert_eq!(change_batch.changes[0].key.as_ref(), b"1");
+        assert_eq!(change_batch.changes[0].old_value, None);
+        assert_eq!(
+            change_batch.changes[1].new_value.as_deref(),
+            Some(&b"2"[..])
+        );
+
+        let change_batch = change_stream.next(None).await.unwrap();
+        assert_eq!(change_batch.commit_instant, second_instant);
+        assert_eq!(change_batch.changes.len(), 1);
+        assert_eq!(
+            change_batch.changes[0].old_value.as_deref(),
+            Some(&b"1"[..])
+        );
+        assert_eq!(
+            change_batch.changes[0].new_value.as_deref(),
+            Some(&b"3"[..])
+        );
+
+        let deadline = Instant::now() + Duration::from_millis(1);
+        assert_eq!(
+            change_stream.next(Some(deadline)).await.err(),
+            Some(Error::Timeout)
+        );
+
+        // Backfill from the first commit instant.
+        let mut change_stream_backfill = database.watch(Some(first_instant)).unwrap();
+        let change_batch = change_stream_backfill.next(None).await.unwrap();
+        assert_eq!(change_batch.commit_instant, second_instant);
+        assert!(database.watch(Some(second_instant + 1)).is_none());
+
+        drop(pinned);
+        drop(change_stream);
+        drop(change_stream_backfill);
+        drop(container);
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        use crate::{Change, ChangeBatch, MonotonicU64};
+
+        let batch = ChangeBatch::<MonotonicU64> {
+            commit_instant: 7,
+            changes: vec![
+                Change {
+                    container: "cdc".into(),
+                    key: b"1".to_vec().into_boxed_slice(),
+                    old_value: None,
+                    new_value: Some(b"1".to_vec().into_boxed_slice()),
+                },
+                Change {
+                    container: "cdc".into(),
+                    key: b"2".to_vec().into_boxed_slice(),
+                    old_value: Some(b"2".to_vec().into_boxed_slice()),
+                    new_value: None,
+                },
+            ],
+        };
+        let mut serialized = Vec::new();
+        assert!(ciborium::into_writer(&batch, &mut serialized).is_ok());
+        let deserialized: ChangeBatch<MonotonicU64> =
+            ciborium::from_reader(serialized.as_slice()).unwrap();
+        assert_eq!(deserialized.commit_instant, batch.commit_instant);
+        assert_eq!(deserialized.changes, batch.changes);
+    }
+}
@@ -0,0 +1,235 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The module tracks the clock values up to which committed transactions are durable and
+//! visible.
+
+use super::Sequencer;
+use std::collections::VecDeque;
+use std::mem::take;
+use std::sync::atomic::Ordering::Acquire;
+use std::sync::Mutex;
+use std::task::Waker;
+
+/// [`CommitWatermark`] keeps track of transactions that have been given a commit instant, but
+/// have yet to be ended.
+///
+/// Commit instants are generated while the [`CommitWatermark`] is locked; therefore, tracked
+/// transactions are ordered by commit instant, and every transaction that was given a commit
+/// instant not greater than the clock value observed right before a tracked transaction was
+/// given its commit instant is either tracked before it or has been ended.
+#[derive(Debug)]
+pub(super) struct CommitWatermark<S: Sequencer> {
+    /// The state of the [`CommitWatermark`].
+    state: Mutex<State<S>>,
+}
+
+/// The state of a [`CommitWatermark`].
+#[derive(Debug)]
+struct State<S: Sequencer> {
+    /// Transactions being committed in commit instant order.
+    committing: VecDeque<Committing<S>>,
+
+    /// Tasks waiting for the watermarks to advance.
+    wakers: Vec<Waker>,
+}
+
+/// A transaction being committed.
+#[derive(Debug)]
+struct Committing<S: Sequencer> {
+    /// The clock value right before the transaction was given its commit instant.
+    preceding: S::Instant,
+
+    /// The commit instant of the transaction.
+    commit_instant: S::Instant,
+
+    /// The commit log record of the transaction has been persisted.
+    durable: bool,
+}
+
+impl<S: Sequencer> CommitWatermark<S> {
+    /// Tracks a transaction being committed.
+    ///
+    /// `advance` generates the commit instant of the transaction while the [`CommitWatermark`] is
+    /// locked.
+    pub(super) fn reserve<F: FnOnce() -> S::Instant>(
+        &self,
+        sequencer: &S,
+        advance: F,
+    ) -> S::Instant {
+        let Ok(mut state) = self.state.lock() else {
+            return advance();
+        };
+        let preceding = sequencer.now(Acquire);
+        let commit_instant = advance();
+        state.committing.push_back(Committing {
+            preceding,
+            commit_instant,
+            durable: false,
+        });
+        commit_instant
+    }
+
+    /// Marks the commit log record of the transaction persisted.
+    pub(super) fn mark_durable(&self, commit_instant: S::Instant) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if let Some(committing) = state
+            .committing
+            .iter_mut()
+            .find(|c| c.commit_instant == commit_instant)
+        {
+            committing.durable = true;
+        }
+        let wakers = take(&mut state.wakers);
+        drop(state);
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    /// Stops tracking the transaction as it has been committed or rolled back.
+    pub(super) fn complete(&self, commit_instant: S::Instant) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if let Some(index) = state
+            .committing
+            .iter()
+            .position(|c| c.commit_instant == commit_instant)
+        {
+            state.committing.remove(index);
+        }
+        let wakers = take(&mut state.wakers);
+        drop(state);
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    /// Returns the clock value up to which every committed transaction is visible.
+    pub(super) fn visible_clock(&self, sequencer: &S) -> S::Instant {
+        let Ok(state) = self.state.lock() else {
+            return S::Instant::default();
+        };
+        state
+            .committing
+            .front()
+            .map_or_else(|| sequencer.now(Acquire), |c| c.preceding)
+    }
+
+    /// Returns the clock value up to which the commit log record of every committed transaction
+    /// has been persisted.
+    pub(super) fn durable_clock(&self, sequencer: &S) -> S::Instant {
+        let Ok(state) = self.state.lock() else {
+            return S::Instant::default();
+        };
+        Self::durable_clock_locked(&state, sequencer)
+    }
+
+    /// Returns `true` if the durable clock has reached `clock`.
+    ///
+    /// If it has not, the [`Waker`] is registered.
+    pub(super) fn is_durable(&self, clock: S::Instant, sequencer: &S, waker: &Waker) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return true;
+        };
+        if Self::durable_clock_locked(&state, sequencer) >= clock {
+            return true;
+        }
+        if !state.wakers.iter().any(|w| w.will_wake(waker)) {
+            state.wakers.push(waker.clone());
+        }
+        false
+    }
+
+    /// Returns the clock value up to which every committed transaction is durable.
+    fn durable_clock_locked(state: &State<S>, sequencer: &S) -> S::Instant {
+        state
+            .committing
+            .iter()
+            .find(|c| !c.durable)
+            .map_or_else(|| sequencer.now(Acquire), |c| c.preceding)
+    }
+}
+
+impl<S: Sequencer> Default for CommitWatermark<S> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            state: Mutex::default(),
+        }
+    }
+}
+
+impl<S: Sequencer> Default for State<S> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            committing: VecDeque::new(),
+            wakers: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Database, Error, MonotonicU64};
+    use std::path::Path;
+    use std::sync::atomic::Ordering::Release;
+    use std::time::{Duration, Instant};
+    use tokio::fs::remove_dir_all;
+
+    #[test]
+    fn watermarks() {
+        let sequencer = MonotonicU64::default();
+        let commit_watermark = CommitWatermark::<MonotonicU64>::default();
+        let start = sequencer.now(Acquire);
+        let first = commit_watermark.reserve(&sequencer, || sequencer.advance(Release));
+        let second = commit_watermark.reserve(&sequencer, || sequencer.advance(Release));
+        assert_eq!(commit_watermark.visible_clock(&sequencer), start);
+        assert_eq!(commit_watermark.durable_clock(&sequencer), start);
+
+        commit_watermark.mark_durable(second);
+        assert_eq!(commit_watermark.durable_clock(&sequencer), start);
+        commit_watermark.mark_durable(first);
+        assert_eq!(commit_watermark.durable_clock(&sequencer), second);
+        assert_eq!(commit_watermark.visible_clock(&sequencer), start);
+
+        commit_watermark.complete(second);
+        assert_eq!(commit_watermark.visible_clock(&sequencer), start);
+        commit_watermark.complete(first);
+        assert_eq!(commit_watermark.visible_clock(&sequencer), second);
+    }
+
+    #[tokio::test]
+    async fn await_durable() {
+        const DIR: &str = "commit_watermark_await_durable_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(journal.create(&[1], None).await.is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        let mut committable = Box::pin(transaction.prepare().await.unwrap());
+        assert!(futures::poll!(committable.as_mut()).is_pending());
+
+        // The commit instant has been generated, but the transaction is not committed yet.
+        let clock = database.sequencer().now(Acquire);
+        assert!(database.durable_clock() < clock);
+        assert!(database.visible_clock() < clock);
+        let deadline = Instant::now() + Duration::from_millis(1);
+        assert_eq!(
+            database.await_durable(clock, Some(deadline)).await,
+            Err(Error::Timeout)
+        );
+
+        let commit_instant = committable.await.unwrap();
+        assert!(database.durable_clock() >= commit_instant);
+        assert!(database.visible_clock() >= commit_instant);
+        assert!(database.await_durable(commit_instant, None).await.is_ok());
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+}
@@ -0,0 +1,246 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Runtime configuration changes.
+
+use super::{ConflictPolicy, VictimPolicy, WaitPolicy};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// [`ConfigDelta`] is a set of changes to the runtime-tunable parameters of a
+/// [`Database`](super::Database).
+///
+/// Parameters that are not specified are left unchanged when the [`ConfigDelta`] is applied
+/// through [`Database::reconfigure`](super::Database::reconfigure). For parameters that can be
+/// disabled, `None` disables them.
+///
+/// The capacity of the page cache of a [`FileIO`](super::FileIO) is fixed when the database
+/// files are opened, and cannot be changed.
+///
+/// # Examples
+///
+/// ```
+/// use sap_tsf::{ConfigDelta, ConflictPolicy};
+/// use std::time::Duration;
+///
+/// let delta = ConfigDelta::new()
+///     .with_lock_timeout(Some(Duration::from_secs(1)))
+///     .with_conflict_policy(ConflictPolicy::WaitDie)
+///     .with_dirty_page_threshold(None);
+/// assert!(!delta.is_empty());
+/// assert!(ConfigDelta::new().is_empty());
+/// ```
+#[allow(clippy::option_option)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ConfigDelta {
+    /// The default memory budget of transactions in bytes.
+    pub(super) transaction_memory_limit: Option<Option<usize>>,
+
+    /// The lock escalation threshold.
+    pub(super) lock_escalation_threshold: Option<Option<usize>>,
+
+    /// The maximum number of unreclaimed versions of a key-value pair.
+    pub(super) max_version_chain_length: Option<Option<usize>>,
+
+    /// The maximum time that a request can wait for a database object.
+    pub(super) lock_timeout: Option<Option<Duration>>,
+
+    /// The maximum number of requests that can wait for a database object.
+    pub(super) max_waiters: Option<Option<usize>>,
+
+    /// The maximum number of rounds that a waiting request spins before being parked.
+    pub(super) spin_limit: Option<u32>,
+
+    /// Whether committing transactions release their locks early.
+    pub(super) early_lock_release: Option<bool>,
+
+    /// The [`ConflictPolicy`].
+    pub(super) conflict_policy: Option<ConflictPolicy>,
+
+    /// The [`WaitPolicy`].
+    pub(super) wait_policy: Option<WaitPolicy>,
+
+    /// The [`VictimPolicy`] of deadlock detection.
+    pub(super) deadlock_detection: Option<Option<VictimPolicy>>,
+
+    /// The interval at which unreachable versions are reclaimed in the background.
+    pub(super) gc_interval: Option<Duration>,
+
+    /// The dirty page ratio above which dirty pages are written back in the background.
+    pub(super) dirty_page_threshold: Option<Option<u8>>,
+
+    /// The maximum size of the log in bytes.
+    pub(super) log_capacity: Option<Option<u64>>,
+
+    /// The length of log records retained when log space is reclaimed.
+    pub(super) log_retention: Option<Option<u64>>,
+}
+
+impl ConfigDelta {
+    /// Creates an empty [`ConfigDelta`].
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the default memory budget of transactions.
+    ///
+    /// See [`Database::set_transaction_memory_limit`].
+    ///
+    /// [`Database::set_transaction_memory_limit`]: super::Database::set_transaction_memory_limit
+    #[inline]
+    #[must_use]
+    pub fn with_transaction_memory_limit(mut self, limit: Option<usize>) -> Self {
+        self.transaction_memory_limit.replace(limit);
+        self
+    }
+
+    /// Sets the lock escalation threshold.
+    ///
+    /// See [`Database::set_lock_escalation_threshold`].
+    ///
+    /// [`Database::set_lock_escalation_threshold`]: super::Database::set_lock_escalation_threshold
+    #[inline]
+    #[must_use]
+    pub fn with_lock_escalation_threshold(mut self, threshold: Option<usize>) -> Self {
+        self.lock_escalation_threshold.replace(threshold);
+        self
+    }
+
+    /// Sets the maximum number of unreclaimed versions of a key-value pair.
+    ///
+    /// See [`Database::set_max_version_chain_length`].
+    ///
+    /// [`Database::set_max_version_chain_length`]: super::Database::set_max_version_chain_length
+    #[inline]
+    #[must_use]
+    pub fn with_max_version_chain_length(mut self, max_length: Option<usize>) -> Self {
+        self.max_version_chain_length.replace(max_length);
+        self
+    }
+
+    /// Sets the maximum time that a request can wait for a database object.
+    ///
+    /// See [`AccessController::set_lock_timeout`](super::AccessController::set_lock_timeout).
+    #[inline]
+    #[must_use]
+    pub fn with_lock_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.lock_timeout.replace(timeout);
+        self
+    }
+
+    /// Sets the maximum number of requests that can wait for a database object.
+    ///
+    /// See [`AccessController::set_max_waiters`](super::AccessController::set_max_waiters).
+    #[inline]
+    #[must_use]
+    pub fn with_max_waiters(mut self, max_waiters: Option<usize>) -> Self {
+        self.max_waiters.replace(max_waiters);
+        self
+    }
+
+    /// Sets the maximum number of rounds that a waiting request spins before being parked.
+    ///
+    /// See [`AccessController::set_spin_limit`](super::AccessController::set_spin_limit).
+    #[inline]
+    #[must_use]
+    pub fn with_spin_limit(mut self, spin_limit: u32) -> Self {
+        self.spin_limit.replace(spin_limit);
+        self
+    }
+
+    /// Enables or disables early lock release.
+    ///
+    /// See [`set_early_lock_release`] of [`AccessController`].
+    ///
+    /// [`set_early_lock_release`]: super::AccessController::set_early_lock_release
+    /// [`AccessController`]: super::AccessController
+    #[inline]
+    #[must_use]
+    pub fn with_early_lock_release(mut self, enabled: bool) -> Self {
+        self.early_lock_release.replace(enabled);
+        self
+    }
+
+    /// Sets the [`ConflictPolicy`].
+    #[inline]
+    #[must_use]
+    pub fn with_conflict_policy(mut self, conflict_policy: ConflictPolicy) -> Self {
+        self.conflict_policy.replace(conflict_policy);
+        self
+    }
+
+    /// Sets the [`WaitPolicy`].
+    #[inline]
+    #[must_use]
+    pub fn with_wait_policy(mut self, wait_policy: WaitPolicy) -> Self {
+        self.wait_policy.replace(wait_policy);
+        self
+    }
+
+    /// Sets the [`VictimPolicy`] of deadlock detection.
+    ///
+    /// See [`AccessController::set_deadlock_detection`].
+    ///
+    /// [`AccessController::set_deadlock_detection`]: super::AccessController::set_deadlock_detection
+    #[inline]
+    #[must_use]
+    pub fn with_deadlock_detection(mut self, victim_policy: Option<VictimPolicy>) -> Self {
+        self.deadlock_detection.replace(victim_policy);
+        self
+    }
+
+    /// Sets the interval at which the background task processor reclaims unreachable versions.
+    ///
+    /// See [`Database::gc_interval`](super::Database::gc_interval).
+    #[inline]
+    #[must_use]
+    pub fn with_gc_interval(mut self, interval: Duration) -> Self {
+        self.gc_interval.replace(interval);
+        self
+    }
+
+    /// Sets the dirty page ratio above which dirty pages are written back in the background.
+    ///
+    /// See [`FileIO::set_dirty_page_threshold`](super::FileIO::set_dirty_page_threshold); other
+    /// persistence layers ignore the parameter.
+    #[inline]
+    #[must_use]
+    pub fn with_dirty_page_threshold(mut self, threshold: Option<u8>) -> Self {
+        self.dirty_page_threshold.replace(threshold);
+        self
+    }
+
+    /// Sets the maximum size of the log in bytes.
+    ///
+    /// See [`FileIO::set_log_capacity`](super::FileIO::set_log_capacity); other persistence
+    /// layers ignore the parameter.
+    #[inline]
+    #[must_use]
+    pub fn with_log_capacity(mut self, capacity: Option<u64>) -> Self {
+        self.log_capacity.replace(capacity);
+        self
+    }
+
+    /// Sets the length of log records in bytes that are retained when log space is reclaimed.
+    ///
+    /// See [`FileIO::set_log_retention`](super::FileIO::set_log_retention); other persistence
+    /// layers ignore the parameter.
+    #[inline]
+    #[must_use]
+    pub fn with_log_retention(mut self, retention: Option<u64>) -> Self {
+        self.log_retention.replace(retention);
+        self
+    }
+
+    /// Returns `true` if the [`ConfigDelta`] does not change any parameters.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
@@ -2,57 +2,5356 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use super::{Metadata, PersistenceLayer, Sequencer};
+use super::cancellation_token::Cancellable;
+use super::catalog::CATALOG_ID;
+use super::journal::Anchor as JournalAnchor;
+use super::secondary_index::SecondaryIndex;
+use super::task_processor::Task;
+use super::transaction::SerializationAnchor;
+use super::utils::Instant;
+use super::{
+    AccessController, Change, Counter, Database, Error, Journal, Metadata, PersistedIndexInterface,
+    PersistenceLayer, Sequencer, Snapshot, TransactionID, TransactionState, VersionRecord,
+};
+use scc::ebr::{self, AtomicShared};
+use scc::TreeIndex;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+#[cfg(feature = "async")]
+use std::fmt::{self, Debug};
+use std::future::poll_fn;
+#[cfg(feature = "async")]
+use std::future::Future;
+use std::ops::{Bound, RangeBounds};
+#[cfg(feature = "async")]
+use std::pin::Pin;
+use std::ptr;
+use std::sync::atomic::Ordering::{AcqRel, Acquire, Relaxed, Release};
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::sync::{Arc, Mutex, OnceLock};
+#[cfg(feature = "async")]
+use std::task::Context;
+use std::task::Poll;
+use std::time::Duration;
 
 /// [`Container`] is a collection of organized data and its [`Metadata`].
+///
+/// [`Container`] stores byte-string key-value pairs, and every modification to a key-value pair
+/// is versioned: each version of a value is identified as a database object in the
+/// [`AccessController`], thereby readers only see versions that are visible to their
+/// [`Snapshot`].
+///
+/// Each version is written to the log of the [`PersistenceLayer`] along with the database object,
+/// and is installed again when the database is recovered.
 #[derive(Debug)]
 pub struct Container<S: Sequencer, P: PersistenceLayer<S>> {
+    /// The identifier of the [`Container`].
+    id: u64,
+
+    /// The name of the [`Container`] as of the latest committed rename.
+    name: Mutex<Arc<str>>,
+
     /// The metadata describing the specification of the [`Container`].
-    _metadata: Metadata,
+    metadata: Metadata,
+
+    /// The records in the [`Container`].
+    ///
+    /// The latest versions visible to a checkpoint are persisted in a B+tree or a linear hash table
+    /// in database pages according to the [`IndexType`](super::IndexType) in the [`Metadata`].
+    /// Versions in a linear hash table are loaded into memory when the database is recovered,
+    /// whereas those in a B+tree are left in [`Container::persisted_index`] and loaded when their
+    /// keys are first accessed.
+    records: TreeIndex<Box<[u8]>, ebr::Shared<Record>>,
+
+    /// The index of the versions persisted by the last checkpoint before the database was
+    /// recovered.
+    ///
+    /// Keys in memory take precedence over those in the index, and the garbage collector keeps
+    /// keys in the index in memory even after all their versions were reclaimed.
+    persisted_index: OnceLock<P::PersistedIndex>,
+
+    /// The approximate number of key-value pairs in the persisted index that have yet to be
+    /// loaded into memory.
+    unloaded_records: AtomicU64,
+
+    /// The error that accesses to key-value pairs fail with since a page of the persisted index
+    /// was found corrupt while the database was being recovered.
+    quarantined: OnceLock<Error>,
+
+    /// Journals holding a lock on the whole [`Container`] along with the [`LockMode`].
+    lock_owners: Mutex<Vec<(ebr::Shared<JournalAnchor<S>>, LockMode)>>,
+
+    /// The access controller of the database that the [`Container`] belongs to.
+    access_controller: Arc<AccessController<S>>,
+
+    /// Secondary indexes of the [`Container`].
+    indexes: Mutex<Vec<Arc<SecondaryIndex<S, P>>>>,
+
+    /// Key ranges read by serializable transactions.
+    ///
+    /// Writers of a key in any of the ranges check the dependency from the reader, which is the
+    /// precision lock equivalent of the [`Record`] readers for keys that did not exist.
+    range_readers: Mutex<Vec<(RangeRead, ebr::Shared<SerializationAnchor>)>>,
+
+    /// The [`Container`] is monitored by the garbage collector.
+    ///
+    /// Writers set the flag after installing a version, and the garbage collector clears it
+    /// before visiting the records, so that a version installed during the visit makes the
+    /// [`Container`] monitored again.
+    monitored: AtomicBool,
+
+    /// The approximate number of key-value pairs.
+    live_records: AtomicU64,
+
+    /// The approximate number of versions awaiting the garbage collector.
+    dead_versions: AtomicU64,
+
+    /// The number of database pages of the index of the [`Container`] persisted by the latest
+    /// checkpoint.
+    persisted_pages: AtomicU64,
+
+    /// The identifier of the latest truncation marker of the [`Container`] that is visible to
+    /// every reader.
+    ///
+    /// Versions of database objects created before the marker are invisible to every reader.
+    truncated: AtomicU64,
+
+    /// Truncation markers that may be invisible to some readers in ascending order along with
+    /// whether each of them was played back from the log.
+    ///
+    /// Versions of database objects created before a marker are invisible to readers that see
+    /// the marker.
+    truncations: Mutex<Vec<(u64, bool)>>,
+
+    /// A link to old versions of the [`Container`].
+    _version: std::marker::PhantomData<(S, P)>,
+}
+
+/// The interval of checking the owners of a [`Container`] lock while waiting for them.
+const LOCK_RECHECK_INTERVAL: Duration = Duration::from_millis(16);
+
+/// The interval of checking the length of a version chain while waiting for the garbage collector
+/// to reclaim old versions.
+const VERSION_CHAIN_RECHECK_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Converts a borrowed key bound into an owned one.
+fn owned_bound<K: AsRef<[u8]>>(bound: Bound<&K>) -> Bound<Box<[u8]>> {
+    match bound {
+        Bound::Included(k) => Bound::Included(k.as_ref().into()),
+        Bound::Excluded(k) => Bound::Excluded(k.as_ref().into()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+/// The number of key-value pairs of which versions are created together by
+/// [`Container::bulk_load`].
+const BULK_LOAD_BATCH_SIZE: usize = 1024;
+
+/// The number of keys that [`Scanner`] looks up at once.
+const SCAN_BATCH_SIZE: usize = 64;
+
+/// The record identifier of the [`VersionRecord`] logging a truncation marker.
+///
+/// No [`Record`] is identified as it, since database object identifiers start at `1 << 63`.
+const TRUNCATION_RECORD_ID: u64 = 0;
+
+/// [`ContainerStatistics`] is a set of approximate statistics of a [`Container`].
+///
+/// The statistics are adjusted when a [`Journal`] that changed the [`Container`] is submitted,
+/// and recomputed whenever the garbage collector visits the [`Container`]; changes made by a
+/// transaction rolled back after submitting the [`Journal`] are therefore counted until the next
+/// visit.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ContainerStatistics {
+    /// The number of key-value pairs.
+    pub live_records: u64,
+
+    /// The number of versions that were deleted or replaced with newer versions, and are awaiting
+    /// the garbage collector.
+    pub dead_versions: u64,
+
+    /// The number of database pages allocated to the index of the [`Container`] by the latest
+    /// checkpoint.
+    ///
+    /// Versions created after the checkpoint are not counted until the next checkpoint persists
+    /// them.
+    pub allocated_pages: u64,
+}
+
+/// [`StatisticsDelta`] is a change to the [`ContainerStatistics`] made by a [`Journal`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(super) struct StatisticsDelta {
+    /// The identifier of the [`Container`].
+    pub(super) container_id: u64,
+
+    /// The change to the number of key-value pairs.
+    live_records: i64,
+
+    /// The number of versions that were deleted or replaced.
+    dead_versions: u64,
+}
+
+/// [`LockMode`] is the mode of a lock on a whole [`Container`].
+///
+/// Modifying a key-value pair implicitly locks the [`Container`] in
+/// [`LockMode::IntentionExclusive`] mode before locking the key-value pair, thereby a transaction
+/// holding the [`Container`] in [`LockMode::Shared`] or [`LockMode::Exclusive`] mode is able to
+/// read or modify the whole [`Container`] without locking individual key-value pairs.
+///
+/// | Requested \ Held | `IntentionShared` | `IntentionExclusive` | `Shared` | `Exclusive` |
+/// |-------------------|-------------------|----------------------|----------|-------------|
+/// | `IntentionShared` | Yes | Yes | Yes | No |
+/// | `IntentionExclusive` | Yes | Yes | No | No |
+/// | `Shared` | Yes | No | Yes | No |
+/// | `Exclusive` | No | No | No | No |
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum LockMode {
+    /// The transaction intends to read key-value pairs.
+    IntentionShared,
+
+    /// The transaction intends to modify key-value pairs.
+    IntentionExclusive,
+
+    /// The transaction reads the whole [`Container`], and other transactions cannot modify it.
+    Shared,
+
+    /// The transaction owns the whole [`Container`].
+    Exclusive,
+}
+
+/// [`Scanner`] visits key-value pairs in a [`Container`] that are visible to a [`Snapshot`] in
+/// ascending key order.
+///
+/// [`Scanner`] does not materialize the whole range; [`Scanner::next`] looks up a batch of next
+/// keys in memory and in the persisted index of the [`Container`] when the keys looked up before
+/// are exhausted, and the pages of the persisted index following those being read are read in the
+/// background as set by [`Scanner::with_readahead`]. Key-value pairs read from the persisted index
+/// are not loaded into memory.
+#[derive(Debug)]
+pub struct Scanner<'c, 's, 'd, 't, 'j, S: Sequencer, P: PersistenceLayer<S>> {
+    /// The [`Container`] to scan.
+    container: &'c Container<S, P>,
+
+    /// The [`Snapshot`] of the reader.
+    snapshot: &'s Snapshot<'d, 't, 'j, S>,
+
+    /// The lower bound of the next key.
+    start: Bound<Box<[u8]>>,
+
+    /// The upper bound of keys.
+    end: Bound<Box<[u8]>>,
+
+    /// The deadline of each visibility check.
+    deadline: Option<Instant>,
+
+    /// The key-value pairs looked up ahead of the consumer.
+    candidates: VecDeque<Candidate>,
+
+    /// The number of pages of the persisted index to read ahead.
+    readahead: usize,
+
+    /// No more keys are in the range other than those looked up.
+    exhausted: bool,
+}
+
+/// [`ScanStream`] is a [`Stream`](futures_core::Stream) of key-value pairs visited by a
+/// [`Scanner`].
+///
+/// The next key-value pair is looked up as soon as the current one is yielded, so that reading
+/// ahead overlaps with the consumer processing the current key-value pair. The stream ends after
+/// an error is yielded.
+#[cfg(feature = "async")]
+pub struct ScanStream<'c: 's, 's, 'd, 't, 'j, S: Sequencer, P: PersistenceLayer<S>> {
+    /// The state of the next key-value pair.
+    state: StreamState<'c, 's, 'd, 't, 'j, S, P>,
+}
+
+/// The type of results of key-value pairs visited by a [`Scanner`].
+type ScanResult = Result<Option<(Box<[u8]>, Vec<u8>)>, Error>;
+
+/// The state of the next key-value pair of a [`ScanStream`].
+#[cfg(feature = "async")]
+enum StreamState<'c: 's, 's, 'd, 't, 'j, S: Sequencer, P: PersistenceLayer<S>> {
+    /// The next key-value pair is being looked up.
+    Pending(Pin<ScanFuture<'c, 's, 'd, 't, 'j, S, P>>),
+
+    /// The next key-value pair has been looked up.
+    Ready(Scanner<'c, 's, 'd, 't, 'j, S, P>, ScanResult),
+
+    /// The stream has ended.
+    Done,
+}
+
+/// The type of futures looking up the next key-value pair of a [`ScanStream`].
+#[cfg(feature = "async")]
+type ScanFuture<'c, 's, 'd, 't, 'j, S, P> =
+    Box<dyn Future<Output = (Scanner<'c, 's, 'd, 't, 'j, S, P>, ScanResult)> + Send + 's>;
+
+/// [`Versions`] visits the versions of a key-value pair in a [`Container`] from the latest to the
+/// oldest.
+///
+/// Versions are protected by the [`ebr::Guard`] supplied to [`Container::versions`], therefore
+/// the values stay valid while the [`ebr::Guard`] is alive even if the versions are removed from
+/// the [`Container`] in the meantime.
+#[derive(Debug)]
+pub struct Versions<'g, S: Sequencer> {
+    /// The access controller of the database that the [`Container`] belongs to.
+    access_controller: &'g AccessController<S>,
+
+    /// The identifier of the [`Container`].
+    container_id: u64,
+
+    /// The [`Record`] of the key-value pair.
+    record: ebr::Ptr<'g, Record>,
+
+    /// The next version to visit.
+    current: Option<&'g Version>,
+
+    /// The [`ebr::Guard`] protecting the versions.
+    guard: &'g ebr::Guard,
+}
+
+/// [`RecordVersion`] is a version of a key-value pair in a [`Container`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecordVersion<'g, I> {
+    /// The database object identifier representing the lifetime of the version.
+    pub object_id: u64,
+
+    /// The state of the version.
+    pub state: VersionState<I>,
+
+    /// The value.
+    pub value: &'g [u8],
+
+    /// The identifier of the [`Container`] that the version belongs to.
+    container_id: u64,
+
+    /// The [`Record`] that the version belongs to.
+    record: ebr::Ptr<'g, Record>,
+}
+
+/// [`Subscription`] is notified when a [`RecordVersion`] is superseded.
+///
+/// A [`RecordVersion`] is superseded once a newer version of the key-value pair becomes visible,
+/// or the key-value pair is deleted after the [`RecordVersion`] became visible. Unlike
+/// [`ChangeStream`](super::ChangeStream), [`Subscription`] does not retain committed changes.
+#[derive(Debug)]
+pub struct Subscription<'d, S: Sequencer, P: PersistenceLayer<S>> {
+    /// The [`Database`] that the [`Container`] belongs to.
+    database: &'d Database<S, P>,
+
+    /// The identifier of the [`Container`] that the version belongs to.
+    container_id: u64,
+
+    /// The [`Record`] that the version belongs to.
+    record: Option<ebr::Shared<Record>>,
+
+    /// The database object identifier of the version.
+    object_id: u64,
+}
+
+/// [`VersionState`] is the state of a version of a key-value pair taken from the
+/// [`AccessController`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum VersionState<I> {
+    /// The version is being created, deleted, or protected by the transactions.
+    Owned(Vec<(TransactionID, TransactionState<I>)>),
+
+    /// The version was created at the instant.
+    Created(I),
+
+    /// The version was deleted or replaced with a newer version at the instant.
+    Deleted(I),
+
+    /// No access control data is kept for the version, therefore it is visible to every reader.
+    Untracked,
+}
+
+/// [`Record`] is associated with a key in a [`Container`].
+#[derive(Debug)]
+struct Record {
+    /// The database object identifier used to serialize writers of the [`Record`].
+    lock_id: u64,
+
+    /// The latest version of the [`Record`].
+    head: AtomicShared<Version>,
+
+    /// Serializable transactions that have read the [`Record`].
+    readers: Mutex<Vec<ebr::Shared<SerializationAnchor>>>,
+
+    /// The [`Record`] has been removed from the [`Container`].
+    ///
+    /// The [`Mutex`] serializes the garbage collector removing the [`Record`] and writers that
+    /// have locked the [`Record`].
+    removed: Mutex<bool>,
+}
+
+/// [`RangeRead`] is a range of keys read by a transaction.
+#[derive(Clone, Debug)]
+struct RangeRead {
+    /// The lower bound of keys.
+    start: Bound<Box<[u8]>>,
+
+    /// The upper bound of keys.
+    end: Bound<Box<[u8]>>,
+}
+
+/// [`Candidate`] is a key-value pair in a [`Container`] of which the visibility has yet to be
+/// checked.
+#[derive(Debug)]
+enum Candidate {
+    /// A key and its [`Record`] in memory.
+    Loaded(Box<[u8]>, ebr::Shared<Record>),
+
+    /// A version in the persisted index of which the key is not in memory.
+    Persisted {
+        /// The database object identifier used to serialize writers of the key.
+        #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+        record_id: u64,
+
+        /// The database object identifier representing the lifetime of the version.
+        object_id: u64,
+
+        /// The key.
+        key: Box<[u8]>,
+
+        /// The value.
+        value: Box<[u8]>,
+    },
+}
+
+/// [`OptimisticAccess`] is an access to a key-value pair made by an optimistic
+/// [`Transaction`](super::Transaction).
+///
+/// Accesses are buffered in the transaction, and they are validated against the latest versions
+/// when the transaction is committed.
+#[derive(Debug)]
+pub(super) struct OptimisticAccess {
+    /// The identifier of the [`Container`].
+    container_id: u64,
+
+    /// The name of the [`Container`].
+    container: Arc<str>,
+
+    /// The key.
+    key: Box<[u8]>,
+
+    /// The [`Record`] associated with the key.
+    record: ebr::Shared<Record>,
+
+    /// The [`Version`] observed when the key was first accessed by the transaction.
+    observed: Option<ebr::Shared<Version>>,
+
+    /// The value that the transaction sees after the access; `None` denotes no value.
+    value: Option<Box<[u8]>>,
+
+    /// The transaction wrote the value.
+    write: bool,
+}
+
+/// [`Version`] is an immutable value of a [`Record`].
+#[derive(Debug)]
+struct Version {
+    /// The database object identifier representing the lifetime of the [`Version`].
+    object_id: u64,
+
+    /// The value.
+    value: Box<[u8]>,
+
+    /// The previous version.
+    ///
+    /// Only the garbage collector modifies it in order to unlink reclaimed versions.
+    prev: AtomicShared<Version>,
+
+    /// The [`Version`] is invisible to every reader, and it is being unlinked from the
+    /// [`Record`].
+    ///
+    /// The flag is set before the access control data of the [`Version`] is removed, since the
+    /// absence of access control data means that the [`Version`] is visible to every reader.
+    reclaimed: AtomicBool,
+
+    /// The serializable transaction that created the [`Version`].
+    creator: Option<ebr::Shared<SerializationAnchor>>,
+}
+
+impl<S: Sequencer, P: PersistenceLayer<S>> Container<S, P> {
+    /// Reads the value associated with the key.
+    ///
+    /// Returns `None` if no value associated with the key is visible to the [`Snapshot`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the visibility of a value could not be determined until the
+    /// deadline was reached, or the persisted index of the [`Container`] could not be read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("container_get")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let container = database
+    ///         .create_container("hello".to_string(), Metadata::default(), &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     assert!(container.insert(b"1", b"one", &mut journal, None).await.is_ok());
+    ///     journal.submit();
+    ///     assert!(transaction.commit().await.is_ok());
+    ///
+    ///     let snapshot = database.snapshot();
+    ///     let value = container.get(b"1", &snapshot, None).await;
+    ///     assert_eq!(value, Ok(Some(b"one".to_vec())));
+    /// };
+    /// ```
+    #[inline]
+    pub async fn get(
+        &self,
+        key: &[u8],
+        snapshot: &Snapshot<'_, '_, '_, S>,
+        deadline: Option<Instant>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let Some(record) = self.load_record(key).await? else {
+            return Ok(None);
+        };
+        let truncation = self.truncation(snapshot, deadline).await?;
+        Ok(Self::visible_version(
+            &self.access_controller,
+            &record,
+            snapshot,
+            truncation,
+            deadline,
+        )
+        .await?
+        .map(|v| v.value.to_vec()))
+    }
+
+    /// Reads the value associated with the key with the [`Journal`].
+    ///
+    /// Changes made by the transaction are visible to the [`Journal`], and the value read by an
+    /// optimistic transaction is validated when the transaction is committed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the visibility of a value could not be determined until the
+    /// deadline was reached, or the persisted index of the [`Container`] could not be read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("container_read")).await.unwrap();
+    ///     let transaction = database.optimistic_transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let container = database
+    ///         .create_container("hello".to_string(), Metadata::default(), &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     assert!(container.insert(b"1", b"one", &mut journal, None).await.is_ok());
+    ///     let value = container.read(b"1", &mut journal, None).await;
+    ///     assert_eq!(value, Ok(Some(b"one".to_vec())));
+    /// };
+    /// ```
+    #[inline]
+    pub async fn read(
+        &self,
+        key: &[u8],
+        journal: &mut Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        if journal.transaction().is_optimistic() {
+            let record = self.record(key, journal).await?;
+            let (buffered, observed, current) =
+                self.optimistic_view(&record, journal, deadline).await?;
+            Self::certify_read(&record, observed.as_ref(), journal)?;
+            if !buffered {
+                journal.push_optimistic_access(OptimisticAccess {
+                    container_id: self.id,
+                    container: self.name(),
+                    key: key.into(),
+                    record,
+                    observed,
+                    value: current.clone(),
+                    write: false,
+                });
+            }
+            return Ok(current.map(Into::into));
+        }
+        let Some(record) = self.load_record(key).await? else {
+            return Ok(None);
+        };
+        let snapshot = Self::journal_view(journal);
+        let current = Self::visible_version(
+            &self.access_controller,
+            &record,
+            &snapshot,
+            self.truncation(&snapshot, deadline).await?,
+            deadline,
+        )
+        .await?;
+        drop(snapshot);
+        Self::certify_read(&record, current.as_ref(), journal)?;
+        Ok(current.map(|v| v.value.to_vec()))
+    }
+
+    /// Reads key-value pairs in the range with the [`Journal`] in ascending key order.
+    ///
+    /// Each key-value pair is read as if by [`Container::read`], and the range itself is
+    /// registered for serializable transactions: a serializable transaction inserting a key into
+    /// the range afterwards, or having inserted one that is invisible to the reader, depends on
+    /// the reader, so that a phantom makes either of them fail with
+    /// [`Error::SerializationFailure`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the visibility of a value could not be determined until the
+    /// deadline was reached, or the persisted index of the [`Container`] could not be read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, IsolationLevel, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("container_read_range")).await.unwrap();
+    ///     let mut transaction = database.transaction();
+    ///     transaction.set_isolation_level(IsolationLevel::Serializable);
+    ///     let mut journal = transaction.journal();
+    ///     let container = database
+    ///         .create_container("hello".to_string(), Metadata::default(), &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     assert!(container.insert(b"1", b"one", &mut journal, None).await.is_ok());
+    ///     let pairs = container.read_range(b"1".as_slice().., &mut journal, None).await;
+    ///     assert_eq!(pairs, Ok(vec![(b"1".as_slice().into(), b"one".to_vec())]));
+    /// };
+    /// ```
+    #[inline]
+    pub async fn read_range<K: AsRef<[u8]>, R: RangeBounds<K>>(
+        &self,
+        range: R,
+        journal: &mut Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<Vec<(Box<[u8]>, Vec<u8>)>, Error> {
+        let range_read = RangeRead {
+            start: owned_bound(range.start_bound()),
+            end: owned_bound(range.end_bound()),
+        };
+
+        // The range is registered before the keys are visited so that writers inserting a key
+        // into the range ever since see the reader.
+        if let Some(reader) = journal.transaction().serialization_anchor() {
+            let oldest = journal.database().access_controller().oldest_serializable();
+            if let Ok(mut range_readers) = self.range_readers.lock() {
+                range_readers.retain(|(_, r)| !r.is_obsolete(oldest));
+                range_readers.push((range_read.clone(), reader.clone()));
+            }
+        }
+        let (start, end) = range_read.bounds();
+        self.load_records(start, end).await?;
+        let keys: Vec<Box<[u8]>> = self
+            .records
+            .range::<[u8], _>(range_read.bounds(), &ebr::Guard::new())
+            .map(|(k, _)| k.clone())
+            .collect();
+        let mut pairs = Vec::new();
+        for key in keys {
+            if let Some(value) = self.read(&key, journal, deadline).await? {
+                pairs.push((key, value));
+            }
+        }
+        Ok(pairs)
+    }
+
+    /// Returns a [`Scanner`] that visits key-value pairs in the range that are visible to the
+    /// [`Snapshot`] in ascending key order.
+    ///
+    /// Key ranges visited by a [`Scanner`] are not protected against phantoms; use
+    /// [`Container::read_range`] instead in serializable transactions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("container_range")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let container = database
+    ///         .create_container("hello".to_string(), Metadata::default(), &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     assert!(container.insert(b"1", b"one", &mut journal, None).await.is_ok());
+    ///     assert!(container.insert(b"2", b"two", &mut journal, None).await.is_ok());
+    ///     journal.submit();
+    ///     assert!(transaction.commit().await.is_ok());
+    ///
+    ///     let snapshot = database.snapshot();
+    ///     let mut scanner = container.range(b"1".as_slice()..b"3".as_slice(), &snapshot, None);
+    ///     while let Ok(Some((key, value))) = scanner.next().await {
+    ///         println!("{key:?}: {value:?}");
+    ///     }
+    /// };
+    /// ```
+    #[inline]
+    pub fn range<'s, 'd, 't, 'j, K: AsRef<[u8]>, R: RangeBounds<K>>(
+        &self,
+        range: R,
+        snapshot: &'s Snapshot<'d, 't, 'j, S>,
+        deadline: Option<Instant>,
+    ) -> Scanner<'_, 's, 'd, 't, 'j, S, P> {
+        Scanner {
+            container: self,
+            snapshot,
+            start: owned_bound(range.start_bound()),
+            end: owned_bound(range.end_bound()),
+            deadline,
+            candidates: VecDeque::new(),
+            readahead: 0,
+            exhausted: false,
+        }
+    }
+
+    /// Returns a [`Versions`] that visits the versions of the key-value pair from the latest to
+    /// the oldest regardless of their visibility.
+    ///
+    /// It is intended for debugging and auditing the history of a key-value pair; versions are
+    /// visited without waiting for the transactions owning them. Versions in the persisted index
+    /// of the [`Container`] are not visited unless the key has been accessed since the database
+    /// was recovered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Metadata, VersionState};
+    /// use scc::ebr::Guard;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("container_versions")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let container = database
+    ///         .create_container("hello".to_string(), Metadata::default(), &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     assert!(container.insert(b"1", b"one", &mut journal, None).await.is_ok());
+    ///     journal.submit();
+    ///     assert!(transaction.commit().await.is_ok());
+    ///
+    ///     let guard = Guard::new();
+    ///     for version in container.versions(b"1", &guard) {
+    ///         if let VersionState::Created(instant) = version.state {
+    ///             println!("{:?} was created at {instant}", version.value);
+    ///         }
+    ///     }
+    /// };
+    /// ```
+    #[inline]
+    pub fn versions<'g>(&'g self, key: &[u8], guard: &'g ebr::Guard) -> Versions<'g, S> {
+        let record = self
+            .records
+            .peek(key, guard)
+            .map_or_else(ebr::Ptr::default, |r| r.get_guarded_ptr(guard));
+        Versions {
+            access_controller: &self.access_controller,
+            container_id: self.id,
+            record,
+            current: record
+                .as_ref()
+                .and_then(|r| r.head.load(Acquire, guard).as_ref()),
+            guard,
+        }
+    }
+
+    /// Inserts a new key-value pair with the [`Journal`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the key exists, another transaction is modifying the key, or the
+    /// deadline was reached.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Error, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("container_insert")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let container = database
+    ///         .create_container("hello".to_string(), Metadata::default(), &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     assert!(container.insert(b"1", b"one", &mut journal, None).await.is_ok());
+    ///     assert_eq!(
+    ///         container.insert(b"1", b"two", &mut journal, None).await,
+    ///         Err(Error::UniquenessViolation)
+    ///     );
+    /// };
+    /// ```
+    #[inline]
+    pub async fn insert(
+        &self,
+        key: &[u8],
+        value: &[u8],
+        journal: &mut Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        if journal.transaction().is_optimistic() {
+            return self
+                .write_optimistically(key, Some(value), false, journal, deadline)
+                .await;
+        }
+        let (record, current) = self.lock_record(key, journal, deadline).await?;
+        if current.is_some() {
+            return Err(Error::UniquenessViolation);
+        }
+        journal.reserve_memory(Self::change_memory_usage(key, None, Some(value)))?;
+        Self::push_version(self.id, &record, key, value, None, journal, deadline).await?;
+        self.monitor(journal.database());
+        Self::record_change(
+            self.id,
+            Change {
+                container: self.name(),
+                key: key.into(),
+                old_value: None,
+                new_value: Some(value.into()),
+            },
+            journal,
+        );
+        self.maintain_indexes(key, None, Some(value), journal, deadline)
+            .await
+    }
+
+    /// Inserts key-value pairs sorted by their keys with the [`Journal`], and returns the number
+    /// of inserted key-value pairs.
+    ///
+    /// The [`Container`] is locked in [`LockMode::Exclusive`] mode instead of locking each key,
+    /// and versions are created in batches; the key-value pairs become visible when the
+    /// transaction is committed as if they were inserted one by one with [`Container::insert`].
+    /// Optimistic transactions insert the key-value pairs one by one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::WrongParameter`] if the keys are not in strictly ascending order, or an
+    /// [`Error`] if a key exists, the [`Container`] could not be locked, or the deadline was
+    /// reached. Key-value pairs inserted before the error occurred are not reverted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("container_bulk_load")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let container = database
+    ///         .create_container("hello".to_string(), Metadata::default(), &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     let pairs = (0_u32..1024).map(|i| (i.to_be_bytes(), i.to_le_bytes()));
+    ///     assert_eq!(container.bulk_load(pairs, &mut journal, None).await, Ok(1024));
+    /// };
+    /// ```
+    #[inline]
+    pub async fn bulk_load<K: AsRef<[u8]>, V: AsRef<[u8]>, I: IntoIterator<Item = (K, V)>>(
+        &self,
+        sorted: I,
+        journal: &mut Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<usize, Error> {
+        let optimistic = journal.transaction().is_optimistic();
+        if !optimistic {
+            self.lock(LockMode::Exclusive, journal, deadline).await?;
+        }
+        let mut num_inserted = 0;
+        let mut last_key: Option<Box<[u8]>> = None;
+        let mut batch = Vec::new();
+        for (key, value) in sorted {
+            let prev_key = batch
+                .last()
+                .map(|(_, k, _): &(ebr::Shared<Record>, K, V)| k.as_ref())
+                .or(last_key.as_deref());
+            if prev_key.is_some_and(|k| k >= key.as_ref()) {
+                return Err(Error::WrongParameter);
+            }
+            if optimistic {
+                self.insert(key.as_ref(), value.as_ref(), journal, deadline)
+                    .await?;
+                last_key.replace(key.as_ref().into());
+                num_inserted += 1;
+                continue;
+            }
+
+            // The garbage collector does not remove records while the container is locked
+            // exclusively.
+            let record = loop {
+                let record = self.record(key.as_ref(), journal).await?;
+                if !record.is_removed() {
+                    break record;
+                }
+            };
+            Self::certify_write(&record, journal)?;
+            self.certify_range_write(key.as_ref(), journal)?;
+            let snapshot = Self::journal_view(journal);
+            let current = Self::visible_version(
+                &self.access_controller,
+                &record,
+                &snapshot,
+                self.truncation(&snapshot, deadline).await?,
+                deadline,
+            )
+            .await;
+            drop(snapshot);
+            if current?.is_some() {
+                return Err(Error::UniquenessViolation);
+            }
+            batch.push((record, key, value));
+            if batch.len() == BULK_LOAD_BATCH_SIZE {
+                last_key = batch.last().map(|(_, k, _)| k.as_ref().into());
+                num_inserted += self.load_batch(&mut batch, journal, deadline).await?;
+            }
+        }
+        if !batch.is_empty() {
+            num_inserted += self.load_batch(&mut batch, journal, deadline).await?;
+        }
+        Ok(num_inserted)
+    }
+
+    /// Updates the value associated with the key with the [`Journal`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the key does not exist, another transaction is modifying the key,
+    /// or the deadline was reached.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("container_update")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let container = database
+    ///         .create_container("hello".to_string(), Metadata::default(), &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     assert!(container.insert(b"1", b"one", &mut journal, None).await.is_ok());
+    ///     assert!(container.update(b"1", b"two", &mut journal, None).await.is_ok());
+    /// };
+    /// ```
+    #[inline]
+    pub async fn update(
+        &self,
+        key: &[u8],
+        value: &[u8],
+        journal: &mut Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        if journal.transaction().is_optimistic() {
+            return self
+                .write_optimistically(key, Some(value), true, journal, deadline)
+                .await;
+        }
+        let (record, current) = self.lock_record(key, journal, deadline).await?;
+        let Some(current) = current else {
+            return Err(Error::NotFound);
+        };
+        self.replace_version(&record, key, &current, value, journal, deadline)
+            .await
+    }
+
+    /// Updates the value associated with the key with the value derived from the current one
+    /// with the [`Journal`].
+    ///
+    /// The current value is read and replaced while the key is locked, and `f` receives the value
+    /// visible to the [`Journal`]. The new value is installed as a new version, and the existing
+    /// version remains visible to older snapshots.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the key does not exist, another transaction is modifying the key,
+    /// or the deadline was reached.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("container_update_with")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let container = database
+    ///         .create_container("hello".to_string(), Metadata::default(), &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     assert!(container.insert(b"1", b"one", &mut journal, None).await.is_ok());
+    ///     let result = container.update_with(b"1", |v| [v, b"+"].concat(), &mut journal, None);
+    ///     assert!(result.await.is_ok());
+    /// };
+    /// ```
+    #[inline]
+    pub async fn update_with<F: FnOnce(&[u8]) -> Vec<u8>>(
+        &self,
+        key: &[u8],
+        f: F,
+        journal: &mut Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        if journal.transaction().is_optimistic() {
+            // The read is validated when the transaction is committed.
+            let Some(current) = self.read(key, journal, deadline).await? else {
+                return Err(Error::NotFound);
+            };
+            let value = f(&current);
+            return self
+                .write_optimistically(key, Some(&value), true, journal, deadline)
+                .await;
+        }
+        let (record, current) = self.lock_record(key, journal, deadline).await?;
+        let Some(current) = current else {
+            return Err(Error::NotFound);
+        };
+        let value = f(&current.value);
+        self.replace_version(&record, key, &current, &value, journal, deadline)
+            .await
+    }
+
+    /// Deletes the key-value pair with the [`Journal`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the key does not exist, another transaction is modifying the key,
+    /// or the deadline was reached.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("container_delete")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let container = database
+    ///         .create_container("hello".to_string(), Metadata::default(), &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     assert!(container.insert(b"1", b"one", &mut journal, None).await.is_ok());
+    ///     assert!(container.delete(b"1", &mut journal, None).await.is_ok());
+    /// };
+    /// ```
+    #[inline]
+    pub async fn delete(
+        &self,
+        key: &[u8],
+        journal: &mut Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        if journal.transaction().is_optimistic() {
+            return self
+                .write_optimistically(key, None, true, journal, deadline)
+                .await;
+        }
+        let (_, current) = self.lock_record(key, journal, deadline).await?;
+        let Some(current) = current else {
+            return Err(Error::NotFound);
+        };
+        journal.reserve_memory(Self::change_memory_usage(key, Some(&current.value), None))?;
+        journal.delete(&[current.object_id], deadline).await?;
+        self.monitor(journal.database());
+        Self::record_change(
+            self.id,
+            Change {
+                container: self.name(),
+                key: key.into(),
+                old_value: Some(current.value.clone()),
+                new_value: None,
+            },
+            journal,
+        );
+        self.maintain_indexes(key, Some(&current.value), None, journal, deadline)
+            .await
+    }
+
+    /// Deletes every key-value pair visible to the [`Journal`].
+    ///
+    /// The [`Container`] is locked in [`LockMode::Exclusive`] mode, and a single truncation
+    /// marker is logged instead of deleting each key-value pair: snapshots that see the marker,
+    /// i.e., those taken after the transaction is committed, see an empty [`Container`], whereas
+    /// older snapshots still see the key-value pairs. The garbage collector reclaims the versions
+    /// once the marker becomes visible to every reader, and the next checkpoint leaves them out
+    /// of the persisted index, thereby freeing their database pages. Secondary indexes of the
+    /// [`Container`] are truncated along with it, and a single [`Change`] without a key or
+    /// values is published for the truncation.
+    ///
+    /// Optimistic transactions do not lock the [`Container`] until they are committed, therefore
+    /// they delete the key-value pairs one by one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the [`Container`] could not be locked, or the deadline was
+    /// reached.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("container_truncate")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let container = database
+    ///         .create_container("hello".to_string(), Metadata::default(), &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     assert!(container.insert(b"1", b"one", &mut journal, None).await.is_ok());
+    ///     assert!(container.insert(b"2", b"two", &mut journal, None).await.is_ok());
+    ///     assert!(container.truncate(&mut journal, None).await.is_ok());
+    ///     assert_eq!(container.read(b"1", &mut journal, None).await, Ok(None));
+    /// };
+    /// ```
+    #[inline]
+    pub async fn truncate(
+        &self,
+        journal: &mut Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        if journal.transaction().is_optimistic() {
+            self.load_records(Bound::Unbounded, Bound::Unbounded)
+                .await?;
+            let keys: Vec<Box<[u8]>> = self
+                .records
+                .iter(&ebr::Guard::new())
+                .map(|(k, _)| k.clone())
+                .collect();
+            for key in keys {
+                match self
+                    .write_optimistically(&key, None, true, journal, deadline)
+                    .await
+                {
+                    Ok(()) | Err(Error::NotFound) => (),
+                    Err(error) => return Err(error),
+                }
+            }
+            return Ok(());
+        }
+
+        self.lock(LockMode::Exclusive, journal, deadline).await?;
+        self.certify_truncate(journal)?;
+        journal.reserve_memory(size_of::<Change>())?;
+        let object_id = journal.database().new_object_id();
+        journal.create(&[object_id], deadline).await?;
+        journal.write(&VersionRecord::new(
+            self.id,
+            TRUNCATION_RECORD_ID,
+            object_id,
+            &[],
+            &[],
+        ))?;
+        self.truncations
+            .lock()
+            .map_err(|_| Error::UnexpectedState)?
+            .push((object_id, false));
+        self.monitor(journal.database());
+
+        // Key-value pairs are counted as if the transaction were committed.
+        let live_records = self.live_records.load(Relaxed);
+        journal.record_statistics_delta(StatisticsDelta {
+            container_id: self.id,
+            live_records: -i64::try_from(live_records).unwrap_or(i64::MAX),
+            dead_versions: live_records,
+        });
+        journal.record_change(Change {
+            container: self.name(),
+            key: Box::default(),
+            old_value: None,
+            new_value: None,
+        });
+
+        let indexes = self
+            .indexes
+            .lock()
+            .map(|i| i.clone())
+            .map_err(|_| Error::UnexpectedState)?;
+        for index in indexes.iter().filter(|i| !i.is_discarded()) {
+            Box::pin(index.index().truncate(journal, deadline)).await?;
+        }
+        Ok(())
+    }
+
+    /// Creates a secondary index of the [`Container`] in the `index` [`Container`] with the
+    /// [`Journal`].
+    ///
+    /// `extractor` derives the index key from each key-value pair, and key-value pairs for which
+    /// it returns `None` are not indexed; index keys do not have to be unique, and the index is
+    /// read through [`Container::lookup_index`]. Every subsequent change made to the
+    /// [`Container`] with a [`Journal`] changes the index with the same [`Journal`], therefore the
+    /// index is always consistent with the [`Container`] even if the change is rolled back.
+    ///
+    /// The [`Container`] is locked in [`LockMode::Shared`] mode until the transaction is ended
+    /// in order to index existing key-value pairs visible to the [`Journal`], and the index is
+    /// discarded if the [`Journal`] or the transaction is rolled back.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::WrongParameter`] if `index` is the [`Container`] itself, or an [`Error`]
+    /// if the [`Container`] could not be locked or existing key-value pairs could not be indexed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("container_create_index")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let users = database
+    ///         .create_container("users".to_string(), Metadata::default(), &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     let by_city = database
+    ///         .create_container("by_city".to_string(), Metadata::default(), &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     let city = |_: &[u8], v: &[u8]| v.split(|b| *b == b',').nth(1).map(<[u8]>::to_vec);
+    ///     assert!(users.create_index(by_city.clone(), city, &mut journal, None).await.is_ok());
+    ///     assert!(users.insert(b"1", b"alice,seoul", &mut journal, None).await.is_ok());
+    ///     journal.submit();
+    ///     assert!(transaction.commit().await.is_ok());
+    ///
+    ///     let snapshot = database.snapshot();
+    ///     let keys = by_city.lookup_index(b"seoul", &snapshot, None).await.unwrap();
+    ///     assert_eq!(keys, vec![b"1".to_vec().into_boxed_slice()]);
+    /// };
+    /// ```
+    #[inline]
+    pub async fn create_index<F: Fn(&[u8], &[u8]) -> Option<Vec<u8>> + Send + Sync + 'static>(
+        &self,
+        index: ebr::Shared<Container<S, P>>,
+        extractor: F,
+        journal: &mut Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        if index.as_ptr() == ptr::from_ref(self) {
+            return Err(Error::WrongParameter);
+        }
+        self.lock(LockMode::Shared, journal, deadline).await?;
+        let mut entry_keys = Vec::new();
+        {
+            let snapshot = Self::journal_view(journal);
+            let mut scanner = self.range::<&[u8], _>(.., &snapshot, deadline);
+            while let Some((key, value)) = scanner.next().await? {
+                if let Some(index_key) = extractor(&key, &value) {
+                    entry_keys.push(SecondaryIndex::<S, P>::entry_key(&index_key, &key));
+                }
+            }
+        }
+        for entry_key in entry_keys {
+            Box::pin(index.insert(&entry_key, &[], journal, deadline)).await?;
+        }
+        let secondary_index =
+            SecondaryIndex::new(index, Box::new(extractor), journal.anchor().clone());
+        let Ok(mut indexes) = self.indexes.lock() else {
+            return Err(Error::UnexpectedState);
+        };
+        indexes.retain(|i| !i.is_discarded());
+        indexes.push(Arc::new(secondary_index));
+        Ok(())
+    }
+
+    /// Returns the keys associated with the index key in the secondary index stored in the
+    /// [`Container`] that are visible to the [`Snapshot`].
+    ///
+    /// The [`Container`] must have been passed to [`Container::create_index`] as the index, and
+    /// the keys are returned in ascending order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the visibility of an index entry could not be determined until the
+    /// deadline was reached.
+    #[inline]
+    pub async fn lookup_index(
+        &self,
+        index_key: &[u8],
+        snapshot: &Snapshot<'_, '_, '_, S>,
+        deadline: Option<Instant>,
+    ) -> Result<Vec<Box<[u8]>>, Error> {
+        let mut keys = Vec::new();
+        let mut scanner = self.range(index_key.., snapshot, deadline);
+        while let Some((entry_key, _)) = scanner.next().await? {
+            if !entry_key.starts_with(index_key) {
+                break;
+            }
+            if let Some(key) = SecondaryIndex::<S, P>::key(&entry_key, index_key) {
+                keys.push(key.into());
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Returns the [`Metadata`] of the [`Container`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, IndexType, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("container_metadata")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let metadata = Metadata::default().with_index_type(IndexType::Hash);
+    ///     let container = database
+    ///         .create_container("hello".to_string(), metadata, &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     assert_eq!(container.metadata(), &metadata);
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Returns the [`ContainerStatistics`] of the [`Container`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("container_statistics")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let container = database
+    ///         .create_container("hello".to_string(), Metadata::default(), &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     assert!(container.insert(b"1", b"one", &mut journal, None).await.is_ok());
+    ///     journal.submit();
+    ///     assert_eq!(container.statistics().live_records, 1);
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn statistics(&self) -> ContainerStatistics {
+        ContainerStatistics {
+            live_records: self.live_records.load(Relaxed),
+            dead_versions: self.dead_versions.load(Relaxed),
+            allocated_pages: self.persisted_pages.load(Relaxed),
+        }
+    }
+
+    /// Applies the changes to the statistics made by a submitted [`Journal`].
+    pub(super) fn apply_statistics_delta(&self, delta: &StatisticsDelta) {
+        if delta.live_records >= 0 {
+            self.live_records
+                .fetch_add(delta.live_records.unsigned_abs(), Relaxed);
+        } else {
+            let decrement = delta.live_records.unsigned_abs();
+            let _: Result<u64, u64> = self
+                .live_records
+                .fetch_update(Relaxed, Relaxed, |l| Some(l.saturating_sub(decrement)));
+        }
+        self.dead_versions.fetch_add(delta.dead_versions, Relaxed);
+    }
+
+    /// Sets the number of database pages of the index of the [`Container`] persisted by a
+    /// checkpoint.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) fn set_persisted_pages(&self, num_pages: u64) {
+        self.persisted_pages.store(num_pages, Relaxed);
+    }
+
+    /// Changes the name of the [`Container`] when the transaction renaming it is committed.
+    pub(super) fn rename(&self, name: Arc<str>) {
+        if let Ok(mut current) = self.name.lock() {
+            *current = name;
+        }
+    }
+
+    /// Returns the name of the [`Container`].
+    fn name(&self) -> Arc<str> {
+        self.name
+            .lock()
+            .map_or_else(|_| Arc::from(""), |name| name.clone())
+    }
+
+    /// Creates a new data [`Container`].
+    #[must_use]
+    pub(super) fn new(
+        id: u64,
+        name: Arc<str>,
+        metadata: Metadata,
+        access_controller: Arc<AccessController<S>>,
+    ) -> Container<S, P> {
+        Container {
+            id,
+            name: Mutex::new(name),
+            metadata,
+            records: TreeIndex::default(),
+            persisted_index: OnceLock::new(),
+            unloaded_records: AtomicU64::new(0),
+            quarantined: OnceLock::new(),
+            lock_owners: Mutex::default(),
+            access_controller,
+            indexes: Mutex::default(),
+            range_readers: Mutex::default(),
+            monitored: AtomicBool::new(false),
+            live_records: AtomicU64::new(0),
+            dead_versions: AtomicU64::new(0),
+            persisted_pages: AtomicU64::new(0),
+            truncated: AtomicU64::new(0),
+            truncations: Mutex::default(),
+            _version: std::marker::PhantomData,
+        }
+    }
+
+    /// Replaces the current [`Version`] of the locked [`Record`] with a new [`Version`] with the
+    /// [`Journal`].
+    async fn replace_version(
+        &self,
+        record: &ebr::Shared<Record>,
+        key: &[u8],
+        current: &Version,
+        value: &[u8],
+        journal: &mut Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        journal.reserve_memory(Self::change_memory_usage(
+            key,
+            Some(&current.value),
+            Some(value),
+        ))?;
+        Self::push_version(
+            self.id,
+            record,
+            key,
+            value,
+            Some(current.object_id),
+            journal,
+            deadline,
+        )
+        .await?;
+        self.monitor(journal.database());
+        Self::record_change(
+            self.id,
+            Change {
+                container: self.name(),
+                key: key.into(),
+                old_value: Some(current.value.clone()),
+                new_value: Some(value.into()),
+            },
+            journal,
+        );
+        self.maintain_indexes(key, Some(&current.value), Some(value), journal, deadline)
+            .await
+    }
+
+    /// Reclaims versions of key-value pairs that are invisible to every reader, and removes
+    /// records that have no versions for `MVCC` garbage collection.
+    ///
+    /// `condition` returns `true` if an instant is visible to every current and future reader,
+    /// and `f` is invoked after each record is visited. Returns the number of reclaimed versions
+    /// and whether the [`Container`] needs to be visited again. It is a blocking and synchronous
+    /// method, therefore it must be invoked in the background.
+    pub(super) fn reclaim_versions_sync<C: Fn(&S::Instant) -> bool, F: FnMut()>(
+        &self,
+        condition: &C,
+        mut f: F,
+    ) -> (u64, bool) {
+        self.monitored.store(false, Release);
+        let (truncated, truncation, unloaded) = self.settle_truncations_sync(condition);
+        let mut num_reclaimed = 0;
+        let mut revisit = truncation != truncated;
+        let mut statistics = (0, 0);
+        let guard = ebr::Guard::new();
+        for (key, record) in self.records.iter(&guard) {
+            let (reclaimed, versioned) =
+                self.reclaim_record_sync(key, record, truncated, condition);
+            num_reclaimed += reclaimed;
+            revisit |= versioned;
+            let (live, dead) = self.record_statistics_sync(record, truncation);
+            statistics.0 += live;
+            statistics.1 += dead;
+            f();
+        }
+        self.live_records.store(statistics.0 + unloaded, Relaxed);
+        self.dead_versions.store(statistics.1, Relaxed);
+        (num_reclaimed, revisit)
+    }
+
+    /// Removes truncation markers that are visible to every current and future reader or were
+    /// rolled back.
+    ///
+    /// Returns the latest truncation marker visible to every reader, the latest truncation marker
+    /// that is yet to be rolled back, and the number of key-value pairs in the persisted index
+    /// that are not truncated by it.
+    fn settle_truncations_sync<C: Fn(&S::Instant) -> bool>(
+        &self,
+        condition: &C,
+    ) -> (u64, u64, u64) {
+        let Ok(mut truncations) = self.truncations.lock() else {
+            let truncated = self.truncated.load(Acquire);
+            return (truncated, truncated, self.unloaded_records.load(Relaxed));
+        };
+        truncations.retain(|(marker, played_back)| {
+            let mut rolled_back = false;
+            if !self
+                .access_controller
+                .try_remove_access_data_sync(*marker, condition, &mut |_| rolled_back = true)
+            {
+                return true;
+            } else if !rolled_back {
+                // Readers load `truncated` after copying the markers, therefore it is updated
+                // before the marker is removed from the list.
+                self.truncated.fetch_max(*marker, Release);
+                if !played_back {
+                    // Every key-value pair in the persisted index predates the marker.
+                    self.unloaded_records.store(0, Relaxed);
+                }
+            }
+            false
+        });
+        let truncated = self.truncated.load(Acquire);
+        let truncation = truncations
+            .last()
+            .map_or(truncated, |(m, _)| *m.max(&truncated));
+        let unloaded = if truncations.iter().any(|(_, played_back)| !played_back) {
+            0
+        } else {
+            self.unloaded_records.load(Relaxed)
+        };
+        (truncated, truncation, unloaded)
+    }
+
+    /// Returns the number of key-value pairs and the number of deleted versions of the
+    /// [`Record`].
+    ///
+    /// Versions owned by active transactions are counted as if the transactions were committed,
+    /// which is how they were counted when their [`Journal`] instances were submitted; versions
+    /// created before the `truncation` marker are counted as deleted.
+    fn record_statistics_sync(&self, record: &Record, truncation: u64) -> (u64, u64) {
+        let guard = ebr::Guard::new();
+        let (mut live, mut dead) = (0, 0);
+        let mut latest = true;
+        let mut current = record.head.load(Acquire, &guard).as_ref();
+        while let Some(version) = current {
+            if !version.reclaimed.load(Acquire) {
+                let state = self.access_controller.version_state_sync(version.object_id);
+                if latest
+                    && version.object_id >= truncation
+                    && !matches!(state, VersionState::Deleted(_))
+                {
+                    live += 1;
+                } else {
+                    dead += 1;
+                }
+                latest = false;
+            }
+            current = version.prev.load(Acquire, &guard).as_ref();
+        }
+        (live, dead)
+    }
+
+    /// Reclaims versions of the [`Record`] that are invisible to every reader, and removes the
+    /// [`Record`] if no versions or locks are left.
+    ///
+    /// Versions created before the `truncated` marker are invisible to every reader. Returns the
+    /// number of reclaimed versions, and whether the [`Record`] still has versions that may be
+    /// reclaimed later.
+    fn reclaim_record_sync<C: Fn(&S::Instant) -> bool>(
+        &self,
+        key: &[u8],
+        record: &ebr::Shared<Record>,
+        truncated: u64,
+        condition: &C,
+    ) -> (u64, bool) {
+        let guard = ebr::Guard::new();
+        let mut num_reclaimed = 0;
+        let mut versioned = false;
+        let mut link = &record.head;
+        let mut current = link.load(Acquire, &guard);
+        while let Some(version) = current.as_ref() {
+            if !self.access_controller.try_remove_access_data_sync(
+                version.object_id,
+                condition,
+                // The version is either deleted or has never been created.
+                &mut |_| version.reclaimed.store(true, Release),
+            ) {
+                versioned = true;
+            } else if version.object_id < truncated {
+                version.reclaimed.store(true, Release);
+            }
+            let next = version.prev.load(Acquire, &guard);
+            if version.reclaimed.load(Acquire) {
+                // Writers may have pushed a new version onto the head in the meantime.
+                if link
+                    .compare_exchange(
+                        current,
+                        (next.get_shared(), ebr::Tag::None),
+                        AcqRel,
+                        Acquire,
+                        &guard,
+                    )
+                    .is_ok()
+                {
+                    num_reclaimed += 1;
+                    current = next;
+                    continue;
+                }
+                versioned = true;
+            }
+            link = &version.prev;
+            current = next;
+        }
+
+        if !versioned && record.head.is_null(Acquire) {
+            let Ok(mut removed) = record.removed.lock() else {
+                return (num_reclaimed, true);
+            };
+            let oldest = self.access_controller.oldest_serializable();
+            let readers_obsolete = record.readers.lock().is_ok_and(|mut r| {
+                r.retain(|r| !r.is_obsolete(oldest));
+                r.is_empty()
+            });
+            // Writers do not lock records of a [`Container`] that they hold exclusively.
+            if !readers_obsolete
+                || !record.head.is_null(Acquire)
+                || self.exclusive_owner().is_some()
+            {
+                versioned = true;
+            } else if self
+                .persisted_index
+                .get()
+                .is_some_and(|i| i.may_contain_sync(key))
+            {
+                // The key is kept until a checkpoint removes it from the persisted index,
+                // otherwise the deleted version in the index would be read again.
+            } else if self.access_controller.try_remove_access_data_sync(
+                record.lock_id,
+                condition,
+                &mut |_| (),
+            ) {
+                // Writers that have locked the record will see the flag, and retry.
+                *removed = true;
+                self.records
+                    .remove_if(key, |r| r.as_ptr() == record.as_ptr());
+            } else {
+                versioned = true;
+            }
+        }
+        (num_reclaimed, versioned)
+    }
+
+    /// Requests the garbage collector to monitor the [`Container`] after a version was installed.
+    fn monitor(&self, database: &Database<S, P>) {
+        if !self.monitored.swap(true, AcqRel)
+            && !database
+                .task_processor()
+                .send_task(Task::MonitorContainer(self.id))
+        {
+            // The next writer will send the request again.
+            self.monitored.store(false, Release);
+        }
+    }
+
+    /// Requests the garbage collector to monitor the [`Container`] identified as the identifier.
+    fn monitor_by_id(container_id: u64, database: &Database<S, P>) {
+        if let Some(container) = database.container(container_id, &ebr::Guard::new()) {
+            container.monitor(database);
+        }
+    }
+
+    /// Changes the secondary indexes of the [`Container`] according to the change made to the
+    /// key-value pair with the [`Journal`].
+    ///
+    /// The [`Journal`] has to be rolled back if this fails, otherwise the secondary indexes may
+    /// not reflect the change.
+    async fn maintain_indexes(
+        &self,
+        key: &[u8],
+        old_value: Option<&[u8]>,
+        new_value: Option<&[u8]>,
+        journal: &mut Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        let indexes = self
+            .indexes
+            .lock()
+            .map(|i| i.clone())
+            .map_err(|_| Error::UnexpectedState)?;
+        for index in indexes.iter().filter(|i| !i.is_discarded()) {
+            let old_index_key = old_value.and_then(|v| index.index_key(key, v));
+            let new_index_key = new_value.and_then(|v| index.index_key(key, v));
+            if old_index_key == new_index_key {
+                continue;
+            }
+            if let Some(old_index_key) = old_index_key {
+                let entry_key = SecondaryIndex::<S, P>::entry_key(&old_index_key, key);
+                Box::pin(index.index().delete(&entry_key, journal, deadline)).await?;
+            }
+            if let Some(new_index_key) = new_index_key {
+                let entry_key = SecondaryIndex::<S, P>::entry_key(&new_index_key, key);
+                Box::pin(index.index().insert(&entry_key, &[], journal, deadline)).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Records a change made by the [`Journal`] unless the [`Container`] is the catalog.
+    fn record_change(container_id: u64, change: Change, journal: &mut Journal<'_, '_, S, P>) {
+        if container_id != CATALOG_ID {
+            journal.record_statistics_delta(StatisticsDelta::new(container_id, &change));
+            journal.record_change(change);
+        }
+    }
+
+    /// Locks the [`Record`] associated with the key, and returns the [`Record`] and its latest
+    /// [`Version`] that is visible to the [`Journal`].
+    async fn lock_record(
+        &self,
+        key: &[u8],
+        journal: &mut Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<(ebr::Shared<Record>, Option<ebr::Shared<Version>>), Error> {
+        self.lock(LockMode::IntentionExclusive, journal, deadline)
+            .await?;
+        let record = loop {
+            let record = self.record(key, journal).await?;
+
+            // Other writers are blocked until the transaction is ended, therefore the latest
+            // visible version does not change afterwards.
+            if self.exclusive_owner() != Some(journal.anchor().transaction_id()) {
+                self.access_controller
+                    .lock(record.lock_id, journal, deadline)
+                    .await?;
+                self.escalate_record_locks(record.lock_id, journal).await;
+            }
+            if !record.is_removed() {
+                break record;
+            }
+        };
+        Self::certify_write(&record, journal)?;
+        self.certify_range_write(key, journal)?;
+        let snapshot = Self::journal_view(journal);
+        let current = Self::visible_version(
+            &self.access_controller,
+            &record,
+            &snapshot,
+            self.truncation(&snapshot, deadline).await?,
+            deadline,
+        )
+        .await?;
+        Ok((record, current))
+    }
+
+    /// Tracks the record lock acquired by the [`Journal`], and escalates the record locks held by
+    /// the [`Journal`] to a [`LockMode::Exclusive`] lock on the [`Container`] if there are too
+    /// many of them.
+    async fn escalate_record_locks(&self, lock_id: u64, journal: &mut Journal<'_, '_, S, P>) {
+        let Some(threshold) = journal.database().lock_escalation_threshold() else {
+            return;
+        };
+        if journal.track_record_lock(self.id, lock_id) <= threshold
+            || self.lock(LockMode::Exclusive, journal, None).await.is_err()
+        {
+            return;
+        }
+        for lock_id in journal.take_record_locks(self.id) {
+            self.access_controller
+                .release(lock_id, journal.anchor())
+                .await;
+        }
+        journal.database().telemetry().add_tagged(
+            Counter::LockEscalations,
+            1,
+            journal.anchor().transaction_anchor().tag(),
+        );
+    }
+
+    /// Returns the identifier of the transaction holding the [`Container`] in
+    /// [`LockMode::Exclusive`] mode.
+    fn exclusive_owner(&self) -> Option<TransactionID> {
+        self.lock_owners.lock().ok().and_then(|owners| {
+            owners.iter().find_map(|(o, m)| {
+                (*m == LockMode::Exclusive && !o.is_terminated()).then(|| o.transaction_id())
+            })
+        })
+    }
+
+    /// Locks the [`Container`] in the specified [`LockMode`] with the [`Journal`].
+    ///
+    /// The lock is released when the transaction is ended or the [`Journal`] is rolled back. A
+    /// transaction holding the [`Container`] in a stronger mode does not need to lock it again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the [`Container`] could not be locked until the deadline was
+    /// reached, or [`Error::SerializationFailure`] if no deadline was specified and another
+    /// transaction holds an incompatible lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Error, LockMode, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("container_lock")).await.unwrap();
+    ///     let transaction_1 = database.transaction();
+    ///     let transaction_2 = database.transaction();
+    ///     let mut journal_1 = transaction_1.journal();
+    ///     let mut journal_2 = transaction_2.journal();
+    ///     let container = database
+    ///         .create_container("hello".to_string(), Metadata::default(), &mut journal_1, None)
+    ///         .await
+    ///         .unwrap();
+    ///     assert!(container.lock(LockMode::Shared, &mut journal_1, None).await.is_ok());
+    ///     assert_eq!(
+    ///         container.insert(b"1", b"one", &mut journal_2, None).await,
+    ///         Err(Error::SerializationFailure)
+    ///     );
+    /// };
+    /// ```
+    #[inline]
+    pub async fn lock(
+        &self,
+        mode: LockMode,
+        journal: &mut Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        let transaction_id = journal.anchor().transaction_id();
+        loop {
+            let conflict = {
+                let Ok(mut owners) = self.lock_owners.lock() else {
+                    return Err(Error::UnexpectedState);
+                };
+                owners.retain(|(o, _)| !o.is_terminated());
+                let conflict = owners.iter().find_map(|(o, m)| {
+                    (o.transaction_id() != transaction_id && !m.is_compatible(mode))
+                        .then(|| o.clone())
+                });
+                if conflict.is_none() {
+                    if !owners
+                        .iter()
+                        .any(|(o, m)| o.transaction_id() == transaction_id && m.covers(mode))
+                    {
+                        owners.push((journal.anchor().clone(), mode));
+                    }
+                    return Ok(());
+                }
+                conflict
+            };
+            let (Some(owner), Some(deadline)) = (conflict, deadline) else {
+                return Err(Error::SerializationFailure);
+            };
+
+            // The owner may release the lock without ending the transaction by rolling back the
+            // journal, therefore the owner is checked at least every `LOCK_RECHECK_INTERVAL`.
+            let recheck = deadline.min(Instant::now() + LOCK_RECHECK_INTERVAL);
+            let await_eot = owner.await_eot(journal.task_processor(), recheck);
+            let cancellation_token = journal.anchor().transaction_anchor().cancellation_token();
+            match Cancellable::new(await_eot, cancellation_token).await {
+                Err(Error::Timeout) if recheck < deadline => (),
+                result => result?,
+            }
+        }
+    }
+
+    /// Installs a [`Version`] written to the log while the database is being recovered.
+    ///
+    /// The [`Version`] is not installed again if it is already the latest one, and the garbage
+    /// collector is requested to recompute the statistics of the [`Container`]. A truncation
+    /// marker is added to the [`Container`] instead of being installed as a [`Version`].
+    pub(super) fn playback_version(&self, version: &VersionRecord<'_>, database: &Database<S, P>) {
+        if version.record_id() == TRUNCATION_RECORD_ID {
+            if let Ok(mut truncations) = self.truncations.lock() {
+                if !truncations.iter().any(|(m, _)| *m == version.object_id()) {
+                    truncations.push((version.object_id(), true));
+                    truncations.sort_unstable();
+                }
+            }
+            self.monitor(database);
+            return;
+        }
+        self.playback_record(version, |record| {
+            let guard = ebr::Guard::new();
+            let prev = record.head.get_shared(Acquire, &guard);
+            if prev
+                .as_ref()
+                .is_some_and(|v| v.object_id == version.object_id())
+            {
+                return;
+            }
+            let new_version = ebr::Shared::new(Version {
+                object_id: version.object_id(),
+                value: version.value().into(),
+                prev: prev.map_or_else(AtomicShared::null, AtomicShared::from),
+                reclaimed: AtomicBool::new(false),
+                creator: None,
+            });
+            record
+                .head
+                .swap((Some(new_version), ebr::Tag::None), Release);
+        });
+        self.monitor(database);
+    }
+
+    /// Sets the index of the versions persisted by a checkpoint while the database is being
+    /// recovered.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) fn playback_persisted_index(&self, index: P::PersistedIndex) {
+        let _: Result<(), P::PersistedIndex> = self.persisted_index.set(index);
+    }
+
+    /// Quarantines the [`Container`] while the database is being recovered after a page of its
+    /// persisted index was found corrupt.
+    ///
+    /// Accesses to key-value pairs of the [`Container`] fail with the error from then on, whereas
+    /// the other containers stay available.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) fn playback_quarantine(&self, error: Error) {
+        let _: Result<(), Error> = self.quarantined.set(error);
+    }
+
+    /// Returns the latest truncation marker played back from the log that was not rolled back.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn playback_truncation(&self) -> u64 {
+        let Ok(truncations) = self.truncations.lock() else {
+            return 0;
+        };
+        truncations
+            .iter()
+            .rev()
+            .find(
+                |(marker, _)| match self.access_controller.version_state_sync(*marker) {
+                    VersionState::Owned(owners) => !owners
+                        .iter()
+                        .all(|(_, s)| matches!(s, TransactionState::RolledBack)),
+                    VersionState::Deleted(_) => false,
+                    VersionState::Created(_) | VersionState::Untracked => true,
+                },
+            )
+            .map_or(0, |(marker, _)| *marker)
+    }
+
+    /// Installs a [`Version`] persisted by a checkpoint while the database is being recovered.
+    ///
+    /// The log is replayed before persisted versions are installed, therefore the [`Version`] is
+    /// installed as the oldest one unless it has been replayed from the log. If the persisted
+    /// index has been set, only versions of keys replayed from the log are installed, and the
+    /// others are left in the index.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) fn playback_persisted_version(
+        &self,
+        version: &VersionRecord<'_>,
+        database: &Database<S, P>,
+    ) {
+        if self.persisted_index.get().is_some() && !self.records.contains(version.key()) {
+            if version.object_id() >= self.playback_truncation() {
+                self.unloaded_records.fetch_add(1, Relaxed);
+            }
+            self.monitor(database);
+            return;
+        }
+        self.playback_record(version, |record| {
+            let guard = ebr::Guard::new();
+            let mut oldest = None;
+            let mut current = record.head.get_shared(Acquire, &guard);
+            while let Some(v) = current {
+                if v.object_id == version.object_id() {
+                    return;
+                }
+                current = v.prev.get_shared(Acquire, &guard);
+                oldest.replace(v);
+            }
+            let new_version = ebr::Shared::new(Version {
+                object_id: version.object_id(),
+                value: version.value().into(),
+                prev: AtomicShared::null(),
+                reclaimed: AtomicBool::new(false),
+                creator: None,
+            });
+            if let Some(oldest) = oldest {
+                oldest
+                    .prev
+                    .swap((Some(new_version), ebr::Tag::None), Release);
+            } else {
+                record
+                    .head
+                    .swap((Some(new_version), ebr::Tag::None), Release);
+            }
+        });
+        self.monitor(database);
+    }
+
+    /// Passes the [`Record`] of the key of the version to `install` while the database is being
+    /// recovered, and inserts a new [`Record`] if the key does not exist.
+    ///
+    /// The [`Record`] is locked against the garbage collector while `install` is invoked, since it
+    /// may otherwise remove the [`Record`] before a [`Version`] is installed.
+    fn playback_record<F: FnOnce(&Record)>(&self, version: &VersionRecord<'_>, install: F) {
+        loop {
+            let record = self
+                .records
+                .peek_with(version.key(), |_, r| r.clone())
+                .or_else(|| {
+                    let record = ebr::Shared::new(Record::new(version.record_id()));
+                    self.records
+                        .insert(version.key().into(), record.clone())
+                        .ok()
+                        .map(|()| record)
+                });
+            let Some(record) = record else {
+                continue;
+            };
+            let Ok(removed) = record.removed.lock() else {
+                return;
+            };
+            if !*removed {
+                install(&record);
+                return;
+            }
+        }
+    }
+
+    /// Passes the latest [`Version`] of each key that is visible to the [`Snapshot`] to
+    /// `visitor` in ascending key order.
+    ///
+    /// It is used to persist the key-value pairs in database pages; once passed, the versions can
+    /// be installed again by [`playback_version`](Self::playback_version).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) async fn scan_visible_versions<F: FnMut(&VersionRecord<'_>)>(
+        &self,
+        snapshot: &Snapshot<'_, '_, '_, S>,
+        deadline: Option<Instant>,
+        mut visitor: F,
+    ) -> Result<(), Error> {
+        let truncation = self.truncation(snapshot, deadline).await?;
+        let mut start = Bound::Unbounded;
+        let mut candidates = VecDeque::new();
+        loop {
+            let more = self
+                .read_candidates(
+                    &mut start,
+                    &Bound::Unbounded,
+                    BULK_LOAD_BATCH_SIZE,
+                    0,
+                    &mut candidates,
+                )
+                .await?;
+            for candidate in candidates.drain(..) {
+                match candidate {
+                    Candidate::Loaded(key, record) => {
+                        if let Some(version) = Self::visible_version(
+                            &self.access_controller,
+                            &record,
+                            snapshot,
+                            truncation,
+                            deadline,
+                        )
+                        .await?
+                        {
+                            visitor(&VersionRecord::new(
+                                self.id,
+                                record.lock_id,
+                                version.object_id,
+                                &key,
+                                &version.value,
+                            ));
+                        }
+                    }
+                    Candidate::Persisted {
+                        record_id,
+                        object_id,
+                        key,
+                        value,
+                    } => {
+                        if object_id >= truncation
+                            && self
+                                .access_controller
+                                .read(object_id, snapshot, deadline)
+                                .await?
+                        {
+                            visitor(&VersionRecord::new(
+                                self.id, record_id, object_id, &key, &value,
+                            ));
+                        }
+                    }
+                }
+            }
+            if !more {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Reads the next keys in the range from memory and from the persisted index into
+    /// `candidates` in ascending key order, and returns `false` if no more keys are in the range.
+    ///
+    /// Up to `limit` keys are read from each of them, and keys are passed up to the smaller of the
+    /// last keys of those that may have more keys; `start` is then moved past the last key passed.
+    /// Keys in memory take precedence over those in the persisted index, and `readahead` pages of
+    /// the persisted index are read ahead.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the persisted index could not be read.
+    async fn read_candidates(
+        &self,
+        start: &mut Bound<Box<[u8]>>,
+        end: &Bound<Box<[u8]>>,
+        limit: usize,
+        readahead: usize,
+        candidates: &mut VecDeque<Candidate>,
+    ) -> Result<bool, Error> {
+        self.check_quarantine()?;
+        let mut persisted = Vec::new();
+        let mut num_persisted = 0;
+        let mut last_persisted = None;
+        if let Some(index) = self.persisted_index.get() {
+            index
+                .scan(
+                    start.as_ref().map(AsRef::as_ref),
+                    end.as_ref().map(AsRef::as_ref),
+                    limit,
+                    readahead,
+                    |v| {
+                        // Keys are loaded into memory while the index is being read, therefore
+                        // a key not in memory now is read from memory below if loaded later.
+                        if !self.records.contains(v.key()) {
+                            persisted.push(Candidate::Persisted {
+                                record_id: v.record_id(),
+                                object_id: v.object_id(),
+                                key: v.key().into(),
+                                value: v.value().into(),
+                            });
+                        }
+                        num_persisted += 1;
+                        last_persisted = Some(Box::<[u8]>::from(v.key()));
+                    },
+                )
+                .await?;
+        }
+        let loaded: Vec<Candidate> = self
+            .records
+            .range::<[u8], _>(
+                (
+                    start.as_ref().map(AsRef::as_ref),
+                    end.as_ref().map(AsRef::as_ref),
+                ),
+                &ebr::Guard::new(),
+            )
+            .take(limit)
+            .map(|(k, r)| Candidate::Loaded(k.clone(), r.clone()))
+            .collect();
+
+        let mut last_key = last_persisted.filter(|_| num_persisted == limit);
+        if loaded.len() == limit {
+            if let Some(Candidate::Loaded(key, _)) = loaded.last() {
+                if last_key.as_ref().is_none_or(|k| key < k) {
+                    last_key = Some(key.clone());
+                }
+            }
+        }
+        let passed = |key: &[u8]| last_key.as_deref().is_none_or(|k| key <= k);
+        let mut persisted = persisted.into_iter().peekable();
+        for candidate in loaded {
+            if !passed(candidate.key()) {
+                break;
+            }
+            while let Some(p) = persisted.next_if(|p| p.key() < candidate.key()) {
+                candidates.push_back(p);
+            }
+            let _: Option<Candidate> = persisted.next_if(|p| p.key() == candidate.key());
+            candidates.push_back(candidate);
+        }
+        candidates.extend(persisted.take_while(|p| passed(p.key())));
+        if let Some(key) = last_key {
+            *start = Bound::Excluded(key);
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Returns the [`Record`] associated with the key, or inserts a new one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the persisted index could not be read.
+    async fn record(
+        &self,
+        key: &[u8],
+        journal: &Journal<'_, '_, S, P>,
+    ) -> Result<ebr::Shared<Record>, Error> {
+        loop {
+            if let Some(record) = self.load_record(key).await? {
+                return Ok(record);
+            }
+            let record = ebr::Shared::new(Record::new(journal.database().new_object_id()));
+            if self
+                .records
+                .insert_async(key.into(), record.clone())
+                .await
+                .is_ok()
+            {
+                return Ok(record);
+            }
+        }
+    }
+
+    /// Returns the [`Record`] associated with the key after loading the key from the persisted
+    /// index if the key is not in memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the persisted index could not be read.
+    async fn load_record(&self, key: &[u8]) -> Result<Option<ebr::Shared<Record>>, Error> {
+        self.check_quarantine()?;
+        if let Some(record) = self.records.peek_with(key, |_, r| r.clone()) {
+            return Ok(Some(record));
+        }
+        if let Some(index) = self.persisted_index.get() {
+            index
+                .scan(Bound::Included(key), Bound::Included(key), 1, 0, |v| {
+                    self.load_version(v);
+                })
+                .await?;
+        }
+        Ok(self.records.peek_with(key, |_, r| r.clone()))
+    }
+
+    /// Loads the key-value pairs in the range from the persisted index into memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the persisted index could not be read.
+    async fn load_records(&self, start: Bound<&[u8]>, end: Bound<&[u8]>) -> Result<(), Error> {
+        self.check_quarantine()?;
+        let Some(index) = self.persisted_index.get() else {
+            return Ok(());
+        };
+        let mut start = start.map(Box::<[u8]>::from);
+        loop {
+            let mut num_loaded = 0;
+            let mut last_key = None;
+            index
+                .scan(
+                    start.as_ref().map(AsRef::as_ref),
+                    end,
+                    BULK_LOAD_BATCH_SIZE,
+                    0,
+                    |v| {
+                        self.load_version(v);
+                        num_loaded += 1;
+                        last_key = Some(Box::<[u8]>::from(v.key()));
+                    },
+                )
+                .await?;
+            match last_key {
+                Some(key) if num_loaded == BULK_LOAD_BATCH_SIZE => start = Bound::Excluded(key),
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Returns the error that the [`Container`] was quarantined with.
+    fn check_quarantine(&self) -> Result<(), Error> {
+        self.quarantined
+            .get()
+            .map_or(Ok(()), |error| Err(error.clone()))
+    }
+
+    /// Installs a [`Version`] read from the persisted index unless the key is in memory.
+    fn load_version(&self, version: &VersionRecord<'_>) {
+        if self.records.contains(version.key()) {
+            return;
+        }
+        let record = ebr::Shared::new(Record::new(version.record_id()));
+        record.head.swap(
+            (
+                Some(ebr::Shared::new(Version {
+                    object_id: version.object_id(),
+                    value: version.value().into(),
+                    prev: AtomicShared::null(),
+                    reclaimed: AtomicBool::new(false),
+                    creator: None,
+                })),
+                ebr::Tag::None,
+            ),
+            Release,
+        );
+        if self.records.insert(version.key().into(), record).is_ok() {
+            let _: Result<u64, u64> = self
+                .unloaded_records
+                .fetch_update(Relaxed, Relaxed, |n| Some(n.saturating_sub(1)));
+        }
+    }
+
+    /// Returns a [`Snapshot`] that sees the latest changes and those made by the [`Journal`] and
+    /// its transaction.
+    fn journal_view<'j>(journal: &'j Journal<'_, '_, S, P>) -> Snapshot<'j, 'j, 'j, S> {
+        let transaction = journal.transaction();
+        journal
+            .snapshot()
+            .combine(transaction.snapshot())
+            .combine(transaction.database().snapshot())
+    }
+
+    /// Returns whether the optimistic transaction has accessed the [`Record`], the [`Version`]
+    /// that the transaction observed first, and the value that the transaction sees.
+    async fn optimistic_view(
+        &self,
+        record: &ebr::Shared<Record>,
+        journal: &Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<(bool, Option<ebr::Shared<Version>>, Option<Box<[u8]>>), Error> {
+        let buffered = journal.find_optimistic_access(|a| {
+            (a.record.as_ptr() == record.as_ptr()).then(|| (a.observed.clone(), a.value.clone()))
+        });
+        if let Some((observed, current)) = buffered {
+            return Ok((true, observed, current));
+        }
+        let snapshot = Self::journal_view(journal);
+        let observed = Self::visible_version(
+            &self.access_controller,
+            record,
+            &snapshot,
+            self.truncation(&snapshot, deadline).await?,
+            deadline,
+        )
+        .await?;
+        let current = observed.as_ref().map(|v| v.value.clone());
+        Ok((false, observed, current))
+    }
+
+    /// Buffers a write to the key-value pair made by an optimistic transaction.
+    ///
+    /// `exists` denotes whether the key is expected to exist.
+    async fn write_optimistically(
+        &self,
+        key: &[u8],
+        value: Option<&[u8]>,
+        exists: bool,
+        journal: &mut Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        // Optimistic transactions never wait for other transactions.
+        self.lock(LockMode::IntentionExclusive, journal, None)
+            .await?;
+        let record = self.record(key, journal).await?;
+        let (_, observed, current) = self.optimistic_view(&record, journal, deadline).await?;
+        if exists && current.is_none() {
+            return Err(Error::NotFound);
+        } else if !exists && current.is_some() {
+            return Err(Error::UniquenessViolation);
+        }
+        journal.reserve_memory(
+            size_of::<OptimisticAccess>() + key.len() + value.map_or(0, <[u8]>::len),
+        )?;
+        journal.push_optimistic_access(OptimisticAccess {
+            container_id: self.id,
+            container: self.name(),
+            key: key.into(),
+            record,
+            observed,
+            value: value.map(Into::into),
+            write: true,
+        });
+        self.maintain_indexes(key, current.as_deref(), value, journal, deadline)
+            .await
+    }
+
+    /// Validates the accesses made by an optimistic transaction, and installs its writes with the
+    /// [`Journal`].
+    ///
+    /// The [`Record`] accessed by the transaction are locked without waiting, and the latest
+    /// version of each of them must be the one that the transaction observed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Conflict`] if any of the [`Record`] could not be locked or was modified
+    /// by other transactions.
+    pub(super) async fn install_optimistic_accesses<
+        'a,
+        I: IntoIterator<Item = &'a OptimisticAccess>,
+    >(
+        accesses: I,
+        journal: &mut Journal<'_, '_, S, P>,
+    ) -> Result<(), Error> {
+        // The last access to each record determines the value to install.
+        let mut latest: Vec<&OptimisticAccess> = Vec::new();
+        for access in accesses {
+            if let Some(l) = latest
+                .iter_mut()
+                .find(|l| l.record.as_ptr() == access.record.as_ptr())
+            {
+                if access.write || !l.write {
+                    *l = access;
+                }
+            } else {
+                latest.push(access);
+            }
+        }
+        latest.sort_by_key(|a| a.record.lock_id);
+
+        let access_controller = journal.database().access_controller();
+        for access in latest {
+            let record = Self::lock_accessed_record(access, journal).await?;
+            if access.write {
+                Self::certify_write(&record, journal)?;
+                if let Some(container) = journal
+                    .database()
+                    .container(access.container_id, &ebr::Guard::new())
+                {
+                    container.certify_range_write(&access.key, journal)?;
+                }
+            }
+            let container = journal.database().shared_container(access.container_id);
+            let snapshot = Self::journal_view(journal);
+            let Ok(current) = async {
+                let truncation = match container.as_ref() {
+                    Some(container) => container.truncation(&snapshot, None).await?,
+                    None => 0,
+                };
+                Self::visible_version(access_controller, &record, &snapshot, truncation, None).await
+            }
+            .await
+            else {
+                drop(snapshot);
+                return Err(Self::conflict(record.lock_id, journal).await);
+            };
+            drop(snapshot);
+            if current.as_ref().map(|v| v.object_id)
+                != access.observed.as_ref().map(|v| v.object_id)
+            {
+                // Another transaction has modified the key-value pair.
+                let object_id = current
+                    .as_ref()
+                    .or(access.observed.as_ref())
+                    .map_or(record.lock_id, |v| v.object_id);
+                return Err(Self::conflict(object_id, journal).await);
+            }
+            if !access.write {
+                continue;
+            }
+            match (access.value.as_ref(), current) {
+                (Some(value), current) => {
+                    Self::push_version(
+                        access.container_id,
+                        &record,
+                        &access.key,
+                        value,
+                        current.as_ref().map(|v| v.object_id),
+                        journal,
+                        None,
+                    )
+                    .await?;
+                    Self::monitor_by_id(access.container_id, journal.database());
+                    Self::record_change(
+                        access.container_id,
+                        Change {
+                            container: access.container.clone(),
+                            key: access.key.clone(),
+                            old_value: current.map(|v| v.value.clone()),
+                            new_value: Some(value.clone()),
+                        },
+                        journal,
+                    );
+                }
+                (None, Some(current)) => {
+                    journal.delete(&[current.object_id], None).await?;
+                    Self::monitor_by_id(access.container_id, journal.database());
+                    Self::record_change(
+                        access.container_id,
+                        Change {
+                            container: access.container.clone(),
+                            key: access.key.clone(),
+                            old_value: Some(current.value.clone()),
+                            new_value: None,
+                        },
+                        journal,
+                    );
+                }
+                (None, None) => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Locks the [`Record`] accessed by an optimistic transaction without waiting, and returns
+    /// the locked [`Record`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Conflict`] if the [`Record`] could not be locked.
+    async fn lock_accessed_record(
+        access: &OptimisticAccess,
+        journal: &mut Journal<'_, '_, S, P>,
+    ) -> Result<ebr::Shared<Record>, Error> {
+        let access_controller = journal.database().access_controller();
+        let mut record = access.record.clone();
+        loop {
+            let locked = if access.write {
+                access_controller.lock(record.lock_id, journal, None).await
+            } else {
+                access_controller.share(record.lock_id, journal, None).await
+            };
+            if locked.is_err() {
+                return Err(Self::conflict(record.lock_id, journal).await);
+            } else if !record.is_removed() {
+                return Ok(record);
+            }
+
+            // The record may have been replaced with a new one after being garbage collected,
+            // which is only a conflict if the transaction observed a version of it.
+            let replacement = access
+                .observed
+                .is_none()
+                .then(|| Self::replacement_record(access.container_id, &access.key, journal));
+            let Some(Some(replacement)) = replacement else {
+                return Err(Self::conflict(record.lock_id, journal).await);
+            };
+            record = replacement;
+        }
+    }
+
+    /// Returns an [`Error::Conflict`] with the transaction other than that of the [`Journal`]
+    /// owning the database object.
+    async fn conflict(object_id: u64, journal: &Journal<'_, '_, S, P>) -> Error {
+        let transaction_id = journal.transaction().id();
+        let owner = journal
+            .database()
+            .access_controller()
+            .owners(object_id)
+            .await
+            .into_iter()
+            .map(|(id, _)| id)
+            .find(|id| *id != transaction_id);
+        Error::conflict(object_id, owner)
+    }
+
+    /// Returns the [`Record`] associated with the key in the [`Container`] identified as the
+    /// identifier, creating one if none is associated.
+    fn replacement_record(
+        container_id: u64,
+        key: &[u8],
+        journal: &Journal<'_, '_, S, P>,
+    ) -> Option<ebr::Shared<Record>> {
+        let guard = ebr::Guard::new();
+        let container = journal.database().container(container_id, &guard)?;
+        let record = ebr::Shared::new(Record::new(journal.database().new_object_id()));
+        let _: Result<(), _> = container.records.insert(key.into(), record);
+        container.records.peek_with(key, |_, r| r.clone())
+    }
+
+    /// Records read-write dependencies from the serializable transaction that has read the
+    /// [`Version`] of the [`Record`] to the creators of newer versions.
+    fn certify_read(
+        record: &Record,
+        visible: Option<&ebr::Shared<Version>>,
+        journal: &Journal<'_, '_, S, P>,
+    ) -> Result<(), Error> {
+        let Some(reader) = journal.transaction().serialization_anchor() else {
+            return Ok(());
+        };
+        let guard = ebr::Guard::new();
+        let mut current = record.head.load(Acquire, &guard).as_ref();
+        while let Some(version) = current {
+            if visible.is_some_and(|v| ptr::eq(v.as_ptr(), version)) {
+                break;
+            }
+            if let Some(creator) = version.creator.as_ref() {
+                SerializationAnchor::depend(reader, creator, true)?;
+            }
+            current = version.prev.load(Acquire, &guard).as_ref();
+        }
+
+        // Writers of the record will check the dependency from the reader.
+        let oldest = journal.database().access_controller().oldest_serializable();
+        if let Ok(mut readers) = record.readers.lock() {
+            readers.retain(|r| !r.is_obsolete(oldest));
+            if !readers.iter().any(|r| r.as_ptr() == reader.as_ptr()) {
+                readers.push(reader.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Records read-write dependencies from serializable transactions that have read the
+    /// [`Record`] to the serializable transaction writing it.
+    fn certify_write(record: &Record, journal: &Journal<'_, '_, S, P>) -> Result<(), Error> {
+        let Some(writer) = journal.transaction().serialization_anchor() else {
+            return Ok(());
+        };
+        let oldest = journal.database().access_controller().oldest_serializable();
+        if let Ok(mut readers) = record.readers.lock() {
+            readers.retain(|r| !r.is_obsolete(oldest));
+            for reader in readers.iter() {
+                SerializationAnchor::depend(reader, writer, false)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Records read-write dependencies from serializable transactions that have read any key or
+    /// range of keys of the [`Container`] to the serializable transaction truncating it.
+    fn certify_truncate(&self, journal: &Journal<'_, '_, S, P>) -> Result<(), Error> {
+        let Some(writer) = journal.transaction().serialization_anchor() else {
+            return Ok(());
+        };
+        let oldest = journal.database().access_controller().oldest_serializable();
+        if let Ok(mut range_readers) = self.range_readers.lock() {
+            range_readers.retain(|(_, r)| !r.is_obsolete(oldest));
+            for (_, reader) in range_readers.iter() {
+                SerializationAnchor::depend(reader, writer, false)?;
+            }
+        }
+
+        // Readers of a key always load its record into memory.
+        for (_, record) in self.records.iter(&ebr::Guard::new()) {
+            Self::certify_write(record, journal)?;
+        }
+        Ok(())
+    }
+
+    /// Records read-write dependencies from serializable transactions that have read a range of
+    /// keys containing the key to the serializable transaction writing it.
+    fn certify_range_write(
+        &self,
+        key: &[u8],
+        journal: &Journal<'_, '_, S, P>,
+    ) -> Result<(), Error> {
+        let Some(writer) = journal.transaction().serialization_anchor() else {
+            return Ok(());
+        };
+        let oldest = journal.database().access_controller().oldest_serializable();
+        if let Ok(mut range_readers) = self.range_readers.lock() {
+            range_readers.retain(|(_, r)| !r.is_obsolete(oldest));
+            for (range_read, reader) in range_readers.iter() {
+                if range_read.contains(key) {
+                    SerializationAnchor::depend(reader, writer, false)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the memory retained by a change to a key-value pair until the transaction is
+    /// ended.
+    ///
+    /// The new value is retained by both the [`Change`] and the new [`Version`].
+    fn change_memory_usage(
+        key: &[u8],
+        old_value: Option<&[u8]>,
+        new_value: Option<&[u8]>,
+    ) -> usize {
+        size_of::<Change>()
+            + key.len()
+            + old_value.map_or(0, <[u8]>::len)
+            + new_value.map_or(0, |v| size_of::<Version>() + 2 * v.len())
+    }
+
+    /// Pushes a new [`Version`] to the [`Record`] by deleting the current version.
+    async fn push_version(
+        container_id: u64,
+        record: &ebr::Shared<Record>,
+        key: &[u8],
+        value: &[u8],
+        current: Option<u64>,
+        journal: &mut Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        Self::throttle_writer(container_id, record, journal, deadline).await?;
+        if let Some(current) = current {
+            journal.delete(&[current], deadline).await?;
+        }
+        let object_id = journal.database().new_object_id();
+        journal.create(&[object_id], deadline).await?;
+        journal.write(&VersionRecord::new(
+            container_id,
+            record.lock_id,
+            object_id,
+            key,
+            value,
+        ))?;
+        Self::install_version(record, value, object_id, journal);
+        Ok(())
+    }
+
+    /// Waits until the version chain of the [`Record`] is shorter than the limit set through
+    /// [`Database::set_max_version_chain_length`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::VersionChainTooLong`] if the garbage collector could not shorten the
+    /// version chain until the deadline was reached or no deadline was specified.
+    async fn throttle_writer(
+        container_id: u64,
+        record: &Record,
+        journal: &Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        let database = journal.database();
+        let Some(max_length) = database.max_version_chain_length() else {
+            return Ok(());
+        };
+        while record.version_chain_length() >= max_length {
+            let Some(deadline) = deadline.filter(|d| Instant::now() < *d) else {
+                return Err(Error::VersionChainTooLong);
+            };
+
+            // Make sure that the garbage collector visits the container before rechecking.
+            Self::monitor_by_id(container_id, database);
+            let recheck = deadline.min(Instant::now() + VERSION_CHAIN_RECHECK_INTERVAL);
+            let await_recheck = poll_fn(|cx| {
+                if recheck <= Instant::now() {
+                    return Poll::Ready(Ok(()));
+                } else if !database
+                    .task_processor()
+                    .send_task(Task::WakeUp(recheck, cx.waker().clone()))
+                {
+                    // The message channel is congested.
+                    cx.waker().wake_by_ref();
+                }
+                Poll::Pending
+            });
+            let cancellation_token = journal.anchor().transaction_anchor().cancellation_token();
+            Cancellable::new(await_recheck, cancellation_token).await?;
+        }
+        Ok(())
+    }
+
+    /// Installs a new [`Version`] of the database object created by the [`Journal`].
+    fn install_version(
+        record: &ebr::Shared<Record>,
+        value: &[u8],
+        object_id: u64,
+        journal: &Journal<'_, '_, S, P>,
+    ) {
+        let prev = record.head.get_shared(Acquire, &ebr::Guard::new());
+        let version = ebr::Shared::new(Version {
+            object_id,
+            value: value.into(),
+            prev: prev.map_or_else(AtomicShared::null, AtomicShared::from),
+            reclaimed: AtomicBool::new(false),
+            creator: journal.transaction().serialization_anchor().cloned(),
+        });
+        record.head.swap((Some(version), ebr::Tag::None), Release);
+    }
+
+    /// Creates versions of the key-value pairs in the batch with the [`Journal`], and returns the
+    /// number of them.
+    ///
+    /// The database objects of the versions are created together before any of the versions is
+    /// installed.
+    async fn load_batch<K: AsRef<[u8]>, V: AsRef<[u8]>>(
+        &self,
+        batch: &mut Vec<(ebr::Shared<Record>, K, V)>,
+        journal: &mut Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<usize, Error> {
+        let memory_usage = batch
+            .iter()
+            .map(|(_, k, v)| Self::change_memory_usage(k.as_ref(), None, Some(v.as_ref())))
+            .sum();
+        journal.reserve_memory(memory_usage)?;
+        let object_ids: Vec<u64> = batch
+            .iter()
+            .map(|_| journal.database().new_object_id())
+            .collect();
+        journal.create(&object_ids, deadline).await?;
+        let num_loaded = batch.len();
+        for ((record, key, value), object_id) in batch.drain(..).zip(object_ids) {
+            let (key, value) = (key.as_ref(), value.as_ref());
+            journal.write(&VersionRecord::new(
+                self.id,
+                record.lock_id,
+                object_id,
+                key,
+                value,
+            ))?;
+            Self::install_version(&record, value, object_id, journal);
+            Self::record_change(
+                self.id,
+                Change {
+                    container: self.name(),
+                    key: key.into(),
+                    old_value: None,
+                    new_value: Some(value.into()),
+                },
+                journal,
+            );
+            self.maintain_indexes(key, None, Some(value), journal, deadline)
+                .await?;
+        }
+        self.monitor(journal.database());
+        Ok(num_loaded)
+    }
+
+    /// Returns the latest truncation marker of the [`Container`] that is visible to the
+    /// [`Snapshot`], or `0` if the [`Container`] has never been truncated.
+    async fn truncation(
+        &self,
+        snapshot: &Snapshot<'_, '_, '_, S>,
+        deadline: Option<Instant>,
+    ) -> Result<u64, Error> {
+        let markers: Vec<u64> = self
+            .truncations
+            .lock()
+            .map(|t| t.iter().map(|(m, _)| *m).collect())
+            .map_err(|_| Error::UnexpectedState)?;
+
+        // The garbage collector updates `truncated` before removing a marker from the list.
+        let truncated = self.truncated.load(Acquire);
+        for marker in markers.into_iter().rev() {
+            if marker <= truncated {
+                break;
+            } else if self
+                .access_controller
+                .read(marker, snapshot, deadline)
+                .await?
+                && (self.truncated.load(Acquire) >= marker
+                    || self
+                        .truncations
+                        .lock()
+                        .is_ok_and(|t| t.iter().any(|(m, _)| *m == marker)))
+            {
+                // A marker without access control data is visible to every reader unless the
+                // garbage collector has removed it from the list after it was rolled back.
+                return Ok(marker);
+            }
+        }
+        Ok(truncated)
+    }
+
+    /// Returns the latest [`Version`] of the [`Record`] that is visible to the [`Snapshot`].
+    ///
+    /// `truncation` is the truncation marker visible to the [`Snapshot`] returned by
+    /// [`Container::truncation`]; versions of database objects created before it are invisible.
+    async fn visible_version(
+        access_controller: &AccessController<S>,
+        record: &Record,
+        snapshot: &Snapshot<'_, '_, '_, S>,
+        truncation: u64,
+        deadline: Option<Instant>,
+    ) -> Result<Option<ebr::Shared<Version>>, Error> {
+        let mut current = record.head.get_shared(Acquire, &ebr::Guard::new());
+        while let Some(version) = current {
+            if version.object_id < truncation {
+                // Older versions were created before the truncation marker as well.
+                return Ok(None);
+            }
+
+            // The flag must be checked after the access control data was read since it is set
+            // before the access control data of a reclaimed version is removed.
+            if access_controller
+                .read(version.object_id, snapshot, deadline)
+                .await?
+                && !version.reclaimed.load(Acquire)
+            {
+                return Ok(Some(version));
+            }
+            current = version.prev.get_shared(Acquire, &ebr::Guard::new());
+        }
+        Ok(None)
+    }
+}
+
+impl StatisticsDelta {
+    /// Creates a new [`StatisticsDelta`] describing the [`Change`].
+    fn new(container_id: u64, change: &Change) -> StatisticsDelta {
+        StatisticsDelta {
+            container_id,
+            live_records: i64::from(change.new_value.is_some())
+                - i64::from(change.old_value.is_some()),
+            dead_versions: u64::from(change.old_value.is_some()),
+        }
+    }
+
+    /// Merges another [`StatisticsDelta`] of the same [`Container`].
+    pub(super) fn merge(&mut self, other: &StatisticsDelta) {
+        debug_assert_eq!(self.container_id, other.container_id);
+        self.live_records += other.live_records;
+        self.dead_versions += other.dead_versions;
+    }
+}
+
+impl Candidate {
+    /// Returns the key.
+    fn key(&self) -> &[u8] {
+        match self {
+            Candidate::Loaded(key, _) | Candidate::Persisted { key, .. } => key,
+        }
+    }
+}
+
+impl Record {
+    /// Creates a new [`Record`] without versions.
+    fn new(lock_id: u64) -> Record {
+        Record {
+            lock_id,
+            head: AtomicShared::null(),
+            readers: Mutex::default(),
+            removed: Mutex::new(false),
+        }
+    }
+
+    /// Returns `true` if the [`Record`] has been removed from the [`Container`].
+    fn is_removed(&self) -> bool {
+        self.removed.lock().map_or(true, |r| *r)
+    }
+
+    /// Returns the number of versions of the [`Record`] that have yet to be reclaimed.
+    fn version_chain_length(&self) -> usize {
+        let guard = ebr::Guard::new();
+        let mut length = 0;
+        let mut current = self.head.load(Acquire, &guard).as_ref();
+        while let Some(version) = current {
+            if !version.reclaimed.load(Acquire) {
+                length += 1;
+            }
+            current = version.prev.load(Acquire, &guard).as_ref();
+        }
+        length
+    }
+}
+
+impl RangeRead {
+    /// Returns the bounds of the [`RangeRead`].
+    fn bounds(&self) -> (Bound<&[u8]>, Bound<&[u8]>) {
+        (
+            self.start.as_ref().map(AsRef::as_ref),
+            self.end.as_ref().map(AsRef::as_ref),
+        )
+    }
+
+    /// Returns `true` if the key is in the range.
+    fn contains(&self, key: &[u8]) -> bool {
+        RangeBounds::<[u8]>::contains(&self.bounds(), key)
+    }
+}
 
-    /// A link to old versions of the [`Container`].
-    _version: std::marker::PhantomData<(S, P)>,
+impl LockMode {
+    /// Returns `true` if the mode is compatible with the other mode held by another transaction.
+    fn is_compatible(self, other: LockMode) -> bool {
+        match self {
+            LockMode::IntentionShared => other != LockMode::Exclusive,
+            LockMode::IntentionExclusive => {
+                matches!(
+                    other,
+                    LockMode::IntentionShared | LockMode::IntentionExclusive
+                )
+            }
+            LockMode::Shared => matches!(other, LockMode::IntentionShared | LockMode::Shared),
+            LockMode::Exclusive => false,
+        }
+    }
+
+    /// Returns `true` if the mode grants the permissions of the other mode.
+    fn covers(self, other: LockMode) -> bool {
+        match self {
+            LockMode::IntentionShared | LockMode::IntentionExclusive => {
+                other == self || other == LockMode::IntentionShared
+            }
+            LockMode::Shared => other == LockMode::Shared || other == LockMode::IntentionShared,
+            LockMode::Exclusive => true,
+        }
+    }
 }
 
-impl<S: Sequencer, P: PersistenceLayer<S>> Container<S, P> {
-    /// Creates a new data [`Container`].
+impl<S: Sequencer, P: PersistenceLayer<S>> Scanner<'_, '_, '_, '_, '_, S, P> {
+    /// Sets the number of pages of the persisted index of the [`Container`] to read ahead.
+    ///
+    /// Key-value pairs persisted by a checkpoint are read from database pages as the [`Scanner`]
+    /// reaches them, and the pages following those being read are read into the page cache in
+    /// the background while the consumer processes the key-value pairs, so that
+    /// [`Scanner::next`] seldom waits for the pages to be read. It has no effect on key-value
+    /// pairs in memory. The default is `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("scanner_readahead")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let container = database
+    ///         .create_container("hello".to_string(), Metadata::default(), &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     journal.submit();
+    ///     assert!(transaction.commit().await.is_ok());
+    ///
+    ///     let snapshot = database.snapshot();
+    ///     let mut scanner = container
+    ///         .range(b"1".as_slice()..b"3".as_slice(), &snapshot, None)
+    ///         .with_readahead(8);
+    ///     assert_eq!(scanner.next().await, Ok(None));
+    /// };
+    /// ```
+    #[inline]
     #[must_use]
-    pub(super) fn new(metadata: Metadata) -> Container<S, P> {
-        Container {
-            _metadata: metadata,
-            _version: std::marker::PhantomData,
+    pub fn with_readahead(mut self, readahead: usize) -> Self {
+        self.readahead = readahead;
+        self
+    }
+
+    /// Returns the next key-value pair.
+    ///
+    /// Returns `None` if no more key-value pairs visible to the [`Snapshot`] are in the range.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the visibility of a value could not be determined until the
+    /// deadline was reached, or the persisted index of the [`Container`] could not be read.
+    #[inline]
+    pub async fn next(&mut self) -> ScanResult {
+        loop {
+            while self.candidates.is_empty() && !self.exhausted {
+                self.exhausted = !self
+                    .container
+                    .read_candidates(
+                        &mut self.start,
+                        &self.end,
+                        SCAN_BATCH_SIZE,
+                        self.readahead,
+                        &mut self.candidates,
+                    )
+                    .await?;
+            }
+            let truncation = self
+                .container
+                .truncation(self.snapshot, self.deadline)
+                .await?;
+            match self.candidates.pop_front() {
+                Some(Candidate::Loaded(key, record)) => {
+                    if let Some(version) = Container::<S, P>::visible_version(
+                        &self.container.access_controller,
+                        &record,
+                        self.snapshot,
+                        truncation,
+                        self.deadline,
+                    )
+                    .await?
+                    {
+                        return Ok(Some((key, version.value.to_vec())));
+                    }
+                }
+                Some(Candidate::Persisted {
+                    object_id,
+                    key,
+                    value,
+                    ..
+                }) => {
+                    if object_id >= truncation
+                        && self
+                            .container
+                            .access_controller
+                            .read(object_id, self.snapshot, self.deadline)
+                            .await?
+                    {
+                        return Ok(Some((key, value.into_vec())));
+                    }
+                }
+                None => return Ok(None),
+            }
         }
     }
+}
 
-    /// Iterates over versioned records for `MVCC` garbage collection.
-    pub(super) fn iter_versioned_records(&self) -> VersionedRecordVisitor<S, P> {
-        VersionedRecordVisitor { _container: self }
+#[cfg(feature = "async")]
+impl<'c: 's, 's, 'd, 't, 'j, S: Sequencer, P: PersistenceLayer<S>>
+    Scanner<'c, 's, 'd, 't, 'j, S, P>
+{
+    /// Converts the [`Scanner`] into a [`ScanStream`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::StreamExt;
+    /// use sap_tsf::{Database, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("scanner_stream")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let container = database
+    ///         .create_container("hello".to_string(), Metadata::default(), &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     assert!(container.insert(b"1", b"one", &mut journal, None).await.is_ok());
+    ///     journal.submit();
+    ///     assert!(transaction.commit().await.is_ok());
+    ///
+    ///     let snapshot = database.snapshot();
+    ///     let mut stream = container
+    ///         .range(b"1".as_slice().., &snapshot, None)
+    ///         .with_readahead(8)
+    ///         .into_stream();
+    ///     while let Some(Ok((key, value))) = stream.next().await {
+    ///         println!("{key:?}: {value:?}");
+    ///     }
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn into_stream(self) -> ScanStream<'c, 's, 'd, 't, 'j, S, P> {
+        ScanStream {
+            state: StreamState::Pending(ScanStream::look_up(self)),
+        }
     }
 }
 
-#[derive(Debug)]
-pub(super) struct VersionedRecordVisitor<'c, S: Sequencer, P: PersistenceLayer<S>> {
-    /// The container to which it is referring.
-    _container: &'c Container<S, P>,
+#[cfg(feature = "async")]
+impl<'c: 's, 's, 'd, 't, 'j, S: Sequencer, P: PersistenceLayer<S>>
+    ScanStream<'c, 's, 'd, 't, 'j, S, P>
+{
+    /// Looks up the next key-value pair with the [`Scanner`].
+    fn look_up(
+        mut scanner: Scanner<'c, 's, 'd, 't, 'j, S, P>,
+    ) -> Pin<ScanFuture<'c, 's, 'd, 't, 'j, S, P>> {
+        Box::pin(async move {
+            let result = scanner.next().await;
+            (scanner, result)
+        })
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'c: 's, 's, S: Sequencer, P: PersistenceLayer<S>> futures_core::Stream
+    for ScanStream<'c, 's, '_, '_, '_, S, P>
+{
+    type Item = Result<(Box<[u8]>, Vec<u8>), Error>;
+
+    #[inline]
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match std::mem::replace(&mut this.state, StreamState::Done) {
+                StreamState::Pending(mut future) => match future.as_mut().poll(cx) {
+                    Poll::Ready((scanner, result)) => {
+                        this.state = StreamState::Ready(scanner, result);
+                    }
+                    Poll::Pending => {
+                        this.state = StreamState::Pending(future);
+                        return Poll::Pending;
+                    }
+                },
+                StreamState::Ready(scanner, Ok(Some(entry))) => {
+                    // Start looking up the next key-value pair before yielding the current one.
+                    let mut future = Self::look_up(scanner);
+                    this.state = match future.as_mut().poll(cx) {
+                        Poll::Ready((scanner, result)) => StreamState::Ready(scanner, result),
+                        Poll::Pending => StreamState::Pending(future),
+                    };
+                    return Poll::Ready(Some(Ok(entry)));
+                }
+                StreamState::Ready(_, Ok(None)) | StreamState::Done => {
+                    return Poll::Ready(None);
+                }
+                StreamState::Ready(_, Err(error)) => return Poll::Ready(Some(Err(error))),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<S: Sequencer, P: PersistenceLayer<S>> Debug for ScanStream<'_, '_, '_, '_, '_, S, P> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match &self.state {
+            StreamState::Pending(_) => "Pending",
+            StreamState::Ready(..) => "Ready",
+            StreamState::Done => "Done",
+        };
+        f.debug_struct("ScanStream").field("state", &state).finish()
+    }
+}
+
+impl<I> RecordVersion<'_, I> {
+    /// Subscribes to the [`RecordVersion`] in order to be notified when it is superseded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Metadata};
+    /// use scc::ebr::Guard;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("subscribe")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let container = database
+    ///         .create_container("hello".to_string(), Metadata::default(), &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     assert!(container.insert(b"1", b"one", &mut journal, None).await.is_ok());
+    ///     journal.submit();
+    ///     assert!(transaction.commit().await.is_ok());
+    ///
+    ///     let subscription = container
+    ///         .versions(b"1", &Guard::new())
+    ///         .next()
+    ///         .map(|v| v.subscribe(&database))
+    ///         .unwrap();
+    ///
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     assert!(container.update(b"1", b"uno", &mut journal, None).await.is_ok());
+    ///     journal.submit();
+    ///     assert!(transaction.commit().await.is_ok());
+    ///     assert!(subscription.changed(None).await.is_ok());
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn subscribe<'d, S: Sequencer<Instant = I>, P: PersistenceLayer<S>>(
+        &self,
+        database: &'d Database<S, P>,
+    ) -> Subscription<'d, S, P> {
+        Subscription {
+            database,
+            container_id: self.container_id,
+            record: self.record.get_shared(),
+            object_id: self.object_id,
+        }
+    }
+}
+
+impl<S: Sequencer, P: PersistenceLayer<S>> Subscription<'_, S, P> {
+    /// Waits until the [`RecordVersion`] is superseded.
+    ///
+    /// It returns immediately if the [`RecordVersion`] has already been superseded; a
+    /// [`RecordVersion`] that is rolled back is regarded as superseded.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Timeout`] if the [`RecordVersion`] was not superseded until the
+    /// deadline, or an [`Error`] if the visibility of a version could not be determined.
+    #[inline]
+    pub async fn changed(&self, deadline: Option<Instant>) -> Result<(), Error> {
+        let change_log = self.database.change_log();
+        loop {
+            // Committed changes are published after they become visible, therefore a change
+            // made visible after the check is always followed by a publication.
+            let num_published = change_log.num_published();
+            if self.is_superseded(deadline).await? {
+                return Ok(());
+            }
+            poll_fn(|cx| {
+                if change_log.has_published_since(num_published, cx.waker()) {
+                    return Poll::Ready(Ok(()));
+                }
+                if let Some(deadline) = deadline {
+                    if deadline < Instant::now() {
+                        return Poll::Ready(Err(Error::Timeout));
+                    } else if !self
+                        .database
+                        .task_processor()
+                        .send_task(Task::WakeUp(deadline, cx.waker().clone()))
+                    {
+                        // The message channel is congested.
+                        cx.waker().wake_by_ref();
+                    }
+                }
+                Poll::Pending
+            })
+            .await?;
+        }
+    }
+
+    /// Returns `true` if the [`RecordVersion`] has been superseded.
+    async fn is_superseded(&self, deadline: Option<Instant>) -> Result<bool, Error> {
+        let Some(record) = self.record.as_ref() else {
+            return Ok(true);
+        };
+        let access_controller = self.database.access_controller();
+        let snapshot = self.database.snapshot();
+        let truncation = match self.database.shared_container(self.container_id) {
+            Some(container) => container.truncation(&snapshot, deadline).await?,
+            None => 0,
+        };
+        if self.object_id < truncation {
+            // The container has been truncated after the version was created.
+            return Ok(true);
+        }
+        let visible = Container::<S, P>::visible_version(
+            access_controller,
+            record,
+            &snapshot,
+            truncation,
+            deadline,
+        )
+        .await?;
+        drop(snapshot);
+        let guard = ebr::Guard::new();
+        let mut newer_visible = false;
+        let mut current = record.head.load(Acquire, &guard).as_ref();
+        while let Some(version) = current {
+            let is_visible = visible
+                .as_ref()
+                .is_some_and(|v| ptr::eq(v.as_ptr(), version));
+            if version.object_id == self.object_id {
+                if is_visible || newer_visible {
+                    return Ok(newer_visible);
+                }
+
+                // The version is yet to be committed, or it has been deleted or rolled back since
+                // an ended owner of an invisible version must have deleted it or been rolled back.
+                return Ok(match access_controller.version_state_sync(self.object_id) {
+                    VersionState::Owned(owners) => owners.iter().any(|(_, s)| {
+                        matches!(
+                            s,
+                            TransactionState::Committed { .. } | TransactionState::RolledBack
+                        )
+                    }),
+                    VersionState::Deleted(_) => true,
+                    VersionState::Created(_) | VersionState::Untracked => false,
+                });
+            }
+            newer_visible |= is_visible;
+            current = version.prev.load(Acquire, &guard).as_ref();
+        }
+
+        // The version has been reclaimed.
+        Ok(true)
+    }
 }
 
-impl<'c, S: Sequencer, P: PersistenceLayer<S>> Iterator for VersionedRecordVisitor<'c, S, P> {
-    type Item = u64;
+impl<'g, S: Sequencer> Iterator for Versions<'g, S> {
+    type Item = RecordVersion<'g, S::Instant>;
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        None
+        let mut version = self.current.take()?;
+        while version.reclaimed.load(Acquire) {
+            version = version.prev.load(Acquire, self.guard).as_ref()?;
+        }
+        self.current = version.prev.load(Acquire, self.guard).as_ref();
+        Some(RecordVersion {
+            object_id: version.object_id,
+            state: self.access_controller.version_state_sync(version.object_id),
+            value: &version.value,
+            container_id: self.container_id,
+            record: self.record,
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{StatisticsDelta, SCAN_BATCH_SIZE};
     use crate::sequencer::MonotonicU64;
-    use crate::{Container, FileIO, Metadata};
+    use crate::Sequencer;
+    use crate::{
+        Change, Container, ContainerStatistics, Database, Error, FileIO, IndexType, IsolationLevel,
+        LockMode, Metadata, TransactionState, VersionState,
+    };
+    use scc::ebr;
+    use std::path::Path;
+    use std::sync::atomic::Ordering::Relaxed;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use tokio::fs::remove_dir_all;
 
     #[tokio::test]
     async fn container() {
-        let metadata = Metadata {};
-        let _container = Container::<MonotonicU64, FileIO<MonotonicU64>>::new(metadata);
+        let metadata = Metadata::default();
+        let _container = Container::<MonotonicU64, FileIO<MonotonicU64>>::new(
+            1,
+            "container".into(),
+            metadata,
+            Arc::default(),
+        );
+    }
+
+    #[tokio::test]
+    async fn persistence() {
+        const DIR: &str = "container_persistence_test";
+        let path = Path::new(DIR);
+        let large_value = vec![7_u8; 1000];
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("kv".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        for key in [b"1", b"2", b"3"] {
+            assert!(container.insert(key, key, &mut journal, None).await.is_ok());
+        }
+        assert!(container
+            .insert(b"large", &large_value, &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .update(b"1", b"one", &mut journal, None)
+            .await
+            .is_ok());
+        assert!(container.delete(b"2", &mut journal, None).await.is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .update(b"3", b"three", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        transaction.rollback();
+        drop(database);
+
+        let database = Database::with_path(path).await.unwrap();
+        let snapshot = database.snapshot();
+        let container = database.get_container("kv", &snapshot).await.unwrap();
+        assert_eq!(
+            container.get(b"1", &snapshot, None).await,
+            Ok(Some(b"one".to_vec()))
+        );
+        assert_eq!(container.get(b"2", &snapshot, None).await, Ok(None));
+        assert_eq!(
+            container.get(b"3", &snapshot, None).await,
+            Ok(Some(b"3".to_vec()))
+        );
+        assert_eq!(
+            container.get(b"large", &snapshot, None).await,
+            Ok(Some(large_value))
+        );
+
+        // Keys are locked by the identifiers restored from the log.
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .insert(b"4", b"4", &mut journal, None)
+            .await
+            .is_ok());
+        assert!(container
+            .update(b"3", b"three", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        drop(snapshot);
+        drop(database);
+
+        let database = Database::with_path(path).await.unwrap();
+        let snapshot = database.snapshot();
+        let container = database.get_container("kv", &snapshot).await.unwrap();
+        assert_eq!(
+            container.get(b"3", &snapshot, None).await,
+            Ok(Some(b"three".to_vec()))
+        );
+        assert_eq!(
+            container.get(b"4", &snapshot, None).await,
+            Ok(Some(b"4".to_vec()))
+        );
+        drop(snapshot);
+        drop(database);
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn checkpoint() {
+        for index_type in [IndexType::Ordered, IndexType::Hash] {
+            checkpoint_with_index_type(index_type).await;
+        }
+    }
+
+    #[allow(clippy::too_many_lines)]
+    async fn checkpoint_with_index_type(index_type: IndexType) {
+        const NUM_KEYS: usize = 1024;
+        let dir = format!("container_checkpoint_test_{index_type:?}");
+        let path = Path::new(&dir);
+        let metadata = Metadata::default().with_index_type(index_type);
+        let large_value = vec![7_u8; 3000];
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("kv".to_string(), metadata, &mut journal, None)
+            .await
+            .unwrap();
+        for i in 0..NUM_KEYS {
+            let key = i.to_be_bytes();
+            assert!(container
+                .insert(&key, &key, &mut journal, None)
+                .await
+                .is_ok());
+        }
+        assert!(container
+            .insert(b"large", &large_value, &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(
+            container.insert(&[0; 512], b"", &mut journal, None).await,
+            Err(Error::WrongParameter)
+        );
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .update(&0_usize.to_be_bytes(), b"zero", &mut journal, None)
+            .await
+            .is_ok());
+        assert!(container
+            .delete(&1_usize.to_be_bytes(), &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        assert!(database.checkpoint(None).await.is_ok());
+        drop(container);
+        drop(database);
+
+        // Key-value pairs are loaded from the checkpoint without the log.
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(path.join("l.log"))
+            .unwrap()
+            .set_len(0)
+            .unwrap();
+        let database = Database::with_path(path).await.unwrap();
+        let snapshot = database.snapshot();
+        let container = database.get_container("kv", &snapshot).await.unwrap();
+        assert_eq!(
+            container.get(&0_usize.to_be_bytes(), &snapshot, None).await,
+            Ok(Some(b"zero".to_vec()))
+        );
+        assert_eq!(
+            container.get(&1_usize.to_be_bytes(), &snapshot, None).await,
+            Ok(None)
+        );
+        for i in 2..NUM_KEYS {
+            let key = i.to_be_bytes();
+            assert_eq!(
+                container.get(&key, &snapshot, None).await,
+                Ok(Some(key.to_vec()))
+            );
+        }
+        assert_eq!(
+            container.get(b"large", &snapshot, None).await,
+            Ok(Some(large_value))
+        );
+
+        // Versions replayed from the log supersede the checkpoint.
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .update(&2_usize.to_be_bytes(), b"two", &mut journal, None)
+            .await
+            .is_ok());
+        assert!(container
+            .delete(&3_usize.to_be_bytes(), &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        drop(snapshot);
+        drop(database);
+
+        let database = Database::with_path(path).await.unwrap();
+        let snapshot = database.snapshot();
+        let container = database.get_container("kv", &snapshot).await.unwrap();
+        assert_eq!(
+            container.get(&0_usize.to_be_bytes(), &snapshot, None).await,
+            Ok(Some(b"zero".to_vec()))
+        );
+        assert_eq!(
+            container.get(&2_usize.to_be_bytes(), &snapshot, None).await,
+            Ok(Some(b"two".to_vec()))
+        );
+        assert_eq!(
+            container.get(&3_usize.to_be_bytes(), &snapshot, None).await,
+            Ok(None)
+        );
+        assert_eq!(
+            container.get(&4_usize.to_be_bytes(), &snapshot, None).await,
+            Ok(Some(4_usize.to_be_bytes().to_vec()))
+        );
+        drop(snapshot);
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[allow(clippy::too_many_lines)]
+    #[tokio::test]
+    async fn persisted_index() {
+        const DIR: &str = "container_persisted_index_test";
+        const NUM_KEYS: usize = 4096;
+        let path = Path::new(DIR);
+        let metadata = Metadata::default().with_index_type(IndexType::Ordered);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("kv".to_string(), metadata, &mut journal, None)
+            .await
+            .unwrap();
+        let keys: Vec<Vec<u8>> = (0..NUM_KEYS).map(|i| i.to_be_bytes().to_vec()).collect();
+        assert_eq!(
+            container
+                .bulk_load(keys.iter().map(|k| (k, k)), &mut journal, None)
+                .await,
+            Ok(NUM_KEYS)
+        );
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        assert!(database.checkpoint(None).await.is_ok());
+        let container_id = container.id;
+        drop(container);
+        drop(database);
+
+        // Key-value pairs are left in the B+tree, and the scan reads them without loading them.
+        let database = Database::with_path(path).await.unwrap();
+        let guard = ebr::Guard::new();
+        let container = database.container(container_id, &guard).unwrap();
+        let snapshot = database.snapshot();
+        assert!(container.records.is_empty());
+        let mut scanner = container
+            .range(
+                keys[1].as_slice()..keys[NUM_KEYS - 1].as_slice(),
+                &snapshot,
+                None,
+            )
+            .with_readahead(4);
+        let mut visited = Vec::new();
+        while let Some((key, value)) = scanner.next().await.unwrap() {
+            assert_eq!(key.as_ref(), value.as_slice());
+            visited.push(key.to_vec());
+        }
+        assert_eq!(visited.as_slice(), &keys[1..NUM_KEYS - 1]);
+        assert!(container.records.is_empty());
+
+        // Accessed keys are loaded.
+        assert_eq!(
+            container.get(&keys[5], &snapshot, None).await,
+            Ok(Some(keys[5].clone()))
+        );
+        assert_eq!(container.records.len(), 1);
+        drop(snapshot);
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert_eq!(
+            container.insert(&keys[6], b"", &mut journal, None).await,
+            Err(Error::UniquenessViolation)
+        );
+        assert!(container.delete(&keys[7], &mut journal, None).await.is_ok());
+        assert!(container
+            .update(&keys[8], b"eight", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        // The deleted key stays in memory while it is in the B+tree, and is removed after a
+        // checkpoint.
+        let condition = |i: &u64| *i <= database.sequencer().min(Relaxed);
+        let _: (u64, bool) = container.reclaim_versions_sync(&condition, || ());
+        assert!(container.records.contains(keys[7].as_slice()));
+        let snapshot = database.snapshot();
+        assert_eq!(container.get(&keys[7], &snapshot, None).await, Ok(None));
+        drop(snapshot);
+        assert!(database.checkpoint(None).await.is_ok());
+        let _: (u64, bool) = container.reclaim_versions_sync(&condition, || ());
+        assert!(!container.records.contains(keys[7].as_slice()));
+        assert_eq!(container.statistics().live_records, NUM_KEYS as u64 - 1);
+
+        let snapshot = database.snapshot();
+        assert_eq!(container.get(&keys[7], &snapshot, None).await, Ok(None));
+        let mut scanner = container.range(keys[6].as_slice()..=keys[8].as_slice(), &snapshot, None);
+        assert_eq!(
+            scanner.next().await,
+            Ok(Some((keys[6].as_slice().into(), keys[6].clone())))
+        );
+        assert_eq!(
+            scanner.next().await,
+            Ok(Some((keys[8].as_slice().into(), b"eight".to_vec())))
+        );
+        assert_eq!(scanner.next().await, Ok(None));
+        drop(snapshot);
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container.truncate(&mut journal, None).await.is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        let snapshot = database.snapshot();
+        let mut scanner = container.range::<&[u8], _>(.., &snapshot, None);
+        assert_eq!(scanner.next().await, Ok(None));
+        drop(snapshot);
+        drop(guard);
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn update_with() {
+        const DIR: &str = "container_update_with_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("kv".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert!(container
+            .insert(b"1", b"1", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(
+            container
+                .update_with(b"2", <[u8]>::to_vec, &mut journal, None)
+                .await,
+            Err(Error::NotFound)
+        );
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        let snapshot_before = database.snapshot();
+        for transaction in [database.transaction(), database.optimistic_transaction()] {
+            let mut journal = transaction.journal();
+            assert!(container
+                .update_with(b"1", |v| [v, b"+"].concat(), &mut journal, None)
+                .await
+                .is_ok());
+            assert_eq!(journal.submit().get(), 1);
+            assert!(transaction.commit().await.is_ok());
+        }
+
+        let snapshot = database.snapshot();
+        assert_eq!(
+            container.get(b"1", &snapshot, None).await,
+            Ok(Some(b"1++".to_vec()))
+        );
+        assert_eq!(
+            container.get(b"1", &snapshot_before, None).await,
+            Ok(Some(b"1".to_vec()))
+        );
+        drop(snapshot);
+        drop(snapshot_before);
+        drop(database);
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn key_value() {
+        const DIR: &str = "container_key_value_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("kv".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert!(container
+            .insert(b"1", b"1", &mut journal, None)
+            .await
+            .is_ok());
+        assert!(container
+            .insert(b"2", b"2", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(
+            container.insert(b"1", b"1", &mut journal, None).await,
+            Err(Error::UniquenessViolation)
+        );
+        assert_eq!(journal.submit().get(), 1);
+
+        let snapshot = database.snapshot();
+        assert_eq!(container.get(b"1", &snapshot, None).await, Ok(None));
+        drop(snapshot);
+        assert!(transaction.commit().await.is_ok());
+
+        let snapshot_before = database.snapshot();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .update(b"1", b"3", &mut journal, None)
+            .await
+            .is_ok());
+        assert!(container.delete(b"2", &mut journal, None).await.is_ok());
+        assert_eq!(
+            container.delete(b"3", &mut journal, None).await,
+            Err(Error::NotFound)
+        );
+        let journal_snapshot = journal.snapshot();
+        assert_eq!(
+            container.get(b"1", &journal_snapshot, None).await,
+            Ok(Some(b"3".to_vec()))
+        );
+        assert_eq!(container.get(b"2", &journal_snapshot, None).await, Ok(None));
+        drop(journal_snapshot);
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        let snapshot_after = database.snapshot();
+        assert_eq!(
+            container.get(b"1", &snapshot_before, None).await,
+            Ok(Some(b"1".to_vec()))
+        );
+        assert_eq!(
+            container.get(b"2", &snapshot_before, None).await,
+            Ok(Some(b"2".to_vec()))
+        );
+        assert_eq!(
+            container.get(b"1", &snapshot_after, None).await,
+            Ok(Some(b"3".to_vec()))
+        );
+        assert_eq!(container.get(b"2", &snapshot_after, None).await, Ok(None));
+        drop(snapshot_before);
+        drop(snapshot_after);
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .insert(b"2", b"4", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        transaction.rollback();
+        let snapshot = database.snapshot();
+        assert_eq!(container.get(b"2", &snapshot, None).await, Ok(None));
+        drop(snapshot);
+
+        drop(container);
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn reclaim_versions() {
+        const DIR: &str = "container_reclaim_versions_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("gc".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        for key in [b"1", b"2"] {
+            assert!(container.insert(key, key, &mut journal, None).await.is_ok());
+        }
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        let snapshot_before = database.snapshot();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .update(b"1", b"3", &mut journal, None)
+            .await
+            .is_ok());
+        assert!(container.delete(b"2", &mut journal, None).await.is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        // Old versions are visible to the snapshot.
+        let condition = |i: &u64| *i <= database.sequencer().min(Relaxed);
+        let (_, revisit) = container.reclaim_versions_sync(&condition, || ());
+        assert!(revisit);
+        assert_eq!(
+            container.get(b"1", &snapshot_before, None).await,
+            Ok(Some(b"1".to_vec()))
+        );
+        assert_eq!(
+            container.get(b"2", &snapshot_before, None).await,
+            Ok(Some(b"2".to_vec()))
+        );
+        drop(snapshot_before);
+
+        let _: (u64, bool) = container.reclaim_versions_sync(&condition, || ());
+        assert_eq!(container.versions(b"1", &ebr::Guard::new()).count(), 1);
+        assert_eq!(container.versions(b"2", &ebr::Guard::new()).count(), 0);
+        assert!(!container.records.contains(b"2".as_slice()));
+
+        let snapshot = database.snapshot();
+        assert_eq!(
+            container.get(b"1", &snapshot, None).await,
+            Ok(Some(b"3".to_vec()))
+        );
+        assert_eq!(container.get(b"2", &snapshot, None).await, Ok(None));
+        drop(snapshot);
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .insert(b"2", b"4", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        assert_eq!(
+            container.get(b"2", &database.snapshot(), None).await,
+            Ok(Some(b"4".to_vec()))
+        );
+        drop(database);
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn max_version_chain_length() {
+        const DIR: &str = "container_max_version_chain_length_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        database.set_max_version_chain_length(Some(2));
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("chain".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert!(container
+            .insert(b"1", b"1", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        // The snapshot keeps the first version from being reclaimed.
+        let snapshot = database.snapshot();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .update(b"1", b"2", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert_eq!(
+            container.update(b"1", b"3", &mut journal, None).await,
+            Err(Error::VersionChainTooLong)
+        );
+        let deadline = Some(Instant::now() + Duration::from_millis(4));
+        assert_eq!(
+            container.update(b"1", b"3", &mut journal, deadline).await,
+            Err(Error::VersionChainTooLong)
+        );
+        drop(journal);
+        transaction.rollback();
+
+        // The writer waits for the garbage collector to reclaim the first version.
+        drop(snapshot);
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let deadline = Some(Instant::now() + Duration::from_mins(1));
+        assert!(container
+            .update(b"1", b"3", &mut journal, deadline)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        assert_eq!(
+            container.get(b"1", &database.snapshot(), None).await,
+            Ok(Some(b"3".to_vec()))
+        );
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[test]
+    fn statistics_delta() {
+        let container = Container::<MonotonicU64, FileIO<MonotonicU64>>::new(
+            1,
+            "container".into(),
+            Metadata::default(),
+            Arc::default(),
+        );
+        let change = |old_value: Option<&[u8]>, new_value: Option<&[u8]>| Change {
+            container: "container".into(),
+            key: b"1".as_slice().into(),
+            old_value: old_value.map(Into::into),
+            new_value: new_value.map(Into::into),
+        };
+        let mut delta = StatisticsDelta::new(1, &change(None, Some(&[0; 255])));
+        delta.merge(&StatisticsDelta::new(
+            1,
+            &change(Some(b"1"), Some(&[0; 256])),
+        ));
+        container.apply_statistics_delta(&delta);
+        let statistics = ContainerStatistics {
+            live_records: 1,
+            dead_versions: 1,
+            allocated_pages: 0,
+        };
+        assert_eq!(container.statistics(), statistics);
+
+        container.apply_statistics_delta(&StatisticsDelta::new(1, &change(Some(b"1"), None)));
+        container.apply_statistics_delta(&StatisticsDelta::new(1, &change(Some(b"1"), None)));
+        assert_eq!(container.statistics().live_records, 0);
+        assert_eq!(container.statistics().dead_versions, 3);
+    }
+
+    #[tokio::test]
+    async fn statistics() {
+        const DIR: &str = "container_statistics_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("stats".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        for key in [b"1", b"2", b"3"] {
+            assert!(container
+                .insert(key, &[0; 255], &mut journal, None)
+                .await
+                .is_ok());
+        }
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        // The garbage collector may visit the container at any time, and the statistics are
+        // exact once the versions are settled.
+        let condition = |i: &u64| *i <= database.sequencer().min(Relaxed);
+        let _: (u64, bool) = container.reclaim_versions_sync(&condition, || ());
+        let statistics = ContainerStatistics {
+            live_records: 3,
+            dead_versions: 0,
+            allocated_pages: 0,
+        };
+        assert_eq!(container.statistics(), statistics);
+
+        // Pages are counted when a checkpoint persists the container: the values are too large
+        // for the root page, and each of them is stored in a blob page.
+        assert!(database.checkpoint(None).await.is_ok());
+        assert_eq!(container.statistics().allocated_pages, 4);
+
+        let snapshot_before = database.snapshot();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .update(b"1", b"4", &mut journal, None)
+            .await
+            .is_ok());
+        assert!(container.delete(b"2", &mut journal, None).await.is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        // Old versions visible to the snapshot are awaiting the garbage collector.
+        let _: (u64, bool) = container.reclaim_versions_sync(&condition, || ());
+        let statistics = ContainerStatistics {
+            live_records: 2,
+            dead_versions: 2,
+            allocated_pages: 4,
+        };
+        assert_eq!(container.statistics(), statistics);
+        drop(snapshot_before);
+        let _: (u64, bool) = container.reclaim_versions_sync(&condition, || ());
+        let statistics = ContainerStatistics {
+            live_records: 2,
+            dead_versions: 0,
+            allocated_pages: 4,
+        };
+        assert_eq!(container.statistics(), statistics);
+
+        let snapshot = database.snapshot();
+        let all = database
+            .container_statistics(&snapshot, None)
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all.get("stats"), Some(&statistics));
+        drop(snapshot);
+
+        // The pages of the index persisted by the checkpoint are counted after recovery.
+        drop(container);
+        drop(database);
+        let database = Database::with_path(path).await.unwrap();
+        let snapshot = database.snapshot();
+        let container = database.get_container("stats", &snapshot).await.unwrap();
+        assert_eq!(container.statistics().allocated_pages, 4);
+        drop(snapshot);
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn versions() {
+        const DIR: &str = "container_versions_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container(
+                "versions".to_string(),
+                Metadata::default(),
+                &mut journal,
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(container
+            .insert(b"1", b"one", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        let commit_instant_1 = transaction.commit().await.unwrap();
+
+        let transaction = database.transaction();
+        let transaction_id = transaction.id();
+        let mut journal = transaction.journal();
+        assert!(container
+            .update(b"1", b"two", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        let versions = container
+            .versions(b"1", &ebr::Guard::new())
+            .map(|v| (v.value.to_vec(), v.state))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            versions,
+            vec![
+                (
+                    b"two".to_vec(),
+                    VersionState::Owned(vec![(transaction_id, TransactionState::Active)])
+                ),
+                (
+                    b"one".to_vec(),
+                    VersionState::Owned(vec![(transaction_id, TransactionState::Active)])
+                ),
+            ]
+        );
+        let commit_instant_2 = transaction.commit().await.unwrap();
+        assert!(commit_instant_1 < commit_instant_2);
+
+        let guard = ebr::Guard::new();
+        let states = container
+            .versions(b"1", &guard)
+            .map(|v| match v.state {
+                VersionState::Owned(owners) => match owners[..] {
+                    [(_, TransactionState::Committed { commit_instant })] => Some(commit_instant),
+                    _ => None,
+                },
+                VersionState::Created(instant) | VersionState::Deleted(instant) => Some(instant),
+                VersionState::Untracked => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(states, vec![Some(commit_instant_2), Some(commit_instant_2)]);
+        assert_eq!(container.versions(b"2", &guard).count(), 0);
+        drop(guard);
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn subscribe() {
+        const DIR: &str = "container_subscribe_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("sub".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert!(container
+            .insert(b"1", b"1", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+
+        // An uncommitted version is not superseded by being committed.
+        let subscribe = |key: &[u8]| {
+            let guard = ebr::Guard::new();
+            let version = container.versions(key, &guard).next().unwrap();
+            version.subscribe(&database)
+        };
+        let subscription = subscribe(b"1");
+        assert!(transaction.commit().await.is_ok());
+        let deadline = Instant::now() + Duration::from_millis(16);
+        assert_eq!(
+            subscription.changed(Some(deadline)).await,
+            Err(Error::Timeout)
+        );
+
+        // Uncommitted and rolled back versions do not supersede the version.
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .update(b"1", b"2", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        let uncommitted = subscribe(b"1");
+        let deadline = Instant::now() + Duration::from_millis(16);
+        assert_eq!(
+            subscription.changed(Some(deadline)).await,
+            Err(Error::Timeout)
+        );
+        transaction.rollback();
+        assert!(uncommitted.changed(None).await.is_ok());
+
+        // The subscriber is woken up when a newer version is committed.
+        let update = async {
+            let transaction = database.transaction();
+            let mut journal = transaction.journal();
+            assert!(container
+                .update(b"1", b"3", &mut journal, None)
+                .await
+                .is_ok());
+            assert_eq!(journal.submit().get(), 1);
+            assert!(transaction.commit().await.is_ok());
+        };
+        let (changed, ()) = tokio::join!(subscription.changed(None), update);
+        assert!(changed.is_ok());
+        assert!(subscription.changed(None).await.is_ok());
+
+        // Deleting the key-value pair supersedes the version.
+        let subscription = subscribe(b"1");
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container.delete(b"1", &mut journal, None).await.is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        assert!(subscription.changed(None).await.is_ok());
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn range() {
+        const DIR: &str = "container_range_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("range".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        for i in 0_u8..16 {
+            let key = [i];
+            assert!(container
+                .insert(&key, &key, &mut journal, None)
+                .await
+                .is_ok());
+        }
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        let snapshot_before = database.snapshot();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        for i in (0_u8..16).filter(|i| i % 2 == 0) {
+            assert!(container.delete(&[i], &mut journal, None).await.is_ok());
+        }
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        let snapshot_after = database.snapshot();
+        let mut scanner = container.range(
+            [4_u8].as_slice()..[12_u8].as_slice(),
+            &snapshot_before,
+            None,
+        );
+        for i in 4_u8..12 {
+            assert_eq!(
+                scanner.next().await,
+                Ok(Some((vec![i].into_boxed_slice(), vec![i])))
+            );
+        }
+        assert_eq!(scanner.next().await, Ok(None));
+
+        let mut scanner = container.range::<&[u8], _>(.., &snapshot_after, None);
+        for i in (0_u8..16).filter(|i| i % 2 == 1) {
+            assert_eq!(
+                scanner.next().await,
+                Ok(Some((vec![i].into_boxed_slice(), vec![i])))
+            );
+        }
+        assert_eq!(scanner.next().await, Ok(None));
+        drop(snapshot_before);
+        drop(snapshot_after);
+
+        drop(container);
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn range_readahead() {
+        const DIR: &str = "container_range_readahead_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container(
+                "readahead".to_string(),
+                Metadata::default(),
+                &mut journal,
+                None,
+            )
+            .await
+            .unwrap();
+        for i in 0_u8..16 {
+            assert!(container
+                .insert(&[i], &[i], &mut journal, None)
+                .await
+                .is_ok());
+        }
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        // Uncommitted changes are invisible regardless of whether they were read ahead.
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        for i in (0_u8..16).filter(|i| i % 3 == 0) {
+            assert!(container.delete(&[i], &mut journal, None).await.is_ok());
+        }
+        assert!(container
+            .insert(&[16], &[16], &mut journal, None)
+            .await
+            .is_ok());
+        let snapshot = database.snapshot();
+        let mut scanner = container
+            .range::<&[u8], _>(.., &snapshot, None)
+            .with_readahead(5);
+        for i in 0_u8..16 {
+            assert_eq!(
+                scanner.next().await,
+                Ok(Some((vec![i].into_boxed_slice(), vec![i])))
+            );
+            assert!(scanner.candidates.len() < SCAN_BATCH_SIZE);
+        }
+        assert_eq!(scanner.next().await, Ok(None));
+        drop(snapshot);
+        drop(journal);
+        drop(transaction);
+
+        drop(container);
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn range_stream() {
+        use futures::StreamExt;
+
+        const DIR: &str = "container_range_stream_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container(
+                "stream".to_string(),
+                Metadata::default(),
+                &mut journal,
+                None,
+            )
+            .await
+            .unwrap();
+        for i in 0_u8..64 {
+            assert!(container
+                .insert(&[i], &[i], &mut journal, None)
+                .await
+                .is_ok());
+        }
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        let snapshot = database.snapshot();
+        let stream = container
+            .range([8_u8].as_slice().., &snapshot, None)
+            .with_readahead(16)
+            .into_stream();
+        let entries: Vec<_> = stream.collect().await;
+        assert_eq!(entries.len(), 56);
+        for (i, entry) in (8_u8..64).zip(entries) {
+            assert_eq!(entry, Ok((vec![i].into_boxed_slice(), vec![i])));
+        }
+        drop(snapshot);
+
+        drop(container);
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn optimistic() {
+        const DIR: &str = "container_optimistic_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("occ".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert!(container
+            .insert(b"1", b"1", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        // Both transactions observe the same version, and the later one fails to commit.
+        let transaction_1 = database.optimistic_transaction();
+        let transaction_2 = database.optimistic_transaction();
+        let mut journal_1 = transaction_1.journal();
+        let mut journal_2 = transaction_2.journal();
+        assert!(container
+            .update(b"1", b"2", &mut journal_1, None)
+            .await
+            .is_ok());
+        assert!(container
+            .update(b"1", b"3", &mut journal_2, None)
+            .await
+            .is_ok());
+        assert_eq!(
+            container.read(b"1", &mut journal_1, None).await,
+            Ok(Some(b"2".to_vec()))
+        );
+        assert_eq!(
+            container.insert(b"1", b"4", &mut journal_1, None).await,
+            Err(Error::UniquenessViolation)
+        );
+        assert_eq!(journal_1.submit().get(), 1);
+        assert_eq!(journal_2.submit().get(), 1);
+
+        // Buffered changes are invisible until committed.
+        let snapshot = database.snapshot();
+        assert_eq!(
+            container.get(b"1", &snapshot, None).await,
+            Ok(Some(b"1".to_vec()))
+        );
+        drop(snapshot);
+
+        let transaction_1_id = transaction_1.id();
+        assert!(transaction_1.commit().await.is_ok());
+        assert!(matches!(
+            transaction_2.commit().await,
+            Err(Error::Conflict(report)) if report.owner == Some(transaction_1_id)
+        ));
+
+        let snapshot = database.snapshot();
+        assert_eq!(
+            container.get(b"1", &snapshot, None).await,
+            Ok(Some(b"2".to_vec()))
+        );
+        drop(snapshot);
+
+        // A transaction that only read a modified key-value pair fails to commit.
+        let transaction_1 = database.optimistic_transaction();
+        let mut journal_1 = transaction_1.journal();
+        assert_eq!(
+            container.read(b"1", &mut journal_1, None).await,
+            Ok(Some(b"2".to_vec()))
+        );
+        assert!(container
+            .insert(b"2", b"2", &mut journal_1, None)
+            .await
+            .is_ok());
+        assert_eq!(journal_1.submit().get(), 1);
+
+        let transaction_2 = database.optimistic_transaction();
+        let mut journal_2 = transaction_2.journal();
+        assert!(container.delete(b"1", &mut journal_2, None).await.is_ok());
+        assert_eq!(container.read(b"1", &mut journal_2, None).await, Ok(None));
+        assert_eq!(journal_2.submit().get(), 1);
+        let transaction_2_id = transaction_2.id();
+        assert!(transaction_2.commit().await.is_ok());
+        assert!(matches!(
+            transaction_1.commit().await,
+            Err(Error::Conflict(report)) if report.owner == Some(transaction_2_id)
+        ));
+
+        let snapshot = database.snapshot();
+        assert_eq!(container.get(b"1", &snapshot, None).await, Ok(None));
+        assert_eq!(container.get(b"2", &snapshot, None).await, Ok(None));
+        drop(snapshot);
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn serializable() {
+        const DIR: &str = "container_serializable_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("ssi".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert!(container
+            .insert(b"x", b"1", &mut journal, None)
+            .await
+            .is_ok());
+        assert!(container
+            .insert(b"y", b"1", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        // Write skew: each transaction reads both keys, and updates a different key.
+        let mut transaction_1 = database.transaction();
+        let mut transaction_2 = database.transaction();
+        transaction_1.set_isolation_level(IsolationLevel::Serializable);
+        transaction_2.set_isolation_level(IsolationLevel::Serializable);
+        let mut journal_1 = transaction_1.journal();
+        let mut journal_2 = transaction_2.journal();
+        for key in [b"x", b"y"] {
+            assert!(container.read(key, &mut journal_1, None).await.is_ok());
+            assert!(container.read(key, &mut journal_2, None).await.is_ok());
+        }
+        assert!(container
+            .update(b"x", b"0", &mut journal_1, None)
+            .await
+            .is_ok());
+        assert!(container
+            .update(b"y", b"0", &mut journal_2, None)
+            .await
+            .is_ok());
+        assert_eq!(journal_1.submit().get(), 1);
+        assert_eq!(journal_2.submit().get(), 1);
+        assert_eq!(
+            transaction_1.commit().await,
+            Err(Error::SerializationFailure)
+        );
+        transaction_2.rollback();
+
+        // A read-only transaction and a writer can be serialized.
+        let mut transaction_1 = database.transaction();
+        let mut transaction_2 = database.transaction();
+        transaction_1.set_isolation_level(IsolationLevel::Serializable);
+        transaction_2.set_isolation_level(IsolationLevel::Serializable);
+        let mut journal_1 = transaction_1.journal();
+        let mut journal_2 = transaction_2.journal();
+        assert_eq!(
+            container.read(b"x", &mut journal_1, None).await,
+            Ok(Some(b"1".to_vec()))
+        );
+        assert!(container
+            .update(b"x", b"2", &mut journal_2, None)
+            .await
+            .is_ok());
+        assert_eq!(
+            container.read(b"x", &mut journal_1, None).await,
+            Ok(Some(b"1".to_vec()))
+        );
+        assert_eq!(journal_1.submit().get(), 1);
+        assert_eq!(journal_2.submit().get(), 1);
+        assert!(transaction_2.commit().await.is_ok());
+        assert!(transaction_1.commit().await.is_ok());
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn phantom() {
+        const DIR: &str = "container_phantom_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("ssi".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert!(container
+            .insert(b"a", b"1", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        // Each transaction inserts a key into the range that the other has read.
+        let mut transaction_1 = database.transaction();
+        let mut transaction_2 = database.transaction();
+        transaction_1.set_isolation_level(IsolationLevel::Serializable);
+        transaction_2.set_isolation_level(IsolationLevel::Serializable);
+        let mut journal_1 = transaction_1.journal();
+        let mut journal_2 = transaction_2.journal();
+        let expected = vec![(b"a".as_slice().into(), b"1".to_vec())];
+        assert_eq!(
+            container
+                .read_range(b"a".as_slice().., &mut journal_1, None)
+                .await,
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            container
+                .read_range(b"a".as_slice().., &mut journal_2, None)
+                .await,
+            Ok(expected.clone())
+        );
+        assert!(container
+            .insert(b"b", b"1", &mut journal_1, None)
+            .await
+            .is_ok());
+        assert!(container
+            .insert(b"c", b"1", &mut journal_2, None)
+            .await
+            .is_ok());
+        assert_eq!(journal_1.submit().get(), 1);
+        assert_eq!(journal_2.submit().get(), 1);
+        assert_eq!(
+            transaction_1.commit().await,
+            Err(Error::SerializationFailure)
+        );
+        transaction_2.rollback();
+
+        // Inserting a key outside the range read by the other is not a conflict.
+        let mut transaction_1 = database.transaction();
+        let mut transaction_2 = database.transaction();
+        transaction_1.set_isolation_level(IsolationLevel::Serializable);
+        transaction_2.set_isolation_level(IsolationLevel::Serializable);
+        let mut journal_1 = transaction_1.journal();
+        let mut journal_2 = transaction_2.journal();
+        let range = b"a".as_slice()..b"b".as_slice();
+        assert_eq!(
+            container
+                .read_range(range.clone(), &mut journal_1, None)
+                .await,
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            container.read_range(range, &mut journal_2, None).await,
+            Ok(expected)
+        );
+        assert!(container
+            .insert(b"b", b"1", &mut journal_1, None)
+            .await
+            .is_ok());
+        assert!(container
+            .insert(b"c", b"1", &mut journal_2, None)
+            .await
+            .is_ok());
+        assert_eq!(journal_1.submit().get(), 1);
+        assert_eq!(journal_2.submit().get(), 1);
+        assert!(transaction_1.commit().await.is_ok());
+        assert!(transaction_2.commit().await.is_ok());
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn container_lock() {
+        const DIR: &str = "container_lock_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("lock".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        // Writers implicitly hold the container in intention exclusive mode.
+        let transaction_1 = database.transaction();
+        let transaction_2 = database.transaction();
+        let mut journal_1 = transaction_1.journal();
+        let mut journal_2 = transaction_2.journal();
+        assert!(container
+            .insert(b"1", b"1", &mut journal_1, None)
+            .await
+            .is_ok());
+        assert_eq!(
+            container.lock(LockMode::Shared, &mut journal_2, None).await,
+            Err(Error::SerializationFailure)
+        );
+        assert!(container
+            .lock(LockMode::IntentionShared, &mut journal_2, None)
+            .await
+            .is_ok());
+        assert!(container
+            .insert(b"2", b"2", &mut journal_2, None)
+            .await
+            .is_ok());
+        assert_eq!(journal_1.submit().get(), 1);
+        assert_eq!(journal_2.submit().get(), 1);
+        assert!(transaction_1.commit().await.is_ok());
+        assert!(transaction_2.commit().await.is_ok());
+
+        // A writer waits for the transaction holding the whole container.
+        let transaction_1 = database.transaction();
+        let transaction_2 = database.transaction();
+        let mut journal_1 = transaction_1.journal();
+        let mut journal_2 = transaction_2.journal();
+        assert!(container
+            .lock(LockMode::Shared, &mut journal_1, None)
+            .await
+            .is_ok());
+        assert!(container
+            .lock(LockMode::IntentionShared, &mut journal_1, None)
+            .await
+            .is_ok());
+        assert_eq!(
+            container.update(b"1", b"3", &mut journal_2, None).await,
+            Err(Error::SerializationFailure)
+        );
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let (result, ()) = tokio::join!(
+            container.update(b"1", b"3", &mut journal_2, Some(deadline)),
+            async {
+                drop(journal_1);
+            }
+        );
+        assert!(result.is_ok());
+        assert_eq!(journal_2.submit().get(), 1);
+        drop(transaction_1);
+
+        // Dropping the container waits for the writer.
+        let transaction_3 = database.transaction();
+        let mut journal_3 = transaction_3.journal();
+        let snapshot = database.snapshot();
+        assert_eq!(
+            database
+                .drop_container("lock", &snapshot, &mut journal_3, None)
+                .await,
+            Err(Error::SerializationFailure)
+        );
+        assert!(transaction_2.commit().await.is_ok());
+        assert!(database
+            .drop_container("lock", &snapshot, &mut journal_3, None)
+            .await
+            .is_ok());
+        drop(snapshot);
+        assert_eq!(journal_3.submit().get(), 1);
+        assert!(transaction_3.commit().await.is_ok());
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn lock_escalation() {
+        const DIR: &str = "container_lock_escalation_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container(
+                "escalation".to_string(),
+                Metadata::default(),
+                &mut journal,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        database.set_lock_escalation_threshold(Some(2));
+        let lock_id = |key: &[u8]| container.records.peek_with(key, |_, r| r.lock_id).unwrap();
+
+        // Escalation fails while another writer holds the container.
+        let transaction_1 = database.transaction();
+        let transaction_2 = database.transaction();
+        let mut journal_1 = transaction_1.journal();
+        let mut journal_2 = transaction_2.journal();
+        assert!(container
+            .insert(b"0", b"0", &mut journal_2, None)
+            .await
+            .is_ok());
+        for key in [b"1", b"2", b"3"] {
+            assert!(container
+                .insert(key, key, &mut journal_1, None)
+                .await
+                .is_ok());
+        }
+        assert_eq!(database.statistics().lock_escalations, 0);
+        assert_eq!(
+            database
+                .access_controller()
+                .owners(lock_id(b"3"))
+                .await
+                .len(),
+            1
+        );
+        assert_eq!(journal_2.submit().get(), 1);
+        assert!(transaction_2.commit().await.is_ok());
+
+        // The record locks are released once the container is locked exclusively.
+        assert!(container
+            .insert(b"4", b"4", &mut journal_1, None)
+            .await
+            .is_ok());
+        assert_eq!(database.statistics().lock_escalations, 1);
+        for key in [b"1", b"2", b"3", b"4"] {
+            assert!(database
+                .access_controller()
+                .owners(lock_id(key))
+                .await
+                .is_empty());
+        }
+        assert!(container
+            .insert(b"5", b"5", &mut journal_1, None)
+            .await
+            .is_ok());
+        assert!(database
+            .access_controller()
+            .owners(lock_id(b"5"))
+            .await
+            .is_empty());
+        let transaction_3 = database.transaction();
+        let mut journal_3 = transaction_3.journal();
+        assert_eq!(
+            container.update(b"0", b"1", &mut journal_3, None).await,
+            Err(Error::SerializationFailure)
+        );
+        drop(journal_3);
+        drop(transaction_3);
+        assert_eq!(journal_1.submit().get(), 1);
+        assert!(transaction_1.commit().await.is_ok());
+
+        let snapshot = database.snapshot();
+        for key in [b"0", b"1", b"2", b"3", b"4", b"5"] {
+            assert_eq!(
+                container.get(key, &snapshot, None).await,
+                Ok(Some(key.to_vec()))
+            );
+        }
+        drop(snapshot);
+
+        drop(container);
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn lock_escalation_rename() {
+        const DIR: &str = "container_lock_escalation_rename_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let renamed = database
+            .create_container("a".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        database.set_lock_escalation_threshold(Some(2));
+
+        // The record locks of the renamed container and the new container under its old name are
+        // tracked separately.
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(database
+            .rename_container("a", "b".to_string(), &mut journal, None)
+            .await
+            .is_ok());
+        let created = database
+            .create_container("a".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert_ne!(renamed.id, created.id);
+        for key in [b"1", b"2"] {
+            assert!(renamed.insert(key, key, &mut journal, None).await.is_ok());
+        }
+        assert!(created.insert(b"1", b"1", &mut journal, None).await.is_ok());
+        assert_eq!(database.statistics().lock_escalations, 0);
+        for key in [b"1", b"2"] {
+            let lock_id = renamed
+                .records
+                .peek_with(&key[..], |_, r| r.lock_id)
+                .unwrap();
+            assert_eq!(database.access_controller().owners(lock_id).await.len(), 1);
+        }
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn rename_on_commit() {
+        const DIR: &str = "container_rename_on_commit_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("a".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        // The name is left unchanged if the rename is rolled back.
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(database
+            .rename_container("a", "b".to_string(), &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        transaction.rollback();
+        assert_eq!(&*container.name(), "a");
+
+        // The name is changed when the rename is committed.
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(database
+            .rename_container("a", "c".to_string(), &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert_eq!(&*container.name(), "a");
+        assert!(transaction.commit().await.is_ok());
+        assert_eq!(&*container.name(), "c");
+
+        // The rename is played back.
+        drop(database);
+        let database = Database::with_path(path).await.unwrap();
+        let snapshot = database.snapshot();
+        let container = database.get_container("c", &snapshot).await.unwrap();
+        assert_eq!(&*container.name(), "c");
+        drop(snapshot);
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn secondary_index() {
+        const DIR: &str = "container_secondary_index_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let first_byte = |_: &[u8], v: &[u8]| v.first().map(|b| vec![*b]);
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("data".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        let index = database
+            .create_container("index".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert!(container
+            .insert(b"1", b"a1", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(
+            container
+                .create_index(container.clone(), first_byte, &mut journal, None)
+                .await,
+            Err(Error::WrongParameter)
+        );
+
+        // Existing key-value pairs are indexed.
+        assert!(container
+            .create_index(index.clone(), first_byte, &mut journal, None)
+            .await
+            .is_ok());
+        assert!(container
+            .insert(b"2", b"b2", &mut journal, None)
+            .await
+            .is_ok());
+        assert!(container
+            .insert(b"3", b"a3", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        let snapshot = database.snapshot();
+        let keys = index.lookup_index(b"a", &snapshot, None).await;
+        assert_eq!(keys, Ok(vec![b"1".to_vec().into(), b"3".to_vec().into()]));
+        drop(snapshot);
+
+        // Index entries are rolled back along with the changes.
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .update(b"1", b"b1", &mut journal, None)
+            .await
+            .is_ok());
+        assert!(container.delete(b"3", &mut journal, None).await.is_ok());
+        let snapshot = journal.snapshot().combine(database.snapshot());
+        assert!(index
+            .lookup_index(b"a", &snapshot, None)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            index
+                .lookup_index(b"b", &snapshot, None)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+        drop(snapshot);
+        drop(journal);
+        transaction.rollback();
+        let snapshot = database.snapshot();
+        assert_eq!(
+            index
+                .lookup_index(b"a", &snapshot, None)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+        let keys = index.lookup_index(b"b", &snapshot, None).await;
+        assert_eq!(keys, Ok(vec![b"2".to_vec().into()]));
+        drop(snapshot);
+
+        // Optimistic transactions maintain indexes when committed.
+        let transaction = database.optimistic_transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .update_with(b"2", |_| b"c2".to_vec(), &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        let snapshot = database.snapshot();
+        assert!(index
+            .lookup_index(b"b", &snapshot, None)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            index.lookup_index(b"c", &snapshot, None).await,
+            Ok(vec![b"2".to_vec().into()])
+        );
+        drop(snapshot);
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn bulk_load() {
+        const DIR: &str = "container_bulk_load_test";
+        const NUM_KEYS: u32 = 3000;
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("bulk".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert!(container
+            .insert(&0_u32.to_be_bytes(), b"0", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        let pairs = |keys: std::ops::Range<u32>| keys.map(|k| (k.to_be_bytes(), k.to_le_bytes()));
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert_eq!(
+            container.bulk_load(pairs(0..2), &mut journal, None).await,
+            Err(Error::UniquenessViolation)
+        );
+        assert_eq!(
+            container
+                .bulk_load([(b"3", b"3"), (b"2", b"2")], &mut journal, None)
+                .await,
+            Err(Error::WrongParameter)
+        );
+        assert_eq!(
+            container
+                .bulk_load(pairs(1..NUM_KEYS), &mut journal, None)
+                .await,
+            Ok(NUM_KEYS as usize - 1)
+        );
+
+        // Keys are not locked individually, and the container is locked exclusively.
+        let lock_id = container
+            .records
+            .peek_with(NUM_KEYS.to_be_bytes().as_slice(), |_, r| r.lock_id);
+        assert!(lock_id.is_none());
+        let lock_id = container
+            .records
+            .peek_with((NUM_KEYS - 1).to_be_bytes().as_slice(), |_, r| r.lock_id)
+            .unwrap();
+        assert!(database
+            .access_controller()
+            .owners(lock_id)
+            .await
+            .is_empty());
+        let other_transaction = database.transaction();
+        let mut other_journal = other_transaction.journal();
+        assert_eq!(
+            container
+                .insert(b"other", b"other", &mut other_journal, None)
+                .await,
+            Err(Error::SerializationFailure)
+        );
+        drop(other_journal);
+        drop(other_transaction);
+
+        let key = 1234_u32.to_be_bytes();
+        assert_eq!(
+            container.get(&key, &database.snapshot(), None).await,
+            Ok(None)
+        );
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        let snapshot = database.snapshot();
+        for k in 1..NUM_KEYS {
+            assert_eq!(
+                container.get(&k.to_be_bytes(), &snapshot, None).await,
+                Ok(Some(k.to_le_bytes().to_vec()))
+            );
+        }
+        drop(snapshot);
+
+        // Optimistic transactions insert key-value pairs one by one.
+        let transaction = database.optimistic_transaction();
+        let mut journal = transaction.journal();
+        assert_eq!(
+            container
+                .bulk_load(pairs(NUM_KEYS..NUM_KEYS + 2), &mut journal, None)
+                .await,
+            Ok(2)
+        );
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        assert!(container
+            .get(&NUM_KEYS.to_be_bytes(), &database.snapshot(), None)
+            .await
+            .is_ok_and(|v| v.is_some()));
+
+        drop(container);
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn truncate() {
+        const DIR: &str = "container_truncate_test";
+        const NUM_KEYS: u32 = 1500;
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container(
+                "truncate".to_string(),
+                Metadata::default(),
+                &mut journal,
+                None,
+            )
+            .await
+            .unwrap();
+        let index = database
+            .create_container("index".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        let extractor = |_: &[u8], v: &[u8]| Some(v.to_vec());
+        assert!(container
+            .create_index(index.clone(), extractor, &mut journal, None)
+            .await
+            .is_ok());
+        let pairs = (0..NUM_KEYS).map(|k| (k.to_be_bytes(), k.to_le_bytes()));
+        assert_eq!(
+            container.bulk_load(pairs, &mut journal, None).await,
+            Ok(NUM_KEYS as usize)
+        );
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        let old_snapshot = database.snapshot();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .delete(&0_u32.to_be_bytes(), &mut journal, None)
+            .await
+            .is_ok());
+        assert!(container.truncate(&mut journal, None).await.is_ok());
+        assert!(container.truncate(&mut journal, None).await.is_ok());
+
+        let key = 1234_u32.to_be_bytes();
+        assert!(container
+            .get(&key, &database.snapshot(), None)
+            .await
+            .is_ok_and(|v| v.is_some()));
+        assert_eq!(container.read(&key, &mut journal, None).await, Ok(None));
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        // Older snapshots still see the key-value pairs.
+        let snapshot = database.snapshot();
+        let mut scanner = container.range::<&[u8], _>(.., &snapshot, None);
+        assert_eq!(scanner.next().await, Ok(None));
+        let mut scanner = index.range::<&[u8], _>(.., &snapshot, None);
+        assert_eq!(scanner.next().await, Ok(None));
+        drop(snapshot);
+        for k in 0..NUM_KEYS {
+            assert_eq!(
+                container.get(&k.to_be_bytes(), &old_snapshot, None).await,
+                Ok(Some(k.to_le_bytes().to_vec()))
+            );
+        }
+        assert_eq!(
+            index
+                .lookup_index(&1234_u32.to_le_bytes(), &old_snapshot, None)
+                .await
+                .map(|k| k.len()),
+            Ok(1)
+        );
+        drop(old_snapshot);
+
+        // Optimistic transactions delete key-value pairs one by one.
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .insert(&key, b"1", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        let transaction = database.optimistic_transaction();
+        let mut journal = transaction.journal();
+        assert!(container.truncate(&mut journal, None).await.is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        assert_eq!(
+            container.get(&key, &database.snapshot(), None).await,
+            Ok(None)
+        );
+
+        drop(index);
+        drop(container);
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn truncate_playback() {
+        const DIR: &str = "container_truncate_playback_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container(
+                "truncate".to_string(),
+                Metadata::default(),
+                &mut journal,
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(container
+            .insert(b"1", b"one", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        // Key-value pairs inserted after the truncation in the same transaction are kept.
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .insert(b"2", b"two", &mut journal, None)
+            .await
+            .is_ok());
+        assert!(container.truncate(&mut journal, None).await.is_ok());
+        assert!(container
+            .insert(b"3", b"three", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        // The garbage collector reclaims the truncated versions.
+        let condition = |i: &u64| *i <= database.sequencer().min(Relaxed);
+        let _: (u64, bool) = container.reclaim_versions_sync(&condition, || ());
+        assert!(!container.records.contains(b"1".as_slice()));
+        assert!(!container.records.contains(b"2".as_slice()));
+        assert_eq!(container.statistics().live_records, 1);
+
+        // A rolled back truncation is ignored.
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container.truncate(&mut journal, None).await.is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        transaction.rollback();
+
+        // The truncation is played back from the log.
+        drop(container);
+        drop(database);
+        let database = Database::with_path(path).await.unwrap();
+        let snapshot = database.snapshot();
+        let container = database.get_container("truncate", &snapshot).await.unwrap();
+        assert_eq!(container.get(b"1", &snapshot, None).await, Ok(None));
+        assert_eq!(container.get(b"2", &snapshot, None).await, Ok(None));
+        assert_eq!(
+            container.get(b"3", &snapshot, None).await,
+            Ok(Some(b"three".to_vec()))
+        );
+        drop(snapshot);
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
     }
 }
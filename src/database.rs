@@ -2,22 +2,48 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use super::task_processor::{Task, TaskProcessor};
+use super::catalog::Catalog;
+use super::change_stream::ChangeLog;
+use super::commit_watermark::CommitWatermark;
+#[cfg(feature = "diagnostics")]
+use super::diagnostics::AnchorRegistry;
+use super::journal::AwaitEOT;
+use super::task_processor::{Task, TaskProcessor, DEFAULT_CHECK_INTERAL};
+use super::transaction::Anchor as TransactionAnchor;
+use super::transaction::ID as TransactionID;
+use super::utils::Instant;
+#[cfg(feature = "diagnostics")]
+use super::LingeringAnchor;
 use super::{
-    AccessController, Container, Error, FileIO, Journal, Metadata, MonotonicU64, PersistenceLayer,
-    Sequencer, Snapshot, Transaction,
+    AccessController, ChangeStream, ConfigDelta, Container, ContainerStatistics, Counter,
+    DefaultPersistenceLayer, Error, Journal, Metadata, MonotonicU64, PersistenceLayer, Sequencer,
+    Session, Snapshot, Statistics, Telemetry, Transaction, TransactionReport, TransactionState,
+    VersionRecord, Watchdog,
 };
-use scc::{ebr, HashIndex};
+#[cfg(not(target_arch = "wasm32"))]
+use super::{Cipher, FileIO, IntegrityProblem, IntegrityReport, OpenOptions};
+use scc::{ebr, HashMap};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::poll_fn;
+#[cfg(not(target_arch = "wasm32"))]
 use std::path::Path;
-use std::sync::Arc;
-use std::time::Instant;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, SeqCst};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize};
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+use std::time::Duration;
 
 /// [`Database`] represents a single stand-alone transactional database.
 ///
 /// [`Database`] provides the interface for users to interact with each individual transactional
 /// [`Container`] in it.
 #[derive(Debug)]
-pub struct Database<S: Sequencer = MonotonicU64, P: PersistenceLayer<S> = FileIO<S>> {
+pub struct Database<
+    S: Sequencer = MonotonicU64,
+    P: PersistenceLayer<S> = DefaultPersistenceLayer<S>,
+> {
     /// The kernel of the database.
     ///
     /// The kernel of the database has to be allocated on the heap in order to provide stable
@@ -36,14 +62,82 @@ pub(super) struct Kernel<S: Sequencer, P: PersistenceLayer<S>> {
     /// The logical clock generator of the [`Database`].
     sequencer: S,
 
-    /// The container map.
-    container_map: HashIndex<String, ebr::Shared<Container<S, P>>>,
+    /// The [`Catalog`] of containers.
+    catalog: Catalog<S, P>,
 
     /// The database access controller.
-    access_controller: AccessController<S>,
+    access_controller: Arc<AccessController<S>>,
+
+    /// The database object identifier generator.
+    ///
+    /// Database object identifiers generated by the [`Database`] start from `1 << 63` in order
+    /// not to clash with those chosen by users of [`AccessController`].
+    object_id_generator: AtomicU64,
+
+    /// Transaction identifier generator.
+    ///
+    /// Transaction identifiers are generated values shifted left by three bits, and the first
+    /// transaction identifier is `8`.
+    transaction_id_generator: AtomicU64,
 
     /// The persistence layer of the database.
     persistence_layer: P,
+
+    /// Committed changes to be received by [`ChangeStream`] instances.
+    change_log: ChangeLog<S>,
+
+    /// Transactions being committed that hold the durable and visible clocks back.
+    commit_watermark: CommitWatermark<S>,
+
+    /// The default memory budget of a [`Transaction`] in bytes.
+    ///
+    /// `usize::MAX` means no limit.
+    transaction_memory_limit: AtomicUsize,
+
+    /// The number of record locks a [`Journal`] may hold in a [`Container`] before the locks are
+    /// escalated to a [`Container`] lock.
+    ///
+    /// `usize::MAX` means no escalation.
+    lock_escalation_threshold: AtomicUsize,
+
+    /// The maximum number of unreclaimed versions of a key-value pair.
+    ///
+    /// `usize::MAX` means no limit.
+    max_version_chain_length: AtomicUsize,
+
+    /// The interval in nanoseconds at which unreachable versions are reclaimed in the background.
+    gc_interval: AtomicU64,
+
+    /// Active transactions indexed by their identifiers.
+    active_transactions: HashMap<TransactionID, ebr::Shared<TransactionAnchor<S>>>,
+
+    /// The [`Watchdog`] that reports long-running transactions.
+    watchdog: Mutex<Option<Arc<Watchdog>>>,
+
+    /// The database is shut down, and new transactions are rejected.
+    shut_down: AtomicBool,
+
+    /// Transaction anchors that have not been dropped.
+    #[cfg(feature = "diagnostics")]
+    anchor_registry: AnchorRegistry,
+}
+
+/// [`ShutdownPolicy`] determines how [`Database::shutdown`] treats active transactions.
+///
+/// Transactions started after the database began shutting down are rejected regardless of the
+/// policy, and they fail to commit with [`Error::ShutDown`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum ShutdownPolicy {
+    /// Waits for active transactions to be committed or rolled back.
+    #[default]
+    Wait,
+
+    /// Aborts active transactions that have not been prepared for commit.
+    ///
+    /// The aborted transactions fail to commit with [`Error::ShutDown`], and the database waits
+    /// for them to be rolled back.
+    Abort,
 }
 
 impl<S: Sequencer, P: PersistenceLayer<S>> Database<S, P> {
@@ -71,11 +165,25 @@ impl<S: Sequencer, P: PersistenceLayer<S>> Database<S, P> {
         recover_until: Option<S::Instant>,
         deadline: Option<Instant>,
     ) -> Result<Database<S, P>, Error> {
+        let access_controller = Arc::<AccessController<S>>::default();
         let kernel = Arc::new(Kernel {
             sequencer: S::default(),
-            container_map: HashIndex::default(),
-            access_controller: AccessController::default(),
+            catalog: Catalog::new(access_controller.clone()),
+            access_controller,
+            object_id_generator: AtomicU64::new(1 << 63),
+            transaction_id_generator: AtomicU64::new(1),
             persistence_layer,
+            change_log: ChangeLog::default(),
+            commit_watermark: CommitWatermark::default(),
+            transaction_memory_limit: AtomicUsize::new(usize::MAX),
+            lock_escalation_threshold: AtomicUsize::new(usize::MAX),
+            max_version_chain_length: AtomicUsize::new(usize::MAX),
+            gc_interval: AtomicU64::new(duration_to_nanos(DEFAULT_CHECK_INTERAL)),
+            active_transactions: HashMap::default(),
+            watchdog: Mutex::default(),
+            shut_down: AtomicBool::new(false),
+            #[cfg(feature = "diagnostics")]
+            anchor_registry: AnchorRegistry::default(),
         });
         let task_processor = TaskProcessor::spawn(kernel.clone());
         let database = Database {
@@ -92,10 +200,26 @@ impl<S: Sequencer, P: PersistenceLayer<S>> Database<S, P> {
 
     /// Backs up the current snapshot of the database.
     ///
+    /// The database keeps serving transactions while being backed up, and the backup is a
+    /// standalone database that can be opened by the same type of the persistence layer.
+    ///
     /// # Errors
     ///
     /// Returns an error if the persistence layer failed to back up the database, memory allocation
     /// failed, or the deadline was reached.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("backup")).await.unwrap();
+    ///     assert!(database.backup(false, Some("backup_copy"), None).await.is_ok());
+    ///     let database_copy = Database::with_path(Path::new("backup_copy")).await.unwrap();
+    /// };
+    /// ```
     #[inline]
     pub async fn backup(
         &self,
@@ -109,6 +233,35 @@ impl<S: Sequencer, P: PersistenceLayer<S>> Database<S, P> {
             .await
     }
 
+    /// Takes a checkpoint of the database.
+    ///
+    /// The persistence layer persists the state of the database visible to the current snapshot
+    /// so that recovery does not depend on the log records preceding the checkpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the persistence layer failed to take a checkpoint, or the deadline was
+    /// reached.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("checkpoint")).await.unwrap();
+    ///     assert!(database.checkpoint(None).await.is_ok());
+    /// };
+    /// ```
+    #[inline]
+    pub async fn checkpoint(&self, deadline: Option<Instant>) -> Result<(), Error> {
+        self.kernel
+            .persistence_layer
+            .checkpoint(self, deadline)
+            .await
+    }
+
     /// Starts a [`Transaction`].
     ///
     /// # Examples
@@ -122,13 +275,22 @@ impl<S: Sequencer, P: PersistenceLayer<S>> Database<S, P> {
     ///     let transaction = database.transaction();
     /// };
     /// ```
+    #[cfg_attr(feature = "diagnostics", track_caller)]
     #[inline]
     #[must_use]
-    pub fn transaction(&self) -> Transaction<S, P> {
-        Transaction::new(self)
+    pub fn transaction(&self) -> Transaction<'_, S, P> {
+        Transaction::new(self, false)
     }
 
-    /// Captures the current state of the [`Database`] as a [`Snapshot`].
+    /// Starts an optimistic [`Transaction`].
+    ///
+    /// Accesses to key-value pairs in [`Container`] instances made by the optimistic transaction
+    /// never wait for other transactions, and they are buffered in the transaction until it is
+    /// prepared for commit. The transaction validates them when it is prepared for commit, and
+    /// returns [`Error::Conflict`] if any of them were modified by other transactions.
+    ///
+    /// Database objects accessed via [`Journal::create`] and
+    /// [`Journal::delete`] are locked as in a normal transaction.
     ///
     /// # Examples
     ///
@@ -137,17 +299,19 @@ impl<S: Sequencer, P: PersistenceLayer<S>> Database<S, P> {
     /// use std::path::Path;
     ///
     /// async {
-    ///     let database = Database::with_path(Path::new("snapshot")).await.unwrap();
-    ///     let snapshot = database.snapshot();
+    ///     let database = Database::with_path(Path::new("optimistic_transaction")).await.unwrap();
+    ///     let transaction = database.optimistic_transaction();
+    ///     assert!(transaction.commit().await.is_ok());
     /// };
     /// ```
+    #[cfg_attr(feature = "diagnostics", track_caller)]
     #[inline]
     #[must_use]
-    pub fn snapshot(&self) -> Snapshot<S> {
-        Snapshot::from_database(self)
+    pub fn optimistic_transaction(&self) -> Transaction<'_, S, P> {
+        Transaction::new(self, true)
     }
 
-    /// Returns a reference to its [`AccessController`].
+    /// Starts a [`Session`] providing read-your-writes consistency across transactions.
     ///
     /// # Examples
     ///
@@ -156,268 +320,1560 @@ impl<S: Sequencer, P: PersistenceLayer<S>> Database<S, P> {
     /// use std::path::Path;
     ///
     /// async {
-    ///     let database = Database::with_path(Path::new("access_controller")).await.unwrap();
-    ///     let access_controller = database.access_controller();
+    ///     let database = Database::with_path(Path::new("database_session")).await.unwrap();
+    ///     let session = database.session();
+    ///     assert!(session.commit(database.transaction()).await.is_ok());
     /// };
     /// ```
     #[inline]
     #[must_use]
-    pub fn access_controller(&self) -> &AccessController<S> {
-        self.kernel.access_controller()
+    pub fn session(&self) -> Session<'_, S, P> {
+        Session::new(self)
     }
 
-    /// Creates a new empty [`Container`].
-    ///
-    /// # Errors
+    /// Sets the default memory budget of [`Transaction`] instances in bytes.
     ///
-    /// If a [`Container`] exists under the specified name, returns an [`Error`].
+    /// The budget applies to transactions started after the method returns, and `None` means no
+    /// limit. See [`Transaction::set_memory_limit`].
     ///
     /// # Examples
     ///
     /// ```
-    /// use sap_tsf::{Database, Metadata};
+    /// use sap_tsf::Database;
     /// use std::path::Path;
     ///
     /// async {
-    ///     let database = Database::with_path(Path::new("create_container")).await.unwrap();
-    ///     let name = "hello".to_string();
-    ///     let metadata = Metadata::default();
-    ///     let transaction = database.transaction();
-    ///     let mut journal = transaction.journal();
-    ///     let result = database.create_container(name, metadata, &mut journal, None).await;
-    ///     assert!(result.is_ok());
+    ///     let database = Database::with_path(Path::new("transaction_memory_limit"))
+    ///         .await
+    ///         .unwrap();
+    ///     database.set_transaction_memory_limit(Some(1 << 20));
+    ///     assert_eq!(database.transaction_memory_limit(), Some(1 << 20));
+    ///     assert_eq!(database.transaction().memory_limit(), Some(1 << 20));
     /// };
     /// ```
     #[inline]
-    pub async fn create_container<'d, 't, 'j>(
-        &'d self,
-        name: String,
-        metadata: Metadata,
-        _journal: &'j mut Journal<'d, 't, S, P>,
-        _deadline: Option<Instant>,
-    ) -> Result<ebr::Shared<Container<S, P>>, Error> {
-        let _: &AccessController<S> = &self.kernel.access_controller;
-        let container = ebr::Shared::new(Container::new(metadata));
-        match self
-            .kernel
-            .container_map
-            .insert_async(name, container.clone())
-            .await
-        {
-            Ok(_) => Ok(container),
-            Err(_) => Err(Error::UniquenessViolation),
-        }
+    pub fn set_transaction_memory_limit(&self, limit: Option<usize>) {
+        self.kernel
+            .transaction_memory_limit
+            .store(limit.unwrap_or(usize::MAX), Relaxed);
     }
 
-    /// Renames an existing [`Container`].
-    ///
-    /// # Errors
+    /// Sets the number of record locks a [`Journal`] may hold in a [`Container`] before they are
+    /// escalated to a [`Container`] lock.
     ///
-    /// If no [`Container`] exists under the specified name, returns an [`Error`].
+    /// Once a [`Journal`] locks more records than the threshold in a [`Container`], it tries to
+    /// lock the [`Container`] in [`LockMode::Exclusive`](super::LockMode::Exclusive) mode without
+    /// waiting, and releases the record locks if successful; subsequent modifications to the
+    /// [`Container`] made by the transaction do not lock records. Escalation fails if another
+    /// transaction holds a lock on the [`Container`], in which case it is retried on the next
+    /// record lock. `None` disables lock escalation, which is the default.
     ///
     /// # Examples
     ///
     /// ```
-    /// use sap_tsf::{Database, Metadata};
+    /// use sap_tsf::Database;
     /// use std::path::Path;
     ///
     /// async {
-    ///     let database = Database::with_path(Path::new("rename_container")).await.unwrap();
-    ///     let name = "hello";
-    ///     let metadata = Metadata::default();
-    ///     let new_name = "hi".to_string();
-    ///     let transaction = database.transaction();
-    ///     let mut journal = transaction.journal();
-    ///     let create_result =
-    ///         database.create_container(name.to_string(), metadata, &mut journal, None).await;
-    ///     assert!(create_result.is_ok());
-    ///     let rename_result =
-    ///         database.rename_container(name, new_name, &mut journal, None).await;
-    ///     assert!(rename_result.is_ok());
+    ///     let database = Database::with_path(Path::new("lock_escalation_threshold"))
+    ///         .await
+    ///         .unwrap();
+    ///     assert!(database.lock_escalation_threshold().is_none());
+    ///     database.set_lock_escalation_threshold(Some(1024));
+    ///     assert_eq!(database.lock_escalation_threshold(), Some(1024));
     /// };
     /// ```
     #[inline]
-    pub async fn rename_container<'d, 't, 'j>(
-        &'d self,
-        name: &str,
-        new_name: String,
-        _journal: &'j mut Journal<'d, 't, S, P>,
-        _deadline: Option<Instant>,
-    ) -> Result<(), Error> {
-        if let Some(container) = self.kernel.container_map.peek_with(name, |_, c| c.clone()) {
-            if self
-                .kernel
-                .container_map
-                .insert_async(new_name, container)
-                .await
-                .is_ok()
-            {
-                Ok(())
-            } else {
-                Err(Error::UniquenessViolation)
-            }
-        } else {
-            Err(Error::NotFound)
-        }
+    pub fn set_lock_escalation_threshold(&self, threshold: Option<usize>) {
+        self.kernel
+            .lock_escalation_threshold
+            .store(threshold.unwrap_or(usize::MAX), Relaxed);
     }
 
-    /// Gets a reference to the [`Container`] under the specified name.
+    /// Returns the lock escalation threshold.
+    #[inline]
+    #[must_use]
+    pub fn lock_escalation_threshold(&self) -> Option<usize> {
+        let threshold = self.kernel.lock_escalation_threshold.load(Relaxed);
+        (threshold != usize::MAX).then_some(threshold)
+    }
+
+    /// Sets the maximum number of versions of a key-value pair that have yet to be reclaimed by
+    /// the garbage collector, including the latest one.
+    ///
+    /// A writer that would exceed the limit waits for the garbage collector to reclaim old
+    /// versions until the deadline is reached, and fails with
+    /// [`Error::VersionChainTooLong`] afterwards, or immediately if no deadline was specified;
+    /// versions visible to active snapshots are not reclaimed, therefore long-running readers
+    /// throttle writers of frequently updated key-value pairs. `None` removes the limit, which is
+    /// the default.
     ///
     /// # Examples
     ///
     /// ```
-    /// use sap_tsf::{Database, Metadata};
+    /// use sap_tsf::Database;
     /// use std::path::Path;
     ///
     /// async {
-    ///     let database = Database::with_path(Path::new("get_container")).await.unwrap();
-    ///     let transaction = database.transaction();
-    ///     let name = "hello";
-    ///     let metadata = Metadata::default();
-    ///     let mut journal = transaction.journal();
-    ///     let create_result =
-    ///         database.create_container(name.to_string(), metadata, &mut journal, None).await;
-    ///     assert!(create_result.is_ok());
-    ///     journal.submit();
-    ///     let snapshot = transaction.snapshot();
-    ///     let get_result = database.get_container(name, &snapshot).await;
-    ///     assert!(get_result.is_some());
+    ///     let database = Database::with_path(Path::new("max_version_chain_length"))
+    ///         .await
+    ///         .unwrap();
+    ///     assert!(database.max_version_chain_length().is_none());
+    ///     database.set_max_version_chain_length(Some(64));
+    ///     assert_eq!(database.max_version_chain_length(), Some(64));
     /// };
     /// ```
-    #[allow(clippy::unused_async)]
     #[inline]
-    pub async fn get_container<'d, 't, 'j, 'r>(
-        &'d self,
-        name: &str,
-        _snapshot: &'r Snapshot<'d, 't, 'j, S>,
-    ) -> Option<&'r Container<S, P>> {
-        self.kernel.container_map.peek_with(name, |_, c|
-            // Safety: `snapshot` is the proof that the returned reference stays valid at least for
-            // the lifetime of `snapshot`; even though the container is dropped, the data remains
-            // until it is garbage collected.
-            unsafe { std::mem::transmute::<&Container<S, P>, &'r Container<S, P>>(&**c) })
+    pub fn set_max_version_chain_length(&self, max_length: Option<usize>) {
+        self.kernel
+            .max_version_chain_length
+            .store(max_length.unwrap_or(usize::MAX), Relaxed);
     }
 
-    /// Drops a [`Container`] under the specified name.
+    /// Returns the maximum number of unreclaimed versions of a key-value pair.
+    #[inline]
+    #[must_use]
+    pub fn max_version_chain_length(&self) -> Option<usize> {
+        let max_length = self.kernel.max_version_chain_length.load(Relaxed);
+        (max_length != usize::MAX).then_some(max_length)
+    }
+
+    /// Returns the interval at which the background task processor reclaims versions of
+    /// key-value pairs that are no longer visible to any transactions.
+    ///
+    /// A shorter interval reclaims unreachable versions sooner at the cost of more frequent
+    /// scans of containers. The default interval is `1` minute, and it can be changed through
+    /// [`Database::reconfigure`].
+    #[inline]
+    #[must_use]
+    pub fn gc_interval(&self) -> Duration {
+        self.kernel.gc_interval()
+    }
+
+    /// Applies the changes to the runtime-tunable parameters of the [`Database`] and its
+    /// [`PersistenceLayer`] without reopening the database.
+    ///
+    /// The changes take effect for operations started after the method returns, and
+    /// [`Statistics::reconfigurations`] counts applied non-empty [`ConfigDelta`] instances.
     ///
     /// # Errors
     ///
-    /// Returns an [`Error`] if the container does not exist.
+    /// Returns [`Error::WrongParameter`] if the garbage collection interval is zero, in which case
+    /// no changes are applied.
     ///
     /// # Examples
     ///
     /// ```
-    /// use sap_tsf::{Database, Metadata};
+    /// use sap_tsf::{ConfigDelta, Database};
     /// use std::path::Path;
+    /// use std::time::Duration;
     ///
     /// async {
-    ///     let database = Database::with_path(Path::new("drop_container")).await.unwrap();
-    ///     let transaction = database.transaction();
-    ///     let name = "hello";
-    ///     let metadata = Metadata::default();
-    ///     let mut journal = transaction.journal();
-    ///     let create_result =
-    ///         database.create_container(name.to_string(), metadata, &mut journal, None).await;
-    ///     assert!(create_result.is_ok());
-    ///     journal.submit();
-    ///     let snapshot = transaction.snapshot();
-    ///     let mut journal = transaction.journal();
-    ///     let drop_result =
-    ///         database.drop_container(name, &snapshot, &mut journal, None).await;
-    ///     assert!(drop_result.is_ok());
+    ///     let database = Database::with_path(Path::new("reconfigure")).await.unwrap();
+    ///     let delta = ConfigDelta::new()
+    ///         .with_lock_timeout(Some(Duration::from_secs(1)))
+    ///         .with_gc_interval(Duration::from_secs(10));
+    ///     assert!(database.reconfigure(&delta).is_ok());
+    ///     assert_eq!(database.gc_interval(), Duration::from_secs(10));
+    ///     assert_eq!(database.statistics().reconfigurations, 1);
     /// };
     /// ```
     #[inline]
-    pub async fn drop_container<'d, 't, 'j>(
-        &'d self,
-        name: &str,
-        _snapshot: &Snapshot<'d, 't, 'j, S>,
-        _journal: &'j mut Journal<'d, 't, S, P>,
-        _deadline: Option<Instant>,
-    ) -> Result<(), Error> {
-        if self.kernel.container_map.remove_async(name).await {
-            Ok(())
-        } else {
-            Err(Error::NotFound)
+    pub fn reconfigure(&self, delta: &ConfigDelta) -> Result<(), Error> {
+        if delta.gc_interval.is_some_and(|i| i.is_zero()) {
+            return Err(Error::WrongParameter);
         }
-    }
-
-    /// Returns a reference to its [`Sequencer`].
-    pub(super) fn sequencer(&self) -> &S {
-        self.kernel.sequencer()
-    }
+        if delta.is_empty() {
+            return Ok(());
+        }
+        if let Some(limit) = delta.transaction_memory_limit {
+            self.set_transaction_memory_limit(limit);
+        }
+        if let Some(threshold) = delta.lock_escalation_threshold {
+            self.set_lock_escalation_threshold(threshold);
+        }
+        if let Some(max_length) = delta.max_version_chain_length {
+            self.set_max_version_chain_length(max_length);
+        }
+        let access_controller = self.access_controller();
+        if let Some(timeout) = delta.lock_timeout {
+            access_controller.set_lock_timeout(timeout);
+        }
+        if let Some(max_waiters) = delta.max_waiters {
+            access_controller.set_max_waiters(max_waiters);
+        }
+        if let Some(spin_limit) = delta.spin_limit {
+            access_controller.set_spin_limit(spin_limit);
+        }
+        if let Some(enabled) = delta.early_lock_release {
+            access_controller.set_early_lock_release(enabled);
+        }
+        if let Some(conflict_policy) = delta.conflict_policy {
+            access_controller.set_conflict_policy(conflict_policy);
+        }
+        if let Some(wait_policy) = delta.wait_policy {
+            access_controller.set_wait_policy(wait_policy);
+        }
+        if let Some(victim_policy) = delta.deadlock_detection {
+            access_controller.set_deadlock_detection(victim_policy);
+        }
+        if let Some(interval) = delta.gc_interval {
+            self.kernel
+                .gc_interval
+                .store(duration_to_nanos(interval), Relaxed);
+        }
+        self.kernel.persistence_layer.reconfigure(delta);
+        self.telemetry().add(Counter::Reconfigurations, 1);
 
-    /// Returns a reference to the [`PersistenceLayer`].
-    pub(super) fn persistence_layer(&self) -> &P {
-        &self.kernel.persistence_layer
+        // The background thread is woken up in order to apply the new interval; the result can be
+        // ignored since the background thread wakes up periodically anyway.
+        self.task_processor.send_task(Task::ScanAccessController);
+        Ok(())
     }
 
-    /// Returns a reference to its [`TaskProcessor`].
-    pub(super) fn task_processor(&self) -> &TaskProcessor {
-        &self.task_processor
+    /// Installs a [`Watchdog`] that reports long-running transactions.
+    ///
+    /// `None` uninstalls the current [`Watchdog`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Watchdog};
+    /// use std::path::Path;
+    /// use std::time::Duration;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("set_watchdog")).await.unwrap();
+    ///     database.set_watchdog(Some(Watchdog::new(Duration::from_secs(10))));
+    ///     database.set_watchdog(None);
+    /// };
+    /// ```
+    #[inline]
+    pub fn set_watchdog(&self, watchdog: Option<Watchdog>) {
+        if let Ok(mut current) = self.kernel.watchdog.lock() {
+            *current = watchdog.map(Arc::new);
+        }
+        // The result can be ignored since the background thread wakes up periodically anyway.
+        self.task_processor.send_task(Task::ScanAccessController);
     }
-}
 
-impl Database<MonotonicU64, FileIO<MonotonicU64>> {
-    /// Creates a new [`Database`] instance from the files in the specified path.
+    /// Returns [`TransactionReport`] instances of active transactions in identifier order.
     ///
-    /// The type of the sequencer is [`MonotonicU64`] and that of the persistence layer is
-    /// [`FileIO`].
+    /// # Examples
     ///
-    /// # Errors
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
     ///
-    /// Returns an error if the persistence layer failed to recover the database, memory allocation
-    /// failed.
+    /// async {
+    ///     let database = Database::with_path(Path::new("active_transactions")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let reports = database.active_transactions();
+    ///     assert_eq!(reports.len(), 1);
+    ///     assert_eq!(reports[0].id, transaction.id());
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn active_transactions(&self) -> Vec<TransactionReport> {
+        let now = Instant::now();
+        let mut reports = Vec::new();
+        self.kernel
+            .active_transactions
+            .scan(|id, anchor| reports.push(anchor.report(*id, now)));
+        reports.sort_unstable_by_key(|r| r.id);
+        reports
+    }
+
+    /// Returns the transactions that were committed or rolled back at least `threshold` ago while
+    /// their anchors are still referenced, the longest lingering one first.
     ///
     /// # Examples
     ///
     /// ```
     /// use sap_tsf::Database;
     /// use std::path::Path;
+    /// use std::time::Duration;
     ///
     /// async {
-    ///     let database = Database::with_path(Path::new("empty")).await.unwrap();
+    ///     let database = Database::with_path(Path::new("lingering_anchors")).await.unwrap();
+    ///     for lingering_anchor in database.lingering_anchors(Duration::from_secs(10)) {
+    ///         println!("{lingering_anchor:?}");
+    ///     }
     /// };
     /// ```
+    #[cfg(feature = "diagnostics")]
     #[inline]
-    pub async fn with_path(path: &Path) -> Result<Self, Error> {
-        let file_io = FileIO::<MonotonicU64>::with_path(path)?;
-        Self::with_persistence_layer(file_io, None, None).await
+    #[must_use]
+    pub fn lingering_anchors(&self, threshold: Duration) -> Vec<LingeringAnchor> {
+        self.kernel.anchor_registry.lingering(threshold)
     }
-}
 
-impl<S: Sequencer, P: PersistenceLayer<S>> Drop for Database<S, P> {
+    /// Returns the default memory budget of [`Transaction`] instances in bytes.
     #[inline]
-    fn drop(&mut self) {
-        while !self.task_processor.send_task(Task::Shutdown) {}
+    #[must_use]
+    pub fn transaction_memory_limit(&self) -> Option<usize> {
+        let limit = self.kernel.transaction_memory_limit.load(Relaxed);
+        (limit != usize::MAX).then_some(limit)
     }
-}
 
-impl<S: Sequencer, P: PersistenceLayer<S>> Kernel<S, P> {
-    /// Returns a reference to its own [`Sequencer`].
-    pub(super) fn sequencer(&self) -> &S {
-        &self.sequencer
+    /// Returns `true` if the background task processor of the [`Database`] has processed every
+    /// task sent to it.
+    ///
+    /// Lock waiters are woken up, and unreachable database objects are cleaned up in the
+    /// background, therefore a [`Simulator`](crate::simulation::Simulator) should wait for the
+    /// [`Database`] to be idle before choosing the next task to run.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("is_idle")).await.unwrap();
+    ///     while !database.is_idle() {
+    ///         std::thread::yield_now();
+    ///     }
+    /// };
+    /// ```
+    #[cfg(feature = "simulation")]
+    #[inline]
+    #[must_use]
+    pub fn is_idle(&self) -> bool {
+        self.task_processor.is_idle()
     }
 
-    /// Returns the [`Container`] identified as the name.
-    pub(super) fn container<'b>(
+    /// Processes the tasks sent to the background task processor of the [`Database`].
+    ///
+    /// Threads cannot be spawned on `wasm32`, therefore the host has to call this method
+    /// periodically, e.g., from a timer of the browser event loop; lock waiters are woken up,
+    /// and unreachable database objects are cleaned up only when this method is called. Returns
+    /// the duration after which this method should be called again, or `None` if tasks are being
+    /// processed by another call.
+    #[cfg(target_arch = "wasm32")]
+    #[inline]
+    #[must_use]
+    pub fn process_tasks(&self) -> Option<Duration> {
+        self.task_processor.process_pending_tasks()
+    }
+
+    /// Returns the current [`Statistics`] of the [`Database`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("statistics")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     assert!(transaction.commit().await.is_ok());
+    ///     assert!(database.statistics().transactions_committed >= 1);
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn statistics(&self) -> Statistics {
+        self.telemetry().statistics()
+    }
+
+    /// Returns the current [`Statistics`] of each tag attached to transactions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("tagged_statistics")).await.unwrap();
+    ///     let mut transaction = database.transaction();
+    ///     assert!(transaction.set_tag("tenant-a").is_ok());
+    ///     transaction.rollback();
+    ///     assert_eq!(database.tagged_statistics()["tenant-a"].transactions_rolled_back, 1);
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn tagged_statistics(&self) -> BTreeMap<String, Statistics> {
+        self.telemetry().tagged_statistics()
+    }
+
+    /// Resets the [`Statistics`] of the [`Database`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("reset_statistics")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     assert!(transaction.commit().await.is_ok());
+    ///     database.reset_statistics();
+    ///     assert_eq!(database.statistics().transactions_committed, 0);
+    /// };
+    /// ```
+    #[inline]
+    pub fn reset_statistics(&self) {
+        self.telemetry().reset();
+    }
+
+    /// Captures the current state of the [`Database`] as a [`Snapshot`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("snapshot")).await.unwrap();
+    ///     let snapshot = database.snapshot();
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn snapshot(&self) -> Snapshot<'_, '_, '_, S> {
+        Snapshot::from_database(self)
+    }
+
+    /// Captures the state of the [`Database`] at the specified past instant as a [`Snapshot`].
+    ///
+    /// The [`Snapshot`] only observes changes committed at or before `instant`, and the
+    /// [`Database`] keeps every database object visible to the [`Snapshot`] until the [`Snapshot`]
+    /// is dropped; therefore, a clock value can be pinned by holding a [`Snapshot`] of the clock
+    /// value.
+    ///
+    /// Returns `None` if `instant` is newer than the current clock value, or database objects
+    /// visible to `instant` may have been garbage collected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("snapshot_at")).await.unwrap();
+    ///     let instant = database.transaction().commit().await.unwrap();
+    ///     let snapshot = database.snapshot_at(instant);
+    ///     assert!(snapshot.is_some());
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn snapshot_at(&self, instant: S::Instant) -> Option<Snapshot<'_, '_, '_, S>> {
+        Snapshot::from_database_at(self, instant)
+    }
+
+    /// Returns the oldest clock value that a [`Snapshot`] in the [`Database`] may observe.
+    ///
+    /// Database object versions only visible to snapshots older than the returned value can be
+    /// garbage collected. The value is maintained by the [`Sequencer`] without scanning active
+    /// transactions or snapshots, therefore it may lag slightly behind.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("oldest_visible_clock")).await.unwrap();
+    ///     let instant = database.transaction().commit().await.unwrap();
+    ///     let snapshot = database.snapshot_at(instant).unwrap();
+    ///     assert!(database.oldest_visible_clock() <= instant);
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn oldest_visible_clock(&self) -> S::Instant {
+        self.sequencer().cached_min(Acquire)
+    }
+
+    /// Returns the clock value up to which every committed transaction is visible.
+    ///
+    /// Every transaction that was given a commit instant not greater than the returned value has
+    /// been committed or rolled back, so that a [`Snapshot`] of the clock value observes a stable
+    /// state of the database without waiting for transactions being committed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("visible_clock")).await.unwrap();
+    ///     let instant = database.transaction().commit().await.unwrap();
+    ///     assert!(database.visible_clock() >= instant);
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn visible_clock(&self) -> S::Instant {
+        self.commit_watermark().visible_clock(self.sequencer())
+    }
+
+    /// Returns the clock value up to which every committed transaction is durable.
+    ///
+    /// The commit log record of every transaction that was given a commit instant not greater
+    /// than the returned value has been persisted by the [`PersistenceLayer`] or the transaction
+    /// was rolled back; changes committed up to the clock value survive a crash as far as the
+    /// durability guarantee of the [`PersistenceLayer`] goes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("durable_clock")).await.unwrap();
+    ///     let instant = database.transaction().commit().await.unwrap();
+    ///     assert!(database.durable_clock() >= instant);
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn durable_clock(&self) -> S::Instant {
+        self.commit_watermark().durable_clock(self.sequencer())
+    }
+
+    /// Waits for [`Database::durable_clock`] to reach `clock`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Timeout`] if the durable clock did not reach `clock` until the deadline.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("await_durable")).await.unwrap();
+    ///     let instant = database.transaction().commit().await.unwrap();
+    ///     assert!(database.await_durable(instant, None).await.is_ok());
+    /// };
+    /// ```
+    #[inline]
+    pub async fn await_durable(
+        &self,
+        clock: S::Instant,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        poll_fn(|cx| {
+            if self
+                .commit_watermark()
+                .is_durable(clock, self.sequencer(), cx.waker())
+            {
+                return Poll::Ready(Ok(()));
+            }
+            if let Some(deadline) = deadline {
+                if deadline < Instant::now() {
+                    return Poll::Ready(Err(Error::Timeout));
+                } else if !self
+                    .task_processor()
+                    .send_task(Task::WakeUp(deadline, cx.waker().clone()))
+                {
+                    // The message channel is congested.
+                    cx.waker().wake_by_ref();
+                }
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Watches changes committed to the [`Database`].
+    ///
+    /// The returned [`ChangeStream`] receives changes committed after `since` in commit order, or
+    /// changes committed from now on if `since` is `None`.
+    ///
+    /// Returns `None` if `since` is newer than the current clock value, or changes committed
+    /// after `since` may have been discarded; committed changes are retained as long as a
+    /// [`Snapshot`] or [`ChangeStream`] that is not newer than the changes exists.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("watch")).await.unwrap();
+    ///     let instant = database.transaction().commit().await.unwrap();
+    ///     let change_stream = database.watch(Some(instant));
+    ///     assert!(change_stream.is_some());
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn watch(&self, since: Option<S::Instant>) -> Option<ChangeStream<'_, S, P>> {
+        let tracker = if let Some(since) = since {
+            self.sequencer().track_at(since, Acquire)?
+        } else {
+            self.sequencer().track(Acquire)
+        };
+        Some(ChangeStream::new(self, tracker))
+    }
+
+    /// Returns a reference to its [`AccessController`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("access_controller")).await.unwrap();
+    ///     let access_controller = database.access_controller();
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn access_controller(&self) -> &AccessController<S> {
+        self.kernel.access_controller()
+    }
+
+    /// Creates a new empty [`Container`].
+    ///
+    /// The [`Container`] is visible to other transactions under the name once the transaction is
+    /// committed, and it is discarded if the [`Journal`] or transaction is rolled back.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UniquenessViolation`] if a [`Container`] exists under the specified name,
+    /// or an [`Error`] if another transaction is creating, renaming, or dropping a [`Container`]
+    /// under the name and it did not end until the deadline was reached.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("create_container")).await.unwrap();
+    ///     let name = "hello".to_string();
+    ///     let metadata = Metadata::default();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let result = database.create_container(name, metadata, &mut journal, None).await;
+    ///     assert!(result.is_ok());
+    /// };
+    /// ```
+    #[inline]
+    pub async fn create_container<'d>(
+        &'d self,
+        name: String,
+        metadata: Metadata,
+        journal: &mut Journal<'d, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<ebr::Shared<Container<S, P>>, Error> {
+        self.kernel
+            .catalog
+            .create(name, metadata, journal, deadline)
+            .await
+    }
+
+    /// Renames an existing [`Container`].
+    ///
+    /// Other transactions see the [`Container`] under the new name once the transaction is
+    /// committed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NotFound`] if no [`Container`] exists under the specified name,
+    /// [`Error::UniquenessViolation`] if a [`Container`] exists under the new name, or an
+    /// [`Error`] if either name could not be locked until the deadline was reached.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("rename_container")).await.unwrap();
+    ///     let name = "hello";
+    ///     let metadata = Metadata::default();
+    ///     let new_name = "hi".to_string();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let create_result =
+    ///         database.create_container(name.to_string(), metadata, &mut journal, None).await;
+    ///     assert!(create_result.is_ok());
+    ///     let rename_result =
+    ///         database.rename_container(name, new_name, &mut journal, None).await;
+    ///     assert!(rename_result.is_ok());
+    /// };
+    /// ```
+    #[inline]
+    pub async fn rename_container<'d>(
+        &'d self,
+        name: &str,
+        new_name: String,
+        journal: &mut Journal<'d, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        self.kernel
+            .catalog
+            .rename(name, &new_name, journal, deadline)
+            .await
+    }
+
+    /// Gets a reference to the [`Container`] under the specified name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("get_container")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let name = "hello";
+    ///     let metadata = Metadata::default();
+    ///     let mut journal = transaction.journal();
+    ///     let create_result =
+    ///         database.create_container(name.to_string(), metadata, &mut journal, None).await;
+    ///     assert!(create_result.is_ok());
+    ///     journal.submit();
+    ///     let snapshot = transaction.snapshot();
+    ///     let get_result = database.get_container(name, &snapshot).await;
+    ///     assert!(get_result.is_some());
+    /// };
+    /// ```
+    #[inline]
+    pub async fn get_container<'d, 'r>(
+        &'d self,
+        name: &str,
+        snapshot: &'r Snapshot<'d, '_, '_, S>,
+    ) -> Option<&'r Container<S, P>> {
+        self.kernel.catalog.get(name, snapshot).await
+    }
+
+    /// Returns the [`ContainerStatistics`] of the containers visible to the [`Snapshot`] indexed
+    /// by their names.
+    ///
+    /// Containers having many versions awaiting the garbage collector relative to their key-value
+    /// pairs are bloated, e.g., by old snapshots preventing the versions from being reclaimed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the catalog could not be read until the deadline was reached.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("catalog_statistics")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let container = database
+    ///         .create_container("hello".to_string(), Metadata::default(), &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     assert!(container.insert(b"1", b"one", &mut journal, None).await.is_ok());
+    ///     journal.submit();
+    ///     assert!(transaction.commit().await.is_ok());
+    ///
+    ///     let snapshot = database.snapshot();
+    ///     let statistics = database.container_statistics(&snapshot, None).await.unwrap();
+    ///     assert_eq!(statistics["hello"].live_records, 1);
+    /// };
+    /// ```
+    #[inline]
+    pub async fn container_statistics(
+        &self,
+        snapshot: &Snapshot<'_, '_, '_, S>,
+        deadline: Option<Instant>,
+    ) -> Result<BTreeMap<String, ContainerStatistics>, Error> {
+        self.kernel.catalog.statistics(snapshot, deadline).await
+    }
+
+    /// Drops a [`Container`] under the specified name.
+    ///
+    /// The [`Container`] is locked in [`LockMode::Exclusive`](super::LockMode::Exclusive) mode
+    /// before being dropped, therefore it waits for transactions modifying the [`Container`] to be
+    /// ended. Snapshots taken before the transaction is committed still see the [`Container`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the container does not exist, or the container could not be locked
+    /// until the deadline was reached.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("drop_container")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let name = "hello";
+    ///     let metadata = Metadata::default();
+    ///     let mut journal = transaction.journal();
+    ///     let create_result =
+    ///         database.create_container(name.to_string(), metadata, &mut journal, None).await;
+    ///     assert!(create_result.is_ok());
+    ///     journal.submit();
+    ///     let snapshot = transaction.snapshot();
+    ///     let mut journal = transaction.journal();
+    ///     let drop_result =
+    ///         database.drop_container(name, &snapshot, &mut journal, None).await;
+    ///     assert!(drop_result.is_ok());
+    /// };
+    /// ```
+    #[inline]
+    pub async fn drop_container<'d>(
+        &'d self,
+        name: &str,
+        _snapshot: &Snapshot<'d, '_, '_, S>,
+        journal: &mut Journal<'d, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        self.kernel.catalog.drop(name, journal, deadline).await
+    }
+
+    /// Aborts an active transaction from a thread that does not own it.
+    ///
+    /// Requests of the transaction waiting for database objects fail with [`Error::Aborted`], and
+    /// so do subsequent requests and [`Transaction::prepare`](super::Transaction::prepare); the
+    /// transaction is rolled back when the owner drops it or rolls it back. If a deadline is
+    /// specified, the method waits for the transaction to be rolled back until the deadline.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NotFound`] if no active transaction has the identifier,
+    /// [`Error::UnexpectedState`] if the transaction began being prepared or was committed, and [`Error::Timeout`] if the transaction was not rolled back until the
+    /// deadline.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Error};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("abort_transaction")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     assert!(database.abort_transaction(transaction.id(), None).await.is_ok());
+    ///     assert_eq!(transaction.commit().await, Err(Error::Aborted));
+    /// };
+    /// ```
+    #[inline]
+    pub async fn abort_transaction(
+        &self,
+        transaction_id: TransactionID,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        let anchor = self
+            .kernel
+            .active_transactions
+            .read_async(&transaction_id, |_, anchor| anchor.clone())
+            .await
+            .ok_or(Error::NotFound)?;
+        if !anchor.abort() {
+            return Err(Error::UnexpectedState);
+        }
+
+        // Waiting requests are rejected by the task processor.
+        self.task_processor().send_task(Task::ScanAccessController);
+
+        let Some(deadline) = deadline else {
+            return Ok(());
+        };
+        AwaitEOT::new(anchor.clone(), self.task_processor(), deadline).await?;
+        if anchor.transaction_state() == TransactionState::RolledBack {
+            Ok(())
+        } else {
+            Err(Error::UnexpectedState)
+        }
+    }
+
+    /// Shuts down the database.
+    ///
+    /// New transactions are rejected, active transactions are treated according to the
+    /// [`ShutdownPolicy`], and then the buffered log records and pages are flushed by the
+    /// persistence layer which marks the database as cleanly shut down; the persistence layer may
+    /// skip part of recovery when the database is opened again. The [`Database`] can be dropped
+    /// after the method returns.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Timeout`] if active transactions did not end until the deadline, or
+    /// immediately if no deadline is specified, and an error if the persistence layer could not
+    /// flush the data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Error, ShutdownPolicy};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("shutdown")).await.unwrap();
+    ///     assert!(database.shutdown(ShutdownPolicy::Wait, None).await.is_ok());
+    ///     assert!(database.is_shut_down());
+    ///     let transaction = database.transaction();
+    ///     assert_eq!(transaction.commit().await, Err(Error::ShutDown));
+    /// };
+    /// ```
+    #[inline]
+    pub async fn shutdown(
+        &self,
+        policy: ShutdownPolicy,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        self.kernel.shut_down.store(true, SeqCst);
+        let mut active_transactions = Vec::new();
+        self.kernel
+            .active_transactions
+            .scan_async(|_, anchor| active_transactions.push(anchor.clone()))
+            .await;
+        if policy == ShutdownPolicy::Abort {
+            for anchor in &active_transactions {
+                anchor.wound();
+            }
+        }
+        for anchor in active_transactions {
+            if anchor.eot_instant().is_some() {
+                continue;
+            }
+            let Some(deadline) = deadline else {
+                return Err(Error::Timeout);
+            };
+            AwaitEOT::new(anchor, self.task_processor(), deadline).await?;
+        }
+        self.kernel
+            .persistence_layer
+            .checkpoint(self, deadline)
+            .await?;
+        self.kernel.persistence_layer.shutdown(self, deadline).await
+    }
+
+    /// Returns `true` if the database is shut down.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("is_shut_down")).await.unwrap();
+    ///     assert!(!database.is_shut_down());
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn is_shut_down(&self) -> bool {
+        self.kernel.shut_down.load(SeqCst)
+    }
+
+    /// Returns a reference to its [`Sequencer`].
+    ///
+    /// A [`PersistenceLayer`] persists the clock of the [`Sequencer`] when the [`Database`] is
+    /// shut down, and advances the clock with [`Sequencer::update`] when the [`Database`] is
+    /// recovered, so that clock values issued without leaving log records behind are not reused.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Sequencer};
+    /// use std::path::Path;
+    /// use std::sync::atomic::Ordering::Acquire;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("sequencer")).await.unwrap();
+    ///     let instant = database.transaction().commit().await.unwrap();
+    ///     assert!(database.sequencer().now(Acquire) >= instant);
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn sequencer(&self) -> &S {
+        self.kernel.sequencer()
+    }
+
+    /// Returns a reference to its [`ChangeLog`].
+    pub(super) fn change_log(&self) -> &ChangeLog<S> {
+        self.kernel.change_log()
+    }
+
+    /// Returns a reference to its [`CommitWatermark`].
+    pub(super) fn commit_watermark(&self) -> &CommitWatermark<S> {
+        &self.kernel.commit_watermark
+    }
+
+    /// Returns a reference to the [`Container`] identified as the identifier.
+    pub(super) fn container<'b>(
+        &self,
+        container_id: u64,
+        barrier: &'b ebr::Guard,
+    ) -> Option<&'b Container<S, P>> {
+        self.kernel.container(container_id, barrier)
+    }
+
+    /// Returns a shared reference to the [`Container`] identified as the identifier.
+    ///
+    /// Unlike [`Database::container`], the [`Container`] can be used across suspension points.
+    pub(super) fn shared_container(
+        &self,
+        container_id: u64,
+    ) -> Option<ebr::Shared<Container<S, P>>> {
+        self.kernel.shared_container(container_id)
+    }
+
+    /// Returns the [`Container`] under the specified name that is visible to the [`Journal`].
+    pub(super) async fn journal_container(
+        &self,
+        name: &str,
+        journal: &mut Journal<'_, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<Option<ebr::Shared<Container<S, P>>>, Error> {
+        self.kernel.catalog.read(name, journal, deadline).await
+    }
+
+    /// Installs a version of a key-value pair written to the log while the [`Database`] is being
+    /// recovered.
+    ///
+    /// Returns the identifier and the new name of the [`Container`] renamed by the version.
+    pub(super) fn playback_version(&self, version: &VersionRecord<'_>) -> Option<(u64, Arc<str>)> {
+        self.reserve_object_id(version.record_id());
+        self.kernel.catalog.playback_version(version, self)
+    }
+
+    /// Changes the names of the renamed containers after the transaction renaming them is
+    /// committed.
+    pub(super) fn rename_containers<I: IntoIterator<Item = (u64, Arc<str>)>>(
+        &self,
+        renamed_containers: I,
+    ) {
+        let guard = ebr::Guard::new();
+        for (container_id, name) in renamed_containers {
+            if let Some(container) = self.container(container_id, &guard) {
+                container.rename(name);
+            }
+        }
+    }
+
+    /// Passes the catalog entries visible to the [`Snapshot`] to `visitor` as versions of the
+    /// catalog [`Container`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) async fn scan_catalog_versions<F: FnMut(&VersionRecord<'_>)>(
+        &self,
+        snapshot: &Snapshot<'_, '_, '_, S>,
+        deadline: Option<Instant>,
+        visitor: F,
+    ) -> Result<(), Error> {
+        self.kernel
+            .catalog
+            .scan_visible_versions(snapshot, deadline, visitor)
+            .await
+    }
+
+    /// Returns the identifiers and [`Metadata`] of the [`Container`] instances visible to the
+    /// [`Snapshot`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) async fn visible_containers(
+        &self,
+        snapshot: &Snapshot<'_, '_, '_, S>,
+        deadline: Option<Instant>,
+    ) -> Result<Vec<(u64, Metadata)>, Error> {
+        self.kernel
+            .catalog
+            .visible_containers(snapshot, deadline)
+            .await
+    }
+
+    /// Passes the versions of the [`Container`] visible to the [`Snapshot`] to `visitor`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) async fn scan_container_versions<F: FnMut(&VersionRecord<'_>)>(
+        &self,
+        container_id: u64,
+        snapshot: &Snapshot<'_, '_, '_, S>,
+        deadline: Option<Instant>,
+        visitor: F,
+    ) -> Result<(), Error> {
+        self.kernel
+            .catalog
+            .scan_container_versions(container_id, snapshot, deadline, visitor)
+            .await
+    }
+
+    /// Installs a version of a key-value pair persisted by a checkpoint while the [`Database`] is
+    /// being recovered.
+    ///
+    /// The version is installed below the versions replayed from the log.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) fn playback_persisted_version(&self, version: &VersionRecord<'_>) {
+        self.reserve_object_id(version.record_id());
+        self.kernel
+            .catalog
+            .playback_persisted_version(version, self);
+    }
+
+    /// Passes the index of a [`Container`] persisted by a checkpoint to the [`Container`] while
+    /// the [`Database`] is being recovered.
+    ///
+    /// Versions of keys that are not replayed from the log are kept in the index instead of being
+    /// installed by [`playback_persisted_version`](Self::playback_persisted_version).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) fn playback_persisted_index(&self, container_id: u64, index: P::PersistedIndex) {
+        self.kernel
+            .catalog
+            .playback_persisted_index(container_id, index);
+    }
+
+    /// Sets the number of database pages of the index of a [`Container`] persisted by a
+    /// checkpoint.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) fn set_persisted_pages(&self, container_id: u64, num_pages: u64) {
+        if let Some(container) = self.container(container_id, &ebr::Guard::new()) {
+            container.set_persisted_pages(num_pages);
+        }
+    }
+
+    /// Quarantines a [`Container`] of which the persisted index is corrupt while the [`Database`]
+    /// is being recovered.
+    ///
+    /// Accesses to key-value pairs of the [`Container`] fail with the error, whereas the other
+    /// containers stay readable and writable.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) fn playback_quarantine(&self, container_id: u64, error: Error) {
+        self.kernel.catalog.playback_quarantine(container_id, error);
+    }
+
+    /// Returns the identifiers that the next database object and transaction are assigned.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) fn next_ids(&self) -> (u64, TransactionID) {
+        (
+            self.kernel.object_id_generator.load(Relaxed),
+            self.kernel.transaction_id_generator.load(Relaxed) << 3,
+        )
+    }
+
+    /// Prevents the database object and transaction identifiers below the supplied ones from
+    /// being generated in the future.
+    ///
+    /// This is used when the identifiers returned by [`next_ids`](Self::next_ids) are recovered
+    /// from the persistence layer.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) fn reserve_ids(&self, next_object_id: u64, next_transaction_id: TransactionID) {
+        self.kernel
+            .object_id_generator
+            .fetch_max(next_object_id, Relaxed);
+        self.kernel
+            .transaction_id_generator
+            .fetch_max(next_transaction_id >> 3, Relaxed);
+    }
+
+    /// Generates a new database object identifier.
+    pub(super) fn new_object_id(&self) -> u64 {
+        self.kernel.object_id_generator.fetch_add(1, Relaxed)
+    }
+
+    /// Generates a new transaction identifier.
+    pub(super) fn new_transaction_id(&self) -> TransactionID {
+        self.kernel.transaction_id_generator.fetch_add(1, Relaxed) << 3
+    }
+
+    /// Prevents the transaction identifier from being generated in the future.
+    ///
+    /// This is used when transactions are recovered from the persistence layer.
+    pub(super) fn reserve_transaction_id(&self, transaction_id: TransactionID) {
+        self.kernel
+            .transaction_id_generator
+            .fetch_max((transaction_id >> 3).saturating_add(1), Relaxed);
+    }
+
+    /// Prevents the database object identifier from being generated in the future.
+    ///
+    /// This is used when database objects are recovered from the persistence layer.
+    pub(super) fn reserve_object_id(&self, object_id: u64) {
+        self.kernel
+            .object_id_generator
+            .fetch_max(object_id.saturating_add(1), Relaxed);
+    }
+
+    /// Returns a reference to the [`PersistenceLayer`].
+    pub(super) fn persistence_layer(&self) -> &P {
+        &self.kernel.persistence_layer
+    }
+
+    /// Returns a reference to the [`Telemetry`] of the [`Database`].
+    pub(super) fn telemetry(&self) -> &Telemetry {
+        self.kernel.telemetry()
+    }
+
+    /// Returns a reference to its [`TaskProcessor`].
+    pub(super) fn task_processor(&self) -> &TaskProcessor {
+        &self.task_processor
+    }
+
+    /// Registers an active transaction.
+    ///
+    /// Returns `false` if the database is shut down, in which case the transaction is not
+    /// registered.
+    pub(super) fn register_transaction(
+        &self,
+        id: TransactionID,
+        anchor: &ebr::Shared<TransactionAnchor<S>>,
+    ) -> bool {
+        let _: Result<(), _> = self.kernel.active_transactions.insert(id, anchor.clone());
+        if self.is_shut_down() {
+            // `shutdown` may not have observed the transaction.
+            self.kernel.active_transactions.remove(&id);
+            return false;
+        }
+        true
+    }
+
+    /// Deregisters a transaction that has been committed or rolled back.
+    pub(super) fn deregister_transaction(&self, id: TransactionID) {
+        #[cfg(feature = "diagnostics")]
+        self.kernel
+            .active_transactions
+            .read(&id, |_, anchor| anchor.record_end());
+        self.kernel.active_transactions.remove(&id);
+    }
+
+    /// Returns the registry of transaction anchors.
+    #[cfg(feature = "diagnostics")]
+    pub(super) fn anchor_registry(&self) -> &AnchorRegistry {
+        &self.kernel.anchor_registry
+    }
+}
+
+impl<S: Sequencer<Instant = u64>, P: PersistenceLayer<S>> Database<S, P> {
+    /// Captures the state of the [`Database`] at the clock value exported by
+    /// [`Snapshot::export`] as a [`Snapshot`].
+    ///
+    /// A coordinator can export a [`Snapshot`] and hand it to worker processes reading replicas
+    /// of the same database, so that all of them read the database at a consistent clock value.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::WrongParameter`] if the bytes were not exported by [`Snapshot::export`],
+    /// or [`Error::NotFound`] if the replica has yet to reach the clock value, or database objects
+    /// visible to the clock value may have been garbage collected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Error};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("snapshot_from")).await.unwrap();
+    ///     let exported = database.snapshot().export();
+    ///     assert!(database.snapshot_from(&exported).is_ok());
+    ///     assert_eq!(
+    ///         database.snapshot_from(&exported[1..]).err(),
+    ///         Some(Error::WrongParameter)
+    ///     );
+    /// };
+    /// ```
+    #[inline]
+    pub fn snapshot_from(&self, exported: &[u8]) -> Result<Snapshot<'_, '_, '_, S>, Error> {
+        let clock = Snapshot::<S>::import(exported)?;
+        self.snapshot_at(clock).ok_or(Error::NotFound)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Database<MonotonicU64, FileIO<MonotonicU64>> {
+    /// Creates a new [`Database`] instance from the files in the specified path.
+    ///
+    /// The type of the sequencer is [`MonotonicU64`] and that of the persistence layer is
+    /// [`FileIO`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the persistence layer failed to recover the database, memory allocation
+    /// failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("empty")).await.unwrap();
+    /// };
+    /// ```
+    #[inline]
+    pub async fn with_path(path: &Path) -> Result<Self, Error> {
+        let file_io = FileIO::<MonotonicU64>::with_path(path)?;
+        Self::with_persistence_layer(file_io, None, None).await
+    }
+
+    /// Creates a new [`Database`] instance from the files in the specified path with the specified
+    /// page size.
+    ///
+    /// The page size is only used when a new database is created, and it must be identical to
+    /// that of the existing database otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the page size is invalid or different from that of the existing
+    /// database, the persistence layer failed to recover the database, or memory allocation
+    /// failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_page_size(Path::new("page_size"), 4096).await.unwrap();
+    /// };
+    /// ```
+    #[inline]
+    pub async fn with_page_size(path: &Path, page_size: u64) -> Result<Self, Error> {
+        let file_io = FileIO::<MonotonicU64>::with_page_size(path, page_size)?;
+        Self::with_persistence_layer(file_io, None, None).await
+    }
+
+    /// Creates a new [`Database`] instance from the files in the specified path that encrypts
+    /// database files using the supplied [`Cipher`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database files are not encrypted, the persistence layer failed to
+    /// recover the database, or memory allocation failed.
+    #[inline]
+    pub async fn with_cipher(path: &Path, cipher: Arc<dyn Cipher>) -> Result<Self, Error> {
+        let file_io = FileIO::<MonotonicU64>::with_cipher(path, cipher)?;
+        Self::with_persistence_layer(file_io, None, None).await
+    }
+
+    /// Creates a new [`Database`] instance from the files in the specified path, and upgrades the
+    /// database files to the current version if they were created by an older version.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database files were created by a newer version, migration failed,
+    /// the persistence layer failed to recover the database, or memory allocation failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::open_and_migrate(Path::new("migrate")).await.unwrap();
+    /// };
+    /// ```
+    #[inline]
+    pub async fn open_and_migrate(path: &Path) -> Result<Self, Error> {
+        let file_io = FileIO::<MonotonicU64>::open_and_migrate(path)?;
+        Self::with_persistence_layer(file_io, None, None).await
+    }
+
+    /// Creates a new [`Database`] instance from the files in the specified path opened with the
+    /// specified [`OpenOptions`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the options are invalid, the database files could not be opened, the
+    /// persistence layer failed to recover the database, or memory allocation failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Durability, OpenOptions};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let options = OpenOptions::new()
+    ///         .with_truncate(true)
+    ///         .with_durability(Durability::OsBuffered);
+    ///     let database = Database::with_options(Path::new("options"), &options).await.unwrap();
+    /// };
+    /// ```
+    #[inline]
+    pub async fn with_options(path: &Path, options: &OpenOptions) -> Result<Self, Error> {
+        let file_io = options.open::<MonotonicU64>(path)?;
+        Self::with_persistence_layer(file_io, None, None).await
+    }
+
+    /// Defragments the database file, and truncates trailing free space.
+    ///
+    /// Returns the length of the database file after defragmentation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the persistence layer failed to defragment the database file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("defragment")).await.unwrap();
+    ///     assert!(database.defragment().await.is_ok());
+    /// };
+    /// ```
+    #[inline]
+    pub async fn defragment(&self) -> Result<u64, Error> {
+        self.kernel.persistence_layer.defragment().await
+    }
+
+    /// Vacuums the database file.
+    ///
+    /// Pages written by the latest checkpoint are moved to free pages nearer the front of the
+    /// database file, and trailing free space is truncated. Returns the length of the database
+    /// file after vacuuming.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ReadOnly`] if the database is read-only, or an error if the persistence
+    /// layer failed to vacuum the database file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("vacuum")).await.unwrap();
+    ///     assert!(database.checkpoint(None).await.is_ok());
+    ///     assert!(database.vacuum().await.is_ok());
+    /// };
+    /// ```
+    #[inline]
+    pub async fn vacuum(&self) -> Result<u64, Error> {
+        self.kernel.persistence_layer.vacuum().await
+    }
+
+    /// Verifies the database files, and returns an [`IntegrityReport`] describing the problems
+    /// found.
+    ///
+    /// In addition to the checks made by [`FileIO::verify`], the logical clock persisted in the
+    /// database file is checked against the clock of the [`Database`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("verify")).await.unwrap();
+    ///     let report = database.verify().await;
+    ///     assert!(report.is_ok(), "{:?}", report.problems);
+    /// };
+    /// ```
+    #[inline]
+    pub async fn verify(&self) -> IntegrityReport {
+        let mut report = self.kernel.persistence_layer.verify().await;
+        let current = self.sequencer().now(Relaxed);
+        if report.clock > current {
+            report.problems.push(IntegrityProblem::ClockAhead {
+                persisted: report.clock,
+                current,
+            });
+        }
+        report
+    }
+}
+
+/// Converts a [`Duration`] into nanoseconds saturating at `u64::MAX`.
+fn duration_to_nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<S: Sequencer, P: PersistenceLayer<S>> Drop for Database<S, P> {
+    #[inline]
+    fn drop(&mut self) {
+        while !self.task_processor.send_task(Task::Shutdown) {}
+    }
+}
+
+impl<S: Sequencer, P: PersistenceLayer<S>> Kernel<S, P> {
+    /// Returns the installed [`Watchdog`].
+    pub(super) fn watchdog(&self) -> Option<Arc<Watchdog>> {
+        self.watchdog.lock().ok().and_then(|w| w.clone())
+    }
+
+    /// Calls `f` with each active transaction.
+    pub(super) fn scan_active_transactions<F: FnMut(TransactionID, &TransactionAnchor<S>)>(
         &self,
-        name: &str,
+        mut f: F,
+    ) {
+        self.active_transactions.scan(|id, anchor| f(*id, anchor));
+    }
+
+    /// Returns the interval at which unreachable versions are reclaimed in the background.
+    pub(super) fn gc_interval(&self) -> Duration {
+        Duration::from_nanos(self.gc_interval.load(Relaxed))
+    }
+
+    /// Returns a reference to its own [`Sequencer`].
+    pub(super) fn sequencer(&self) -> &S {
+        &self.sequencer
+    }
+
+    /// Returns the [`Container`] identified as the identifier.
+    pub(super) fn container<'b>(
+        &self,
+        container_id: u64,
         barrier: &'b ebr::Guard,
     ) -> Option<&'b Container<S, P>> {
-        self.container_map.peek(name, barrier).map(|c| c.as_ref())
+        self.catalog.container(container_id, barrier)
+    }
+
+    /// Returns a shared reference to the [`Container`] identified as the identifier.
+    pub(super) fn shared_container(
+        &self,
+        container_id: u64,
+    ) -> Option<ebr::Shared<Container<S, P>>> {
+        self.catalog.shared_container(container_id)
     }
 
     /// Returns a reference to its [`AccessController`].
     pub(super) fn access_controller(&self) -> &AccessController<S> {
         &self.access_controller
     }
+
+    /// Returns a reference to its [`ChangeLog`].
+    pub(super) fn change_log(&self) -> &ChangeLog<S> {
+        &self.change_log
+    }
+
+    /// Returns a reference to the [`Telemetry`] of its [`PersistenceLayer`].
+    pub(super) fn telemetry(&self) -> &Telemetry {
+        self.persistence_layer.telemetry()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Database;
+    use crate::{Database, IndexType, Playback, VictimPolicy};
     use std::sync::atomic::Ordering::Relaxed;
     use std::sync::Arc;
     use tokio::fs::remove_dir_all;
@@ -444,4 +1900,513 @@ mod tests {
 
         assert!(remove_dir_all(path).await.is_ok());
     }
+
+    #[tokio::test]
+    async fn statistics() {
+        const DIR: &str = "database_statistics_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        database.reset_statistics();
+
+        let transaction_1 = database.transaction();
+        let mut journal_1 = transaction_1.journal();
+        assert!(journal_1.create(&[1], None).await.is_ok());
+        assert_eq!(journal_1.submit().get(), 1);
+
+        let transaction_2 = database.transaction();
+        let mut journal_2 = transaction_2.journal();
+        let deadline = Instant::now() + std::time::Duration::from_millis(1);
+        assert_eq!(
+            database
+                .access_controller()
+                .lock(1, &mut journal_2, Some(deadline))
+                .await,
+            Err(Error::Timeout)
+        );
+        drop(journal_2);
+        transaction_2.rollback();
+        assert!(transaction_1.commit().await.is_ok());
+
+        let statistics = database.statistics();
+        assert_eq!(statistics.transactions_started, 2);
+        assert_eq!(statistics.transactions_committed, 1);
+        assert_eq!(statistics.transactions_rolled_back, 1);
+        assert_eq!(statistics.lock_waits, 1);
+        assert_eq!(statistics.lock_timeouts, 1);
+        assert!(statistics.log_bytes_written > 0);
+        assert!(statistics.fsyncs > 0);
+
+        database.reset_statistics();
+        assert_eq!(database.statistics().transactions_started, 0);
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn reconfigure() {
+        const DIR: &str = "database_reconfigure_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        database.reset_statistics();
+
+        let gc_interval = database.gc_interval();
+        assert_eq!(
+            database.reconfigure(
+                &ConfigDelta::new()
+                    .with_max_waiters(Some(4))
+                    .with_gc_interval(Duration::ZERO)
+            ),
+            Err(Error::WrongParameter)
+        );
+        assert_eq!(database.access_controller().max_waiters(), None);
+        assert_eq!(database.gc_interval(), gc_interval);
+        assert!(database.reconfigure(&ConfigDelta::new()).is_ok());
+        assert_eq!(database.statistics().reconfigurations, 0);
+
+        let delta = ConfigDelta::new()
+            .with_transaction_memory_limit(Some(1 << 20))
+            .with_lock_escalation_threshold(Some(64))
+            .with_max_version_chain_length(Some(16))
+            .with_lock_timeout(Some(Duration::from_millis(1)))
+            .with_max_waiters(Some(4))
+            .with_spin_limit(4)
+            .with_early_lock_release(true)
+            .with_deadlock_detection(Some(VictimPolicy::LeastWork))
+            .with_gc_interval(Duration::from_millis(100))
+            .with_dirty_page_threshold(Some(50))
+            .with_log_capacity(None)
+            .with_log_retention(Some(1 << 20));
+        assert!(database.reconfigure(&delta).is_ok());
+        assert_eq!(database.transaction_memory_limit(), Some(1 << 20));
+        assert_eq!(database.lock_escalation_threshold(), Some(64));
+        assert_eq!(database.max_version_chain_length(), Some(16));
+        assert_eq!(
+            database.access_controller().lock_timeout(),
+            Some(Duration::from_millis(1))
+        );
+        assert_eq!(database.access_controller().max_waiters(), Some(4));
+        assert_eq!(database.access_controller().spin_limit(), 4);
+        assert!(database.access_controller().early_lock_release());
+        assert_eq!(
+            database.access_controller().deadlock_detection(),
+            Some(VictimPolicy::LeastWork)
+        );
+        assert_eq!(database.gc_interval(), Duration::from_millis(100));
+        assert_eq!(database.statistics().reconfigurations, 1);
+
+        // The lock timeout shortens the deadline.
+        let transaction_1 = database.transaction();
+        let mut journal_1 = transaction_1.journal();
+        assert!(journal_1.create(&[1], None).await.is_ok());
+        assert_eq!(journal_1.submit().get(), 1);
+        let transaction_2 = database.transaction();
+        let mut journal_2 = transaction_2.journal();
+        let deadline = Instant::now() + Duration::from_mins(1);
+        assert_eq!(
+            database
+                .access_controller()
+                .lock(1, &mut journal_2, Some(deadline))
+                .await,
+            Err(Error::Timeout)
+        );
+        drop(journal_2);
+        transaction_2.rollback();
+        assert!(transaction_1.commit().await.is_ok());
+        assert_eq!(database.statistics().lock_timeouts, 1);
+
+        assert!(database
+            .reconfigure(&ConfigDelta::new().with_lock_timeout(None))
+            .is_ok());
+        assert_eq!(database.access_controller().lock_timeout(), None);
+        assert_eq!(database.statistics().reconfigurations, 2);
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn snapshot_export() {
+        const DIR: &str = "database_snapshot_export_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+
+        let instant = database.transaction().commit().await.unwrap();
+        let snapshot = database.snapshot_at(instant).unwrap();
+        let exported = snapshot.export();
+        assert_eq!(database.snapshot_from(&exported).unwrap(), instant);
+        assert!(database.transaction().commit().await.unwrap() > instant);
+        assert_eq!(database.snapshot_from(&exported).unwrap(), instant);
+
+        let mut malformed = exported.clone();
+        malformed[0] = 0;
+        assert!(matches!(
+            database.snapshot_from(&malformed),
+            Err(Error::WrongParameter)
+        ));
+        assert!(matches!(
+            database.snapshot_from(&exported[..8]),
+            Err(Error::WrongParameter)
+        ));
+
+        let mut future = vec![exported[0]];
+        future.extend_from_slice(&(instant + 1024).to_le_bytes());
+        assert!(matches!(
+            database.snapshot_from(&future),
+            Err(Error::NotFound)
+        ));
+
+        drop(snapshot);
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn shutdown() {
+        const DIR: &str = "database_shutdown_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+
+        let transaction_1 = database.transaction();
+        assert_eq!(
+            database.shutdown(ShutdownPolicy::Wait, None).await,
+            Err(Error::Timeout)
+        );
+        assert!(database.is_shut_down());
+        let transaction_2 = database.transaction();
+        assert_eq!(transaction_2.commit().await, Err(Error::ShutDown));
+        assert!(transaction_1.commit().await.is_ok());
+        assert!(database.shutdown(ShutdownPolicy::Wait, None).await.is_ok());
+        let instant = database.sequencer().now(Relaxed);
+        drop(database);
+
+        let database = Database::with_path(path).await.unwrap();
+        assert_eq!(database.sequencer().now(Relaxed), instant);
+        let report = database.verify().await;
+        assert!(report.is_ok(), "{:?}", report.problems);
+        assert_eq!(report.clock, instant);
+        assert!(!database.is_shut_down());
+        let transaction_3 = database.transaction();
+        let deadline = Instant::now() + std::time::Duration::from_millis(1);
+        assert_eq!(
+            database
+                .shutdown(ShutdownPolicy::Abort, Some(deadline))
+                .await,
+            Err(Error::Timeout)
+        );
+        assert_eq!(transaction_3.commit().await, Err(Error::ShutDown));
+        assert!(database.shutdown(ShutdownPolicy::Abort, None).await.is_ok());
+        drop(database);
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn active_transactions() {
+        const DIR: &str = "database_active_transactions_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+
+        let mut transaction_1 = database.transaction();
+        assert!(transaction_1.set_tag("first").is_ok());
+        let journal = transaction_1.journal();
+        assert_eq!(journal.submit().get(), 1);
+        let transaction_2 = database.transaction();
+        assert!(transaction_1.id() < transaction_2.id());
+        assert_eq!(transaction_2.id() & 0b111, 0);
+
+        let reports = database.active_transactions();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].id, transaction_1.id());
+        assert_eq!(reports[0].tag.as_deref(), Some("first"));
+        assert_eq!(reports[0].num_submitted_journals, 1);
+        assert_eq!(reports[1].id, transaction_2.id());
+        assert_eq!(reports[1].tag, None);
+        assert_eq!(reports[1].num_submitted_journals, 0);
+        assert!(reports[0].start_clock < reports[1].start_clock);
+
+        assert!(transaction_1.commit().await.is_ok());
+        transaction_2.rollback();
+        assert!(database.active_transactions().is_empty());
+
+        // Identifiers of recovered transactions are not reused.
+        let playback = Playback::with_id(&database, 1 << 20);
+        assert_eq!(playback.id(), 1 << 20);
+        assert!(database.transaction().id() > 1 << 20);
+        playback.rollback();
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn abort_transaction() {
+        const DIR: &str = "database_abort_transaction_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+
+        let transaction_1 = database.transaction();
+        let mut journal_1 = transaction_1.journal();
+        assert!(journal_1.create(&[1], None).await.is_ok());
+        assert_eq!(journal_1.submit().get(), 1);
+
+        // A waiting request of the aborted transaction fails.
+        let transaction_2 = database.transaction();
+        let id = transaction_2.id();
+        let mut journal_2 = transaction_2.journal();
+        let deadline = Instant::now() + std::time::Duration::from_secs(10);
+        let mut delete = Box::pin(journal_2.delete(&[1], Some(deadline)));
+        assert!(futures::poll!(delete.as_mut()).is_pending());
+        assert!(database.abort_transaction(id, None).await.is_ok());
+        assert_eq!(delete.await, Err(Error::Aborted));
+        assert_eq!(journal_2.create(&[2], None).await, Err(Error::Aborted));
+        drop(journal_2);
+
+        let anchor = database
+            .kernel
+            .active_transactions
+            .read(&id, |_, anchor| anchor.clone())
+            .unwrap();
+        let (result, ()) = futures::join!(database.abort_transaction(id, Some(deadline)), async {
+            assert_eq!(transaction_2.commit().await, Err(Error::Aborted));
+        });
+        assert!(result.is_ok());
+        assert_eq!(anchor.transaction_state(), TransactionState::RolledBack);
+        assert_eq!(
+            database.abort_transaction(id, None).await,
+            Err(Error::NotFound)
+        );
+
+        // A transaction being committed cannot be aborted.
+        let id = transaction_1.id();
+        let committable = transaction_1.prepare().await.unwrap();
+        assert_eq!(
+            database.abort_transaction(id, None).await,
+            Err(Error::UnexpectedState)
+        );
+        assert!(committable.await.is_ok());
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn abort_transaction_commit() {
+        const DIR: &str = "database_abort_transaction_commit_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+
+        // Either the commit or the abort wins, and the aborted transaction leaves nothing behind.
+        let mut committed = Vec::new();
+        for o in 0..64 {
+            let transaction = database.transaction();
+            let id = transaction.id();
+            let mut journal = transaction.journal();
+            journal.create(&[o], None).await.unwrap();
+            assert_eq!(journal.submit().get(), 1);
+            let (commit_result, abort_result) = std::thread::scope(|s| {
+                let commit = s.spawn(|| futures::executor::block_on(transaction.commit()));
+                let abort = futures::executor::block_on(database.abort_transaction(id, None));
+                (commit.join().unwrap(), abort)
+            });
+            if commit_result.is_ok() {
+                assert!(matches!(
+                    abort_result,
+                    Err(Error::UnexpectedState | Error::NotFound)
+                ));
+                committed.push(o);
+            } else {
+                assert_eq!(commit_result, Err(Error::Aborted));
+                assert!(abort_result.is_ok());
+            }
+        }
+        drop(database);
+
+        let database_recovered = Database::with_path(path).await.unwrap();
+        let snapshot = database_recovered.snapshot();
+        for o in 0..64 {
+            assert_eq!(
+                database_recovered
+                    .access_controller()
+                    .read(o, &snapshot, None)
+                    .await,
+                Ok(committed.contains(&o))
+            );
+        }
+
+        drop(snapshot);
+        drop(database_recovered);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn oldest_visible_clock() {
+        const DIR: &str = "database_oldest_visible_clock_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let instant = database.transaction().commit().await.unwrap();
+        let snapshot = database.snapshot_at(instant).unwrap();
+        assert!(database.transaction().commit().await.unwrap() > instant);
+        assert!(database.sequencer().min(Relaxed) <= instant);
+        assert!(database.oldest_visible_clock() <= instant);
+
+        drop(snapshot);
+        while database.sequencer().min(Relaxed) <= instant {
+            tokio::task::yield_now().await;
+        }
+        assert!(database.oldest_visible_clock() > instant);
+        drop(database);
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn clock_persistence() {
+        const DIR: &str = "database_clock_persistence_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let commit_instant = database.transaction().commit().await.unwrap();
+
+        // Clock values not recorded in the log.
+        for _ in 0..3 {
+            database.sequencer().advance(Relaxed);
+        }
+        let instant = database.sequencer().now(Relaxed);
+        assert!(instant > commit_instant);
+        assert!(database.shutdown(ShutdownPolicy::Wait, None).await.is_ok());
+        drop(database);
+
+        let database = Database::with_path(path).await.unwrap();
+        assert_eq!(database.sequencer().now(Relaxed), instant);
+        let report = database.verify().await;
+        assert!(report.is_ok(), "{:?}", report.problems);
+        assert_eq!(report.clock, instant);
+        assert!(database.transaction().commit().await.unwrap() > instant);
+        drop(database);
+
+        let database = Database::with_path(path).await.unwrap();
+        assert!(database.sequencer().now(Relaxed) > instant);
+        drop(database);
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn read_only() {
+        const DIR: &str = "database_read_only_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let instant = database.transaction().commit().await.unwrap();
+        assert!(database.shutdown(ShutdownPolicy::Wait, None).await.is_ok());
+        drop(database);
+        let images: Vec<Vec<u8>> = ["db.dat", "l.log"]
+            .iter()
+            .map(|f| std::fs::read(path.join(f)).unwrap())
+            .collect();
+
+        assert_eq!(
+            Database::with_options(
+                Path::new("nonexistent"),
+                &OpenOptions::new().with_read_only(true)
+            )
+            .await
+            .err(),
+            Some(Error::Generic("the database files do not exist"))
+        );
+        let options = OpenOptions::new().with_read_only(true);
+        let database = Database::with_options(path, &options).await.unwrap();
+        assert!(database.persistence_layer().is_read_only());
+        assert_eq!(database.sequencer().now(Relaxed), instant);
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert_eq!(
+            database
+                .create_container("hello".to_string(), Metadata::default(), &mut journal, None)
+                .await
+                .err(),
+            Some(Error::ReadOnly)
+        );
+        assert_eq!(journal.create(&[1], None).await, Err(Error::ReadOnly));
+        drop(journal);
+        assert!(transaction.commit().await.is_ok());
+        assert_eq!(database.defragment().await, Err(Error::ReadOnly));
+        assert_eq!(database.vacuum().await, Err(Error::ReadOnly));
+        assert!(database.shutdown(ShutdownPolicy::Wait, None).await.is_ok());
+        drop(database);
+        for (file, image) in ["db.dat", "l.log"].iter().zip(images) {
+            assert_eq!(std::fs::read(path.join(file)).unwrap(), image);
+        }
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn vacuum() {
+        const DIR: &str = "database_vacuum_test";
+        const NUM_KEYS: usize = 2048;
+        let path = Path::new(DIR);
+        let large_value = vec![3_u8; 20000];
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        for index_type in [IndexType::Ordered, IndexType::Hash] {
+            let container = database
+                .create_container(
+                    format!("{index_type:?}"),
+                    Metadata::default().with_index_type(index_type),
+                    &mut journal,
+                    None,
+                )
+                .await
+                .unwrap();
+            for i in 0..NUM_KEYS {
+                let key = i.to_be_bytes();
+                let value = if i % 256 == 0 { &large_value[..] } else { &key };
+                assert!(container
+                    .insert(&key, value, &mut journal, None)
+                    .await
+                    .is_ok());
+            }
+        }
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        // The second checkpoint is written after the pages of the first one.
+        assert!(database.checkpoint(None).await.is_ok());
+        assert!(database.checkpoint(None).await.is_ok());
+        let file_len = database.defragment().await.unwrap();
+        let vacuumed_len = database.vacuum().await.unwrap();
+        assert!(vacuumed_len < file_len, "{vacuumed_len} {file_len}");
+        assert_eq!(database.vacuum().await, Ok(vacuumed_len));
+        assert!(database.verify().await.problems.is_empty());
+        drop(database);
+
+        // The moved pages are loaded without the log.
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(path.join("l.log"))
+            .unwrap()
+            .set_len(0)
+            .unwrap();
+        let database = Database::with_path(path).await.unwrap();
+        let snapshot = database.snapshot();
+        for index_type in [IndexType::Ordered, IndexType::Hash] {
+            let container = database
+                .get_container(&format!("{index_type:?}"), &snapshot)
+                .await
+                .unwrap();
+            for i in 0..NUM_KEYS {
+                let key = i.to_be_bytes();
+                let value = if i % 256 == 0 { &large_value[..] } else { &key };
+                assert_eq!(
+                    container.get(&key, &snapshot, None).await,
+                    Ok(Some(value.to_vec()))
+                );
+            }
+        }
+        drop(snapshot);
+        drop(database);
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
 }
@@ -0,0 +1,235 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The module detects cycles of transactions waiting for each other.
+
+use super::TransactionID;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+/// [`VictimPolicy`] determines which transaction of a deadlock gives up.
+///
+/// Deadlocks are detected in the background once a policy is set through
+/// [`AccessController::set_deadlock_detection`](super::AccessController::set_deadlock_detection),
+/// and the waiting request of the victim fails with
+/// [`Error::DeadlockDetected`](super::Error::DeadlockDetected). Ties are broken in favor of older
+/// transactions.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum VictimPolicy {
+    /// The transaction that started last gives up.
+    #[default]
+    Youngest,
+
+    /// The transaction that has submitted the fewest [`Journal`](super::Journal) instances gives
+    /// up, and then the one retaining the least memory for its uncommitted changes.
+    LeastWork,
+
+    /// The transaction of the lowest [`Transaction::priority`](super::Transaction::priority)
+    /// gives up.
+    LowestPriority,
+}
+
+/// [`DeadlockReport`] describes a cycle of transactions waiting for each other.
+///
+/// # Examples
+///
+/// ```
+/// use sap_tsf::{DeadlockReport, WaitsFor};
+/// use std::time::Duration;
+///
+/// let report = DeadlockReport {
+///     victim: 16,
+///     cycle: vec![
+///         WaitsFor {
+///             waiter: 8,
+///             holder: 16,
+///             object_id: 1,
+///             wait_duration: Duration::from_millis(2),
+///         },
+///         WaitsFor {
+///             waiter: 16,
+///             holder: 8,
+///             object_id: 2,
+///             wait_duration: Duration::from_millis(1),
+///         },
+///     ],
+/// };
+/// assert_eq!(
+///     report.to_string(),
+///     "transaction 8 waits for transaction 16 on object 1 for 2ms, \
+///      transaction 16 waits for transaction 8 on object 2 for 1ms, victim: transaction 16"
+/// );
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct DeadlockReport {
+    /// The transaction that was chosen to give up.
+    pub victim: TransactionID,
+
+    /// The edges of the cycle; the holder of each edge is the waiter of the next one, and the
+    /// holder of the last edge is the waiter of the first one.
+    pub cycle: Vec<WaitsFor>,
+}
+
+/// [`WaitsFor`] is a transaction waiting for a database object held by another transaction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct WaitsFor {
+    /// The waiting transaction.
+    pub waiter: TransactionID,
+
+    /// The transaction holding the database object.
+    pub holder: TransactionID,
+
+    /// The identifier of the database object.
+    pub object_id: u64,
+
+    /// The time elapsed since the waiter requested access to the database object.
+    pub wait_duration: Duration,
+}
+
+impl VictimPolicy {
+    /// Ranks a transaction of a deadlock; the transaction of the highest rank gives up.
+    pub(super) fn rank(
+        self,
+        start_clock: u64,
+        priority: u8,
+        num_submitted_journals: u32,
+        memory_usage: usize,
+    ) -> (u64, u64, u64) {
+        match self {
+            VictimPolicy::Youngest => (start_clock, 0, 0),
+            VictimPolicy::LeastWork => (
+                u64::MAX - u64::from(num_submitted_journals),
+                u64::MAX - u64::try_from(memory_usage).unwrap_or(u64::MAX),
+                start_clock,
+            ),
+            VictimPolicy::LowestPriority => (u64::from(u8::MAX - priority), start_clock, 0),
+        }
+    }
+}
+
+impl fmt::Display for DeadlockReport {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for edge in &self.cycle {
+            write!(
+                f,
+                "transaction {} waits for transaction {} on object {} for {:?}, ",
+                edge.waiter, edge.holder, edge.object_id, edge.wait_duration
+            )?;
+        }
+        write!(f, "victim: transaction {}", self.victim)
+    }
+}
+
+/// Finds a cycle in the waits-for graph.
+///
+/// Returns the edges of the cycle in the order described in [`DeadlockReport::cycle`].
+pub(super) fn find_cycle(edges: &[WaitsFor]) -> Option<Vec<WaitsFor>> {
+    let mut adjacency: BTreeMap<TransactionID, Vec<&WaitsFor>> = BTreeMap::new();
+    for edge in edges {
+        adjacency.entry(edge.waiter).or_default().push(edge);
+    }
+
+    // `true` if the transaction is on the current path, and `false` if it is known not to be
+    // part of any cycle.
+    let mut visited: BTreeMap<TransactionID, bool> = BTreeMap::new();
+    for &start in adjacency.keys() {
+        if visited.contains_key(&start) {
+            continue;
+        }
+        visited.insert(start, true);
+
+        // `path_edges[i]` leads from `path[i]` to `path[i + 1]`.
+        let mut path: Vec<(TransactionID, usize)> = vec![(start, 0)];
+        let mut path_edges: Vec<&WaitsFor> = Vec::new();
+        while let Some(&(transaction_id, next)) = path.last() {
+            let Some(edge) = adjacency.get(&transaction_id).and_then(|e| e.get(next)) else {
+                visited.insert(transaction_id, false);
+                path.pop();
+                path_edges.pop();
+                continue;
+            };
+            if let Some((_, next)) = path.last_mut() {
+                *next += 1;
+            }
+            match visited.get(&edge.holder) {
+                Some(true) => {
+                    let position = path
+                        .iter()
+                        .position(|(t, _)| *t == edge.holder)
+                        .unwrap_or_default();
+                    let mut cycle: Vec<WaitsFor> =
+                        path_edges[position..].iter().map(|e| **e).collect();
+                    cycle.push(**edge);
+                    return Some(cycle);
+                }
+                Some(false) => (),
+                None => {
+                    visited.insert(edge.holder, true);
+                    path.push((edge.holder, 0));
+                    path_edges.push(edge);
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(waiter: TransactionID, holder: TransactionID, object_id: u64) -> WaitsFor {
+        WaitsFor {
+            waiter,
+            holder,
+            object_id,
+            wait_duration: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn find_cycle_in_graph() {
+        assert!(find_cycle(&[]).is_none());
+        assert!(find_cycle(&[edge(8, 16, 1), edge(16, 24, 2), edge(8, 24, 3)]).is_none());
+
+        let cycle = find_cycle(&[edge(8, 16, 1), edge(16, 24, 2), edge(24, 16, 3)]).unwrap();
+        assert_eq!(cycle, vec![edge(16, 24, 2), edge(24, 16, 3)]);
+
+        let cycle = find_cycle(&[edge(8, 16, 1), edge(16, 24, 2), edge(24, 8, 3)]).unwrap();
+        assert_eq!(cycle, vec![edge(8, 16, 1), edge(16, 24, 2), edge(24, 8, 3)]);
+    }
+
+    #[test]
+    fn rank() {
+        let youngest = VictimPolicy::Youngest;
+        assert!(youngest.rank(2, 0, 4, 64) > youngest.rank(1, 0, 0, 0));
+
+        let least_work = VictimPolicy::LeastWork;
+        assert!(least_work.rank(1, 0, 1, 64) > least_work.rank(2, 0, 2, 0));
+        assert!(least_work.rank(1, 0, 1, 0) > least_work.rank(2, 0, 1, 64));
+
+        let lowest_priority = VictimPolicy::LowestPriority;
+        assert!(lowest_priority.rank(1, 0, 0, 0) > lowest_priority.rank(2, 1, 0, 0));
+        assert!(lowest_priority.rank(2, 1, 0, 0) > lowest_priority.rank(1, 1, 0, 0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let mut cycle = vec![edge(8, 16, 1), edge(16, 8, 2)];
+        cycle[0].wait_duration = Duration::from_millis(2);
+        let report = DeadlockReport { victim: 16, cycle };
+        let mut serialized = Vec::new();
+        assert!(ciborium::into_writer(&report, &mut serialized).is_ok());
+        let deserialized: DeadlockReport = ciborium::from_reader(serialized.as_slice()).unwrap();
+        assert_eq!(deserialized, report);
+    }
+}
@@ -0,0 +1,185 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! [`DependencyGraph`] describes which transactions hold and wait for database objects.
+
+use super::{JournalID, TransactionID, TransactionState};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt::{Debug, Write};
+
+/// [`DependencyGraph`] is a snapshot of the database objects owned by transactions in an
+/// [`AccessController`](super::AccessController), and of the transactions waiting for them.
+///
+/// It is meant to help debug stuck workloads: a transaction waiting for an object held by another
+/// transaction depends on the other transaction, and a cycle of such dependencies that is never
+/// resolved is a deadlock. The graph is taken without stopping transactions, therefore it may
+/// not be consistent across database objects.
+///
+/// # Examples
+///
+/// ```
+/// use sap_tsf::Database;
+/// use std::path::Path;
+///
+/// async {
+///     let database = Database::with_path(Path::new("dependency_graph")).await.unwrap();
+///     let access_controller = database.access_controller();
+///     let transaction = database.transaction();
+///     let mut journal = transaction.journal();
+///     assert!(access_controller.lock(1, &mut journal, None).await.is_ok());
+///     let graph = access_controller.dependency_graph().await;
+///     assert_eq!(graph.objects[0].object_id, 1);
+///     assert!(graph.waits_for().is_empty());
+///     println!("{}", graph.to_dot());
+/// };
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct DependencyGraph<I> {
+    /// The transactions holding or waiting for database objects ordered by their identifiers.
+    pub transactions: Vec<(TransactionID, TransactionState<I>)>,
+
+    /// The database objects owned by transactions ordered by their identifiers.
+    pub objects: Vec<ObjectDependency>,
+}
+
+/// [`ObjectDependency`] describes the owners and waiters of a database object.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ObjectDependency {
+    /// The identifier of the database object.
+    pub object_id: u64,
+
+    /// The accesses granted to the owners.
+    pub holders: Vec<Access>,
+
+    /// The accesses that are waiting to be granted in the order of the wait queue.
+    pub waiters: Vec<Access>,
+}
+
+/// [`Access`] is an access to a database object made by a [`Journal`](super::Journal).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Access {
+    /// The identifier of the transaction.
+    pub transaction_id: TransactionID,
+
+    /// The identifier of the [`Journal`](super::Journal) of the transaction.
+    pub journal_id: JournalID,
+
+    /// The type of the access.
+    pub access_type: AccessType,
+}
+
+/// Types of [`Access`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum AccessType {
+    /// The database object is created.
+    Create,
+
+    /// The database object is protected from being modified.
+    Protect,
+
+    /// The database object is locked.
+    Lock,
+
+    /// The database object is deleted.
+    Delete,
+}
+
+/// [`ObjectHolder`] describes a transaction holding a database object without identifying the
+/// transaction.
+///
+/// It is returned by [`Journal::try_peek`](super::Journal::try_peek), so that applications can
+/// decide whether to wait for the database object or to do something else, e.g., based on how
+/// old the holder is, or which workload it belongs to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ObjectHolder<I> {
+    /// The type of the access granted to the holder.
+    pub access_type: AccessType,
+
+    /// The logical clock value when the holder transaction started.
+    ///
+    /// A transaction that started earlier has a smaller value.
+    pub start_clock: u64,
+
+    /// The tag attached to the holder transaction through
+    /// [`Transaction::set_tag`](super::Transaction::set_tag).
+    pub tag: Option<String>,
+
+    /// The current state of the holder transaction.
+    pub state: TransactionState<I>,
+}
+
+impl<I: Debug> DependencyGraph<I> {
+    /// Returns the waits-for edges of the graph as `(WAITER, HOLDER, OBJECT)` tuples.
+    ///
+    /// A journal waiting for a database object that its own transaction holds does not produce an
+    /// edge.
+    #[inline]
+    #[must_use]
+    pub fn waits_for(&self) -> Vec<(TransactionID, TransactionID, u64)> {
+        let mut edges = Vec::new();
+        for object in &self.objects {
+            for waiter in &object.waiters {
+                for holder in &object.holders {
+                    if waiter.transaction_id != holder.transaction_id {
+                        edges.push((
+                            waiter.transaction_id,
+                            holder.transaction_id,
+                            object.object_id,
+                        ));
+                    }
+                }
+            }
+        }
+        edges.sort_unstable();
+        edges.dedup();
+        edges
+    }
+
+    /// Renders the graph in the `DOT` language.
+    ///
+    /// Transactions and database objects are nodes; an edge from a database object to a
+    /// transaction denotes ownership, and an edge from a transaction to a database object denotes
+    /// that the transaction is waiting for it.
+    #[inline]
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph dependencies {\n");
+        for (transaction_id, state) in &self.transactions {
+            let _: Result<(), _> = writeln!(
+                dot,
+                "    \"t{transaction_id}\" [shape=box, label=\"transaction {transaction_id}\\n\
+                 {state:?}\"];"
+            );
+        }
+        for object in &self.objects {
+            let object_id = object.object_id;
+            let _: Result<(), _> = writeln!(
+                dot,
+                "    \"o{object_id}\" [shape=ellipse, label=\"object {object_id}\"];"
+            );
+            for holder in &object.holders {
+                let _: Result<(), _> = writeln!(
+                    dot,
+                    "    \"o{object_id}\" -> \"t{}\" [label=\"{:?}\"];",
+                    holder.transaction_id, holder.access_type
+                );
+            }
+            for waiter in &object.waiters {
+                let _: Result<(), _> = writeln!(
+                    dot,
+                    "    \"t{}\" -> \"o{object_id}\" [label=\"{:?}\", style=dashed];",
+                    waiter.transaction_id, waiter.access_type
+                );
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
@@ -0,0 +1,203 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Diagnostics of transaction anchors enabled by the `diagnostics` feature.
+
+use super::utils::Instant;
+use super::TransactionID;
+use std::cmp::Reverse;
+use std::panic::Location;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::{Acquire, Release};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+/// [`LingeringAnchor`] describes a transaction that ended while its anchor is still referenced.
+///
+/// The anchor of a transaction holds its state and commit instant, and it is referenced by the
+/// versions of database objects that the transaction created or deleted, by its journals, and by
+/// [`CommitHandle`](super::CommitHandle) instances. The anchor is usually dropped soon after the
+/// versions are consolidated; an anchor lingering long after the transaction ended denotes a
+/// leaked reference, e.g., a [`CommitHandle`](super::CommitHandle) that is never dropped, or a
+/// version chain that cannot be consolidated because an old [`Snapshot`](super::Snapshot) pins
+/// it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LingeringAnchor {
+    /// The identifier of the transaction.
+    pub transaction_id: TransactionID,
+
+    /// The tag attached to the transaction.
+    pub tag: Option<String>,
+
+    /// The location in the source code where the transaction was started.
+    pub origin: &'static Location<'static>,
+
+    /// The time elapsed since the transaction was committed or rolled back.
+    pub lingering_for: Duration,
+}
+
+/// [`AnchorRegistry`] keeps track of transaction anchors that have not been dropped.
+#[derive(Debug, Default)]
+pub(super) struct AnchorRegistry {
+    /// Traces of anchors.
+    ///
+    /// Traces of dropped anchors are pruned when the number of traces doubles, or when lingering
+    /// anchors are reported.
+    traces: Mutex<Traces>,
+}
+
+/// [`AnchorTracker`] is embedded in a transaction anchor, and it marks the trace of the anchor
+/// dropped when the anchor is dropped.
+///
+/// Anchors can be dropped while the registry is being accessed by the same thread, therefore the
+/// tracker only touches its own trace when dropped.
+#[derive(Debug)]
+pub(super) struct AnchorTracker {
+    /// The trace of the anchor.
+    trace: Arc<AnchorTrace>,
+}
+
+/// Traces of anchors in an [`AnchorRegistry`].
+#[derive(Debug, Default)]
+struct Traces {
+    /// Traces of anchors including dropped ones.
+    traces: Vec<Arc<AnchorTrace>>,
+
+    /// The number of traces that triggers pruning.
+    prune_at: usize,
+}
+
+/// The trace of an anchor.
+#[derive(Debug)]
+struct AnchorTrace {
+    /// The identifier of the transaction.
+    transaction_id: TransactionID,
+
+    /// The location in the source code where the transaction was started.
+    origin: &'static Location<'static>,
+
+    /// The tag attached to the transaction when it ended.
+    tag: OnceLock<String>,
+
+    /// The time when the transaction ended.
+    end_time: OnceLock<Instant>,
+
+    /// The anchor has been dropped.
+    dropped: AtomicBool,
+}
+
+impl AnchorRegistry {
+    /// Registers the anchor of the transaction.
+    pub(super) fn track(
+        &self,
+        transaction_id: TransactionID,
+        origin: &'static Location<'static>,
+    ) -> AnchorTracker {
+        let trace = Arc::new(AnchorTrace {
+            transaction_id,
+            origin,
+            tag: OnceLock::new(),
+            end_time: OnceLock::new(),
+            dropped: AtomicBool::new(false),
+        });
+        if let Ok(mut traces) = self.traces.lock() {
+            if traces.traces.len() >= traces.prune_at {
+                traces.traces.retain(|t| !t.dropped.load(Acquire));
+                traces.prune_at = (traces.traces.len() * 2).max(MIN_PRUNE_AT);
+            }
+            traces.traces.push(trace.clone());
+        }
+        AnchorTracker { trace }
+    }
+
+    /// Returns the anchors of transactions that ended at least `threshold` ago, the longest
+    /// lingering one first.
+    pub(super) fn lingering(&self, threshold: Duration) -> Vec<LingeringAnchor> {
+        let now = Instant::now();
+        let mut lingering = Vec::new();
+        if let Ok(mut traces) = self.traces.lock() {
+            traces.traces.retain(|t| !t.dropped.load(Acquire));
+            for trace in &traces.traces {
+                let Some(end_time) = trace.end_time.get() else {
+                    continue;
+                };
+                let lingering_for = now.saturating_duration_since(*end_time);
+                if lingering_for >= threshold {
+                    lingering.push(LingeringAnchor {
+                        transaction_id: trace.transaction_id,
+                        tag: trace.tag.get().cloned(),
+                        origin: trace.origin,
+                        lingering_for,
+                    });
+                }
+            }
+        }
+        lingering.sort_unstable_by_key(|l| Reverse(l.lingering_for));
+        lingering
+    }
+}
+
+impl AnchorTracker {
+    /// Records that the transaction was committed or rolled back.
+    pub(super) fn end(&self, tag: Option<&str>) {
+        if let Some(tag) = tag {
+            let _: Result<(), _> = self.trace.tag.set(tag.to_string());
+        }
+        let _: Result<(), _> = self.trace.end_time.set(Instant::now());
+    }
+}
+
+impl Drop for AnchorTracker {
+    #[inline]
+    fn drop(&mut self) {
+        self.trace.dropped.store(true, Release);
+    }
+}
+
+/// The minimum number of traces that triggers pruning.
+const MIN_PRUNE_AT: usize = 64;
+
+#[cfg(test)]
+mod tests {
+    use crate::Database;
+    use scc::ebr;
+    use std::path::Path;
+    use std::time::Duration;
+    use tokio::fs::remove_dir_all;
+
+    #[tokio::test]
+    async fn lingering_anchors() {
+        const DIR: &str = "diagnostics_lingering_anchors_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let mut transaction = database.transaction();
+        assert!(transaction.set_tag("leak").is_ok());
+        let transaction_id = transaction.id();
+        let line = line!() - 3;
+        let commit_handle = transaction.commit_handle();
+        assert!(database.lingering_anchors(Duration::ZERO).is_empty());
+        assert!(transaction.commit().await.is_ok());
+
+        // The leaked `CommitHandle` keeps the anchor alive.
+        let lingering = database.lingering_anchors(Duration::ZERO);
+        assert_eq!(lingering.len(), 1);
+        assert_eq!(lingering[0].transaction_id, transaction_id);
+        assert_eq!(lingering[0].tag.as_deref(), Some("leak"));
+        assert_eq!(lingering[0].origin.file(), file!());
+        assert_eq!(lingering[0].origin.line(), line);
+        assert!(database
+            .lingering_anchors(Duration::from_mins(1))
+            .is_empty());
+
+        // The anchor is dropped once the memory reclaimer catches up.
+        drop(commit_handle);
+        while !database.lingering_anchors(Duration::ZERO).is_empty() {
+            drop(ebr::Guard::new());
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+}
@@ -2,38 +2,108 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+use super::{DeadlockReport, TransactionID};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::io;
 
+/// [`ConflictReport`] describes the change to a database object that an operation conflicts
+/// with.
+///
+/// # Examples
+///
+/// ```
+/// use sap_tsf::{ConflictReport, Error};
+///
+/// let error = Error::Conflict(Box::new(ConflictReport {
+///     object_id: 3,
+///     owner: Some(8),
+/// }));
+/// assert_eq!(
+///     error.to_string(),
+///     "the operation conflicts with transaction 8 on object 3"
+/// );
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ConflictReport {
+    /// The identifier of the database object.
+    pub object_id: u64,
+
+    /// The transaction that holds or changed the database object.
+    ///
+    /// `None` if the transaction has already been forgotten.
+    pub owner: Option<TransactionID>,
+}
+
 /// [`Error`] defines all the error codes used by the database storage system.
 ///
 /// This only defines error codes used in the framework, and individual component implementations
 /// may define separate error codes.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Error {
-    /// The operation conflicts with others.
-    Conflict,
+    /// The transaction was aborted by another thread.
+    Aborted,
+
+    /// The database files are locked by another process or instance.
+    AlreadyInUse,
+
+    /// The operation was cancelled by a [`CancellationToken`](super::CancellationToken).
+    Cancelled,
+
+    /// The operation conflicts with the change described in the [`ConflictReport`].
+    Conflict(Box<ConflictReport>),
 
     /// The database is corrupt.
     CorruptDatabase,
 
+    /// The log is corrupt at the offset.
+    CorruptLog(u64),
+
+    /// The page at the address is corrupt.
+    CorruptPage(u64),
+
     /// The operation causes a deadlock.
     Deadlock,
 
+    /// The transaction was chosen as the victim of the deadlock described in the
+    /// [`DeadlockReport`](super::DeadlockReport).
+    DeadlockDetected(Box<DeadlockReport>),
+
+    /// The storage device has run out of space.
+    DiskFull,
+
     /// Generic errors with a message string attached to it.
     Generic(&'static str),
 
     /// IO error.
     IO(io::ErrorKind),
 
+    /// IO error at the offset of a database file.
+    IOAt(io::ErrorKind, u64),
+
     /// The desired resource could not be found in the database.
     NotFound,
 
     /// Memory allocation failed.
     OutOfMemory,
 
+    /// The transaction exceeded its memory budget.
+    OutOfMemoryBudget,
+
+    /// Too many requests are waiting for the same resource.
+    Overloaded,
+
+    /// The database was opened read-only.
+    ReadOnly,
+
     /// The operation failed to be serialized with others.
     SerializationFailure,
 
+    /// The database is shut down.
+    ShutDown,
+
     /// The operation was timed out.
     Timeout,
 
@@ -43,6 +113,123 @@ pub enum Error {
     /// The operation causes the same key to be inserted into a unique container.
     UniquenessViolation,
 
+    /// The key-value pair has too many versions that have yet to be reclaimed.
+    VersionChainTooLong,
+
     /// The supplied parameter value is wrong.
     WrongParameter,
 }
+
+impl fmt::Display for Error {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Aborted => f.write_str("the transaction was aborted"),
+            Error::AlreadyInUse => f.write_str("the database files are in use"),
+            Error::Cancelled => f.write_str("the operation was cancelled"),
+            Error::Conflict(report) => write!(f, "the operation conflicts with {report}"),
+            Error::CorruptDatabase => f.write_str("the database is corrupt"),
+            Error::CorruptLog(offset) => write!(f, "the log is corrupt at {offset:#x}"),
+            Error::CorruptPage(address) => write!(f, "the page at {address:#x} is corrupt"),
+            Error::Deadlock => f.write_str("the operation causes a deadlock"),
+            Error::DeadlockDetected(report) => {
+                write!(f, "the operation causes a deadlock: {report}")
+            }
+            Error::DiskFull => f.write_str("the storage device has run out of space"),
+            Error::Generic(message) => f.write_str(message),
+            Error::IO(kind) => write!(f, "IO error: {kind}"),
+            Error::IOAt(kind, offset) => write!(f, "IO error at {offset:#x}: {kind}"),
+            Error::NotFound => f.write_str("the resource could not be found"),
+            Error::OutOfMemory => f.write_str("memory allocation failed"),
+            Error::OutOfMemoryBudget => f.write_str("the transaction exceeded its memory budget"),
+            Error::Overloaded => f.write_str("too many requests are waiting for the resource"),
+            Error::ReadOnly => f.write_str("the database is read-only"),
+            Error::SerializationFailure => {
+                f.write_str("the operation failed to be serialized with others")
+            }
+            Error::ShutDown => f.write_str("the database is shut down"),
+            Error::Timeout => f.write_str("the operation was timed out"),
+            Error::UnexpectedState => f.write_str("the database object is in an unexpected state"),
+            Error::UniquenessViolation => f.write_str("the key already exists"),
+            Error::VersionChainTooLong => {
+                f.write_str("the key-value pair has too many unreclaimed versions")
+            }
+            Error::WrongParameter => f.write_str("the parameter value is wrong"),
+        }
+    }
+}
+
+impl Error {
+    /// Creates an [`Error::Conflict`] with the database object and its owner.
+    pub(crate) fn conflict(object_id: u64, owner: Option<TransactionID>) -> Self {
+        Error::Conflict(Box::new(ConflictReport { object_id, owner }))
+    }
+
+    /// Attaches the offset of the database file to an [`Error::IO`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn at(self, offset: u64) -> Self {
+        if let Error::IO(kind) = self {
+            Error::IOAt(kind, offset)
+        } else {
+            self
+        }
+    }
+}
+
+impl fmt::Display for ConflictReport {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(owner) = self.owner {
+            write!(f, "transaction {owner} on object {}", self.object_id)
+        } else {
+            write!(f, "another transaction on object {}", self.object_id)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    #[inline]
+    fn from(error: io::Error) -> Self {
+        if error.kind() == io::ErrorKind::StorageFull {
+            Error::DiskFull
+        } else {
+            Error::IO(error.kind())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            Error::CorruptPage(0x2000).to_string(),
+            "the page at 0x2000 is corrupt"
+        );
+        assert_eq!(
+            Error::from(io::Error::from(io::ErrorKind::NotFound)).to_string(),
+            format!("IO error: {}", io::ErrorKind::NotFound)
+        );
+        assert_eq!(
+            Error::from(io::Error::from(io::ErrorKind::StorageFull)),
+            Error::DiskFull
+        );
+        assert_eq!(
+            Error::from(io::Error::from(io::ErrorKind::NotFound))
+                .at(0x200)
+                .to_string(),
+            format!("IO error at 0x200: {}", io::ErrorKind::NotFound)
+        );
+        assert_eq!(Error::DiskFull.at(0x200), Error::DiskFull);
+        assert_eq!(
+            Error::conflict(3, None).to_string(),
+            "the operation conflicts with another transaction on object 3"
+        );
+        let error: Box<dyn std::error::Error> = Box::new(Error::Timeout);
+        assert_eq!(error.to_string(), "the operation was timed out");
+    }
+}
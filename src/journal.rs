@@ -2,15 +2,26 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use super::access_controller::ObjectState;
+use super::access_controller::{AccessController, ObjectState};
+use super::container::{OptimisticAccess, StatisticsDelta};
+use super::journal_pool::{self, RecordLocks};
 use super::snapshot::{JournalSnapshot, TransactionSnapshot};
 use super::task_processor::{Task, TaskProcessor};
 use super::transaction::Anchor as TransactionAnchor;
+use super::transaction::Visibility;
 use super::transaction::ID as TransactionID;
-use super::{Error, PersistenceLayer, Sequencer, Snapshot, Transaction};
+use super::utils::Instant;
+use super::{
+    Change, ConflictPolicy, Counter, Error, ObjectHolder, PersistenceLayer, Sequencer, Snapshot,
+    Telemetry, Transaction, TransactionState, VersionRecord,
+};
 use scc::ebr;
 use scc::hash_map::OccupiedEntry;
+use std::collections::BTreeSet;
 use std::future::Future;
+use std::hint::spin_loop;
+use std::marker::PhantomData;
+use std::mem::{forget, take};
 use std::num::NonZeroU32;
 use std::pin::Pin;
 use std::ptr;
@@ -18,7 +29,6 @@ use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::{Arc, Mutex, MutexGuard};
 use std::task::{Context, Poll, Waker};
-use std::time::Instant;
 
 /// [`Journal`] keeps the change history.
 #[derive(Debug)]
@@ -31,8 +41,38 @@ pub struct Journal<'d, 't, S: Sequencer, P: PersistenceLayer<S>> {
 
     /// [`Anchor`] may outlive the [`Journal`].
     anchor: ebr::Shared<Anchor<S>>,
+
+    /// Changes to key-value pairs made by the [`Journal`].
+    changes: Vec<Change>,
+
+    /// Accesses to key-value pairs made by the [`Journal`] of an optimistic transaction.
+    optimistic_accesses: Vec<OptimisticAccess>,
+
+    /// Database objects read by the [`Journal`] along with their visibility.
+    reads: Vec<ObjectRead>,
+
+    /// Memory retained by the [`Journal`] in bytes.
+    memory_usage: usize,
+
+    /// Record locks acquired by the [`Journal`] indexed by container identifiers.
+    record_locks: Vec<RecordLocks>,
+
+    /// Changes to the statistics of containers made by the [`Journal`].
+    statistics_deltas: Vec<StatisticsDelta>,
+
+    /// Database objects created or deleted by the [`Journal`].
+    owned_objects: Vec<u64>,
+
+    /// Containers renamed by the [`Journal`] along with their new names.
+    renamed_containers: Vec<RenamedContainer>,
 }
 
+/// The identifier of a database object read by a [`Journal`] along with its visibility.
+pub(super) type ObjectRead = (u64, bool);
+
+/// The identifier of a container renamed by a [`Journal`] along with its new name.
+pub(super) type RenamedContainer = (u64, Arc<str>);
+
 /// The type of journal identifiers.
 ///
 /// The identifier of a journal is only within the transaction, and the same identifier can be used
@@ -44,6 +84,7 @@ pub type ID = u64;
 /// [`Anchor`] is a piece of data that outlives its associated [`Journal`] allowing asynchronous
 /// operations.
 #[derive(Debug)]
+#[allow(clippy::struct_field_names)]
 #[repr(align(16))]
 pub(super) struct Anchor<S: Sequencer> {
     /// Points to the key fields of the [`Transaction`].
@@ -86,21 +127,38 @@ pub(super) type ResultWakerPair = (Option<Result<bool, Error>>, Option<Waker>);
 /// [`AwaitResponse`] is a [`Future`] to await any response to the request to acquire the specified
 /// resource.
 #[derive(Debug)]
-pub(super) struct AwaitResponse<'d> {
+pub(super) struct AwaitResponse<'d, S: Sequencer> {
+    /// The [`AccessController`] adapting the number of spin rounds.
+    access_controller: &'d AccessController<S>,
+
     /// The object identifier of the desired resource.
     object_id: u64,
 
     /// Indicates that the object identifier was sent to the [`TaskProcessor`].
     object_id_registered: bool,
 
+    /// Indicates that the request has spun before being parked.
+    spun: bool,
+
     /// The corresponding [`TaskProcessor`] that monitors database resources being released.
     task_processor: &'d TaskProcessor,
 
+    /// The [`Telemetry`] recording lock waits.
+    telemetry: &'d Telemetry,
+
     /// The deadline.
     deadline: Instant,
 
     /// The placeholder for the result and [`Waker`].
     result_placeholder: Arc<AccessRequestResult>,
+
+    /// The requester transaction that can be cancelled or wounded while waiting.
+    transaction_anchor: ebr::Shared<TransactionAnchor<S>>,
+
+    /// The requester transaction can be wounded while waiting.
+    ///
+    /// It is only set under [`ConflictPolicy::WoundWait`].
+    woundable: bool,
 }
 
 /// [`AwaitEOT`] is returned by an [`Anchor`] for the caller to await the final transaction state
@@ -117,6 +175,28 @@ pub(super) struct AwaitEOT<'d, S: Sequencer> {
     deadline: Instant,
 }
 
+/// [`LogSpaceReservation`] releases the log space reserved for changes to database objects when
+/// dropped unless the log records of the changes have been generated.
+///
+/// The reservation is released if acquiring the database objects failed or the future acquiring
+/// them was dropped.
+struct LogSpaceReservation<'d, 'o, S: Sequencer, P: PersistenceLayer<S>> {
+    /// The persistence layer in which the log space is reserved.
+    persistence_layer: &'d P,
+
+    /// The transaction identifier.
+    transaction_id: TransactionID,
+
+    /// The journal identifier.
+    journal_id: ID,
+
+    /// The database objects being created or deleted.
+    object_ids: &'o [u64],
+
+    /// Phantom to use `S`.
+    _phantom: PhantomData<S>,
+}
+
 /// Relationship between the access requester and the database object owner.
 #[derive(Debug)]
 pub(super) enum Relationship<S: Sequencer> {
@@ -199,8 +279,25 @@ impl<'d, 't, S: Sequencer, P: PersistenceLayer<S>> Journal<'d, 't, S, P> {
     #[inline]
     #[must_use]
     pub fn submit(mut self) -> NonZeroU32 {
+        let guard = ebr::Guard::new();
+        for delta in &self.statistics_deltas {
+            if let Some(container) = self.database().container(delta.container_id, &guard) {
+                container.apply_statistics_delta(delta);
+            }
+        }
+        let submit_instant = self.transaction.submit_journal(
+            &self.anchor,
+            self.log_buffer.take(),
+            take(&mut self.changes),
+            take(&mut self.optimistic_accesses),
+            take(&mut self.reads),
+            take(&mut self.memory_usage),
+        );
+        self.transaction
+            .submit_owned_objects(submit_instant, take(&mut self.owned_objects));
         self.transaction
-            .submit_journal(&self.anchor, self.log_buffer.take())
+            .submit_renamed_containers(submit_instant, take(&mut self.renamed_containers));
+        submit_instant
     }
 
     /// Captures the current state of the [`Journal`] as a [`Snapshot`].
@@ -224,6 +321,27 @@ impl<'d, 't, S: Sequencer, P: PersistenceLayer<S>> Journal<'d, 't, S, P> {
         Snapshot::from_journal(self.transaction.database(), self.journal_snapshot())
     }
 
+    /// Returns the current state of the [`Transaction`] that the [`Journal`] belongs to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, TransactionState};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("transaction_state")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let journal = transaction.journal();
+    ///     assert_eq!(journal.transaction_state(), TransactionState::Active);
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn transaction_state(&self) -> TransactionState<S::Instant> {
+        self.anchor.transaction_state()
+    }
+
     /// Creates database objects with the [`Journal`].
     ///
     /// # Errors
@@ -235,6 +353,12 @@ impl<'d, 't, S: Sequencer, P: PersistenceLayer<S>> Journal<'d, 't, S, P> {
         object_ids: &[u64],
         deadline: Option<Instant>,
     ) -> Result<(), Error> {
+        let reservation = LogSpaceReservation::new(
+            self.transaction.database().persistence_layer(),
+            self.transaction.id(),
+            self.id(),
+            object_ids,
+        )?;
         for id in object_ids {
             self.transaction
                 .database()
@@ -242,7 +366,7 @@ impl<'d, 't, S: Sequencer, P: PersistenceLayer<S>> Journal<'d, 't, S, P> {
                 .create(*id, self, deadline)
                 .await?;
         }
-        let log_buffer = self.log_buffer.take().map_or_else(Arc::default, |b| b);
+        let log_buffer = self.log_buffer.take().unwrap_or_default();
         let log_buffer = self.transaction.database().persistence_layer().create(
             log_buffer,
             self.transaction.id(),
@@ -250,9 +374,178 @@ impl<'d, 't, S: Sequencer, P: PersistenceLayer<S>> Journal<'d, 't, S, P> {
             object_ids,
         )?;
         self.log_buffer.replace(log_buffer);
+        reservation.consume();
+        Ok(())
+    }
+
+    /// Creates database objects in ascending identifier order with the [`Journal`].
+    ///
+    /// The identifiers are sorted and deduplicated before any of them is created, therefore
+    /// transactions creating overlapping sets of database objects with this method always acquire
+    /// them in the same order and never deadlock each other. A single log record is generated for
+    /// all the database objects.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the database objects could not be created; database objects
+    /// created before the failure remain owned by the [`Journal`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("create_many")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     assert!(journal.create_many([3, 1, 2, 1], None).await.is_ok());
+    /// };
+    /// ```
+    #[inline]
+    pub async fn create_many<I: IntoIterator<Item = u64>>(
+        &mut self,
+        object_ids: I,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        let mut object_ids: Vec<u64> = object_ids.into_iter().collect();
+        object_ids.sort_unstable();
+        object_ids.dedup();
+        self.create(&object_ids, deadline).await
+    }
+
+    /// Shares database objects with the [`Journal`].
+    ///
+    /// Shared ownership prevents the database objects from being deleted or locked by other
+    /// transactions until the transaction is ended, while multiple transactions can share the same
+    /// database object. The ownership can be upgraded to exclusive ownership by deleting or
+    /// locking the database object in the same transaction once the other shared owners are
+    /// ended.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the database objects could not be shared, e.g., being deleted.
+    /// [`Error::Deadlock`] is returned when upgrading the ownership if another shared owner is
+    /// already waiting to upgrade its ownership of the same database object.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("share")).await.unwrap();
+    ///     let transaction_1 = database.transaction();
+    ///     let transaction_2 = database.transaction();
+    ///     let mut journal_1 = transaction_1.journal();
+    ///     let mut journal_2 = transaction_2.journal();
+    ///     assert!(journal_1.share(&[1], None).await.is_ok());
+    ///     assert!(journal_2.share(&[1], None).await.is_ok());
+    ///     assert!(journal_2.delete(&[1], None).await.is_err());
+    /// };
+    /// ```
+    #[inline]
+    pub async fn share(
+        &mut self,
+        object_ids: &[u64],
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        for id in object_ids {
+            self.transaction
+                .database()
+                .access_controller()
+                .share(*id, self, deadline)
+                .await?;
+        }
         Ok(())
     }
 
+    /// Reads a database object with the [`Journal`], and returns `true` if the database object is
+    /// visible to the [`Snapshot`].
+    ///
+    /// The visibility of the database object is recorded in the [`Journal`] once it is
+    /// submitted, and the visibility is revalidated against the latest committed state of the
+    /// database when the transaction is prepared for commit; the transaction fails to be
+    /// prepared with [`Error::Conflict`] if another transaction has committed a creation or
+    /// deletion of the database object that changes the visibility. Database objects owned by
+    /// the transaction at the time are not revalidated.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the database object could not be read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("journal_read")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let snapshot = database.snapshot();
+    ///     assert_eq!(journal.read(1, &snapshot, None).await, Ok(true));
+    /// };
+    /// ```
+    #[inline]
+    pub async fn read(
+        &mut self,
+        object_id: u64,
+        snapshot: &Snapshot<'_, '_, '_, S>,
+        deadline: Option<Instant>,
+    ) -> Result<bool, Error> {
+        let visible = self
+            .transaction
+            .database()
+            .access_controller()
+            .read(object_id, snapshot, deadline)
+            .await?;
+        journal_pool::reserve(&mut self.reads, self.transaction.database().telemetry());
+        self.reads.push((object_id, visible));
+        Ok(visible)
+    }
+
+    /// Returns the other transactions holding the database object that have yet to be ended.
+    ///
+    /// The database object is examined without waiting for it or being queued for it, so that
+    /// the caller can do something else instead of being blocked if the database object is held
+    /// by other transactions; an empty [`Vec`] means that no other active transactions hold it.
+    /// The holders are described as of when the database object was examined; they may release
+    /// the database object or other transactions may acquire it afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{AccessType, Database};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("journal_try_peek")).await.unwrap();
+    ///     let mut transaction_1 = database.transaction();
+    ///     transaction_1.set_tag("batch").unwrap();
+    ///     let mut journal_1 = transaction_1.journal();
+    ///     assert!(journal_1.create(&[1], None).await.is_ok());
+    ///     assert!(journal_1.try_peek(1).is_empty());
+    ///
+    ///     let transaction_2 = database.transaction();
+    ///     let journal_2 = transaction_2.journal();
+    ///     let holders = journal_2.try_peek(1);
+    ///     assert_eq!(holders[0].access_type, AccessType::Create);
+    ///     assert_eq!(holders[0].tag.as_deref(), Some("batch"));
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn try_peek(&self, object_id: u64) -> Vec<ObjectHolder<S::Instant>> {
+        self.transaction
+            .database()
+            .access_controller()
+            .holders(object_id, self.transaction.id())
+    }
+
     /// Deletes database objects with the [`Journal`].
     ///
     /// # Errors
@@ -264,6 +557,12 @@ impl<'d, 't, S: Sequencer, P: PersistenceLayer<S>> Journal<'d, 't, S, P> {
         object_ids: &[u64],
         deadline: Option<Instant>,
     ) -> Result<(), Error> {
+        let reservation = LogSpaceReservation::new(
+            self.transaction.database().persistence_layer(),
+            self.transaction.id(),
+            self.id(),
+            object_ids,
+        )?;
         for id in object_ids {
             self.transaction
                 .database()
@@ -271,7 +570,7 @@ impl<'d, 't, S: Sequencer, P: PersistenceLayer<S>> Journal<'d, 't, S, P> {
                 .delete(*id, self, deadline)
                 .await?;
         }
-        let log_buffer = self.log_buffer.take().map_or_else(Arc::default, |b| b);
+        let log_buffer = self.log_buffer.take().unwrap_or_default();
         let log_buffer = self.transaction.database().persistence_layer().delete(
             log_buffer,
             self.transaction.id(),
@@ -279,9 +578,134 @@ impl<'d, 't, S: Sequencer, P: PersistenceLayer<S>> Journal<'d, 't, S, P> {
             object_ids,
         )?;
         self.log_buffer.replace(log_buffer);
+        reservation.consume();
+        Ok(())
+    }
+
+    /// Writes a new version of a key-value pair identified as a database object created by the
+    /// [`Journal`] to the log.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if log space could not be reserved for the version.
+    pub(super) fn write(&mut self, version: &VersionRecord<'_>) -> Result<(), Error> {
+        let persistence_layer = self.transaction.database().persistence_layer();
+        persistence_layer.reserve_version_log_space(version)?;
+        let log_buffer = self.log_buffer.take().unwrap_or_default();
+        let log_buffer =
+            persistence_layer.write(log_buffer, self.transaction.id(), self.id(), version)?;
+        self.log_buffer.replace(log_buffer);
+        Ok(())
+    }
+
+    /// Reserves memory to be retained by the [`Journal`] until the transaction is ended.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::OutOfMemoryBudget`] if the memory budget of the transaction would be
+    /// exceeded.
+    pub(super) fn reserve_memory(&mut self, bytes: usize) -> Result<(), Error> {
+        let memory_usage = self.memory_usage.saturating_add(bytes);
+        if let Some(limit) = self.transaction.memory_limit() {
+            if self.transaction.memory_usage().saturating_add(memory_usage) > limit {
+                return Err(Error::OutOfMemoryBudget);
+            }
+        }
+        self.memory_usage = memory_usage;
         Ok(())
     }
 
+    /// Records a change to a key-value pair.
+    pub(super) fn record_change(&mut self, change: Change) {
+        journal_pool::reserve(&mut self.changes, self.transaction.database().telemetry());
+        self.changes.push(change);
+    }
+
+    /// Records a database object that the [`Journal`] is creating or deleting.
+    pub(super) fn record_owned_object(&mut self, object_id: u64) {
+        self.owned_objects.push(object_id);
+    }
+
+    /// Records a change to the statistics of a container.
+    pub(super) fn record_statistics_delta(&mut self, delta: StatisticsDelta) {
+        if let Some(d) = self
+            .statistics_deltas
+            .iter_mut()
+            .find(|d| d.container_id == delta.container_id)
+        {
+            d.merge(&delta);
+        } else {
+            self.statistics_deltas.push(delta);
+        }
+    }
+
+    /// Records that the container was renamed, so that the name of the container is changed when
+    /// the transaction is committed.
+    pub(super) fn record_rename(&mut self, container_id: u64, name: Arc<str>) {
+        self.renamed_containers.push((container_id, name));
+    }
+
+    /// Tracks a record lock acquired in the container, and returns the number of record locks
+    /// held by the [`Journal`] in the container.
+    pub(super) fn track_record_lock(&mut self, container_id: u64, lock_id: u64) -> usize {
+        let index = if let Some(index) = self
+            .record_locks
+            .iter()
+            .position(|(c, _)| *c == container_id)
+        {
+            index
+        } else {
+            journal_pool::reserve(
+                &mut self.record_locks,
+                self.transaction.database().telemetry(),
+            );
+            self.record_locks.push((container_id, BTreeSet::new()));
+            self.record_locks.len() - 1
+        };
+        let locks = &mut self.record_locks[index].1;
+        locks.insert(lock_id);
+        locks.len()
+    }
+
+    /// Takes the record locks acquired by the [`Journal`] in the container.
+    pub(super) fn take_record_locks(&mut self, container_id: u64) -> BTreeSet<u64> {
+        self.record_locks
+            .iter()
+            .position(|(c, _)| *c == container_id)
+            .map(|index| self.record_locks.swap_remove(index).1)
+            .unwrap_or_default()
+    }
+
+    /// Buffers an access to a key-value pair made by an optimistic transaction.
+    pub(super) fn push_optimistic_access(&mut self, access: OptimisticAccess) {
+        let telemetry = self.transaction.database().telemetry();
+        journal_pool::reserve(&mut self.optimistic_accesses, telemetry);
+        self.optimistic_accesses.push(access);
+    }
+
+    /// Finds the latest access to a key-value pair made by the [`Journal`] or submitted
+    /// [`Journal`] instances of the optimistic transaction with the supplied function.
+    pub(super) fn find_optimistic_access<R, F: FnMut(&OptimisticAccess) -> Option<R>>(
+        &self,
+        mut f: F,
+    ) -> Option<R> {
+        self.optimistic_accesses
+            .iter()
+            .rev()
+            .find_map(&mut f)
+            .or_else(|| self.transaction.find_optimistic_access(f))
+    }
+
+    /// Returns a reference to the [`Transaction`].
+    pub(super) fn transaction(&self) -> &'t Transaction<'d, S, P> {
+        self.transaction
+    }
+
+    /// Returns a reference to the [`Database`](super::Database).
+    pub(super) fn database(&self) -> &'d super::Database<S, P> {
+        self.transaction.database()
+    }
+
     /// Returns a reference to the [`TaskProcessor`].
     pub(super) fn task_processor(&self) -> &'d TaskProcessor {
         self.transaction.database().task_processor()
@@ -301,16 +725,24 @@ impl<'d, 't, S: Sequencer, P: PersistenceLayer<S>> Journal<'d, 't, S, P> {
             transaction,
             log_buffer: None,
             anchor: ebr::Shared::new(Anchor::new(transaction_anchor, transaction.now())),
+            changes: Vec::new(),
+            optimistic_accesses: Vec::new(),
+            reads: Vec::new(),
+            memory_usage: 0,
+            record_locks: Vec::new(),
+            statistics_deltas: Vec::new(),
+            owned_objects: Vec::new(),
+            renamed_containers: Vec::new(),
         }
     }
 
     /// Creates a new [`JournalSnapshot`].
-    fn journal_snapshot(&self) -> JournalSnapshot {
+    fn journal_snapshot(&self) -> JournalSnapshot<'_> {
         JournalSnapshot::new(self.anchor.id())
     }
 }
 
-impl<'d, 't, S: Sequencer, P: PersistenceLayer<S>> Drop for Journal<'d, 't, S, P> {
+impl<S: Sequencer, P: PersistenceLayer<S>> Drop for Journal<'_, '_, S, P> {
     #[inline]
     fn drop(&mut self) {
         if self.anchor.submit_instant().is_none() {
@@ -325,19 +757,58 @@ impl<'d, 't, S: Sequencer, P: PersistenceLayer<S>> Drop for Journal<'d, 't, S, P
                 );
             }
         }
+        journal_pool::recycle(take(&mut self.changes));
+        journal_pool::recycle(take(&mut self.optimistic_accesses));
+        journal_pool::recycle(take(&mut self.reads));
+        journal_pool::recycle(take(&mut self.record_locks));
+    }
+}
+
+impl<'d, 'o, S: Sequencer, P: PersistenceLayer<S>> LogSpaceReservation<'d, 'o, S, P> {
+    /// Reserves log space for the database objects.
+    fn new(
+        persistence_layer: &'d P,
+        transaction_id: TransactionID,
+        journal_id: ID,
+        object_ids: &'o [u64],
+    ) -> Result<Self, Error> {
+        persistence_layer.reserve_log_space(transaction_id, journal_id, object_ids)?;
+        Ok(LogSpaceReservation {
+            persistence_layer,
+            transaction_id,
+            journal_id,
+            object_ids,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Consumes the reservation after the log records have been generated.
+    fn consume(self) {
+        forget(self);
+    }
+}
+
+impl<S: Sequencer, P: PersistenceLayer<S>> Drop for LogSpaceReservation<'_, '_, S, P> {
+    #[inline]
+    fn drop(&mut self) {
+        self.persistence_layer.release_log_space(
+            self.transaction_id,
+            self.journal_id,
+            self.object_ids,
+        );
     }
 }
 
 impl<S: Sequencer> Anchor<S> {
     /// The identifier of the corresponding journal is returned.
     pub(super) fn id(&self) -> ID {
-        debug_assert_eq!((self as *const Anchor<S> as ID) & 0b111, 0);
-        self as *const Anchor<S> as ID
+        debug_assert_eq!((std::ptr::from_ref::<Anchor<S>>(self) as ID) & 0b111, 0);
+        std::ptr::from_ref::<Anchor<S>>(self) as ID
     }
 
     /// The transaction identifier is returned.
     pub(super) fn transaction_id(&self) -> TransactionID {
-        self.transaction_anchor.as_ptr() as TransactionID
+        self.transaction_anchor.id()
     }
 
     /// Gets the end-of-transaction time instant.
@@ -347,6 +818,16 @@ impl<S: Sequencer> Anchor<S> {
         self.transaction_anchor.eot_instant()
     }
 
+    /// Returns the current state of the transaction.
+    pub(super) fn transaction_state(&self) -> TransactionState<S::Instant> {
+        self.transaction_anchor.transaction_state()
+    }
+
+    /// Returns the [`Anchor`](TransactionAnchor) of the transaction.
+    pub(super) fn transaction_anchor(&self) -> &ebr::Shared<TransactionAnchor<S>> {
+        &self.transaction_anchor
+    }
+
     /// Checks if the [`Journal`] was rolled back.
     pub(super) fn is_rolled_back(&self) -> bool {
         // The anchor was rolled back.
@@ -375,7 +856,7 @@ impl<S: Sequencer> Anchor<S> {
         deadline: Option<Instant>,
     ) -> Result<bool, AwaitEOT<'d, S>> {
         if let Some(journal_snapshot) = snapshot.journal_snapshot() {
-            if JournalSnapshot::new(self as *const _ as u64) == *journal_snapshot {
+            if JournalSnapshot::new(std::ptr::from_ref(self) as u64) == *journal_snapshot {
                 // It comes from the same transaction and journal.
                 return Ok(true);
             }
@@ -394,31 +875,29 @@ impl<S: Sequencer> Anchor<S> {
             }
         }
 
-        if let Some(eot_instant) = self.transaction_anchor.eot_instant() {
-            if self.is_rolled_back() {
-                // The journal was rolled back.
-                //
-                // `rolled_back` has to be checked after checking the transaction state.
-                return Ok(false);
-            }
-            return Ok(
-                eot_instant != S::Instant::default() && eot_instant <= snapshot.database_snapshot()
-            );
-        }
-
-        if let Some(prepare_instant) = self.transaction_anchor.prepare_instant() {
-            if prepare_instant != S::Instant::default()
-                && prepare_instant >= snapshot.database_snapshot()
-            {
-                return Ok(false);
-            }
-
-            if let Some(deadline) = deadline {
-                return Err(self.await_eot(snapshot.task_processor(), deadline));
+        match self
+            .transaction_anchor
+            .visibility(snapshot.database_snapshot())
+        {
+            // The journal may have been rolled back.
+            //
+            // `rolled_back` has to be checked after checking the transaction state.
+            Visibility::Visible => Ok(!self.is_rolled_back()),
+            Visibility::Invisible => Ok(false),
+            Visibility::Undetermined => {
+                if snapshot.transaction_snapshot().is_some_and(|t| {
+                    self.transaction_anchor
+                        .is_visible_to_dependent(t.id(), snapshot.database_snapshot())
+                }) {
+                    // The reader took over a lock that the transaction released before its commit
+                    // log record was persisted, and the reader depends on its commit.
+                    return Ok(!self.is_rolled_back());
+                }
+                deadline.map_or(Ok(false), |deadline| {
+                    Err(self.await_eot(snapshot.task_processor(), deadline))
+                })
             }
         }
-
-        Ok(false)
     }
 
     /// Checks if the supplied [`Journal`] is able to modify any outcomes of `self`.
@@ -441,7 +920,7 @@ impl<S: Sequencer> Anchor<S> {
         } else if self.transaction_id() == anchor.transaction_id() {
             // They are from the same transaction.
             let submit_instant = self.submit_instant();
-            if submit_instant.map_or(false, |i| anchor.creation_instant.map_or(false, |a| i <= a)) {
+            if submit_instant.is_some_and(|i| anchor.creation_instant.is_some_and(|a| i <= a)) {
                 if self.submitted.load(Relaxed) {
                     // The requester is a newer journal in the transaction, or the same with the owner.
                     Relationship::Linearizable
@@ -463,6 +942,21 @@ impl<S: Sequencer> Anchor<S> {
         }
     }
 
+    /// Lets the supplied [`Journal`] take over a lock owned by `self` if the transaction released
+    /// its locks before its commit log record was persisted.
+    ///
+    /// Returns the commit instant of the transaction of `self` if the lock can be taken over, and
+    /// the transaction of the requester depends on the commit of the transaction of `self` from
+    /// then on.
+    pub(super) fn take_over(&self, anchor: &Anchor<S>) -> Option<S::Instant> {
+        if self.transaction_id() == anchor.transaction_id() || self.is_rolled_back() {
+            return None;
+        }
+        let commit_instant = self.transaction_anchor.release_instant()?;
+        TransactionAnchor::depend_on_commit(&anchor.transaction_anchor, &self.transaction_anchor);
+        Some(commit_instant)
+    }
+
     /// Tells the [`Anchor`] that there is a transaction waiting for a recourse owned by the
     /// [`Anchor`].
     pub(super) fn set_wake_up_others(&self) {
@@ -475,11 +969,7 @@ impl<S: Sequencer> Anchor<S> {
         task_processor: &'d TaskProcessor,
         deadline: Instant,
     ) -> AwaitEOT<'d, S> {
-        AwaitEOT {
-            transaction_anchor: self.transaction_anchor.clone(),
-            task_processor,
-            deadline,
-        }
+        AwaitEOT::new(self.transaction_anchor.clone(), task_processor, deadline)
     }
 
     /// Sets the next [`Anchor`].
@@ -522,8 +1012,21 @@ impl<S: Sequencer> Anchor<S> {
 
     /// Rolls back the changes contained in the associated [`Journal`].
     pub(super) fn rollback(&self, task_processor: &TaskProcessor) {
+        if self.revert() {
+            // The result can be ignored since messages pending in the queue mean that the access
+            // controller will be scanned in the future.
+            task_processor.send_task(Task::ScanAccessController);
+        }
+    }
+
+    /// Rolls back the changes contained in the associated [`Journal`] without waking up waiting
+    /// transactions.
+    ///
+    /// Returns `true` if there are transactions waiting for any resources the [`Journal`] has
+    /// acquired, and the caller has to wake them up.
+    pub(super) fn revert(&self) -> bool {
         self.rolled_back.store(true, Release);
-        self.wake_up_others(task_processor);
+        self.wake_up_others.load(Acquire)
     }
 
     /// Reads its submit instant.
@@ -563,32 +1066,43 @@ impl AccessRequestResult {
     /// therefore this must be called by [`TaskProcessor`].
     ///
     /// `None` is returned if the [`Mutex`] is poisoned.
-    pub(super) fn lock_sync(&self) -> Option<MutexGuard<ResultWakerPair>> {
+    pub(super) fn lock_sync(&self) -> Option<MutexGuard<'_, ResultWakerPair>> {
         self.result_waker.lock().ok()
     }
 }
 
-impl<'d> AwaitResponse<'d> {
+impl<'d, S: Sequencer> AwaitResponse<'d, S> {
     /// Creates a new [`AwaitResponse`].
-    pub(super) fn new<S: Sequencer>(
+    pub(super) fn new(
+        access_controller: &'d AccessController<S>,
         entry: OccupiedEntry<u64, ObjectState<S>>,
         task_processor: &'d TaskProcessor,
+        telemetry: &'d Telemetry,
         deadline: Instant,
         result_placeholder: Arc<AccessRequestResult>,
-    ) -> AwaitResponse<'d> {
+        transaction_anchor: ebr::Shared<TransactionAnchor<S>>,
+    ) -> AwaitResponse<'d, S> {
         let object_id = *entry.key();
         drop(entry);
+        telemetry.add_tagged(Counter::LockWaits, 1, transaction_anchor.tag());
+        #[cfg(feature = "tracing")]
+        tracing::debug!(object_id, "lock wait started");
         AwaitResponse {
+            access_controller,
             object_id,
             object_id_registered: false,
+            spun: false,
             task_processor,
+            telemetry,
             deadline,
             result_placeholder,
+            transaction_anchor,
+            woundable: access_controller.conflict_policy() == ConflictPolicy::WoundWait,
         }
     }
 }
 
-impl<'d> Future for AwaitResponse<'d> {
+impl<S: Sequencer> Future for AwaitResponse<'_, S> {
     type Output = Result<bool, Error>;
 
     #[inline]
@@ -597,8 +1111,25 @@ impl<'d> Future for AwaitResponse<'d> {
             if let Some(result) = result_waker.0.as_ref() {
                 return Poll::Ready(result.clone());
             }
+            if self.transaction_anchor.is_aborted() {
+                // The transaction was aborted by another thread.
+                result_waker.0.replace(Err(Error::Aborted));
+                return Poll::Ready(Err(Error::Aborted));
+            }
+            if self.woundable && self.transaction_anchor.wait_wound(cx.waker().clone()) {
+                // The transaction was wounded by an older transaction.
+                result_waker.0.replace(Err(Error::Deadlock));
+                return Poll::Ready(Err(Error::Deadlock));
+            }
+            if self.transaction_anchor.wait_cancel(cx.waker()) {
+                // The transaction was cancelled.
+                result_waker.0.replace(Err(Error::Cancelled));
+                return Poll::Ready(Err(Error::Cancelled));
+            }
             if self.deadline < Instant::now() {
                 // The deadline was reached.
+                self.telemetry
+                    .add_tagged(Counter::LockTimeouts, 1, self.transaction_anchor.tag());
                 result_waker.0.replace(Err(Error::Timeout));
                 return Poll::Ready(Err(Error::Timeout));
             }
@@ -608,17 +1139,29 @@ impl<'d> Future for AwaitResponse<'d> {
             return Poll::Pending;
         }
 
-        if !self.object_id_registered {
-            if self
+        let this = self.get_mut();
+        if !this.object_id_registered {
+            if this
                 .task_processor
-                .send_task(Task::MonitorObject(self.object_id))
+                .send_task(Task::MonitorObject(this.object_id))
             {
-                self.get_mut().object_id_registered = true;
+                this.object_id_registered = true;
             }
             cx.waker().wake_by_ref();
-        } else if !self
+            return Poll::Pending;
+        }
+        if !this.spun {
+            // Spin once the object is monitored, so that short waits do not pay for wake-ups.
+            this.spun = true;
+            let result = this.spin();
+            this.access_controller.adapt_spin_rounds(result.is_some());
+            if let Some(result) = result {
+                return Poll::Ready(result);
+            }
+        }
+        if !this
             .task_processor
-            .send_task(Task::WakeUp(self.deadline, cx.waker().clone()))
+            .send_task(Task::WakeUp(this.deadline, cx.waker().clone()))
         {
             cx.waker().wake_by_ref();
         }
@@ -626,7 +1169,49 @@ impl<'d> Future for AwaitResponse<'d> {
     }
 }
 
-impl<'d, S: Sequencer> Future for AwaitEOT<'d, S> {
+impl<S: Sequencer> AwaitResponse<'_, S> {
+    /// Busy-waits for the result with exponential backoff.
+    ///
+    /// Returns `None` if no result was available after the spin rounds.
+    fn spin(&self) -> Option<Result<bool, Error>> {
+        for round in 0..self.access_controller.spin_rounds() {
+            for _ in 0..(1_u32 << round) {
+                spin_loop();
+            }
+            if let Ok(result_waker) = self.result_placeholder.result_waker.try_lock() {
+                if let Some(result) = result_waker.0.as_ref() {
+                    return Some(result.clone());
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl<S: Sequencer> Drop for AwaitResponse<'_, S> {
+    #[inline]
+    fn drop(&mut self) {
+        tracing::debug!(object_id = self.object_id, "lock wait ended");
+    }
+}
+
+impl<'d, S: Sequencer> AwaitEOT<'d, S> {
+    /// Creates an [`AwaitEOT`] for the transaction.
+    pub(super) fn new(
+        transaction_anchor: ebr::Shared<TransactionAnchor<S>>,
+        task_processor: &'d TaskProcessor,
+        deadline: Instant,
+    ) -> AwaitEOT<'d, S> {
+        AwaitEOT {
+            transaction_anchor,
+            task_processor,
+            deadline,
+        }
+    }
+}
+
+impl<S: Sequencer> Future for AwaitEOT<'_, S> {
     type Output = Result<(), Error>;
 
     #[inline]
@@ -659,10 +1244,11 @@ impl<'d, S: Sequencer> Future for AwaitEOT<'d, S> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Database, MonotonicU64};
+    use crate::{AccessType, Database, MonotonicU64};
     use static_assertions::assert_eq_size;
     use std::num::NonZeroU32;
     use std::path::Path;
+    use std::time::Duration;
     use tokio::fs::remove_dir_all;
 
     assert_eq_size!(ID, [u8; 8]);
@@ -684,4 +1270,160 @@ mod tests {
         assert_eq!(Some(journal_3.submit()), NonZeroU32::new(4));
         assert!(remove_dir_all(path).await.is_ok());
     }
+
+    #[tokio::test]
+    async fn share() {
+        const DIR: &str = "journal_share_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(journal.create(&[1], None).await.is_ok());
+        assert_eq!(Some(journal.submit()), NonZeroU32::new(1));
+        assert!(transaction.commit().await.is_ok());
+
+        let transaction_1 = database.transaction();
+        let transaction_2 = database.transaction();
+        let mut journal_1 = transaction_1.journal();
+        let mut journal_2 = transaction_2.journal();
+        assert!(journal_1.share(&[1], None).await.is_ok());
+        assert!(journal_2.share(&[1], None).await.is_ok());
+        assert!(journal_2.delete(&[1], None).await.is_err());
+
+        // Both shared owners trying to upgrade would wait for each other.
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let (result_1, ()) = tokio::join!(journal_1.delete(&[1], Some(deadline)), async {
+            assert_eq!(
+                journal_2.delete(&[1], Some(deadline)).await,
+                Err(Error::Deadlock)
+            );
+            drop(journal_2);
+        });
+        assert!(result_1.is_ok());
+        assert_eq!(Some(journal_1.submit()), NonZeroU32::new(1));
+        assert!(transaction_1.commit().await.is_ok());
+        drop(transaction_2);
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn create_many() {
+        const DIR: &str = "journal_create_many_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction_1 = database.transaction();
+        let mut journal_1 = transaction_1.journal();
+        assert!(journal_1
+            .create_many((0..16).rev().chain(4..8), None)
+            .await
+            .is_ok());
+
+        let transaction_2 = database.transaction();
+        let mut journal_2 = transaction_2.journal();
+        for id in 0..16 {
+            assert_eq!(
+                journal_2.create(&[id], None).await,
+                Err(Error::SerializationFailure)
+            );
+        }
+        assert!(journal_2.create_many([16, 17], None).await.is_ok());
+        drop(journal_2);
+
+        assert_eq!(Some(journal_1.submit()), NonZeroU32::new(1));
+        assert!(transaction_1.commit().await.is_ok());
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn read() {
+        const DIR: &str = "journal_read_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+
+        let transaction_1 = database.transaction();
+        let mut journal_1 = transaction_1.journal();
+        let snapshot = database.snapshot();
+        assert_eq!(journal_1.read(1, &snapshot, None).await, Ok(true));
+        assert_eq!(journal_1.read(2, &snapshot, None).await, Ok(true));
+        drop(snapshot);
+        assert!(journal_1.create(&[2], None).await.is_ok());
+        assert_eq!(Some(journal_1.submit()), NonZeroU32::new(1));
+
+        let transaction_2 = database.transaction();
+        let mut journal_2 = transaction_2.journal();
+        assert!(journal_2.create(&[3], None).await.is_ok());
+        let _: NonZeroU32 = journal_2.submit();
+        let snapshot = database.snapshot();
+        let transaction_2_id = transaction_2.id();
+        assert!(transaction_2.commit().await.is_ok());
+
+        // Reading `3` before it was created conflicts with `transaction_2`.
+        let transaction_3 = database.transaction();
+        let mut journal_3 = transaction_3.journal();
+        assert_eq!(journal_3.read(3, &snapshot, None).await, Ok(false));
+        let _: NonZeroU32 = journal_3.submit();
+        assert_eq!(
+            transaction_3.prepare().await.err(),
+            Some(Error::conflict(3, Some(transaction_2_id)))
+        );
+
+        // Rewinding the transaction discards the recorded reads.
+        let mut transaction_4 = database.transaction();
+        let _: NonZeroU32 = transaction_4.journal().submit();
+        let mut journal_4 = transaction_4.journal();
+        assert_eq!(journal_4.read(3, &snapshot, None).await, Ok(false));
+        drop(snapshot);
+        assert_eq!(Some(journal_4.submit()), NonZeroU32::new(2));
+        assert_eq!(
+            transaction_4.rewind(NonZeroU32::new(1)),
+            Ok(NonZeroU32::new(1))
+        );
+        assert!(transaction_4.commit().await.is_ok());
+
+        assert!(transaction_1.commit().await.is_ok());
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn try_peek() {
+        const DIR: &str = "journal_try_peek_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+
+        let mut transaction_1 = database.transaction();
+        assert!(transaction_1.set_tag("writer").is_ok());
+        let mut journal_1 = transaction_1.journal();
+        assert!(journal_1.create(&[1], None).await.is_ok());
+        assert!(journal_1.try_peek(1).is_empty());
+        assert_eq!(Some(journal_1.submit()), NonZeroU32::new(1));
+
+        let transaction_2 = database.transaction();
+        let mut journal_2 = transaction_2.journal();
+        assert!(journal_2.try_peek(2).is_empty());
+        let holders = journal_2.try_peek(1);
+        assert_eq!(holders.len(), 1);
+        assert_eq!(holders[0].access_type, AccessType::Create);
+        assert_eq!(holders[0].tag.as_deref(), Some("writer"));
+        assert_eq!(holders[0].state, TransactionState::Active);
+        let writer_start_clock = holders[0].start_clock;
+
+        // Database objects held by ended transactions are not reported.
+        assert!(transaction_1.commit().await.is_ok());
+        assert!(journal_2.try_peek(1).is_empty());
+        assert!(journal_2.share(&[1], None).await.is_ok());
+        assert!(journal_2.try_peek(1).is_empty());
+
+        let transaction_3 = database.transaction();
+        let journal_3 = transaction_3.journal();
+        let holders = journal_3.try_peek(1);
+        assert_eq!(holders.len(), 1);
+        assert_eq!(holders[0].access_type, AccessType::Protect);
+        assert_eq!(holders[0].tag, None);
+        assert!(holders[0].start_clock > writer_start_clock);
+        drop(journal_3);
+        drop(journal_2);
+        drop(transaction_2);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
 }
@@ -0,0 +1,200 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Thread-local pool of buffers used by [`Journal`](super::Journal) instances.
+//!
+//! A [`Journal`](super::Journal) takes a buffer from the pool of the current thread when it first
+//! records a change, a read, an optimistic access, or a record lock, and the buffer is returned
+//! to the pool of the thread that finishes using it; therefore, a thread that repeatedly starts
+//! and submits short transactions reuses the same buffers without allocating memory for them.
+
+use super::change_stream::Change;
+use super::container::OptimisticAccess;
+use super::journal::ObjectRead;
+use super::{Counter, Telemetry};
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+
+/// The maximum number of buffers of each kind retained by a thread.
+const MAX_POOLED_BUFFERS: usize = 16;
+
+/// The maximum capacity of a buffer to be retained.
+///
+/// Larger buffers are released in order not to keep memory that was used by a large
+/// transaction.
+const MAX_POOLED_CAPACITY: usize = 4096;
+
+/// Record locks acquired by a [`Journal`](super::Journal) indexed by container identifiers.
+pub(super) type RecordLocks = (u64, BTreeSet<u64>);
+
+/// [`Pooled`] is implemented by the types of elements of pooled buffers.
+pub(super) trait Pooled: Sized {
+    /// Returns the free list of buffers of the type.
+    fn free_list(pool: &mut Pool) -> &mut Vec<Vec<Self>>;
+}
+
+/// [`Pool`] is a set of free lists of buffers.
+#[derive(Debug, Default)]
+pub(super) struct Pool {
+    /// Buffers of changes to key-value pairs.
+    changes: Vec<Vec<Change>>,
+
+    /// Buffers of optimistic accesses to key-value pairs.
+    optimistic_accesses: Vec<Vec<OptimisticAccess>>,
+
+    /// Buffers of database objects read.
+    reads: Vec<Vec<ObjectRead>>,
+
+    /// Buffers of record locks.
+    record_locks: Vec<Vec<RecordLocks>>,
+}
+
+thread_local! {
+    static POOL: RefCell<Pool> = RefCell::default();
+}
+
+/// Makes sure that the buffer has memory allocated for it by taking a buffer from the pool if
+/// the buffer has yet to be allocated.
+///
+/// Each attempt to take a buffer from the pool is reported to the [`Telemetry`] as a
+/// [`Counter::JournalBufferHits`] or a [`Counter::JournalBufferMisses`].
+pub(super) fn reserve<T: Pooled>(buffer: &mut Vec<T>, telemetry: &Telemetry) {
+    if buffer.capacity() != 0 {
+        return;
+    }
+    let pooled = POOL
+        .try_with(|pool| {
+            pool.try_borrow_mut()
+                .ok()
+                .and_then(|mut pool| T::free_list(&mut pool).pop())
+        })
+        .ok()
+        .flatten();
+    if let Some(pooled) = pooled {
+        telemetry.add(Counter::JournalBufferHits, 1);
+        *buffer = pooled;
+    } else {
+        telemetry.add(Counter::JournalBufferMisses, 1);
+    }
+}
+
+/// Returns the buffer to the pool of the current thread after clearing it.
+pub(super) fn recycle<T: Pooled>(mut buffer: Vec<T>) {
+    if buffer.capacity() == 0 || buffer.capacity() > MAX_POOLED_CAPACITY {
+        return;
+    }
+    buffer.clear();
+    let _: Result<(), _> = POOL.try_with(|pool| {
+        if let Ok(mut pool) = pool.try_borrow_mut() {
+            let free_list = T::free_list(&mut pool);
+            if free_list.len() < MAX_POOLED_BUFFERS {
+                free_list.push(buffer);
+            }
+        }
+    });
+}
+
+/// Concatenates the buffers into one of them, and returns the others to the pool.
+pub(super) fn concat<T: Pooled, I: IntoIterator<Item = Vec<T>>>(buffers: I) -> Vec<T> {
+    let mut concatenated = Vec::new();
+    for mut buffer in buffers {
+        if concatenated.capacity() == 0 {
+            concatenated = buffer;
+        } else {
+            concatenated.append(&mut buffer);
+            recycle(buffer);
+        }
+    }
+    concatenated
+}
+
+impl Pooled for Change {
+    fn free_list(pool: &mut Pool) -> &mut Vec<Vec<Self>> {
+        &mut pool.changes
+    }
+}
+
+impl Pooled for OptimisticAccess {
+    fn free_list(pool: &mut Pool) -> &mut Vec<Vec<Self>> {
+        &mut pool.optimistic_accesses
+    }
+}
+
+impl Pooled for ObjectRead {
+    fn free_list(pool: &mut Pool) -> &mut Vec<Vec<Self>> {
+        &mut pool.reads
+    }
+}
+
+impl Pooled for RecordLocks {
+    fn free_list(pool: &mut Pool) -> &mut Vec<Vec<Self>> {
+        &mut pool.record_locks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Database;
+    use std::path::Path;
+    use tokio::fs::remove_dir_all;
+
+    #[test]
+    fn reserve_recycle() {
+        let telemetry = Telemetry::default();
+        let mut reads: Vec<ObjectRead> = Vec::new();
+        reserve(&mut reads, &telemetry);
+        assert_eq!(telemetry.statistics().journal_buffer_misses, 1);
+        reads.push((1, true));
+        let capacity = reads.capacity();
+        recycle(reads);
+
+        let mut reads: Vec<ObjectRead> = Vec::new();
+        reserve(&mut reads, &telemetry);
+        assert_eq!(telemetry.statistics().journal_buffer_hits, 1);
+        assert!(reads.is_empty());
+        assert_eq!(reads.capacity(), capacity);
+
+        // Buffers that are already allocated are not replaced.
+        reserve(&mut reads, &telemetry);
+        assert_eq!(telemetry.statistics().journal_buffer_hits, 1);
+        assert_eq!(telemetry.statistics().journal_buffer_misses, 1);
+
+        // Buffers other than the first one are returned to the pool.
+        let concatenated = concat([vec![(1, true)], vec![(2, false)], Vec::new()]);
+        assert_eq!(concatenated, vec![(1, true), (2, false)]);
+        let mut reads: Vec<ObjectRead> = Vec::new();
+        reserve(&mut reads, &telemetry);
+        assert_eq!(telemetry.statistics().journal_buffer_hits, 2);
+        drop(reads);
+
+        // Large buffers are not retained.
+        recycle(Vec::<ObjectRead>::with_capacity(MAX_POOLED_CAPACITY + 1));
+        let mut reads: Vec<ObjectRead> = Vec::new();
+        reserve(&mut reads, &telemetry);
+        assert_eq!(telemetry.statistics().journal_buffer_misses, 2);
+    }
+
+    #[tokio::test]
+    async fn begin_submit_cycles() {
+        const DIR: &str = "journal_pool_begin_submit_cycles";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let num_cycles = 64;
+        for i in 0..num_cycles {
+            let transaction = database.transaction();
+            let mut journal = transaction.journal();
+            let snapshot = database.snapshot();
+            assert_eq!(journal.read(i, &snapshot, None).await, Ok(true));
+            drop(snapshot);
+            let _: std::num::NonZeroU32 = journal.submit();
+            assert!(transaction.commit().await.is_ok());
+        }
+        let statistics = database.statistics();
+        assert!(statistics.journal_buffer_hits >= num_cycles - 1);
+        assert!(statistics.journal_buffer_misses <= 1);
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+}
@@ -13,41 +13,103 @@
 //! SAP Transactional Storage Framework
 
 mod access_controller;
-pub use access_controller::AccessController;
+pub use access_controller::{AccessController, ConflictPolicy, WaitPolicy};
+
+#[cfg(all(feature = "capi", not(target_arch = "wasm32")))]
+pub mod capi;
+
+mod cancellation_token;
+pub use cancellation_token::CancellationToken;
+
+mod config_delta;
+pub use config_delta::ConfigDelta;
+
+mod catalog;
+
+mod change_stream;
+pub use change_stream::{Change, ChangeBatch, ChangeStream};
+
+mod commit_watermark;
 
 mod container;
-pub use container::Container;
+#[cfg(feature = "async")]
+pub use container::ScanStream;
+pub use container::{
+    Container, ContainerStatistics, LockMode, RecordVersion, Scanner, Subscription, VersionState,
+    Versions,
+};
 
 mod database;
-pub use database::Database;
+pub use database::{Database, ShutdownPolicy};
+
+mod deadlock;
+pub use deadlock::{DeadlockReport, VictimPolicy, WaitsFor};
+
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
+#[cfg(feature = "diagnostics")]
+pub use diagnostics::LingeringAnchor;
+
+mod dependency_graph;
+pub use dependency_graph::{Access, AccessType, DependencyGraph, ObjectDependency, ObjectHolder};
 
 mod error;
-pub use error::Error;
+pub use error::{ConflictReport, Error};
 
 mod journal;
 pub use journal::Journal;
 pub use journal::ID as JournalID;
 
+mod journal_pool;
+
 mod metadata;
-pub use metadata::Metadata;
+pub use metadata::{IndexType, Metadata};
 
 mod persistence_layer;
-pub use persistence_layer::{AwaitIO, FileIO, PersistenceLayer};
+pub use persistence_layer::{
+    AwaitIO, AwaitRecovery, DefaultPersistenceLayer, LogBufferInterface, MemoryPersistence,
+    MemoryStorage, PersistedIndexInterface, PersistenceLayer, RecoveryResult, VersionRecord,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use persistence_layer::{
+    Cipher, DirectoryStore, Durability, Fault, FaultyFile, FileIO, IOBackend, IntegrityProblem,
+    IntegrityReport, LogArchiver, ObjectStore, ObjectStoreArchiver, OpenOptions,
+};
+
+mod replication;
+pub use replication::{ChannelTransport, Follower, Leader, Transport};
 
 pub mod sequencer;
-pub use sequencer::{MonotonicU64, Sequencer};
+pub use sequencer::{MonotonicU64, RemoteSequencer, Sequencer, ShardedU64, TimestampOracle};
+
+#[cfg(feature = "simulation")]
+pub mod simulation;
+
+mod secondary_index;
+
+mod session;
+pub use session::Session;
 
 mod snapshot;
 pub use snapshot::Snapshot;
 
+mod sync;
+
 mod transaction;
 pub use transaction::ID as TransactionID;
-pub use transaction::{Committable, Transaction};
+pub use transaction::{
+    CommitHandle, Committable, IsolationLevel, Playback, Transaction, TransactionState,
+};
 
 pub mod utils;
 
 mod task_processor;
+
 mod telemetry;
+pub use telemetry::{Counter, Statistics, Telemetry};
+
+mod watchdog;
+pub use watchdog::{TransactionReport, Watchdog};
 
 #[cfg(test)]
 mod tests;
@@ -2,7 +2,77 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 /// [`Metadata`] is associated with a [`Container`](super::Container), describing the specification
 /// of the [`Container`](super::Container).
-#[derive(Debug, Default)]
-pub struct Metadata {}
+///
+/// # Examples
+///
+/// ```
+/// use sap_tsf::{IndexType, Metadata};
+///
+/// let metadata = Metadata::default().with_index_type(IndexType::Hash);
+/// assert_eq!(metadata.index_type(), IndexType::Hash);
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Metadata {
+    /// The type of the index structure.
+    index_type: IndexType,
+}
+
+/// [`IndexType`] is the type of the persistent index structure organizing key-value pairs of a
+/// [`Container`](super::Container).
+///
+/// Both types of index structures are built on the same database pages, and key-value pairs in
+/// them follow the same visibility rules.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum IndexType {
+    /// Key-value pairs are stored in a B+tree ordered by their keys.
+    #[default]
+    Ordered,
+
+    /// Key-value pairs are stored in a linear hash table optimized for point lookups.
+    ///
+    /// Key-value pairs are not ordered in the hash table, therefore range scans have to visit
+    /// every bucket.
+    Hash,
+}
+
+impl Metadata {
+    /// Sets the [`IndexType`].
+    #[inline]
+    #[must_use]
+    pub fn with_index_type(mut self, index_type: IndexType) -> Metadata {
+        self.index_type = index_type;
+        self
+    }
+
+    /// Returns the [`IndexType`].
+    #[inline]
+    #[must_use]
+    pub fn index_type(&self) -> IndexType {
+        self.index_type
+    }
+
+    /// Encodes the [`Metadata`] into a byte.
+    pub(super) fn encode(self) -> u8 {
+        match self.index_type {
+            IndexType::Ordered => 0,
+            IndexType::Hash => 1,
+        }
+    }
+
+    /// Decodes [`Metadata`] encoded by [`Metadata::encode`].
+    pub(super) fn decode(byte: u8) -> Option<Metadata> {
+        let index_type = match byte {
+            0 => IndexType::Ordered,
+            1 => IndexType::Hash,
+            _ => return None,
+        };
+        Some(Metadata { index_type })
+    }
+}
@@ -2,18 +2,42 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(not(target_arch = "wasm32"))]
 mod file_io;
-pub use file_io::FileIO;
+#[cfg(not(target_arch = "wasm32"))]
+pub use file_io::{
+    Cipher, DirectoryStore, Durability, Fault, FaultyFile, FileIO, IOBackend, IntegrityProblem,
+    IntegrityReport, LogArchiver, ObjectStore, ObjectStoreArchiver, OpenOptions,
+};
 
-use super::{Database, Error, JournalID, Sequencer, TransactionID};
+mod memory;
+pub use memory::{MemoryPersistence, MemoryStorage};
+
+/// The default [`PersistenceLayer`] of [`Database`].
+///
+/// [`FileIO`] is used unless the target is `wasm32`.
+#[cfg(not(target_arch = "wasm32"))]
+pub type DefaultPersistenceLayer<S> = FileIO<S>;
+
+/// The default [`PersistenceLayer`] of [`Database`].
+///
+/// [`MemoryPersistence`] is used on `wasm32` where no file system is available; storage engines
+/// backed by browser storage can be plugged in by implementing [`PersistenceLayer`].
+#[cfg(target_arch = "wasm32")]
+pub type DefaultPersistenceLayer<S> = MemoryPersistence<S>;
+
+use super::utils::Instant;
+#[cfg(doc)]
+use super::Playback;
+use super::{ConfigDelta, Database, Error, JournalID, Sequencer, Telemetry, TransactionID};
 use std::fmt::Debug;
-use std::future::Future;
+use std::future::{ready, Future};
 use std::marker::PhantomData;
 use std::num::{NonZeroU32, NonZeroU64};
+use std::ops::Bound;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll, Waker};
-use std::time::Instant;
 
 /// The [`PersistenceLayer`] trait defines the interface between [`Database`](super::Database) and
 /// the persistence layer of the database.
@@ -27,11 +51,25 @@ use std::time::Instant;
 ///
 /// The content of each log record must be *idempotent*; the same log record can be applied to the
 /// database more than once on recovery if the log record is close to a checkpoint.
+///
+/// Storage engines other than [`FileIO`] and [`MemoryPersistence`] can be plugged in by
+/// implementing the trait: logged transactions are replayed through [`Playback`] in
+/// [`PersistenceLayer::recover`], values written by [`PersistenceLayer::write`] are installed
+/// through [`Playback::write`], and the recovered [`Database`] is handed over to the
+/// [`AwaitRecovery`] created with [`AwaitRecovery::new`] through
+/// [`PersistenceLayer::check_recovery`]. [`MemoryPersistence`] is built only on these public
+/// interfaces, and serves as a reference implementation; `examples/opfs.rs` stores the log in the
+/// Origin Private File System of a browser on `wasm32`.
 pub trait PersistenceLayer<S: Sequencer>: 'static + Debug + Send + Sized + Sync {
     /// [`PersistenceLayer::LogBuffer`] is kept in a transaction journal to store own log records
     /// until the transaction or journal is ended.
     type LogBuffer: LogBufferInterface;
 
+    /// [`PersistenceLayer::PersistedIndex`] is kept in a [`Container`](super::Container) to read
+    /// key-value pairs that were persisted by a checkpoint and were not loaded into memory when
+    /// the database was recovered.
+    type PersistedIndex: PersistedIndexInterface;
+
     /// Returns `true` if the prepare log record of the transaction needs to be persisted before
     /// committing the transaction.
     ///
@@ -56,7 +94,7 @@ pub trait PersistenceLayer<S: Sequencer>: 'static + Debug + Send + Sized + Sync
         database: Database<S, Self>,
         until: Option<S::Instant>,
         deadline: Option<Instant>,
-    ) -> Result<AwaitRecovery<S, Self>, Error>;
+    ) -> Result<AwaitRecovery<'_, S, Self>, Error>;
 
     /// Backs up the complete database.
     ///
@@ -72,7 +110,42 @@ pub trait PersistenceLayer<S: Sequencer>: 'static + Debug + Send + Sized + Sync
         catalog_only: bool,
         path: Option<&str>,
         deadline: Option<Instant>,
-    ) -> AwaitIO<S, Self>;
+    ) -> AwaitIO<'_, S, Self>;
+
+    /// Takes a checkpoint of the database.
+    ///
+    /// The persistence layer may persist the catalog entries and key-value pairs visible to a
+    /// snapshot of the database, so that recovery does not depend on the log records preceding
+    /// the checkpoint. It is also invoked before the database is shut down. The default
+    /// implementation does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the checkpoint could not be taken.
+    #[inline]
+    fn checkpoint(
+        &self,
+        _database: &Database<S, Self>,
+        _deadline: Option<Instant>,
+    ) -> impl Future<Output = Result<(), Error>> + Send {
+        ready(Ok(()))
+    }
+
+    /// Makes every change durable, and marks the database as cleanly shut down.
+    ///
+    /// The current clock of the database is persisted, so that the database resumes assigning
+    /// clock values above it when opened again. The mark is invalidated once anything is written
+    /// afterwards, and the persistence layer may skip part of recovery when the database is
+    /// opened again if the mark is intact.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the database could not be shut down.
+    fn shutdown(
+        &self,
+        database: &Database<S, Self>,
+        deadline: Option<Instant>,
+    ) -> AwaitIO<'_, S, Self>;
 
     /// The transaction is participating in a distributed transaction.
     fn participate(
@@ -80,7 +153,41 @@ pub trait PersistenceLayer<S: Sequencer>: 'static + Debug + Send + Sized + Sync
         transaction_id: TransactionID,
         xid: &[u8],
         deadline: Option<Instant>,
-    ) -> AwaitIO<S, Self>;
+    ) -> AwaitIO<'_, S, Self>;
+
+    /// Reserves log space for the fact that the supplied database objects are created or deleted.
+    ///
+    /// It is invoked before the database objects are created or deleted, so that the failure to
+    /// log the changes is reported before any changes are made, and the log records generated
+    /// afterwards by [`PersistenceLayer::create`] or [`PersistenceLayer::delete`] are not expected
+    /// to fail for the lack of log space. The default implementation does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if log space could not be reserved.
+    #[inline]
+    fn reserve_log_space(
+        &self,
+        _transaction_id: TransactionID,
+        _journal_id: JournalID,
+        _object_ids: &[u64],
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Releases log space reserved by [`PersistenceLayer::reserve_log_space`].
+    ///
+    /// It is invoked if the database objects could not be created or deleted after the log space
+    /// was reserved, and therefore no log records will be generated for them. The default
+    /// implementation does nothing.
+    #[inline]
+    fn release_log_space(
+        &self,
+        _transaction_id: TransactionID,
+        _journal_id: JournalID,
+        _object_ids: &[u64],
+    ) {
+    }
 
     /// Writes the fact that the supplied database objects have been created.
     ///
@@ -99,6 +206,40 @@ pub trait PersistenceLayer<S: Sequencer>: 'static + Debug + Send + Sized + Sync
         object_ids: &[u64],
     ) -> Result<Arc<Self::LogBuffer>, Error>;
 
+    /// Reserves log space for the [`VersionRecord`] to be written by [`PersistenceLayer::write`].
+    ///
+    /// It is invoked before the [`VersionRecord`] is written, so that the failure to log the
+    /// value is reported before the log buffer is passed to [`PersistenceLayer::write`]. The
+    /// default implementation does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if log space could not be reserved, or the [`VersionRecord`] cannot be
+    /// persisted, e.g., [`Error::WrongParameter`] if the key is too large.
+    #[inline]
+    fn reserve_version_log_space(&self, _version: &VersionRecord<'_>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Writes a new version of a key-value pair.
+    ///
+    /// It is invoked after the database object identifying the version was created with
+    /// [`PersistenceLayer::create`], and the version has to be installed through
+    /// [`Playback::write`] when the log is replayed, otherwise the value is lost once the
+    /// [`Database`] is closed. Full buffers used in the method are automatically submitted to the
+    /// persistence layer, therefore the supplied log buffer and the returned one may differ.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if it fails to write the data to the log buffer.
+    fn write(
+        &self,
+        log_buffer: Arc<Self::LogBuffer>,
+        transaction_id: TransactionID,
+        journal_id: JournalID,
+        version: &VersionRecord<'_>,
+    ) -> Result<Arc<Self::LogBuffer>, Error>;
+
     /// Writes the fact that the supplied database objects have been deleted.
     ///
     /// Full buffers used in the method are automatically submitted to the persistence layer, and a
@@ -166,7 +307,7 @@ pub trait PersistenceLayer<S: Sequencer>: 'static + Debug + Send + Sized + Sync
         transaction_id: TransactionID,
         prepare_instant: S::Instant,
         deadline: Option<Instant>,
-    ) -> AwaitIO<S, Self>;
+    ) -> AwaitIO<'_, S, Self>;
 
     /// A transaction is being committed.
     ///
@@ -179,7 +320,7 @@ pub trait PersistenceLayer<S: Sequencer>: 'static + Debug + Send + Sized + Sync
         transaction_id: TransactionID,
         commit_instant: S::Instant,
         deadline: Option<Instant>,
-    ) -> AwaitIO<S, Self>;
+    ) -> AwaitIO<'_, S, Self>;
 
     /// Returns the current flush epoch.
     ///
@@ -191,7 +332,15 @@ pub trait PersistenceLayer<S: Sequencer>: 'static + Debug + Send + Sized + Sync
     ///
     /// If `None` is given as `expected_flush_epoch` or the current flush epoch has not reached the
     /// specified one, it stores the supplied [`Waker`] in it and returns `false`.
-    fn check_io_completion(&self, expected_flush_epoch: Option<NonZeroU64>, waker: &Waker) -> bool;
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the persistence layer failed to make the IO operation durable.
+    fn check_io_completion(
+        &self,
+        expected_flush_epoch: Option<NonZeroU64>,
+        waker: &Waker,
+    ) -> Result<bool, Error>;
 
     /// Checks if the database has been recovered from the persistence layer.
     ///
@@ -204,6 +353,17 @@ pub trait PersistenceLayer<S: Sequencer>: 'static + Debug + Send + Sized + Sync
 
     /// Cancels database recovery.
     fn cancel_recovery(&self);
+
+    /// Returns the [`Telemetry`] of the persistence layer.
+    ///
+    /// The [`Database`] records its own statistics in the same [`Telemetry`].
+    fn telemetry(&self) -> &Telemetry;
+
+    /// Applies the changes to the parameters of the persistence layer.
+    ///
+    /// Parameters that the persistence layer does not have are ignored.
+    #[inline]
+    fn reconfigure(&self, _delta: &ConfigDelta) {}
 }
 
 /// The interface between a log buffer and the persistence layer.
@@ -222,6 +382,43 @@ pub trait LogBufferInterface: Debug + Default + Send + Sized {
     fn get_durable_flush_epoch(&self) -> Option<NonZeroU64>;
 }
 
+/// The interface between a [`Container`](super::Container) and the index of its key-value pairs
+/// persisted by a checkpoint.
+///
+/// The persistence layer may leave the key-value pairs of a container in an ordered index instead
+/// of installing them in memory when the database is recovered. The container reads a key-value
+/// pair from the index when it is first accessed, and from then on the version in memory takes
+/// precedence over the one in the index; versions in the index are visible according to their
+/// database objects in the same way as versions in memory. A checkpoint passes the versions in
+/// the index to the persistence layer along with those in memory.
+pub trait PersistedIndexInterface: Debug + Send + Sized + Sync {
+    /// Passes the versions of up to `limit` keys in the range to `visitor` in ascending key order.
+    ///
+    /// `readahead` is the number of pages following those being read that the index reads in the
+    /// background, so that the next call does not wait for them; an index that is not paged may
+    /// ignore it. The versions in the index must remain readable until `visitor` returns, i.e.,
+    /// the index must not be replaced with a new one while the versions are passed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the index could not be read.
+    fn scan<F: FnMut(&VersionRecord<'_>) + Send>(
+        &self,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+        limit: usize,
+        readahead: usize,
+        visitor: F,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Returns `false` if the key is certainly not in the index.
+    ///
+    /// The garbage collector keeps a key in memory without any versions as long as the key may be
+    /// in the index, so that the version in the index is not read again after it was deleted. It
+    /// is a synchronous method, therefore it should be run in the background.
+    fn may_contain_sync(&self, key: &[u8]) -> bool;
+}
+
 /// The result of database recovery.
 #[derive(Debug)]
 pub enum RecoveryResult<S: Sequencer, P: PersistenceLayer<S>> {
@@ -237,6 +434,30 @@ pub enum RecoveryResult<S: Sequencer, P: PersistenceLayer<S>> {
     Recovered(Database<S, P>),
 }
 
+/// [`VersionRecord`] is a version of a key-value pair in a [`Container`](super::Container)
+/// passed to [`PersistenceLayer::write`].
+///
+/// The visibility of the version is determined by the database object identified as
+/// [`VersionRecord::object_id`], therefore the log records of the database object are sufficient
+/// to recover it along with the version.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct VersionRecord<'v> {
+    /// The identifier of the [`Container`](super::Container).
+    container_id: u64,
+
+    /// The identifier of the database object locking the key.
+    record_id: u64,
+
+    /// The identifier of the database object of the version.
+    object_id: u64,
+
+    /// The key.
+    key: &'v [u8],
+
+    /// The value.
+    value: &'v [u8],
+}
+
 /// [`AwaitIO`] is returned by a [`PersistenceLayer`] if the content of a log record was
 /// successfully materialized in memory and ready for being persisted.
 ///
@@ -253,6 +474,9 @@ pub struct AwaitIO<'p, S: Sequencer, P: PersistenceLayer<S>> {
 
     /// The deadline of the IO operation.
     deadline: Option<Instant>,
+
+    /// The error that the IO operation failed with before being submitted.
+    error: Option<Error>,
 }
 
 /// [`AwaitRecovery`] is returned by a [`PersistenceLayer`] after triggering a database recovery.
@@ -282,26 +506,112 @@ impl<'p, S: Sequencer, P: PersistenceLayer<S>> AwaitIO<'p, S, P> {
             persistence_layer,
             log_buffer,
             deadline,
+            error: None,
+        }
+    }
+
+    /// Creates an [`AwaitIO`] that immediately fails with the supplied [`Error`].
+    #[inline]
+    pub fn with_error(persistence_layer: &'p P, error: Error) -> AwaitIO<'p, S, P> {
+        AwaitIO {
+            persistence_layer,
+            log_buffer: Arc::default(),
+            deadline: None,
+            error: Some(error),
+        }
+    }
+}
+
+impl<'v> VersionRecord<'v> {
+    /// Creates a new [`VersionRecord`].
+    #[inline]
+    #[must_use]
+    pub fn new(
+        container_id: u64,
+        record_id: u64,
+        object_id: u64,
+        key: &'v [u8],
+        value: &'v [u8],
+    ) -> VersionRecord<'v> {
+        VersionRecord {
+            container_id,
+            record_id,
+            object_id,
+            key,
+            value,
+        }
+    }
+
+    /// Returns the identifier of the [`Container`](super::Container).
+    #[inline]
+    #[must_use]
+    pub fn container_id(&self) -> u64 {
+        self.container_id
+    }
+
+    /// Returns the identifier of the database object locking the key.
+    ///
+    /// The identifier has to be restored along with the version, so that it is not reused for
+    /// other database objects.
+    #[inline]
+    #[must_use]
+    pub fn record_id(&self) -> u64 {
+        self.record_id
+    }
+
+    /// Returns the identifier of the database object of the version.
+    #[inline]
+    #[must_use]
+    pub fn object_id(&self) -> u64 {
+        self.object_id
+    }
+
+    /// Returns the key.
+    #[inline]
+    #[must_use]
+    pub fn key(&self) -> &'v [u8] {
+        self.key
+    }
+
+    /// Returns the value.
+    #[inline]
+    #[must_use]
+    pub fn value(&self) -> &'v [u8] {
+        self.value
+    }
+}
+
+impl<'p, S: Sequencer, P: PersistenceLayer<S>> AwaitRecovery<'p, S, P> {
+    /// Creates an [`AwaitRecovery`] that polls [`PersistenceLayer::check_recovery`] until the
+    /// database is recovered or the deadline is reached.
+    #[inline]
+    pub fn new(persistence_layer: &'p P, deadline: Option<Instant>) -> AwaitRecovery<'p, S, P> {
+        AwaitRecovery {
+            persistence_layer,
+            deadline,
+            _phantom: PhantomData,
         }
     }
 }
 
-impl<'p, S: Sequencer, P: PersistenceLayer<S>> Future for AwaitIO<'p, S, P> {
+impl<S: Sequencer, P: PersistenceLayer<S>> Future for AwaitIO<'_, S, P> {
     type Output = Result<(), Error>;
 
     #[inline]
-    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(error) = self.error.take() {
+            return Poll::Ready(Err(error));
+        }
         let flush_epoch = self.log_buffer.get_durable_flush_epoch();
-        if self
+        match self
             .persistence_layer
             .check_io_completion(flush_epoch, cx.waker())
         {
-            Poll::Ready(Ok(()))
-        } else if self
-            .deadline
-            .as_ref()
-            .map_or(false, |d| *d < Instant::now())
-        {
+            Ok(true) => return Poll::Ready(Ok(())),
+            Err(error) => return Poll::Ready(Err(error)),
+            Ok(false) => (),
+        }
+        if self.deadline.as_ref().is_some_and(|d| *d < Instant::now()) {
             Poll::Ready(Err(Error::Timeout))
         } else {
             // It assumes that the persistence layer will wake up the executor when ready.
@@ -315,7 +625,7 @@ impl<'p, S: Sequencer, P: PersistenceLayer<S>> Future for AwaitIO<'p, S, P> {
     }
 }
 
-impl<'p, S: Sequencer, P: PersistenceLayer<S>> Future for AwaitRecovery<'p, S, P> {
+impl<S: Sequencer, P: PersistenceLayer<S>> Future for AwaitRecovery<'_, S, P> {
     type Output = Result<Database<S, P>, Error>;
 
     #[inline]
@@ -331,12 +641,8 @@ impl<'p, S: Sequencer, P: PersistenceLayer<S>> Future for AwaitRecovery<'p, S, P
                 }
             },
             Err(error) => return Poll::Ready(Err(error)),
-        };
-        if self
-            .deadline
-            .as_ref()
-            .map_or(false, |d| *d < Instant::now())
-        {
+        }
+        if self.deadline.as_ref().is_some_and(|d| *d < Instant::now()) {
             self.persistence_layer.cancel_recovery();
             Poll::Ready(Err(Error::Timeout))
         } else {
@@ -0,0 +1,111 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Memory buffer aligned to the system page size.
+
+use crate::Error;
+use std::alloc::{alloc_zeroed, dealloc, Layout};
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+use std::slice;
+
+/// [`AlignedBuffer`] is a zeroed memory buffer of which the start address is aligned to
+/// [`BUFFER_ALIGNMENT`].
+///
+/// Buffers for direct IO operations must be aligned to the logical block size of the device.
+pub struct AlignedBuffer {
+    /// The start address of the buffer.
+    ptr: NonNull<u8>,
+
+    /// The length of the buffer.
+    len: usize,
+}
+
+/// The alignment of [`AlignedBuffer`].
+pub const BUFFER_ALIGNMENT: usize = 4096;
+
+impl AlignedBuffer {
+    /// Allocates a zeroed buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::OutOfMemory`] if memory allocation failed.
+    pub fn new(len: usize) -> Result<AlignedBuffer, Error> {
+        if len == 0 {
+            return Ok(AlignedBuffer {
+                ptr: NonNull::<[u8; BUFFER_ALIGNMENT]>::dangling().cast(),
+                len,
+            });
+        }
+        let layout = Self::layout(len)?;
+        // Safety: the size of the layout is non-zero.
+        let ptr = unsafe { alloc_zeroed(layout) };
+        let ptr = NonNull::new(ptr).ok_or(Error::OutOfMemory)?;
+        Ok(AlignedBuffer { ptr, len })
+    }
+
+    /// Returns the memory layout of a buffer.
+    fn layout(len: usize) -> Result<Layout, Error> {
+        Layout::from_size_align(len, BUFFER_ALIGNMENT).map_err(|_| Error::OutOfMemory)
+    }
+}
+
+impl Clone for AlignedBuffer {
+    #[inline]
+    fn clone(&self) -> Self {
+        let mut buffer = AlignedBuffer::new(self.len).unwrap_or_else(|_| {
+            std::alloc::handle_alloc_error(Layout::new::<[u8; BUFFER_ALIGNMENT]>())
+        });
+        buffer.copy_from_slice(self);
+        buffer
+    }
+}
+
+impl fmt::Debug for AlignedBuffer {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AlignedBuffer")
+            .field("len", &self.len)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Deref for AlignedBuffer {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        // Safety: the buffer is allocated and initialized, or dangling with zero length.
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl DerefMut for AlignedBuffer {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // Safety: the buffer is allocated and initialized, or dangling with zero length.
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl Drop for AlignedBuffer {
+    #[inline]
+    fn drop(&mut self) {
+        if let Ok(layout) = Self::layout(self.len) {
+            if self.len != 0 {
+                // Safety: the buffer was allocated with the same layout.
+                unsafe {
+                    dealloc(self.ptr.as_ptr(), layout);
+                }
+            }
+        }
+    }
+}
+
+// Safety: the buffer is exclusively owned.
+unsafe impl Send for AlignedBuffer {}
+
+// Safety: the buffer is exclusively owned, and shared references only allow reading it.
+unsafe impl Sync for AlignedBuffer {}
@@ -0,0 +1,634 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Full and incremental backups of the database files.
+//!
+//! A full backup consists of a copy of the database file and the log file, and it can be opened
+//! as a database. The clock of a backup is the length of the log file at the time of the backup.
+//!
+//! An incremental backup consists of the `db.inc` file and the `l.log` file; `db.inc` starts with
+//! `BASE_CLOCK 64-bit|CLOCK 64-bit|DB_LEN 64-bit|PAGE_SIZE 64-bit` followed by
+//! `ADDRESS 64-bit|PAGE` records of the pages written since the base backup, and `l.log` contains
+//! the log records generated since the base backup. A full backup of a database of which the files
+//! are split into segments is also split into segments of the same size.
+
+use super::database_header::DatabaseHeader;
+use super::log_record::LogRecord;
+use super::random_access_file::segment_path;
+use super::{FaultyFile, FileIOData, RandomAccessFile, Sequencer};
+use crate::Error;
+use std::fs::create_dir_all;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::sync::atomic::Ordering::{Acquire, Relaxed};
+use std::sync::Arc;
+
+/// [`BackupTarget`] is a set of files to store backed up data.
+#[derive(Debug)]
+pub struct BackupTarget {
+    /// The file to store database pages.
+    db: RandomAccessFile,
+
+    /// The file to store log records.
+    log: RandomAccessFile,
+
+    /// The clock of the base backup if the backup is incremental.
+    base_clock: Option<u64>,
+}
+
+/// The name of the file storing pages of an incremental backup.
+const INCREMENTAL_FILE: &str = "db.inc";
+
+/// The length of the header of [`INCREMENTAL_FILE`].
+const INCREMENTAL_HEADER_LEN: u64 = 32;
+
+impl BackupTarget {
+    /// Creates the backup files in the specified path.
+    ///
+    /// Existing backup files in the path are truncated. The files of a full backup are split into
+    /// segments if `segment_size` is not `0`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::WrongParameter`] if the path is the same as that of the database, and an
+    /// error if the files could not be created.
+    pub(super) fn create(
+        path: &Path,
+        database_path: &Path,
+        base_clock: Option<u64>,
+        segment_size: u64,
+    ) -> Result<Box<BackupTarget>, Error> {
+        if create_dir_all(path).is_err() {
+            return Err(Error::Generic("the path could not be created"));
+        }
+        if path.canonicalize().ok() == database_path.canonicalize().ok() {
+            return Err(Error::WrongParameter);
+        }
+        let db_file = if base_clock.is_some() {
+            INCREMENTAL_FILE
+        } else {
+            "db.dat"
+        };
+        let mut db = RandomAccessFile::from_file(&path.join(db_file))?;
+        let mut log = RandomAccessFile::from_file(&path.join("l.log"))?;
+        if base_clock.is_none() && segment_size != 0 {
+            db.enable_segments(segment_size)?;
+            log.enable_segments(segment_size)?;
+        }
+        db.set_len(0)?;
+        log.set_len(0)?;
+        Ok(Box::new(BackupTarget {
+            db,
+            log,
+            base_clock,
+        }))
+    }
+
+    /// Attaches the [`FaultyFile`] to the backup files.
+    pub(super) fn set_faulty_file(&self, faulty_file: &FaultyFile) {
+        let _: bool = self.db.set_faulty_file(faulty_file.clone());
+        let _: bool = self.log.set_faulty_file(faulty_file.clone());
+    }
+
+    /// Copies pages and log records up to `log_offset` to the backup files.
+    ///
+    /// It is a synchronous method, therefore it should be run in the background.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database files could not be read or the backup files could not be
+    /// written, in which case the backup is incomplete.
+    pub(super) fn backup_sync<S: Sequencer<Instant = u64>>(
+        &self,
+        file_io_data: &Arc<FileIOData<S>>,
+        log_offset: u64,
+    ) -> Result<(), Error> {
+        let page_manager = &file_io_data.page_manager;
+        let base_clock = self.base_clock.map_or(0, |c| c.min(log_offset));
+        // Log records before the head may have been reclaimed, and a full backup leaves the range
+        // unwritten.
+        let log_head = file_io_data.log_head.load(Acquire).min(log_offset);
+        if self.base_clock.is_some() && base_clock < log_head {
+            return Err(Error::WrongParameter);
+        }
+        if self.base_clock.is_some() {
+            let record_len = 8 + page_manager.page_size();
+            let mut offset = INCREMENTAL_HEADER_LEN;
+            let db_len = page_manager.copy_pages_sync(self.base_clock, log_offset, |a, p| {
+                self.db.write(&a.to_le_bytes(), offset)?;
+                self.db.write(p, offset + 8)?;
+                offset += record_len;
+                Ok(())
+            })?;
+            let mut header = [0_u8; 32];
+            for (i, field) in [base_clock, log_offset, db_len, page_manager.page_size()]
+                .into_iter()
+                .enumerate()
+            {
+                header[i * 8..i * 8 + 8].copy_from_slice(&field.to_le_bytes());
+            }
+            self.db.write(&header, 0)?;
+        } else {
+            let db_len =
+                page_manager.copy_pages_sync(None, log_offset, |a, p| self.db.write(p, a))?;
+            self.db.set_len(db_len)?;
+        }
+        let copy_start = base_clock.max(log_head);
+        copy(
+            &file_io_data.log,
+            copy_start,
+            log_offset,
+            &self.log,
+            copy_start - base_clock,
+        )?;
+        self.log.set_len(log_offset - base_clock)
+    }
+}
+
+/// Returns the clock of the backup in the specified path.
+///
+/// # Errors
+///
+/// Returns an error if the path does not contain a backup.
+pub(super) fn backup_clock(path: &Path) -> Result<u64, Error> {
+    let incremental_path = path.join(INCREMENTAL_FILE);
+    if incremental_path.exists() {
+        let header = read_incremental_header(&RandomAccessFile::from_file(&incremental_path)?)?;
+        return Ok(header[1]);
+    }
+    let (_, log) = open_full_backup(path)?;
+    Ok(log.len(Relaxed))
+}
+
+/// Applies the incremental backup to the full backup, and returns the new clock of the full
+/// backup.
+///
+/// # Errors
+///
+/// Returns [`Error::WrongParameter`] if the clock of the full backup is not the base clock of the
+/// incremental backup, [`Error::CorruptDatabase`] if the incremental backup is incomplete, and an
+/// error if the files could not be read or written.
+pub(super) fn restore(base: &Path, incremental: &Path) -> Result<u64, Error> {
+    if !base.join("db.dat").exists() {
+        return Err(Error::WrongParameter);
+    }
+    let incremental_db = RandomAccessFile::from_file(&incremental.join(INCREMENTAL_FILE))?;
+    let incremental_log = RandomAccessFile::from_file(&incremental.join("l.log"))?;
+    let [base_clock, clock, db_len, page_size] = read_incremental_header(&incremental_db)?;
+    let (db, log) = open_full_backup(base)?;
+    if log.len(Relaxed) != base_clock {
+        return Err(Error::WrongParameter);
+    }
+    if incremental_log.len(Relaxed) != clock - base_clock {
+        return Err(Error::CorruptDatabase);
+    }
+
+    let mut address_buffer = [0_u8; 8];
+    let mut page_buffer = vec![0_u8; usize::try_from(page_size).map_err(|_| Error::OutOfMemory)?];
+    let mut offset = INCREMENTAL_HEADER_LEN;
+    while offset < incremental_db.len(Relaxed) {
+        incremental_db.read(&mut address_buffer, offset)?;
+        incremental_db.read(&mut page_buffer, offset + 8)?;
+        db.write(&page_buffer, u64::from_le_bytes(address_buffer))?;
+        offset += 8 + page_size;
+    }
+    db.set_len(db_len)?;
+
+    copy(&incremental_log, 0, clock - base_clock, &log, base_clock)?;
+    Ok(clock)
+}
+
+/// Appends archived log segments to the full backup, truncates the log at the first commit log
+/// record of which the commit clock is greater than `clock`, and returns the new clock of the full
+/// backup.
+///
+/// # Errors
+///
+/// Returns [`Error::WrongParameter`] if the full backup is encrypted, not split into segments, or
+/// contains transactions committed after the clock, [`Error::CorruptDatabase`] if an archived log
+/// segment is too long, and an error if the files could not be read or written.
+pub(super) fn restore_to<S: Sequencer<Instant = u64>>(
+    base: &Path,
+    archive: &Path,
+    clock: u64,
+) -> Result<u64, Error> {
+    if !base.join("db.dat").exists() {
+        return Err(Error::WrongParameter);
+    }
+    let (db, log) = open_full_backup(base)?;
+    let segment_size = DatabaseHeader::read_segment_size(&db)?;
+    if segment_size == 0 || DatabaseHeader::read_nonce_epoch(&db)? != 0 {
+        return Err(Error::WrongParameter);
+    }
+
+    let base_clock = log.len(Relaxed);
+    let archive_log = archive.join("l.log");
+    let mut segment_index = base_clock / segment_size;
+    loop {
+        let archived_segment_path = segment_path(
+            &archive_log,
+            usize::try_from(segment_index).unwrap_or(usize::MAX),
+        );
+        if !archived_segment_path.exists() {
+            break;
+        }
+        let archived_segment = RandomAccessFile::from_file(&archived_segment_path)?;
+        let segment_len = archived_segment.len(Relaxed);
+        if segment_len > segment_size {
+            return Err(Error::CorruptDatabase);
+        }
+        let segment_start = segment_index * segment_size;
+        let log_len = log.len(Relaxed);
+        if segment_start + segment_len > log_len {
+            copy(
+                &archived_segment,
+                log_len - segment_start,
+                segment_len,
+                &log,
+                log_len,
+            )?;
+        }
+        if segment_len < segment_size {
+            break;
+        }
+        segment_index += 1;
+    }
+
+    if let Some(commit_offset) = find_commit_after::<S>(&log, clock)? {
+        if commit_offset < base_clock {
+            log.set_len(base_clock)?;
+            return Err(Error::WrongParameter);
+        }
+        log.set_len(commit_offset)?;
+    }
+    Ok(log.len(Relaxed))
+}
+
+/// Opens the database and log files of a full backup.
+fn open_full_backup(path: &Path) -> Result<(RandomAccessFile, RandomAccessFile), Error> {
+    if !path.join("db.dat").exists() {
+        return Err(Error::IO(ErrorKind::NotFound));
+    }
+    let mut db = RandomAccessFile::from_file(&path.join("db.dat"))?;
+    let mut log = RandomAccessFile::from_file(&path.join("l.log"))?;
+    let segment_size = DatabaseHeader::read_segment_size(&db)?;
+    if segment_size != 0 {
+        db.enable_segments(segment_size)?;
+        log.enable_segments(segment_size)?;
+    }
+    Ok((db, log))
+}
+
+/// Returns the offset of the first commit log record of which the commit clock is greater than
+/// the specified clock.
+fn find_commit_after<S: Sequencer<Instant = u64>>(
+    log: &RandomAccessFile,
+    clock: u64,
+) -> Result<Option<u64>, Error> {
+    let log_len = log.len(Relaxed);
+    let mut buffer = vec![0_u8; 1 << 16];
+    let mut offset = 0;
+    while offset < log_len {
+        let len = usize::try_from(log_len - offset)
+            .unwrap_or(usize::MAX)
+            .min(buffer.len());
+        log.read(&mut buffer[..len], offset)?;
+        let mut remaining = &buffer[..len];
+        while let Some((log_record, next)) = LogRecord::<S>::from_raw_data(remaining) {
+            match log_record {
+                LogRecord::EndOfLog => return Ok(None),
+                LogRecord::TransactionCommitted(_, commit_instant) if commit_instant > clock => {
+                    return Ok(Some(offset + (len - remaining.len()) as u64));
+                }
+                _ => (),
+            }
+            remaining = next;
+        }
+        if remaining.len() == len {
+            // The last log record is incomplete.
+            break;
+        }
+        offset += (len - remaining.len()) as u64;
+    }
+    Ok(None)
+}
+
+/// Copies `[start, end)` of the source file to `offset` of the target file.
+fn copy(
+    source: &RandomAccessFile,
+    start: u64,
+    end: u64,
+    target: &RandomAccessFile,
+    offset: u64,
+) -> Result<(), Error> {
+    let mut buffer = vec![0_u8; 1 << 16];
+    let mut pos = start;
+    while pos < end {
+        let len = usize::try_from(end - pos)
+            .unwrap_or(usize::MAX)
+            .min(buffer.len());
+        source.read(&mut buffer[..len], pos)?;
+        target.write(&buffer[..len], offset + pos - start)?;
+        pos += len as u64;
+    }
+    Ok(())
+}
+
+/// Reads the header fields of an incremental backup.
+fn read_incremental_header(incremental_db: &RandomAccessFile) -> Result<[u64; 4], Error> {
+    let mut header = [0_u8; 32];
+    incremental_db
+        .read(&mut header, 0)
+        .map_err(|_| Error::CorruptDatabase)?;
+    let mut fields = [0_u64; 4];
+    for (field, bytes) in fields.iter_mut().zip(header.chunks(8)) {
+        *field = u64::from_le_bytes(bytes.try_into().unwrap_or_default());
+    }
+    if fields[0] > fields[1] || fields[3] == 0 {
+        return Err(Error::CorruptDatabase);
+    }
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::evictable_page::EvictablePage;
+    use super::INCREMENTAL_HEADER_LEN;
+    use crate::{Database, Error, Fault, FaultyFile, FileIO, LogArchiver, MonotonicU64, Sequencer};
+    use std::fs::{copy, create_dir_all};
+    use std::io::ErrorKind;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::Ordering::Relaxed;
+    use std::sync::{Arc, Mutex};
+    use tokio::fs::remove_dir_all;
+
+    #[tokio::test]
+    async fn incremental() {
+        const DIR: &str = "backup_incremental_test";
+        const BASE_DIR: &str = "backup_incremental_test_base";
+        const INCREMENTAL_DIR: &str = "backup_incremental_test_incremental";
+        let path = Path::new(DIR);
+        let base_path = Path::new(BASE_DIR);
+        let incremental_path = Path::new(INCREMENTAL_DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let file_io = database.persistence_layer();
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.create(&[0, 1, 2, 3], None).await.unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        assert!(database.backup(false, Some(BASE_DIR), None).await.is_ok());
+        let base_clock = FileIO::<MonotonicU64>::backup_clock(base_path).unwrap();
+
+        let page_size = file_io.page_size();
+        let page_address = file_io.page_manager().create_page(page_size).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.create(&[4, 5, 6, 7], None).await.unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        let instant = database.sequencer().now(Relaxed);
+
+        assert_eq!(
+            file_io
+                .backup_incremental(u64::MAX, incremental_path, None)
+                .await,
+            Err(Error::WrongParameter)
+        );
+        let clock = file_io
+            .backup_incremental(base_clock, incremental_path, None)
+            .await
+            .unwrap();
+        assert!(clock > base_clock);
+        assert_eq!(
+            FileIO::<MonotonicU64>::backup_clock(incremental_path),
+            Ok(clock)
+        );
+
+        // Only the newly created page is backed up.
+        let incremental_len = incremental_path.join("db.inc").metadata().unwrap().len();
+        assert_eq!(incremental_len, INCREMENTAL_HEADER_LEN + 8 + page_size);
+
+        assert_eq!(
+            FileIO::<MonotonicU64>::restore_incremental(base_path, incremental_path),
+            Ok(clock)
+        );
+        assert_eq!(
+            FileIO::<MonotonicU64>::restore_incremental(base_path, incremental_path),
+            Err(Error::WrongParameter)
+        );
+        drop(database);
+
+        let database_restored = Database::with_path(base_path).await.unwrap();
+        assert_eq!(database_restored.sequencer().now(Relaxed), instant);
+        let prev_page_address = database_restored
+            .persistence_layer()
+            .page_manager()
+            .read_page(page_address, EvictablePage::prev_page_address)
+            .await
+            .unwrap();
+        assert_eq!(prev_page_address, page_size);
+        let snapshot = database_restored.snapshot();
+        for o in 0..8 {
+            assert_eq!(
+                database_restored
+                    .access_controller()
+                    .read(o, &snapshot, None)
+                    .await,
+                Ok(true)
+            );
+        }
+
+        drop(snapshot);
+        drop(database_restored);
+        assert!(remove_dir_all(path).await.is_ok());
+        assert!(remove_dir_all(base_path).await.is_ok());
+        assert!(remove_dir_all(incremental_path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn faulty_file() {
+        const DIR: &str = "backup_faulty_file_test";
+        const BACKUP_DIR: &str = "backup_faulty_file_test_backup";
+        let path = Path::new(DIR);
+        let backup_path = Path::new(BACKUP_DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.create(&[0, 1, 2, 3], None).await.unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        // The backup fails with the error instead of retrying the write.
+        let faulty_file = FaultyFile::default();
+        faulty_file.inject(0, Fault::Fail(Error::IO(ErrorKind::Other)));
+        database
+            .persistence_layer()
+            .set_backup_faults(faulty_file.clone());
+        assert_eq!(
+            database.backup(false, Some(BACKUP_DIR), None).await,
+            Err(Error::IOAt(ErrorKind::Other, 0))
+        );
+        assert_eq!(faulty_file.num_writes(), 1);
+
+        // The database is unaffected, and the next backup succeeds.
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.delete(&[0], None).await.unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        assert!(database.backup(false, Some(BACKUP_DIR), None).await.is_ok());
+        drop(database);
+
+        let database_backup = Database::with_path(backup_path).await.unwrap();
+        let snapshot = database_backup.snapshot();
+        for (o, visible) in [(0, false), (1, true)] {
+            assert_eq!(
+                database_backup
+                    .access_controller()
+                    .read(o, &snapshot, None)
+                    .await,
+                Ok(visible)
+            );
+        }
+
+        drop(snapshot);
+        drop(database_backup);
+        assert!(remove_dir_all(path).await.is_ok());
+        assert!(remove_dir_all(backup_path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn faulty_log_copy() {
+        const DIR: &str = "backup_faulty_log_copy_test";
+        const BASE_DIR: &str = "backup_faulty_log_copy_test_base";
+        const INCREMENTAL_DIR: &str = "backup_faulty_log_copy_test_incremental";
+        let path = Path::new(DIR);
+        let base_path = Path::new(BASE_DIR);
+        let incremental_path = Path::new(INCREMENTAL_DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let file_io = database.persistence_layer();
+        assert!(database.backup(false, Some(BASE_DIR), None).await.is_ok());
+        let base_clock = FileIO::<MonotonicU64>::backup_clock(base_path).unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.create(&[0, 1, 2, 3], None).await.unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        // Count the write operations of the backup; copying the log is the last one.
+        let faulty_file = FaultyFile::default();
+        file_io.set_backup_faults(faulty_file.clone());
+        assert!(file_io
+            .backup_incremental(base_clock, incremental_path, None)
+            .await
+            .is_ok());
+        let num_writes = faulty_file.num_writes();
+        faulty_file.inject(2 * num_writes - 1, Fault::Fail(Error::DiskFull));
+        assert_eq!(
+            file_io
+                .backup_incremental(base_clock, incremental_path, None)
+                .await,
+            Err(Error::DiskFull)
+        );
+        assert_eq!(faulty_file.num_writes(), 2 * num_writes);
+
+        // The aborted backup is detected as incomplete.
+        assert_eq!(
+            FileIO::<MonotonicU64>::restore_incremental(base_path, incremental_path),
+            Err(Error::CorruptDatabase)
+        );
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+        assert!(remove_dir_all(base_path).await.is_ok());
+        assert!(remove_dir_all(incremental_path).await.is_ok());
+    }
+
+    #[derive(Debug, Default)]
+    struct DirectoryArchiver(PathBuf, Mutex<Vec<u64>>);
+
+    impl LogArchiver for DirectoryArchiver {
+        fn archive(&self, segment_index: u64, segment_path: &Path) {
+            create_dir_all(&self.0).unwrap();
+            copy(segment_path, self.0.join(segment_path.file_name().unwrap())).unwrap();
+            self.1.lock().unwrap().push(segment_index);
+        }
+    }
+
+    #[tokio::test]
+    async fn point_in_time() {
+        const DIR: &str = "backup_point_in_time_test";
+        const BASE_DIR: &str = "backup_point_in_time_test_base";
+        const ARCHIVE_DIR: &str = "backup_point_in_time_test_archive";
+        let path = Path::new(DIR);
+        let base_path = Path::new(BASE_DIR);
+        let archive_path = Path::new(ARCHIVE_DIR);
+        let file_io = FileIO::with_segment_size(path, 1 << 20).unwrap();
+        let archiver = Arc::new(DirectoryArchiver(
+            archive_path.to_path_buf(),
+            Mutex::default(),
+        ));
+        file_io.set_log_archiver(archiver.clone());
+        let database: Database<MonotonicU64> =
+            Database::with_persistence_layer(file_io, None, None)
+                .await
+                .unwrap();
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.create(&[0, 1, 2, 3], None).await.unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        assert!(database.backup(false, Some(BASE_DIR), None).await.is_ok());
+
+        // Object identifiers in descending order are logged individually to fill a log segment.
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let object_ids: Vec<u64> = (16..65536).rev().collect();
+        journal.create(&object_ids, None).await.unwrap();
+        journal.delete(&[1], None).await.unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        let instant = database.sequencer().now(Relaxed);
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.delete(&[0], None).await.unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        assert_eq!(*archiver.1.lock().unwrap(), vec![0]);
+        assert_eq!(database.persistence_layer().sealed_log_segments().len(), 1);
+        drop(database);
+
+        // The most recent log segment is also replayed.
+        copy(path.join("l.log.1"), archive_path.join("l.log.1")).unwrap();
+        assert_eq!(
+            FileIO::<MonotonicU64>::restore_to(base_path, archive_path, 0),
+            Err(Error::WrongParameter)
+        );
+        let clock = FileIO::<MonotonicU64>::restore_to(base_path, archive_path, instant).unwrap();
+        assert!(clock > 1 << 20);
+
+        let database_restored = Database::with_path(base_path).await.unwrap();
+        assert_eq!(database_restored.sequencer().now(Relaxed), instant);
+        let snapshot = database_restored.snapshot();
+        for (o, visible) in [(0, true), (1, false), (2, true)] {
+            assert_eq!(
+                database_restored
+                    .access_controller()
+                    .read(o, &snapshot, None)
+                    .await,
+                Ok(visible)
+            );
+        }
+
+        drop(snapshot);
+        drop(database_restored);
+        assert!(remove_dir_all(path).await.is_ok());
+        assert!(remove_dir_all(base_path).await.is_ok());
+        assert!(remove_dir_all(archive_path).await.is_ok());
+    }
+}
@@ -0,0 +1,355 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Large objects stored in a chain of database pages.
+
+use super::evictable_page::EvictablePage;
+use super::page_manager::PageManager;
+use crate::Error;
+
+/// [`Blob`] is a large object stored in a chain of database pages.
+///
+/// A [`Blob`] is identified by the address of its first page, and the pages of the [`Blob`] are
+/// linked in order through the page header. The first page is inserted after the page specified
+/// when the [`Blob`] was created, so that the pages are freed along with the page chain of the
+/// owner, e.g., a container index storing values that do not fit in its pages.
+///
+/// The first page starts with `LEN 64-bit` followed by data, and the other pages only contain
+/// data; a [`Blob`] that has not been finished has `LEN = 0`. The visibility of the data is
+/// determined by the owner of the [`Blob`].
+///
+/// Writers must be serialized by the owner of the [`Blob`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Blob {
+    /// The address of the first page.
+    id: u64,
+}
+
+/// [`BlobWriter`] writes data to a new [`Blob`].
+///
+/// Pages are allocated as data is written, and the [`Blob`] becomes visible when
+/// [`BlobWriter::finish`] is called. [`BlobWriter::abort`] returns the pages to the free page list,
+/// e.g., when the transaction writing the [`Blob`] is rolled back or writing data failed.
+#[derive(Debug)]
+pub struct BlobWriter<'p> {
+    /// The [`PageManager`] owning the pages.
+    page_manager: &'p PageManager,
+
+    /// The [`Blob`] being written.
+    blob: Blob,
+
+    /// The address of the last page.
+    last_page_address: u64,
+
+    /// The position of the next byte in the buffer of the last page.
+    offset: usize,
+
+    /// The number of bytes written.
+    len: u64,
+}
+
+/// The length of the blob header in the first page.
+const BLOB_HEADER_LEN: usize = 8;
+
+impl Blob {
+    /// Creates a new [`Blob`], and returns a [`BlobWriter`] to write data to it.
+    ///
+    /// The first page of the [`Blob`] is inserted after the specified page.
+    #[inline]
+    pub async fn create(
+        page_manager: &PageManager,
+        prev_page_address: u64,
+    ) -> Result<BlobWriter<'_>, Error> {
+        let id = page_manager.create_linked_page(prev_page_address).await?;
+        page_manager
+            .write_page(id, |page| {
+                page.buffer_mut()[..BLOB_HEADER_LEN].fill(0);
+                page.set_dirty();
+            })
+            .await?;
+        page_manager.request_write_back(id);
+        Ok(BlobWriter {
+            page_manager,
+            blob: Blob { id },
+            last_page_address: id,
+            offset: BLOB_HEADER_LEN,
+            len: 0,
+        })
+    }
+
+    /// Returns the identifier of the [`Blob`].
+    #[inline]
+    pub fn id(self) -> u64 {
+        self.id
+    }
+
+    /// Reads the data of the [`Blob`] identified as `id`.
+    ///
+    /// It is a synchronous method, therefore it should be run in the background.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page could not be read, or the [`Blob`] is corrupt.
+    #[inline]
+    pub fn read_sync(page_manager: &PageManager, id: u64) -> Result<Vec<u8>, Error> {
+        let (len, mut page_address, mut data) = page_manager.read_page_sync(id, |page| {
+            let buffer = page.buffer();
+            let len = u64::from_le_bytes(buffer[..BLOB_HEADER_LEN].try_into().unwrap_or_default());
+            (
+                len,
+                page.next_page_address(),
+                buffer[BLOB_HEADER_LEN..].to_vec(),
+            )
+        })?;
+        let len = usize::try_from(len).map_err(|_| Error::CorruptPage(id))?;
+        while data.len() < len {
+            if page_address == 0 {
+                return Err(Error::CorruptPage(id));
+            }
+            page_address = page_manager.read_page_sync(page_address, |page| {
+                data.extend_from_slice(page.buffer());
+                page.next_page_address()
+            })?;
+        }
+        data.truncate(len);
+        Ok(data)
+    }
+
+    /// Reads the data of the [`Blob`] identified as `id` through the page cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page could not be read, or the [`Blob`] is corrupt.
+    #[inline]
+    pub async fn read(page_manager: &PageManager, id: u64) -> Result<Vec<u8>, Error> {
+        let (len, mut page_address, mut data) = page_manager
+            .read_page(id, |page| {
+                let buffer = page.buffer();
+                let len =
+                    u64::from_le_bytes(buffer[..BLOB_HEADER_LEN].try_into().unwrap_or_default());
+                (
+                    len,
+                    page.next_page_address(),
+                    buffer[BLOB_HEADER_LEN..].to_vec(),
+                )
+            })
+            .await?;
+        let len = usize::try_from(len).map_err(|_| Error::CorruptPage(id))?;
+        while data.len() < len {
+            if page_address == 0 {
+                return Err(Error::CorruptPage(id));
+            }
+            page_address = page_manager
+                .read_page(page_address, |page| {
+                    data.extend_from_slice(page.buffer());
+                    page.next_page_address()
+                })
+                .await?;
+        }
+        data.truncate(len);
+        Ok(data)
+    }
+
+    /// Moves the pages of the [`Blob`] identified as `id` to free pages nearer the front of the
+    /// database file, and returns the new identifier of the [`Blob`] and the number of pages moved.
+    ///
+    /// The first page identifies the [`Blob`], therefore the owner has to replace the identifier
+    /// with the returned one if the first page was moved; the trailing free pages can be truncated
+    /// afterwards by [`PageManager::defragment`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page could not be moved, or the [`Blob`] is corrupt.
+    #[inline]
+    pub async fn compact(page_manager: &PageManager, id: u64) -> Result<(u64, usize), Error> {
+        let len = Self::len(page_manager, id).await?;
+        let first_page_len = (page_manager.page_payload_len() - BLOB_HEADER_LEN) as u64;
+        let page_len = page_manager.page_payload_len() as u64;
+        let num_pages = len.saturating_sub(first_page_len).div_ceil(page_len);
+        let new_id = page_manager.relocate_page(id).await?;
+        let mut num_pages_moved = usize::from(new_id != id);
+        let mut page_address = new_id;
+        for _ in 0..num_pages {
+            page_address = page_manager
+                .read_page(page_address, EvictablePage::next_page_address)
+                .await?;
+            if page_address == 0 {
+                return Err(Error::CorruptPage(new_id));
+            }
+            let new_page_address = page_manager.relocate_page(page_address).await?;
+            if new_page_address != page_address {
+                page_address = new_page_address;
+                num_pages_moved += 1;
+            }
+        }
+        Ok((new_id, num_pages_moved))
+    }
+
+    /// Reads the length of the [`Blob`].
+    async fn len(page_manager: &PageManager, id: u64) -> Result<u64, Error> {
+        page_manager
+            .read_page(id, |page| {
+                u64::from_le_bytes(
+                    page.buffer()[..BLOB_HEADER_LEN]
+                        .try_into()
+                        .unwrap_or_default(),
+                )
+            })
+            .await
+    }
+
+    /// Frees the pages of the [`Blob`] containing `len` bytes of data.
+    async fn free(page_manager: &PageManager, id: u64, len: u64) -> Result<(), Error> {
+        let first_page_len = (page_manager.page_payload_len() - BLOB_HEADER_LEN) as u64;
+        let page_len = page_manager.page_payload_len() as u64;
+        let num_pages = 1 + len.saturating_sub(first_page_len).div_ceil(page_len);
+        let mut page_addresses = Vec::new();
+        let mut page_address = id;
+        for _ in 0..num_pages {
+            if page_address == 0 {
+                return Err(Error::CorruptPage(id));
+            }
+            page_addresses.push(page_address);
+            page_address = page_manager
+                .read_page(page_address, EvictablePage::next_page_address)
+                .await?;
+        }
+        for page_address in page_addresses.into_iter().rev() {
+            page_manager.delete_page(page_address).await?;
+            page_manager.request_write_back(page_address);
+        }
+        Ok(())
+    }
+}
+
+impl BlobWriter<'_> {
+    /// Appends data to the [`Blob`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if a new page could not be allocated or written; the [`BlobWriter`]
+    /// should then be aborted.
+    #[inline]
+    pub async fn write(&mut self, mut data: &[u8]) -> Result<(), Error> {
+        let page_len = self.page_manager.page_payload_len();
+        while !data.is_empty() {
+            if self.offset == page_len {
+                self.last_page_address = self
+                    .page_manager
+                    .create_linked_page(self.last_page_address)
+                    .await?;
+                self.offset = 0;
+            }
+            let offset = self.offset;
+            let (chunk, rest) = data.split_at((page_len - offset).min(data.len()));
+            self.page_manager
+                .write_page(self.last_page_address, |page| {
+                    page.buffer_mut()[offset..offset + chunk.len()].copy_from_slice(chunk);
+                    page.set_dirty();
+                })
+                .await?;
+            self.page_manager.request_write_back(self.last_page_address);
+            self.offset += chunk.len();
+            self.len += chunk.len() as u64;
+            data = rest;
+        }
+        Ok(())
+    }
+
+    /// Finishes writing data.
+    #[inline]
+    pub async fn finish(self) -> Result<Blob, Error> {
+        let len = self.len;
+        self.page_manager
+            .write_page(self.blob.id, |page| {
+                page.buffer_mut()[..BLOB_HEADER_LEN].copy_from_slice(&len.to_le_bytes());
+                page.set_dirty();
+            })
+            .await?;
+        self.page_manager.request_write_back(self.blob.id);
+        Ok(self.blob)
+    }
+
+    /// Aborts writing data, and returns the pages to the free page list.
+    #[inline]
+    pub async fn abort(self) -> Result<(), Error> {
+        Blob::free(self.page_manager, self.blob.id, self.len).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{FileIO, MonotonicU64};
+    use std::path::Path;
+    use tokio::fs::remove_dir_all;
+
+    #[tokio::test]
+    async fn write_read() {
+        const DIR: &str = "blob_write_read_test";
+        let path = Path::new(DIR);
+        let file_io = FileIO::<MonotonicU64>::with_page_size(path, 512).unwrap();
+        let page_manager = file_io.page_manager();
+        let owner = page_manager.container_directory_head();
+        let data = (0..2048_u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let mut writer = Blob::create(page_manager, owner).await.unwrap();
+        for chunk in data.chunks(300) {
+            assert!(writer.write(chunk).await.is_ok());
+        }
+        let blob = writer.finish().await.unwrap();
+        assert_eq!(Blob::read_sync(page_manager, blob.id()), Ok(data.clone()));
+        assert_eq!(
+            page_manager
+                .read_page(owner, EvictablePage::next_page_address)
+                .await,
+            Ok(blob.id())
+        );
+
+        let mut writer = Blob::create(page_manager, owner).await.unwrap();
+        assert!(writer.write(&data).await.is_ok());
+        assert!(writer.abort().await.is_ok());
+        assert_eq!(
+            page_manager
+                .read_page(owner, EvictablePage::next_page_address)
+                .await,
+            Ok(blob.id())
+        );
+        assert_eq!(Blob::read_sync(page_manager, blob.id()), Ok(data));
+        assert!(page_manager.verify().await.problems.is_empty());
+
+        drop(file_io);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn compact() {
+        const DIR: &str = "blob_compact_test";
+        let path = Path::new(DIR);
+        let file_io = FileIO::<MonotonicU64>::with_page_size(path, 512).unwrap();
+        let page_manager = file_io.page_manager();
+        let owner = page_manager.container_directory_head();
+
+        // Pages freed by aborting the first blob are reused by the second one once compacted.
+        let data = (0..2048_u32).map(|i| (i % 241) as u8).collect::<Vec<_>>();
+        let mut first = Blob::create(page_manager, owner).await.unwrap();
+        assert!(first.write(&data).await.is_ok());
+        let mut writer = Blob::create(page_manager, owner).await.unwrap();
+        assert!(writer.write(&data).await.is_ok());
+        let second = writer.finish().await.unwrap();
+        let file_len = page_manager.defragment().await.unwrap();
+        assert!(first.abort().await.is_ok());
+        assert_eq!(page_manager.defragment().await, Ok(file_len));
+
+        let (id, num_pages_moved) = Blob::compact(page_manager, second.id()).await.unwrap();
+        assert!(num_pages_moved > 0);
+        assert_ne!(id, second.id());
+        assert_eq!(Blob::compact(page_manager, id).await, Ok((id, 0)));
+        assert!(page_manager.defragment().await.unwrap() < file_len);
+        assert_eq!(Blob::read_sync(page_manager, id), Ok(data));
+        assert!(page_manager.verify().await.problems.is_empty());
+
+        drop(file_io);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+}
@@ -0,0 +1,983 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Persistent B+tree built on top of database pages.
+
+use super::container_directory::{take, DirectoryRecord, IndexEntry};
+use super::evictable_page::EvictablePage;
+use super::page_manager::PageManager;
+use crate::Error;
+use std::collections::VecDeque;
+use std::ops::{Bound, RangeBounds};
+
+/// [`BTree`] is a persistent B+tree mapping byte-string keys to versions of key-value pairs in a
+/// [`Container`](crate::Container).
+///
+/// Each entry is an [`IndexEntry`] carrying the identifier of the database object of the
+/// version, therefore the visibility of an entry is determined by the
+/// [`AccessController`](crate::AccessController) in the same way as that of the in-memory version
+/// it was written from: the entry is visible to every reader unless access control data of the
+/// database object is retained.
+///
+/// The address of the root page never changes, and all the pages of the [`BTree`] are linked to
+/// the root page through the page header, so that the [`BTree`] is freed by freeing the page
+/// chain.
+///
+/// The layout of a node in a page buffer is as follows.
+/// - `KIND 8-bit|RESERVED 8-bit|LEN 16-bit|PREFIX LEN 16-bit|RESERVED 16-bit|LINK 64-bit|PREFIX`.
+///   - `KIND = 0` represents a leaf node, and `LINK` is the address of the next leaf node.
+///   - `KIND = 1` represents an internal node, and `LINK` is the address of the leftmost child.
+///   - `PREFIX` is the longest common prefix of the keys in the node, and it is omitted from the
+///     keys of the entries.
+/// - A leaf node contains encoded [`IndexEntry`] instances; values larger than a quarter of a node
+///   are stored in [`Blob`](super::blob::Blob) instances linked to the root page.
+/// - An internal node contains `KEY LEN 16-bit|CHILD 64-bit|KEY` entries.
+///
+/// Nodes are split at the middle of the encoded entries, except that a node receiving a new last
+/// entry only moves the new entry to the new node, so that the nodes are almost full when entries
+/// are inserted in ascending key order as a checkpoint does. The separator of split leaf nodes is
+/// the shortest prefix of the first key in the right node that is greater than the last key in the
+/// left node.
+///
+/// Writers must be serialized by the owner of the [`BTree`].
+#[derive(Debug)]
+pub struct BTree {
+    /// The root page address.
+    root: u64,
+
+    /// The size of the page buffer available for a node.
+    node_len: usize,
+}
+
+/// An in-memory representation of a node.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Node {
+    /// A leaf node.
+    Leaf {
+        /// The next leaf node address.
+        next: u64,
+
+        /// The entries.
+        entries: Vec<IndexEntry>,
+    },
+
+    /// An internal node.
+    Internal {
+        /// The leftmost child node address.
+        leftmost: u64,
+
+        /// The separator keys and child node addresses.
+        entries: Vec<(Box<[u8]>, u64)>,
+    },
+}
+
+/// The length of the node header.
+const NODE_HEADER_LEN: usize = 16;
+
+/// The size of an internal node entry excluding the key.
+const INTERNAL_ENTRY_LEN: usize = 10;
+
+impl BTree {
+    /// Creates a new empty [`BTree`].
+    ///
+    /// The root page of the [`BTree`] is linked to the specified page.
+    #[inline]
+    pub async fn create(page_manager: &PageManager, prev_page_address: u64) -> Result<Self, Error> {
+        let root = page_manager.create_page(prev_page_address).await?;
+        let btree = Self {
+            root,
+            node_len: page_manager.page_payload_len(),
+        };
+        btree
+            .write_node(
+                page_manager,
+                root,
+                &Node::Leaf {
+                    next: 0,
+                    entries: Vec::new(),
+                },
+            )
+            .await?;
+        Ok(btree)
+    }
+
+    /// Opens an existing [`BTree`].
+    #[inline]
+    pub fn open(page_manager: &PageManager, root: u64) -> Self {
+        debug_assert_eq!(root % page_manager.page_size(), 0);
+        Self {
+            root,
+            node_len: page_manager.page_payload_len(),
+        }
+    }
+
+    /// Returns the address of the root page.
+    #[inline]
+    pub fn root(&self) -> u64 {
+        self.root
+    }
+
+    /// Returns the maximum size of an encoded entry in the database.
+    ///
+    /// An entry must not take more than a quarter of a node, so that both nodes split from a full
+    /// node are able to contain the entries.
+    #[inline]
+    pub fn max_entry_len(page_manager: &PageManager) -> usize {
+        (page_manager.page_payload_len() - NODE_HEADER_LEN) / 4
+    }
+
+    /// Inserts a new entry.
+    ///
+    /// The value is stored in a [`Blob`](super::blob::Blob) if the entry is larger than
+    /// [`max_entry_len`](Self::max_entry_len).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UniquenessViolation`] if an entry associated with the key exists, or
+    /// [`Error::WrongParameter`] if the key is too large.
+    #[inline]
+    pub async fn insert(
+        &mut self,
+        page_manager: &PageManager,
+        record: DirectoryRecord,
+    ) -> Result<(), Error> {
+        let mut path = self.find_path(page_manager, &record.key).await?;
+        let (leaf_address, mut leaf) = path.pop().ok_or(Error::CorruptDatabase)?;
+        let Node::Leaf { entries, .. } = &mut leaf else {
+            return Err(Error::CorruptPage(leaf_address));
+        };
+        let Err(pos) = entries.binary_search_by(|e| e.record.key.cmp(&record.key)) else {
+            return Err(Error::UniquenessViolation);
+        };
+        let max_len = Self::max_entry_len(page_manager);
+        let entry = IndexEntry::new(page_manager, self.root, record, max_len).await?;
+        let appended = pos == entries.len();
+        entries.insert(pos, entry);
+        self.insert_and_split(page_manager, path, leaf_address, leaf, appended)
+            .await
+    }
+
+    /// Passes every entry to `visitor` in ascending key order.
+    ///
+    /// It is a synchronous method, therefore it should be run in the background.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a node could not be read or is corrupt.
+    #[inline]
+    pub fn scan_sync<F: FnMut(DirectoryRecord)>(
+        &self,
+        page_manager: &PageManager,
+        mut visitor: F,
+    ) -> Result<(), Error> {
+        let mut address = self.root;
+        loop {
+            let node = page_manager
+                .read_page_sync(address, |page| Node::decode(page.buffer()))?
+                .ok_or(Error::CorruptPage(address))?;
+            match node {
+                Node::Leaf { next, entries } => {
+                    for entry in entries {
+                        visitor(entry.into_record_sync(page_manager)?);
+                    }
+                    if next == 0 {
+                        return Ok(());
+                    }
+                    address = next;
+                }
+                Node::Internal { leftmost, .. } => address = leftmost,
+            }
+        }
+    }
+
+    /// Passes up to `limit` entries in the range to `visitor` in ascending key order.
+    ///
+    /// Up to `readahead` leaf nodes following the leaf node being visited are read into the page
+    /// cache in the background.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a node could not be read or is corrupt.
+    #[inline]
+    pub async fn scan<F: FnMut(DirectoryRecord)>(
+        &self,
+        page_manager: &PageManager,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+        limit: usize,
+        readahead: usize,
+        mut visitor: F,
+    ) -> Result<(), Error> {
+        let first_key = match start {
+            Bound::Included(key) | Bound::Excluded(key) => key,
+            Bound::Unbounded => &[],
+        };
+        let mut path = self.find_path(page_manager, first_key).await?;
+        let (mut address, mut node) = path.pop().ok_or(Error::CorruptDatabase)?;
+        let mut following = VecDeque::new();
+        if readahead != 0 {
+            following = Self::following_leaves(&path, address);
+            for leaf in following.iter().take(readahead) {
+                page_manager.prefetch_page(*leaf).await;
+            }
+        }
+        let mut num_visited = 0;
+        loop {
+            let Node::Leaf { next, entries } = node else {
+                return Err(Error::CorruptPage(address));
+            };
+            for entry in entries {
+                let key: &[u8] = &entry.record.key;
+                if num_visited == limit
+                    || !RangeBounds::<[u8]>::contains(&(Bound::Unbounded, end), key)
+                {
+                    return Ok(());
+                }
+                if RangeBounds::<[u8]>::contains(&(start, Bound::Unbounded), key) {
+                    visitor(entry.into_record(page_manager).await?);
+                    num_visited += 1;
+                }
+            }
+            if next == 0 {
+                return Ok(());
+            }
+            address = next;
+            if readahead == 0 {
+                node = Self::read_node(page_manager, address).await?;
+            } else if following.pop_front() == Some(address) {
+                if let Some(leaf) = following.get(readahead - 1) {
+                    page_manager.prefetch_page(*leaf).await;
+                }
+                node = Self::read_node(page_manager, address).await?;
+            } else {
+                // The leaf node belongs to another parent node, which lists the leaf nodes to
+                // read ahead next.
+                node = Self::read_node(page_manager, address).await?;
+                if node.len() != 0 {
+                    path = self.find_path(page_manager, node.key(0)).await?;
+                    let _: Option<(u64, Node)> = path.pop();
+                    following = Self::following_leaves(&path, address);
+                    for leaf in following.iter().take(readahead) {
+                        page_manager.prefetch_page(*leaf).await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the addresses of the leaf nodes following the leaf node in its parent node at the
+    /// end of the path.
+    fn following_leaves(path: &[(u64, Node)], leaf_address: u64) -> VecDeque<u64> {
+        let Some((_, Node::Internal { leftmost, entries })) = path.last() else {
+            return VecDeque::new();
+        };
+        let mut children = std::iter::once(*leftmost).chain(entries.iter().map(|(_, c)| *c));
+        if children.any(|c| c == leaf_address) {
+            children.collect()
+        } else {
+            VecDeque::new()
+        }
+    }
+
+    /// Returns `true` if an entry associated with the key exists.
+    ///
+    /// It is a synchronous method, therefore it should be run in the background.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a node could not be read or is corrupt.
+    #[inline]
+    pub fn contains_sync(&self, page_manager: &PageManager, key: &[u8]) -> Result<bool, Error> {
+        let mut address = self.root;
+        loop {
+            let node = page_manager
+                .read_page_sync(address, |page| Node::decode(page.buffer()))?
+                .ok_or(Error::CorruptPage(address))?;
+            match &node {
+                Node::Leaf { entries, .. } => {
+                    return Ok(entries
+                        .binary_search_by(|e| (*e.record.key).cmp(key))
+                        .is_ok());
+                }
+                Node::Internal { .. } => address = node.child(key),
+            }
+        }
+    }
+
+    /// Moves the nodes except for the root node and the [`Blob`](super::blob::Blob) instances to
+    /// free pages nearer the front of the database file, and returns the number of pages moved.
+    ///
+    /// Nodes are visited level by level, and the node referring to a moved page is written right
+    /// after the page is moved, so that no node refers to a freed page that may be reused by the
+    /// next page to move.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page could not be moved or is corrupt.
+    #[inline]
+    pub async fn compact(&self, page_manager: &PageManager) -> Result<usize, Error> {
+        let mut num_pages_moved = 0;
+        let mut level = vec![self.root];
+        let mut prev_leaf = None;
+        while !level.is_empty() {
+            let mut children = Vec::new();
+            for address in level {
+                let mut node = Self::read_node(page_manager, address).await?;
+                if matches!(node, Node::Leaf { .. }) {
+                    for i in 0..node.len() {
+                        let Node::Leaf { entries, .. } = &mut node else {
+                            break;
+                        };
+                        let blob = entries[i].blob;
+                        num_pages_moved += entries[i].compact_blob(page_manager).await?;
+                        if entries[i].blob != blob {
+                            self.write_node(page_manager, address, &node).await?;
+                        }
+                    }
+                    continue;
+                }
+                for i in 0..=node.len() {
+                    let child = *node.child_at_mut(i);
+                    let new_child = page_manager.relocate_page(child).await?;
+                    if new_child != child {
+                        *node.child_at_mut(i) = new_child;
+                        self.write_node(page_manager, address, &node).await?;
+                        num_pages_moved += 1;
+                    }
+                    if let Node::Leaf { .. } = Self::read_node(page_manager, new_child).await? {
+                        // The previous leaf node has to be linked to the moved leaf node.
+                        if let Some(prev_address) = prev_leaf.replace(new_child) {
+                            let mut prev = Self::read_node(page_manager, prev_address).await?;
+                            if let Node::Leaf { next, .. } = &mut prev {
+                                if *next != new_child {
+                                    *next = new_child;
+                                    self.write_node(page_manager, prev_address, &prev).await?;
+                                }
+                            }
+                        }
+                    }
+                    children.push(new_child);
+                }
+            }
+            level = children;
+        }
+        Ok(num_pages_moved)
+    }
+
+    /// Frees all the pages of the [`BTree`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page could not be freed.
+    #[inline]
+    pub async fn free(self, page_manager: &PageManager) -> Result<(), Error> {
+        loop {
+            let next = page_manager
+                .read_page(self.root, EvictablePage::next_page_address)
+                .await?;
+            if next == 0 {
+                break;
+            }
+            Self::free_page(page_manager, next).await?;
+        }
+        Self::free_page(page_manager, self.root).await
+    }
+
+    /// Finds the path from the root to the leaf node that may contain the key.
+    ///
+    /// Each element of the path consists of the address of a node and the node itself.
+    async fn find_path(
+        &self,
+        page_manager: &PageManager,
+        key: &[u8],
+    ) -> Result<Vec<(u64, Node)>, Error> {
+        let mut path = Vec::new();
+        let mut address = self.root;
+        loop {
+            let node = Self::read_node(page_manager, address).await?;
+            let next = match &node {
+                Node::Leaf { .. } => None,
+                Node::Internal { .. } => Some(node.child(key)),
+            };
+            path.push((address, node));
+            let Some(next_address) = next else {
+                return Ok(path);
+            };
+            address = next_address;
+        }
+    }
+
+    /// Writes the node, and splits it if the node overflows.
+    ///
+    /// `appended` denotes whether the last entry of the node is the new one.
+    async fn insert_and_split(
+        &mut self,
+        page_manager: &PageManager,
+        mut path: Vec<(u64, Node)>,
+        mut address: u64,
+        mut node: Node,
+        mut appended: bool,
+    ) -> Result<(), Error> {
+        while node.encoded_len() > self.node_len {
+            let (separator, mut right) = node.split(appended);
+            if path.is_empty() {
+                // The root node stays at the same address.
+                let left_address = self.allocate_page(page_manager).await?;
+                let right_address = self.allocate_page(page_manager).await?;
+                if let Node::Leaf { next, .. } = &mut node {
+                    *next = right_address;
+                }
+                self.write_node(page_manager, left_address, &node).await?;
+                self.write_node(page_manager, right_address, &right).await?;
+                node = Node::Internal {
+                    leftmost: left_address,
+                    entries: vec![(separator, right_address)],
+                };
+                break;
+            }
+            let right_address = self.allocate_page(page_manager).await?;
+            if let (
+                Node::Leaf { next, .. },
+                Node::Leaf {
+                    next: right_next, ..
+                },
+            ) = (&mut node, &mut right)
+            {
+                *right_next = *next;
+                *next = right_address;
+            }
+            self.write_node(page_manager, right_address, &right).await?;
+            self.write_node(page_manager, address, &node).await?;
+
+            let (parent_address, mut parent) = path.pop().ok_or(Error::CorruptDatabase)?;
+            let Node::Internal { entries, .. } = &mut parent else {
+                return Err(Error::CorruptPage(parent_address));
+            };
+            let pos = entries.partition_point(|(k, _)| *k < separator);
+            appended = pos == entries.len();
+            entries.insert(pos, (separator, right_address));
+            address = parent_address;
+            node = parent;
+        }
+        self.write_node(page_manager, address, &node).await
+    }
+
+    /// Allocates a new page, and links it to the root page.
+    async fn allocate_page(&self, page_manager: &PageManager) -> Result<u64, Error> {
+        page_manager.create_linked_page(self.root).await
+    }
+
+    /// Unlinks the page from the root page, and returns it to the free page list.
+    async fn free_page(page_manager: &PageManager, address: u64) -> Result<(), Error> {
+        page_manager.delete_page(address).await?;
+        page_manager.request_write_back(address);
+        Ok(())
+    }
+
+    /// Reads a node from the page.
+    async fn read_node(page_manager: &PageManager, address: u64) -> Result<Node, Error> {
+        page_manager
+            .read_page(address, |page| Node::decode(page.buffer()))
+            .await?
+            .ok_or(Error::CorruptPage(address))
+    }
+
+    /// Writes the node to the page.
+    async fn write_node(
+        &self,
+        page_manager: &PageManager,
+        address: u64,
+        node: &Node,
+    ) -> Result<(), Error> {
+        let data = node.encode();
+        page_manager
+            .write_page(address, |page| {
+                page.buffer_mut()[..data.len()].copy_from_slice(&data);
+                page.set_dirty();
+            })
+            .await?;
+        page_manager.request_write_back(address);
+        Ok(())
+    }
+}
+
+impl Node {
+    /// Decodes a node from the buffer.
+    fn decode(buffer: &[u8]) -> Option<Self> {
+        let kind = *buffer.first()?;
+        let len = u16::from_le_bytes(buffer.get(2..4)?.try_into().ok()?);
+        let prefix_len = u16::from_le_bytes(buffer.get(4..6)?.try_into().ok()?);
+        let link = u64::from_le_bytes(buffer.get(8..NODE_HEADER_LEN)?.try_into().ok()?);
+        let mut data = buffer.get(NODE_HEADER_LEN..)?;
+        let prefix = take(&mut data, usize::from(prefix_len))?;
+        let read_u64 = |data: &mut &[u8]| Some(u64::from_le_bytes(take(data, 8)?.try_into().ok()?));
+        let read_key_len = |data: &mut &[u8]| {
+            Some(usize::from(u16::from_le_bytes(
+                take(data, 2)?.try_into().ok()?,
+            )))
+        };
+        match kind {
+            0 => {
+                let entries = (0..len)
+                    .map(|_| IndexEntry::decode(prefix, &mut data))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(Node::Leaf {
+                    next: link,
+                    entries,
+                })
+            }
+            1 => {
+                let mut entries = Vec::with_capacity(usize::from(len));
+                for _ in 0..len {
+                    let key_len = read_key_len(&mut data)?;
+                    let child = read_u64(&mut data)?;
+                    let key = [prefix, take(&mut data, key_len)?].concat();
+                    entries.push((key.into(), child));
+                }
+                Some(Node::Internal {
+                    leftmost: link,
+                    entries,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Encodes the node.
+    fn encode(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.encoded_len());
+        let (kind, link) = match self {
+            Node::Leaf { next, .. } => (0_u8, *next),
+            Node::Internal { leftmost, .. } => (1_u8, *leftmost),
+        };
+        let prefix = self.prefix();
+        data.extend_from_slice(&[kind, 0]);
+        #[allow(clippy::cast_possible_truncation)]
+        data.extend_from_slice(&(self.len() as u16).to_le_bytes());
+        #[allow(clippy::cast_possible_truncation)]
+        data.extend_from_slice(&(prefix.len() as u16).to_le_bytes());
+        data.extend_from_slice(&[0; 2]);
+        data.extend_from_slice(&link.to_le_bytes());
+        data.extend_from_slice(prefix);
+        match self {
+            Node::Leaf { entries, .. } => {
+                for e in entries {
+                    e.encode(prefix.len(), &mut data);
+                }
+            }
+            Node::Internal { entries, .. } => {
+                for (key, child) in entries {
+                    #[allow(clippy::cast_possible_truncation)]
+                    data.extend_from_slice(&((key.len() - prefix.len()) as u16).to_le_bytes());
+                    data.extend_from_slice(&child.to_le_bytes());
+                    data.extend_from_slice(&key[prefix.len()..]);
+                }
+            }
+        }
+        data
+    }
+
+    /// Returns the number of entries in the node.
+    fn len(&self) -> usize {
+        match self {
+            Node::Leaf { entries, .. } => entries.len(),
+            Node::Internal { entries, .. } => entries.len(),
+        }
+    }
+
+    /// Returns the key of the entry at the position.
+    fn key(&self, pos: usize) -> &[u8] {
+        match self {
+            Node::Leaf { entries, .. } => &entries[pos].record.key,
+            Node::Internal { entries, .. } => &entries[pos].0,
+        }
+    }
+
+    /// Returns the longest common prefix of the keys in the node.
+    ///
+    /// Keys are sorted, therefore the common prefix of the first and last keys is shared by all
+    /// the keys.
+    fn prefix(&self) -> &[u8] {
+        match self.len() {
+            0 => &[],
+            len => {
+                let first = self.key(0);
+                &first[..common_prefix_len(first, self.key(len - 1))]
+            }
+        }
+    }
+
+    /// Returns the encoded size of each entry without the common prefix of the keys.
+    fn entry_lens(&self) -> Vec<usize> {
+        let prefix_len = self.prefix().len();
+        match self {
+            Node::Leaf { entries, .. } => entries
+                .iter()
+                .map(|e| e.encoded_len() - prefix_len)
+                .collect(),
+            Node::Internal { entries, .. } => entries
+                .iter()
+                .map(|(k, _)| INTERNAL_ENTRY_LEN + k.len() - prefix_len)
+                .collect(),
+        }
+    }
+
+    /// Returns the number of bytes required to encode the node.
+    fn encoded_len(&self) -> usize {
+        NODE_HEADER_LEN + self.prefix().len() + self.entry_lens().iter().sum::<usize>()
+    }
+
+    /// Returns the address of the child node that may contain the key.
+    fn child(&self, key: &[u8]) -> u64 {
+        let Node::Internal { leftmost, entries } = self else {
+            unreachable!("logic error");
+        };
+        match entries.partition_point(|(k, _)| **k <= *key) {
+            0 => *leftmost,
+            pos => entries[pos - 1].1,
+        }
+    }
+
+    /// Returns a mutable reference to the address of the child node at the position.
+    ///
+    /// The leftmost child is at position `0`.
+    fn child_at_mut(&mut self, pos: usize) -> &mut u64 {
+        let Node::Internal { leftmost, entries } = self else {
+            unreachable!("logic error");
+        };
+        match pos {
+            0 => leftmost,
+            pos => &mut entries[pos - 1].1,
+        }
+    }
+
+    /// Splits the node into two, and returns the separator key and the right half.
+    ///
+    /// Only the last entry is moved to the right half if `appended` is `true`.
+    fn split(&mut self, appended: bool) -> (Box<[u8]>, Node) {
+        let at = if appended {
+            self.len() - 1
+        } else {
+            let entry_lens = self.entry_lens();
+            let half = entry_lens.iter().sum::<usize>() / 2;
+            let mut left_len = 0;
+            entry_lens
+                .iter()
+                .position(|len| {
+                    left_len += len;
+                    left_len >= half
+                })
+                .unwrap_or(0)
+                .clamp(1, self.len() - 1)
+        };
+        match self {
+            Node::Leaf { entries, .. } => {
+                let right_entries = entries.split_off(at);
+                let first = &right_entries[0].record.key;
+                let separator_len = common_prefix_len(&entries[at - 1].record.key, first) + 1;
+                (
+                    first[..separator_len].into(),
+                    Node::Leaf {
+                        next: 0,
+                        entries: right_entries,
+                    },
+                )
+            }
+            Node::Internal { entries, .. } => {
+                let mut right_entries = entries.split_off(at);
+                let (separator, leftmost) = right_entries.remove(0);
+                (
+                    separator,
+                    Node::Internal {
+                        leftmost,
+                        entries: right_entries,
+                    },
+                )
+            }
+        }
+    }
+}
+
+/// Returns the length of the common prefix of the two keys.
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::evictable_page::{PAGE_FOOTER_LEN, PAGE_HEADER_LEN};
+    use super::*;
+    use crate::{FileIO, MonotonicU64, PersistenceLayer};
+    use std::path::Path;
+    use std::time::{Duration, Instant};
+    use tokio::fs::remove_dir_all;
+
+    /// Returns an entry of which the key is derived from the number.
+    fn entry(i: u64) -> DirectoryRecord {
+        DirectoryRecord {
+            record_id: i,
+            object_id: i + 1,
+            key: format!("key-{:08}", i * 7).into_bytes().into(),
+            value: vec![0xAB; usize::try_from(i % 16).unwrap()].into(),
+        }
+    }
+
+    /// Returns an entry of which the value is stored in a blob if the number is a multiple of 97.
+    fn large_entry(i: u64) -> DirectoryRecord {
+        let mut entry = entry(i);
+        if i.is_multiple_of(97) {
+            entry.value = vec![0xEF; usize::try_from(i % 3000).unwrap()].into();
+        }
+        entry
+    }
+
+    #[test]
+    fn node_encode_decode() {
+        const NODE_LEN: usize = 512 - PAGE_HEADER_LEN - PAGE_FOOTER_LEN;
+        let mut buffer = [0_u8; NODE_LEN];
+        let mut entries: Vec<IndexEntry> = (0..8)
+            .map(|i| IndexEntry {
+                record: entry(i),
+                blob: None,
+            })
+            .collect();
+        entries[3].record.value = Box::default();
+        entries[3].blob = Some(512 * 3);
+        let leaf = Node::Leaf {
+            next: 512 * 7,
+            entries,
+        };
+        let data = leaf.encode();
+        assert_eq!(data.len(), leaf.encoded_len());
+        buffer[..data.len()].copy_from_slice(&data);
+        assert_eq!(Node::decode(&buffer), Some(leaf));
+        let internal = Node::Internal {
+            leftmost: 512 * 11,
+            entries: (0..16).map(|i| (entry(i).key, 512 * i)).collect(),
+        };
+        let data = internal.encode();
+        assert_eq!(data.len(), internal.encoded_len());
+        buffer[..data.len()].copy_from_slice(&data);
+        assert_eq!(Node::decode(&buffer), Some(internal));
+        assert_eq!(Node::decode(&[2; NODE_LEN]), None);
+        assert_eq!(Node::decode(&data[..data.len() - 1]), None);
+    }
+
+    #[test]
+    fn key_compression() {
+        let entries: Vec<IndexEntry> = (0..8)
+            .map(|i| IndexEntry {
+                record: entry(i),
+                blob: None,
+            })
+            .collect();
+        let uncompressed_len =
+            NODE_HEADER_LEN + entries.iter().map(IndexEntry::encoded_len).sum::<usize>();
+        let mut leaf = Node::Leaf { next: 0, entries };
+        assert_eq!(leaf.prefix(), b"key-000000");
+        assert_eq!(leaf.encoded_len(), uncompressed_len - 7 * 10);
+        assert_eq!(leaf.encode().len(), leaf.encoded_len());
+
+        let (separator, right) = leaf.split(true);
+        assert_eq!(&*separator, b"key-00000049");
+        assert_eq!(right.key(0), b"key-00000049");
+        assert_eq!(leaf.key(leaf.len() - 1), b"key-00000042");
+
+        let keys: [&[u8]; 4] = [b"apple", b"apricot", b"banana", b"bandana"];
+        let mut leaf = Node::Leaf {
+            next: 0,
+            entries: keys
+                .iter()
+                .enumerate()
+                .map(|(i, k)| IndexEntry {
+                    record: DirectoryRecord {
+                        record_id: i as u64,
+                        object_id: i as u64,
+                        key: (*k).into(),
+                        value: Box::default(),
+                    },
+                    blob: None,
+                })
+                .collect(),
+        };
+        assert!(leaf.prefix().is_empty());
+        let (separator, _) = leaf.split(true);
+        assert_eq!(&*separator, b"band");
+    }
+
+    #[tokio::test]
+    async fn insert() {
+        for page_size in [512, 4096] {
+            insert_with_page_size(page_size).await;
+        }
+    }
+
+    async fn insert_with_page_size(page_size: u64) {
+        const NUM_KEYS: u64 = 2048;
+        let dir = format!("btree_insert_test_{page_size}");
+        let path = Path::new(&dir);
+        let file_io = FileIO::<MonotonicU64>::with_page_size(path, page_size).unwrap();
+        let page_manager = file_io.page_manager();
+        let mut btree = BTree::create(page_manager, page_manager.page_size())
+            .await
+            .unwrap();
+        for i in 0..NUM_KEYS {
+            let i = (i * 7) % NUM_KEYS;
+            assert!(btree.insert(page_manager, large_entry(i)).await.is_ok());
+        }
+        assert_eq!(
+            btree.insert(page_manager, entry(3)).await,
+            Err(Error::UniquenessViolation)
+        );
+        let mut oversized = entry(NUM_KEYS);
+        oversized.key = vec![0; BTree::max_entry_len(page_manager)].into();
+        assert_eq!(
+            btree.insert(page_manager, oversized).await,
+            Err(Error::WrongParameter)
+        );
+        let mut scanned = Vec::new();
+        assert!(btree.scan_sync(page_manager, |e| scanned.push(e)).is_ok());
+        assert_eq!(scanned, (0..NUM_KEYS).map(large_entry).collect::<Vec<_>>());
+
+        let num_free_pages = page_manager.verify().await.free_pages;
+        assert!(btree.free(page_manager).await.is_ok());
+        let report = page_manager.verify().await;
+        assert!(report.problems.is_empty(), "{report:?}");
+        assert!(report.free_pages > num_free_pages);
+        drop(file_io);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn ascending_insert() {
+        const DIR: &str = "btree_ascending_insert_test";
+        const NUM_KEYS: u64 = 4096;
+        let path = Path::new(DIR);
+        let file_io = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        let page_manager = file_io.page_manager();
+        let mut btree = BTree::create(page_manager, page_manager.page_size())
+            .await
+            .unwrap();
+        let mut data_len = 0;
+        for i in 0..NUM_KEYS {
+            let entry = entry(i);
+            data_len += IndexEntry::inline_len(&entry.key, &entry.value);
+            assert!(btree.insert(page_manager, entry).await.is_ok());
+        }
+
+        // Leaf nodes are almost full.
+        let mut num_pages = 1;
+        let mut page_address = btree.root();
+        while page_address != 0 {
+            page_address = page_manager
+                .read_page(page_address, EvictablePage::next_page_address)
+                .await
+                .unwrap();
+            num_pages += 1;
+        }
+        let node_len = page_manager.page_payload_len() - NODE_HEADER_LEN;
+        assert!(num_pages * node_len < data_len * 5 / 4, "{num_pages}");
+        drop(file_io);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn reopen() {
+        const DIR: &str = "btree_reopen_test";
+        const NUM_KEYS: u64 = 256;
+        let path = Path::new(DIR);
+        let file_io = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        let page_manager = file_io.page_manager();
+        let mut btree = BTree::create(page_manager, page_manager.container_directory_head())
+            .await
+            .unwrap();
+        for i in 0..NUM_KEYS {
+            assert!(btree.insert(page_manager, large_entry(i)).await.is_ok());
+        }
+        let root = btree.root();
+        drop(file_io);
+
+        let file_io_recovered = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        let page_manager = file_io_recovered.page_manager();
+        let btree = BTree::open(page_manager, root);
+        let mut scanned = Vec::new();
+        assert!(btree.scan_sync(page_manager, |e| scanned.push(e)).is_ok());
+        assert_eq!(scanned, (0..NUM_KEYS).map(large_entry).collect::<Vec<_>>());
+        drop(file_io_recovered);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn scan_readahead() {
+        const DIR: &str = "btree_scan_readahead_test";
+        const NUM_KEYS: u64 = 4096;
+        const READAHEAD: usize = 4;
+        let path = Path::new(DIR);
+        let file_io = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        let page_manager = file_io.page_manager();
+        let mut btree = BTree::create(page_manager, page_manager.container_directory_head())
+            .await
+            .unwrap();
+        for i in 0..NUM_KEYS {
+            assert!(btree.insert(page_manager, entry(i)).await.is_ok());
+        }
+        let root = btree.root();
+        drop(file_io);
+
+        let file_io_recovered = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        let page_manager = file_io_recovered.page_manager();
+        let telemetry = file_io_recovered.telemetry();
+        let btree = BTree::open(page_manager, root);
+        let pages_read = telemetry.statistics().pages_read;
+        let mut visited = Vec::new();
+        assert!(btree
+            .scan(
+                page_manager,
+                Bound::Unbounded,
+                Bound::Unbounded,
+                1,
+                READAHEAD,
+                |e| visited.push(e)
+            )
+            .await
+            .is_ok());
+        assert_eq!(visited, vec![entry(0)]);
+
+        // The leaf nodes following the first one are read in the background.
+        let path_to_first = btree.find_path(page_manager, &[]).await.unwrap();
+        let expected = pages_read + (path_to_first.len() + READAHEAD) as u64;
+        let deadline = Instant::now() + Duration::from_mins(1);
+        while telemetry.statistics().pages_read < expected {
+            assert!(Instant::now() < deadline);
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        let page_cache_misses = telemetry.statistics().page_cache_misses;
+        let mut address = path_to_first.last().unwrap().0;
+        for _ in 0..=READAHEAD {
+            let Node::Leaf { next, .. } = BTree::read_node(page_manager, address).await.unwrap()
+            else {
+                unreachable!("logic error");
+            };
+            address = next;
+        }
+        assert_eq!(telemetry.statistics().page_cache_misses, page_cache_misses);
+
+        let mut visited = Vec::new();
+        let first_key = entry(0).key;
+        assert!(btree
+            .scan(
+                page_manager,
+                Bound::Excluded(&first_key),
+                Bound::Unbounded,
+                usize::MAX,
+                READAHEAD,
+                |e| visited.push(e)
+            )
+            .await
+            .is_ok());
+        assert_eq!(visited, (1..NUM_KEYS).map(entry).collect::<Vec<_>>());
+        drop(file_io_recovered);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+}
@@ -0,0 +1,375 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Encryption at rest.
+
+use crate::Error;
+use std::fmt::Debug;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::Arc;
+
+/// [`Cipher`] encrypts data before it is written to database files, and decrypts data after it is
+/// read from database files.
+///
+/// A [`Cipher`] must be a length-preserving authenticated cipher with a detached tag, e.g.,
+/// `AES-GCM` or `ChaCha20-Poly1305` with the `64-bit` nonce zero-extended, since encrypted data is
+/// stored in place of the plaintext, and the tag is stored next to it. `aad` is authenticated but
+/// not encrypted; it binds the encrypted data to its location in the file, so that encrypted data
+/// copied to another location fails to be decrypted.
+///
+/// The pair of `nonce` and the key is never reused: every page write and every batch of log
+/// records is encrypted using a new `nonce`; the upper `24 bits` of a `nonce` are the nonce epoch
+/// that is persisted in the database header and advanced whenever the database file is opened
+/// for writing, and the lower `40 bits` are a counter.
+pub trait Cipher: 'static + Debug + Send + Sync {
+    /// Returns the identifier of the key used to encrypt data.
+    fn key_id(&self) -> u32;
+
+    /// Encrypts the data in place, and returns the authentication tag.
+    fn encrypt(&self, nonce: u64, aad: &[u8], data: &mut [u8]) -> [u8; TAG_LEN];
+
+    /// Verifies the authentication tag, and decrypts the data in place using the specified key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the key is not available, or [`Error::CorruptDatabase`] if the
+    /// authentication tag does not match the data.
+    fn decrypt(
+        &self,
+        key_id: u32,
+        nonce: u64,
+        aad: &[u8],
+        data: &mut [u8],
+        tag: &[u8; TAG_LEN],
+    ) -> Result<(), Error>;
+}
+
+/// The length of an authentication tag.
+pub const TAG_LEN: usize = 16;
+
+/// The maximum nonce epoch.
+pub const MAX_NONCE_EPOCH: u64 = (1 << 24) - 1;
+
+/// The number of bits of the nonce counter.
+const NONCE_COUNTER_BITS: u32 = 40;
+
+/// [`Encryption`] associates a [`Cipher`] with a file.
+#[derive(Debug)]
+pub struct Encryption {
+    /// The cipher.
+    cipher: Arc<dyn Cipher>,
+
+    /// The nonces shared by the database and log files.
+    nonces: Arc<NonceSequence>,
+}
+
+/// [`NonceSequence`] issues unique nonces of a nonce epoch.
+#[derive(Debug, Default)]
+pub struct NonceSequence {
+    /// The next nonce to issue.
+    next: AtomicU64,
+
+    /// The end of the nonces of the current nonce epoch.
+    ///
+    /// `0` indicates that no nonce epoch has been started, e.g., the database is read-only.
+    end: AtomicU64,
+}
+
+impl Encryption {
+    /// Creates a new [`Encryption`].
+    #[inline]
+    pub fn new(cipher: Arc<dyn Cipher>, nonces: Arc<NonceSequence>) -> Self {
+        Self { cipher, nonces }
+    }
+
+    /// Returns a reference to the [`Cipher`].
+    #[inline]
+    pub fn cipher(&self) -> &dyn Cipher {
+        self.cipher.as_ref()
+    }
+
+    /// Returns a reference to the [`NonceSequence`].
+    #[inline]
+    pub fn nonces(&self) -> &NonceSequence {
+        &self.nonces
+    }
+
+    /// Returns a new unique nonce.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no nonce epoch has been started, or all the nonces of the current nonce
+    /// epoch have been used up; the database has to be reopened to start a new nonce epoch.
+    #[inline]
+    pub fn new_nonce(&self) -> Result<u64, Error> {
+        let end = self.nonces.end.load(Relaxed);
+        self.nonces
+            .next
+            .fetch_update(Relaxed, Relaxed, |next| (next < end).then_some(next + 1))
+            .map_err(|_| Error::Generic("nonces exhausted"))
+    }
+}
+
+impl NonceSequence {
+    /// Starts issuing nonces of the nonce epoch.
+    ///
+    /// The nonce epoch must have been durably recorded in the database header, and must never be
+    /// started again.
+    #[inline]
+    pub fn start_epoch(&self, nonce_epoch: u64) {
+        debug_assert!((1..=MAX_NONCE_EPOCH).contains(&nonce_epoch));
+        self.next.store(nonce_epoch << NONCE_COUNTER_BITS, Relaxed);
+        self.end
+            .store((nonce_epoch + 1) << NONCE_COUNTER_BITS, Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::evictable_page::{crc32c, PAGE_FOOTER_LEN, PAGE_HEADER_LEN};
+    use super::super::random_access_file::FRAME_OVERHEAD;
+    use super::*;
+    use crate::{Database, FileIO, Metadata, MonotonicU64};
+    use std::num::NonZeroU32;
+    use std::path::Path;
+    use tokio::fs::{read, remove_dir_all, write};
+
+    /// A toy authenticated cipher that must never be used in production.
+    #[derive(Debug)]
+    struct XorCipher(u64);
+
+    impl XorCipher {
+        fn apply(&self, nonce: u64, data: &mut [u8]) {
+            for (position, byte) in (0_u64..).zip(data.iter_mut()) {
+                let key_stream = (self.0 ^ nonce ^ position).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+                *byte ^= key_stream.to_le_bytes()[7];
+            }
+        }
+
+        fn tag(&self, nonce: u64, aad: &[u8], data: &[u8]) -> [u8; TAG_LEN] {
+            let mut state = [self.0 ^ nonce, !nonce ^ aad.len() as u64];
+            for (i, byte) in aad.iter().chain(data.iter()).enumerate() {
+                let s = &mut state[i % 2];
+                *s = (*s ^ u64::from(*byte))
+                    .wrapping_mul(0x9E37_79B9_7F4A_7C15)
+                    .rotate_left(17);
+            }
+            let mut tag = [0_u8; TAG_LEN];
+            tag[..8].copy_from_slice(&state[0].to_le_bytes());
+            tag[8..].copy_from_slice(&state[1].to_le_bytes());
+            tag
+        }
+    }
+
+    impl Cipher for XorCipher {
+        fn key_id(&self) -> u32 {
+            1
+        }
+        fn encrypt(&self, nonce: u64, aad: &[u8], data: &mut [u8]) -> [u8; TAG_LEN] {
+            self.apply(nonce, data);
+            self.tag(nonce, aad, data)
+        }
+        fn decrypt(
+            &self,
+            key_id: u32,
+            nonce: u64,
+            aad: &[u8],
+            data: &mut [u8],
+            tag: &[u8; TAG_LEN],
+        ) -> Result<(), Error> {
+            if key_id != self.key_id() {
+                return Err(Error::NotFound);
+            }
+            if self.tag(nonce, aad, data) != *tag {
+                return Err(Error::CorruptDatabase);
+            }
+            self.apply(nonce, data);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn encrypt_decrypt() {
+        const DIR: &str = "cipher_encrypt_decrypt_test";
+        const SECRET: &[u8] = b"top secret page payload";
+        const LOG_RECORD: &[u8] = b"top secret log record";
+        let path = Path::new(DIR);
+        let cipher = Arc::new(XorCipher(7));
+
+        let file_io = FileIO::<MonotonicU64>::with_cipher(path, cipher.clone()).unwrap();
+        let page_size = file_io.page_size();
+        let page_address = file_io.page_manager().create_page(page_size).await.unwrap();
+        assert!(file_io
+            .page_manager()
+            .write_page(page_address, |e| e.buffer_mut()[..SECRET.len()]
+                .copy_from_slice(SECRET))
+            .await
+            .is_ok());
+        file_io.page_manager().write_back_sync(page_address);
+
+        let log = &file_io.file_io_data.log;
+        let frame_len = log.write_encrypted_batch(&[(LOG_RECORD, 0)]).unwrap();
+        assert_eq!(frame_len, LOG_RECORD.len() as u64 + FRAME_OVERHEAD);
+        assert_eq!(log.frame_len(0), Ok(frame_len));
+        assert_eq!(log.frame_len(frame_len).ok(), None);
+        let mut buffer = vec![0_u8; usize::try_from(frame_len).unwrap()];
+        log.read(&mut buffer, 0).unwrap();
+        assert!(!buffer.windows(LOG_RECORD.len()).any(|w| w == LOG_RECORD));
+        assert_eq!(log.read_decrypted_frame(0, frame_len).unwrap(), LOG_RECORD);
+
+        // Tampered frames fail to be authenticated.
+        log.write(&[buffer[20] ^ 1], 20).unwrap();
+        assert_eq!(
+            log.read_decrypted_frame(0, frame_len),
+            Err(Error::CorruptDatabase)
+        );
+        drop(file_io);
+
+        let db = read(path.join("db.dat")).await.unwrap();
+        assert!(!db.windows(SECRET.len()).any(|w| w == SECRET));
+        assert_eq!(
+            FileIO::<MonotonicU64>::with_path(path).err(),
+            Some(Error::WrongParameter)
+        );
+
+        let file_io = FileIO::<MonotonicU64>::with_cipher(path, cipher.clone()).unwrap();
+        let result = file_io
+            .page_manager()
+            .read_page(page_address, |e| e.buffer()[..SECRET.len()].to_vec())
+            .await
+            .unwrap();
+        assert_eq!(result, SECRET);
+        drop(file_io);
+
+        // Tampered pages fail to be authenticated even if the checksum matches.
+        let mut db = read(path.join("db.dat")).await.unwrap();
+        let page_start = usize::try_from(page_address).unwrap();
+        let page_end = page_start + usize::try_from(page_size).unwrap();
+        let footer_offset = page_end - PAGE_FOOTER_LEN;
+        db[page_start + PAGE_HEADER_LEN] ^= 1;
+        let checksum = crc32c(&db[page_start..footer_offset]);
+        db[footer_offset..page_end].copy_from_slice(&checksum.to_le_bytes());
+        write(path.join("db.dat"), &db).await.unwrap();
+        let file_io = FileIO::<MonotonicU64>::with_cipher(path, cipher).unwrap();
+        assert!(file_io
+            .page_manager()
+            .read_page(page_address, |e| e.buffer()[..SECRET.len()].to_vec())
+            .await
+            .is_err());
+        drop(file_io);
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn recover() {
+        const DIR: &str = "cipher_recover_test";
+        let path = Path::new(DIR);
+        let cipher = Arc::new(XorCipher(13));
+
+        let database = Database::with_cipher(path, cipher.clone()).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.create(&[0, 1, 2, 3], None).await.unwrap();
+        let container = database
+            .create_container("kv".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert!(container
+            .insert(b"key", b"top secret value", &mut journal, None)
+            .await
+            .is_ok());
+        let _: NonZeroU32 = journal.submit();
+        assert!(transaction.commit().await.is_ok());
+        drop(database);
+
+        // A torn frame at the end of the log file is discarded.
+        let mut log = read(path.join("l.log")).await.unwrap();
+        assert!(!log.windows(16).any(|w| w == b"top secret value"));
+        let log_len = log.len();
+        log.extend_from_slice(&[1_u8; 24]);
+        write(path.join("l.log"), &log).await.unwrap();
+
+        let database = Database::with_cipher(path, cipher.clone()).await.unwrap();
+        assert_eq!(read(path.join("l.log")).await.unwrap().len(), log_len);
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.delete(&[2, 3], None).await.unwrap();
+        let _: NonZeroU32 = journal.submit();
+        assert!(transaction.commit().await.is_ok());
+        drop(database);
+
+        let database = Database::with_cipher(path, cipher.clone()).await.unwrap();
+        let snapshot = database.snapshot();
+        for (o, exists) in [(0, true), (1, true), (2, false), (3, false)] {
+            assert_eq!(
+                database.access_controller().read(o, &snapshot, None).await,
+                Ok(exists)
+            );
+        }
+        let container = database.get_container("kv", &snapshot).await.unwrap();
+        assert_eq!(
+            container.get(b"key", &snapshot, None).await,
+            Ok(Some(b"top secret value".to_vec()))
+        );
+        drop(snapshot);
+        drop(database);
+
+        // A tampered frame that is not the last one makes the log corrupt.
+        let mut log = read(path.join("l.log")).await.unwrap();
+        log[20] ^= 1;
+        write(path.join("l.log"), &log).await.unwrap();
+        assert_eq!(
+            Database::with_cipher(path, cipher).await.err(),
+            Some(Error::CorruptLog(0))
+        );
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn unique_nonces() {
+        const DIR: &str = "cipher_unique_nonces_test";
+        let path = Path::new(DIR);
+        let cipher = Arc::new(XorCipher(11));
+
+        // Each session encrypts data using nonces of a new nonce epoch.
+        let mut nonces = Vec::new();
+        let mut page_address = 0;
+        for _ in 0..3 {
+            let file_io = FileIO::<MonotonicU64>::with_cipher(path, cipher.clone()).unwrap();
+            let page_size = file_io.page_size();
+            if page_address == 0 {
+                page_address = file_io.page_manager().create_page(page_size).await.unwrap();
+            }
+            for _ in 0..2 {
+                assert!(file_io
+                    .page_manager()
+                    .write_page(page_address, |e| e.buffer_mut()[0] += 1)
+                    .await
+                    .is_ok());
+                file_io.page_manager().write_back_sync(page_address);
+                let db = read(path.join("db.dat")).await.unwrap();
+                let nonce_offset = usize::try_from(page_address + page_size).unwrap()
+                    - PAGE_FOOTER_LEN
+                    - super::super::evictable_page::ENCRYPTION_INFO_LEN;
+                nonces.push(u64::from_le_bytes(
+                    db[nonce_offset..nonce_offset + 8].try_into().unwrap(),
+                ));
+            }
+            drop(file_io);
+        }
+        assert!(nonces.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(
+            nonces
+                .iter()
+                .map(|n| n >> NONCE_COUNTER_BITS)
+                .collect::<Vec<_>>(),
+            vec![1, 1, 2, 2, 3, 3]
+        );
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+}
@@ -0,0 +1,744 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The container directory.
+
+use super::blob::Blob;
+use super::btree::BTree;
+use super::evictable_page::EvictablePage;
+use super::hash_table::HashTable;
+use super::page_manager::PageManager;
+use crate::{Error, IndexType, TransactionID, VersionRecord};
+use std::ops::Bound;
+
+/// [`ContainerDirectory`] is the state of the database persisted by the latest checkpoint.
+///
+/// The encoded [`ContainerDirectory`] is stored in the container directory head page followed by
+/// a chain of overflow pages of which the first one is linked back to the head page. The payload of
+/// the head page is `LEN 64|FIRST OVERFLOW PAGE 64|DATA`, where `LEN` is the length of the
+/// encoded data, and `0` if no checkpoints have been taken. The overflow pages of a new
+/// checkpoint are written before the head page, and the head page is only written once they have
+/// reached the device, therefore the previous checkpoint remains intact until the head page is
+/// written.
+///
+/// The encoded form is
+/// `CLOCK 64|NEXT OBJECT ID 64|NEXT TRANSACTION ID 64|LOG START 64|NUM RECORDS 64|RECORD..|NUM
+/// OBJECTS 64|OBJECT ID 64..|NUM INDEXES 64|INDEX..` where each catalog record is `RECORD ID
+/// 64|OBJECT ID 64|KEY LEN 32|VALUE LEN 32|KEY|VALUE`, and each container index is `CONTAINER ID
+/// 64|INDEX TYPE 8|ROOT 64`.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct ContainerDirectory {
+    /// The logical clock of the snapshot that the checkpoint was taken with.
+    pub clock: u64,
+
+    /// The identifier that the next database object is assigned.
+    pub next_object_id: u64,
+
+    /// The identifier that the next transaction is assigned.
+    pub next_transaction_id: TransactionID,
+
+    /// The offset of the log from which log records are replayed over the checkpoint.
+    ///
+    /// Log records before the offset only belong to transactions of which the outcome is
+    /// reflected in the checkpoint, therefore they can be reclaimed once the checkpoint is
+    /// persisted.
+    pub log_start: u64,
+
+    /// Versions of catalog entries visible to the snapshot in ascending key order.
+    pub catalog: Vec<DirectoryRecord>,
+
+    /// Identifiers of database objects invisible to the snapshot in ascending order.
+    ///
+    /// The visibility of the other database objects is either recorded in the log records
+    /// following [`ContainerDirectory::log_start`] or implied by the absence of access control
+    /// data.
+    pub invisible_objects: Vec<u64>,
+
+    /// Indexes of the containers created by the checkpoint in ascending container identifier
+    /// order.
+    pub indexes: Vec<ContainerIndex>,
+}
+
+/// [`ContainerIndex`] is the persistent index of a container holding the versions of key-value
+/// pairs visible to the snapshot of the checkpoint.
+///
+/// The index is a [`BTree`] or a [`HashTable`] according to the [`IndexType`] of the container.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ContainerIndex {
+    /// The identifier of the container.
+    pub container_id: u64,
+
+    /// The type of the index.
+    pub index_type: IndexType,
+
+    /// The root page address of the index.
+    pub root: u64,
+}
+
+/// [`DirectoryRecord`] is a version of a key-value pair persisted in the [`ContainerDirectory`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DirectoryRecord {
+    /// The identifier of the database object locking the key.
+    pub record_id: u64,
+
+    /// The identifier of the database object of the version.
+    pub object_id: u64,
+
+    /// The key.
+    pub key: Box<[u8]>,
+
+    /// The value.
+    pub value: Box<[u8]>,
+}
+
+/// [`IndexEntry`] is a [`DirectoryRecord`] stored in a [`ContainerIndex`].
+///
+/// The value of a [`DirectoryRecord`] that does not fit in an index page is stored in a [`Blob`]
+/// linked to the index, and the [`IndexEntry`] refers to the [`Blob`] instead.
+///
+/// The encoded form is `KEY LEN 16|VALUE LEN 32|RECORD ID 64|OBJECT ID 64|KEY|VALUE`, and
+/// `VALUE LEN = u32::MAX` means that `VALUE` is the `BLOB ID 64` of the [`Blob`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IndexEntry {
+    /// The record; the value is empty if it is stored in a [`Blob`].
+    pub record: DirectoryRecord,
+
+    /// The identifier of the [`Blob`] storing the value.
+    pub blob: Option<u64>,
+}
+
+/// The length of the header of the container directory head page.
+const HEAD_LEN: usize = 16;
+
+/// The length of the fixed fields of the encoded [`ContainerDirectory`].
+const FIXED_LEN: usize = 40;
+
+/// The length of the fixed fields of an encoded [`DirectoryRecord`].
+const RECORD_FIXED_LEN: usize = 24;
+
+/// The length of an encoded [`ContainerIndex`].
+const INDEX_LEN: usize = 17;
+
+/// The length of the fixed fields of an encoded [`IndexEntry`].
+pub(super) const INDEX_ENTRY_FIXED_LEN: usize = 22;
+
+/// The value length of an encoded [`IndexEntry`] referring to a [`Blob`].
+const BLOB_VALUE_LEN: u32 = u32::MAX;
+
+impl ContainerDirectory {
+    /// Writes the [`ContainerDirectory`] to new overflow pages, and returns the payload of the
+    /// head page referring to them along with the addresses of the overflow pages.
+    ///
+    /// The head page is not modified; the overflow pages are freed if any of them could not be
+    /// written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page could not be allocated or written.
+    pub async fn write(&self, page_manager: &PageManager) -> Result<(Vec<u8>, Vec<u64>), Error> {
+        let data = self.encode();
+        let payload_len = page_manager.page_payload_len();
+        let (head_data, overflow_data) = data.split_at(data.len().min(payload_len - HEAD_LEN));
+        let mut pages = Vec::new();
+        let result = async {
+            for chunk in overflow_data.chunks(payload_len) {
+                let page_address = if let Some(last) = pages.last() {
+                    page_manager.create_linked_page(*last).await?
+                } else {
+                    page_manager
+                        .create_page(page_manager.container_directory_head())
+                        .await?
+                };
+                pages.push(page_address);
+                page_manager
+                    .write_page(page_address, |page| {
+                        page.buffer_mut()[..chunk.len()].copy_from_slice(chunk);
+                        page.set_dirty();
+                    })
+                    .await?;
+                page_manager.request_write_back(page_address);
+            }
+            Ok(())
+        }
+        .await;
+        if let Err(error) = result {
+            drop(Self::free(page_manager, &pages).await);
+            return Err(error);
+        }
+        let mut head_payload = vec![0_u8; payload_len];
+        head_payload[..8].copy_from_slice(&(data.len() as u64).to_le_bytes());
+        head_payload[8..HEAD_LEN].copy_from_slice(&pages.first().unwrap_or(&0).to_le_bytes());
+        head_payload[HEAD_LEN..HEAD_LEN + head_data.len()].copy_from_slice(head_data);
+        Ok((head_payload, pages))
+    }
+
+    /// Frees the overflow pages of a [`ContainerDirectory`] that is no longer referenced by the
+    /// head page.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page could not be freed.
+    pub async fn free(page_manager: &PageManager, pages: &[u64]) -> Result<(), Error> {
+        for page_address in pages.iter().rev() {
+            page_manager.delete_page(*page_address).await?;
+            page_manager.request_write_back(*page_address);
+        }
+        Ok(())
+    }
+
+    /// Reads the [`ContainerDirectory`] along with the addresses of its overflow pages.
+    ///
+    /// Returns `None` if no checkpoints have been taken. It is a synchronous method, therefore it
+    /// should be run in the background.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page could not be read, or the [`ContainerDirectory`] is corrupt.
+    pub fn read_sync(page_manager: &PageManager) -> Result<Option<(Self, Vec<u64>)>, Error> {
+        let head = page_manager.container_directory_head();
+        let (len, mut page_address, mut data) = page_manager.read_page_sync(head, |page| {
+            let buffer = page.buffer();
+            let len = u64::from_le_bytes(buffer[..8].try_into().unwrap_or_default());
+            let first = u64::from_le_bytes(buffer[8..HEAD_LEN].try_into().unwrap_or_default());
+            (len, first, buffer[HEAD_LEN..].to_vec())
+        })?;
+        if len == 0 {
+            return Ok(None);
+        }
+        let len = usize::try_from(len).map_err(|_| Error::CorruptPage(head))?;
+        let mut pages = Vec::new();
+        let mut prev_page_address = head;
+        while data.len() < len {
+            if page_address == 0 || pages.contains(&page_address) {
+                return Err(Error::CorruptPage(prev_page_address));
+            }
+            let (prev, next) = page_manager.read_page_sync(page_address, |page| {
+                data.extend_from_slice(page.buffer());
+                (page.prev_page_address(), page.next_page_address())
+            })?;
+            if prev != prev_page_address {
+                return Err(Error::CorruptPage(page_address));
+            }
+            pages.push(page_address);
+            prev_page_address = page_address;
+            page_address = next;
+        }
+        data.truncate(len);
+        let directory = Self::decode(&data).ok_or(Error::CorruptPage(head))?;
+        Ok(Some((directory, pages)))
+    }
+
+    /// Encodes the [`ContainerDirectory`].
+    fn encode(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(
+            FIXED_LEN
+                + self
+                    .catalog
+                    .iter()
+                    .map(|r| RECORD_FIXED_LEN + r.key.len() + r.value.len())
+                    .sum::<usize>()
+                + 8
+                + self.invisible_objects.len() * 8
+                + 8
+                + self.indexes.len() * INDEX_LEN,
+        );
+        data.extend_from_slice(&self.clock.to_le_bytes());
+        data.extend_from_slice(&self.next_object_id.to_le_bytes());
+        data.extend_from_slice(&self.next_transaction_id.to_le_bytes());
+        data.extend_from_slice(&self.log_start.to_le_bytes());
+        data.extend_from_slice(&(self.catalog.len() as u64).to_le_bytes());
+        for record in &self.catalog {
+            data.extend_from_slice(&record.record_id.to_le_bytes());
+            data.extend_from_slice(&record.object_id.to_le_bytes());
+            #[allow(clippy::cast_possible_truncation)]
+            data.extend_from_slice(&(record.key.len() as u32).to_le_bytes());
+            #[allow(clippy::cast_possible_truncation)]
+            data.extend_from_slice(&(record.value.len() as u32).to_le_bytes());
+            data.extend_from_slice(&record.key);
+            data.extend_from_slice(&record.value);
+        }
+        data.extend_from_slice(&(self.invisible_objects.len() as u64).to_le_bytes());
+        for object_id in &self.invisible_objects {
+            data.extend_from_slice(&object_id.to_le_bytes());
+        }
+        data.extend_from_slice(&(self.indexes.len() as u64).to_le_bytes());
+        for index in &self.indexes {
+            data.extend_from_slice(&index.container_id.to_le_bytes());
+            data.push(match index.index_type {
+                IndexType::Ordered => 0,
+                IndexType::Hash => 1,
+            });
+            data.extend_from_slice(&index.root.to_le_bytes());
+        }
+        data
+    }
+
+    /// Decodes a [`ContainerDirectory`] encoded by [`ContainerDirectory::encode`].
+    fn decode(mut data: &[u8]) -> Option<Self> {
+        let clock = u64::from_le_bytes(take(&mut data, 8)?.try_into().ok()?);
+        let next_object_id = u64::from_le_bytes(take(&mut data, 8)?.try_into().ok()?);
+        let next_transaction_id = u64::from_le_bytes(take(&mut data, 8)?.try_into().ok()?);
+        let log_start = u64::from_le_bytes(take(&mut data, 8)?.try_into().ok()?);
+        let num_records = u64::from_le_bytes(take(&mut data, 8)?.try_into().ok()?);
+        let mut catalog = Vec::new();
+        for _ in 0..num_records {
+            let record_id = u64::from_le_bytes(take(&mut data, 8)?.try_into().ok()?);
+            let object_id = u64::from_le_bytes(take(&mut data, 8)?.try_into().ok()?);
+            let key_len = u32::from_le_bytes(take(&mut data, 4)?.try_into().ok()?);
+            let value_len = u32::from_le_bytes(take(&mut data, 4)?.try_into().ok()?);
+            let key = take(&mut data, usize::try_from(key_len).ok()?)?.into();
+            let value = take(&mut data, usize::try_from(value_len).ok()?)?.into();
+            catalog.push(DirectoryRecord {
+                record_id,
+                object_id,
+                key,
+                value,
+            });
+        }
+        let num_objects = u64::from_le_bytes(take(&mut data, 8)?.try_into().ok()?);
+        let mut invisible_objects = Vec::new();
+        for _ in 0..num_objects {
+            invisible_objects.push(u64::from_le_bytes(take(&mut data, 8)?.try_into().ok()?));
+        }
+        let num_indexes = u64::from_le_bytes(take(&mut data, 8)?.try_into().ok()?);
+        let mut indexes = Vec::new();
+        for _ in 0..num_indexes {
+            let container_id = u64::from_le_bytes(take(&mut data, 8)?.try_into().ok()?);
+            let index_type = match take(&mut data, 1)?[0] {
+                0 => IndexType::Ordered,
+                1 => IndexType::Hash,
+                _ => return None,
+            };
+            let root = u64::from_le_bytes(take(&mut data, 8)?.try_into().ok()?);
+            indexes.push(ContainerIndex {
+                container_id,
+                index_type,
+                root,
+            });
+        }
+        Some(ContainerDirectory {
+            clock,
+            next_object_id,
+            next_transaction_id,
+            log_start,
+            catalog,
+            invisible_objects,
+            indexes,
+        })
+    }
+}
+
+impl ContainerIndex {
+    /// Returns `true` if the key can be stored in any type of [`ContainerIndex`] in the database.
+    ///
+    /// Values of any length can be stored since they are stored in a [`Blob`] if needed.
+    pub fn fits(page_manager: &PageManager, key: &[u8]) -> bool {
+        IndexEntry::inline_len(key, &[0; 8]) <= BTree::max_entry_len(page_manager)
+    }
+
+    /// Writes a new index of the records and returns it.
+    ///
+    /// The pages of the index are linked to the container directory head page; they are freed if
+    /// any of the records could not be inserted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page could not be allocated or written, or a record is too large.
+    pub async fn write(
+        page_manager: &PageManager,
+        container_id: u64,
+        index_type: IndexType,
+        records: Vec<DirectoryRecord>,
+    ) -> Result<Self, Error> {
+        let head = page_manager.container_directory_head();
+        let (root, result) = match index_type {
+            IndexType::Ordered => {
+                let mut btree = BTree::create(page_manager, head).await?;
+                let result = async {
+                    for record in records {
+                        btree.insert(page_manager, record).await?;
+                    }
+                    Ok(())
+                }
+                .await;
+                (btree.root(), result)
+            }
+            IndexType::Hash => {
+                let mut hash_table = HashTable::create(page_manager, head).await?;
+                let result = async {
+                    for record in records {
+                        hash_table.insert(page_manager, record).await?;
+                    }
+                    Ok(())
+                }
+                .await;
+                (hash_table.directory(), result)
+            }
+        };
+        let index = ContainerIndex {
+            container_id,
+            index_type,
+            root,
+        };
+        if let Err(error) = result {
+            drop(index.free(page_manager).await);
+            return Err(error);
+        }
+        Ok(index)
+    }
+
+    /// Passes every record in the index to `visitor`.
+    ///
+    /// It is a synchronous method, therefore it should be run in the background.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page could not be read or is corrupt.
+    pub fn scan_sync<F: FnMut(DirectoryRecord)>(
+        &self,
+        page_manager: &PageManager,
+        visitor: F,
+    ) -> Result<(), Error> {
+        match self.index_type {
+            IndexType::Ordered => {
+                BTree::open(page_manager, self.root).scan_sync(page_manager, visitor)
+            }
+            IndexType::Hash => {
+                HashTable::open(page_manager, self.root).scan_sync(page_manager, visitor)
+            }
+        }
+    }
+
+    /// Passes up to `limit` records in the range to `visitor` in ascending key order, reading up
+    /// to `readahead` pages ahead in the background.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::WrongParameter`] if the index is not ordered, or an error if a page could
+    /// not be read or is corrupt.
+    pub async fn scan<F: FnMut(DirectoryRecord)>(
+        &self,
+        page_manager: &PageManager,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+        limit: usize,
+        readahead: usize,
+        visitor: F,
+    ) -> Result<(), Error> {
+        match self.index_type {
+            IndexType::Ordered => {
+                BTree::open(page_manager, self.root)
+                    .scan(page_manager, start, end, limit, readahead, visitor)
+                    .await
+            }
+            IndexType::Hash => Err(Error::WrongParameter),
+        }
+    }
+
+    /// Returns `true` if a record associated with the key exists.
+    ///
+    /// It is a synchronous method, therefore it should be run in the background.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::WrongParameter`] if the index is not ordered, or an error if a page could
+    /// not be read or is corrupt.
+    pub fn contains_sync(&self, page_manager: &PageManager, key: &[u8]) -> Result<bool, Error> {
+        match self.index_type {
+            IndexType::Ordered => {
+                BTree::open(page_manager, self.root).contains_sync(page_manager, key)
+            }
+            IndexType::Hash => Err(Error::WrongParameter),
+        }
+    }
+
+    /// Returns the number of pages of the index.
+    ///
+    /// Every page of the index, including [`Blob`] pages, is linked to the root page.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page could not be read.
+    pub async fn num_pages(&self, page_manager: &PageManager) -> Result<u64, Error> {
+        let mut num_pages = 1;
+        let mut page_address = self.root;
+        loop {
+            page_address = page_manager
+                .read_page(page_address, EvictablePage::next_page_address)
+                .await?;
+            if page_address == 0 {
+                return Ok(num_pages);
+            }
+            num_pages += 1;
+        }
+    }
+
+    /// Returns the addresses of the pages of the index starting with the root page.
+    ///
+    /// It is a synchronous method, therefore it should be run in the background.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page could not be read.
+    pub fn pages_sync(&self, page_manager: &PageManager) -> Result<Vec<u64>, Error> {
+        let mut pages = vec![self.root];
+        loop {
+            let page_address = pages[pages.len() - 1];
+            let next_page_address =
+                page_manager.read_page_sync(page_address, EvictablePage::next_page_address)?;
+            if next_page_address == 0 {
+                return Ok(pages);
+            }
+            pages.push(next_page_address);
+        }
+    }
+
+    /// Moves the pages of the index except for the root page to free pages nearer the front of
+    /// the database file, and returns the number of pages moved.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page could not be moved or is corrupt.
+    pub async fn compact(&self, page_manager: &PageManager) -> Result<usize, Error> {
+        match self.index_type {
+            IndexType::Ordered => {
+                BTree::open(page_manager, self.root)
+                    .compact(page_manager)
+                    .await
+            }
+            IndexType::Hash => {
+                HashTable::open(page_manager, self.root)
+                    .compact(page_manager)
+                    .await
+            }
+        }
+    }
+
+    /// Frees all the pages of the index.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page could not be freed.
+    pub async fn free(self, page_manager: &PageManager) -> Result<(), Error> {
+        match self.index_type {
+            IndexType::Ordered => {
+                BTree::open(page_manager, self.root)
+                    .free(page_manager)
+                    .await
+            }
+            IndexType::Hash => {
+                HashTable::open(page_manager, self.root)
+                    .free(page_manager)
+                    .await
+            }
+        }
+    }
+}
+
+impl IndexEntry {
+    /// Creates an [`IndexEntry`] of the record to be stored in pages of an index of which the
+    /// entries must not be larger than `max_len`.
+    ///
+    /// The value is written to a new [`Blob`] linked to the specified page if the record is too
+    /// large.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::WrongParameter`] if the key is too large, or an error if the [`Blob`]
+    /// could not be written.
+    pub async fn new(
+        page_manager: &PageManager,
+        owner: u64,
+        mut record: DirectoryRecord,
+        max_len: usize,
+    ) -> Result<Self, Error> {
+        if Self::inline_len(&record.key, &record.value) <= max_len {
+            return Ok(IndexEntry { record, blob: None });
+        }
+        if Self::inline_len(&record.key, &[0; 8]) > max_len {
+            return Err(Error::WrongParameter);
+        }
+        let mut writer = Blob::create(page_manager, owner).await?;
+        if let Err(error) = writer.write(&record.value).await {
+            drop(writer.abort().await);
+            return Err(error);
+        }
+        let blob = writer.finish().await?;
+        record.value = Box::default();
+        Ok(IndexEntry {
+            record,
+            blob: Some(blob.id()),
+        })
+    }
+
+    /// Returns the encoded length of an [`IndexEntry`] of the key and value stored in the page.
+    pub fn inline_len(key: &[u8], value: &[u8]) -> usize {
+        INDEX_ENTRY_FIXED_LEN + key.len() + value.len()
+    }
+
+    /// Returns the encoded length of the [`IndexEntry`].
+    pub fn encoded_len(&self) -> usize {
+        if self.blob.is_some() {
+            Self::inline_len(&self.record.key, &[0; 8])
+        } else {
+            Self::inline_len(&self.record.key, &self.record.value)
+        }
+    }
+
+    /// Reads the value from the [`Blob`] if the value is stored in it, and returns the record.
+    ///
+    /// It is a synchronous method, therefore it should be run in the background.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the [`Blob`] could not be read.
+    pub fn into_record_sync(self, page_manager: &PageManager) -> Result<DirectoryRecord, Error> {
+        let mut record = self.record;
+        if let Some(blob) = self.blob {
+            record.value = Blob::read_sync(page_manager, blob)?.into();
+        }
+        Ok(record)
+    }
+
+    /// Reads the value from the [`Blob`] through the page cache if the value is stored in it, and
+    /// returns the record.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the [`Blob`] could not be read.
+    pub async fn into_record(self, page_manager: &PageManager) -> Result<DirectoryRecord, Error> {
+        let mut record = self.record;
+        if let Some(blob) = self.blob {
+            record.value = Blob::read(page_manager, blob).await?.into();
+        }
+        Ok(record)
+    }
+
+    /// Moves the pages of the [`Blob`] storing the value to free pages nearer the front of the
+    /// database file, and returns the number of pages moved.
+    ///
+    /// The page storing the [`IndexEntry`] has to be written if the identifier of the [`Blob`]
+    /// changed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page could not be moved, or the [`Blob`] is corrupt.
+    pub async fn compact_blob(&mut self, page_manager: &PageManager) -> Result<usize, Error> {
+        let Some(blob) = self.blob else {
+            return Ok(0);
+        };
+        let (blob, num_pages_moved) = Blob::compact(page_manager, blob).await?;
+        self.blob = Some(blob);
+        Ok(num_pages_moved)
+    }
+
+    /// Appends the encoded [`IndexEntry`] to `data` omitting the first `prefix_len` bytes of the
+    /// key.
+    pub fn encode(&self, prefix_len: usize, data: &mut Vec<u8>) {
+        let record = &self.record;
+        #[allow(clippy::cast_possible_truncation)]
+        data.extend_from_slice(&((record.key.len() - prefix_len) as u16).to_le_bytes());
+        #[allow(clippy::cast_possible_truncation)]
+        let value_len = self
+            .blob
+            .map_or(record.value.len() as u32, |_| BLOB_VALUE_LEN);
+        data.extend_from_slice(&value_len.to_le_bytes());
+        data.extend_from_slice(&record.record_id.to_le_bytes());
+        data.extend_from_slice(&record.object_id.to_le_bytes());
+        data.extend_from_slice(&record.key[prefix_len..]);
+        if let Some(blob) = self.blob {
+            data.extend_from_slice(&blob.to_le_bytes());
+        } else {
+            data.extend_from_slice(&record.value);
+        }
+    }
+
+    /// Decodes an [`IndexEntry`] from the front of `data`, and prepends `prefix` to the key.
+    pub fn decode(prefix: &[u8], data: &mut &[u8]) -> Option<Self> {
+        let key_len = u16::from_le_bytes(take(data, 2)?.try_into().ok()?);
+        let value_len = u32::from_le_bytes(take(data, 4)?.try_into().ok()?);
+        let record_id = u64::from_le_bytes(take(data, 8)?.try_into().ok()?);
+        let object_id = u64::from_le_bytes(take(data, 8)?.try_into().ok()?);
+        let key = [prefix, take(data, usize::from(key_len))?].concat().into();
+        let (value, blob) = if value_len == BLOB_VALUE_LEN {
+            let blob = u64::from_le_bytes(take(data, 8)?.try_into().ok()?);
+            (Box::default(), Some(blob))
+        } else {
+            (take(data, usize::try_from(value_len).ok()?)?.into(), None)
+        };
+        Some(IndexEntry {
+            record: DirectoryRecord {
+                record_id,
+                object_id,
+                key,
+                value,
+            },
+            blob,
+        })
+    }
+}
+
+/// Takes `len` bytes from the front of `data`.
+pub(super) fn take<'d>(data: &mut &'d [u8], len: usize) -> Option<&'d [u8]> {
+    if data.len() < len {
+        return None;
+    }
+    let (taken, remaining) = data.split_at(len);
+    *data = remaining;
+    Some(taken)
+}
+
+impl From<&VersionRecord<'_>> for DirectoryRecord {
+    #[inline]
+    fn from(version: &VersionRecord<'_>) -> Self {
+        DirectoryRecord {
+            record_id: version.record_id(),
+            object_id: version.object_id(),
+            key: version.key().into(),
+            value: version.value().into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode_decode() {
+        let directory = ContainerDirectory {
+            clock: 7,
+            next_object_id: 1 << 63,
+            next_transaction_id: 24,
+            log_start: 4096,
+            catalog: (0..4_u8)
+                .map(|i| DirectoryRecord {
+                    record_id: u64::from(i),
+                    object_id: u64::from(i) + 100,
+                    key: vec![i; usize::from(i)].into(),
+                    value: vec![i; 11].into(),
+                })
+                .collect(),
+            invisible_objects: vec![3, 8, 1 << 40],
+            indexes: (0..3)
+                .map(|i| ContainerIndex {
+                    container_id: i + 1,
+                    index_type: if i % 2 == 0 {
+                        IndexType::Ordered
+                    } else {
+                        IndexType::Hash
+                    },
+                    root: (i + 5) * 512,
+                })
+                .collect(),
+        };
+        let data = directory.encode();
+        assert_eq!(ContainerDirectory::decode(&data), Some(directory));
+        assert_eq!(ContainerDirectory::decode(&data[..data.len() - 1]), None);
+        assert_eq!(
+            ContainerDirectory::decode(&ContainerDirectory::default().encode()),
+            Some(ContainerDirectory::default())
+        );
+    }
+}
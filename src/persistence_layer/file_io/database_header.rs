@@ -4,14 +4,18 @@
 
 //! The header of the database file.
 
-use super::evictable_page::{EvictablePage, PAGE_HEADER_LEN, PAGE_SIZE};
+use super::cipher::MAX_NONCE_EPOCH;
+use super::evictable_page::{
+    EvictablePage, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE, MIN_PAGE_SIZE, PAGE_HEADER_LEN,
+};
 use super::RandomAccessFile;
 use crate::Error;
-use scc::Bag;
-use std::sync::atomic::AtomicU64;
 use std::sync::atomic::Ordering::Relaxed;
 
 /// The header of the database file that occupies the first page of the database.
+///
+/// The header is the root of the manifest of the database files: the page size and the size of
+/// segment files are stored in the header, and it is always located in the first segment.
 #[derive(Debug)]
 pub struct DatabaseHeader {
     /// Database version.
@@ -26,70 +30,381 @@ pub struct DatabaseHeader {
     #[allow(dead_code)]
     pub container_directory_head: u64,
 
-    /// The free page set.
+    /// The size of a page.
     ///
-    /// TODO: optimize memory usage, e.g., by using a bit-vector.
-    pub free_pages: Bag<u64>,
+    /// The page size is determined when the database is created, and cannot be changed.
+    pub page_size: u64,
 
-    /// The current offset of the free page scanner.
-    pub free_page_scanner_offset: AtomicU64,
+    /// The size of a segment of the database and log files.
+    ///
+    /// `0` indicates that the files are not split into segments.
+    pub segment_size: u64,
+
+    /// The database was shut down cleanly, and no pages have been written since then.
+    pub clean_shutdown: bool,
+
+    /// The logical clock of the database when it was last shut down cleanly.
+    ///
+    /// The database must not reuse clock values up to it when reopened.
+    pub clock: u64,
 }
 
 /// The current database version.
-pub const VERSION: u64 = 1;
+///
+/// Version `2` added page checksums and configurable page sizes.
+pub const VERSION: u64 = 2;
+
+/// The page size of database files of version `1`.
+const VERSION_1_PAGE_SIZE: u64 = 512;
+
+/// The page number where the log head is located.
+const DEFAULT_LOG_HEAD_PAGE: u64 = 1;
+
+/// The page number where the container directory head is located.
+const DEFAULT_CONTAINER_DIRECTORY_PAGE: u64 = 2;
+
+/// The page number of the first page that can be allocated.
+const DEFAULT_FREE_PAGE: u64 = 3;
+
+/// The offset of the page size field in the header page.
+const PAGE_SIZE_OFFSET: usize = 24;
 
-/// The address where the log head is located.
-const DEFAULT_LOG_HEAD_PAGE: u64 = PAGE_SIZE;
+/// The offset of the nonce epoch field in the header page.
+const NONCE_EPOCH_OFFSET: usize = 32;
 
-/// The address where the container directory head is located.
-const DEFAULT_CONTAINER_DIRECTORY_PAGE: u64 = PAGE_SIZE * 2;
+/// The offset of the segment size field in the header page.
+const SEGMENT_SIZE_OFFSET: usize = 40;
 
-/// The offset where the log container directory head page address if stored.
-const DEFAULT_FREE_PAGE: u64 = PAGE_SIZE * 3;
+/// The offset of the clean shutdown marker in the header page.
+const CLEAN_SHUTDOWN_OFFSET: usize = 48;
+
+/// The offset of the logical clock field in the header page.
+const CLOCK_OFFSET: usize = 56;
+
+/// The segment size must be a multiple of the value.
+pub const SEGMENT_SIZE_UNIT: u64 = 1 << 20;
 
 impl DatabaseHeader {
     /// Reads the header from the database file.
     ///
-    /// It writes the header information into the file if none present.
+    /// It writes the header information into the file if none present; the page size of a new
+    /// database is set to the specified one, or [`DEFAULT_PAGE_SIZE`] if `None`, and the same
+    /// applies to the segment size. If `migrate` is `true`, a database file of an older version is
+    /// upgraded to the current version.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::WrongParameter`] if the specified page or segment size is invalid or
+    /// different from that of the existing database, or whether a cipher is set to the file does not match
+    /// whether the database is encrypted, and [`Error::Generic`] if the version of the database
+    /// file is not supported or the database file needs to be migrated.
     #[inline]
-    pub fn from_file(db: &RandomAccessFile) -> Result<Self, Error> {
+    pub fn from_file(
+        db: &RandomAccessFile,
+        page_size: Option<u64>,
+        segment_size: Option<u64>,
+        migrate: bool,
+    ) -> Result<Self, Error> {
+        if page_size.is_some_and(|p| !is_valid_page_size(p))
+            || segment_size.is_some_and(|s| s == 0 || !s.is_multiple_of(SEGMENT_SIZE_UNIT))
+        {
+            return Err(Error::WrongParameter);
+        }
         if db.len(Relaxed) == 0 {
             // The file is empty, creating a new database.
-            db.set_len(PAGE_SIZE * 4)?;
-
-            let base_offset = PAGE_HEADER_LEN as u64;
-            db.write(&VERSION.to_le_bytes(), base_offset)?;
-            db.write(&DEFAULT_LOG_HEAD_PAGE.to_le_bytes(), base_offset + 8)?;
-            db.write(
-                &DEFAULT_CONTAINER_DIRECTORY_PAGE.to_le_bytes(),
-                base_offset + 16,
-            )?;
+            Self::create(db, page_size, segment_size)
+        } else {
+            // The version has to be checked before the header page is verified.
+            let mut version_buffer = [0_u8; 8];
+            db.read(&mut version_buffer, PAGE_HEADER_LEN as u64)?;
+            let version = u64::from_le_bytes(version_buffer);
+            if version > VERSION {
+                return Err(Error::Generic(
+                    "the database file was created by a newer version",
+                ));
+            } else if version < VERSION {
+                if !migrate {
+                    return Err(Error::Generic(
+                        "the database file needs to be migrated to the current version",
+                    ));
+                } else if db.cipher().is_some() {
+                    // Database files of older versions are not encrypted.
+                    return Err(Error::WrongParameter);
+                }
+                Self::migrate(db, version)?;
+            }
 
-            // The fourth page is initially free.
-            let free_pages = Bag::new();
-            free_pages.push(DEFAULT_FREE_PAGE);
+            let mut page_size_buffer = [0_u8; 8];
+            db.read(
+                &mut page_size_buffer,
+                (PAGE_HEADER_LEN + PAGE_SIZE_OFFSET) as u64,
+            )?;
+            let stored_page_size = u64::from_le_bytes(page_size_buffer);
+            if !is_valid_page_size(stored_page_size) {
+                return Err(Error::CorruptDatabase);
+            }
+            if page_size.is_some_and(|p| p != stored_page_size) {
+                return Err(Error::WrongParameter);
+            }
 
-            Ok(Self {
-                version: VERSION,
-                log_head: DEFAULT_LOG_HEAD_PAGE,
-                container_directory_head: DEFAULT_CONTAINER_DIRECTORY_PAGE,
-                free_pages,
-                free_page_scanner_offset: AtomicU64::new(DEFAULT_FREE_PAGE),
-            })
-        } else {
-            let database_page = EvictablePage::from_file(db, 0)?;
+            let database_page = EvictablePage::from_file(db, 0, stored_page_size)?;
             let mut iter = database_page.buffer().chunks(8);
             let version = u64::from_le_bytes(iter.next().unwrap().try_into().unwrap());
             let log_head = u64::from_le_bytes(iter.next().unwrap().try_into().unwrap());
             let container_directory_head =
                 u64::from_le_bytes(iter.next().unwrap().try_into().unwrap());
+            iter.next();
+            let mut nonce_epoch = u64::from_le_bytes(iter.next().unwrap().try_into().unwrap());
+            if (nonce_epoch != 0) != db.cipher().is_some() {
+                return Err(Error::WrongParameter);
+            } else if nonce_epoch > MAX_NONCE_EPOCH {
+                // Older versions encrypted log records using a single nonce stored in the field.
+                return Err(Error::Generic(
+                    "the encrypted database file was created by an older version",
+                ));
+            }
+            let stored_segment_size = u64::from_le_bytes(iter.next().unwrap().try_into().unwrap());
+            if segment_size.is_some_and(|s| s != stored_segment_size) {
+                return Err(Error::WrongParameter);
+            }
+            let clean_shutdown = u64::from_le_bytes(iter.next().unwrap().try_into().unwrap()) != 0;
+            let clock = u64::from_le_bytes(iter.next().unwrap().try_into().unwrap());
+            if let Some(encryption) = db.encryption().filter(|_| !db.is_read_only()) {
+                nonce_epoch = Self::advance_nonce_epoch(db, stored_page_size, nonce_epoch)?;
+                encryption.nonces().start_epoch(nonce_epoch);
+            }
             Ok(Self {
                 version,
                 log_head,
                 container_directory_head,
-                free_pages: Bag::new(),
-                free_page_scanner_offset: AtomicU64::new(DEFAULT_FREE_PAGE),
+                page_size: stored_page_size,
+                segment_size: stored_segment_size,
+                clean_shutdown,
+                clock,
             })
         }
     }
+
+    /// Writes the header of a new database into the empty database file.
+    fn create(
+        db: &RandomAccessFile,
+        page_size: Option<u64>,
+        segment_size: Option<u64>,
+    ) -> Result<Self, Error> {
+        let page_size = page_size.unwrap_or(DEFAULT_PAGE_SIZE);
+        db.set_len(page_size * 4)?;
+
+        let log_head = DEFAULT_LOG_HEAD_PAGE * page_size;
+        let container_directory_head = DEFAULT_CONTAINER_DIRECTORY_PAGE * page_size;
+        let mut database_page = EvictablePage::new(db, 0, page_size)?;
+        let buffer = database_page.buffer_mut();
+        buffer[0..8].copy_from_slice(&VERSION.to_le_bytes());
+        buffer[8..16].copy_from_slice(&log_head.to_le_bytes());
+        buffer[16..24].copy_from_slice(&container_directory_head.to_le_bytes());
+        buffer[PAGE_SIZE_OFFSET..PAGE_SIZE_OFFSET + 8].copy_from_slice(&page_size.to_le_bytes());
+        let nonce_epoch = u64::from(db.cipher().is_some());
+        buffer[NONCE_EPOCH_OFFSET..NONCE_EPOCH_OFFSET + 8]
+            .copy_from_slice(&nonce_epoch.to_le_bytes());
+        let segment_size = segment_size.unwrap_or(0);
+        buffer[SEGMENT_SIZE_OFFSET..SEGMENT_SIZE_OFFSET + 8]
+            .copy_from_slice(&segment_size.to_le_bytes());
+        database_page.write_back(db)?;
+        if let Some(encryption) = db.encryption() {
+            encryption.nonces().start_epoch(nonce_epoch);
+        }
+
+        // The fourth page is initially free.
+        Ok(Self {
+            version: VERSION,
+            log_head,
+            container_directory_head,
+            page_size,
+            segment_size,
+            clean_shutdown: false,
+            clock: 0,
+        })
+    }
+
+    /// Reads the segment size from the database file without verifying the header page.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database file could not be read.
+    #[inline]
+    pub fn read_segment_size(db: &RandomAccessFile) -> Result<u64, Error> {
+        Self::read_field(db, SEGMENT_SIZE_OFFSET)
+    }
+
+    /// Reads the nonce epoch from the database file without verifying the header page.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database file could not be read.
+    #[inline]
+    pub fn read_nonce_epoch(db: &RandomAccessFile) -> Result<u64, Error> {
+        Self::read_field(db, NONCE_EPOCH_OFFSET)
+    }
+
+    /// Sets the clean shutdown marker along with the clock in the header page if a clock is
+    /// specified, otherwise clears the marker.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header page could not be read or written.
+    #[inline]
+    pub fn write_clean_shutdown(
+        db: &RandomAccessFile,
+        page_size: u64,
+        clock: Option<u64>,
+    ) -> Result<(), Error> {
+        let mut database_page = EvictablePage::from_file(db, 0, page_size)?;
+        let buffer = database_page.buffer_mut();
+        buffer[CLEAN_SHUTDOWN_OFFSET..CLEAN_SHUTDOWN_OFFSET + 8]
+            .copy_from_slice(&u64::from(clock.is_some()).to_le_bytes());
+        if let Some(clock) = clock {
+            buffer[CLOCK_OFFSET..CLOCK_OFFSET + 8].copy_from_slice(&clock.to_le_bytes());
+        }
+        database_page.set_dirty();
+        database_page.write_back(db)
+    }
+
+    /// Durably advances the nonce epoch in the header page, and returns the new nonce epoch.
+    ///
+    /// The new nonce epoch has to be synchronized with the device before any data is encrypted
+    /// using it, otherwise nonces can be reused after a crash.
+    fn advance_nonce_epoch(
+        db: &RandomAccessFile,
+        page_size: u64,
+        nonce_epoch: u64,
+    ) -> Result<u64, Error> {
+        if nonce_epoch >= MAX_NONCE_EPOCH {
+            return Err(Error::Generic("nonce epochs exhausted"));
+        }
+        let nonce_epoch = nonce_epoch + 1;
+        let mut database_page = EvictablePage::from_file(db, 0, page_size)?;
+        database_page.buffer_mut()[NONCE_EPOCH_OFFSET..NONCE_EPOCH_OFFSET + 8]
+            .copy_from_slice(&nonce_epoch.to_le_bytes());
+        database_page.set_dirty();
+        database_page.write_back(db)?;
+        db.sync()?;
+        Ok(nonce_epoch)
+    }
+
+    /// Reads a `u64` field of the header page.
+    fn read_field(db: &RandomAccessFile, offset: usize) -> Result<u64, Error> {
+        let mut field_buffer = [0_u8; 8];
+        db.read(&mut field_buffer, (PAGE_HEADER_LEN + offset) as u64)?;
+        Ok(u64::from_le_bytes(field_buffer))
+    }
+
+    /// Upgrades the database file to the current version.
+    ///
+    /// The header page is rewritten at last, therefore an interrupted migration can be resumed.
+    fn migrate(db: &RandomAccessFile, mut version: u64) -> Result<(), Error> {
+        while version < VERSION {
+            match version {
+                1 => Self::migrate_from_version_1(db)?,
+                _ => return Err(Error::Generic("the database file version is unknown")),
+            }
+            version += 1;
+        }
+        Ok(())
+    }
+
+    /// Adds page footers, and the page size field to the header page.
+    fn migrate_from_version_1(db: &RandomAccessFile) -> Result<(), Error> {
+        let num_pages = db.len(Relaxed) / VERSION_1_PAGE_SIZE;
+        for page_address in (1..num_pages).map(|p| p * VERSION_1_PAGE_SIZE) {
+            let mut page =
+                EvictablePage::from_file_without_footer(db, page_address, VERSION_1_PAGE_SIZE)?;
+            if page.is_dirty() {
+                page.write_back(db)?;
+            }
+        }
+        let mut database_page =
+            EvictablePage::from_file_without_footer(db, 0, VERSION_1_PAGE_SIZE)?;
+        let buffer = database_page.buffer_mut();
+        buffer[0..8].copy_from_slice(&2_u64.to_le_bytes());
+        buffer[PAGE_SIZE_OFFSET..PAGE_SIZE_OFFSET + 8]
+            .copy_from_slice(&VERSION_1_PAGE_SIZE.to_le_bytes());
+        database_page.set_dirty();
+        database_page.write_back(db)
+    }
+
+    /// Returns the address of the first page that can be allocated.
+    #[inline]
+    pub fn first_free_page(&self) -> u64 {
+        DEFAULT_FREE_PAGE * self.page_size
+    }
+}
+
+/// Returns `true` if the page size is a power of two between [`MIN_PAGE_SIZE`] and
+/// [`MAX_PAGE_SIZE`].
+pub fn is_valid_page_size(page_size: u64) -> bool {
+    page_size.is_power_of_two() && (MIN_PAGE_SIZE..=MAX_PAGE_SIZE).contains(&page_size)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Error, FileIO, MonotonicU64};
+    use std::fs::{create_dir_all, OpenOptions};
+    use std::os::unix::fs::FileExt;
+    use std::path::Path;
+    use tokio::fs::remove_dir_all;
+
+    #[tokio::test]
+    async fn migrate() {
+        const DIR: &str = "database_header_migrate_test";
+        let path = Path::new(DIR);
+
+        // Write a database file of version `1`.
+        create_dir_all(path).unwrap();
+        let db = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(path.join("db.dat"))
+            .unwrap();
+        db.set_len(512 * 4).unwrap();
+        for (i, field) in [1_u64, 512, 1024].into_iter().enumerate() {
+            db.write_all_at(&field.to_le_bytes(), 16 + 8 * i as u64)
+                .unwrap();
+        }
+        db.write_all_at(&512_u64.to_le_bytes(), 512 * 3).unwrap();
+        db.write_all_at(&[41], 512 * 3 + 16).unwrap();
+        drop(db);
+
+        assert!(matches!(
+            FileIO::<MonotonicU64>::with_path(path).err(),
+            Some(Error::Generic(_))
+        ));
+        let file_io = FileIO::<MonotonicU64>::open_and_migrate(path).unwrap();
+        let result = file_io
+            .page_manager()
+            .read_page(512 * 3, |e| (e.prev_page_address(), e.buffer()[0]))
+            .await
+            .unwrap();
+        assert_eq!(result, (512, 41));
+        drop(file_io);
+
+        let file_io = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        assert_eq!(file_io.page_size(), 512);
+        drop(file_io);
+
+        // Pretend that the database file was created by a newer version.
+        let db = OpenOptions::new()
+            .write(true)
+            .open(path.join("db.dat"))
+            .unwrap();
+        db.write_all_at(&3_u64.to_le_bytes(), 16).unwrap();
+        drop(db);
+        assert_eq!(
+            FileIO::<MonotonicU64>::open_and_migrate(path).err(),
+            Some(Error::Generic(
+                "the database file was created by a newer version"
+            ))
+        );
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
 }
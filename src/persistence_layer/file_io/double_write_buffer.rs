@@ -0,0 +1,91 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Double-write buffer.
+
+use super::evictable_page::EvictablePage;
+use super::random_access_file::RandomAccessFile;
+use crate::Error;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::Mutex;
+
+/// [`DoubleWriteBuffer`] protects pages of the database file against torn writes.
+///
+/// A page may be larger than the unit of atomic writes of the storage device, therefore a crash
+/// in the middle of writing a page may leave the page half-written. Pages are first written to
+/// the double-write file, and then written to the database file after the double-write file is
+/// synchronized with the device; if a page in the database file is found torn on recovery, the
+/// intact copy of the page in the double-write file is written back. A crash in the middle of
+/// writing the double-write file leaves the pages in the database file intact.
+///
+/// The double-write file only retains the latest run of contiguous pages in the
+/// `ADDRESS 64-bit|LEN 64-bit|PAGES` layout, and runs are written one at a time.
+#[derive(Debug)]
+pub struct DoubleWriteBuffer {
+    /// The double-write file.
+    file: RandomAccessFile,
+
+    /// The lock serializing writes of runs.
+    lock: Mutex<()>,
+}
+
+/// The length of the header of the double-write file.
+const HEADER_LEN: usize = 16;
+
+impl DoubleWriteBuffer {
+    /// Creates a new [`DoubleWriteBuffer`] on top of the file.
+    #[inline]
+    pub fn new(file: RandomAccessFile) -> Self {
+        Self {
+            file,
+            lock: Mutex::default(),
+        }
+    }
+
+    /// Writes the image of contiguous pages starting at the address to the double-write file, and
+    /// then to the database file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either file could not be written.
+    #[inline]
+    pub fn write(&self, db: &RandomAccessFile, address: u64, image: &[u8]) -> Result<(), Error> {
+        let _guard = self.lock.lock().map_err(|_| Error::UnexpectedState)?;
+        let mut header = [0_u8; HEADER_LEN];
+        header[0..8].copy_from_slice(&address.to_le_bytes());
+        header[8..16].copy_from_slice(&(image.len() as u64).to_le_bytes());
+        self.file
+            .write_batch(&[(&header, 0), (image, HEADER_LEN as u64)])?;
+        db.write(image, address)
+    }
+
+    /// Returns the intact image of the page at the address if the page is retained in the
+    /// double-write file.
+    #[inline]
+    pub fn read_page(&self, address: u64, page_size: u64) -> Option<Vec<u8>> {
+        let _guard = self.lock.lock().ok()?;
+        let mut header = [0_u8; HEADER_LEN];
+        self.file.read(&mut header, 0).ok()?;
+        let start_address = u64::from_le_bytes(header[0..8].try_into().ok()?);
+        let len = u64::from_le_bytes(header[8..16].try_into().ok()?);
+        if address < start_address
+            || address + page_size > start_address.checked_add(len)?
+            || HEADER_LEN as u64 + len > self.file.len(Relaxed)
+        {
+            return None;
+        }
+        let mut image = vec![0_u8; usize::try_from(page_size).ok()?];
+        self.file
+            .read(&mut image, HEADER_LEN as u64 + address - start_address)
+            .ok()?;
+        EvictablePage::is_intact(&image).then_some(image)
+    }
+
+    /// Returns the error that writing the double-write file failed with if the file refuses
+    /// writes.
+    #[inline]
+    pub fn write_error(&self) -> Option<&Error> {
+        self.file.write_error()
+    }
+}
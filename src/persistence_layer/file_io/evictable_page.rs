@@ -4,9 +4,10 @@
 
 //! Persistent page implementation.
 
+use super::aligned_buffer::AlignedBuffer;
+use super::cipher::TAG_LEN;
 use super::random_access_file::RandomAccessFile;
 use crate::Error;
-use std::mem::MaybeUninit;
 
 /// The in-memory representation of a persistent page.
 ///
@@ -14,6 +15,17 @@ use std::mem::MaybeUninit;
 /// - `PREV_OFFSET 64-bit|NEXT_OFFSET 64-bit`.
 /// - _This assumes that any operations on the page header are atomically applied to the device.
 ///
+/// The last `4B` of a page is the page footer containing the `CRC32C` checksum of the rest of the
+/// page; the checksum is recomputed whenever the page is written back, and verified whenever the
+/// page is read from the file, so that torn or silently corrupted pages are detected. A page
+/// filled with zeros is regarded as a valid page that has never been written.
+///
+/// If the database file is encrypted, the page footer is extended to
+/// `NONCE 64-bit|KEY_ID 32-bit|TAG 128-bit|CHECKSUM 32-bit`, and the content of the page between
+/// the header and footer is encrypted using a new nonce whenever written back; the address of the
+/// page is authenticated along with the content. The page header and the first page of the
+/// database file are never encrypted.
+///
 /// The layout suggests that a database consists of linked list of pages, and the `PREV_OFFSET`
 /// field represents the state of a page.
 /// - `NULL|*`: the page is unreachable, and will be eventually added to a free page list.
@@ -40,27 +52,64 @@ use std::mem::MaybeUninit;
 /// 4. The page is inserted into a free page list.
 #[derive(Debug)]
 pub struct EvictablePage {
-    /// The address of the page and the dirty flag of the page.
+    /// The address of the page and the flags of the page.
     ///
-    /// The layout of the field is `address: 63-bit|dirty_flag: 1-bit`.
+    /// The layout of the field is `address: 62-bit|encrypted_flag: 1-bit|dirty_flag: 1-bit`.
     address_and_dirty_flag: u64,
 
     /// The content of the page.
     ///
-    /// The first `16B` is reserved for the header of the page.
-    page_buffer: PageBuffer,
+    /// The first `16B` is reserved for the header of the page, and the last `4B` for the footer;
+    /// the buffer is aligned so that it can be used for direct IO operations.
+    page_buffer: AlignedBuffer,
 }
 
-/// The type of a page buffer.
-#[allow(clippy::cast_possible_truncation)]
-pub type PageBuffer = [u8; PAGE_SIZE as usize];
+/// The default size of a page.
+pub const DEFAULT_PAGE_SIZE: u64 = 512;
 
-/// The size of a page.
-pub const PAGE_SIZE: u64 = 512;
+/// The minimum size of a page.
+pub const MIN_PAGE_SIZE: u64 = 512;
+
+/// The maximum size of a page.
+pub const MAX_PAGE_SIZE: u64 = 64 * 1024;
 
 /// The length of the page header of a page.
 pub const PAGE_HEADER_LEN: usize = 16;
 
+/// The length of the page footer of a page.
+pub const PAGE_FOOTER_LEN: usize = 4;
+
+/// The length of the encryption information stored in the page footer of an encrypted page.
+pub const ENCRYPTION_INFO_LEN: usize = 12 + TAG_LEN;
+
+/// The dirty flag.
+const DIRTY_FLAG: u64 = 1;
+
+/// The encrypted flag.
+const ENCRYPTED_FLAG: u64 = 2;
+
+/// The `CRC32C` lookup table.
+const CRC32C_TABLE: [u32; 256] = {
+    let mut table = [0_u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        #[allow(clippy::cast_possible_truncation)]
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 0 {
+                crc >> 1
+            } else {
+                (crc >> 1) ^ 0x82F6_3B78
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
 impl EvictablePage {
     /// Creates an [`EvictablePage`] from a file.
     ///
@@ -69,35 +118,131 @@ impl EvictablePage {
     ///
     /// TODO: it is a blocking system call, therefore need to replace it with an AIO lib.
     #[inline]
-    pub fn from_file(db: &RandomAccessFile, address: u64) -> Result<EvictablePage, Error> {
-        #[allow(clippy::uninit_assumed_init, invalid_value)]
-        // Safety: the buffer will be filled by the following file IO.
-        let mut page_buffer: PageBuffer = unsafe { MaybeUninit::uninit().assume_init() };
-        db.read(page_buffer.as_mut_slice(), address)?;
+    pub fn from_file(
+        db: &RandomAccessFile,
+        address: u64,
+        page_size: u64,
+    ) -> Result<EvictablePage, Error> {
+        debug_assert_eq!(address % page_size, 0);
+        let mut page_buffer = Self::alloc_buffer(page_size)?;
+        db.read(&mut page_buffer, address)?;
 
-        Ok(Self {
+        let mut evictable_page = Self {
+            address_and_dirty_flag: address | Self::encrypted_flag(db, address),
+            page_buffer,
+        };
+        if !evictable_page.verify_checksum() {
+            return Err(Error::CorruptPage(address));
+        }
+        if let Some(cipher) = db.cipher().filter(|_| evictable_page.is_encrypted()) {
+            let payload_end = evictable_page.payload_end();
+            let info = &evictable_page.page_buffer[payload_end..payload_end + ENCRYPTION_INFO_LEN];
+            let nonce = u64::from_le_bytes(info[0..8].try_into().unwrap_or_default());
+            let key_id = u32::from_le_bytes(info[8..12].try_into().unwrap_or_default());
+            let tag: [u8; TAG_LEN] = info[12..].try_into().unwrap_or_default();
+            if nonce != 0 {
+                // The page has been written; a page failing to be authenticated is corrupt.
+                cipher
+                    .decrypt(
+                        key_id,
+                        nonce,
+                        &address.to_le_bytes(),
+                        &mut evictable_page.page_buffer[PAGE_HEADER_LEN..payload_end],
+                        &tag,
+                    )
+                    .map_err(|error| {
+                        if error == Error::CorruptDatabase {
+                            Error::CorruptPage(address)
+                        } else {
+                            error
+                        }
+                    })?;
+            }
+        }
+        Ok(evictable_page)
+    }
+
+    /// Creates an [`EvictablePage`] from a file written before page footers were introduced.
+    ///
+    /// The returned page is dirty if the page footer has to be written. It is only used to
+    /// migrate database files of version `1`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the page footer area is in use, or the page could not be read.
+    #[inline]
+    pub fn from_file_without_footer(
+        db: &RandomAccessFile,
+        address: u64,
+        page_size: u64,
+    ) -> Result<EvictablePage, Error> {
+        debug_assert_eq!(address % page_size, 0);
+        debug_assert!(db.cipher().is_none());
+        let mut page_buffer = Self::alloc_buffer(page_size)?;
+        db.read(&mut page_buffer, address)?;
+
+        let mut evictable_page = Self {
             address_and_dirty_flag: address,
             page_buffer,
+        };
+        if evictable_page.verify_checksum() {
+            // Already migrated.
+            return Ok(evictable_page);
+        }
+        let footer_offset = evictable_page.footer_offset();
+        if evictable_page.page_buffer[footer_offset..]
+            .iter()
+            .any(|b| *b != 0)
+        {
+            return Err(Error::Generic(
+                "the database file cannot be migrated: page footer area in use",
+            ));
+        }
+        evictable_page.set_dirty();
+        Ok(evictable_page)
+    }
+
+    /// Creates a new dirty [`EvictablePage`] filled with zeros.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if memory allocation failed.
+    #[inline]
+    pub fn new(
+        db: &RandomAccessFile,
+        address: u64,
+        page_size: u64,
+    ) -> Result<EvictablePage, Error> {
+        debug_assert_eq!(address % page_size, 0);
+        Ok(Self {
+            address_and_dirty_flag: address | Self::encrypted_flag(db, address) | DIRTY_FLAG,
+            page_buffer: Self::alloc_buffer(page_size)?,
         })
     }
 
     /// Returns the address of the page.
     #[inline]
     pub fn address(&self) -> u64 {
-        self.address_and_dirty_flag & (!1_u64)
+        self.address_and_dirty_flag & !(DIRTY_FLAG | ENCRYPTED_FLAG)
     }
 
     /// Returns `true` if the page is dirty.
     #[inline]
     pub fn is_dirty(&self) -> bool {
-        (self.address_and_dirty_flag & 1_u64) != 0
+        (self.address_and_dirty_flag & DIRTY_FLAG) != 0
     }
 
     /// Sets the page dirty.
     #[allow(dead_code)]
     #[inline]
     pub fn set_dirty(&mut self) {
-        self.address_and_dirty_flag |= 1_u64;
+        self.address_and_dirty_flag |= DIRTY_FLAG;
+    }
+
+    /// Returns `true` if the content of the page is encrypted in the file.
+    #[inline]
+    pub fn is_encrypted(&self) -> bool {
+        (self.address_and_dirty_flag & ENCRYPTED_FLAG) != 0
     }
 
     /// Returns the previous page address.
@@ -137,14 +282,29 @@ impl EvictablePage {
     #[allow(dead_code)]
     #[inline]
     pub fn buffer(&self) -> &[u8] {
-        &self.page_buffer[PAGE_HEADER_LEN..]
+        &self.page_buffer[PAGE_HEADER_LEN..self.payload_end()]
     }
 
     /// Gets a mutable reference to the buffer.
     #[allow(dead_code)]
     #[inline]
     pub fn buffer_mut(&mut self) -> &mut [u8] {
-        &mut self.page_buffer[PAGE_HEADER_LEN..]
+        let payload_end = self.payload_end();
+        &mut self.page_buffer[PAGE_HEADER_LEN..payload_end]
+    }
+
+    /// Sets the page clean.
+    ///
+    /// The caller must make sure that the content of the page has been written to the file.
+    #[inline]
+    pub fn set_clean(&mut self) {
+        self.address_and_dirty_flag &= !DIRTY_FLAG;
+    }
+
+    /// Returns the header and payload of the page that are written back to the file.
+    #[inline]
+    pub fn contents(&self) -> &[u8] {
+        &self.page_buffer[..self.payload_end()]
     }
 
     /// Writes back the page buffer to the file.
@@ -154,13 +314,107 @@ impl EvictablePage {
     /// Returns an error if writing back the content failed.
     #[inline]
     pub fn write_back(&mut self, db: &RandomAccessFile) -> Result<(), Error> {
-        db.write(
-            self.page_buffer.as_slice(),
-            self.address_and_dirty_flag & (!1_u64),
-        )?;
-        self.address_and_dirty_flag &= !1_u64;
+        let address = self.address();
+        self.seal(db, |image| db.write(image, address))??;
+        self.set_clean();
         Ok(())
     }
+
+    /// Passes the image of the page to be written to the file to `writer` after updating the
+    /// checksum.
+    ///
+    /// The page remains dirty; it can be set clean once the image is written to the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the page could not be encrypted.
+    #[inline]
+    pub fn seal<R, F: FnOnce(&[u8]) -> R>(
+        &mut self,
+        db: &RandomAccessFile,
+        writer: F,
+    ) -> Result<R, Error> {
+        let footer_offset = self.footer_offset();
+        if self.is_encrypted() {
+            let encryption = db.encryption().ok_or(Error::UnexpectedState)?;
+            let payload_end = self.payload_end();
+            let nonce = encryption.new_nonce()?;
+            let mut encrypted_buffer = self.page_buffer.clone();
+            let tag = encryption.cipher().encrypt(
+                nonce,
+                &self.address().to_le_bytes(),
+                &mut encrypted_buffer[PAGE_HEADER_LEN..payload_end],
+            );
+            encrypted_buffer[payload_end..payload_end + 8].copy_from_slice(&nonce.to_le_bytes());
+            encrypted_buffer[payload_end + 8..payload_end + 12]
+                .copy_from_slice(&encryption.cipher().key_id().to_le_bytes());
+            encrypted_buffer[payload_end + 12..footer_offset].copy_from_slice(&tag);
+            let checksum = crc32c(&encrypted_buffer[..footer_offset]);
+            encrypted_buffer[footer_offset..].copy_from_slice(&checksum.to_le_bytes());
+            Ok(writer(&encrypted_buffer))
+        } else {
+            let checksum = crc32c(&self.page_buffer[..footer_offset]);
+            self.page_buffer[footer_offset..].copy_from_slice(&checksum.to_le_bytes());
+            Ok(writer(&self.page_buffer))
+        }
+    }
+
+    /// Returns `true` if the checksum stored in the page footer of the page image read from the
+    /// file is valid.
+    #[inline]
+    pub fn is_intact(image: &[u8]) -> bool {
+        let Some(footer_offset) = image.len().checked_sub(PAGE_FOOTER_LEN) else {
+            return false;
+        };
+        let checksum = u32::from_le_bytes(image[footer_offset..].try_into().unwrap_or_default());
+        if checksum == crc32c(&image[..footer_offset]) {
+            return true;
+        }
+
+        // The page has never been written.
+        checksum == 0 && image.iter().all(|b| *b == 0)
+    }
+
+    /// Returns `true` if the checksum stored in the page footer is valid.
+    fn verify_checksum(&self) -> bool {
+        Self::is_intact(&self.page_buffer)
+    }
+
+    /// Returns the offset of the checksum in the page footer.
+    fn footer_offset(&self) -> usize {
+        self.page_buffer.len() - PAGE_FOOTER_LEN
+    }
+
+    /// Returns the end offset of the content of the page.
+    fn payload_end(&self) -> usize {
+        if self.is_encrypted() {
+            self.footer_offset() - ENCRYPTION_INFO_LEN
+        } else {
+            self.footer_offset()
+        }
+    }
+
+    /// Returns [`ENCRYPTED_FLAG`] if the page at the address is encrypted in the file.
+    fn encrypted_flag(db: &RandomAccessFile, address: u64) -> u64 {
+        if address != 0 && db.cipher().is_some() {
+            ENCRYPTED_FLAG
+        } else {
+            0
+        }
+    }
+
+    /// Allocates a zeroed page buffer.
+    fn alloc_buffer(page_size: u64) -> Result<AlignedBuffer, Error> {
+        let page_size = usize::try_from(page_size).map_err(|_| Error::WrongParameter)?;
+        AlignedBuffer::new(page_size)
+    }
+}
+
+/// Computes the `CRC32C` checksum of the data.
+pub(super) fn crc32c(data: &[u8]) -> u32 {
+    !data.iter().fold(!0_u32, |crc, b| {
+        CRC32C_TABLE[usize::from(crc.to_le_bytes()[0] ^ *b)] ^ (crc >> 8)
+    })
 }
 
 impl Drop for EvictablePage {
@@ -176,5 +430,28 @@ mod tests {
     use super::*;
     use static_assertions::assert_eq_size;
 
-    assert_eq_size!(EvictablePage, [u64; 65]);
+    assert_eq_size!(EvictablePage, [u64; 3]);
+
+    #[test]
+    fn checksum() {
+        assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+
+        for page_size in [MIN_PAGE_SIZE, 4096, MAX_PAGE_SIZE] {
+            let mut page = EvictablePage {
+                address_and_dirty_flag: 0,
+                page_buffer: EvictablePage::alloc_buffer(page_size).unwrap(),
+            };
+            let footer_offset = page.footer_offset();
+            assert_eq!(page.buffer().len(), footer_offset - PAGE_HEADER_LEN);
+            assert!(page.verify_checksum());
+            page.buffer_mut()[0] = 1;
+            assert!(!page.verify_checksum());
+            page.set_next_page_address(page_size);
+            let checksum = crc32c(&page.page_buffer[..footer_offset]);
+            page.page_buffer[footer_offset..].copy_from_slice(&checksum.to_le_bytes());
+            assert!(page.verify_checksum());
+            page.page_buffer[footer_offset - 1] ^= 1;
+            assert!(!page.verify_checksum());
+        }
+    }
 }
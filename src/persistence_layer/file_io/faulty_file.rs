@@ -0,0 +1,202 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fault injection for the database and log files.
+//!
+//! A [`FaultyFile`] is attached to a file through [`FileIO::set_log_faults`] or
+//! [`FileIO::set_database_faults`], and then intercepts every batch of writes that the file
+//! passes to the device. A script of [`Fault`] values keyed by write operation numbers decides
+//! whether a write fails, is torn, or crashes the device, and writes in a batch can be reordered
+//! to emulate a device that does not preserve the submission order. Tests drive a database into
+//! the faulty state, drop it, and reopen the same directory without faults to check that
+//! recovery restores exactly the acknowledged transactions.
+//!
+//! [`FileIO::set_log_faults`]: super::FileIO::set_log_faults
+//! [`FileIO::set_database_faults`]: super::FileIO::set_database_faults
+
+use crate::Error;
+use std::collections::BTreeMap;
+use std::sync::atomic::Ordering::{AcqRel, Acquire, Relaxed, Release};
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::sync::{Arc, Mutex};
+
+/// [`Fault`] is a fault injected into a write operation by [`FaultyFile`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Fault {
+    /// The write operation fails with the [`Error`] without writing anything.
+    Fail(Error),
+
+    /// Only the specified number of leading bytes are written, and then the device crashes.
+    TornWrite(usize),
+
+    /// The device crashes before the write operation.
+    Crash,
+}
+
+/// [`FaultyFile`] wraps a file of [`FileIO`](super::FileIO), and injects scripted faults into its
+/// write operations in order to deterministically test recovery.
+///
+/// Each buffer written to the file counts as a write operation, and write operations are numbered
+/// from `0` in the order of being passed to the device. Once the device crashes, every write
+/// operation is silently dropped as if the data had been lost on power failure; a database opened
+/// by the same process afterwards must not rely on acknowledged writes, and it has to be reopened
+/// without faults to check what survived the crash.
+///
+/// Handles cloned from a [`FaultyFile`] share the same script.
+///
+/// # Examples
+///
+/// ```
+/// use sap_tsf::{Fault, FaultyFile};
+///
+/// let faulty_file = FaultyFile::default();
+/// faulty_file.inject(3, Fault::TornWrite(8));
+/// assert_eq!(faulty_file.num_writes(), 0);
+/// assert!(!faulty_file.is_crashed());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct FaultyFile(Arc<FaultyFileData>);
+
+/// [`FaultyFileData`] is shared among [`FaultyFile`] handles.
+#[derive(Debug, Default)]
+struct FaultyFileData {
+    /// Faults indexed by write operation numbers.
+    faults: Mutex<BTreeMap<u64, Fault>>,
+
+    /// The number of write operations passed to the device.
+    num_writes: AtomicU64,
+
+    /// Write operations in a batch are passed to the device in the reverse order.
+    reorder: AtomicBool,
+
+    /// The device crashed.
+    crashed: AtomicBool,
+}
+
+impl FaultyFile {
+    /// Injects a [`Fault`] into the write operation of the specified number.
+    ///
+    /// The previously injected [`Fault`] for the same write operation is replaced.
+    #[inline]
+    pub fn inject(&self, write_number: u64, fault: Fault) {
+        if let Ok(mut faults) = self.0.faults.lock() {
+            faults.insert(write_number, fault);
+        }
+    }
+
+    /// Makes write operations in a batch reach the device in the reverse order.
+    ///
+    /// Write operations in a batch are not ordered until the batch is synchronized with the
+    /// device, therefore a crash in the middle of a batch may leave later writes durable while
+    /// earlier ones are lost.
+    #[inline]
+    pub fn set_reorder(&self, reorder: bool) {
+        self.0.reorder.store(reorder, Relaxed);
+    }
+
+    /// Simulates a device crash; every subsequent write operation is dropped.
+    #[inline]
+    pub fn crash(&self) {
+        self.0.crashed.store(true, Release);
+    }
+
+    /// Returns `true` if the device crashed.
+    #[inline]
+    #[must_use]
+    pub fn is_crashed(&self) -> bool {
+        self.0.crashed.load(Acquire)
+    }
+
+    /// Returns the number of write operations passed to the device, including dropped ones.
+    #[inline]
+    #[must_use]
+    pub fn num_writes(&self) -> u64 {
+        self.0.num_writes.load(Acquire)
+    }
+
+    /// Passes a batch of writes to `write` after applying injected faults.
+    pub(super) fn write_batch<F: FnOnce(&[(&[u8], u64)]) -> Result<(), Error>>(
+        &self,
+        writes: &[(&[u8], u64)],
+        write: F,
+    ) -> Result<(), Error> {
+        let mut writes = writes.to_vec();
+        if self.0.reorder.load(Relaxed) {
+            writes.reverse();
+        }
+        let mut survived = Vec::with_capacity(writes.len());
+        let mut result = Ok(());
+        for (buffer, offset) in writes {
+            let write_number = self.0.num_writes.fetch_add(1, AcqRel);
+            if self.is_crashed() {
+                continue;
+            }
+            let fault = self
+                .0
+                .faults
+                .lock()
+                .ok()
+                .and_then(|mut f| f.remove(&write_number));
+            match fault {
+                None => survived.push((buffer, offset)),
+                Some(Fault::Fail(error)) => {
+                    result = Err(error);
+                    break;
+                }
+                Some(Fault::TornWrite(len)) => {
+                    survived.push((&buffer[..len.min(buffer.len())], offset));
+                    self.crash();
+                }
+                Some(Fault::Crash) => self.crash(),
+            }
+        }
+        if !survived.is_empty() {
+            write(&survived)?;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence_layer::file_io::random_access_file::RandomAccessFile;
+    use std::fs::remove_file;
+    use std::path::Path;
+
+    #[test]
+    fn write_batch() {
+        const FILE: &str = "faulty_file_write_batch_test";
+        let random_access_file = RandomAccessFile::from_file(Path::new(FILE)).unwrap();
+        let faulty_file = FaultyFile::default();
+        assert!(random_access_file.set_faulty_file(faulty_file.clone()));
+        assert!(!random_access_file.set_faulty_file(FaultyFile::default()));
+        faulty_file.inject(1, Fault::Fail(Error::DiskFull));
+        faulty_file.inject(4, Fault::TornWrite(2));
+
+        assert!(random_access_file.write(&[1; 4], 0).is_ok());
+        assert_eq!(random_access_file.write(&[2; 4], 4), Err(Error::DiskFull));
+        assert!(random_access_file.write_error().is_none());
+
+        // The last write reaches the device first, and the first one is torn.
+        faulty_file.set_reorder(true);
+        assert!(random_access_file
+            .write_batch(&[(&[3; 4], 8), (&[4; 4], 12), (&[5; 4], 16)])
+            .is_ok());
+        assert!(faulty_file.is_crashed());
+        assert!(random_access_file.write(&[6; 4], 20).is_ok());
+        assert_eq!(faulty_file.num_writes(), 6);
+        assert_eq!(random_access_file.len(Relaxed), 20);
+
+        let mut read_buffer: [u8; 20] = [0; 20];
+        assert!(random_access_file.read(&mut read_buffer, 0).is_ok());
+        assert_eq!(
+            read_buffer,
+            [1, 1, 1, 1, 0, 0, 0, 0, 3, 3, 0, 0, 4, 4, 4, 4, 5, 5, 5, 5]
+        );
+
+        drop(random_access_file);
+        assert!(remove_file(FILE).is_ok());
+    }
+}
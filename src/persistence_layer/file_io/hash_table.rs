@@ -0,0 +1,642 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Persistent linear hash table built on top of database pages.
+
+use super::container_directory::{DirectoryRecord, IndexEntry};
+use super::evictable_page::EvictablePage;
+use super::page_manager::PageManager;
+use crate::Error;
+
+/// [`HashTable`] is a persistent linear hash table mapping byte-string keys to versions of
+/// key-value pairs in a [`Container`](crate::Container).
+///
+/// Unlike [`BTree`](super::btree::BTree), entries are not ordered, and a point lookup reads only
+/// the directory page and the pages of a single bucket. Each entry is an [`IndexEntry`], and the
+/// visibility rule is the same as that of [`BTree`](super::btree::BTree).
+///
+/// The address of the directory page never changes, and all the pages of the [`HashTable`] are
+/// linked to the directory page through the page header. Buckets are split one by one in order
+/// when the load factor exceeds [`MAX_LOAD_FACTOR_PERCENT`], and the number of buckets is bound
+/// by the capacity of the directory page; once the directory page is full, buckets grow by
+/// chaining overflow pages.
+///
+/// The layout of a page buffer is as follows.
+/// - Directory: `LEVEL 8-bit|RESERVED 24-bit|SPLIT 32-bit|SIZE 64-bit` followed by
+///   `BUCKET 64-bit` addresses of `2^LEVEL + SPLIT` buckets; `SIZE` is the total size of the
+///   encoded entries.
+/// - Bucket: `LEN 16-bit|RESERVED 48-bit|OVERFLOW 64-bit` followed by encoded [`IndexEntry`]
+///   instances; `OVERFLOW` is the address of the next page of the bucket. Values that do not fit
+///   in a bucket page are stored in [`Blob`](super::blob::Blob) instances linked to the directory
+///   page.
+///
+/// Writers must be serialized by the owner of the [`HashTable`].
+#[derive(Debug)]
+pub struct HashTable {
+    /// The directory page address.
+    directory: u64,
+
+    /// The size of the page buffer available for a directory or bucket.
+    page_len: usize,
+}
+
+/// An in-memory representation of the directory.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Directory {
+    /// The number of times the number of buckets has been doubled.
+    level: u8,
+
+    /// The next bucket to split.
+    split: u32,
+
+    /// The total size of the encoded entries.
+    size: u64,
+
+    /// The first page addresses of the buckets.
+    buckets: Vec<u64>,
+}
+
+/// An in-memory representation of a bucket page.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct Bucket {
+    /// The next page address of the bucket.
+    overflow: u64,
+
+    /// The entries.
+    entries: Vec<IndexEntry>,
+}
+
+/// The length of the directory header and the bucket header.
+const HEADER_LEN: usize = 16;
+
+/// The size of a bucket address in the directory.
+const BUCKET_ADDRESS_LEN: usize = 8;
+
+/// Buckets are split when the size of the entries exceeds this percentage of the capacity of the
+/// first pages of all the buckets.
+const MAX_LOAD_FACTOR_PERCENT: usize = 75;
+
+impl HashTable {
+    /// Creates a new empty [`HashTable`].
+    ///
+    /// The directory page of the [`HashTable`] is linked to the specified page.
+    #[inline]
+    pub async fn create(page_manager: &PageManager, prev_page_address: u64) -> Result<Self, Error> {
+        let directory = page_manager.create_page(prev_page_address).await?;
+        let hash_table = Self {
+            directory,
+            page_len: page_manager.page_payload_len(),
+        };
+        let bucket = hash_table.allocate_page(page_manager).await?;
+        hash_table
+            .write_bucket(page_manager, bucket, &Bucket::default())
+            .await?;
+        hash_table
+            .write_directory(
+                page_manager,
+                &Directory {
+                    level: 0,
+                    split: 0,
+                    size: 0,
+                    buckets: vec![bucket],
+                },
+            )
+            .await?;
+        Ok(hash_table)
+    }
+
+    /// Opens an existing [`HashTable`].
+    #[inline]
+    pub fn open(page_manager: &PageManager, directory: u64) -> Self {
+        debug_assert_eq!(directory % page_manager.page_size(), 0);
+        Self {
+            directory,
+            page_len: page_manager.page_payload_len(),
+        }
+    }
+
+    /// Returns the address of the directory page.
+    #[inline]
+    pub fn directory(&self) -> u64 {
+        self.directory
+    }
+
+    /// Inserts a new entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UniquenessViolation`] if an entry associated with the key exists, or
+    /// [`Error::WrongParameter`] if the key does not fit in a bucket page.
+    #[inline]
+    pub async fn insert(
+        &mut self,
+        page_manager: &PageManager,
+        record: DirectoryRecord,
+    ) -> Result<(), Error> {
+        let mut directory = self.read_directory(page_manager).await?;
+        let (addresses, mut entries) = self
+            .read_chain(page_manager, directory.bucket(&record.key))
+            .await?;
+        if entries.iter().any(|e| e.record.key == record.key) {
+            return Err(Error::UniquenessViolation);
+        }
+        let max_len = self.page_len - HEADER_LEN;
+        let entry = IndexEntry::new(page_manager, self.directory, record, max_len).await?;
+        directory.size += entry.encoded_len() as u64;
+        entries.push(entry);
+        self.write_chain(page_manager, addresses, &entries).await?;
+        if directory.overloaded(self.page_len) {
+            self.split(page_manager, &mut directory).await?;
+        }
+        self.write_directory(page_manager, &directory).await
+    }
+
+    /// Passes every entry to `visitor` in bucket order.
+    ///
+    /// It is a synchronous method, therefore it should be run in the background.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page could not be read or is corrupt.
+    #[inline]
+    pub fn scan_sync<F: FnMut(DirectoryRecord)>(
+        &self,
+        page_manager: &PageManager,
+        mut visitor: F,
+    ) -> Result<(), Error> {
+        let directory = page_manager
+            .read_page_sync(self.directory, |page| Directory::decode(page.buffer()))?
+            .map_err(|_| Error::CorruptPage(self.directory))?;
+        for mut address in directory.buckets {
+            while address != 0 {
+                let bucket = page_manager
+                    .read_page_sync(address, |page| Bucket::decode(page.buffer()))?
+                    .map_err(|_| Error::CorruptPage(address))?;
+                for entry in bucket.entries {
+                    visitor(entry.into_record_sync(page_manager)?);
+                }
+                address = bucket.overflow;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the pages of the buckets and the [`Blob`](super::blob::Blob) instances to free pages
+    /// nearer the front of the database file, and returns the number of pages moved.
+    ///
+    /// The directory page or bucket page referring to a moved page is written right after the
+    /// page is moved, so that no page refers to a freed page that may be reused by the next page
+    /// to move.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page could not be moved or is corrupt.
+    #[inline]
+    pub async fn compact(&self, page_manager: &PageManager) -> Result<usize, Error> {
+        let mut num_pages_moved = 0;
+        let mut directory = self.read_directory(page_manager).await?;
+        for i in 0..directory.buckets.len() {
+            let mut address = page_manager.relocate_page(directory.buckets[i]).await?;
+            if address != directory.buckets[i] {
+                directory.buckets[i] = address;
+                self.write_directory(page_manager, &directory).await?;
+                num_pages_moved += 1;
+            }
+            while address != 0 {
+                let mut bucket = Self::read_bucket(page_manager, address).await?;
+                for j in 0..bucket.entries.len() {
+                    let blob = bucket.entries[j].blob;
+                    num_pages_moved += bucket.entries[j].compact_blob(page_manager).await?;
+                    if bucket.entries[j].blob != blob {
+                        self.write_bucket(page_manager, address, &bucket).await?;
+                    }
+                }
+                if bucket.overflow != 0 {
+                    let overflow = page_manager.relocate_page(bucket.overflow).await?;
+                    if overflow != bucket.overflow {
+                        bucket.overflow = overflow;
+                        self.write_bucket(page_manager, address, &bucket).await?;
+                        num_pages_moved += 1;
+                    }
+                }
+                address = bucket.overflow;
+            }
+        }
+        Ok(num_pages_moved)
+    }
+
+    /// Frees all the pages of the [`HashTable`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page could not be freed.
+    #[inline]
+    pub async fn free(self, page_manager: &PageManager) -> Result<(), Error> {
+        loop {
+            let next = page_manager
+                .read_page(self.directory, EvictablePage::next_page_address)
+                .await?;
+            if next == 0 {
+                break;
+            }
+            Self::free_page(page_manager, next).await?;
+        }
+        Self::free_page(page_manager, self.directory).await
+    }
+
+    /// Splits the next bucket.
+    ///
+    /// The new bucket is written before the entries are removed from the old bucket, and the
+    /// directory is written last.
+    async fn split(
+        &self,
+        page_manager: &PageManager,
+        directory: &mut Directory,
+    ) -> Result<(), Error> {
+        let split = directory.split as usize;
+        let (addresses, entries) = self
+            .read_chain(page_manager, directory.buckets[split])
+            .await?;
+        let modulus = 1_u64 << (directory.level + 1);
+        let (stay, moved): (Vec<IndexEntry>, Vec<IndexEntry>) = entries
+            .into_iter()
+            .partition(|e| hash(&e.record.key) % modulus == split as u64);
+        let new_bucket = self.allocate_page(page_manager).await?;
+        self.write_chain(page_manager, vec![new_bucket], &moved)
+            .await?;
+        self.write_chain(page_manager, addresses, &stay).await?;
+        directory.buckets.push(new_bucket);
+        directory.split += 1;
+        if directory.split == 1_u32 << directory.level {
+            directory.level += 1;
+            directory.split = 0;
+        }
+        Ok(())
+    }
+
+    /// Reads all the pages of the bucket, and returns their addresses and the entries.
+    async fn read_chain(
+        &self,
+        page_manager: &PageManager,
+        mut address: u64,
+    ) -> Result<(Vec<u64>, Vec<IndexEntry>), Error> {
+        let mut addresses = Vec::new();
+        let mut entries = Vec::new();
+        while address != 0 {
+            let mut bucket = Self::read_bucket(page_manager, address).await?;
+            addresses.push(address);
+            entries.append(&mut bucket.entries);
+            address = bucket.overflow;
+        }
+        Ok((addresses, entries))
+    }
+
+    /// Writes the entries to the pages of the bucket.
+    ///
+    /// Overflow pages are allocated or freed as the size of the entries changes; the first page of
+    /// the bucket is always retained.
+    async fn write_chain(
+        &self,
+        page_manager: &PageManager,
+        mut addresses: Vec<u64>,
+        entries: &[IndexEntry],
+    ) -> Result<(), Error> {
+        let mut chunks = vec![Vec::new()];
+        let mut chunk_len = HEADER_LEN;
+        for entry in entries {
+            let entry_len = entry.encoded_len();
+            if chunk_len + entry_len > self.page_len {
+                chunks.push(Vec::new());
+                chunk_len = HEADER_LEN;
+            }
+            chunk_len += entry_len;
+            if let Some(chunk) = chunks.last_mut() {
+                chunk.push(entry.clone());
+            }
+        }
+        while addresses.len() < chunks.len() {
+            addresses.push(self.allocate_page(page_manager).await?);
+        }
+        for address in addresses.split_off(chunks.len()) {
+            Self::free_page(page_manager, address).await?;
+        }
+        for (i, (address, entries)) in addresses.iter().zip(chunks).enumerate() {
+            let bucket = Bucket {
+                overflow: addresses.get(i + 1).copied().unwrap_or(0),
+                entries,
+            };
+            self.write_bucket(page_manager, *address, &bucket).await?;
+        }
+        Ok(())
+    }
+
+    /// Allocates a new page, and links it to the directory page.
+    async fn allocate_page(&self, page_manager: &PageManager) -> Result<u64, Error> {
+        page_manager.create_linked_page(self.directory).await
+    }
+
+    /// Unlinks the page from the directory page, and returns it to the free page list.
+    async fn free_page(page_manager: &PageManager, address: u64) -> Result<(), Error> {
+        page_manager.delete_page(address).await?;
+        page_manager.request_write_back(address);
+        Ok(())
+    }
+
+    /// Reads the directory page.
+    async fn read_directory(&self, page_manager: &PageManager) -> Result<Directory, Error> {
+        page_manager
+            .read_page(self.directory, |page| Directory::decode(page.buffer()))
+            .await?
+    }
+
+    /// Writes the directory page.
+    async fn write_directory(
+        &self,
+        page_manager: &PageManager,
+        directory: &Directory,
+    ) -> Result<(), Error> {
+        page_manager
+            .write_page(self.directory, |page| {
+                directory.encode(page.buffer_mut());
+                page.set_dirty();
+            })
+            .await?;
+        page_manager.request_write_back(self.directory);
+        Ok(())
+    }
+
+    /// Reads a bucket from the page.
+    async fn read_bucket(page_manager: &PageManager, address: u64) -> Result<Bucket, Error> {
+        page_manager
+            .read_page(address, |page| Bucket::decode(page.buffer()))
+            .await?
+    }
+
+    /// Writes the bucket to the page.
+    async fn write_bucket(
+        &self,
+        page_manager: &PageManager,
+        address: u64,
+        bucket: &Bucket,
+    ) -> Result<(), Error> {
+        page_manager
+            .write_page(address, |page| {
+                bucket.encode(page.buffer_mut());
+                page.set_dirty();
+            })
+            .await?;
+        page_manager.request_write_back(address);
+        Ok(())
+    }
+}
+
+impl Directory {
+    /// Returns the first page address of the bucket that may contain the key.
+    fn bucket(&self, key: &[u8]) -> u64 {
+        let hash = hash(key);
+        let mut index = hash % (1_u64 << self.level);
+        if index < u64::from(self.split) {
+            index = hash % (1_u64 << (self.level + 1));
+        }
+        usize::try_from(index).map_or(0, |i| self.buckets[i])
+    }
+
+    /// Returns `true` if the next bucket has to be split.
+    fn overloaded(&self, page_len: usize) -> bool {
+        let max_buckets = (page_len - HEADER_LEN) / BUCKET_ADDRESS_LEN;
+        let max_size = self.buckets.len() * (page_len - HEADER_LEN) * MAX_LOAD_FACTOR_PERCENT / 100;
+        self.buckets.len() < max_buckets
+            && usize::try_from(self.size).unwrap_or(usize::MAX) > max_size
+    }
+
+    /// Decodes the directory from the buffer.
+    fn decode(buffer: &[u8]) -> Result<Self, Error> {
+        let read_u64 = |offset: usize| {
+            u64::from_le_bytes(buffer[offset..offset + 8].try_into().unwrap_or_default())
+        };
+        let level = buffer[0];
+        let split = u32::from_le_bytes(buffer[4..8].try_into().unwrap_or_default());
+        let num_buckets = 1_usize
+            .checked_shl(u32::from(level))
+            .and_then(|n| n.checked_add(split as usize))
+            .filter(|n| *n <= (buffer.len() - HEADER_LEN) / BUCKET_ADDRESS_LEN)
+            .ok_or(Error::CorruptDatabase)?;
+        Ok(Directory {
+            level,
+            split,
+            size: read_u64(8),
+            buckets: (0..num_buckets)
+                .map(|i| read_u64(HEADER_LEN + i * BUCKET_ADDRESS_LEN))
+                .collect(),
+        })
+    }
+
+    /// Encodes the directory into the buffer.
+    fn encode(&self, buffer: &mut [u8]) {
+        buffer[0] = self.level;
+        buffer[4..8].copy_from_slice(&self.split.to_le_bytes());
+        buffer[8..16].copy_from_slice(&self.size.to_le_bytes());
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            let offset = HEADER_LEN + i * BUCKET_ADDRESS_LEN;
+            buffer[offset..offset + 8].copy_from_slice(&bucket.to_le_bytes());
+        }
+    }
+}
+
+impl Bucket {
+    /// Decodes a bucket from the buffer.
+    fn decode(buffer: &[u8]) -> Result<Self, Error> {
+        let len = u16::from_le_bytes([buffer[0], buffer[1]]);
+        let overflow = u64::from_le_bytes(buffer[8..HEADER_LEN].try_into().unwrap_or_default());
+        let mut data = &buffer[HEADER_LEN..];
+        let entries = (0..len)
+            .map(|_| IndexEntry::decode(&[], &mut data))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(Error::CorruptDatabase)?;
+        Ok(Bucket { overflow, entries })
+    }
+
+    /// Encodes the bucket into the buffer.
+    fn encode(&self, buffer: &mut [u8]) {
+        #[allow(clippy::cast_possible_truncation)]
+        let len = self.entries.len() as u16;
+        buffer[0..2].copy_from_slice(&len.to_le_bytes());
+        buffer[8..HEADER_LEN].copy_from_slice(&self.overflow.to_le_bytes());
+        let mut data = Vec::new();
+        for e in &self.entries {
+            e.encode(0, &mut data);
+        }
+        buffer[HEADER_LEN..HEADER_LEN + data.len()].copy_from_slice(&data);
+    }
+}
+
+/// Returns the hash value of the key.
+///
+/// The hash value must stay the same across processes since it determines the bucket of each
+/// entry in the database file, therefore the `FNV-1a` hash of the key is finalized with a fixed
+/// `SplitMix64` finalizer.
+fn hash(key: &[u8]) -> u64 {
+    let mut h = key.iter().fold(0xCBF2_9CE4_8422_2325_u64, |h, b| {
+        (h ^ u64::from(*b)).wrapping_mul(0x0000_0100_0000_01B3)
+    });
+    h = (h ^ (h >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    h ^ (h >> 31)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::evictable_page::{PAGE_FOOTER_LEN, PAGE_HEADER_LEN};
+    use super::*;
+    use crate::{FileIO, MonotonicU64};
+    use std::path::Path;
+    use tokio::fs::remove_dir_all;
+
+    /// Returns an entry of which the key is derived from the number.
+    fn entry(i: u64) -> DirectoryRecord {
+        DirectoryRecord {
+            record_id: i,
+            object_id: i + 1,
+            key: i.to_string().into_bytes().into(),
+            value: vec![0xCD; usize::try_from(i % 24).unwrap()].into(),
+        }
+    }
+
+    /// Returns an entry of which the value is stored in a blob if the number is a multiple of 97.
+    fn large_entry(i: u64) -> DirectoryRecord {
+        let mut entry = entry(i);
+        if i.is_multiple_of(97) {
+            entry.value = vec![0xEF; 5000].into();
+        }
+        entry
+    }
+
+    #[test]
+    fn page_encode_decode() {
+        const PAGE_LEN: usize = 512 - PAGE_HEADER_LEN - PAGE_FOOTER_LEN;
+        let mut buffer = [0_u8; PAGE_LEN];
+        let mut entries: Vec<IndexEntry> = (0..12)
+            .map(|i| IndexEntry {
+                record: entry(i),
+                blob: None,
+            })
+            .collect();
+        entries[5].record.value = Box::default();
+        entries[5].blob = Some(512 * 3);
+        let bucket = Bucket {
+            overflow: 512 * 7,
+            entries,
+        };
+        bucket.encode(&mut buffer);
+        assert_eq!(Bucket::decode(&buffer), Ok(bucket));
+        buffer[0] = 0xFF;
+        assert_eq!(Bucket::decode(&buffer), Err(Error::CorruptDatabase));
+        let directory = Directory {
+            level: 4,
+            split: 3,
+            size: 11,
+            buckets: (0..19).map(|i| 512 * i).collect(),
+        };
+        directory.encode(&mut buffer);
+        assert_eq!(Directory::decode(&buffer), Ok(directory));
+        buffer[0] = 8;
+        assert_eq!(Directory::decode(&buffer), Err(Error::CorruptDatabase));
+    }
+
+    #[tokio::test]
+    async fn insert() {
+        for page_size in [512, 4096] {
+            insert_with_page_size(page_size).await;
+        }
+    }
+
+    async fn insert_with_page_size(page_size: u64) {
+        const NUM_KEYS: u64 = 4096;
+        let dir = format!("hash_table_insert_test_{page_size}");
+        let path = Path::new(&dir);
+        let file_io = FileIO::<MonotonicU64>::with_page_size(path, page_size).unwrap();
+        let page_manager = file_io.page_manager();
+        let mut hash_table = HashTable::create(page_manager, page_manager.page_size())
+            .await
+            .unwrap();
+        for i in 0..NUM_KEYS {
+            assert!(hash_table
+                .insert(page_manager, large_entry(i))
+                .await
+                .is_ok());
+        }
+        assert_eq!(
+            hash_table.insert(page_manager, entry(3)).await,
+            Err(Error::UniquenessViolation)
+        );
+        let mut oversized = entry(NUM_KEYS);
+        oversized.key = vec![0; page_manager.page_payload_len()].into();
+        assert_eq!(
+            hash_table.insert(page_manager, oversized).await,
+            Err(Error::WrongParameter)
+        );
+        let directory = hash_table.read_directory(page_manager).await.unwrap();
+        assert!(directory.buckets.len() > 1);
+        let mut scanned = Vec::new();
+        assert!(hash_table
+            .scan_sync(page_manager, |e| scanned.push(e))
+            .is_ok());
+        scanned.sort_by_key(|e| e.record_id);
+        assert_eq!(scanned, (0..NUM_KEYS).map(large_entry).collect::<Vec<_>>());
+        let max_len = page_manager.page_payload_len() - HEADER_LEN;
+        let expected_size = scanned
+            .iter()
+            .map(|e| {
+                let len = IndexEntry::inline_len(&e.key, &e.value);
+                if len <= max_len {
+                    len as u64
+                } else {
+                    IndexEntry::inline_len(&e.key, &[0; 8]) as u64
+                }
+            })
+            .sum();
+        assert_eq!(directory.size, expected_size);
+
+        let num_free_pages = page_manager.verify().await.free_pages;
+        assert!(hash_table.free(page_manager).await.is_ok());
+        let report = page_manager.verify().await;
+        assert!(report.problems.is_empty(), "{report:?}");
+        assert!(report.free_pages > num_free_pages);
+        drop(file_io);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn reopen() {
+        const DIR: &str = "hash_table_reopen_test";
+        const NUM_KEYS: u64 = 256;
+        let path = Path::new(DIR);
+        let file_io = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        let page_manager = file_io.page_manager();
+        let mut hash_table =
+            HashTable::create(page_manager, page_manager.container_directory_head())
+                .await
+                .unwrap();
+        for i in 0..NUM_KEYS {
+            assert!(hash_table
+                .insert(page_manager, large_entry(i))
+                .await
+                .is_ok());
+        }
+        let directory = hash_table.directory();
+        drop(file_io);
+
+        let file_io_recovered = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        let page_manager = file_io_recovered.page_manager();
+        let hash_table = HashTable::open(page_manager, directory);
+        let mut scanned = Vec::new();
+        assert!(hash_table
+            .scan_sync(page_manager, |e| scanned.push(e))
+            .is_ok());
+        scanned.sort_by_key(|e| e.record_id);
+        assert_eq!(scanned, (0..NUM_KEYS).map(large_entry).collect::<Vec<_>>());
+        drop(file_io_recovered);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+}
@@ -0,0 +1,94 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Integrity verification of database files.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// [`IntegrityReport`] is the result of verifying the database file of a
+/// [`FileIO`](super::FileIO).
+///
+/// Pages are verified without stopping database workers, therefore pages being allocated, freed,
+/// or linked while the database file is verified may be falsely reported.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct IntegrityReport {
+    /// The number of pages verified including the header page.
+    pub pages_checked: u64,
+
+    /// The number of free pages.
+    pub free_pages: u64,
+
+    /// The logical clock persisted in the header page.
+    pub clock: u64,
+
+    /// The problems found in the ascending page address order.
+    pub problems: Vec<IntegrityProblem>,
+}
+
+/// Problems found by verifying database files.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum IntegrityProblem {
+    /// The checksum of the page does not match the content, or the page could not be read.
+    CorruptPage(u64),
+
+    /// The page header of the page contains an address that is not aligned to the page size or
+    /// beyond the end of the database file.
+    InvalidLink {
+        /// The address of the page.
+        page: u64,
+
+        /// The invalid address.
+        link: u64,
+    },
+
+    /// The next page of the page does not link back to the page.
+    BrokenLink {
+        /// The address of the page.
+        page: u64,
+
+        /// The address of the next page.
+        next: u64,
+
+        /// The address of the previous page of the next page.
+        prev_of_next: u64,
+    },
+
+    /// The previous page of the page is a free page.
+    DanglingLink {
+        /// The address of the page.
+        page: u64,
+
+        /// The address of the free page.
+        prev: u64,
+    },
+
+    /// The page is free, but it is not in the free page list; the page is returned to the free
+    /// page list when the database is reopened.
+    UnlistedFreePage(u64),
+
+    /// The page is in the free page list, but it is in use.
+    AllocatedFreePage(u64),
+
+    /// The logical clock persisted in the database file is ahead of the clock of the
+    /// [`Database`](crate::Database).
+    ClockAhead {
+        /// The logical clock persisted in the database file.
+        persisted: u64,
+
+        /// The current clock of the [`Database`](crate::Database).
+        current: u64,
+    },
+}
+
+impl IntegrityReport {
+    /// Returns `true` if no problems were found.
+    #[inline]
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
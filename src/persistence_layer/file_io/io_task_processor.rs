@@ -4,16 +4,19 @@
 
 //! IO task processor.
 
+use super::backup::BackupTarget;
 use super::evictable_page::EvictablePage;
 use super::log_record::LogRecord;
 use super::recovery::recover_database;
 use super::LogBufferInterface;
 use super::{FileIOData, FileLogBuffer, Sequencer};
+use crate::{Counter, Error};
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
-use std::sync::mpsc::Receiver;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
 use std::sync::Arc;
 use std::thread::yield_now;
+use std::time::{Duration, Instant};
 
 /// Types of file IO related tasks.
 #[derive(Debug)]
@@ -33,23 +36,64 @@ pub enum IOTask {
     /// Writes back the evicted page.
     WriteBackEvicted(Box<EvictablePage>),
 
+    /// Truncates trailing free pages of the database file.
+    Defragment,
+
+    /// Backs up the database to the target, and marks the log buffer durable.
+    Backup(Box<BackupTarget>, Arc<FileLogBuffer>),
+
     /// Recovers the database.
     Recover,
 
+    /// Flushes any pending log buffers, writes back dirty pages, writes the payload of the
+    /// container directory head page, reclaims the log space before the offset, and marks the log
+    /// buffer durable.
+    Checkpoint(Vec<u8>, u64, Arc<FileLogBuffer>),
+
+    /// Flushes any pending log buffers, writes back dirty pages, marks the database file cleanly
+    /// shut down at the clock, and marks the log buffer durable.
+    CleanShutdown(u64, Arc<FileLogBuffer>),
+
     /// Shuts down the IO task processor.
     Shutdown,
 }
 
+/// The interval of running the background flusher of dirty pages.
+const FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+
 /// Processes file IO tasks.
 ///
-/// Synchronous calls are made in the function, therefore database workers must not invoke it.
+/// Dirty pages are written back by the background flusher every [`FLUSH_INTERVAL`] between tasks
+/// if the ratio of dirty pages exceeds the threshold, and pending log buffers are flushed at the
+/// same interval. Synchronous calls are made in the function,
+/// therefore database workers must not invoke it.
 pub(super) fn process_sync<S: Sequencer<Instant = u64>>(
     receiver: &mut Receiver<IOTask>,
     file_io_data: &Arc<FileIOData<S>>,
 ) {
     let mut log_offset = file_io_data.log.len(Acquire);
+    let mut log_reclaimed = 0;
+    let mut next_flush = Instant::now() + FLUSH_INTERVAL;
 
-    while let Ok(task) = receiver.recv() {
+    loop {
+        let task = match receiver.recv_timeout(next_flush.saturating_duration_since(Instant::now()))
+        {
+            Ok(task) => Some(task),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        if Instant::now() >= next_flush {
+            // Flush requests are dropped while the task queue is full, therefore pending log
+            // buffers are periodically flushed as well.
+            process_log_buffer_batch(file_io_data, &mut log_offset);
+            if let Some(threshold) = file_io_data.page_manager.dirty_page_threshold() {
+                file_io_data.page_manager.flush_dirty_pages_sync(threshold);
+            }
+            next_flush = Instant::now() + FLUSH_INTERVAL;
+        }
+        let Some(task) = task else {
+            continue;
+        };
         match task {
             IOTask::Flush => {
                 process_log_buffer_batch(file_io_data, &mut log_offset);
@@ -68,18 +112,93 @@ pub(super) fn process_sync<S: Sequencer<Instant = u64>>(
                     .page_manager
                     .write_back_evicted_sync(&mut evictable_page);
             }
+            IOTask::Defragment => {
+                file_io_data.page_manager.defragment_sync();
+            }
+            IOTask::Backup(target, log_buffer) => {
+                process_log_buffer_batch(file_io_data, &mut log_offset);
+                let result = target.backup_sync(file_io_data, log_offset);
+                mark_completed(file_io_data, &log_buffer, result);
+            }
             IOTask::Recover => {
                 recover_database(file_io_data);
                 log_offset = file_io_data.log.len(Relaxed);
             }
+            IOTask::Checkpoint(payload, log_start, log_buffer) => {
+                process_log_buffer_batch(file_io_data, &mut log_offset);
+                let result = file_io_data.log.sync().and_then(|()| {
+                    file_io_data
+                        .page_manager
+                        .write_container_directory_sync(&payload)
+                });
+                if result.is_ok() {
+                    reclaim_log_sync(file_io_data, &mut log_reclaimed, log_start, log_offset);
+                }
+                mark_completed(file_io_data, &log_buffer, result);
+            }
+            IOTask::CleanShutdown(clock, log_buffer) => {
+                process_log_buffer_batch(file_io_data, &mut log_offset);
+                drop(file_io_data.log.sync());
+                file_io_data.page_manager.mark_clean_shutdown_sync(clock);
+                mark_completed(file_io_data, &log_buffer, Ok(()));
+            }
             IOTask::Shutdown => {
                 process_log_buffer_batch(file_io_data, &mut log_offset);
+                drop(file_io_data.log.sync());
                 break;
             }
         }
     }
 }
 
+/// Marks the log buffer of a background IO operation durable by advancing the flush epoch.
+///
+/// The error that the operation failed with is returned by the pending
+/// [`AwaitIO`](crate::AwaitIO) of the log buffer.
+fn mark_completed<S: Sequencer<Instant = u64>>(
+    file_io_data: &Arc<FileIOData<S>>,
+    log_buffer: &FileLogBuffer,
+    result: Result<(), Error>,
+) {
+    let durable_flush_epoch = file_io_data.flush_epoch.load(Relaxed) + 1;
+    if let Err(error) = result {
+        drop(file_io_data.io_errors.insert(durable_flush_epoch, error));
+    }
+    log_buffer.set_durable_flush_epoch(durable_flush_epoch);
+    file_io_data.flush_epoch.store(durable_flush_epoch, Release);
+    file_io_data.waker_bag.pop_all((), |(), w| w.wake());
+}
+
+/// Reclaims the log space before the offset from which log records are replayed over the
+/// persisted checkpoint.
+///
+/// Log records within the log retention before the end of the log, and those following the latest
+/// backup are retained. `log_reclaimed` is the offset up to which log space has been reclaimed
+/// since the database was opened.
+fn reclaim_log_sync<S: Sequencer<Instant = u64>>(
+    file_io_data: &Arc<FileIOData<S>>,
+    log_reclaimed: &mut u64,
+    log_start: u64,
+    log_offset: u64,
+) {
+    let retention = file_io_data.log_retention.load(Relaxed);
+    let end = log_start.min(log_offset.saturating_sub(retention)).min(
+        file_io_data
+            .page_manager
+            .latest_backup_clock()
+            .unwrap_or(u64::MAX),
+    );
+    if end <= *log_reclaimed {
+        return;
+    }
+    file_io_data.log_head.fetch_max(end, Release);
+    if file_io_data.log.discard(*log_reclaimed, end).is_ok() {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(start = *log_reclaimed, end, "log space reclaimed");
+        *log_reclaimed = end;
+    }
+}
+
 /// Processes a batch of log buffers.
 fn process_log_buffer_batch<S: Sequencer<Instant = u64>>(
     file_io_data: &Arc<FileIOData<S>>,
@@ -89,18 +208,35 @@ fn process_log_buffer_batch<S: Sequencer<Instant = u64>>(
     if let Some(mut log_buffer) =
         take_log_buffer_link(&file_io_data.log_buffer_link, durable_flush_epoch)
     {
-        loop {
-            if file_io_data
-                .log
-                .write(&log_buffer.buffer[0..log_buffer.pos()], *log_offset)
-                .is_err()
-            {
-                yield_now();
-                continue;
+        if file_io_data.page_manager.is_read_only() {
+            // Log buffers of a read-only database only mark the ends of journals and
+            // transactions that changed nothing, therefore they are discarded as durable.
+            while let Some(next_log_buffer) = log_buffer.take_next() {
+                log_buffer = next_log_buffer;
             }
-            *log_offset += log_buffer.pos() as u64;
+            file_io_data.flush_epoch.store(durable_flush_epoch, Release);
+            file_io_data.waker_bag.pop_all((), |(), w| w.wake());
+            return;
+        }
 
-            if log_buffer.eoj_logging.load(Relaxed) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("flush", flush_epoch = durable_flush_epoch).entered();
+
+        // Log buffers are written in a single batch, so that the log file is synchronized with
+        // the device only once if the IO backend supports it.
+        let mut log_buffers = Vec::new();
+        loop {
+            let next_log_buffer = log_buffer.take_next();
+            log_buffers.push(log_buffer);
+            if let Some(next_log_buffer) = next_log_buffer {
+                log_buffer = next_log_buffer;
+            } else {
+                break;
+            }
+        }
+        let eoj_buffers: Vec<[u8; 8]> = log_buffers
+            .iter()
+            .map(|log_buffer| {
                 let mut eoj_buffer = [0_u8; 8];
                 if log_buffer.submit_instant.load(Relaxed) == 0 {
                     let discard_log_record = LogRecord::<S>::BufferDiscarded;
@@ -110,20 +246,86 @@ fn process_log_buffer_batch<S: Sequencer<Instant = u64>>(
                         LogRecord::<S>::BufferSubmitted(log_buffer.submit_instant.load(Relaxed));
                     submit_log_record.write(&mut eoj_buffer);
                 }
-                while file_io_data.log.write(&eoj_buffer, *log_offset).is_err() {
+                eoj_buffer
+            })
+            .collect();
+        // Log records written after a clean shutdown invalidate the marker.
+        file_io_data.page_manager.clear_clean_shutdown_sync();
+        if file_io_data.write_error().is_some() {
+            // The log buffers are failed, and the database has to be reopened.
+            file_io_data.waker_bag.pop_all((), |(), w| w.wake());
+            return;
+        }
+        let start_offset = *log_offset;
+        let mut writes = Vec::with_capacity(log_buffers.len() * 2);
+        for (log_buffer, eoj_buffer) in log_buffers.iter().zip(eoj_buffers.iter()) {
+            writes.push((&log_buffer.buffer[0..log_buffer.pos()], *log_offset));
+            *log_offset += log_buffer.pos() as u64;
+            if log_buffer.eoj_logging.load(Relaxed) {
+                writes.push((&eoj_buffer[..], *log_offset));
+                *log_offset += eoj_buffer.len() as u64;
+            }
+        }
+        let bytes_written = loop {
+            match file_io_data.log.write_encrypted_batch(&writes) {
+                Ok(bytes_written) => break bytes_written,
+                Err(error) => {
+                    file_io_data.handle_write_error(&error);
+                    if file_io_data.write_error().is_some() {
+                        // Whether the log records reached the device is unknown.
+                        file_io_data.waker_bag.pop_all((), |(), w| w.wake());
+                        return;
+                    }
                     yield_now();
                 }
-                *log_offset += eoj_buffer.len() as u64;
             }
+        };
+        // Encrypted log records are written in a frame that is larger than the log records.
+        *log_offset = start_offset + bytes_written;
+        file_io_data.telemetry.add(Counter::Fsyncs, 1);
+        file_io_data
+            .telemetry
+            .add(Counter::LogBytesWritten, *log_offset - start_offset);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(bytes = *log_offset - start_offset, "log flushed");
+        file_io_data.flush_epoch.store(durable_flush_epoch, Release);
+        file_io_data.waker_bag.pop_all((), |(), w| w.wake());
+        archive_sealed_log_segments(file_io_data, start_offset, *log_offset);
+    }
+}
 
-            if let Some(next_log_buffer) = log_buffer.take_next() {
-                log_buffer = next_log_buffer;
-            } else {
-                break;
-            }
+/// Notifies the [`LogArchiver`](super::LogArchiver) of log segments sealed by writing log
+/// records in `[start_offset, end_offset)`.
+fn archive_sealed_log_segments<S: Sequencer<Instant = u64>>(
+    file_io_data: &Arc<FileIOData<S>>,
+    start_offset: u64,
+    end_offset: u64,
+) {
+    let segment_size = file_io_data.page_manager.segment_size();
+    if segment_size == 0 {
+        return;
+    }
+    let num_sealed_segments = |log_len: u64| log_len.saturating_sub(1) / segment_size;
+    let sealed_segments = num_sealed_segments(start_offset)..num_sealed_segments(end_offset);
+    if sealed_segments.is_empty() {
+        return;
+    }
+    let Some(log_archiver) = file_io_data
+        .log_archiver
+        .lock()
+        .ok()
+        .and_then(|guard| guard.clone())
+    else {
+        return;
+    };
+    let segment_paths = file_io_data.log.segment_paths();
+    for segment_index in sealed_segments {
+        if let Some(segment_path) = usize::try_from(segment_index)
+            .ok()
+            .and_then(|i| segment_paths.get(i))
+        {
+            log_archiver.archive(segment_index, segment_path);
         }
-        file_io_data.flush_epoch.store(durable_flush_epoch, Release);
-        file_io_data.waker_bag.pop_all((), |_, w| w.wake());
     }
 }
 
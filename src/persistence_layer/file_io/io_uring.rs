@@ -0,0 +1,562 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Minimal `io_uring` interface for batched file IO on Linux.
+
+use crate::Error;
+use libc::{c_long, c_void, MAP_FAILED, MAP_POPULATE, MAP_SHARED, PROT_READ, PROT_WRITE};
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::ptr::{self, null_mut};
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use std::thread::yield_now;
+
+/// [`IOUring`] owns an `io_uring` instance, and performs a batch of file operations at once.
+#[derive(Debug)]
+pub struct IOUring {
+    /// The file descriptor of the `io_uring` instance.
+    fd: OwnedFd,
+
+    /// The submission queue ring.
+    sq_ring: Mapping,
+
+    /// The completion queue ring.
+    cq_ring: Mapping,
+
+    /// The submission queue entries.
+    sqes: Mapping,
+
+    /// The parameters returned by the kernel.
+    params: Params,
+}
+
+/// [`Operation`] is a file operation submitted to an [`IOUring`].
+#[derive(Debug)]
+pub enum Operation<'b> {
+    /// Reads data at the offset into the buffer.
+    Read(&'b mut [u8], u64),
+
+    /// Writes the buffer at the offset.
+    Write(&'b [u8], u64),
+}
+
+/// A memory-mapped region.
+///
+/// The kernel aligns every field and array in the region to its type.
+#[derive(Debug)]
+struct Mapping {
+    /// The address of the region.
+    ptr: *mut u8,
+
+    /// The length of the region.
+    len: usize,
+}
+
+/// `struct io_uring_params`.
+#[derive(Debug, Default)]
+#[repr(C)]
+struct Params {
+    sq_entries: u32,
+    cq_entries: u32,
+    flags: u32,
+    sq_thread_cpu: u32,
+    sq_thread_idle: u32,
+    features: u32,
+    wq_fd: u32,
+    resv: [u32; 3],
+    sq_off: SubmissionQueueOffsets,
+    cq_off: CompletionQueueOffsets,
+}
+
+/// `struct io_sqring_offsets`.
+#[derive(Debug, Default)]
+#[repr(C)]
+struct SubmissionQueueOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    flags: u32,
+    dropped: u32,
+    array: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+/// `struct io_cqring_offsets`.
+#[derive(Debug, Default)]
+#[repr(C)]
+struct CompletionQueueOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    overflow: u32,
+    cqes: u32,
+    flags: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+/// `struct io_uring_sqe`.
+#[derive(Debug, Default)]
+#[repr(C)]
+struct SubmissionQueueEntry {
+    opcode: u8,
+    flags: u8,
+    ioprio: u16,
+    fd: i32,
+    off: u64,
+    addr: u64,
+    len: u32,
+    rw_flags: u32,
+    user_data: u64,
+    buf_index: u16,
+    personality: u16,
+    splice_fd_in: i32,
+    addr3: u64,
+    pad: u64,
+}
+
+/// `struct io_uring_cqe`.
+#[derive(Debug, Default)]
+#[repr(C)]
+struct CompletionQueueEntry {
+    user_data: u64,
+    res: i32,
+    flags: u32,
+}
+
+/// The number of submission queue entries.
+const NUM_ENTRIES: u32 = 64;
+
+/// `IORING_OP_FSYNC`.
+const OP_FSYNC: u8 = 3;
+
+/// `IORING_OP_READ`.
+const OP_READ: u8 = 22;
+
+/// `IORING_OP_WRITE`.
+const OP_WRITE: u8 = 23;
+
+/// `IOSQE_IO_DRAIN`.
+const SQE_IO_DRAIN: u8 = 1 << 1;
+
+/// `IORING_ENTER_GETEVENTS`.
+const ENTER_GETEVENTS: u32 = 1;
+
+/// `IORING_OFF_SQ_RING`.
+const OFF_SQ_RING: i64 = 0;
+
+/// `IORING_OFF_CQ_RING`.
+const OFF_CQ_RING: i64 = 0x800_0000;
+
+/// `IORING_OFF_SQES`.
+const OFF_SQES: i64 = 0x1000_0000;
+
+impl IOUring {
+    /// Creates a new [`IOUring`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the kernel does not support `io_uring`.
+    pub fn new() -> Result<IOUring, Error> {
+        let mut params = Params::default();
+        // Safety: `params` is a valid `struct io_uring_params`.
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_io_uring_setup,
+                c_long::from(NUM_ENTRIES),
+                ptr::addr_of_mut!(params),
+            )
+        };
+        let fd = RawFd::try_from(fd).map_err(|_| last_error())?;
+        if fd < 0 {
+            return Err(last_error());
+        }
+        // Safety: the file descriptor was just created, and is not owned by anything else.
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        let sq_ring_len = params.sq_off.array as usize
+            + params.sq_entries as usize * size_of::<u32>();
+        let cq_ring_len = params.cq_off.cqes as usize
+            + params.cq_entries as usize * size_of::<CompletionQueueEntry>();
+        let sqes_len = params.sq_entries as usize * size_of::<SubmissionQueueEntry>();
+        let sq_ring = Mapping::new(fd.as_raw_fd(), sq_ring_len, OFF_SQ_RING)?;
+        let cq_ring = Mapping::new(fd.as_raw_fd(), cq_ring_len, OFF_CQ_RING)?;
+        let sqes = Mapping::new(fd.as_raw_fd(), sqes_len, OFF_SQES)?;
+        Ok(IOUring {
+            fd,
+            sq_ring,
+            cq_ring,
+            sqes,
+            params,
+        })
+    }
+
+    /// Submits the operations, and waits for all of them to be completed.
+    ///
+    /// If `sync` is `true`, the file is synchronized with the device after the operations have
+    /// been completed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the operations failed.
+    pub fn submit_and_wait(
+        &mut self,
+        fd: RawFd,
+        operations: &mut [Operation],
+        sync: bool,
+    ) -> Result<(), Error> {
+        // Lengths are validated before anything is pushed, otherwise entries pushed before an
+        // invalid one would be left in the submission queue.
+        if operations
+            .iter()
+            .any(|operation| u32::try_from(operation.len()).is_err())
+        {
+            return Err(Error::WrongParameter);
+        }
+        let batch_size = self.params.sq_entries as usize - 1;
+        let mut result = Ok(());
+        let mut chunks = operations.chunks_mut(batch_size).peekable();
+        while let Some(chunk) = chunks.next() {
+            let mut num_entries = 0;
+            for operation in chunk.iter_mut() {
+                let mut sqe = SubmissionQueueEntry {
+                    fd,
+                    ..SubmissionQueueEntry::default()
+                };
+                match operation {
+                    Operation::Read(buffer, offset) => {
+                        sqe.opcode = OP_READ;
+                        sqe.addr = buffer.as_mut_ptr() as u64;
+                        sqe.off = *offset;
+                    }
+                    Operation::Write(buffer, offset) => {
+                        sqe.opcode = OP_WRITE;
+                        sqe.addr = buffer.as_ptr() as u64;
+                        sqe.off = *offset;
+                    }
+                }
+                {
+                    #![allow(clippy::cast_possible_truncation)]
+                    sqe.len = operation.len() as u32;
+                }
+                sqe.user_data = u64::from(sqe.len);
+                self.push(sqe);
+                num_entries += 1;
+            }
+            if sync && chunks.peek().is_none() {
+                // `IOSQE_IO_DRAIN` makes the kernel start the operation after the preceding ones.
+                self.push(SubmissionQueueEntry {
+                    opcode: OP_FSYNC,
+                    flags: SQE_IO_DRAIN,
+                    fd,
+                    user_data: 0,
+                    ..SubmissionQueueEntry::default()
+                });
+                num_entries += 1;
+            }
+            self.enter(num_entries)?;
+            for _ in 0..num_entries {
+                let cqe = self.pop();
+                let expected = usize::try_from(cqe.user_data).unwrap_or(usize::MAX);
+                if cqe.res < 0 {
+                    result = Err(Error::IO(io::Error::from_raw_os_error(-cqe.res).kind()));
+                } else if usize::try_from(cqe.res).unwrap_or(0) != expected {
+                    // Short reads and writes are reported as failures to be retried.
+                    result = Err(Error::IO(io::ErrorKind::UnexpectedEof));
+                }
+            }
+        }
+        result
+    }
+
+    /// Pushes a submission queue entry.
+    ///
+    /// The submission queue must have a vacant slot.
+    #[allow(clippy::cast_ptr_alignment)]
+    fn push(&mut self, sqe: SubmissionQueueEntry) {
+        let tail = self.sq_atomic(self.params.sq_off.tail).load(Relaxed);
+        let index = tail & self.sq_value(self.params.sq_off.ring_mask);
+        // Safety: `index` is within the submission queue entry array, and the kernel does not
+        // read the entry until the tail is updated.
+        unsafe {
+            self.sqes
+                .ptr
+                .cast::<SubmissionQueueEntry>()
+                .add(index as usize)
+                .write(sqe);
+            self.sq_ring
+                .ptr
+                .add(self.params.sq_off.array as usize)
+                .cast::<u32>()
+                .add(index as usize)
+                .write(index);
+        }
+        self.sq_atomic(self.params.sq_off.tail)
+            .store(tail.wrapping_add(1), Release);
+    }
+
+    /// Pops a completion queue entry.
+    ///
+    /// The completion queue must have an entry.
+    #[allow(clippy::cast_ptr_alignment)]
+    fn pop(&mut self) -> CompletionQueueEntry {
+        let head = self.cq_atomic(self.params.cq_off.head).load(Relaxed);
+        debug_assert_ne!(head, self.cq_atomic(self.params.cq_off.tail).load(Acquire));
+        let index = head & self.cq_value(self.params.cq_off.ring_mask);
+        // Safety: `index` is within the completion queue entry array, and the kernel does not
+        // overwrite the entry until the head is updated.
+        let cqe = unsafe {
+            self.cq_ring
+                .ptr
+                .add(self.params.cq_off.cqes as usize)
+                .cast::<CompletionQueueEntry>()
+                .add(index as usize)
+                .read()
+        };
+        self.cq_atomic(self.params.cq_off.head)
+            .store(head.wrapping_add(1), Release);
+        cqe
+    }
+
+    /// Submits the pushed entries, and waits for the same number of completion queue entries.
+    ///
+    /// If submitting or waiting fails, the operations are abandoned before the error is returned.
+    fn enter(&mut self, num_entries: u32) -> Result<(), Error> {
+        let sq_head = self.sq_atomic(self.params.sq_off.head).load(Acquire);
+        let mut to_submit = num_entries;
+        loop {
+            let completed = self.num_completed();
+            if to_submit == 0 && completed >= num_entries {
+                return Ok(());
+            }
+            match self.io_uring_enter(to_submit, num_entries - completed) {
+                Ok(submitted) => to_submit -= submitted.min(to_submit),
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => (),
+                Err(error) => {
+                    self.abandon(sq_head);
+                    return Err(Error::IO(error.kind()));
+                }
+            }
+        }
+    }
+
+    /// Removes the entries that the kernel has yet to consume from the submission queue, and
+    /// discards the completion queue entries of the consumed ones.
+    ///
+    /// The consumed entries refer to buffers of the caller, therefore their completion is awaited
+    /// even if waiting fails.
+    fn abandon(&mut self, sq_head: u32) {
+        let consumed_sq_head = self.sq_atomic(self.params.sq_off.head).load(Acquire);
+        self.sq_atomic(self.params.sq_off.tail)
+            .store(consumed_sq_head, Release);
+        let num_consumed = consumed_sq_head.wrapping_sub(sq_head);
+        while self.num_completed() < num_consumed {
+            if self
+                .io_uring_enter(0, num_consumed - self.num_completed())
+                .is_err()
+            {
+                yield_now();
+            }
+        }
+        for _ in 0..num_consumed {
+            self.pop();
+        }
+    }
+
+    /// Calls `io_uring_enter`, and returns the number of consumed submission queue entries.
+    fn io_uring_enter(&self, to_submit: u32, min_complete: u32) -> io::Result<u32> {
+        // Safety: the arguments are valid for `io_uring_enter`.
+        let result = unsafe {
+            libc::syscall(
+                libc::SYS_io_uring_enter,
+                self.fd.as_raw_fd(),
+                to_submit,
+                min_complete,
+                ENTER_GETEVENTS,
+                null_mut::<c_void>(),
+                0,
+            )
+        };
+        if result < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(u32::try_from(result).unwrap_or(to_submit))
+        }
+    }
+
+    /// Returns the number of completion queue entries that have yet to be popped.
+    fn num_completed(&self) -> u32 {
+        self.cq_atomic(self.params.cq_off.tail)
+            .load(Acquire)
+            .wrapping_sub(self.cq_atomic(self.params.cq_off.head).load(Relaxed))
+    }
+
+    /// Returns an atomic reference to a field of the submission queue ring.
+    #[allow(clippy::cast_ptr_alignment)]
+    fn sq_atomic(&self, offset: u32) -> &AtomicU32 {
+        // Safety: the field is a `u32` shared with the kernel within the mapped region.
+        unsafe { &*self.sq_ring.ptr.add(offset as usize).cast::<AtomicU32>() }
+    }
+
+    /// Returns the value of a field of the submission queue ring that is never modified.
+    fn sq_value(&self, offset: u32) -> u32 {
+        self.sq_atomic(offset).load(Relaxed)
+    }
+
+    /// Returns an atomic reference to a field of the completion queue ring.
+    #[allow(clippy::cast_ptr_alignment)]
+    fn cq_atomic(&self, offset: u32) -> &AtomicU32 {
+        // Safety: the field is a `u32` shared with the kernel within the mapped region.
+        unsafe { &*self.cq_ring.ptr.add(offset as usize).cast::<AtomicU32>() }
+    }
+
+    /// Returns the value of a field of the completion queue ring that is never modified.
+    fn cq_value(&self, offset: u32) -> u32 {
+        self.cq_atomic(offset).load(Relaxed)
+    }
+}
+
+// Safety: the memory-mapped regions are only accessed through `&mut IOUring` except for reading
+// fields that the kernel never modifies or atomically updates.
+unsafe impl Send for IOUring {}
+
+impl Operation<'_> {
+    /// Returns the length of the buffer.
+    fn len(&self) -> usize {
+        match self {
+            Operation::Read(buffer, _) => buffer.len(),
+            Operation::Write(buffer, _) => buffer.len(),
+        }
+    }
+}
+
+impl Mapping {
+    /// Maps a region of the `io_uring` instance.
+    fn new(fd: RawFd, len: usize, offset: i64) -> Result<Mapping, Error> {
+        // Safety: the arguments are valid for `mmap`.
+        let ptr = unsafe {
+            libc::mmap(
+                null_mut(),
+                len,
+                PROT_READ | PROT_WRITE,
+                MAP_SHARED | MAP_POPULATE,
+                fd,
+                offset,
+            )
+        };
+        if ptr == MAP_FAILED {
+            return Err(last_error());
+        }
+        Ok(Mapping {
+            ptr: ptr.cast(),
+            len,
+        })
+    }
+}
+
+impl Drop for Mapping {
+    #[inline]
+    fn drop(&mut self) {
+        // Safety: the region was mapped by `mmap`.
+        unsafe {
+            libc::munmap(self.ptr.cast(), self.len);
+        }
+    }
+}
+
+/// Returns the last OS error.
+fn last_error() -> Error {
+    Error::IO(io::Error::last_os_error().kind())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CompletionQueueEntry, IOUring, Operation, Params, SubmissionQueueEntry};
+    use static_assertions::assert_eq_size;
+    use std::fs::{remove_file, OpenOptions};
+    use std::os::fd::AsRawFd;
+
+    assert_eq_size!(Params, [u8; 120]);
+    assert_eq_size!(SubmissionQueueEntry, [u8; 64]);
+    assert_eq_size!(CompletionQueueEntry, [u8; 16]);
+
+    #[test]
+    fn read_write() {
+        const FILE: &str = "io_uring_read_write_test";
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .read(true)
+            .write(true)
+            .open(FILE)
+            .unwrap();
+        let mut io_uring = IOUring::new().unwrap();
+        let buffers: Vec<[u8; 16]> = (0..128_u8).map(|i| [i; 16]).collect();
+        let mut operations: Vec<Operation> = buffers
+            .iter()
+            .enumerate()
+            .map(|(i, b)| Operation::Write(b, i as u64 * 16))
+            .collect();
+        assert!(io_uring
+            .submit_and_wait(file.as_raw_fd(), &mut operations, true)
+            .is_ok());
+        assert_eq!(file.metadata().unwrap().len(), 128 * 16);
+
+        let mut buffer = [0_u8; 16];
+        let mut operations = [Operation::Read(&mut buffer, 77 * 16)];
+        assert!(io_uring
+            .submit_and_wait(file.as_raw_fd(), &mut operations, false)
+            .is_ok());
+        assert_eq!(buffer, [77; 16]);
+
+        let mut buffer = [0_u8; 16];
+        let mut operations = [Operation::Read(&mut buffer, 128 * 16)];
+        assert!(io_uring
+            .submit_and_wait(file.as_raw_fd(), &mut operations, false)
+            .is_err());
+
+        drop(file);
+        assert!(remove_file(FILE).is_ok());
+    }
+
+    #[test]
+    fn failure() {
+        const FILE: &str = "io_uring_failure_test";
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .read(true)
+            .write(true)
+            .open(FILE)
+            .unwrap();
+        let mut io_uring = IOUring::new().unwrap();
+
+        // Failed operations leave nothing in the queues.
+        let buffers: Vec<[u8; 16]> = (0..96_u8).map(|i| [i; 16]).collect();
+        let mut operations: Vec<Operation> = buffers
+            .iter()
+            .enumerate()
+            .map(|(i, b)| Operation::Write(b, i as u64 * 16))
+            .collect();
+        assert!(io_uring.submit_and_wait(-1, &mut operations, true).is_err());
+        assert_eq!(io_uring.num_completed(), 0);
+        assert!(io_uring
+            .submit_and_wait(file.as_raw_fd(), &mut operations, true)
+            .is_ok());
+
+        let mut buffer = [0_u8; 16];
+        let mut operations = [Operation::Read(&mut buffer, 95 * 16)];
+        assert!(io_uring
+            .submit_and_wait(file.as_raw_fd(), &mut operations, false)
+            .is_ok());
+        assert_eq!(buffer, [95; 16]);
+
+        drop(file);
+        assert!(remove_file(FILE).is_ok());
+    }
+}
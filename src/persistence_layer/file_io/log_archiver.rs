@@ -0,0 +1,27 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Log segment archiving.
+
+use std::fmt::Debug;
+use std::path::Path;
+
+/// [`LogArchiver`] is notified whenever a segment of the log file is sealed.
+///
+/// A log segment is sealed once log records are written to the next segment, and a sealed segment
+/// is never modified afterwards until its log space is reclaimed by a checkpoint, see
+/// [`FileIO::set_log_retention`](super::FileIO::set_log_retention). Archived log segments can be replayed over a full backup to
+/// recover the database to a point in time, see
+/// [`FileIO::restore_to`](super::FileIO::restore_to).
+///
+/// The file name of an archived log segment must be the same as the original one, e.g., `l.log`
+/// or `l.log.1`.
+pub trait LogArchiver: 'static + Debug + Send + Sync {
+    /// Archives the sealed log segment.
+    ///
+    /// `segment_index` is the zero-based index of the segment, and `segment_path` is the path of
+    /// the segment file. The method is called by the thread performing file IO operations,
+    /// therefore long-running tasks should be offloaded to other threads.
+    fn archive(&self, segment_index: u64, segment_path: &Path);
+}
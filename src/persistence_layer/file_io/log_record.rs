@@ -2,7 +2,7 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{JournalID, Sequencer, TransactionID};
+use crate::{JournalID, Sequencer, TransactionID, VersionRecord};
 use std::mem::{size_of, MaybeUninit};
 use std::ptr::addr_of;
 
@@ -14,24 +14,28 @@ use std::ptr::addr_of;
 /// The bit representation of [`LogRecord`] is as follows.
 /// - 61-bit transaction ID, and 3-bit transaction control opcode follows.
 /// - If `transaction opcode = 0b000`, the event is unrelated to a transaction.
-/// -- `0b00000000`: the end of log file.
-/// -- `0b00001000`: the buffer was submitted, and a `u32` value follows.
-/// -- `0b00010000`: the buffer was discarded.
-/// -- TODO: page reorganization.
+///   - `0b00000000`: the end of log file.
+///   - `0b00001000`: the buffer was submitted, and a `u32` value follows.
+///   - `0b00010000`: the buffer was discarded.
+///   - TODO: page reorganization.
 /// - If `transaction opcode = 0b100`, the event happened in a transaction.
-/// -- 61-bit journal ID, 3-bit opcode.
-/// -- If `opcode = 0b000`, the journal created data identified as a `u64` value that follows.
-/// -- If `opcode = 0b001`, the journal created data identified as a `u64` and two `u32` values
-/// that follow.
-/// -- If `opcode = 0b010`, the journal deleted data identified as a `u64` value that follows.
-/// -- If `opcode = 0b011`, the journal deleted data identified as a `u64` and two `u32` values
-/// that follow.
-/// -- If `opcode = 0b100`, the journal was submitted.
-/// -- If `opcode = 0b101`, the journal was discarded.
+///   - 61-bit journal ID, 3-bit opcode.
+///   - If `opcode = 0b000`, the journal created data identified as a `u64` value that follows.
+///   - If `opcode = 0b001`, the journal created data identified as a `u64` and two `u32` values
+///     that follow.
+///   - If `opcode = 0b010`, the journal deleted data identified as a `u64` value that follows.
+///   - If `opcode = 0b011`, the journal deleted data identified as a `u64` and two `u32` values
+///     that follow.
+///   - If `opcode = 0b100`, the journal was submitted.
+///   - If `opcode = 0b101`, the journal was discarded.
+///   - If `opcode = 0b110`, the journal wrote a version of a key-value pair identified as a `u64`
+///     value, and a `u32` value follows, which is the length of the payload directly following
+///     the log record: `CONTAINER ID 64-bit|RECORD ID 64-bit|KEY LENGTH 32-bit|VALUE LENGTH
+///     32-bit|KEY|VALUE`, padded to a multiple of four bytes.
 /// - If `transaction opcode = 0b101`, the transaction is being prepared for commit, and
-/// `S::Instant` follows.
+///   `S::Instant` follows.
 /// - If `transaction opcode = 0b110`, the transaction is being committed, and `S::Instant`
-/// follows.
+///   follows.
 /// - If `transaction opcode = 0b111`, the transaction is being rolled back.
 #[derive(Copy, Clone, Debug, Eq)]
 pub(super) enum LogRecord<S: Sequencer> {
@@ -66,6 +70,10 @@ pub(super) enum LogRecord<S: Sequencer> {
     /// `(starting database object identifier, interval, number of objects)`.
     JournalDeletedObjectRange(TransactionID, JournalID, u64, u32, u32),
 
+    /// The journal wrote a version identified as the `u64` value, and the payload of which the
+    /// length is the `u32` value follows.
+    JournalWroteVersion(TransactionID, JournalID, u64, u32),
+
     /// The journal was submitted.
     ///
     /// The handling of this log record type is identical to that of [`Self::BufferSubmitted`].
@@ -116,6 +124,12 @@ pub const JOURNAL_SUBMITTED: u64 = 0b100;
 /// The journal was discarded.
 pub const JOURNAL_DISCARDED: u64 = 0b101;
 
+/// The journal wrote a version of a key-value pair.
+pub const JOURNAL_WROTE_VERSION: u64 = 0b110;
+
+/// The length of the fixed part of the payload of [`LogRecord::JournalWroteVersion`].
+pub(super) const VERSION_HEADER_LEN: usize = 24;
+
 /// The transaction updated the database.
 pub const TRANSACTION_UPDATED: u64 = 0b100;
 
@@ -234,6 +248,19 @@ impl<S: Sequencer> LogRecord<S> {
                         LogRecord::JournalDiscarded(transaction_id, journal_id),
                         value,
                     )),
+                    JOURNAL_WROTE_VERSION => {
+                        let (object_id, value) = read_part::<u64>(value)?;
+                        let (payload_len, value) = read_part::<u32>(value)?;
+                        Some((
+                            LogRecord::JournalWroteVersion(
+                                transaction_id,
+                                journal_id,
+                                object_id,
+                                payload_len,
+                            ),
+                            value,
+                        ))
+                    }
                     _ => unimplemented!(),
                 }
             }
@@ -262,6 +289,39 @@ impl<S: Sequencer> LogRecord<S> {
         }
     }
 
+    /// Returns the number of bytes that the log record occupies when written to a buffer.
+    pub(super) const fn size(&self) -> usize {
+        match self {
+            LogRecord::EndOfLog | LogRecord::BufferSubmitted(_) | LogRecord::BufferDiscarded => {
+                size_of::<u64>()
+            }
+            LogRecord::JournalCreatedObjectSingle(..)
+            | LogRecord::JournalDeletedObjectSingle(..) => {
+                size_of::<TransactionID>() + size_of::<JournalID>() + size_of::<u64>()
+            }
+            LogRecord::JournalCreatedObjectRange(..) | LogRecord::JournalDeletedObjectRange(..) => {
+                size_of::<TransactionID>()
+                    + size_of::<JournalID>()
+                    + size_of::<u64>()
+                    + size_of::<u32>() * 2
+            }
+            LogRecord::JournalSubmitted(..) => {
+                size_of::<TransactionID>() + size_of::<JournalID>() + size_of::<u32>()
+            }
+            LogRecord::JournalWroteVersion(..) => {
+                size_of::<TransactionID>()
+                    + size_of::<JournalID>()
+                    + size_of::<u64>()
+                    + size_of::<u32>()
+            }
+            LogRecord::JournalDiscarded(..) => size_of::<TransactionID>() + size_of::<JournalID>(),
+            LogRecord::TransactionPrepared(..) | LogRecord::TransactionCommitted(..) => {
+                size_of::<TransactionID>() + size_of::<S::Instant>()
+            }
+            LogRecord::TransactionRolledBack(..) => size_of::<TransactionID>() + size_of::<u32>(),
+        }
+    }
+
     /// Writes the data into the supplied buffer.
     ///
     /// Returns `None` if the data could not be written to the buffer, otherwise returns the number
@@ -277,12 +337,12 @@ impl<S: Sequencer> LogRecord<S> {
             }
             LogRecord::BufferDiscarded => write_part::<u64>(BUFFER_DISCARDED, buffer)?,
             LogRecord::JournalCreatedObjectSingle(transaction_id, journal_id, object_id) => {
-                debug_assert_eq!(transaction_id & OPCODE_MASK, 0);
-                debug_assert_eq!(journal_id & OPCODE_MASK, 0);
-                let transaction_id_with_mark = transaction_id | TRANSACTION_UPDATED;
-                let buffer = write_part::<TransactionID>(transaction_id_with_mark, buffer)?;
-                let journal_id_with_opcode = journal_id | JOURNAL_CREATED_SINGLE;
-                let buffer = write_part::<JournalID>(journal_id_with_opcode, buffer)?;
+                let buffer = write_journal_header(
+                    *transaction_id,
+                    *journal_id,
+                    JOURNAL_CREATED_SINGLE,
+                    buffer,
+                )?;
                 write_part::<u64>(*object_id, buffer)?
             }
             LogRecord::JournalCreatedObjectRange(
@@ -292,23 +352,23 @@ impl<S: Sequencer> LogRecord<S> {
                 interval,
                 num_objects,
             ) => {
-                debug_assert_eq!(transaction_id & OPCODE_MASK, 0);
-                debug_assert_eq!(journal_id & OPCODE_MASK, 0);
-                let transaction_id_with_mark = transaction_id | TRANSACTION_UPDATED;
-                let buffer = write_part::<TransactionID>(transaction_id_with_mark, buffer)?;
-                let journal_id_with_opcode = journal_id | JOURNAL_CREATED_RANGE;
-                let buffer = write_part::<JournalID>(journal_id_with_opcode, buffer)?;
+                let buffer = write_journal_header(
+                    *transaction_id,
+                    *journal_id,
+                    JOURNAL_CREATED_RANGE,
+                    buffer,
+                )?;
                 let buffer = write_part::<u64>(*object_id, buffer)?;
                 let buffer = write_part::<u32>(*interval, buffer)?;
                 write_part::<u32>(*num_objects, buffer)?
             }
             LogRecord::JournalDeletedObjectSingle(transaction_id, journal_id, object_id) => {
-                debug_assert_eq!(transaction_id & OPCODE_MASK, 0);
-                debug_assert_eq!(journal_id & OPCODE_MASK, 0);
-                let transaction_id_with_mark = transaction_id | TRANSACTION_UPDATED;
-                let buffer = write_part::<TransactionID>(transaction_id_with_mark, buffer)?;
-                let journal_id_with_opcode = journal_id | JOURNAL_DELETED_SINGLE;
-                let buffer = write_part::<JournalID>(journal_id_with_opcode, buffer)?;
+                let buffer = write_journal_header(
+                    *transaction_id,
+                    *journal_id,
+                    JOURNAL_DELETED_SINGLE,
+                    buffer,
+                )?;
                 write_part::<u64>(*object_id, buffer)?
             }
             LogRecord::JournalDeletedObjectRange(
@@ -318,32 +378,33 @@ impl<S: Sequencer> LogRecord<S> {
                 interval,
                 num_objects,
             ) => {
-                debug_assert_eq!(transaction_id & OPCODE_MASK, 0);
-                debug_assert_eq!(journal_id & OPCODE_MASK, 0);
-                let transaction_id_with_mark = transaction_id | TRANSACTION_UPDATED;
-                let buffer = write_part::<TransactionID>(transaction_id_with_mark, buffer)?;
-                let journal_id_with_opcode = journal_id | JOURNAL_DELETED_RANGE;
-                let buffer = write_part::<JournalID>(journal_id_with_opcode, buffer)?;
+                let buffer = write_journal_header(
+                    *transaction_id,
+                    *journal_id,
+                    JOURNAL_DELETED_RANGE,
+                    buffer,
+                )?;
                 let buffer = write_part::<u64>(*object_id, buffer)?;
                 let buffer = write_part::<u32>(*interval, buffer)?;
                 write_part::<u32>(*num_objects, buffer)?
             }
             LogRecord::JournalSubmitted(transaction_id, journal_id, transaction_instant) => {
-                debug_assert_eq!(transaction_id & OPCODE_MASK, 0);
-                debug_assert_eq!(journal_id & OPCODE_MASK, 0);
-                let transaction_id_with_mark = transaction_id | TRANSACTION_UPDATED;
-                let buffer = write_part::<TransactionID>(transaction_id_with_mark, buffer)?;
-                let journal_id_with_opcode = journal_id | JOURNAL_SUBMITTED;
-                let buffer = write_part::<JournalID>(journal_id_with_opcode, buffer)?;
+                let buffer =
+                    write_journal_header(*transaction_id, *journal_id, JOURNAL_SUBMITTED, buffer)?;
                 write_part::<u32>(*transaction_instant, buffer)?
             }
             LogRecord::JournalDiscarded(transaction_id, journal_id) => {
-                debug_assert_eq!(transaction_id & OPCODE_MASK, 0);
-                debug_assert_eq!(journal_id & OPCODE_MASK, 0);
-                let transaction_id_with_mark = transaction_id | TRANSACTION_UPDATED;
-                let buffer = write_part::<TransactionID>(transaction_id_with_mark, buffer)?;
-                let journal_id_with_opcode = journal_id | JOURNAL_DISCARDED;
-                write_part::<JournalID>(journal_id_with_opcode, buffer)?
+                write_journal_header(*transaction_id, *journal_id, JOURNAL_DISCARDED, buffer)?
+            }
+            LogRecord::JournalWroteVersion(transaction_id, journal_id, object_id, payload_len) => {
+                let buffer = write_journal_header(
+                    *transaction_id,
+                    *journal_id,
+                    JOURNAL_WROTE_VERSION,
+                    buffer,
+                )?;
+                let buffer = write_part::<u64>(*object_id, buffer)?;
+                write_part::<u32>(*payload_len, buffer)?
             }
             LogRecord::TransactionPrepared(transaction_id, instant) => {
                 debug_assert_eq!(transaction_id & OPCODE_MASK, 0);
@@ -398,6 +459,10 @@ impl<S: Sequencer> PartialEq for LogRecord<S> {
             (Self::JournalDiscarded(l0, l1), Self::JournalDiscarded(r0, r1)) => {
                 l0 == r0 && l1 == r1
             }
+            (
+                Self::JournalWroteVersion(l0, l1, l2, l3),
+                Self::JournalWroteVersion(r0, r1, r2, r3),
+            ) => l0 == r0 && l1 == r1 && l2 == r2 && l3 == r3,
             (Self::TransactionPrepared(l0, l1), Self::TransactionPrepared(r0, r1))
             | (Self::TransactionCommitted(l0, l1), Self::TransactionCommitted(r0, r1)) => {
                 l0 == r0 && l1 == r1
@@ -410,6 +475,52 @@ impl<S: Sequencer> PartialEq for LogRecord<S> {
     }
 }
 
+/// Returns the length of the payload of [`LogRecord::JournalWroteVersion`].
+///
+/// Returns `None` if the key or value is too large.
+pub(super) fn version_payload_len(version: &VersionRecord<'_>) -> Option<u32> {
+    let len = VERSION_HEADER_LEN
+        .checked_add(version.key().len())?
+        .checked_add(version.value().len())?
+        .checked_next_multiple_of(4)?;
+    u32::try_from(len).ok()
+}
+
+/// Encodes the payload of [`LogRecord::JournalWroteVersion`].
+///
+/// Returns `None` if the key or value is too large.
+pub(super) fn encode_version(version: &VersionRecord<'_>) -> Option<Vec<u8>> {
+    let payload_len = usize::try_from(version_payload_len(version)?).ok()?;
+    let mut payload = Vec::with_capacity(payload_len);
+    payload.extend_from_slice(&version.container_id().to_le_bytes());
+    payload.extend_from_slice(&version.record_id().to_le_bytes());
+    payload.extend_from_slice(&u32::try_from(version.key().len()).ok()?.to_le_bytes());
+    payload.extend_from_slice(&u32::try_from(version.value().len()).ok()?.to_le_bytes());
+    payload.extend_from_slice(version.key());
+    payload.extend_from_slice(version.value());
+    payload.resize(payload_len, 0);
+    Some(payload)
+}
+
+/// Decodes the payload of [`LogRecord::JournalWroteVersion`] encoded by [`encode_version`].
+pub(super) fn decode_version(object_id: u64, payload: &[u8]) -> Option<VersionRecord<'_>> {
+    let (container_id, payload) = payload.split_first_chunk::<8>()?;
+    let (record_id, payload) = payload.split_first_chunk::<8>()?;
+    let (key_len, payload) = payload.split_first_chunk::<4>()?;
+    let (value_len, payload) = payload.split_first_chunk::<4>()?;
+    let key_len = usize::try_from(u32::from_le_bytes(*key_len)).ok()?;
+    let value_len = usize::try_from(u32::from_le_bytes(*value_len)).ok()?;
+    let key = payload.get(..key_len)?;
+    let value = payload.get(key_len..key_len.checked_add(value_len)?)?;
+    Some(VersionRecord::new(
+        u64::from_le_bytes(*container_id),
+        u64::from_le_bytes(*record_id),
+        object_id,
+        key,
+        value,
+    ))
+}
+
 fn read_part<T: Copy + Sized>(value: &[u8]) -> Option<(T, &[u8])> {
     if value.len() < size_of::<T>() {
         return None;
@@ -426,6 +537,18 @@ fn read_part<T: Copy + Sized>(value: &[u8]) -> Option<(T, &[u8])> {
     unsafe { Some((uninit_t.assume_init(), &value[size_of::<T>()..])) }
 }
 
+fn write_journal_header(
+    transaction_id: TransactionID,
+    journal_id: JournalID,
+    opcode: u64,
+    buffer: &mut [u8],
+) -> Option<&mut [u8]> {
+    debug_assert_eq!(transaction_id & OPCODE_MASK, 0);
+    debug_assert_eq!(journal_id & OPCODE_MASK, 0);
+    let buffer = write_part::<TransactionID>(transaction_id | TRANSACTION_UPDATED, buffer)?;
+    write_part::<JournalID>(journal_id | opcode, buffer)
+}
+
 fn write_part<T: Copy + Sized>(value: T, buffer: &mut [u8]) -> Option<&mut [u8]> {
     if buffer.len() < size_of::<T>() {
         return None;
@@ -467,7 +590,7 @@ mod tests {
                             let eol = LogRecord::<MonotonicU64>::EndOfLog;
                             assert!(eol.write(&mut small_buffer).is_none());
                             assert!(eol.write(&mut medium_buffer).is_some());
-                            assert!(eol.write(&mut large_buffer).is_some());
+                            assert_eq!(eol.write(&mut large_buffer), Some(eol.size()));
                             if let Some((recovered_from_medium, _)) = LogRecord::<MonotonicU64>::from_raw_data(&medium_buffer) {
                                 if let Some((recovered_from_large, _)) = LogRecord::<MonotonicU64>::from_raw_data(&large_buffer) {
                                     assert_eq!(recovered_from_large, eol);
@@ -484,7 +607,7 @@ mod tests {
                             let buffer_committed = LogRecord::<MonotonicU64>::BufferSubmitted(transaction_instant);
                             assert!(buffer_committed.write(&mut small_buffer).is_none());
                             assert!(buffer_committed.write(&mut medium_buffer).is_some());
-                            assert!(buffer_committed.write(&mut large_buffer).is_some());
+                            assert_eq!(buffer_committed.write(&mut large_buffer), Some(buffer_committed.size()));
                             if let Some((recovered_from_medium, _)) = LogRecord::<MonotonicU64>::from_raw_data(&medium_buffer) {
                                 if let Some((recovered_from_large, _)) = LogRecord::<MonotonicU64>::from_raw_data(&large_buffer) {
                                     assert_eq!(recovered_from_large, buffer_committed);
@@ -500,7 +623,7 @@ mod tests {
                             let buffer_rolled_back = LogRecord::<MonotonicU64>::BufferDiscarded;
                             assert!(buffer_rolled_back.write(&mut small_buffer).is_none());
                             assert!(buffer_rolled_back.write(&mut medium_buffer).is_some());
-                            assert!(buffer_rolled_back.write(&mut large_buffer).is_some());
+                            assert_eq!(buffer_rolled_back.write(&mut large_buffer), Some(buffer_rolled_back.size()));
                             if let Some((recovered_from_medium, _)) = LogRecord::<MonotonicU64>::from_raw_data(&medium_buffer) {
                                 if let Some((recovered_from_large, _)) = LogRecord::<MonotonicU64>::from_raw_data(&large_buffer) {
                                     assert_eq!(recovered_from_large, buffer_rolled_back);
@@ -525,7 +648,7 @@ mod tests {
                             let created = LogRecord::<MonotonicU64>::JournalCreatedObjectSingle(transaction_id, journal_id, hash);
                             assert!(created.write(&mut small_buffer).is_none());
                             assert!(created.write(&mut medium_buffer).is_none());
-                            assert!(created.write(&mut large_buffer).is_some());
+                            assert_eq!(created.write(&mut large_buffer), Some(created.size()));
                             if let Some((recovered, _)) = LogRecord::<MonotonicU64>::from_raw_data(&large_buffer) {
                                 assert_eq!(recovered, created);
                             } else {
@@ -537,7 +660,7 @@ mod tests {
                             let created = LogRecord::<MonotonicU64>::JournalCreatedObjectRange(transaction_id, journal_id, hash, (hash >> 32) as u32, hash as u32);
                             assert!(created.write(&mut small_buffer).is_none());
                             assert!(created.write(&mut medium_buffer).is_none());
-                            assert!(created.write(&mut large_buffer).is_some());
+                            assert_eq!(created.write(&mut large_buffer), Some(created.size()));
                             if let Some((recovered, _)) = LogRecord::<MonotonicU64>::from_raw_data(&large_buffer) {
                                 assert_eq!(recovered, created);
                             } else {
@@ -548,7 +671,7 @@ mod tests {
                             let deleted = LogRecord::<MonotonicU64>::JournalDeletedObjectSingle(transaction_id, journal_id, hash);
                             assert!(deleted.write(&mut small_buffer).is_none());
                             assert!(deleted.write(&mut medium_buffer).is_none());
-                            assert!(deleted.write(&mut large_buffer).is_some());
+                            assert_eq!(deleted.write(&mut large_buffer), Some(deleted.size()));
                             if let Some((recovered, _)) = LogRecord::<MonotonicU64>::from_raw_data(&large_buffer) {
                                 assert_eq!(recovered, deleted);
                             } else {
@@ -560,7 +683,7 @@ mod tests {
                             let deleted = LogRecord::<MonotonicU64>::JournalDeletedObjectRange(transaction_id, journal_id, hash, (hash >> 32) as u32, hash as u32);
                             assert!(deleted.write(&mut small_buffer).is_none());
                             assert!(deleted.write(&mut medium_buffer).is_none());
-                            assert!(deleted.write(&mut large_buffer).is_some());
+                            assert_eq!(deleted.write(&mut large_buffer), Some(deleted.size()));
                             if let Some((recovered, _)) = LogRecord::<MonotonicU64>::from_raw_data(&large_buffer) {
                                 assert_eq!(recovered, deleted);
                             } else {
@@ -571,18 +694,30 @@ mod tests {
                             let submitted = LogRecord::<MonotonicU64>::JournalSubmitted(transaction_id, journal_id, hash.try_into().ok().map_or(0, |v| v));
                             assert!(submitted.write(&mut small_buffer).is_none());
                             assert!(submitted.write(&mut medium_buffer).is_none());
-                            assert!(submitted.write(&mut large_buffer).is_some());
+                            assert_eq!(submitted.write(&mut large_buffer), Some(submitted.size()));
                             if let Some((recovered, _)) = LogRecord::<MonotonicU64>::from_raw_data(&large_buffer) {
                                 assert_eq!(recovered, submitted);
                             } else {
                                 unreachable!();
                             }
                         }
+                        JOURNAL_WROTE_VERSION => {
+                            #[allow(clippy::cast_possible_truncation)]
+                            let wrote = LogRecord::<MonotonicU64>::JournalWroteVersion(transaction_id, journal_id, hash, hash as u32);
+                            assert!(wrote.write(&mut small_buffer).is_none());
+                            assert!(wrote.write(&mut medium_buffer).is_none());
+                            assert_eq!(wrote.write(&mut large_buffer), Some(wrote.size()));
+                            if let Some((recovered, _)) = LogRecord::<MonotonicU64>::from_raw_data(&large_buffer) {
+                                assert_eq!(recovered, wrote);
+                            } else {
+                                unreachable!();
+                            }
+                        }
                         JOURNAL_DISCARDED => {
                             let discarded = LogRecord::<MonotonicU64>::JournalDiscarded(transaction_id, journal_id);
                             assert!(discarded.write(&mut small_buffer).is_none());
                             assert!(discarded.write(&mut medium_buffer).is_none());
-                            assert!(discarded.write(&mut large_buffer).is_some());
+                            assert_eq!(discarded.write(&mut large_buffer), Some(discarded.size()));
                             if let Some((recovered, _)) = LogRecord::<MonotonicU64>::from_raw_data(&large_buffer) {
                                 assert_eq!(recovered, discarded);
                             } else {
@@ -596,7 +731,7 @@ mod tests {
                     let prepared = LogRecord::<MonotonicU64>::TransactionPrepared(transaction_id, instant);
                     assert!(prepared.write(&mut small_buffer).is_none());
                     assert!(prepared.write(&mut medium_buffer).is_none());
-                    assert!(prepared.write(&mut large_buffer).is_some());
+                    assert_eq!(prepared.write(&mut large_buffer), Some(prepared.size()));
                     if let Some((recovered, _)) = LogRecord::<MonotonicU64>::from_raw_data(&large_buffer) {
                         assert_eq!(recovered, prepared);
                     } else {
@@ -607,7 +742,7 @@ mod tests {
                     let committed = LogRecord::<MonotonicU64>::TransactionCommitted(transaction_id, instant);
                     assert!(committed.write(&mut small_buffer).is_none());
                     assert!(committed.write(&mut medium_buffer).is_none());
-                    assert!(committed.write(&mut large_buffer).is_some());
+                    assert_eq!(committed.write(&mut large_buffer), Some(committed.size()));
                     if let Some((recovered, _)) = LogRecord::<MonotonicU64>::from_raw_data(&large_buffer) {
                         assert_eq!(recovered, committed);
                     } else {
@@ -618,7 +753,7 @@ mod tests {
                     let rolled_back = LogRecord::<MonotonicU64>::TransactionRolledBack(transaction_id, 1);
                     assert!(rolled_back.write(&mut small_buffer).is_none());
                     assert!(rolled_back.write(&mut medium_buffer).is_some());
-                    assert!(rolled_back.write(&mut large_buffer).is_some());
+                    assert_eq!(rolled_back.write(&mut large_buffer), Some(rolled_back.size()));
                     if let Some((recovered_from_medium, _)) = LogRecord::<MonotonicU64>::from_raw_data(&medium_buffer) {
                         if let Some((recovered_from_large, _)) = LogRecord::<MonotonicU64>::from_raw_data(&large_buffer) {
                             assert_eq!(recovered_from_large, rolled_back);
@@ -634,4 +769,14 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn version_payload() {
+        let version = VersionRecord::new(7, 11, 13, b"key", b"value");
+        let payload = encode_version(&version).unwrap();
+        assert_eq!(payload.len(), VERSION_HEADER_LEN + 8);
+        assert_eq!(version_payload_len(&version), Some(32));
+        assert_eq!(decode_version(13, &payload), Some(version));
+        assert!(decode_version(13, &payload[..VERSION_HEADER_LEN + 2]).is_none());
+    }
 }
@@ -0,0 +1,174 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Memory-mapped view of a file.
+
+use crate::Error;
+use libc::{
+    c_void, _SC_PAGESIZE, MADV_SEQUENTIAL, MAP_FAILED, MAP_SHARED, MS_SYNC, PROT_READ, PROT_WRITE,
+};
+use std::fs::File;
+use std::io;
+use std::os::fd::AsRawFd;
+use std::ptr::{self, null_mut};
+
+/// [`MemoryMap`] maps the whole content of a file into memory.
+///
+/// The length of the file must not be changed without remapping it, otherwise accessing the
+/// truncated part of the map leads to a bus error.
+#[derive(Debug)]
+pub struct MemoryMap {
+    /// The start address of the map.
+    ///
+    /// It is null if the map is empty.
+    ptr: *mut u8,
+
+    /// The length of the map.
+    len: usize,
+}
+
+impl MemoryMap {
+    /// Maps the file with the specified length.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file could not be mapped.
+    pub fn new(file: &File, len: u64) -> Result<MemoryMap, Error> {
+        let len = usize::try_from(len).map_err(|_| Error::OutOfMemory)?;
+        if len == 0 {
+            return Ok(MemoryMap {
+                ptr: null_mut(),
+                len,
+            });
+        }
+        // Safety: the arguments are valid for `mmap`.
+        let ptr = unsafe {
+            libc::mmap(
+                null_mut(),
+                len,
+                PROT_READ | PROT_WRITE,
+                MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == MAP_FAILED {
+            return Err(last_error());
+        }
+        Ok(MemoryMap {
+            ptr: ptr.cast(),
+            len,
+        })
+    }
+
+    /// Returns the length of the map.
+    pub fn len(&self) -> u64 {
+        self.len as u64
+    }
+
+    /// Copies data at the offset into the buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the range is out of the map.
+    pub fn read(&self, buffer: &mut [u8], offset: u64) -> Result<(), Error> {
+        let start = self.check_range(buffer.len(), offset)?;
+        // Safety: the range is within the map, and `buffer` cannot overlap with the map.
+        unsafe {
+            ptr::copy_nonoverlapping(self.ptr.add(start), buffer.as_mut_ptr(), buffer.len());
+        }
+        Ok(())
+    }
+
+    /// Copies the buffer into the map at the offset.
+    ///
+    /// The caller has to exclude any other access to the range.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the range is out of the map.
+    pub fn write(&self, buffer: &[u8], offset: u64) -> Result<(), Error> {
+        let start = self.check_range(buffer.len(), offset)?;
+        // Safety: the range is within the map, and `buffer` cannot overlap with the map.
+        unsafe {
+            ptr::copy_nonoverlapping(buffer.as_ptr(), self.ptr.add(start), buffer.len());
+        }
+        Ok(())
+    }
+
+    /// Synchronizes the range of the map with the device.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the range is out of the map or `msync` failed.
+    pub fn sync(&self, len: usize, offset: u64) -> Result<(), Error> {
+        let start = self.check_range(len, offset)?;
+        if len == 0 {
+            return Ok(());
+        }
+        // `msync` requires the address to be aligned to the system page size.
+        let aligned_start = start - start % system_page_size();
+        // Safety: the range is within the map.
+        let result = unsafe {
+            libc::msync(
+                self.ptr.add(aligned_start).cast::<c_void>(),
+                start + len - aligned_start,
+                MS_SYNC,
+            )
+        };
+        if result != 0 {
+            return Err(last_error());
+        }
+        Ok(())
+    }
+
+    /// Advises the kernel that the map will be read sequentially.
+    pub fn advise_sequential(&self) {
+        if self.len != 0 {
+            // Safety: the range is the map; a failure is harmless since it is a hint.
+            unsafe {
+                libc::madvise(self.ptr.cast::<c_void>(), self.len, MADV_SEQUENTIAL);
+            }
+        }
+    }
+
+    /// Checks if the range is within the map, and returns the start of the range.
+    fn check_range(&self, len: usize, offset: u64) -> Result<usize, Error> {
+        let start = usize::try_from(offset).map_err(|_| Error::IO(io::ErrorKind::UnexpectedEof))?;
+        if start.checked_add(len).is_none_or(|end| end > self.len) {
+            return Err(Error::IO(io::ErrorKind::UnexpectedEof));
+        }
+        Ok(start)
+    }
+}
+
+impl Drop for MemoryMap {
+    #[inline]
+    fn drop(&mut self) {
+        if self.len != 0 {
+            // Safety: the region was mapped by `mmap`.
+            unsafe {
+                libc::munmap(self.ptr.cast::<c_void>(), self.len);
+            }
+        }
+    }
+}
+
+// Safety: the caller of `write` excludes other accesses to the range.
+unsafe impl Send for MemoryMap {}
+
+// Safety: the caller of `write` excludes other accesses to the range.
+unsafe impl Sync for MemoryMap {}
+
+/// Returns the size of a page of the system.
+fn system_page_size() -> usize {
+    // Safety: `sysconf` has no preconditions.
+    let page_size = unsafe { libc::sysconf(_SC_PAGESIZE) };
+    usize::try_from(page_size).unwrap_or(4096)
+}
+
+/// Returns the last OS error.
+fn last_error() -> Error {
+    Error::IO(io::Error::last_os_error().kind())
+}
@@ -7,36 +7,73 @@
 //!
 //! The [`FileIO`] persistence layer only supports `u64` [`Sequencer`] types.
 
+mod aligned_buffer;
+mod backup;
+mod blob;
+mod btree;
+mod cipher;
+mod container_directory;
 mod database_header;
+mod double_write_buffer;
 mod evictable_page;
+mod faulty_file;
+mod hash_table;
+mod integrity;
 mod io_task_processor;
+#[cfg(target_os = "linux")]
+mod io_uring;
+mod log_archiver;
 mod log_record;
+mod memory_map;
+mod object_store;
+mod open_options;
+mod page_allocator;
 mod page_manager;
 mod random_access_file;
 mod recovery;
 
-use super::LogBufferInterface;
+pub use cipher::Cipher;
+pub use faulty_file::{Fault, FaultyFile};
+pub use integrity::{IntegrityProblem, IntegrityReport};
+pub use log_archiver::LogArchiver;
+pub use object_store::{DirectoryStore, ObjectStore, ObjectStoreArchiver};
+pub use open_options::OpenOptions;
+pub use random_access_file::{Durability, IOBackend};
+
+use super::{LogBufferInterface, PersistedIndexInterface};
+use crate::catalog::CATALOG_ID;
 use crate::persistence_layer::{AwaitIO, AwaitRecovery, RecoveryResult};
-use crate::{utils, Database, Error, JournalID, PersistenceLayer, Sequencer, TransactionID};
+use crate::{
+    utils, ConfigDelta, Database, Error, JournalID, PersistenceLayer, Sequencer, Telemetry,
+    TransactionID, VersionRecord,
+};
+use backup::BackupTarget;
+use cipher::{Encryption, NonceSequence};
+use container_directory::{ContainerDirectory, ContainerIndex};
 use io_task_processor::IOTask;
 use log_record::LogRecord;
 use page_manager::PageManager;
-use random_access_file::RandomAccessFile;
+use random_access_file::{RandomAccessFile, FRAME_OVERHEAD};
 use recovery::RecoveryData;
 use scc::Bag;
-use std::fs::create_dir_all;
+use std::fs::{self, create_dir_all};
+use std::future::poll_fn;
 use std::marker::PhantomData;
-use std::mem::take;
-use std::num::{NonZeroU32, NonZeroU64};
+use std::mem::{replace, take};
+use std::num::{NonZeroU32, NonZeroU64, NonZeroUsize};
+use std::ops::Bound;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::Ordering::{AcqRel, Acquire, Relaxed, Release};
 use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicU8, AtomicUsize};
 use std::sync::mpsc::{self, SyncSender, TrySendError};
-use std::sync::{Arc, Mutex};
-use std::task::Waker;
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Poll, Waker};
 use std::thread::{self, JoinHandle};
 use std::time::Instant;
 
+/// The size of the emergency disk space that is released when the storage device runs out of space.
+const EMERGENCY_SPACE_SIZE: usize = 256 * 1024;
+
 /// [`FileIO`] abstracts the OS file system layer to implement [`PersistenceLayer`].
 ///
 /// [`FileIO`] spawns a thread for file operations and synchronization with the device. Any
@@ -44,7 +81,11 @@ use std::time::Instant;
 ///
 /// [`FileIO`] spawns two additional threads that are dedicated to file IO operations.
 ///
-/// TODO: implement checkpoint.
+/// A checkpoint records the offset of the log from which log records are replayed over it on
+/// recovery, and the log space before the offset is reclaimed once the checkpoint is persisted.
+/// Log offsets are never reused, and the reclaimed log space is deallocated from the storage
+/// device instead; log records consumed by others, e.g., [`LogArchiver`] implementations or
+/// incremental backups, can be retained through [`FileIO::set_log_retention`].
 #[derive(Debug)]
 pub struct FileIO<S: Sequencer<Instant = u64>> {
     /// The file IO worker thread.
@@ -56,6 +97,9 @@ pub struct FileIO<S: Sequencer<Instant = u64>> {
     /// Shared data among the workers and database threads.
     file_io_data: Arc<FileIOData<S>>,
 
+    /// The path to the database files.
+    path: PathBuf,
+
     /// This pacifies `Clippy` complaining the lack of usage of `S`.
     _phantom: PhantomData<S>,
 }
@@ -85,6 +129,32 @@ pub struct FileLogBuffer {
     next: AtomicUsize,
 }
 
+/// [`FileIndex`] is the persisted index type for [`FileIO`].
+///
+/// It refers to the ordered index of a container written by the latest checkpoint, and the pages
+/// of the index are read through the page cache.
+#[derive(Debug)]
+pub struct FileIndex<S: Sequencer<Instant = u64>> {
+    /// Shared data among the workers and database threads.
+    ///
+    /// The reference is weak since a container may outlive the [`FileIO`].
+    file_io_data: Weak<FileIOData<S>>,
+
+    /// The identifier of the container.
+    container_id: u64,
+}
+
+/// [`IndexReader`] prevents the pages of the container indexes from being freed or moved while
+/// it is alive.
+///
+/// The task waiting for the container indexes to be locked is woken up through the [`Bag`] when
+/// the last [`IndexReader`] is dropped.
+struct IndexReader<'d>(&'d AtomicUsize, &'d Bag<Waker>);
+
+/// The flag set in [`FileIOData::index_readers`] while the pages of the container indexes are
+/// being freed or moved.
+const INDEXES_LOCKED: usize = 1 << (usize::BITS - 1);
+
 /// [`FileIOData`] is shared among the worker and database threads.
 #[derive(Debug)]
 struct FileIOData<S: Sequencer<Instant = u64>> {
@@ -94,26 +164,162 @@ struct FileIOData<S: Sequencer<Instant = u64>> {
     /// Recovery cancelled.
     recovery_cancelled: AtomicBool,
 
+    /// The number of threads replaying log records during recovery.
+    recovery_parallelism: AtomicUsize,
+
     /// The log file.
-    ///
-    /// TODO: replace it with database pages.
     log: RandomAccessFile,
 
+    /// The offset of the log before which log records may have been reclaimed.
+    log_head: AtomicU64,
+
+    /// The length of log records before the end of the log that are retained when log space is
+    /// reclaimed.
+    log_retention: AtomicU64,
+
+    /// The length of the log when each open transaction started generating log records.
+    transaction_log_offsets: scc::HashMap<TransactionID, u64>,
+
     /// [`FileLogBuffer`] link.
     ///
     /// The whole link must be consumed at once otherwise it is susceptible to ABA problems.
     log_buffer_link: AtomicUsize,
 
+    /// The maximum length of the log file that log records of database changes may reach.
+    log_capacity: AtomicU64,
+
+    /// The length of the log file including log space reserved for log records not yet written.
+    log_reserved: AtomicU64,
+
+    /// The path to the file occupying the emergency disk space.
+    emergency_space: PathBuf,
+
+    /// The storage device ran out of space, and the database is in the read-only mode.
+    disk_full: AtomicBool,
+
     /// The page manager.
     page_manager: PageManager,
 
+    /// A checkpoint is being taken.
+    checkpointing: AtomicBool,
+
+    /// The overflow pages of the container directory written by the latest checkpoint.
+    container_directory_pages: Mutex<Vec<u64>>,
+
+    /// The container indexes written by the latest checkpoint.
+    container_indexes: Mutex<Vec<ContainerIndex>>,
+
+    /// The number of [`IndexReader`] instances, and [`INDEXES_LOCKED`] if the pages of the
+    /// container indexes are being freed or moved.
+    index_readers: AtomicUsize,
+
     /// The current flush epoch.
     flush_epoch: AtomicU64,
 
+    /// The [`LogArchiver`] notified of sealed log segments.
+    log_archiver: Mutex<Option<Arc<dyn LogArchiver>>>,
+
+    /// The [`FaultyFile`] attached to the files of backups taken afterwards.
+    backup_faults: Mutex<Option<FaultyFile>>,
+
+    /// Errors that background IO operations failed with, keyed by the flush epochs that mark the
+    /// completion of the operations.
+    io_errors: scc::HashMap<u64, Error>,
+
+    /// Statistics of the database.
+    telemetry: Arc<Telemetry>,
+
     /// [`Waker`] bag.
     waker_bag: Bag<Waker>,
 }
 
+impl<S: Sequencer<Instant = u64>> FileIOData<S> {
+    /// Returns the error that writing the log or database file failed with if the files refuse
+    /// writes.
+    fn write_error(&self) -> Option<Error> {
+        self.log
+            .write_error()
+            .or_else(|| self.page_manager.write_error())
+            .cloned()
+    }
+
+    /// Waits until the container indexes are not locked, and returns an [`IndexReader`].
+    async fn read_indexes(&self) -> IndexReader<'_> {
+        poll_fn(|cx| {
+            if let Some(reader) = self.try_read_indexes() {
+                return Poll::Ready(reader);
+            }
+
+            // Push the `Waker` into the bag, and check the lock again.
+            self.waker_bag.push(cx.waker().clone());
+            self.try_read_indexes().map_or(Poll::Pending, Poll::Ready)
+        })
+        .await
+    }
+
+    /// Returns an [`IndexReader`] unless the container indexes are locked.
+    fn try_read_indexes(&self) -> Option<IndexReader<'_>> {
+        let locked = self.index_readers.fetch_add(1, Acquire) & INDEXES_LOCKED != 0;
+        let reader = IndexReader(&self.index_readers, &self.waker_bag);
+        if locked {
+            // The task locking the container indexes may be waiting for the reader.
+            drop(reader);
+            None
+        } else {
+            Some(reader)
+        }
+    }
+
+    /// Locks the container indexes, and waits for every [`IndexReader`] to be dropped.
+    ///
+    /// Only one task holding the checkpoint lock may lock the container indexes.
+    async fn lock_indexes(&self) {
+        self.index_readers.fetch_or(INDEXES_LOCKED, Acquire);
+        poll_fn(|cx| {
+            if self.index_readers.load(Acquire) == INDEXES_LOCKED {
+                return Poll::Ready(());
+            }
+
+            // Push the `Waker` into the bag, and check the readers again.
+            self.waker_bag.push(cx.waker().clone());
+            if self.index_readers.load(Acquire) == INDEXES_LOCKED {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await;
+    }
+
+    /// Unlocks the container indexes, and wakes up tasks waiting for an [`IndexReader`].
+    fn unlock_indexes(&self) {
+        self.index_readers.fetch_and(!INDEXES_LOCKED, Release);
+        self.waker_bag.pop_all((), |(), w| w.wake());
+    }
+
+    /// Returns the index of the container written by the latest checkpoint.
+    fn container_index(&self, container_id: u64) -> Option<ContainerIndex> {
+        self.container_indexes.lock().ok().and_then(|indexes| {
+            indexes
+                .iter()
+                .find(|i| i.container_id == container_id)
+                .cloned()
+        })
+    }
+
+    /// Handles an IO error of writing log records.
+    ///
+    /// If the storage device ran out of space, the database is put into the read-only mode, and the
+    /// emergency disk space is released so that the pending log records can be written.
+    fn handle_write_error(&self, error: &Error) {
+        if *error == Error::DiskFull && !self.disk_full.swap(true, AcqRel) {
+            #[cfg(feature = "tracing")]
+            tracing::error!("the storage device ran out of space");
+            drop(fs::remove_file(&self.emergency_space));
+        }
+    }
+}
+
 impl<S: Sequencer<Instant = u64>> FileIO<S> {
     /// Creates a default [`FileIO`].
     ///
@@ -126,25 +332,217 @@ impl<S: Sequencer<Instant = u64>> FileIO<S> {
     /// directory could not be created, or database files could not be opened.
     #[inline]
     pub fn with_path(path: &Path) -> Result<Self, Error> {
-        if create_dir_all(path).is_err() {
+        Self::open(path, &OpenOptions::new())
+    }
+
+    /// Creates a [`FileIO`] with the specified page size.
+    ///
+    /// The page size must be a power of two between `512B` and `64KB`, and it is only used when a
+    /// new database is created; opening an existing database with a different page size fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the page size is invalid or different from that of the existing
+    /// database, memory allocation failed, spawning a thread failed, the specified directory could
+    /// not be created, or database files could not be opened.
+    #[inline]
+    pub fn with_page_size(path: &Path, page_size: u64) -> Result<Self, Error> {
+        Self::open(path, &OpenOptions::new().with_page_size(page_size))
+    }
+
+    /// Creates a [`FileIO`] that encrypts database pages and log records using the supplied
+    /// [`Cipher`].
+    ///
+    /// A database created with a [`Cipher`] can only be opened with a [`Cipher`], and vice versa.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is not encrypted, memory allocation failed, spawning a
+    /// thread failed, the specified directory could not be created, or database files could not
+    /// be opened.
+    #[inline]
+    pub fn with_cipher(path: &Path, cipher: Arc<dyn Cipher>) -> Result<Self, Error> {
+        Self::open(path, &OpenOptions::new().with_cipher(cipher))
+    }
+
+    /// Creates a [`FileIO`] from the files in the specified path, and upgrades the database file
+    /// to the current version if it was created by an older version.
+    ///
+    /// Opening a database file of an older version without migration fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database file was created by a newer version, migration failed,
+    /// memory allocation failed, spawning a thread failed, the specified directory could not be
+    /// created, or database files could not be opened.
+    #[inline]
+    pub fn open_and_migrate(path: &Path) -> Result<Self, Error> {
+        Self::open(path, &OpenOptions::new().with_migration(true))
+    }
+
+    /// Creates a [`FileIO`] that performs file IO operations using the specified [`IOBackend`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the [`IOBackend`] is not supported, memory allocation failed, spawning
+    /// a thread failed, the specified directory could not be created, or database files could not
+    /// be opened.
+    #[inline]
+    pub fn with_io_backend(path: &Path, io_backend: IOBackend) -> Result<Self, Error> {
+        Self::open(path, &OpenOptions::new().with_io_backend(io_backend))
+    }
+
+    /// Creates a [`FileIO`] that synchronizes the database and log files with the device according
+    /// to the specified [`Durability`].
+    ///
+    /// Transactions committed with [`Durability::OsBuffered`] or [`Durability::None`] may be lost
+    /// if the system fails before the files are synchronized with the device.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if memory allocation failed, spawning a thread failed, the specified
+    /// directory could not be created, or database files could not be opened.
+    #[inline]
+    pub fn with_durability(path: &Path, durability: Durability) -> Result<Self, Error> {
+        Self::open(path, &OpenOptions::new().with_durability(durability))
+    }
+
+    /// Creates a [`FileIO`] that splits the database and log files into segments of the specified
+    /// size.
+    ///
+    /// The segment size must be a multiple of `1MB`, and it is only used when a new database is
+    /// created; opening an existing database with a different segment size fails. Segments
+    /// following the first one are named `db.dat.1`, `db.dat.2`, `l.log.1`, `l.log.2`, and so on,
+    /// and no segment grows beyond the segment size, so that file systems with file size limits
+    /// can be used.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the segment size is invalid or different from that of the existing
+    /// database, memory allocation failed, spawning a thread failed, the specified directory could
+    /// not be created, or database files could not be opened.
+    #[inline]
+    pub fn with_segment_size(path: &Path, segment_size: u64) -> Result<Self, Error> {
+        Self::open(path, &OpenOptions::new().with_segment_size(segment_size))
+    }
+
+    /// Returns the paths of sealed log segments.
+    ///
+    /// A log segment is sealed once log records are written to the next segment, and a sealed
+    /// segment is never modified afterwards, therefore it can be copied to an archive while the
+    /// database is in use unless the log space of the segment is reclaimed by a checkpoint, see
+    /// [`FileIO::set_log_retention`]. An empty [`Vec`] is returned if the log file is not split
+    /// into segments.
+    #[inline]
+    #[must_use]
+    pub fn sealed_log_segments(&self) -> Vec<PathBuf> {
+        let mut segment_paths = self.file_io_data.log.segment_paths();
+        segment_paths.pop();
+        segment_paths
+    }
+
+    /// Returns its page manager.
+    #[inline]
+    #[must_use]
+    pub fn page_manager(&self) -> &PageManager {
+        &self.file_io_data.page_manager
+    }
+
+    /// Returns the size of database pages.
+    #[inline]
+    #[must_use]
+    pub fn page_size(&self) -> u64 {
+        self.file_io_data.page_manager.page_size()
+    }
+
+    /// Opens the database files in the specified path.
+    fn open(path: &Path, options: &OpenOptions) -> Result<Self, Error> {
+        if !options.read_only && create_dir_all(path).is_err() {
             return Err(Error::Generic("the path could not be created"));
         }
 
         let mut path_buffer = PathBuf::with_capacity(path.as_os_str().len() + 6);
         path_buffer.push(path);
 
-        let log = Self::open_file(&mut path_buffer, "l.log")?;
-        let db = Self::open_file(&mut path_buffer, "db.dat")?;
+        // The log file is sequentially appended in small chunks, therefore it does not bypass the
+        // operating system cache.
+        let io_backend = options.io_backend;
+        let log_io_backend = if io_backend == IOBackend::Direct {
+            IOBackend::Synchronous
+        } else {
+            io_backend
+        };
+
+        // The database file is locked before any other files are touched.
+        let mut db = Self::open_file(&mut path_buffer, "db.dat", io_backend, options)?;
+        if options.locking {
+            db.try_lock(!options.read_only)?;
+        }
+        let mut log = Self::open_file(&mut path_buffer, "l.log", log_io_backend, options)?;
+        let emergency_space = path.join("emergency.dat");
+        if !options.read_only && !emergency_space.exists() {
+            // The space has to be actually allocated, therefore the file is filled with zeros.
+            fs::write(&emergency_space, vec![0_u8; EMERGENCY_SPACE_SIZE])?;
+        }
+        // Nonces are shared by the database and log files, and the nonce epoch is started once
+        // the database header is read.
+        let nonces = Arc::new(NonceSequence::default());
+        if let Some(cipher) = options.cipher.as_ref() {
+            db.set_encryption(Encryption::new(cipher.clone(), nonces.clone()));
+        }
         let (file_io_task_sender, mut file_io_task_receiver) =
             mpsc::sync_channel::<IOTask>(utils::advise_num_shards() * 16);
-        let page_manager = PageManager::from_db(db, file_io_task_sender.clone())?;
+        let telemetry = Arc::new(Telemetry::default());
+        let double_write_file = if options.read_only {
+            None
+        } else {
+            Some(Self::open_file(
+                &mut path_buffer,
+                "dw.dat",
+                log_io_backend,
+                options,
+            )?)
+        };
+        let page_manager = PageManager::from_db(
+            db,
+            double_write_file,
+            options,
+            file_io_task_sender.clone(),
+            telemetry.clone(),
+        )?;
+        if page_manager.segment_size() != 0 {
+            log.enable_segments(page_manager.segment_size())?;
+        }
+        if let Some(cipher) = options.cipher.clone() {
+            log.set_encryption(Encryption::new(cipher, nonces));
+        }
         let file_io_data = Arc::new(FileIOData {
             recovery_data: Mutex::default(),
             recovery_cancelled: AtomicBool::new(false),
+            recovery_parallelism: AtomicUsize::new(
+                options
+                    .recovery_parallelism
+                    .map_or_else(utils::advise_num_shards, NonZeroUsize::get),
+            ),
             log,
+            log_head: AtomicU64::new(0),
+            log_retention: AtomicU64::new(0),
+            transaction_log_offsets: scc::HashMap::default(),
             log_buffer_link: AtomicUsize::new(0),
+            log_capacity: AtomicU64::new(u64::MAX),
+            log_reserved: AtomicU64::new(0),
+            emergency_space,
+            disk_full: AtomicBool::new(false),
             page_manager,
+            checkpointing: AtomicBool::new(false),
+            container_directory_pages: Mutex::default(),
+            container_indexes: Mutex::default(),
+            index_readers: AtomicUsize::new(0),
             flush_epoch: AtomicU64::new(0),
+            log_archiver: Mutex::default(),
+            backup_faults: Mutex::default(),
+            io_errors: scc::HashMap::default(),
+            telemetry,
             waker_bag: Bag::default(),
         });
         let file_io_data_clone = file_io_data.clone();
@@ -154,40 +552,311 @@ impl<S: Sequencer<Instant = u64>> FileIO<S> {
             })),
             file_io_task_sender,
             file_io_data,
+            path: path.to_path_buf(),
             _phantom: PhantomData,
         })
     }
 
-    /// Returns its page manager.
+    /// Defragments the database file.
+    ///
+    /// Returns the length of the database file after defragmentation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the IO task processor is not available.
+    #[inline]
+    pub async fn defragment(&self) -> Result<u64, Error> {
+        self.file_io_data.page_manager.defragment().await
+    }
+
+    /// Vacuums the database file.
+    ///
+    /// The pages of the container indexes written by the latest checkpoint are moved to free pages
+    /// nearer the front of the database file, and then the database file is defragmented; no
+    /// checkpoint is taken while the pages are moved. Returns the length of the database file
+    /// after vacuuming.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ReadOnly`] if the database is read-only, or an error if a page could not
+    /// be moved or the IO task processor is not available.
+    #[inline]
+    pub async fn vacuum(&self) -> Result<u64, Error> {
+        if self.page_manager().is_read_only() {
+            return Err(Error::ReadOnly);
+        }
+        self.lock_checkpoint().await;
+        let result = self.compact_indexes().await;
+        self.unlock_checkpoint();
+        result?;
+        self.defragment().await
+    }
+
+    /// Verifies the database file, and returns an [`IntegrityReport`].
+    ///
+    /// The header page, the checksums of pages, the free page list, and the links between pages
+    /// are verified; the pages are read through the page cache, and database workers are not
+    /// stopped while the database file is verified.
+    #[inline]
+    pub async fn verify(&self) -> IntegrityReport {
+        self.file_io_data.page_manager.verify().await
+    }
+
+    /// Returns the addresses of quarantined pages in ascending order.
+    ///
+    /// A page is quarantined when it fails checksum verification, e.g., when it is read or
+    /// verified; accesses to a quarantined page fail with [`Error::CorruptPage`] whereas the
+    /// other pages remain readable and writable, and the page is repaired when the database is
+    /// recovered after a crash. A container of which the index persisted by the latest checkpoint
+    /// has a quarantined page when the database is recovered is quarantined as well: accesses to
+    /// its key-value pairs fail with [`Error::CorruptPage`], and checkpoints fail until the page
+    /// is repaired, whereas the other containers remain readable and writable.
     #[inline]
     #[must_use]
-    pub fn page_manager(&self) -> &PageManager {
-        &self.file_io_data.page_manager
+    pub fn quarantined_pages(&self) -> Vec<u64> {
+        self.file_io_data.page_manager.quarantined_pages()
+    }
+
+    /// Backs up pages written and log records generated since the backup of the specified clock
+    /// into the specified path.
+    ///
+    /// Returns the clock of the incremental backup. The clock of a backup is returned by
+    /// [`FileIO::backup_clock`], and an incremental backup is applied to a full backup by
+    /// [`FileIO::restore_incremental`]. If the base backup was not taken by the [`FileIO`] after
+    /// it was opened, every page is backed up.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::WrongParameter`] if the base clock is newer than the log, log records
+    /// following the base clock have been reclaimed, or the path is that of the database,
+    /// [`Error::Timeout`] if the deadline was reached, and an error if the backup files could not
+    /// be created.
+    #[inline]
+    pub async fn backup_incremental(
+        &self,
+        base_clock: u64,
+        path: &Path,
+        deadline: Option<Instant>,
+    ) -> Result<u64, Error> {
+        if base_clock > self.file_io_data.log.len(Acquire) || base_clock < self.log_head() {
+            return Err(Error::WrongParameter);
+        }
+        let target = self.backup_target(path, Some(base_clock), 0)?;
+        let log_buffer = Arc::<FileLogBuffer>::default();
+        if self
+            .file_io_task_sender
+            .send(IOTask::Backup(target, log_buffer.clone()))
+            .is_err()
+        {
+            return Err(Error::UnexpectedState);
+        }
+        AwaitIO::with_log_buffer(self, log_buffer, deadline).await?;
+        backup::backup_clock(path)
+    }
+
+    /// Returns the clock of the full or incremental backup in the specified path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path does not contain a backup.
+    #[inline]
+    pub fn backup_clock(path: &Path) -> Result<u64, Error> {
+        backup::backup_clock(path)
+    }
+
+    /// Applies the incremental backup to the full backup in the base path.
+    ///
+    /// Incremental backups have to be applied in the order they were taken, and the new clock of
+    /// the full backup is returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::WrongParameter`] if the clock of the full backup is not the base clock of
+    /// the incremental backup, [`Error::CorruptDatabase`] if the incremental backup is incomplete,
+    /// and an error if the backup files could not be read or written.
+    #[inline]
+    pub fn restore_incremental(base: &Path, incremental: &Path) -> Result<u64, Error> {
+        backup::restore(base, incremental)
+    }
+
+    /// Sets the [`LogArchiver`] that is notified whenever a log segment is sealed.
+    ///
+    /// Log segments sealed before the [`LogArchiver`] is set are not notified, and they can be
+    /// retrieved by [`FileIO::sealed_log_segments`].
+    #[inline]
+    pub fn set_log_archiver(&self, log_archiver: Arc<dyn LogArchiver>) {
+        if let Ok(mut guard) = self.file_io_data.log_archiver.lock() {
+            guard.replace(log_archiver);
+        }
+    }
+
+    /// Sets the [`FaultyFile`] that injects faults into write operations of the log file.
+    ///
+    /// Returns `false` if a [`FaultyFile`] was already set.
+    #[inline]
+    #[must_use]
+    pub fn set_log_faults(&self, faulty_file: FaultyFile) -> bool {
+        self.file_io_data.log.set_faulty_file(faulty_file)
+    }
+
+    /// Sets the [`FaultyFile`] that injects faults into write operations of the database file.
+    ///
+    /// Returns `false` if a [`FaultyFile`] was already set.
+    #[inline]
+    #[must_use]
+    pub fn set_database_faults(&self, faulty_file: FaultyFile) -> bool {
+        self.page_manager().set_faulty_file(faulty_file)
+    }
+
+    /// Sets the [`FaultyFile`] that injects faults into write operations of the files of backups
+    /// taken afterwards.
+    ///
+    /// The database and log files of a backup share the script of the [`FaultyFile`].
+    #[inline]
+    pub fn set_backup_faults(&self, faulty_file: FaultyFile) {
+        if let Ok(mut guard) = self.file_io_data.backup_faults.lock() {
+            guard.replace(faulty_file);
+        }
+    }
+
+    /// Sets the maximum length of the log file, or removes the limit if `None` is specified.
+    ///
+    /// Log space for log records of database changes is reserved before the log records are
+    /// generated, and [`Error::DiskFull`] is returned if the log would grow beyond the capacity;
+    /// log records ending journals and transactions are always allowed so that transactions
+    /// holding reserved log space are able to finish. Log space reclaimed by checkpoints does not
+    /// count towards the capacity.
+    #[inline]
+    pub fn set_log_capacity(&self, capacity: Option<u64>) {
+        self.file_io_data
+            .log_capacity
+            .store(capacity.unwrap_or(u64::MAX), Relaxed);
+    }
+
+    /// Sets the length of log records before the end of the log that checkpoints retain, or stops
+    /// reclaiming log space if `None` is specified.
+    ///
+    /// A checkpoint reclaims the log space before the offset from which log records are replayed
+    /// over the checkpoint, and log records generated since the latest backup taken by the
+    /// [`FileIO`] are always retained for the next incremental backup. Consumers of the log, e.g.,
+    /// change data capture or replication reading sealed log segments, should retain as many log
+    /// records as they may lag behind. The default value is `0`.
+    #[inline]
+    pub fn set_log_retention(&self, retention: Option<u64>) {
+        self.file_io_data
+            .log_retention
+            .store(retention.unwrap_or(u64::MAX), Relaxed);
+    }
+
+    /// Returns the offset of the log before which log records may have been reclaimed.
+    ///
+    /// The offset is a lower bound of the clock of backups that incremental backups can be based
+    /// on, see [`FileIO::backup_incremental`].
+    #[inline]
+    #[must_use]
+    pub fn log_head(&self) -> u64 {
+        self.file_io_data.log_head.load(Acquire)
+    }
+
+    /// Returns `true` if the database is in the read-only mode since it was opened with
+    /// [`OpenOptions::with_read_only`] or the storage device ran out of space.
+    ///
+    /// In the read-only mode, database objects cannot be created or deleted, whereas transactions
+    /// are still able to be committed or rolled back. If the storage device ran out of space, the
+    /// emergency disk space reserved when the database was opened is released in order for the
+    /// pending log records to be written, and the database has to be reopened after securing disk
+    /// space to leave the read-only mode.
+    #[inline]
+    #[must_use]
+    pub fn is_read_only(&self) -> bool {
+        self.file_io_data.disk_full.load(Acquire) || self.file_io_data.page_manager.is_read_only()
+    }
+
+    /// Sets the ratio of dirty pages to the capacity of the page cache in percent above which
+    /// the background flusher writes back dirty pages, or disables the flusher if `None` is
+    /// specified.
+    ///
+    /// The flusher periodically writes back dirty pages in ascending address order until the
+    /// ratio falls to half the threshold, coalescing adjacent pages into single writes, so that
+    /// fewer dirty pages have to be written back when evicted or when the database is shut down.
+    /// The default value is `25`, and values greater than `100` are regarded as `100`.
+    #[inline]
+    pub fn set_dirty_page_threshold(&self, threshold: Option<u8>) {
+        self.file_io_data
+            .page_manager
+            .set_dirty_page_threshold(threshold);
+    }
+
+    /// Sets the number of threads replaying log records during recovery.
+    ///
+    /// Database object changes in the log are partitioned by database object identifier and
+    /// replayed by the specified number of threads, whereas transaction state changes are applied
+    /// by the thread reading the log. The default value is the available parallelism of the
+    /// system, and the value must be set before the database is recovered.
+    #[inline]
+    pub fn set_recovery_parallelism(&self, parallelism: NonZeroUsize) {
+        self.file_io_data
+            .recovery_parallelism
+            .store(parallelism.get(), Relaxed);
+    }
+
+    /// Replays the log segments archived in the specified path over the full backup in the base
+    /// path up to the specified commit clock, and returns the new clock of the full backup.
+    ///
+    /// The archived log segments following the log of the full backup are appended to it until a
+    /// segment is missing or incomplete, and then the log is truncated at the first commit log
+    /// record of which the commit clock is greater than `clock`; transactions committed after
+    /// the clock are rolled back when the full backup is opened. The most recent log segment may
+    /// also be copied into the archive path in order to replay the log records in it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::WrongParameter`] if the database files of the full backup are encrypted
+    /// or not split into segments, or the full backup contains transactions committed after the
+    /// clock, [`Error::CorruptDatabase`] if an archived log segment is too long, and an error if
+    /// the files could not be read or written.
+    #[inline]
+    pub fn restore_to(base: &Path, archive: &Path, clock: S::Instant) -> Result<u64, Error> {
+        backup::restore_to::<S>(base, archive, clock)
     }
 
     /// Opens the specified file.
     fn open_file(
         path_buffer: &mut PathBuf,
         file_name: &'static str,
+        io_backend: IOBackend,
+        options: &OpenOptions,
     ) -> Result<RandomAccessFile, Error> {
         path_buffer.push(Path::new(file_name));
-        let file = RandomAccessFile::from_file(path_buffer.as_path())?;
+        let file = if options.read_only {
+            RandomAccessFile::read_only(path_buffer.as_path(), io_backend)
+        } else {
+            RandomAccessFile::with_durability(path_buffer.as_path(), io_backend, options.durability)
+        };
         path_buffer.pop();
-        Ok(file)
+        file
     }
 
-    /// Pushes a [`FileLogBuffer`] into the log buffer linked list.
-    fn push_log_buffer(log_buffer_link: &AtomicUsize, log_buffer_ptr: *const FileLogBuffer) {
+    /// Pushes a chain of [`FileLogBuffer`] instances into the log buffer linked list.
+    ///
+    /// `last_ptr` has to reach `first_ptr` by following `next`, and the whole chain is pushed at
+    /// once, therefore the log buffers in the chain are consumed in order without any other log
+    /// buffers in between.
+    fn push_log_buffer(
+        log_buffer_link: &AtomicUsize,
+        first_ptr: *const FileLogBuffer,
+        last_ptr: *const FileLogBuffer,
+    ) {
         let mut head = log_buffer_link.load(Acquire);
         loop {
             // SAFETY: it assumes that the caller provided a valid pointer.
-            let log_buffer = unsafe { &*log_buffer_ptr };
+            let log_buffer = unsafe { &*first_ptr };
             debug_assert_ne!(log_buffer.bytes_written.load(Relaxed), 0);
             log_buffer.next.store(head, Relaxed);
 
             // `Acquire` is needed to correctly load `batch_sequence_number` afterwards.
             if let Err(actual) =
-                log_buffer_link.compare_exchange(head, log_buffer_ptr as usize, AcqRel, Acquire)
+                log_buffer_link.compare_exchange(head, last_ptr as usize, AcqRel, Acquire)
             {
                 head = actual;
             } else {
@@ -197,94 +866,32 @@ impl<S: Sequencer<Instant = u64>> FileIO<S> {
     }
 
     /// Flushes a log buffer.
-    fn flush(&self, log_buffer: Arc<FileLogBuffer>, deadline: Option<Instant>) -> AwaitIO<S, Self> {
+    fn flush(
+        &self,
+        log_buffer: Arc<FileLogBuffer>,
+        deadline: Option<Instant>,
+    ) -> AwaitIO<'_, S, Self> {
         let log_buffer_clone = log_buffer.clone();
         let file_log_buffer_ptr = Arc::into_raw(log_buffer);
-        Self::push_log_buffer(&self.file_io_data.log_buffer_link, file_log_buffer_ptr);
+        Self::push_log_buffer(
+            &self.file_io_data.log_buffer_link,
+            file_log_buffer_ptr,
+            file_log_buffer_ptr,
+        );
         drop(self.file_io_task_sender.try_send(IOTask::Flush));
         AwaitIO::with_log_buffer(self, log_buffer_clone, deadline)
     }
-}
-
-impl<S: Sequencer<Instant = u64>> Drop for FileIO<S> {
-    #[inline]
-    fn drop(&mut self) {
-        loop {
-            match self.file_io_task_sender.try_send(IOTask::Shutdown) {
-                Ok(_) | Err(TrySendError::Disconnected(_)) => break,
-                _ => (),
-            }
-        }
-        if let Some(worker) = self.file_io_worker.take() {
-            drop(worker.join());
-        }
-    }
-}
 
-impl<S: Sequencer<Instant = u64>> PersistenceLayer<S> for FileIO<S> {
-    type LogBuffer = FileLogBuffer;
-
-    #[inline]
-    fn wait_prepare_logging() -> bool {
-        // Log records do not represent full database change history.
-        true
-    }
-
-    #[inline]
-    fn recover(
-        &self,
-        database: Database<S, Self>,
-        until: Option<u64>,
-        deadline: Option<Instant>,
-    ) -> Result<AwaitRecovery<S, Self>, Error> {
-        if let Ok(mut recovery_data) = self.file_io_data.recovery_data.lock() {
-            debug_assert!(recovery_data.is_none());
-            recovery_data.replace(Box::new(RecoveryData::new(database, until)));
-        } else {
-            // Locking unexpectedly failed.
-            return Err(Error::UnexpectedState);
-        }
-        if self.file_io_task_sender.try_send(IOTask::Recover).is_err() {
-            // `Recover` must be the first request.
-            return Err(Error::UnexpectedState);
-        }
-
-        Ok(AwaitRecovery {
-            persistence_layer: self,
-            deadline,
-            _phantom: PhantomData,
-        })
-    }
-
-    #[inline]
-    fn backup(
-        &self,
-        _database: &Database<S, Self>,
-        _catalog_only: bool,
-        _path: Option<&str>,
-        _deadline: Option<Instant>,
-    ) -> AwaitIO<S, Self> {
-        todo!()
-    }
-
-    #[inline]
-    fn participate(
-        &self,
-        _id: TransactionID,
-        _xid: &[u8],
-        _deadline: Option<Instant>,
-    ) -> AwaitIO<S, Self> {
-        todo!()
-    }
-
-    #[inline]
-    fn create(
-        &self,
-        mut log_buffer: Arc<Self::LogBuffer>,
+    /// Generates log records of the database objects having been created.
+    ///
+    /// Consecutive database object identifiers with the same interval are coalesced into a single
+    /// log record.
+    fn created_log_records(
         transaction_id: TransactionID,
         journal_id: JournalID,
         object_ids: &[u64],
-    ) -> Result<Arc<Self::LogBuffer>, Error> {
+    ) -> Vec<LogRecord<S>> {
+        let mut log_records = Vec::new();
         let mut current_log: Option<LogRecord<S>> = None;
         for id in object_ids {
             let new_log = if let Some(log) = current_log.take() {
@@ -327,124 +934,657 @@ impl<S: Sequencer<Instant = u64>> PersistenceLayer<S> for FileIO<S> {
                     }
                     _ => None,
                 };
-                new_log.map_or_else(
-                    || {
-                        let bytes_written =
-                            if let Some(bytes_written) = log.write(log_buffer.buffer_mut()) {
-                                bytes_written
-                            } else {
-                                // The log buffer is full, therefore flush it.
-                                self.flush(take(&mut log_buffer), None);
-                                log.write(log_buffer.buffer_mut()).unwrap()
-                            };
-                        log_buffer.set_buffer_position(log_buffer.pos() + bytes_written);
-                        LogRecord::JournalCreatedObjectSingle(transaction_id, journal_id, *id)
-                    },
-                    |l| l,
-                )
+                new_log.unwrap_or_else(|| {
+                    log_records.push(log);
+                    LogRecord::JournalCreatedObjectSingle(transaction_id, journal_id, *id)
+                })
             } else {
                 LogRecord::JournalCreatedObjectSingle(transaction_id, journal_id, *id)
             };
             current_log.replace(new_log);
         }
 
-        if let Some(log) = current_log {
-            let bytes_written = if let Some(bytes_written) = log.write(log_buffer.buffer_mut()) {
-                bytes_written
-            } else {
-                // The log buffer is full, therefore flush it.
-                self.flush(take(&mut log_buffer), None);
-                log.write(log_buffer.buffer_mut()).unwrap()
-            };
-            log_buffer.set_buffer_position(log_buffer.pos() + bytes_written);
+        if let Some(log) = current_log {
+            log_records.push(log);
+        }
+        log_records
+    }
+
+    /// Generates log records of the database objects having been deleted.
+    ///
+    /// Consecutive database object identifiers with the same interval are coalesced into a single
+    /// log record.
+    fn deleted_log_records(
+        transaction_id: TransactionID,
+        journal_id: JournalID,
+        object_ids: &[u64],
+    ) -> Vec<LogRecord<S>> {
+        let mut log_records = Vec::new();
+        let mut current_log: Option<LogRecord<S>> = None;
+        for id in object_ids {
+            let new_log = if let Some(log) = current_log.take() {
+                let new_log = match log {
+                    LogRecord::JournalDeletedObjectSingle(_, _, prev_id) => {
+                        if let Some(interval) = id.checked_sub(prev_id) {
+                            if let Ok(interval) = u32::try_from(interval) {
+                                Some(LogRecord::JournalDeletedObjectRange(
+                                    transaction_id,
+                                    journal_id,
+                                    prev_id,
+                                    interval,
+                                    2,
+                                ))
+                            } else {
+                                None
+                            }
+                        } else {
+                            None
+                        }
+                    }
+                    LogRecord::JournalDeletedObjectRange(_, _, start_id, interval, num_objects) => {
+                        if let Some(diff) = id.checked_sub(start_id) {
+                            if num_objects != u32::MAX
+                                && diff == u64::from(interval) * u64::from(num_objects)
+                            {
+                                Some(LogRecord::JournalDeletedObjectRange(
+                                    transaction_id,
+                                    journal_id,
+                                    start_id,
+                                    interval,
+                                    num_objects + 1,
+                                ))
+                            } else {
+                                None
+                            }
+                        } else {
+                            None
+                        }
+                    }
+                    _ => None,
+                };
+                new_log.unwrap_or_else(|| {
+                    log_records.push(log);
+                    LogRecord::JournalDeletedObjectSingle(transaction_id, journal_id, *id)
+                })
+            } else {
+                LogRecord::JournalDeletedObjectSingle(transaction_id, journal_id, *id)
+            };
+            current_log.replace(new_log);
+        }
+
+        if let Some(log) = current_log {
+            log_records.push(log);
+        }
+        log_records
+    }
+
+    /// Returns the size of log records of the supplied database objects being created or deleted.
+    ///
+    /// The log records of an encrypted database may be written in a frame of their own.
+    fn log_size(
+        &self,
+        transaction_id: TransactionID,
+        journal_id: JournalID,
+        object_ids: &[u64],
+    ) -> u64 {
+        // Log records of created and deleted database objects are of the same size.
+        let log_size: usize = Self::created_log_records(transaction_id, journal_id, object_ids)
+            .iter()
+            .map(LogRecord::size)
+            .sum();
+        if self.file_io_data.log.encryption().is_some() {
+            log_size as u64 + FRAME_OVERHEAD
+        } else {
+            log_size as u64
+        }
+    }
+
+    /// Records the length of the log when the transaction starts generating log records.
+    ///
+    /// Log records of the transaction are written at or after the offset, and the offset is
+    /// forgotten when the transaction is ended.
+    fn track_transaction(&self, transaction_id: TransactionID) {
+        let transaction_log_offsets = &self.file_io_data.transaction_log_offsets;
+        if !transaction_log_offsets.contains(&transaction_id) {
+            let _: Result<(), (TransactionID, u64)> =
+                transaction_log_offsets.insert(transaction_id, self.file_io_data.log.len(Acquire));
+        }
+    }
+
+    /// Reserves log space for log records of the specified size.
+    ///
+    /// Returns an error if the log capacity would be exceeded.
+    fn reserve_log_bytes(&self, size: u64) -> Result<(), Error> {
+        if self.file_io_data.disk_full.load(Acquire) {
+            return Err(Error::DiskFull);
+        }
+        if let Some(error) = self.file_io_data.write_error() {
+            return Err(error);
+        }
+        let log_capacity = self
+            .file_io_data
+            .log_capacity
+            .load(Relaxed)
+            .saturating_add(self.file_io_data.log_head.load(Relaxed));
+        self.file_io_data
+            .log_reserved
+            .fetch_update(Relaxed, Relaxed, |reserved| {
+                // Reserved log space that has been written is already included in the length.
+                let reserved = reserved.max(self.file_io_data.log.len(Relaxed));
+                reserved
+                    .checked_add(size)
+                    .filter(|reserved| *reserved <= log_capacity)
+            })
+            .map(|_| ())
+            .map_err(|_| Error::DiskFull)
+    }
+
+    /// Writes the log records into log buffers.
+    ///
+    /// Log buffers filled up by the log records are flushed at once, so that the log records are
+    /// written to a contiguous region of the log file without log records of other transactions
+    /// in between.
+    fn write_log_records(
+        &self,
+        mut log_buffer: Arc<FileLogBuffer>,
+        log_records: &[LogRecord<S>],
+    ) -> Arc<FileLogBuffer> {
+        let mut full_log_buffers = Vec::new();
+        for log in log_records {
+            let bytes_written = if let Some(bytes_written) = log.write(log_buffer.buffer_mut()) {
+                bytes_written
+            } else {
+                // The log buffer is full, therefore set it aside.
+                full_log_buffers.push(take(&mut log_buffer));
+                log.write(log_buffer.buffer_mut()).unwrap()
+            };
+            log_buffer.set_buffer_position(log_buffer.pos() + bytes_written);
+        }
+        self.push_log_buffers(full_log_buffers);
+        log_buffer
+    }
+
+    /// Writes the log record and the payload following it into log buffers.
+    ///
+    /// Every log buffer used in the method is flushed at once including the last one, so that the
+    /// payload directly follows the log record in the log file, and a new log buffer is returned.
+    fn write_payload_log_record(
+        &self,
+        mut log_buffer: Arc<FileLogBuffer>,
+        log_record: &LogRecord<S>,
+        mut payload: &[u8],
+    ) -> Arc<FileLogBuffer> {
+        let mut log_buffers = Vec::new();
+        let bytes_written = if let Some(bytes_written) = log_record.write(log_buffer.buffer_mut()) {
+            bytes_written
+        } else {
+            log_buffers.push(take(&mut log_buffer));
+            log_record.write(log_buffer.buffer_mut()).unwrap()
+        };
+        log_buffer.set_buffer_position(log_buffer.pos() + bytes_written);
+        loop {
+            let buffer = log_buffer.buffer_mut();
+            let len = buffer.len().min(payload.len());
+            buffer[..len].copy_from_slice(&payload[..len]);
+            log_buffer.set_buffer_position(log_buffer.pos() + len);
+            payload = &payload[len..];
+            log_buffers.push(take(&mut log_buffer));
+            if payload.is_empty() {
+                break;
+            }
+        }
+        self.push_log_buffers(log_buffers);
+        log_buffer
+    }
+
+    /// Pushes the log buffers into the log buffer linked list at once, and flushes them.
+    fn push_log_buffers(&self, log_buffers: Vec<Arc<FileLogBuffer>>) {
+        let mut log_buffers = log_buffers.into_iter();
+        if let Some(first) = log_buffers.next() {
+            let first_ptr = Arc::into_raw(first);
+            let last_ptr = log_buffers.fold(first_ptr, |prev_ptr, log_buffer| {
+                log_buffer.next.store(prev_ptr as usize, Relaxed);
+                Arc::into_raw(log_buffer)
+            });
+            Self::push_log_buffer(&self.file_io_data.log_buffer_link, first_ptr, last_ptr);
+            drop(self.file_io_task_sender.try_send(IOTask::Flush));
+        }
+    }
+
+    /// Returns the size of the log record of the version including its payload.
+    ///
+    /// Returns [`Error::WrongParameter`] if the key or value is too large to be logged.
+    fn version_log_size(&self, version: &VersionRecord<'_>) -> Result<u64, Error> {
+        let payload_len = log_record::version_payload_len(version).ok_or(Error::WrongParameter)?;
+        let log_size =
+            LogRecord::<S>::JournalWroteVersion(0, 0, 0, 0).size() as u64 + u64::from(payload_len);
+        if self.file_io_data.log.encryption().is_some() {
+            Ok(log_size + FRAME_OVERHEAD)
+        } else {
+            Ok(log_size)
+        }
+    }
+
+    /// Writes the catalog entries visible to the current snapshot to new container directory
+    /// pages along with an index of each container, and frees the pages of the previous
+    /// checkpoint once the head page is written.
+    async fn write_checkpoint(
+        &self,
+        database: &Database<S, Self>,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        // Log records before the offset belong to transactions that were ended before the
+        // snapshot is taken, or open transactions that started generating log records after them.
+        let mut log_start = self.file_io_data.log.len(Acquire);
+        self.file_io_data
+            .transaction_log_offsets
+            .scan(|_, offset| log_start = log_start.min(*offset));
+        let snapshot = database.snapshot();
+        let (next_object_id, next_transaction_id) = database.next_ids();
+        let mut directory = ContainerDirectory {
+            clock: snapshot.database_snapshot(),
+            next_object_id,
+            next_transaction_id,
+            log_start,
+            catalog: Vec::new(),
+            invisible_objects: Vec::new(),
+            indexes: Vec::new(),
+        };
+        database
+            .scan_catalog_versions(&snapshot, deadline, |v| directory.catalog.push(v.into()))
+            .await?;
+        directory.invisible_objects = database
+            .access_controller()
+            .invisible_objects(&snapshot, deadline)
+            .await?;
+        let page_manager = self.page_manager();
+        let mut persisted_pages = Vec::new();
+        let result = async {
+            for (container_id, metadata) in database.visible_containers(&snapshot, deadline).await?
+            {
+                let mut records = Vec::new();
+                database
+                    .scan_container_versions(container_id, &snapshot, deadline, |v| {
+                        records.push(v.into());
+                    })
+                    .await?;
+                if records.is_empty() {
+                    persisted_pages.push((container_id, 0));
+                    continue;
+                }
+                let index_type = metadata.index_type();
+                let index =
+                    ContainerIndex::write(page_manager, container_id, index_type, records).await?;
+                let num_pages = index.num_pages(page_manager).await;
+                directory.indexes.push(index);
+                persisted_pages.push((container_id, num_pages?));
+            }
+            Ok(())
+        }
+        .await;
+        if let Err(error) = result {
+            Self::free_indexes(page_manager, take(&mut directory.indexes)).await;
+            return Err(error);
+        }
+
+        let (payload, pages) = match directory.write(page_manager).await {
+            Ok(written) => written,
+            Err(error) => {
+                Self::free_indexes(page_manager, directory.indexes).await;
+                return Err(error);
+            }
+        };
+        let log_buffer = Arc::<FileLogBuffer>::default();
+        if self
+            .file_io_task_sender
+            .send(IOTask::Checkpoint(payload, log_start, log_buffer.clone()))
+            .is_err()
+        {
+            drop(ContainerDirectory::free(page_manager, &pages).await);
+            Self::free_indexes(page_manager, directory.indexes).await;
+            return Err(Error::UnexpectedState);
+        }
+
+        // It is unknown which pages the head page refers to until the request is processed.
+        AwaitIO::with_log_buffer(self, log_buffer, None).await?;
+
+        // Containers reading the old indexes are waited for before the new indexes are
+        // installed, and the old indexes are freed after they are unlocked since containers only
+        // read the new indexes from then on. The snapshot is kept until the new indexes are
+        // installed, so that the garbage collector does not remove keys that are in the new
+        // indexes from memory.
+        self.file_io_data.lock_indexes().await;
+        let installed = (|| {
+            let old_pages = self
+                .file_io_data
+                .container_directory_pages
+                .lock()
+                .map(|mut guard| replace(&mut *guard, pages))
+                .map_err(|_| Error::UnexpectedState)?;
+            let old_indexes = self
+                .file_io_data
+                .container_indexes
+                .lock()
+                .map(|mut guard| replace(&mut *guard, directory.indexes))
+                .map_err(|_| Error::UnexpectedState)?;
+            Ok::<_, Error>((old_pages, old_indexes))
+        })();
+        self.file_io_data.unlock_indexes();
+        drop(snapshot);
+        let (old_pages, old_indexes) = installed?;
+        for (container_id, num_pages) in persisted_pages {
+            database.set_persisted_pages(container_id, num_pages);
+        }
+        ContainerDirectory::free(page_manager, &old_pages).await?;
+        for index in old_indexes {
+            index.free(page_manager).await?;
+        }
+        Ok(())
+    }
+
+    /// Waits for the checkpoint being taken to finish, and prevents other checkpoints from being
+    /// taken until [`Self::unlock_checkpoint`] is called.
+    async fn lock_checkpoint(&self) {
+        poll_fn(|cx| {
+            if !self.file_io_data.checkpointing.swap(true, Acquire) {
+                return Poll::Ready(());
+            }
+
+            // Push the `Waker` into the bag, and try again.
+            self.file_io_data.waker_bag.push(cx.waker().clone());
+            if self.file_io_data.checkpointing.swap(true, Acquire) {
+                Poll::Pending
+            } else {
+                Poll::Ready(())
+            }
+        })
+        .await;
+    }
+
+    /// Allows other checkpoints to be taken, and wakes up tasks waiting for the checkpoint lock.
+    fn unlock_checkpoint(&self) {
+        self.file_io_data.checkpointing.store(false, Release);
+        self.file_io_data.waker_bag.pop_all((), |(), w| w.wake());
+    }
+
+    /// Moves the pages of the container indexes written by the latest checkpoint to free pages
+    /// nearer the front of the database file.
+    ///
+    /// Containers do not read the indexes while the pages are moved.
+    async fn compact_indexes(&self) -> Result<(), Error> {
+        let indexes = self
+            .file_io_data
+            .container_indexes
+            .lock()
+            .map(|guard| guard.clone())
+            .map_err(|_| Error::UnexpectedState)?;
+        let page_manager = self.page_manager();
+        self.file_io_data.lock_indexes().await;
+        let result = async {
+            for index in indexes {
+                index.compact(page_manager).await?;
+            }
+            Ok(())
+        }
+        .await;
+        self.file_io_data.unlock_indexes();
+        result
+    }
+
+    /// Frees container indexes that are not referenced by the container directory.
+    ///
+    /// Pages that could not be freed are reclaimed when the database is recovered from a crash.
+    async fn free_indexes(page_manager: &PageManager, indexes: Vec<ContainerIndex>) {
+        for index in indexes {
+            drop(index.free(page_manager).await);
+        }
+    }
+
+    /// Creates a [`BackupTarget`] in the path, and attaches the [`FaultyFile`] for backups to it.
+    fn backup_target(
+        &self,
+        path: &Path,
+        base_clock: Option<u64>,
+        segment_size: u64,
+    ) -> Result<Box<BackupTarget>, Error> {
+        let target = BackupTarget::create(path, &self.path, base_clock, segment_size)?;
+        if let Some(faulty_file) = self
+            .file_io_data
+            .backup_faults
+            .lock()
+            .ok()
+            .and_then(|guard| guard.clone())
+        {
+            target.set_faulty_file(&faulty_file);
+        }
+        Ok(target)
+    }
+
+    /// Returns the result of the background IO operation completed in the flush epoch.
+    fn io_result(&self, flush_epoch: u64) -> Result<bool, Error> {
+        match self.file_io_data.io_errors.remove(&flush_epoch) {
+            Some((_, error)) => Err(error),
+            None => Ok(true),
+        }
+    }
+}
+
+impl<S: Sequencer<Instant = u64>> Drop for FileIO<S> {
+    #[inline]
+    fn drop(&mut self) {
+        loop {
+            match self.file_io_task_sender.try_send(IOTask::Shutdown) {
+                Ok(()) | Err(TrySendError::Disconnected(_)) => break,
+                _ => (),
+            }
+        }
+        if let Some(worker) = self.file_io_worker.take() {
+            drop(worker.join());
+        }
+    }
+}
+
+impl<S: Sequencer<Instant = u64>> PersistenceLayer<S> for FileIO<S> {
+    type LogBuffer = FileLogBuffer;
+    type PersistedIndex = FileIndex<S>;
+
+    #[inline]
+    fn wait_prepare_logging() -> bool {
+        // Log records do not represent full database change history.
+        true
+    }
+
+    #[inline]
+    fn recover(
+        &self,
+        database: Database<S, Self>,
+        until: Option<u64>,
+        deadline: Option<Instant>,
+    ) -> Result<AwaitRecovery<'_, S, Self>, Error> {
+        if let Ok(mut recovery_data) = self.file_io_data.recovery_data.lock() {
+            debug_assert!(recovery_data.is_none());
+            recovery_data.replace(Box::new(RecoveryData::new(database, until)));
+        } else {
+            // Locking unexpectedly failed.
+            return Err(Error::UnexpectedState);
+        }
+        if self.file_io_task_sender.try_send(IOTask::Recover).is_err() {
+            // `Recover` must be the first request.
+            return Err(Error::UnexpectedState);
+        }
+
+        Ok(AwaitRecovery::new(self, deadline))
+    }
+
+    /// Backs up the database files into the specified path, or `backup` in the database path.
+    ///
+    /// Writers are not blocked while the database files are being copied, and the backup contains
+    /// every transaction of which the commit log record had been generated before the method was
+    /// called. The backed up database can be opened by [`FileIO::with_path`] with the recovered
+    /// logical clock that is not newer than the current one. `catalog_only` is ignored since the
+    /// database file and the log are copied as a whole.
+    #[inline]
+    fn backup(
+        &self,
+        _database: &Database<S, Self>,
+        _catalog_only: bool,
+        path: Option<&str>,
+        deadline: Option<Instant>,
+    ) -> AwaitIO<'_, S, Self> {
+        let path = path.map_or_else(|| self.path.join("backup"), PathBuf::from);
+        let target = match self.backup_target(&path, None, self.page_manager().segment_size()) {
+            Ok(target) => target,
+            Err(error) => return AwaitIO::with_error(self, error),
+        };
+        let log_buffer = Arc::<FileLogBuffer>::default();
+        if self
+            .file_io_task_sender
+            .send(IOTask::Backup(target, log_buffer.clone()))
+            .is_err()
+        {
+            return AwaitIO::with_error(self, Error::UnexpectedState);
+        }
+        AwaitIO::with_log_buffer(self, log_buffer, deadline)
+    }
+
+    /// Writes back dirty pages, and persists the catalog entries visible to the current snapshot
+    /// in the container directory pages.
+    ///
+    /// The container directory head page is written at last, therefore the previous checkpoint
+    /// is used if the database is not shut down cleanly before the checkpoint is complete. The
+    /// catalog is played back from the latest checkpoint when the database is recovered, and the
+    /// log records are replayed over it. Checkpoints are serialized, and nothing is written if the
+    /// database is read-only.
+    #[inline]
+    async fn checkpoint(
+        &self,
+        database: &Database<S, Self>,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        if self.page_manager().is_read_only() {
+            return Ok(());
+        }
+        self.lock_checkpoint().await;
+        let result = self.write_checkpoint(database, deadline).await;
+        self.unlock_checkpoint();
+        result
+    }
+
+    /// Flushes the log, writes back dirty pages, and sets the clean shutdown marker along with
+    /// the current clock in the header of the database file.
+    ///
+    /// The torn page scan is skipped when the database is recovered if the marker is intact.
+    #[inline]
+    fn shutdown(
+        &self,
+        database: &Database<S, Self>,
+        deadline: Option<Instant>,
+    ) -> AwaitIO<'_, S, Self> {
+        let clock = database.sequencer().now(Acquire);
+        let log_buffer = Arc::<FileLogBuffer>::default();
+        if self
+            .file_io_task_sender
+            .send(IOTask::CleanShutdown(clock, log_buffer.clone()))
+            .is_err()
+        {
+            return AwaitIO::with_error(self, Error::UnexpectedState);
+        }
+        AwaitIO::with_log_buffer(self, log_buffer, deadline)
+    }
+
+    #[inline]
+    fn participate(
+        &self,
+        _id: TransactionID,
+        _xid: &[u8],
+        _deadline: Option<Instant>,
+    ) -> AwaitIO<'_, S, Self> {
+        todo!()
+    }
+
+    #[inline]
+    fn reserve_log_space(
+        &self,
+        transaction_id: TransactionID,
+        journal_id: JournalID,
+        object_ids: &[u64],
+    ) -> Result<(), Error> {
+        self.reserve_log_bytes(self.log_size(transaction_id, journal_id, object_ids))
+    }
+
+    #[inline]
+    fn release_log_space(
+        &self,
+        transaction_id: TransactionID,
+        journal_id: JournalID,
+        object_ids: &[u64],
+    ) {
+        let size = self.log_size(transaction_id, journal_id, object_ids);
+        let log_len = self.file_io_data.log.len(Relaxed);
+        let _: Result<u64, u64> =
+            self.file_io_data
+                .log_reserved
+                .fetch_update(Relaxed, Relaxed, |reserved| {
+                    // Reserved log space that has been written is already included in the length.
+                    Some(reserved.saturating_sub(size).max(log_len))
+                });
+    }
+
+    #[inline]
+    fn create(
+        &self,
+        log_buffer: Arc<Self::LogBuffer>,
+        transaction_id: TransactionID,
+        journal_id: JournalID,
+        object_ids: &[u64],
+    ) -> Result<Arc<Self::LogBuffer>, Error> {
+        self.track_transaction(transaction_id);
+        let log_records = Self::created_log_records(transaction_id, journal_id, object_ids);
+        Ok(self.write_log_records(log_buffer, &log_records))
+    }
+
+    #[inline]
+    fn reserve_version_log_space(&self, version: &VersionRecord<'_>) -> Result<(), Error> {
+        // Keys must fit in container index pages to be persisted by checkpoints.
+        if version.container_id() != CATALOG_ID
+            && !ContainerIndex::fits(self.page_manager(), version.key())
+        {
+            return Err(Error::WrongParameter);
         }
+        self.reserve_log_bytes(self.version_log_size(version)?)
+    }
 
-        Ok(log_buffer)
+    #[inline]
+    fn write(
+        &self,
+        log_buffer: Arc<Self::LogBuffer>,
+        transaction_id: TransactionID,
+        journal_id: JournalID,
+        version: &VersionRecord<'_>,
+    ) -> Result<Arc<Self::LogBuffer>, Error> {
+        let payload_len = log_record::version_payload_len(version).ok_or(Error::WrongParameter)?;
+        let payload = log_record::encode_version(version).ok_or(Error::WrongParameter)?;
+        self.track_transaction(transaction_id);
+        let log_record = LogRecord::JournalWroteVersion(
+            transaction_id,
+            journal_id,
+            version.object_id(),
+            payload_len,
+        );
+        Ok(self.write_payload_log_record(log_buffer, &log_record, &payload))
     }
 
     #[inline]
     fn delete(
         &self,
-        mut log_buffer: Arc<Self::LogBuffer>,
+        log_buffer: Arc<Self::LogBuffer>,
         transaction_id: TransactionID,
         journal_id: JournalID,
         object_ids: &[u64],
     ) -> Result<Arc<Self::LogBuffer>, Error> {
-        let mut current_log: Option<LogRecord<S>> = None;
-        for id in object_ids {
-            let new_log = if let Some(log) = current_log.take() {
-                let new_log = match log {
-                    LogRecord::JournalDeletedObjectSingle(_, _, prev_id) => {
-                        if let Some(interval) = id.checked_sub(prev_id) {
-                            if let Ok(interval) = u32::try_from(interval) {
-                                Some(LogRecord::JournalDeletedObjectRange(
-                                    transaction_id,
-                                    journal_id,
-                                    prev_id,
-                                    interval,
-                                    2,
-                                ))
-                            } else {
-                                None
-                            }
-                        } else {
-                            None
-                        }
-                    }
-                    LogRecord::JournalDeletedObjectRange(_, _, start_id, interval, num_objects) => {
-                        if let Some(diff) = id.checked_sub(start_id) {
-                            if num_objects != u32::MAX
-                                && diff == u64::from(interval) * u64::from(num_objects)
-                            {
-                                Some(LogRecord::JournalDeletedObjectRange(
-                                    transaction_id,
-                                    journal_id,
-                                    start_id,
-                                    interval,
-                                    num_objects + 1,
-                                ))
-                            } else {
-                                None
-                            }
-                        } else {
-                            None
-                        }
-                    }
-                    _ => None,
-                };
-                new_log.map_or_else(
-                    || {
-                        let bytes_written =
-                            if let Some(bytes_written) = log.write(log_buffer.buffer_mut()) {
-                                bytes_written
-                            } else {
-                                // The log buffer is full, therefore flush it.
-                                self.flush(take(&mut log_buffer), None);
-                                log.write(log_buffer.buffer_mut()).unwrap()
-                            };
-                        log_buffer.set_buffer_position(log_buffer.pos() + bytes_written);
-                        LogRecord::JournalDeletedObjectSingle(transaction_id, journal_id, *id)
-                    },
-                    |l| l,
-                )
-            } else {
-                LogRecord::JournalDeletedObjectSingle(transaction_id, journal_id, *id)
-            };
-            current_log.replace(new_log);
-        }
-
-        if let Some(log) = current_log {
-            let bytes_written = if let Some(bytes_written) = log.write(log_buffer.buffer_mut()) {
-                bytes_written
-            } else {
-                // The log buffer is full, therefore flush it.
-                self.flush(take(&mut log_buffer), None);
-                log.write(log_buffer.buffer_mut()).unwrap()
-            };
-            log_buffer.set_buffer_position(log_buffer.pos() + bytes_written);
-        }
-
-        Ok(log_buffer)
+        self.track_transaction(transaction_id);
+        let log_records = Self::deleted_log_records(transaction_id, journal_id, object_ids);
+        Ok(self.write_log_records(log_buffer, &log_records))
     }
 
     #[inline]
@@ -515,6 +1655,12 @@ impl<S: Sequencer<Instant = u64>> PersistenceLayer<S> for FileIO<S> {
             unreachable!("logic error");
         };
         log_buffer.set_buffer_position(new_pos);
+        if transaction_instant.is_none() {
+            let _: Option<(TransactionID, u64)> = self
+                .file_io_data
+                .transaction_log_offsets
+                .remove(&transaction_id);
+        }
         self.flush(log_buffer, deadline);
     }
 
@@ -525,7 +1671,8 @@ impl<S: Sequencer<Instant = u64>> PersistenceLayer<S> for FileIO<S> {
         transaction_id: TransactionID,
         prepare_instant: u64,
         deadline: Option<Instant>,
-    ) -> AwaitIO<S, Self> {
+    ) -> AwaitIO<'_, S, Self> {
+        self.track_transaction(transaction_id);
         let Some(new_pos) = LogRecord::<S>::TransactionPrepared(transaction_id, prepare_instant)
             .write(log_buffer.buffer_mut())
         else {
@@ -542,13 +1689,18 @@ impl<S: Sequencer<Instant = u64>> PersistenceLayer<S> for FileIO<S> {
         transaction_id: TransactionID,
         commit_instant: u64,
         deadline: Option<Instant>,
-    ) -> AwaitIO<S, Self> {
+    ) -> AwaitIO<'_, S, Self> {
         let Some(new_pos) = LogRecord::<S>::TransactionCommitted(transaction_id, commit_instant)
             .write(log_buffer.buffer_mut())
         else {
             unreachable!("logic error");
         };
         log_buffer.set_buffer_position(new_pos);
+        // The changes of the transaction are visible to snapshots taken afterwards.
+        let _: Option<(TransactionID, u64)> = self
+            .file_io_data
+            .transaction_log_offsets
+            .remove(&transaction_id);
         self.flush(log_buffer, deadline)
     }
 
@@ -558,24 +1710,34 @@ impl<S: Sequencer<Instant = u64>> PersistenceLayer<S> for FileIO<S> {
     }
 
     #[inline]
-    fn check_io_completion(&self, expected_flush_epoch: Option<NonZeroU64>, waker: &Waker) -> bool {
+    fn check_io_completion(
+        &self,
+        expected_flush_epoch: Option<NonZeroU64>,
+        waker: &Waker,
+    ) -> Result<bool, Error> {
         let expected_flush_epoch = expected_flush_epoch.map_or(0, NonZeroU64::get);
-        if expected_flush_epoch == 0 {
-            self.file_io_data.waker_bag.push(waker.clone());
-            return false;
-        }
-
-        if self.file_io_data.flush_epoch.load(Acquire) >= expected_flush_epoch {
-            return true;
+        if expected_flush_epoch != 0
+            && self.file_io_data.flush_epoch.load(Acquire) >= expected_flush_epoch
+        {
+            return self.io_result(expected_flush_epoch);
         }
 
         // Push the `Waker` into the bag, and check the value again.
         self.file_io_data.waker_bag.push(waker.clone());
-        if self.file_io_data.flush_epoch.load(Relaxed) >= expected_flush_epoch {
-            return true;
+        if let Some(error) = self
+            .file_io_data
+            .write_error()
+            .filter(|e| *e != Error::ReadOnly)
+        {
+            return Err(error);
+        }
+        if expected_flush_epoch != 0
+            && self.file_io_data.flush_epoch.load(Relaxed) >= expected_flush_epoch
+        {
+            return self.io_result(expected_flush_epoch);
         }
 
-        false
+        Ok(false)
     }
 
     #[inline]
@@ -604,6 +1766,24 @@ impl<S: Sequencer<Instant = u64>> PersistenceLayer<S> for FileIO<S> {
             guard.as_mut().unwrap().cancel();
         }
     }
+
+    #[inline]
+    fn telemetry(&self) -> &Telemetry {
+        &self.file_io_data.telemetry
+    }
+
+    #[inline]
+    fn reconfigure(&self, delta: &ConfigDelta) {
+        if let Some(threshold) = delta.dirty_page_threshold {
+            self.set_dirty_page_threshold(threshold);
+        }
+        if let Some(capacity) = delta.log_capacity {
+            self.set_log_capacity(capacity);
+        }
+        if let Some(retention) = delta.log_retention {
+            self.set_log_retention(retention);
+        }
+    }
 }
 
 impl FileLogBuffer {
@@ -613,7 +1793,7 @@ impl FileLogBuffer {
     }
 
     /// Returns the current remaining buffer size.
-    fn buffer_mut<'s>(self: &'s mut Arc<Self>) -> &'s mut [u8] {
+    fn buffer_mut(self: &mut Arc<Self>) -> &mut [u8] {
         let self_mut = Arc::get_mut(self).unwrap();
         &mut self_mut.buffer[self_mut.bytes_written.load(Relaxed) as usize..]
     }
@@ -639,6 +1819,70 @@ impl FileLogBuffer {
     }
 }
 
+impl<S: Sequencer<Instant = u64>> PersistedIndexInterface for FileIndex<S> {
+    #[inline]
+    async fn scan<F: FnMut(&VersionRecord<'_>) + Send>(
+        &self,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+        limit: usize,
+        readahead: usize,
+        mut visitor: F,
+    ) -> Result<(), Error> {
+        let file_io_data = self.file_io_data.upgrade().ok_or(Error::UnexpectedState)?;
+        let _reader = file_io_data.read_indexes().await;
+        let Some(index) = file_io_data.container_index(self.container_id) else {
+            // Every key-value pair was deleted before the latest checkpoint.
+            return Ok(());
+        };
+        index
+            .scan(
+                &file_io_data.page_manager,
+                start,
+                end,
+                limit,
+                readahead,
+                |record| {
+                    visitor(&VersionRecord::new(
+                        self.container_id,
+                        record.record_id,
+                        record.object_id,
+                        &record.key,
+                        &record.value,
+                    ));
+                },
+            )
+            .await
+    }
+
+    #[inline]
+    fn may_contain_sync(&self, key: &[u8]) -> bool {
+        let Some(file_io_data) = self.file_io_data.upgrade() else {
+            return true;
+        };
+        let Some(_reader) = file_io_data.try_read_indexes() else {
+            return true;
+        };
+        file_io_data
+            .container_index(self.container_id)
+            .is_some_and(|index| {
+                index
+                    .contains_sync(&file_io_data.page_manager, key)
+                    .unwrap_or(true)
+            })
+    }
+}
+
+impl Drop for IndexReader<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        if self.0.fetch_sub(1, Release) == INDEXES_LOCKED + 1 {
+            // The last reader wakes up the task locking the container indexes.
+            self.1.pop_all((), |(), w| w.wake());
+        }
+    }
+}
+
 impl LogBufferInterface for FileLogBuffer {
     #[inline]
     fn set_durable_flush_epoch(&self, flush_epoch: u64) {
@@ -655,15 +1899,17 @@ impl LogBufferInterface for FileLogBuffer {
 
 #[cfg(test)]
 mod test {
+    use super::evictable_page::EvictablePage;
     use super::*;
-    use crate::MonotonicU64;
+    use crate::{IndexType, Metadata, MonotonicU64, ShutdownPolicy};
     use static_assertions::assert_eq_size;
+    use std::sync::atomic::Ordering::Relaxed;
     use std::time::Duration;
     use tokio::fs::remove_dir_all;
 
     assert_eq_size!(FileLogBuffer, ([u64; 6], usize));
 
-    const TIMEOUT_UNEXPECTED: Duration = Duration::from_secs(60);
+    const TIMEOUT_UNEXPECTED: Duration = Duration::from_mins(1);
 
     #[tokio::test]
     async fn open_close() {
@@ -674,6 +1920,75 @@ mod test {
         assert!(remove_dir_all(path).await.is_ok());
     }
 
+    #[tokio::test]
+    async fn open_options() {
+        const DIR: &str = "file_io_open_options_test";
+        let path = Path::new(DIR);
+        let open = |options: OpenOptions| options.open::<MonotonicU64>(path);
+        assert_eq!(
+            open(OpenOptions::new().with_create(false)).err(),
+            Some(Error::Generic("the database files do not exist"))
+        );
+        for options in [
+            OpenOptions::new().with_page_size(1000),
+            OpenOptions::new().with_segment_size(1000),
+            OpenOptions::new().with_page_cache_capacity(0),
+            OpenOptions::new().with_truncate(true).with_create(false),
+            OpenOptions::new().with_truncate(true).with_migration(true),
+        ] {
+            assert!(matches!(open(options).err(), Some(Error::Generic(_))));
+        }
+        assert!(!path.exists());
+
+        let file_io = open(OpenOptions::new().with_page_size(1024)).unwrap();
+        drop(file_io);
+        let file_io = open(OpenOptions::new().with_create(false)).unwrap();
+        assert_eq!(file_io.page_size(), 1024);
+        drop(file_io);
+        assert_eq!(
+            open(OpenOptions::new().with_page_size(2048)).err(),
+            Some(Error::WrongParameter)
+        );
+
+        let file_io = open(
+            OpenOptions::new()
+                .with_truncate(true)
+                .with_page_size(2048)
+                .with_page_cache_capacity(16)
+                .with_recovery_parallelism(NonZeroUsize::MIN),
+        )
+        .unwrap();
+        assert_eq!(file_io.page_size(), 2048);
+        assert_eq!(file_io.file_io_data.recovery_parallelism.load(Relaxed), 1);
+        drop(file_io);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn file_lock() {
+        const DIR: &str = "file_io_file_lock_test";
+        let path = Path::new(DIR);
+        let read_only = OpenOptions::new().with_read_only(true);
+        let open = |options: &OpenOptions| options.open::<MonotonicU64>(path);
+        let writer = open(&OpenOptions::new()).unwrap();
+        assert_eq!(open(&OpenOptions::new()).err(), Some(Error::AlreadyInUse));
+        assert_eq!(open(&read_only).err(), Some(Error::AlreadyInUse));
+        assert!(open(&read_only.clone().with_locking(false)).is_ok());
+        assert!(matches!(
+            open(&OpenOptions::new().with_locking(false)).err(),
+            Some(Error::Generic(_))
+        ));
+        drop(writer);
+
+        let reader_1 = open(&read_only).unwrap();
+        let reader_2 = open(&read_only).unwrap();
+        assert_eq!(open(&OpenOptions::new()).err(), Some(Error::AlreadyInUse));
+        drop(reader_1);
+        drop(reader_2);
+        assert!(open(&OpenOptions::new()).is_ok());
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
     #[tokio::test]
     async fn log_buffer() {
         const DIR: &str = "file_io_log_buffer_test";
@@ -716,4 +2031,704 @@ mod test {
         drop(file_io);
         assert!(remove_dir_all(path).await.is_ok());
     }
+
+    #[tokio::test]
+    async fn backup() {
+        const DIR: &str = "file_io_backup_test";
+        const BACKUP_DIR: &str = "file_io_backup_test_backup";
+        let path = Path::new(DIR);
+        let backup_path = Path::new(BACKUP_DIR);
+        let database = Database::with_path(path).await.unwrap();
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.create(&[0, 1, 2, 3], None).await.unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        let instant = database.sequencer().now(Relaxed);
+
+        // Uncommitted changes are not backed up.
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.create(&[4, 5, 6, 7], None).await.unwrap();
+        assert_eq!(journal.submit().get(), 1);
+
+        assert_eq!(
+            database.backup(false, Some(DIR), None).await,
+            Err(Error::WrongParameter)
+        );
+        assert!(database.backup(false, Some(BACKUP_DIR), None).await.is_ok());
+        assert!(transaction.commit().await.is_ok());
+
+        let database_backup = Database::with_path(backup_path).await.unwrap();
+        assert_eq!(database_backup.sequencer().now(Relaxed), instant);
+        let snapshot = database_backup.snapshot();
+        for o in 0..4 {
+            assert_eq!(
+                database_backup
+                    .access_controller()
+                    .read(o, &snapshot, None)
+                    .await,
+                Ok(true)
+            );
+        }
+        for o in 4..8 {
+            assert_eq!(
+                database_backup
+                    .access_controller()
+                    .read(o, &snapshot, None)
+                    .await,
+                Ok(false)
+            );
+        }
+
+        drop(snapshot);
+        drop(database_backup);
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+        assert!(remove_dir_all(backup_path).await.is_ok());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn io_uring() {
+        const DIR: &str = "file_io_io_uring_test";
+        let path = Path::new(DIR);
+        let file_io = FileIO::with_io_backend(path, IOBackend::IOUring).unwrap();
+        let database: Database<MonotonicU64> =
+            Database::with_persistence_layer(file_io, None, None)
+                .await
+                .unwrap();
+        for o in 0..16 {
+            let transaction = database.transaction();
+            let mut journal = transaction.journal();
+            journal.create(&[o], None).await.unwrap();
+            assert_eq!(journal.submit().get(), 1);
+            assert!(transaction.commit().await.is_ok());
+        }
+        let page_address = database
+            .persistence_layer()
+            .page_manager()
+            .create_page(database.persistence_layer().page_size())
+            .await
+            .unwrap();
+        let instant = database.sequencer().now(Relaxed);
+        drop(database);
+
+        let database_recovered = Database::with_path(path).await.unwrap();
+        assert_eq!(database_recovered.sequencer().now(Relaxed), instant);
+        let prev_page_address = database_recovered
+            .persistence_layer()
+            .page_manager()
+            .read_page(page_address, EvictablePage::prev_page_address)
+            .await
+            .unwrap();
+        assert_eq!(
+            prev_page_address,
+            database_recovered.persistence_layer().page_size()
+        );
+
+        drop(database_recovered);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn direct() {
+        const DIR: &str = "file_io_direct_test";
+        let path = Path::new(DIR);
+        let file_io = FileIO::with_io_backend(path, IOBackend::Direct).unwrap();
+        let database: Database<MonotonicU64> =
+            Database::with_persistence_layer(file_io, None, None)
+                .await
+                .unwrap();
+        for o in 0..16 {
+            let transaction = database.transaction();
+            let mut journal = transaction.journal();
+            journal.create(&[o], None).await.unwrap();
+            assert_eq!(journal.submit().get(), 1);
+            assert!(transaction.commit().await.is_ok());
+        }
+        let page_address = database
+            .persistence_layer()
+            .page_manager()
+            .create_page(database.persistence_layer().page_size())
+            .await
+            .unwrap();
+        let instant = database.sequencer().now(Relaxed);
+        drop(database);
+
+        let file_io = FileIO::with_io_backend(path, IOBackend::Direct).unwrap();
+        let database_recovered: Database<MonotonicU64> =
+            Database::with_persistence_layer(file_io, None, None)
+                .await
+                .unwrap();
+        assert_eq!(database_recovered.sequencer().now(Relaxed), instant);
+        let prev_page_address = database_recovered
+            .persistence_layer()
+            .page_manager()
+            .read_page(page_address, EvictablePage::prev_page_address)
+            .await
+            .unwrap();
+        assert_eq!(
+            prev_page_address,
+            database_recovered.persistence_layer().page_size()
+        );
+
+        drop(database_recovered);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn segments() {
+        const DIR: &str = "file_io_segments_test";
+        const BACKUP_DIR: &str = "file_io_segments_test_backup";
+        const SEGMENT_SIZE: u64 = 1 << 20;
+        let path = Path::new(DIR);
+        let backup_path = Path::new(BACKUP_DIR);
+        assert_eq!(
+            FileIO::<MonotonicU64>::with_segment_size(path, 4096).err(),
+            Some(Error::WrongParameter)
+        );
+        let file_io = FileIO::with_segment_size(path, SEGMENT_SIZE).unwrap();
+        let database: Database<MonotonicU64> =
+            Database::with_persistence_layer(file_io, None, None)
+                .await
+                .unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.create(&[0, 1, 2, 3], None).await.unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        // The database file grows beyond the first segment when the second page is created.
+        let mut page_address = 0;
+        for _ in 0..2 {
+            page_address = database
+                .persistence_layer()
+                .page_manager()
+                .create_page(database.persistence_layer().page_size())
+                .await
+                .unwrap();
+        }
+        assert!(database
+            .persistence_layer()
+            .sealed_log_segments()
+            .is_empty());
+        assert!(path.join("db.dat.1").exists());
+        assert_eq!(path.join("db.dat").metadata().unwrap().len(), SEGMENT_SIZE);
+        assert!(database.backup(false, Some(BACKUP_DIR), None).await.is_ok());
+        let instant = database.sequencer().now(Relaxed);
+        drop(database);
+
+        assert_eq!(
+            FileIO::<MonotonicU64>::with_segment_size(path, 2 * SEGMENT_SIZE).err(),
+            Some(Error::WrongParameter)
+        );
+        for path in [path, backup_path] {
+            let database_recovered = Database::with_path(path).await.unwrap();
+            assert_eq!(database_recovered.sequencer().now(Relaxed), instant);
+            let prev_page_address = database_recovered
+                .persistence_layer()
+                .page_manager()
+                .read_page(page_address, EvictablePage::prev_page_address)
+                .await
+                .unwrap();
+            assert_eq!(
+                prev_page_address,
+                database_recovered.persistence_layer().page_size()
+            );
+        }
+        assert!(backup_path.join("db.dat.1").exists());
+
+        assert!(remove_dir_all(path).await.is_ok());
+        assert!(remove_dir_all(backup_path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn log_capacity() {
+        const DIR: &str = "file_io_log_capacity_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let file_io = database.persistence_layer();
+        let object_log_size = LogRecord::<MonotonicU64>::JournalCreatedObjectSingle(0, 0, 0).size();
+        file_io.set_log_capacity(Some(
+            file_io.file_io_data.log.len(Acquire) + 2 * object_log_size as u64,
+        ));
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.create(&[0], None).await.unwrap();
+        journal.create(&[1], None).await.unwrap();
+        assert_eq!(journal.create(&[2], None).await, Err(Error::DiskFull));
+        drop(journal);
+        drop(transaction);
+
+        // Log records spanning multiple log buffers are written contiguously.
+        file_io.set_log_capacity(None);
+        let object_ids: Vec<u64> = (0..64).map(|o| o * o + 8).collect();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.create(&object_ids, None).await.unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        drop(database);
+
+        let database_recovered = Database::with_path(path).await.unwrap();
+        let snapshot = database_recovered.snapshot();
+        for o in object_ids {
+            assert_eq!(
+                database_recovered
+                    .access_controller()
+                    .read(o, &snapshot, None)
+                    .await,
+                Ok(true)
+            );
+        }
+
+        drop(snapshot);
+        drop(database_recovered);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn log_truncation() {
+        const DIR: &str = "file_io_log_truncation_test";
+        const BACKUP_DIR: &str = "file_io_log_truncation_test_backup";
+        const INCREMENTAL_DIR: &str = "file_io_log_truncation_test_incremental";
+        let path = Path::new(DIR);
+        let backup_path = Path::new(BACKUP_DIR);
+        let incremental_path = Path::new(INCREMENTAL_DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let file_io = database.persistence_layer();
+
+        for t in 0..16 {
+            let object_ids: Vec<u64> = (t * 1024..(t + 1) * 1024).map(|o| o * o + 8).collect();
+            let transaction = database.transaction();
+            let mut journal = transaction.journal();
+            journal.create(&object_ids, None).await.unwrap();
+            assert_eq!(journal.submit().get(), 1);
+            assert!(transaction.commit().await.is_ok());
+        }
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.delete(&[8, 9, 12], None).await.unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        // The open transaction keeps its log records from being reclaimed.
+        let open_log_offset = file_io.file_io_data.log.len(Acquire);
+        let open_transaction = database.transaction();
+        let mut journal = open_transaction.journal();
+        journal.create(&[3], None).await.unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(database.checkpoint(None).await.is_ok());
+        assert_eq!(file_io.log_head(), open_log_offset);
+        assert!(open_transaction.commit().await.is_ok());
+        assert_eq!(
+            file_io.backup_incremental(0, incremental_path, None).await,
+            Err(Error::WrongParameter)
+        );
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let metadata = fs::metadata(path.join("l.log")).unwrap();
+            assert!(metadata.blocks() * 512 < open_log_offset / 2);
+        }
+
+        // Log records are retained for the next incremental backup and within the retention.
+        assert!(database.backup(false, Some(BACKUP_DIR), None).await.is_ok());
+        let backup_clock = FileIO::<MonotonicU64>::backup_clock(backup_path).unwrap();
+        file_io.set_log_retention(None);
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.create(&[5], None).await.unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        assert!(database.checkpoint(None).await.is_ok());
+        assert_eq!(file_io.log_head(), open_log_offset);
+        file_io.set_log_retention(Some(0));
+        assert!(database.checkpoint(None).await.is_ok());
+        assert_eq!(file_io.log_head(), backup_clock);
+        assert!(file_io
+            .backup_incremental(backup_clock, incremental_path, None)
+            .await
+            .is_ok());
+        drop(database);
+
+        let database_recovered = Database::with_path(path).await.unwrap();
+        let snapshot = database_recovered.snapshot();
+        for (o, visible) in [
+            (3, true),
+            (5, true),
+            (8, false),
+            (9, false),
+            (12, false),
+            (17, true),
+            (1023 * 1023 + 8, true),
+        ] {
+            assert_eq!(
+                database_recovered
+                    .access_controller()
+                    .read(o, &snapshot, None)
+                    .await,
+                Ok(visible)
+            );
+        }
+
+        drop(snapshot);
+        drop(database_recovered);
+        assert!(remove_dir_all(path).await.is_ok());
+        assert!(remove_dir_all(backup_path).await.is_ok());
+        assert!(remove_dir_all(incremental_path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn log_reservation() {
+        const DIR: &str = "file_io_log_reservation_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let file_io = database.persistence_layer();
+        let transaction_1 = database.transaction();
+        let mut journal_1 = transaction_1.journal();
+        journal_1.create(&[0], None).await.unwrap();
+        assert_eq!(journal_1.submit().get(), 1);
+
+        // The end-of-journal log record of `journal_1` may be written after the capacity is set.
+        let object_log_size = LogRecord::<MonotonicU64>::JournalCreatedObjectSingle(0, 0, 0).size();
+        let eoj_log_size = LogRecord::<MonotonicU64>::BufferSubmitted(0).size();
+        file_io.set_log_capacity(Some(
+            file_io.file_io_data.log_reserved.load(Relaxed)
+                + (2 * object_log_size + eoj_log_size) as u64,
+        ));
+
+        // Failed and abandoned acquisitions do not exhaust the reserved log space.
+        let transaction_2 = database.transaction();
+        for _ in 0..8 {
+            let mut journal_2 = transaction_2.journal();
+            assert_eq!(
+                journal_2.delete(&[0], Some(Instant::now())).await,
+                Err(Error::Timeout)
+            );
+            drop(journal_2);
+            let mut journal_2 = transaction_2.journal();
+            let mut delete =
+                Box::pin(journal_2.delete(&[0], Some(Instant::now() + TIMEOUT_UNEXPECTED)));
+            assert!(futures::poll!(delete.as_mut()).is_pending());
+        }
+        let mut journal_2 = transaction_2.journal();
+        journal_2.create(&[1], None).await.unwrap();
+        journal_2.create(&[2], None).await.unwrap();
+        assert_eq!(journal_2.create(&[3], None).await, Err(Error::DiskFull));
+        drop(journal_2);
+        drop(transaction_2);
+
+        file_io.set_log_capacity(None);
+        assert!(transaction_1.commit().await.is_ok());
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn read_only() {
+        const DIR: &str = "file_io_read_only_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let file_io = database.persistence_layer();
+        assert!(!file_io.is_read_only());
+        assert!(file_io.file_io_data.emergency_space.exists());
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.create(&[0], None).await.unwrap();
+        file_io.file_io_data.handle_write_error(&Error::DiskFull);
+        assert!(file_io.is_read_only());
+        assert!(!file_io.file_io_data.emergency_space.exists());
+
+        // Transactions are able to finish in the read-only mode.
+        assert_eq!(journal.create(&[1], None).await, Err(Error::DiskFull));
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        drop(database);
+
+        let database_recovered = Database::with_path(path).await.unwrap();
+        assert!(!database_recovered.persistence_layer().is_read_only());
+        let snapshot = database_recovered.snapshot();
+        assert_eq!(
+            database_recovered
+                .access_controller()
+                .read(0, &snapshot, None)
+                .await,
+            Ok(true)
+        );
+
+        drop(snapshot);
+        drop(database_recovered);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn write_error() {
+        const DIR: &str = "file_io_write_error_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.create(&[0], None).await.unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        // The log file refuses writes after a write failure.
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.create(&[1], None).await.unwrap();
+        let file_io = database.persistence_layer();
+        let error = file_io
+            .file_io_data
+            .log
+            .write(&[0; 8], u64::MAX - 8)
+            .unwrap_err();
+        assert_eq!(journal.create(&[2], None).await, Err(error.clone()));
+        assert_eq!(journal.submit().get(), 1);
+        assert_eq!(transaction.commit().await, Err(error));
+        drop(database);
+
+        // The database is verified against the log when reopened.
+        let database_recovered = Database::with_path(path).await.unwrap();
+        let snapshot = database_recovered.snapshot();
+        assert_eq!(
+            database_recovered
+                .access_controller()
+                .read(0, &snapshot, None)
+                .await,
+            Ok(true)
+        );
+
+        drop(snapshot);
+        drop(database_recovered);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn log_faults() {
+        const DIR: &str = "file_io_log_faults_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal
+            .create(&(0..8).collect::<Vec<u64>>(), None)
+            .await
+            .unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        let faulty_file = FaultyFile::default();
+        assert!(database
+            .persistence_layer()
+            .set_log_faults(faulty_file.clone()));
+        faulty_file.inject(4, Fault::Crash);
+        let mut durable = Vec::new();
+        for o in 0..8 {
+            let transaction = database.transaction();
+            let mut journal = transaction.journal();
+            journal.delete(&[o], None).await.unwrap();
+            assert_eq!(journal.submit().get(), 1);
+            assert!(transaction.commit().await.is_ok());
+            if !faulty_file.is_crashed() {
+                durable.push(o);
+            }
+        }
+        assert!(faulty_file.is_crashed());
+        assert!(!durable.is_empty() && durable.len() < 8);
+        drop(database);
+
+        // Transactions committed before the crash survive.
+        let database_recovered = Database::with_path(path).await.unwrap();
+        let snapshot = database_recovered.snapshot();
+        for o in durable {
+            assert_eq!(
+                database_recovered
+                    .access_controller()
+                    .read(o, &snapshot, None)
+                    .await,
+                Ok(false)
+            );
+        }
+
+        drop(snapshot);
+        drop(database_recovered);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn durability() {
+        const DIR: &str = "file_io_durability_test";
+        let path = Path::new(DIR);
+        for durability in [Durability::OsBuffered, Durability::None] {
+            let file_io = FileIO::with_durability(path, durability).unwrap();
+            let database: Database<MonotonicU64> =
+                Database::with_persistence_layer(file_io, None, None)
+                    .await
+                    .unwrap();
+            let transaction = database.transaction();
+            let mut journal = transaction.journal();
+            journal.create(&[0, 1, 2, 3], None).await.unwrap();
+            assert_eq!(journal.submit().get(), 1);
+            assert!(transaction.commit().await.is_ok());
+            assert!(database.shutdown(ShutdownPolicy::Wait, None).await.is_ok());
+            drop(database);
+
+            let file_io = FileIO::with_durability(path, durability).unwrap();
+            let database_recovered: Database<MonotonicU64> =
+                Database::with_persistence_layer(file_io, None, None)
+                    .await
+                    .unwrap();
+            let snapshot = database_recovered.snapshot();
+            for o in 0..4 {
+                assert_eq!(
+                    database_recovered
+                        .access_controller()
+                        .read(o, &snapshot, None)
+                        .await,
+                    Ok(true)
+                );
+            }
+
+            drop(snapshot);
+            drop(database_recovered);
+            assert!(remove_dir_all(path).await.is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn memory_mapped() {
+        const DIR: &str = "file_io_memory_mapped_test";
+        let path = Path::new(DIR);
+        let file_io = FileIO::with_io_backend(path, IOBackend::MemoryMapped).unwrap();
+        let database: Database<MonotonicU64> =
+            Database::with_persistence_layer(file_io, None, None)
+                .await
+                .unwrap();
+        for o in 0..16 {
+            let transaction = database.transaction();
+            let mut journal = transaction.journal();
+            journal.create(&[o], None).await.unwrap();
+            assert_eq!(journal.submit().get(), 1);
+            assert!(transaction.commit().await.is_ok());
+        }
+        let instant = database.sequencer().now(Relaxed);
+        drop(database);
+
+        let file_io = FileIO::with_io_backend(path, IOBackend::MemoryMapped).unwrap();
+        let database_recovered: Database<MonotonicU64> =
+            Database::with_persistence_layer(file_io, None, None)
+                .await
+                .unwrap();
+        assert_eq!(database_recovered.sequencer().now(Relaxed), instant);
+        let snapshot = database_recovered.snapshot();
+        for o in 0..16 {
+            assert_eq!(
+                database_recovered
+                    .access_controller()
+                    .read(o, &snapshot, None)
+                    .await,
+                Ok(true)
+            );
+        }
+
+        drop(snapshot);
+        drop(database_recovered);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn quarantine() {
+        const DIR: &str = "file_io_quarantine_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        for name in ["a", "b"] {
+            let container = database
+                .create_container(
+                    name.to_string(),
+                    Metadata::default().with_index_type(IndexType::Ordered),
+                    &mut journal,
+                    None,
+                )
+                .await
+                .unwrap();
+            for i in 0_u8..64 {
+                let key = [name.as_bytes(), &[i]].concat();
+                assert!(container
+                    .insert(&key, &key, &mut journal, None)
+                    .await
+                    .is_ok());
+            }
+        }
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        assert!(database.shutdown(ShutdownPolicy::Wait, None).await.is_ok());
+
+        // The root page of the index of `a` is corrupted.
+        let file_io = database.persistence_layer();
+        let indexes = file_io
+            .file_io_data
+            .container_indexes
+            .lock()
+            .unwrap()
+            .clone();
+        let page = indexes
+            .iter()
+            .find(|index| {
+                let mut first_key = None;
+                assert!(index
+                    .scan_sync(file_io.page_manager(), |r| {
+                        first_key.get_or_insert(r.key);
+                    })
+                    .is_ok());
+                first_key.is_some_and(|k| k.starts_with(b"a"))
+            })
+            .unwrap()
+            .root;
+        drop(database);
+
+        // The page is not torn by a crash, therefore it is not repaired when recovered.
+        let db = std::fs::OpenOptions::new()
+            .write(true)
+            .open(path.join("db.dat"))
+            .unwrap();
+        std::os::unix::fs::FileExt::write_all_at(&db, &[31], page + 64).unwrap();
+        drop(db);
+
+        // `a` is quarantined whereas `b` stays readable and writable.
+        let database = Database::with_path(path).await.unwrap();
+        assert_eq!(database.persistence_layer().quarantined_pages(), vec![page]);
+        let snapshot = database.snapshot();
+        let a = database.get_container("a", &snapshot).await.unwrap();
+        let b = database.get_container("b", &snapshot).await.unwrap();
+        assert_eq!(
+            a.get(b"a\x01", &snapshot, None).await,
+            Err(Error::CorruptPage(page))
+        );
+        assert_eq!(
+            b.get(b"b\x01", &snapshot, None).await,
+            Ok(Some(b"b\x01".to_vec()))
+        );
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert_eq!(
+            a.insert(b"a\xff", b"", &mut journal, None).await,
+            Err(Error::CorruptPage(page))
+        );
+        assert!(b.insert(b"b\xff", b"", &mut journal, None).await.is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        assert_eq!(
+            database.checkpoint(None).await,
+            Err(Error::CorruptPage(page))
+        );
+        drop(snapshot);
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
 }
@@ -0,0 +1,418 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Log segment archiving to object stores.
+
+use super::LogArchiver;
+use crate::Error;
+use std::fmt::{self, Debug};
+use std::fs::{self, create_dir_all};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// [`ObjectStore`] is a flat namespace of immutable objects, e.g., an `S3` or `GCS` bucket.
+///
+/// Clients of object stores are not bundled with the crate; implementing the trait on top of a
+/// client lets [`ObjectStoreArchiver`] keep sealed log segments in the object store.
+pub trait ObjectStore: 'static + Debug + Send + Sync {
+    /// Stores the object under the name, replacing any existing object of the same name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the object could not be stored.
+    fn put(&self, name: &str, data: &[u8]) -> Result<(), Error>;
+
+    /// Returns the object stored under the name, or `None` if there is none.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the object could not be retrieved.
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>, Error>;
+
+    /// Returns the names of all the objects.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the objects could not be listed.
+    fn list(&self) -> Result<Vec<String>, Error>;
+}
+
+/// [`DirectoryStore`] is an [`ObjectStore`] storing objects as files in a directory.
+///
+/// It is suitable for file systems backed by object stores, e.g., a mounted bucket, and for
+/// testing.
+///
+/// # Examples
+///
+/// ```
+/// use sap_tsf::{DirectoryStore, ObjectStore};
+/// use std::fs::remove_dir_all;
+/// use std::path::Path;
+///
+/// let store = DirectoryStore::new(Path::new("directory_store"));
+/// assert!(store.put("l.log", b"log").is_ok());
+/// assert_eq!(store.get("l.log"), Ok(Some(b"log".to_vec())));
+/// assert_eq!(store.list(), Ok(vec!["l.log".to_string()]));
+/// assert!(remove_dir_all("directory_store").is_ok());
+/// ```
+#[derive(Debug)]
+pub struct DirectoryStore {
+    /// The directory containing objects.
+    path: PathBuf,
+}
+
+/// [`ObjectStoreArchiver`] is a [`LogArchiver`] uploading sealed log segments to an
+/// [`ObjectStore`].
+///
+/// The directory of the [`FileIO`](super::FileIO) acts as the local cache of the database: the
+/// active log segment and database pages are read and written locally, and each log segment is
+/// uploaded once it is sealed. Uploads are performed by a dedicated thread so that the thread
+/// performing file IO operations is never blocked by the object store; archived log segments are
+/// downloaded by [`ObjectStoreArchiver::fetch`] in order to be replayed over a full backup with
+/// [`FileIO::restore_to`](super::FileIO::restore_to), e.g., when the local disk of a cloud
+/// instance was lost.
+///
+/// # Examples
+///
+/// ```
+/// use sap_tsf::{Database, DirectoryStore, FileIO, MonotonicU64, ObjectStoreArchiver};
+/// use std::path::Path;
+/// use std::sync::Arc;
+///
+/// async {
+///     let file_io = FileIO::with_segment_size(Path::new("object_store"), 1 << 20).unwrap();
+///     let store = DirectoryStore::new(Path::new("bucket"));
+///     let archiver = Arc::new(ObjectStoreArchiver::new(store));
+///     file_io.set_log_archiver(archiver.clone());
+///     let database: Database<MonotonicU64> =
+///         Database::with_persistence_layer(file_io, None, None).await.unwrap();
+///     assert!(archiver.flush().is_ok());
+/// };
+/// ```
+pub struct ObjectStoreArchiver<O: ObjectStore> {
+    /// The object store.
+    store: Arc<O>,
+
+    /// The upload state shared with the upload thread.
+    state: Arc<(Mutex<UploadState>, Condvar)>,
+
+    /// The sender of segments to upload.
+    sender: Option<Mutex<Sender<(u64, PathBuf)>>>,
+
+    /// The upload thread.
+    uploader: Option<JoinHandle<()>>,
+}
+
+/// The upload state of an [`ObjectStoreArchiver`].
+#[derive(Debug, Default)]
+struct UploadState {
+    /// The number of segments that have yet to be uploaded.
+    num_pending: usize,
+
+    /// The indexes of segments that were uploaded.
+    uploaded: Vec<u64>,
+
+    /// The first error since the last flush.
+    error: Option<Error>,
+}
+
+impl DirectoryStore {
+    /// Creates a new [`DirectoryStore`] in the path.
+    ///
+    /// The directory is created when the first object is stored.
+    #[inline]
+    #[must_use]
+    pub fn new(path: &Path) -> DirectoryStore {
+        DirectoryStore {
+            path: path.to_path_buf(),
+        }
+    }
+}
+
+impl ObjectStore for DirectoryStore {
+    #[inline]
+    fn put(&self, name: &str, data: &[u8]) -> Result<(), Error> {
+        create_dir_all(&self.path).map_err(|e| Error::IO(e.kind()))?;
+
+        // The object is renamed after being fully written so that it is never seen partially.
+        let temp_path = self.path.join(format!(".{name}.tmp"));
+        fs::write(&temp_path, data).map_err(|e| Error::IO(e.kind()))?;
+        fs::rename(&temp_path, self.path.join(name)).map_err(|e| Error::IO(e.kind()))
+    }
+
+    #[inline]
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>, Error> {
+        match fs::read(self.path.join(name)) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::IO(e.kind())),
+        }
+    }
+
+    #[inline]
+    fn list(&self) -> Result<Vec<String>, Error> {
+        let entries = match fs::read_dir(&self.path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(Error::IO(e.kind())),
+        };
+        let mut names = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| Error::IO(e.kind()))?;
+            if let Some(name) = entry.file_name().to_str() {
+                if !name.starts_with('.') {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort_unstable();
+        Ok(names)
+    }
+}
+
+impl<O: ObjectStore> ObjectStoreArchiver<O> {
+    /// Creates a new [`ObjectStoreArchiver`] uploading sealed log segments to the
+    /// [`ObjectStore`].
+    #[inline]
+    #[must_use]
+    pub fn new(store: O) -> ObjectStoreArchiver<O> {
+        let store = Arc::new(store);
+        let state: Arc<(Mutex<UploadState>, Condvar)> = Arc::default();
+        let (sender, receiver) = mpsc::channel::<(u64, PathBuf)>();
+        let (store_clone, state_clone) = (store.clone(), state.clone());
+        let uploader = thread::spawn(move || {
+            while let Ok((segment_index, segment_path)) = receiver.recv() {
+                let result = Self::upload(&*store_clone, &segment_path);
+                let (lock, condvar) = &*state_clone;
+                if let Ok(mut upload_state) = lock.lock() {
+                    match result {
+                        Ok(()) => upload_state.uploaded.push(segment_index),
+                        Err(error) => {
+                            upload_state.error.get_or_insert(error);
+                        }
+                    }
+                    upload_state.num_pending -= 1;
+                }
+                condvar.notify_all();
+            }
+        });
+        ObjectStoreArchiver {
+            store,
+            state,
+            sender: Some(Mutex::new(sender)),
+            uploader: Some(uploader),
+        }
+    }
+
+    /// Returns a reference to the [`ObjectStore`].
+    #[inline]
+    #[must_use]
+    pub fn store(&self) -> &O {
+        &self.store
+    }
+
+    /// Returns the indexes of the log segments uploaded so far in the order of the uploads.
+    #[inline]
+    #[must_use]
+    pub fn uploaded_segments(&self) -> Vec<u64> {
+        self.state
+            .0
+            .lock()
+            .map(|s| s.uploaded.clone())
+            .unwrap_or_default()
+    }
+
+    /// Waits for the pending uploads to be completed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`Error`] that an upload failed with since the last call.
+    #[inline]
+    pub fn flush(&self) -> Result<(), Error> {
+        let (lock, condvar) = &*self.state;
+        let Ok(upload_state) = lock.lock() else {
+            return Err(Error::UnexpectedState);
+        };
+        let Ok(mut upload_state) = condvar.wait_while(upload_state, |s| s.num_pending != 0) else {
+            return Err(Error::UnexpectedState);
+        };
+        upload_state.error.take().map_or(Ok(()), Err)
+    }
+
+    /// Downloads the log segments in the [`ObjectStore`] into the archive path, and returns the
+    /// number of downloaded log segments.
+    ///
+    /// Log segments that already exist in the archive path are not downloaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the log segments could not be downloaded.
+    #[inline]
+    pub fn fetch(store: &O, archive: &Path) -> Result<usize, Error> {
+        create_dir_all(archive).map_err(|e| Error::IO(e.kind()))?;
+        let mut num_downloaded = 0;
+        for name in store.list()? {
+            if !Self::is_log_segment(&name) || archive.join(&name).exists() {
+                continue;
+            }
+            if let Some(data) = store.get(&name)? {
+                fs::write(archive.join(&name), data).map_err(|e| Error::IO(e.kind()))?;
+                num_downloaded += 1;
+            }
+        }
+        Ok(num_downloaded)
+    }
+
+    /// Uploads the log segment.
+    fn upload(store: &O, segment_path: &Path) -> Result<(), Error> {
+        let Some(name) = segment_path.file_name().and_then(|n| n.to_str()) else {
+            return Err(Error::WrongParameter);
+        };
+        let data = fs::read(segment_path).map_err(|e| Error::IO(e.kind()))?;
+        store.put(name, &data)
+    }
+
+    /// Returns `true` if the name is that of a log segment, e.g., `l.log` or `l.log.1`.
+    fn is_log_segment(name: &str) -> bool {
+        name.strip_prefix("l.log").is_some_and(|suffix| {
+            suffix.is_empty()
+                || suffix
+                    .strip_prefix('.')
+                    .is_some_and(|i| !i.is_empty() && i.bytes().all(|b| b.is_ascii_digit()))
+        })
+    }
+}
+
+impl<O: ObjectStore> Debug for ObjectStoreArchiver<O> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ObjectStoreArchiver")
+            .field("store", &self.store)
+            .field("state", &self.state.0)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<O: ObjectStore> Drop for ObjectStoreArchiver<O> {
+    #[inline]
+    fn drop(&mut self) {
+        // Pending uploads are completed before the upload thread exits.
+        drop(self.sender.take());
+        if let Some(uploader) = self.uploader.take() {
+            let _: Result<(), _> = uploader.join();
+        }
+    }
+}
+
+impl<O: ObjectStore> LogArchiver for ObjectStoreArchiver<O> {
+    #[inline]
+    fn archive(&self, segment_index: u64, segment_path: &Path) {
+        let (lock, _) = &*self.state;
+        if let Ok(mut upload_state) = lock.lock() {
+            upload_state.num_pending += 1;
+        }
+        let sent = self.sender.as_ref().is_some_and(|sender| {
+            sender.lock().is_ok_and(|sender| {
+                sender
+                    .send((segment_index, segment_path.to_path_buf()))
+                    .is_ok()
+            })
+        });
+        if !sent {
+            if let Ok(mut upload_state) = lock.lock() {
+                upload_state.num_pending -= 1;
+                upload_state.error.get_or_insert(Error::UnexpectedState);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Database, FileIO, MonotonicU64, Sequencer};
+    use std::sync::atomic::Ordering::Relaxed;
+    use tokio::fs::remove_dir_all;
+
+    #[test]
+    fn log_segment_names() {
+        type Archiver = ObjectStoreArchiver<DirectoryStore>;
+        assert!(Archiver::is_log_segment("l.log"));
+        assert!(Archiver::is_log_segment("l.log.12"));
+        assert!(!Archiver::is_log_segment("l.log."));
+        assert!(!Archiver::is_log_segment("l.log.1a"));
+        assert!(!Archiver::is_log_segment("db.dat"));
+    }
+
+    #[tokio::test]
+    async fn archive_restore() {
+        const DIR: &str = "object_store_archive_restore_test";
+        const BASE_DIR: &str = "object_store_archive_restore_test_base";
+        const BUCKET_DIR: &str = "object_store_archive_restore_test_bucket";
+        const ARCHIVE_DIR: &str = "object_store_archive_restore_test_archive";
+        let path = Path::new(DIR);
+        let base_path = Path::new(BASE_DIR);
+        let archive_path = Path::new(ARCHIVE_DIR);
+        let file_io = FileIO::with_segment_size(path, 1 << 20).unwrap();
+        let archiver = Arc::new(ObjectStoreArchiver::new(DirectoryStore::new(Path::new(
+            BUCKET_DIR,
+        ))));
+        file_io.set_log_archiver(archiver.clone());
+        let database: Database<MonotonicU64> =
+            Database::with_persistence_layer(file_io, None, None)
+                .await
+                .unwrap();
+        assert!(database.backup(false, Some(BASE_DIR), None).await.is_ok());
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.create(&[0], None).await.unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        let instant = database.sequencer().now(Relaxed);
+
+        // Object identifiers in descending order are logged individually to fill a log segment.
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let object_ids: Vec<u64> = (16..65536).rev().collect();
+        journal.create(&object_ids, None).await.unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        assert!(archiver.flush().is_ok());
+        assert_eq!(archiver.uploaded_segments(), vec![0]);
+        assert_eq!(archiver.store().list(), Ok(vec!["l.log".to_string()]));
+        drop(database);
+
+        // The sealed segment is downloaded from the object store, and replayed over the backup
+        // that was taken before the object was created.
+        assert_eq!(
+            ObjectStoreArchiver::fetch(archiver.store(), archive_path),
+            Ok(1)
+        );
+        assert_eq!(
+            ObjectStoreArchiver::fetch(archiver.store(), archive_path),
+            Ok(0)
+        );
+        assert!(FileIO::<MonotonicU64>::restore_to(base_path, archive_path, instant).is_ok());
+        let database_restored = Database::with_path(base_path).await.unwrap();
+        let snapshot = database_restored.snapshot();
+        assert_eq!(
+            database_restored
+                .access_controller()
+                .read(0, &snapshot, None)
+                .await,
+            Ok(true)
+        );
+        drop(snapshot);
+        drop(database_restored);
+
+        drop(archiver);
+        assert!(remove_dir_all(path).await.is_ok());
+        assert!(remove_dir_all(base_path).await.is_ok());
+        assert!(remove_dir_all(BUCKET_DIR).await.is_ok());
+        assert!(remove_dir_all(archive_path).await.is_ok());
+    }
+}
@@ -0,0 +1,316 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Options for opening database files.
+
+use super::database_header::{is_valid_page_size, SEGMENT_SIZE_UNIT};
+use super::{Cipher, Durability, FileIO, IOBackend};
+use crate::{Error, Sequencer};
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::sync::Arc;
+
+/// [`OpenOptions`] specifies how a [`FileIO`] opens the database files in a directory.
+///
+/// The options are validated before any files are touched, and [`OpenOptions::open`] fails with
+/// an error describing the first invalid option.
+///
+/// # Examples
+///
+/// ```
+/// use sap_tsf::{Durability, FileIO, MonotonicU64, OpenOptions};
+/// use std::path::Path;
+///
+/// let file_io = OpenOptions::new()
+///     .with_page_size(4096)
+///     .with_durability(Durability::OsBuffered)
+///     .with_page_cache_capacity(1024)
+///     .open::<MonotonicU64>(Path::new("open_options"));
+/// assert!(file_io.is_ok());
+///
+/// drop(file_io);
+/// assert!(std::fs::remove_dir_all("open_options").is_ok());
+/// ```
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Debug)]
+pub struct OpenOptions {
+    /// The page size of a new database.
+    pub(super) page_size: Option<u64>,
+
+    /// The segment size of a new database.
+    pub(super) segment_size: Option<u64>,
+
+    /// Upgrades the database file if it was created by an older version.
+    pub(super) migrate: bool,
+
+    /// The cipher encrypting database pages and log records.
+    pub(super) cipher: Option<Arc<dyn Cipher>>,
+
+    /// The IO backend.
+    pub(super) io_backend: IOBackend,
+
+    /// The durability of writes.
+    pub(super) durability: Durability,
+
+    /// Creates the database files if they do not exist.
+    create: bool,
+
+    /// Removes the existing database files.
+    truncate: bool,
+
+    /// Opens the existing database files without write permission.
+    pub(super) read_only: bool,
+
+    /// Locks the database file.
+    pub(super) locking: bool,
+
+    /// The maximum number of pages in the page cache.
+    pub(super) page_cache_capacity: Option<usize>,
+
+    /// The number of threads replaying log records.
+    pub(super) recovery_parallelism: Option<NonZeroUsize>,
+}
+
+impl OpenOptions {
+    /// Creates a new [`OpenOptions`] with the default options.
+    ///
+    /// The database files are created if they do not exist, and existing database files are
+    /// neither truncated nor migrated.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the page size of a new database.
+    ///
+    /// The page size must be a power of two between `512B` and `64KB`, and it must be identical to
+    /// that of the existing database.
+    #[inline]
+    #[must_use]
+    pub fn with_page_size(mut self, page_size: u64) -> Self {
+        self.page_size.replace(page_size);
+        self
+    }
+
+    /// Sets the segment size of a new database.
+    ///
+    /// The segment size must be a multiple of `1MB`, and it must be identical to that of the
+    /// existing database.
+    #[inline]
+    #[must_use]
+    pub fn with_segment_size(mut self, segment_size: u64) -> Self {
+        self.segment_size.replace(segment_size);
+        self
+    }
+
+    /// Sets whether the database file is upgraded to the current version if it was created by an
+    /// older version.
+    #[inline]
+    #[must_use]
+    pub fn with_migration(mut self, migrate: bool) -> Self {
+        self.migrate = migrate;
+        self
+    }
+
+    /// Sets the [`Cipher`] encrypting database pages and log records.
+    #[inline]
+    #[must_use]
+    pub fn with_cipher(mut self, cipher: Arc<dyn Cipher>) -> Self {
+        self.cipher.replace(cipher);
+        self
+    }
+
+    /// Sets the [`IOBackend`].
+    #[inline]
+    #[must_use]
+    pub fn with_io_backend(mut self, io_backend: IOBackend) -> Self {
+        self.io_backend = io_backend;
+        self
+    }
+
+    /// Sets the [`Durability`] of writes.
+    #[inline]
+    #[must_use]
+    pub fn with_durability(mut self, durability: Durability) -> Self {
+        self.durability = durability;
+        self
+    }
+
+    /// Sets whether the database files are created if they do not exist.
+    ///
+    /// Opening a directory without database files fails if disabled.
+    #[inline]
+    #[must_use]
+    pub fn with_create(mut self, create: bool) -> Self {
+        self.create = create;
+        self
+    }
+
+    /// Sets whether the existing database and log files are removed, so that an empty database is
+    /// created.
+    #[inline]
+    #[must_use]
+    pub fn with_truncate(mut self, truncate: bool) -> Self {
+        self.truncate = truncate;
+        self
+    }
+
+    /// Sets whether the existing database files are opened without write permission.
+    ///
+    /// A read-only database can be opened from a backup or while another process is using the
+    /// database files, and it is not recovered from a crash; transactions can read the database,
+    /// whereas creating or deleting database objects fails with [`Error::ReadOnly`], and
+    /// committing a transaction that did not modify the database succeeds without writing
+    /// anything.
+    #[inline]
+    #[must_use]
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Sets whether the database file is locked while the database files are open.
+    ///
+    /// The lock is an advisory lock on the database file that is exclusive unless the database
+    /// files are opened read-only, in which case the lock is shared; opening the database files
+    /// fails with [`Error::AlreadyInUse`] if another process or [`FileIO`] holds a conflicting
+    /// lock, so that only a single writer or multiple read-only openers can use the database files
+    /// at a time. Disabling the lock allows a read-only opener to inspect the database files while
+    /// a writer is using them, and it cannot be disabled for writers.
+    #[inline]
+    #[must_use]
+    pub fn with_locking(mut self, locking: bool) -> Self {
+        self.locking = locking;
+        self
+    }
+
+    /// Sets the maximum number of pages in the page cache.
+    ///
+    /// The default capacity amounts to `8GB` of pages.
+    #[inline]
+    #[must_use]
+    pub fn with_page_cache_capacity(mut self, capacity: usize) -> Self {
+        self.page_cache_capacity.replace(capacity);
+        self
+    }
+
+    /// Sets the number of threads replaying log records on recovery.
+    ///
+    /// See [`FileIO::set_recovery_parallelism`].
+    #[inline]
+    #[must_use]
+    pub fn with_recovery_parallelism(mut self, parallelism: NonZeroUsize) -> Self {
+        self.recovery_parallelism.replace(parallelism);
+        self
+    }
+
+    /// Opens the database files in the specified [`Path`] with the options.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the options are invalid, the database files do not exist and creating
+    /// them is disabled or they are opened read-only, memory allocation failed, spawning a thread
+    /// failed, the specified directory could not be created, or database files could not be
+    /// opened.
+    #[inline]
+    pub fn open<S: Sequencer<Instant = u64>>(&self, path: &Path) -> Result<FileIO<S>, Error> {
+        self.validate()?;
+        if self.truncate {
+            Self::remove_database_files(path)?;
+        } else if (!self.create || self.read_only) && !path.join("db.dat").exists() {
+            return Err(Error::Generic("the database files do not exist"));
+        }
+        FileIO::open(path, self)
+    }
+
+    /// Validates the options.
+    fn validate(&self) -> Result<(), Error> {
+        if self.page_size.is_some_and(|p| !is_valid_page_size(p)) {
+            return Err(Error::Generic(
+                "the page size must be a power of two between 512B and 64KB",
+            ));
+        }
+        if self
+            .segment_size
+            .is_some_and(|s| s == 0 || !s.is_multiple_of(SEGMENT_SIZE_UNIT))
+        {
+            return Err(Error::Generic("the segment size must be a multiple of 1MB"));
+        }
+        if self.page_cache_capacity == Some(0) {
+            return Err(Error::Generic("the page cache capacity must not be zero"));
+        }
+        if self.truncate && !self.create {
+            return Err(Error::Generic(
+                "truncating the database files requires creating them",
+            ));
+        }
+        if self.truncate && self.migrate {
+            return Err(Error::Generic(
+                "truncated database files cannot be migrated",
+            ));
+        }
+        if self.read_only && (self.truncate || self.migrate) {
+            return Err(Error::Generic(
+                "read-only database files cannot be truncated or migrated",
+            ));
+        }
+        if !self.read_only && !self.locking {
+            return Err(Error::Generic(
+                "the database file must be locked unless opened read-only",
+            ));
+        }
+        if self.read_only && self.io_backend == IOBackend::MemoryMapped {
+            return Err(Error::Generic(
+                "read-only database files cannot be memory-mapped",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Removes the database, double-write, and log files in the directory.
+    fn remove_database_files(path: &Path) -> Result<(), Error> {
+        let Ok(entries) = fs::read_dir(path) else {
+            return Ok(());
+        };
+        for entry in entries {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            let Some(file_name) = file_name.to_str() else {
+                continue;
+            };
+            if ["db.dat", "l.log", "dw.dat"].iter().any(|f| {
+                file_name == *f
+                    || file_name
+                        .strip_prefix(f)
+                        .and_then(|s| s.strip_prefix('.'))
+                        .is_some_and(|s| s.parse::<u64>().is_ok())
+            }) {
+                fs::remove_file(entry.path())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for OpenOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            page_size: None,
+            segment_size: None,
+            migrate: false,
+            cipher: None,
+            io_backend: IOBackend::default(),
+            durability: Durability::default(),
+            create: true,
+            truncate: false,
+            read_only: false,
+            locking: true,
+            page_cache_capacity: None,
+            recovery_parallelism: None,
+        }
+    }
+}
@@ -0,0 +1,177 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Free page allocator.
+
+use super::evictable_page::PAGE_HEADER_LEN;
+use super::RandomAccessFile;
+use crate::Error;
+use std::collections::BTreeSet;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::Mutex;
+
+/// [`PageAllocator`] keeps track of free pages in the database file.
+///
+/// The free page list is not separately persisted; a page is free if the `PREV_OFFSET` field of
+/// the page header is `NULL`, and the page header is synchronously written whenever a page is
+/// allocated or freed, therefore the free page list can always be reconstructed by scanning the
+/// page headers when the database is opened.
+///
+/// Free pages are allocated in ascending address order in order to keep the database file
+/// compact.
+#[derive(Debug, Default)]
+pub struct PageAllocator {
+    /// The set of free page addresses.
+    ///
+    /// TODO: optimize memory usage, e.g., by using a bit-vector.
+    free_pages: Mutex<BTreeSet<u64>>,
+
+    /// The address of the first page that can be allocated.
+    first_page_address: u64,
+
+    /// The size of a page.
+    page_size: u64,
+}
+
+impl PageAllocator {
+    /// Creates a new [`PageAllocator`] by scanning the page headers in the database file.
+    ///
+    /// Pages before `first_page_address` are never allocated.
+    #[inline]
+    pub fn from_file(
+        db: &RandomAccessFile,
+        first_page_address: u64,
+        page_size: u64,
+    ) -> Result<Self, Error> {
+        debug_assert_eq!(first_page_address % page_size, 0);
+        let mut free_pages = BTreeSet::new();
+        let mut page_header = [0_u8; PAGE_HEADER_LEN];
+        let mut page_address = first_page_address;
+        while page_address < db.len(Relaxed) {
+            db.read(&mut page_header, page_address)?;
+            if page_header[0..8].iter().all(|b| *b == 0) {
+                free_pages.insert(page_address);
+            }
+            page_address += page_size;
+        }
+        Ok(Self {
+            free_pages: Mutex::new(free_pages),
+            first_page_address,
+            page_size,
+        })
+    }
+
+    /// Returns `true` if there is no free page.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.free_pages.lock().map_or(true, |f| f.is_empty())
+    }
+
+    /// Returns `true` if the page is in the free page list.
+    #[inline]
+    pub fn is_free(&self, page_address: u64) -> bool {
+        self.free_pages
+            .lock()
+            .is_ok_and(|f| f.contains(&page_address))
+    }
+
+    /// Allocates a single free page.
+    #[inline]
+    pub fn allocate(&self) -> Option<u64> {
+        self.free_pages.lock().ok()?.pop_first()
+    }
+
+    /// Allocates the specified number of contiguous free pages.
+    ///
+    /// Returns the address of the first page.
+    #[inline]
+    pub fn allocate_extent(&self, num_pages: u64) -> Option<u64> {
+        debug_assert_ne!(num_pages, 0);
+        let mut free_pages = self.free_pages.lock().ok()?;
+        let mut extent_start = 0;
+        let mut extent_len = 0;
+        for page_address in free_pages.iter() {
+            if extent_len != 0 && extent_start + extent_len * self.page_size == *page_address {
+                extent_len += 1;
+            } else {
+                extent_start = *page_address;
+                extent_len = 1;
+            }
+            if extent_len == num_pages {
+                break;
+            }
+        }
+        if extent_len != num_pages {
+            return None;
+        }
+        for i in 0..num_pages {
+            free_pages.remove(&(extent_start + i * self.page_size));
+        }
+        Some(extent_start)
+    }
+
+    /// Frees a page.
+    #[inline]
+    pub fn free(&self, page_address: u64) {
+        self.free_extent(page_address, 1);
+    }
+
+    /// Frees the specified number of contiguous pages.
+    #[inline]
+    pub fn free_extent(&self, page_address: u64, num_pages: u64) {
+        debug_assert_eq!(page_address % self.page_size, 0);
+        debug_assert!(page_address >= self.first_page_address);
+        if let Ok(mut free_pages) = self.free_pages.lock() {
+            for i in 0..num_pages {
+                free_pages.insert(page_address + i * self.page_size);
+            }
+        }
+    }
+
+    /// Removes free pages at the end of the file from the free page list.
+    ///
+    /// Returns the new length of the file without the trailing free pages.
+    #[inline]
+    pub fn truncate(&self, file_len: u64) -> u64 {
+        let Ok(mut free_pages) = self.free_pages.lock() else {
+            return file_len;
+        };
+        let mut new_len = file_len;
+        while new_len > self.first_page_address {
+            let last_page_address = new_len - self.page_size;
+            if !free_pages.remove(&last_page_address) {
+                break;
+            }
+            new_len = last_page_address;
+        }
+        new_len
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PAGE_SIZE: u64 = 4096;
+
+    #[test]
+    fn allocate_free() {
+        let page_allocator = PageAllocator {
+            free_pages: Mutex::default(),
+            first_page_address: PAGE_SIZE,
+            page_size: PAGE_SIZE,
+        };
+        assert!(page_allocator.is_empty());
+        page_allocator.free_extent(PAGE_SIZE, 4);
+        page_allocator.free_extent(PAGE_SIZE * 6, 3);
+        assert_eq!(page_allocator.allocate(), Some(PAGE_SIZE));
+        assert_eq!(page_allocator.allocate_extent(4), None);
+        assert_eq!(page_allocator.allocate_extent(3), Some(PAGE_SIZE * 2));
+        assert_eq!(page_allocator.allocate_extent(2), Some(PAGE_SIZE * 6));
+        page_allocator.free(PAGE_SIZE * 2);
+        assert_eq!(page_allocator.truncate(PAGE_SIZE * 9), PAGE_SIZE * 8);
+        assert_eq!(page_allocator.allocate(), Some(PAGE_SIZE * 2));
+        assert!(page_allocator.is_empty());
+    }
+}
@@ -5,16 +5,23 @@
 //! Page management.
 
 use super::database_header::DatabaseHeader;
-use super::evictable_page::{EvictablePage, PAGE_SIZE};
+use super::double_write_buffer::DoubleWriteBuffer;
+use super::evictable_page::{
+    EvictablePage, DEFAULT_PAGE_SIZE, ENCRYPTION_INFO_LEN, PAGE_FOOTER_LEN, PAGE_HEADER_LEN,
+};
+use super::integrity::{IntegrityProblem, IntegrityReport};
 use super::io_task_processor::IOTask;
-use super::RandomAccessFile;
-use crate::Error;
+use super::page_allocator::PageAllocator;
+use super::{FaultyFile, OpenOptions, RandomAccessFile};
+use crate::{Counter, Error, Telemetry};
 use scc::hash_cache::Entry;
-use scc::{Bag, HashCache};
+use scc::{Bag, HashCache, HashMap, HashSet};
 use std::future::Future;
 use std::pin::Pin;
-use std::sync::atomic::Ordering::Relaxed;
+use std::sync::atomic::Ordering::{AcqRel, Acquire, Relaxed, Release};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8};
 use std::sync::mpsc::SyncSender;
+use std::sync::Arc;
 use std::task::{Context, Poll, Waker};
 use std::thread::yield_now;
 
@@ -25,30 +32,87 @@ pub struct PageManager {
     /// The database file.
     db: RandomAccessFile,
 
+    /// The double-write buffer protecting pages of the database file against torn writes.
+    ///
+    /// It is `None` if the database file is opened read-only.
+    double_write_buffer: Option<DoubleWriteBuffer>,
+
     /// The database header.
     db_header: DatabaseHeader,
 
+    /// The free page allocator.
+    page_allocator: PageAllocator,
+
+    /// The number of completed defragmentation requests.
+    defragmentation_epoch: AtomicU64,
+
     /// Cached pages.
     page_cache: HashCache<u64, Box<EvictablePage>>,
 
+    /// Pages that failed checksum verification.
+    ///
+    /// The pages are quarantined until they are repaired: accesses to them fail with
+    /// [`Error::CorruptPage`] without reading the file, whereas the other pages remain accessible.
+    quarantined_pages: HashSet<u64>,
+
+    /// The ratio of dirty pages to the capacity of the page cache in percent above which the
+    /// background flusher writes back dirty pages, or `u8::MAX` if the flusher is disabled.
+    dirty_page_threshold: AtomicU8,
+
+    /// The clean shutdown marker is set in the header page.
+    clean_shutdown: AtomicBool,
+
+    /// The logical clock persisted in the header page.
+    clock: AtomicU64,
+
+    /// The clock of the latest backup, or `u64::MAX` if no backups have been taken.
+    backup_clock: AtomicU64,
+
+    /// The clocks of backups that have been taken.
+    backup_clocks: HashSet<u64>,
+
+    /// The clock of the latest backup when a page was last written.
+    ///
+    /// Pages written before the first backup are not recorded.
+    page_clocks: HashMap<u64, u64>,
+
     /// File IO task sender.
     file_io_task_sender: SyncSender<IOTask>,
 
+    /// Statistics of page accesses.
+    telemetry: Arc<Telemetry>,
+
     /// [`Waker`] bag for free pages.
     waker_bag_for_free_page: Bag<Waker>,
 
     /// [`Waker`] bag for caching pages.
     waker_bag_for_caching_page: Bag<Waker>,
+
+    /// [`Waker`] bag for defragmentation.
+    waker_bag_for_defragmentation: Bag<Waker>,
 }
 
-/// Free page allocation unit.
-pub const ALLOCATION_UNIT: u64 = PAGE_SIZE * 2048;
+/// Free page allocation unit in pages.
+pub const ALLOCATION_UNIT: u64 = 2048;
+
+/// The default ratio of dirty pages to the capacity of the page cache in percent above which the
+/// background flusher writes back dirty pages.
+pub const DEFAULT_DIRTY_PAGE_THRESHOLD: u8 = 25;
+
+/// The maximum number of adjacent pages coalesced into a single write.
+pub const MAX_COALESCED_PAGES: usize = 64;
 
 /// [`AwaitFreePage`] waits until a free page is available for the corresponding page manager.
 #[derive(Debug)]
 pub struct AwaitFreePage<'p> {
     /// The page manager to provide free pages for which the [`AwaitFreePage`] waits.
     page_manager: &'p PageManager,
+
+    /// The length of the database file when free pages were found insufficient.
+    file_len: u64,
+
+    /// The number of contiguous free pages to wait for.
+    num_pages: u64,
 }
 
 /// [`AwaitCachedPage`] waits until the specified page is cached from the file.
@@ -61,31 +125,114 @@ pub struct AwaitCachedPage<'p> {
     page_address: u64,
 }
 
+/// [`AwaitDefragmentation`] waits until the database file is defragmented.
+#[derive(Debug)]
+pub struct AwaitDefragmentation<'p> {
+    /// The page manager that defragments the database file.
+    page_manager: &'p PageManager,
+
+    /// The defragmentation epoch before the defragmentation request was made.
+    defragmentation_epoch: u64,
+}
+
 impl PageManager {
     /// Creates a new [`PageManager`].
+    ///
+    /// The page and segment sizes are only used when a new database is created; if `None`, the
+    /// sizes of the existing database or the default sizes are used. If `migrate` is `true`, the
+    /// database file is upgraded to the current version if needed. Pages are written to
+    /// `double_write_file` before being written to the database file.
     #[inline]
     pub fn from_db(
-        db: RandomAccessFile,
+        mut db: RandomAccessFile,
+        double_write_file: Option<RandomAccessFile>,
+        options: &OpenOptions,
         file_io_task_sender: SyncSender<IOTask>,
+        telemetry: Arc<Telemetry>,
     ) -> Result<Self, Error> {
-        let db_header = DatabaseHeader::from_file(&db)?;
+        let db_header = DatabaseHeader::from_file(
+            &db,
+            options.page_size,
+            options.segment_size,
+            options.migrate,
+        )?;
+        if db_header.segment_size != 0 {
+            db.enable_segments(db_header.segment_size)?;
+        }
+        let page_allocator =
+            PageAllocator::from_file(&db, db_header.first_free_page(), db_header.page_size)?;
+        let page_cache_capacity = match options.page_cache_capacity {
+            Some(capacity) => capacity,
+            None => usize::try_from(0x100_0000 * DEFAULT_PAGE_SIZE / db_header.page_size)
+                .map_err(|_| Error::OutOfMemory)?,
+        };
+        let clean_shutdown = db_header.clean_shutdown;
+        let clock = db_header.clock;
         Ok(Self {
             db,
+            double_write_buffer: double_write_file.map(DoubleWriteBuffer::new),
             db_header,
-            page_cache: HashCache::with_capacity(0x10, 0x100_0000),
+            page_allocator,
+            defragmentation_epoch: AtomicU64::new(0),
+            page_cache: HashCache::with_capacity(0x10, page_cache_capacity),
+            quarantined_pages: HashSet::default(),
+            dirty_page_threshold: AtomicU8::new(DEFAULT_DIRTY_PAGE_THRESHOLD),
+            clean_shutdown: AtomicBool::new(clean_shutdown),
+            clock: AtomicU64::new(clock),
+            backup_clock: AtomicU64::new(u64::MAX),
+            backup_clocks: HashSet::default(),
+            page_clocks: HashMap::default(),
             file_io_task_sender,
+            telemetry,
             waker_bag_for_free_page: Bag::default(),
             waker_bag_for_caching_page: Bag::default(),
+            waker_bag_for_defragmentation: Bag::default(),
         })
     }
 
+    /// Returns the size of a page.
+    #[inline]
+    pub fn page_size(&self) -> u64 {
+        self.db_header.page_size
+    }
+
+    /// Returns the size of a segment of the database and log files.
+    ///
+    /// `0` is returned if the files are not split into segments.
+    #[inline]
+    pub fn segment_size(&self) -> u64 {
+        self.db_header.segment_size
+    }
+
+    /// Returns `true` if the database file is opened read-only.
+    #[inline]
+    pub fn is_read_only(&self) -> bool {
+        self.db.is_read_only()
+    }
+
+    /// Returns the size of the content of a page.
+    #[inline]
+    pub fn page_payload_len(&self) -> usize {
+        let page_size = usize::try_from(self.page_size()).unwrap_or(usize::MAX);
+        if self.db.cipher().is_some() {
+            page_size - PAGE_HEADER_LEN - PAGE_FOOTER_LEN - ENCRYPTION_INFO_LEN
+        } else {
+            page_size - PAGE_HEADER_LEN - PAGE_FOOTER_LEN
+        }
+    }
+
+    /// Returns the address of the container directory head page.
+    #[inline]
+    pub fn container_directory_head(&self) -> u64 {
+        self.db_header.container_directory_head
+    }
+
     /// Creates a new page and appends the newly created page to the specified page.
     ///
     /// This assumes that the caller owns the page chain.
-    #[allow(dead_code)]
     #[inline]
     pub async fn create_page(&self, prev_page_address: u64) -> Result<u64, Error> {
-        debug_assert_eq!(prev_page_address % PAGE_SIZE, 0);
+        debug_assert_eq!(prev_page_address % self.page_size(), 0);
         loop {
             let free_page_address = self.get_free_page().await?;
             let result = self
@@ -110,13 +257,46 @@ impl PageManager {
         }
     }
 
+    /// Creates a new page, and inserts it between the specified page and the next page of it.
+    ///
+    /// Unlike [`create_page`](Self::create_page), the new page is reachable from the specified
+    /// page, therefore [`delete_page`](Self::delete_page) is able to unlink the new page from the
+    /// page chain. This assumes that the caller owns the page chain.
+    #[inline]
+    pub async fn create_linked_page(&self, prev_page_address: u64) -> Result<u64, Error> {
+        let new_page_address = self.create_page(prev_page_address).await?;
+        let old_next_page_address = self
+            .write_page(prev_page_address, |prev_page| {
+                let old_next_page_address = prev_page.next_page_address();
+                prev_page.set_next_page_address(new_page_address);
+                prev_page.set_dirty();
+                old_next_page_address
+            })
+            .await?;
+        self.write_page(new_page_address, |new_page| {
+            new_page.set_next_page_address(old_next_page_address);
+            new_page.set_dirty();
+        })
+        .await?;
+        if old_next_page_address != 0 {
+            self.write_page(old_next_page_address, |old_next_page| {
+                old_next_page.set_prev_page_address(new_page_address);
+                old_next_page.set_dirty();
+            })
+            .await?;
+            self.request_write_back(old_next_page_address);
+        }
+        self.request_write_back(new_page_address);
+        self.request_write_back(prev_page_address);
+        Ok(new_page_address)
+    }
+
     /// Deletes an existing page.
     ///
     /// This assumes that the caller owns the page chain.
-    #[allow(dead_code)]
     #[inline]
     pub async fn delete_page(&self, page_address: u64) -> Result<(), Error> {
-        debug_assert_eq!(page_address % PAGE_SIZE, 0);
+        debug_assert_eq!(page_address % self.page_size(), 0);
         let (prev_page_address, next_page_address) = self
             .read_page(page_address, |e| {
                 (e.prev_page_address(), e.next_page_address())
@@ -169,6 +349,316 @@ impl PageManager {
         Ok(())
     }
 
+    /// Moves the page to the first free page if the free page precedes it, and returns the new
+    /// address of the page.
+    ///
+    /// The content of the page is copied to the free page, and the neighbours of the page are
+    /// linked to the new page before the page is freed, so that pages at the end of the database
+    /// file can be truncated by [`defragment`](Self::defragment). The page must only be referenced
+    /// through the page headers of its neighbours, and this assumes that the caller owns the page
+    /// chain.
+    #[inline]
+    pub async fn relocate_page(&self, page_address: u64) -> Result<u64, Error> {
+        debug_assert_eq!(page_address % self.page_size(), 0);
+        let Some(new_page_address) = self.page_allocator.allocate() else {
+            return Ok(page_address);
+        };
+        if new_page_address >= page_address {
+            self.add_free_page(new_page_address);
+            return Ok(page_address);
+        }
+        let (prev_page_address, next_page_address, content) = self
+            .read_page(page_address, |e| {
+                (
+                    e.prev_page_address(),
+                    e.next_page_address(),
+                    e.buffer().to_vec(),
+                )
+            })
+            .await?;
+
+        // 1. Copy the page, and write back.
+        self.write_page(new_page_address, |e| {
+            e.set_prev_page_address(prev_page_address);
+            e.set_next_page_address(next_page_address);
+            e.buffer_mut().copy_from_slice(&content);
+            e.set_dirty();
+        })
+        .await?;
+        self.request_write_back(new_page_address);
+
+        // 2. Update `prev.next` and `next.prev`, and write back.
+        if prev_page_address != 0 {
+            let linked = self
+                .write_page(prev_page_address, |e| {
+                    if e.next_page_address() != page_address {
+                        return false;
+                    }
+                    e.set_next_page_address(new_page_address);
+                    e.set_dirty();
+                    true
+                })
+                .await?;
+            if linked {
+                self.request_write_back(prev_page_address);
+            }
+        }
+        if next_page_address != 0 {
+            self.write_page(next_page_address, |e| {
+                e.set_prev_page_address(new_page_address);
+                e.set_dirty();
+            })
+            .await?;
+            self.request_write_back(next_page_address);
+        }
+
+        // 3. Free the page; a crash before this leaves the page unreachable from the neighbours,
+        // and the page is detected by back-tracking `PREV_OFFSET` during recovery.
+        self.write_page(page_address, |e| {
+            e.set_prev_page_address(0);
+            e.set_next_page_address(0);
+            e.set_dirty();
+        })
+        .await?;
+        self.request_write_back(page_address);
+        self.add_free_page(page_address);
+        Ok(new_page_address)
+    }
+
+    /// Creates the specified number of contiguous pages, and appends them to the specified page.
+    ///
+    /// The newly created pages are linked in ascending address order, and the address of the
+    /// first page is returned. This assumes that the caller owns the page chain.
+    #[allow(dead_code)]
+    #[inline]
+    pub async fn create_extent(
+        &self,
+        prev_page_address: u64,
+        num_pages: u64,
+    ) -> Result<u64, Error> {
+        debug_assert_eq!(prev_page_address % self.page_size(), 0);
+        debug_assert_ne!(num_pages, 0);
+        'retry: loop {
+            let extent_address = self.get_free_extent(num_pages).await?;
+            for i in 0..num_pages {
+                let page_address = extent_address + i * self.page_size();
+                if self
+                    .read_page(page_address, EvictablePage::prev_page_address)
+                    .await?
+                    != 0
+                {
+                    // The page is linked to another page; return the other ones.
+                    for j in (0..num_pages).filter(|j| *j != i) {
+                        self.add_free_page(extent_address + j * self.page_size());
+                    }
+                    continue 'retry;
+                }
+            }
+            for i in 0..num_pages {
+                let page_address = extent_address + i * self.page_size();
+                let prev = if i == 0 {
+                    prev_page_address
+                } else {
+                    page_address - self.page_size()
+                };
+                let next = if i + 1 == num_pages {
+                    0
+                } else {
+                    page_address + self.page_size()
+                };
+                self.write_page(page_address, |e| {
+                    e.set_prev_page_address(prev);
+                    e.set_next_page_address(next);
+                    e.set_dirty();
+                })
+                .await?;
+                self.request_write_back(page_address);
+            }
+            return Ok(extent_address);
+        }
+    }
+
+    /// Deletes the specified number of contiguous pages that were created by
+    /// [`create_extent`](Self::create_extent).
+    ///
+    /// This assumes that the caller owns the page chain.
+    #[allow(dead_code)]
+    #[inline]
+    pub async fn delete_extent(&self, extent_address: u64, num_pages: u64) -> Result<(), Error> {
+        debug_assert_eq!(extent_address % self.page_size(), 0);
+        debug_assert_ne!(num_pages, 0);
+        let last_page_address = extent_address + (num_pages - 1) * self.page_size();
+        let prev_page_address = self
+            .read_page(extent_address, EvictablePage::prev_page_address)
+            .await?;
+        let next_page_address = self
+            .read_page(last_page_address, EvictablePage::next_page_address)
+            .await?;
+
+        // The procedure is identical to that of `delete_page`.
+        if prev_page_address != 0 {
+            self.write_page(prev_page_address, |e| {
+                e.set_next_page_address(next_page_address);
+                e.set_dirty();
+            })
+            .await?;
+            self.request_write_back(prev_page_address);
+        }
+        if next_page_address != 0 {
+            self.write_page(next_page_address, |e| {
+                e.set_prev_page_address(prev_page_address);
+                e.set_dirty();
+            })
+            .await?;
+            self.request_write_back(next_page_address);
+        }
+        for i in 0..num_pages {
+            let page_address = extent_address + i * self.page_size();
+            self.write_page(page_address, |e| {
+                e.set_prev_page_address(0);
+                e.set_next_page_address(0);
+                e.set_dirty();
+            })
+            .await?;
+            self.request_write_back(page_address);
+        }
+        self.page_allocator.free_extent(extent_address, num_pages);
+        self.waker_bag_for_free_page.pop_all((), |(), w| w.wake());
+
+        Ok(())
+    }
+
+    /// Defragments the database file.
+    ///
+    /// Free pages at the end of the database file are returned to the file system, and the new
+    /// length of the database file is returned. Pages in use are not relocated since page
+    /// addresses are directly referred to by other pages; instead, free pages are always allocated
+    /// in ascending address order to let used pages gather at the beginning of the file, and
+    /// owners of page chains may move their pages with [`relocate_page`](Self::relocate_page).
+    #[inline]
+    pub async fn defragment(&self) -> Result<u64, Error> {
+        if self.is_read_only() {
+            return Err(Error::ReadOnly);
+        }
+        let defragmentation_epoch = self.defragmentation_epoch.load(Acquire);
+        if self.file_io_task_sender.send(IOTask::Defragment).is_err() {
+            return Err(Error::UnexpectedState);
+        }
+        AwaitDefragmentation {
+            page_manager: self,
+            defragmentation_epoch,
+        }
+        .await;
+        Ok(self.db.len(Relaxed))
+    }
+
+    /// Verifies the header page, the page checksums, the free page list, and the links between
+    /// pages.
+    ///
+    /// Pages are read through the page cache, therefore cached pages are verified in their latest
+    /// state without being written back.
+    #[inline]
+    pub async fn verify(&self) -> IntegrityReport {
+        let mut report = IntegrityReport {
+            clock: self.clock(),
+            ..IntegrityReport::default()
+        };
+        let file_len = self.db.len(Relaxed);
+        let is_valid_link =
+            |link: u64| link.is_multiple_of(self.page_size()) && link != 0 && link < file_len;
+        let mut headers = Vec::new();
+        for page_address in (0..file_len / self.page_size()).map(|p| p * self.page_size()) {
+            report.pages_checked += 1;
+            match self
+                .read_page(page_address, |e| {
+                    (e.prev_page_address(), e.next_page_address())
+                })
+                .await
+            {
+                Ok(header) => headers.push((page_address, header)),
+                Err(_) => report
+                    .problems
+                    .push(IntegrityProblem::CorruptPage(page_address)),
+            }
+        }
+        let header_of = |page_address: u64| {
+            headers
+                .binary_search_by_key(&page_address, |(a, _)| *a)
+                .ok()
+                .map(|i| headers[i].1)
+        };
+        let first_free_page = self.db_header.first_free_page();
+        for &(page_address, (prev, next)) in headers.iter().filter(|(a, _)| *a != 0) {
+            let is_free = page_address >= first_free_page && prev == 0;
+            if is_free {
+                report.free_pages += 1;
+                if !self.page_allocator.is_free(page_address) {
+                    report
+                        .problems
+                        .push(IntegrityProblem::UnlistedFreePage(page_address));
+                }
+            } else if self.page_allocator.is_free(page_address) {
+                report
+                    .problems
+                    .push(IntegrityProblem::AllocatedFreePage(page_address));
+            }
+            for link in [prev, next] {
+                if link != 0 && !is_valid_link(link) {
+                    report.problems.push(IntegrityProblem::InvalidLink {
+                        page: page_address,
+                        link,
+                    });
+                }
+            }
+            if is_valid_link(prev)
+                && prev >= first_free_page
+                && header_of(prev).is_some_and(|(prev_of_prev, _)| prev_of_prev == 0)
+            {
+                report.problems.push(IntegrityProblem::DanglingLink {
+                    page: page_address,
+                    prev,
+                });
+            }
+            if let Some((prev_of_next, _)) = header_of(next).filter(|_| is_valid_link(next)) {
+                if prev_of_next != page_address {
+                    report.problems.push(IntegrityProblem::BrokenLink {
+                        page: page_address,
+                        next,
+                        prev_of_next,
+                    });
+                }
+            }
+        }
+        report
+    }
+
+    /// Requests the IO task processor to write back the page.
+    #[inline]
+    pub fn request_write_back(&self, page_address: u64) {
+        debug_assert_eq!(page_address % self.page_size(), 0);
+        drop(
+            self.file_io_task_sender
+                .send(IOTask::WriteBack(page_address)),
+        );
+    }
+
+    /// Requests the IO task processor to read the page into the page cache unless it is cached.
+    ///
+    /// It does not wait for the page to be read, so that the page is read in the background
+    /// while the caller processes other pages.
+    #[inline]
+    pub async fn prefetch_page(&self, page_address: u64) {
+        debug_assert_eq!(page_address % self.page_size(), 0);
+        if !self.page_cache.contains_async(&page_address).await
+            && !self.quarantined_pages.contains_async(&page_address).await
+        {
+            drop(
+                self.file_io_task_sender
+                    .send(IOTask::FillCache(page_address)),
+            );
+        }
+    }
+
     /// Reads a page in the database.
     ///
     /// # Errors
@@ -180,7 +670,7 @@ impl PageManager {
         page_address: u64,
         reader: F,
     ) -> Result<R, Error> {
-        debug_assert_eq!(page_address % PAGE_SIZE, 0);
+        debug_assert_eq!(page_address % self.page_size(), 0);
         let mut reader = Some(reader);
         loop {
             if let Some(result) = self
@@ -188,8 +678,13 @@ impl PageManager {
                 .read_async(&page_address, |_, v| reader.take().unwrap()(v))
                 .await
             {
+                self.telemetry.add(Counter::PageCacheHits, 1);
                 return Ok(result);
             }
+            self.telemetry.add(Counter::PageCacheMisses, 1);
+            if self.quarantined_pages.contains_async(&page_address).await {
+                return Err(Error::CorruptPage(page_address));
+            }
             drop(
                 self.file_io_task_sender
                     .send(IOTask::FillCache(page_address)),
@@ -199,6 +694,9 @@ impl PageManager {
                 page_address,
             }
             .await;
+            if self.quarantined_pages.contains_async(&page_address).await {
+                return Err(Error::CorruptPage(page_address));
+            }
         }
     }
 
@@ -213,11 +711,19 @@ impl PageManager {
         page_address: u64,
         writer: F,
     ) -> Result<R, Error> {
-        debug_assert_eq!(page_address % PAGE_SIZE, 0);
+        debug_assert_eq!(page_address % self.page_size(), 0);
+        if self.is_read_only() {
+            return Err(Error::ReadOnly);
+        }
         loop {
             if let Entry::Occupied(mut o) = self.page_cache.entry_async(page_address).await {
+                self.telemetry.add(Counter::PageCacheHits, 1);
                 return Ok(writer(o.get_mut()));
             }
+            self.telemetry.add(Counter::PageCacheMisses, 1);
+            if self.quarantined_pages.contains_async(&page_address).await {
+                return Err(Error::CorruptPage(page_address));
+            }
             drop(
                 self.file_io_task_sender
                     .send(IOTask::FillCache(page_address)),
@@ -227,18 +733,64 @@ impl PageManager {
                 page_address,
             }
             .await;
+            if self.quarantined_pages.contains_async(&page_address).await {
+                return Err(Error::CorruptPage(page_address));
+            }
+        }
+    }
+
+    /// Reads a page in the database synchronously.
+    ///
+    /// It is a synchronous method, therefore it should be run in the background.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the page is beyond the end of the database file or torn.
+    pub(super) fn read_page_sync<R, F: FnOnce(&EvictablePage) -> R>(
+        &self,
+        page_address: u64,
+        reader: F,
+    ) -> Result<R, Error> {
+        debug_assert_eq!(page_address % self.page_size(), 0);
+        if !page_address.is_multiple_of(self.page_size()) || page_address >= self.db.len(Relaxed) {
+            return Err(Error::CorruptPage(page_address));
+        }
+        let mut reader = Some(reader);
+        loop {
+            if let Some(result) = self
+                .page_cache
+                .read(&page_address, |_, v| reader.take().unwrap()(v))
+            {
+                return Ok(result);
+            }
+            if self.quarantined_pages.contains(&page_address) {
+                return Err(Error::CorruptPage(page_address));
+            }
+            self.fill_cache_sync(page_address);
         }
     }
 
     /// Fills the cache entry corresponding to the specified page address.
     pub(super) fn fill_cache_sync(&self, page_address: u64) {
-        debug_assert_eq!(page_address % PAGE_SIZE, 0);
+        debug_assert_eq!(page_address % self.page_size(), 0);
         while let Entry::Vacant(v) = self.page_cache.entry(page_address) {
-            let Ok(evictable_page) = EvictablePage::from_file(&self.db, page_address) else {
-                drop(v);
-                yield_now();
-                continue;
-            };
+            let evictable_page =
+                match EvictablePage::from_file(&self.db, page_address, self.page_size()) {
+                    Ok(evictable_page) => {
+                        self.telemetry.add(Counter::PagesRead, 1);
+                        evictable_page
+                    }
+                    Err(Error::CorruptPage(_)) => {
+                        // The page is torn; it is quarantined, and readers are notified of it.
+                        let _ = self.quarantined_pages.insert(page_address);
+                        break;
+                    }
+                    Err(_) => {
+                        drop(v);
+                        yield_now();
+                        continue;
+                    }
+                };
             let (evicted, inserted) = v.put_entry(Box::new(evictable_page));
             if let Some((_, mut evicted)) = evicted {
                 if evicted.is_dirty() {
@@ -251,134 +803,619 @@ impl PageManager {
             }
             break;
         }
-        self.waker_bag_for_caching_page.pop_all((), |_, w| w.wake());
+        self.waker_bag_for_caching_page
+            .pop_all((), |(), w| w.wake());
     }
 
-    /// Resizes the database file.
+    /// Returns the dirty page threshold of the background flusher in percent, or `None` if the
+    /// flusher is disabled.
+    pub(super) fn dirty_page_threshold(&self) -> Option<u8> {
+        Some(self.dirty_page_threshold.load(Relaxed)).filter(|t| *t != u8::MAX)
+    }
+
+    /// Sets the dirty page threshold of the background flusher in percent, or disables the
+    /// flusher if `None` is specified.
+    pub(super) fn set_dirty_page_threshold(&self, threshold: Option<u8>) {
+        self.dirty_page_threshold
+            .store(threshold.map_or(u8::MAX, |t| t.min(100)), Relaxed);
+    }
+
+    /// Writes back dirty cached pages if the ratio of dirty pages to the capacity of the page
+    /// cache is at or above `threshold` percent, and returns the number of pages written back.
     ///
-    /// It is a synchronous method, therefore it should be run in the background.
-    pub(super) fn resize_sync(&self, new_size: u64) {
-        debug_assert_eq!(new_size % PAGE_SIZE, 0);
-        let old_size = self.db.len(Relaxed);
-        debug_assert_eq!(old_size % PAGE_SIZE, 0);
-        while self.db.set_len(new_size).is_err() {
-            yield_now();
+    /// Dirty pages are written back in ascending address order until the ratio falls to half the
+    /// threshold, so that the amount of background writes grows with the number of dirty pages,
+    /// and runs of adjacent pages are coalesced into single writes of up to
+    /// [`MAX_COALESCED_PAGES`] pages. Similarly to evicting pages, pages are written back
+    /// regardless of the order of write back requests. It is a synchronous method, therefore it
+    /// should be run in the background.
+    pub(super) fn flush_dirty_pages_sync(&self, threshold: u8) -> usize {
+        if self.write_error().is_some() {
+            return 0;
+        }
+        let mut dirty_pages = Vec::new();
+        self.page_cache.scan(|page_address, page| {
+            if page.is_dirty() {
+                dirty_pages.push(*page_address);
+            }
+        });
+        let capacity = self.page_cache.capacity().max(1);
+        let threshold = usize::from(threshold.min(100));
+        if dirty_pages.is_empty() || dirty_pages.len() * 100 < capacity * threshold {
+            return 0;
         }
-        for i in old_size / PAGE_SIZE..new_size / PAGE_SIZE {
-            let free_page_address = i * PAGE_SIZE;
-            self.add_free_page(free_page_address);
+        dirty_pages.sort_unstable();
+        dirty_pages.truncate(dirty_pages.len() - capacity * threshold / 200);
+        let mut num_pages_written = 0;
+        let mut start = 0;
+        for end in 1..=dirty_pages.len() {
+            if end == dirty_pages.len()
+                || end - start == MAX_COALESCED_PAGES
+                || dirty_pages[end - 1] + self.page_size() != dirty_pages[end]
+            {
+                num_pages_written += self.write_back_coalesced_sync(&dirty_pages[start..end]);
+                if self.write_error().is_some() {
+                    break;
+                }
+                start = end;
+            }
         }
+        num_pages_written
     }
 
-    /// Writes back a dirty page with the page retained in the cache.
+    /// Repairs torn pages before the database is recovered from the log.
+    ///
+    /// Returns the number of torn pages. A torn page is restored from the double-write buffer if
+    /// an intact copy of it is retained there. Every torn page is restored before any is reset,
+    /// since resetting a page overwrites the double-write buffer.
+    ///
+    /// `checkpointed` is invoked once the torn pages are restored if any torn page is left, and
+    /// returns the pages that the latest checkpoint refers to, or `None` if they could not be
+    /// determined. A torn page that the checkpoint may refer to is left quarantined, since the log
+    /// records needed to reconstruct it may have been reclaimed; the container of which the index
+    /// contains the page is quarantined when the index is loaded. The other torn pages are reset
+    /// and returned to the free page list.
+    ///
+    /// TODO: replay page-level log records once page modifications are logged.
     ///
     /// It is a synchronous method, therefore it should be run in the background.
-    pub(super) fn write_back_sync(&self, page_address: u64) {
-        debug_assert_eq!(page_address % PAGE_SIZE, 0);
-        while let Some(mut o) = self.page_cache.get(&page_address) {
-            if o.get_mut().write_back(&self.db).is_ok() {
-                break;
+    pub(super) fn repair_torn_pages_sync<F: FnOnce() -> Option<Vec<u64>>>(
+        &self,
+        checkpointed: F,
+    ) -> usize {
+        let mut num_torn_pages = 0;
+        let mut pages_to_reset = Vec::new();
+        for page_address in
+            (1..self.db.len(Relaxed) / self.page_size()).map(|p| p * self.page_size())
+        {
+            if !matches!(
+                EvictablePage::from_file(&self.db, page_address, self.page_size()),
+                Err(Error::CorruptPage(_))
+            ) {
+                continue;
+            }
+            num_torn_pages += 1;
+            let Some(image) = self
+                .double_write_buffer
+                .as_ref()
+                .and_then(|d| d.read_page(page_address, self.page_size()))
+            else {
+                pages_to_reset.push(page_address);
+                continue;
+            };
+            while self.db.write(&image, page_address).is_err() {
+                if self.write_error().is_some() {
+                    break;
+                }
+                yield_now();
+            }
+            self.page_cache.remove(&page_address);
+            self.quarantined_pages.remove(&page_address);
+        }
+        if pages_to_reset.is_empty() {
+            return num_torn_pages;
+        }
+        let Some(checkpointed) = checkpointed() else {
+            return num_torn_pages;
+        };
+        for page_address in pages_to_reset {
+            if checkpointed.contains(&page_address) {
+                continue;
+            }
+            let Ok(mut evictable_page) =
+                EvictablePage::new(&self.db, page_address, self.page_size())
+            else {
+                continue;
+            };
+            self.write_back_evicted_sync(&mut evictable_page);
+            self.page_cache.remove(&page_address);
+            self.quarantined_pages.remove(&page_address);
+            if page_address >= self.db_header.first_free_page() {
+                self.add_free_page(page_address);
             }
-            drop(o);
-            yield_now();
         }
+        num_torn_pages
     }
 
-    /// Write back the evicted page.
+    /// Returns the addresses of quarantined pages in ascending order.
+    pub(super) fn quarantined_pages(&self) -> Vec<u64> {
+        let mut quarantined_pages = Vec::new();
+        self.quarantined_pages.scan(|p| quarantined_pages.push(*p));
+        quarantined_pages.sort_unstable();
+        quarantined_pages
+    }
+
+    /// Returns the logical clock persisted in the header page.
+    pub(super) fn clock(&self) -> u64 {
+        self.clock.load(Acquire)
+    }
+
+    /// Writes back every dirty cached page, and sets the clean shutdown marker along with the
+    /// clock in the header page.
     ///
-    /// It is a synchronous method, therefore it should be run in the background.
-    pub(super) fn write_back_evicted_sync(&self, page: &mut EvictablePage) {
-        while page.write_back(&self.db).is_err() {
+    /// The marker is cleared as soon as a page is written afterwards, whereas the clock is
+    /// retained. It is a synchronous method, therefore it should be run in the background.
+    pub(super) fn mark_clean_shutdown_sync(&self, clock: u64) {
+        self.write_back_dirty_pages_sync();
+        let clock = clock.max(self.clock());
+        if self.db.sync().is_err() {
+            // The database file has to be verified when reopened.
+            return;
+        }
+        while DatabaseHeader::write_clean_shutdown(&self.db, self.page_size(), Some(clock)).is_err()
+        {
+            if self.write_error().is_some() {
+                return;
+            }
             yield_now();
         }
+        drop(self.db.sync());
+        self.clock.store(clock, Release);
+        self.clean_shutdown.store(true, Release);
     }
 
-    /// Gets a free page.
-    async fn get_free_page(&self) -> Result<u64, Error> {
-        loop {
-            if let Some(free_page_address) = self.db_header.free_pages.pop() {
-                return Ok(free_page_address);
-            }
+    /// Writes back every dirty cached page, and then writes the payload of the container directory
+    /// head page.
+    ///
+    /// The pages written back are synchronized with the device before the head page is written,
+    /// so that the head page never refers to pages that have not reached the device. It is a
+    /// synchronous method, therefore it should be run in the background.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pages could not be written, or the head page is torn.
+    pub(super) fn write_container_directory_sync(&self, payload: &[u8]) -> Result<(), Error> {
+        let head = self.container_directory_head();
+        self.write_back_dirty_pages_sync();
+        self.db.sync()?;
+        loop {
+            if let Some(mut o) = self.page_cache.get(&head) {
+                let page = o.get_mut();
+                page.buffer_mut()[..payload.len()].copy_from_slice(payload);
+                page.set_dirty();
+                break;
+            }
+            if self.quarantined_pages.contains(&head) {
+                return Err(Error::CorruptPage(head));
+            }
+            self.fill_cache_sync(head);
+        }
+        self.write_back_sync(head);
+        if let Some(error) = self.write_error() {
+            return Err(error.clone());
+        }
+        self.db.sync()
+    }
 
-            // It is mandated to read at least `2048` pages.
-            let file_len = self.db.len(Relaxed);
-            let num_pages_to_read = ALLOCATION_UNIT / PAGE_SIZE;
-            let mut resize_file = false;
-            let read_from =
-                self.db_header
-                    .free_page_scanner_offset
-                    .fetch_update(Relaxed, Relaxed, |o| {
-                        if let Some(new_offset) = o.checked_add(ALLOCATION_UNIT) {
-                            if new_offset >= file_len {
-                                resize_file = true;
-                            } else {
-                                return Some(new_offset);
-                            }
-                        }
-
-                        // Integer overflow.
-                        None
-                    });
-            if let Ok(read_from) = read_from {
-                for i in 0..num_pages_to_read {
-                    #[allow(clippy::no_effect_underscore_binding)]
-                    let _offset = read_from + PAGE_SIZE * i;
-                    // TODO: asynchronously read the header.
+    /// Frees page chains of which the first page is linked back to the container directory head
+    /// page if the container directory does not refer to them, and returns the number of pages
+    /// freed.
+    ///
+    /// Such page chains are left behind if the database was not shut down cleanly while a
+    /// checkpoint was being taken, or before the pages of the previous checkpoint were freed. It is
+    /// a synchronous method, therefore it should be run in the background before the database is
+    /// recovered.
+    pub(super) fn reclaim_orphaned_pages_sync(&self, referenced: &[u64]) -> usize {
+        let head = self.container_directory_head();
+        let mut orphaned_pages = Vec::new();
+        for page_address in (self.db_header.first_free_page() / self.page_size()
+            ..self.db.len(Relaxed) / self.page_size())
+            .map(|p| p * self.page_size())
+        {
+            let Ok(prev_page_address) =
+                self.read_page_sync(page_address, EvictablePage::prev_page_address)
+            else {
+                continue;
+            };
+            if prev_page_address != head || referenced.contains(&page_address) {
+                continue;
+            }
+            let mut prev_page_address = page_address;
+            let mut next_page_address = page_address;
+            while next_page_address != 0 && !orphaned_pages.contains(&next_page_address) {
+                let Ok((prev, next)) = self.read_page_sync(next_page_address, |p| {
+                    (p.prev_page_address(), p.next_page_address())
+                }) else {
+                    break;
+                };
+                if next_page_address != page_address && prev != prev_page_address {
+                    break;
                 }
-            } else if resize_file {
-                drop(
-                    self.file_io_task_sender
-                        .send(IOTask::Resize(file_len + ALLOCATION_UNIT)),
-                );
-                AwaitFreePage { page_manager: self }.await;
+                orphaned_pages.push(next_page_address);
+                prev_page_address = next_page_address;
+                next_page_address = next;
+            }
+        }
+        for page_address in &orphaned_pages {
+            let Ok(mut evictable_page) =
+                EvictablePage::new(&self.db, *page_address, self.page_size())
+            else {
+                continue;
+            };
+            self.write_back_evicted_sync(&mut evictable_page);
+            self.page_cache.remove(page_address);
+            self.add_free_page(*page_address);
+        }
+        orphaned_pages.len()
+    }
+
+    /// Clears the clean shutdown marker in the header page.
+    ///
+    /// Returns `true` if the marker was set. It is a synchronous method, therefore it should be
+    /// run in the background.
+    pub(super) fn clear_clean_shutdown_sync(&self) -> bool {
+        if !self.clean_shutdown.load(Acquire) || !self.clean_shutdown.swap(false, AcqRel) {
+            return false;
+        }
+        while DatabaseHeader::write_clean_shutdown(&self.db, self.page_size(), None).is_err() {
+            if self.write_error().is_some() {
+                break;
+            }
+            yield_now();
+        }
+        true
+    }
+
+    /// Resizes the database file.
+    ///
+    /// It is a synchronous method, therefore it should be run in the background.
+    pub(super) fn resize_sync(&self, new_size: u64) {
+        debug_assert_eq!(new_size % self.page_size(), 0);
+        let old_size = self.db.len(Relaxed);
+        debug_assert_eq!(old_size % self.page_size(), 0);
+        if new_size > old_size {
+            // Outdated requests must not shrink the file.
+            while self.db.set_len(new_size).is_err() {
+                yield_now();
+            }
+            self.page_allocator
+                .free_extent(old_size, (new_size - old_size) / self.page_size());
+        }
+        self.waker_bag_for_free_page.pop_all((), |(), w| w.wake());
+    }
+
+    /// Truncates trailing free pages of the database file.
+    ///
+    /// It is a synchronous method, therefore it should be run in the background; all the write
+    /// back requests made before the defragmentation request must have been processed.
+    pub(super) fn defragment_sync(&self) {
+        let file_len = self.db.len(Relaxed);
+        let new_len = self.page_allocator.truncate(file_len);
+        if new_len != file_len {
+            for page_address in (new_len / self.page_size()..file_len / self.page_size())
+                .map(|p| p * self.page_size())
+            {
+                // The pages are free, therefore they can be discarded once written back.
+                self.write_back_sync(page_address);
+                self.page_cache.remove(&page_address);
+                self.record_page_write(page_address);
+            }
+            while self.db.set_len(new_len).is_err() {
+                yield_now();
+            }
+        }
+        self.defragmentation_epoch.fetch_add(1, Release);
+        self.waker_bag_for_defragmentation
+            .pop_all((), |(), w| w.wake());
+    }
+
+    /// Returns the clock of the latest backup taken by the [`PageManager`].
+    pub(super) fn latest_backup_clock(&self) -> Option<u64> {
+        Some(self.backup_clock.load(Relaxed)).filter(|c| *c != u64::MAX)
+    }
+
+    /// Passes the content of pages to `writer` in ascending address order, and returns the length
+    /// of the database file.
+    ///
+    /// If `since` is the clock of a backup taken by the [`PageManager`], only the pages written
+    /// after the backup are passed, otherwise every page is passed. Dirty cached pages are written
+    /// back before being passed, and `clock` becomes the clock of the latest backup. It is a
+    /// synchronous method, therefore it should be run in the background.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page could not be read or `writer` failed, in which case `clock`
+    /// does not become the clock of a backup.
+    pub(super) fn copy_pages_sync<F: FnMut(u64, &[u8]) -> Result<(), Error>>(
+        &self,
+        since: Option<u64>,
+        clock: u64,
+        mut writer: F,
+    ) -> Result<u64, Error> {
+        let since = since.filter(|c| self.backup_clocks.contains(c));
+        let file_len = self.db.len(Relaxed);
+        let mut page_buffer = vec![0_u8; usize::try_from(self.page_size()).unwrap_or(0)];
+        for page_address in (0..file_len / self.page_size()).map(|p| p * self.page_size()) {
+            if self.page_cache.read(&page_address, |_, p| p.is_dirty()) == Some(true) {
+                self.write_back_sync(page_address);
+            }
+            if since.is_some_and(|since| {
+                self.page_clocks
+                    .read(&page_address, |_, c| *c < since)
+                    .unwrap_or(true)
+            }) {
+                continue;
+            }
+            self.db.read(&mut page_buffer, page_address)?;
+            writer(page_address, &page_buffer)?;
+        }
+        let _ = self.backup_clocks.insert(clock);
+        self.backup_clock.store(clock, Relaxed);
+        Ok(file_len)
+    }
+
+    /// Writes back a dirty page with the page retained in the cache.
+    ///
+    /// It is a synchronous method, therefore it should be run in the background.
+    pub(super) fn write_back_sync(&self, page_address: u64) {
+        debug_assert_eq!(page_address % self.page_size(), 0);
+        self.clear_clean_shutdown_sync();
+        while let Some(mut o) = self.page_cache.get(&page_address) {
+            if self.write_back_page(o.get_mut()).is_ok() {
+                self.telemetry.add(Counter::PagesWritten, 1);
+                self.record_page_write(page_address);
+                break;
+            }
+            if self.write_error().is_some() {
+                // The page remains dirty since the content on the device is unknown.
+                break;
+            }
+            drop(o);
+            yield_now();
+        }
+    }
+
+    /// Write back the evicted page.
+    ///
+    /// It is a synchronous method, therefore it should be run in the background.
+    pub(super) fn write_back_evicted_sync(&self, page: &mut EvictablePage) {
+        self.clear_clean_shutdown_sync();
+        while self.write_back_page(page).is_err() {
+            if self.write_error().is_some() {
+                return;
+            }
+            yield_now();
+        }
+        self.telemetry.add(Counter::PagesWritten, 1);
+        self.record_page_write(page.address());
+    }
+
+    /// Sets the [`FaultyFile`] that injects faults into write operations of the database file.
+    pub(super) fn set_faulty_file(&self, faulty_file: FaultyFile) -> bool {
+        self.db.set_faulty_file(faulty_file)
+    }
+
+    /// Returns the error that writing the database file failed with if the file refuses writes.
+    pub(super) fn write_error(&self) -> Option<&Error> {
+        self.db.write_error().or_else(|| {
+            self.double_write_buffer
+                .as_ref()
+                .and_then(DoubleWriteBuffer::write_error)
+        })
+    }
+
+    /// Writes back every dirty cached page.
+    fn write_back_dirty_pages_sync(&self) {
+        for page_address in
+            (1..self.db.len(Relaxed) / self.page_size()).map(|p| p * self.page_size())
+        {
+            if self.page_cache.read(&page_address, |_, p| p.is_dirty()) == Some(true) {
+                self.write_back_sync(page_address);
+            }
+        }
+    }
+
+    /// Writes back the page through the double-write buffer.
+    fn write_back_page(&self, page: &mut EvictablePage) -> Result<(), Error> {
+        let page_address = page.address();
+        page.seal(&self.db, |image| {
+            self.double_write_buffer
+                .as_ref()
+                .ok_or(Error::ReadOnly)?
+                .write(&self.db, page_address, image)
+        })??;
+        page.set_clean();
+        Ok(())
+    }
+
+    /// Records that the page was written after the latest backup.
+    fn record_page_write(&self, page_address: u64) {
+        let backup_clock = self.backup_clock.load(Relaxed);
+        if backup_clock != u64::MAX {
+            self.page_clocks.upsert(page_address, backup_clock);
+        }
+    }
+
+    /// Writes back the specified adjacent dirty pages in a single write, and returns the number of
+    /// pages written back.
+    ///
+    /// Pages that are modified while being written remain dirty, and the run is cut short at the
+    /// first page that is no longer cached or dirty.
+    fn write_back_coalesced_sync(&self, page_addresses: &[u64]) -> usize {
+        let Some(&start_address) = page_addresses.first() else {
+            return 0;
+        };
+        let page_size = usize::try_from(self.page_size()).unwrap_or(0);
+        let mut image = Vec::with_capacity(page_addresses.len() * page_size);
+        let mut contents = Vec::with_capacity(page_addresses.len());
+        for page_address in page_addresses {
+            let Some(mut o) = self.page_cache.get(page_address) else {
+                break;
+            };
+            if !o.get().is_dirty() {
+                break;
+            }
+            let page_contents = o.get().contents().to_vec();
+            if o.get_mut()
+                .seal(&self.db, |page_image| image.extend_from_slice(page_image))
+                .is_err()
+            {
+                break;
+            }
+            contents.push(page_contents);
+        }
+        if contents.is_empty() {
+            return 0;
+        }
+        self.clear_clean_shutdown_sync();
+        while self
+            .double_write_buffer
+            .as_ref()
+            .ok_or(Error::ReadOnly)
+            .and_then(|d| d.write(&self.db, start_address, &image))
+            .is_err()
+        {
+            if self.write_error().is_some() {
+                // The pages remain dirty since the content on the device is unknown.
+                return 0;
+            }
+            yield_now();
+        }
+        for (page_address, page_contents) in page_addresses.iter().zip(contents.iter()) {
+            if let Some(mut o) = self.page_cache.get(page_address) {
+                if o.get().contents() == page_contents.as_slice() {
+                    o.get_mut().set_clean();
+                }
+            }
+            self.record_page_write(*page_address);
+        }
+        self.telemetry
+            .add(Counter::PagesWritten, contents.len() as u64);
+        contents.len()
+    }
+
+    /// Gets a free page.
+    async fn get_free_page(&self) -> Result<u64, Error> {
+        self.get_free_extent(1).await
+    }
+
+    /// Gets the specified number of contiguous free pages.
+    async fn get_free_extent(&self, num_pages: u64) -> Result<u64, Error> {
+        if self.is_read_only() {
+            return Err(Error::ReadOnly);
+        }
+        loop {
+            let file_len = self.db.len(Relaxed);
+            let extent_address = if num_pages == 1 {
+                self.page_allocator.allocate()
             } else {
-                // TODO: handle the integer overflow.
-                todo!();
+                self.page_allocator.allocate_extent(num_pages)
+            };
+            if let Some(extent_address) = extent_address {
+                return Ok(extent_address);
+            }
+
+            // It is mandated to allocate at least `2048` pages.
+            let Some(new_len) = num_pages
+                .max(ALLOCATION_UNIT)
+                .checked_mul(self.page_size())
+                .and_then(|l| file_len.checked_add(l))
+            else {
+                return Err(Error::OutOfMemory);
+            };
+            drop(self.file_io_task_sender.send(IOTask::Resize(new_len)));
+            AwaitFreePage {
+                page_manager: self,
+                file_len,
+                num_pages,
             }
+            .await;
         }
     }
 
     /// Adds a free page.
     fn add_free_page(&self, free_page_address: u64) {
-        debug_assert_eq!(free_page_address % PAGE_SIZE, 0);
-        self.db_header.free_pages.push(free_page_address);
-        self.waker_bag_for_free_page.pop_all((), |_, w| w.wake());
+        debug_assert_eq!(free_page_address % self.page_size(), 0);
+        self.page_allocator.free(free_page_address);
+        self.waker_bag_for_free_page.pop_all((), |(), w| w.wake());
     }
 }
 
-impl<'p> Future for AwaitFreePage<'p> {
+impl Future for AwaitFreePage<'_> {
     type Output = ();
 
     #[inline]
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        if !self.page_manager.db_header.free_pages.is_empty() {
+        let available = || {
+            self.page_manager.db.len(Relaxed) != self.file_len
+                || (self.num_pages == 1 && !self.page_manager.page_allocator.is_empty())
+        };
+        if available() {
             return Poll::Ready(());
         }
 
         self.page_manager
             .waker_bag_for_free_page
             .push(cx.waker().clone());
-        if self.page_manager.db_header.free_pages.is_empty() {
-            Poll::Pending
+        if available() {
+            Poll::Ready(())
         } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl Future for AwaitDefragmentation<'_> {
+    type Output = ();
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let completed =
+            || self.page_manager.defragmentation_epoch.load(Acquire) != self.defragmentation_epoch;
+        if completed() {
+            return Poll::Ready(());
+        }
+        self.page_manager
+            .waker_bag_for_defragmentation
+            .push(cx.waker().clone());
+        if completed() {
             Poll::Ready(())
+        } else {
+            Poll::Pending
         }
     }
 }
 
-impl<'p> Future for AwaitCachedPage<'p> {
+impl Future for AwaitCachedPage<'_> {
     type Output = ();
 
     #[inline]
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         // TODO: it is a synchronous call, fix me later.
-        if self.page_manager.page_cache.contains(&self.page_address) {
+        let available = || {
+            self.page_manager.page_cache.contains(&self.page_address)
+                || self
+                    .page_manager
+                    .quarantined_pages
+                    .contains(&self.page_address)
+        };
+        if available() {
             return Poll::Ready(());
         }
         self.page_manager
             .waker_bag_for_caching_page
             .push(cx.waker().clone());
-        if self.page_manager.page_cache.contains(&self.page_address) {
+        if available() {
             return Poll::Ready(());
         }
         Poll::Pending
@@ -387,9 +1424,12 @@ impl<'p> Future for AwaitCachedPage<'p> {
 
 #[cfg(test)]
 mod test {
-    use super::PAGE_SIZE;
-    use crate::{FileIO, MonotonicU64};
+    use super::{EvictablePage, DEFAULT_PAGE_SIZE as PAGE_SIZE};
+    use crate::{Error, FaultyFile, FileIO, IntegrityProblem, MonotonicU64, PersistenceLayer};
+    use std::fs::OpenOptions;
+    use std::os::unix::fs::FileExt;
     use std::path::Path;
+    use std::sync::atomic::Ordering::Relaxed;
     use tokio::fs::remove_dir_all;
 
     #[tokio::test]
@@ -464,4 +1504,379 @@ mod test {
 
         assert!(remove_dir_all(path).await.is_ok());
     }
+
+    #[tokio::test]
+    async fn extent_defragment() {
+        const DIR: &str = "page_manager_extent_defragment_test";
+        let path = Path::new(DIR);
+
+        let data: u8 = 23;
+
+        let file_io = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        let extent = file_io
+            .page_manager()
+            .create_extent(PAGE_SIZE, 4)
+            .await
+            .unwrap();
+        for i in 0..4 {
+            let page_address = extent + i * PAGE_SIZE;
+            assert!(file_io
+                .page_manager()
+                .write_page(page_address, |e| e.buffer_mut()[0] = data)
+                .await
+                .is_ok());
+            file_io.page_manager().write_back_sync(page_address);
+        }
+        assert_eq!(file_io.defragment().await.unwrap(), extent + 4 * PAGE_SIZE);
+        drop(file_io);
+
+        let file_io_recovered = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        for i in 0..4 {
+            let result = file_io_recovered
+                .page_manager()
+                .read_page(extent + i * PAGE_SIZE, |e| e.buffer()[0])
+                .await
+                .unwrap();
+            assert_eq!(result, data);
+        }
+        assert!(file_io_recovered
+            .page_manager()
+            .delete_extent(extent, 4)
+            .await
+            .is_ok());
+        assert_eq!(file_io_recovered.defragment().await.unwrap(), extent);
+        drop(file_io_recovered);
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn torn_page() {
+        const DIR: &str = "page_manager_torn_page_test";
+        let path = Path::new(DIR);
+
+        let file_io = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        let page = file_io.page_manager().create_page(PAGE_SIZE).await.unwrap();
+        assert!(file_io
+            .page_manager()
+            .write_page(page, |e| e.buffer_mut()[0] = 29)
+            .await
+            .is_ok());
+        file_io.page_manager().write_back_sync(page);
+        drop(file_io);
+
+        let tear_page = |page: u64| {
+            let db = OpenOptions::new()
+                .write(true)
+                .open(path.join("db.dat"))
+                .unwrap();
+            db.write_all_at(&[31], page + 64).unwrap();
+        };
+
+        // The intact copy of the page in the double-write buffer is restored.
+        tear_page(page);
+        let file_io_recovered = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        assert_eq!(
+            file_io_recovered
+                .page_manager()
+                .read_page(page, |e| e.buffer()[0])
+                .await,
+            Err(Error::CorruptPage(page))
+        );
+
+        // The page is quarantined without affecting the other pages.
+        assert_eq!(file_io_recovered.quarantined_pages(), vec![page]);
+        let pages_read = file_io_recovered.telemetry().statistics().pages_read;
+        assert_eq!(
+            file_io_recovered
+                .page_manager()
+                .write_page(page, EvictablePage::set_dirty)
+                .await,
+            Err(Error::CorruptPage(page))
+        );
+        assert_eq!(
+            file_io_recovered.telemetry().statistics().pages_read,
+            pages_read
+        );
+        assert!(file_io_recovered
+            .page_manager()
+            .read_page(PAGE_SIZE, EvictablePage::next_page_address)
+            .await
+            .is_ok());
+
+        assert_eq!(
+            file_io_recovered
+                .page_manager()
+                .repair_torn_pages_sync(|| Some(Vec::new())),
+            1
+        );
+        assert!(file_io_recovered.quarantined_pages().is_empty());
+        let result = file_io_recovered
+            .page_manager()
+            .read_page(page, |e| (e.prev_page_address(), e.buffer()[0]))
+            .await
+            .unwrap();
+        assert_eq!(result, (PAGE_SIZE, 29));
+
+        // The page is reset once the double-write buffer is overwritten by another page.
+        let other_page = file_io_recovered
+            .page_manager()
+            .create_page(PAGE_SIZE)
+            .await
+            .unwrap();
+        assert_ne!(other_page, page);
+        file_io_recovered.page_manager().write_back_sync(other_page);
+        drop(file_io_recovered);
+
+        tear_page(page);
+
+        // The page is left quarantined if the checkpoint refers to it.
+        let file_io_recovered = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        assert_eq!(
+            file_io_recovered
+                .page_manager()
+                .repair_torn_pages_sync(|| Some(vec![page])),
+            1
+        );
+        assert_eq!(
+            file_io_recovered
+                .page_manager()
+                .read_page(page, EvictablePage::prev_page_address)
+                .await,
+            Err(Error::CorruptPage(page))
+        );
+        assert_eq!(file_io_recovered.quarantined_pages(), vec![page]);
+        drop(file_io_recovered);
+
+        let file_io_recovered = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        assert_eq!(
+            file_io_recovered
+                .page_manager()
+                .repair_torn_pages_sync(|| Some(Vec::new())),
+            1
+        );
+        let result = file_io_recovered
+            .page_manager()
+            .read_page(page, |e| (e.prev_page_address(), e.buffer()[0]))
+            .await
+            .unwrap();
+        assert_eq!(result, (0, 0));
+        drop(file_io_recovered);
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn clean_shutdown() {
+        const DIR: &str = "page_manager_clean_shutdown_test";
+        let path = Path::new(DIR);
+
+        let file_io = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        let page = file_io.page_manager().create_page(PAGE_SIZE).await.unwrap();
+        assert!(file_io
+            .page_manager()
+            .write_page(page, |e| {
+                e.buffer_mut()[0] = 43;
+                e.set_dirty();
+            })
+            .await
+            .is_ok());
+        file_io.page_manager().mark_clean_shutdown_sync(7);
+        drop(file_io);
+
+        let file_io_reopened = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        let result = file_io_reopened
+            .page_manager()
+            .read_page(page, |e| e.buffer()[0])
+            .await
+            .unwrap();
+        assert_eq!(result, 43);
+        assert_eq!(file_io_reopened.page_manager().clock(), 7);
+        assert!(file_io_reopened.page_manager().clear_clean_shutdown_sync());
+        assert!(!file_io_reopened.page_manager().clear_clean_shutdown_sync());
+        file_io_reopened.page_manager().mark_clean_shutdown_sync(5);
+        file_io_reopened.page_manager().write_back_sync(page);
+        drop(file_io_reopened);
+
+        let file_io_reopened = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        assert!(!file_io_reopened.page_manager().clear_clean_shutdown_sync());
+        assert_eq!(file_io_reopened.page_manager().clock(), 7);
+        drop(file_io_reopened);
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn flush_dirty_pages() {
+        const DIR: &str = "page_manager_flush_dirty_pages_test";
+        let path = Path::new(DIR);
+
+        let file_io = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        file_io.set_dirty_page_threshold(None);
+        let faulty_file = FaultyFile::default();
+        assert!(file_io.set_database_faults(faulty_file.clone()));
+        let extent = file_io
+            .page_manager()
+            .create_extent(PAGE_SIZE, 8)
+            .await
+            .unwrap();
+        let pages: Vec<u64> = (0..8)
+            .filter(|i| *i != 4)
+            .map(|i| extent + i * PAGE_SIZE)
+            .collect();
+        // Write back requests for the new extent are processed before defragmentation.
+        assert!(file_io.defragment().await.is_ok());
+        for (page_address, data) in pages.iter().zip(47_u8..) {
+            assert!(file_io
+                .page_manager()
+                .write_page(*page_address, |e| {
+                    e.buffer_mut()[0] = data;
+                    e.set_dirty();
+                })
+                .await
+                .is_ok());
+        }
+
+        // The dirty pages are too few to be flushed at the threshold.
+        assert_eq!(file_io.page_manager().flush_dirty_pages_sync(100), 0);
+
+        // Adjacent pages are coalesced into a single write.
+        let num_writes = faulty_file.num_writes();
+        assert_eq!(file_io.page_manager().flush_dirty_pages_sync(0), 7);
+        assert_eq!(faulty_file.num_writes(), num_writes + 2);
+        for page_address in &pages {
+            assert_eq!(
+                file_io
+                    .page_manager()
+                    .read_page(*page_address, EvictablePage::is_dirty)
+                    .await,
+                Ok(false)
+            );
+        }
+        assert_eq!(file_io.page_manager().flush_dirty_pages_sync(0), 0);
+        drop(file_io);
+
+        let file_io_reopened = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        for (page_address, data) in pages.iter().zip(47_u8..) {
+            let result = file_io_reopened
+                .page_manager()
+                .read_page(*page_address, |e| e.buffer()[0])
+                .await
+                .unwrap();
+            assert_eq!(result, data);
+        }
+        drop(file_io_reopened);
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn verify() {
+        const DIR: &str = "page_manager_verify_test";
+        let path = Path::new(DIR);
+
+        let file_io = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        let owner = file_io.page_manager().container_directory_head();
+        let first = file_io
+            .page_manager()
+            .create_linked_page(owner)
+            .await
+            .unwrap();
+        let second = file_io
+            .page_manager()
+            .create_linked_page(first)
+            .await
+            .unwrap();
+        let report = file_io.verify().await;
+        assert!(report.is_ok(), "{:?}", report.problems);
+        assert_eq!(
+            report.pages_checked,
+            file_io.page_manager().db.len(Relaxed) / PAGE_SIZE
+        );
+
+        // The next page does not link back, and the page header links beyond the file.
+        let invalid_link = 1 << 40;
+        assert!(file_io
+            .page_manager()
+            .write_page(second, |e| {
+                e.set_prev_page_address(owner);
+                e.set_next_page_address(invalid_link);
+                e.set_dirty();
+            })
+            .await
+            .is_ok());
+        file_io.page_manager().write_back_sync(second);
+        let report = file_io.verify().await;
+        assert_eq!(
+            report.problems,
+            vec![
+                IntegrityProblem::BrokenLink {
+                    page: first,
+                    next: second,
+                    prev_of_next: owner,
+                },
+                IntegrityProblem::InvalidLink {
+                    page: second,
+                    link: invalid_link,
+                },
+            ]
+        );
+        file_io.page_manager().page_cache.remove(&second);
+        drop(file_io);
+
+        let db = OpenOptions::new()
+            .write(true)
+            .open(path.join("db.dat"))
+            .unwrap();
+        db.write_all_at(&[31], second + 64).unwrap();
+        drop(db);
+        let file_io_reopened = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        let report = file_io_reopened.verify().await;
+        assert!(report
+            .problems
+            .contains(&IntegrityProblem::CorruptPage(second)));
+        drop(file_io_reopened);
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn page_size() {
+        const DIR: &str = "page_manager_page_size_test";
+        let path = Path::new(DIR);
+
+        assert_eq!(
+            FileIO::<MonotonicU64>::with_page_size(path, 1000).err(),
+            Some(Error::WrongParameter)
+        );
+        let file_io = FileIO::<MonotonicU64>::with_page_size(path, 4096).unwrap();
+        let page = file_io.page_manager().create_page(4096).await.unwrap();
+        assert_eq!(page, 4096 * 3);
+        assert!(file_io
+            .page_manager()
+            .write_page(page, |e| {
+                assert_eq!(e.buffer().len(), 4096 - 20);
+                e.buffer_mut()[4000] = 37;
+            })
+            .await
+            .is_ok());
+        file_io.page_manager().write_back_sync(page);
+        drop(file_io);
+
+        assert_eq!(
+            FileIO::<MonotonicU64>::with_page_size(path, 8192).err(),
+            Some(Error::WrongParameter)
+        );
+        let file_io_recovered = FileIO::<MonotonicU64>::with_path(path).unwrap();
+        assert_eq!(file_io_recovered.page_size(), 4096);
+        let result = file_io_recovered
+            .page_manager()
+            .read_page(page, |e| e.buffer()[4000])
+            .await
+            .unwrap();
+        assert_eq!(result, 37);
+        drop(file_io_recovered);
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
 }
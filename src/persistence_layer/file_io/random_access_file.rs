@@ -4,45 +4,322 @@
 
 //! Abstraction over an operating system file for random access operations.
 
+use super::aligned_buffer::AlignedBuffer;
+use super::cipher::{Cipher, Encryption, TAG_LEN};
+use super::faulty_file::FaultyFile;
+#[cfg(target_os = "linux")]
+use super::io_uring::{IOUring, Operation};
+use super::memory_map::MemoryMap;
 use crate::Error;
 use libc::O_SYNC;
-use std::fs::{File, OpenOptions};
+use std::ffi::OsString;
+use std::fs::{remove_file, File, OpenOptions, TryLockError};
+use std::io::{self, ErrorKind};
+use std::os::fd::AsRawFd;
 use std::os::raw::c_int;
 use std::os::unix::fs::FileExt;
 use std::os::unix::fs::OpenOptionsExt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::AtomicU64;
 use std::sync::atomic::Ordering::{self, Relaxed, Release};
+use std::sync::{Mutex, OnceLock, RwLock};
+
+/// [`IOBackend`] determines how file IO operations are performed.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum IOBackend {
+    /// Each read and write operation is a synchronous system call, and a write operation returns
+    /// after the data is synchronized with the device.
+    #[default]
+    Synchronous,
+
+    /// Read and write operations are submitted to an `io_uring` instance in batches, and the
+    /// file is synchronized with the device once per batch of write operations.
+    ///
+    /// It is only available on Linux.
+    IOUring,
+
+    /// The file is mapped into memory, and read and write operations directly access the
+    /// operating system page cache; written ranges are synchronized with the device using `msync`
+    /// once per batch of write operations.
+    ///
+    /// It suits read-mostly workloads where the operating system page cache should be used instead
+    /// of system calls for each read operation.
+    MemoryMapped,
+
+    /// The database file is opened with `O_DIRECT` on Linux or `F_NOCACHE` on macOS to bypass the
+    /// operating system cache, and a write operation returns after the data is synchronized with
+    /// the device; the log file is not affected.
+    ///
+    /// Database pages are read into aligned buffers, and IO operations that are not aligned to
+    /// `512B` are performed by reading and writing the enclosing blocks. It is only available on
+    /// Linux and macOS.
+    Direct,
+}
+
+/// [`Durability`] determines when written data is synchronized with the device.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Durability {
+    /// A write operation returns after the data is synchronized with the device.
+    ///
+    /// Files are opened with `O_SYNC`, and `F_FULLFSYNC` is additionally issued on macOS in order
+    /// to flush the drive cache.
+    #[default]
+    Full,
+
+    /// A write operation returns after the data is handed over to the operating system, and the
+    /// files are only synchronized with the device when the database is shut down.
+    ///
+    /// Committed transactions survive a crash of the process, but not a failure of the system.
+    OsBuffered,
+
+    /// A write operation returns after the data is handed over to the operating system, and the
+    /// files are never explicitly synchronized with the device.
+    None,
+}
 
 /// [`RandomAccessFile`] allows the user to freely read and write any random location of the
 /// [`File`].
 #[derive(Debug)]
 pub struct RandomAccessFile {
     /// The underlying file handle.
+    ///
+    /// It is the first segment of the file if the file is split into segments.
     file: File,
 
+    /// The path of the file.
+    path: PathBuf,
+
     /// The current length of the file.
     len: AtomicU64,
+
+    /// Encryption of the file content.
+    encryption: Option<Encryption>,
+
+    /// The `io_uring` instance if the file uses [`IOBackend::IOUring`].
+    #[cfg(target_os = "linux")]
+    io_uring: Option<Mutex<IOUring>>,
+
+    /// The memory map of the file if the file uses [`IOBackend::MemoryMapped`].
+    ///
+    /// The exclusive lock is acquired when the data is written or the file is resized.
+    memory_map: Option<RwLock<MemoryMap>>,
+
+    /// The lock to serialize writing unaligned blocks if the file uses [`IOBackend::Direct`].
+    direct_io_lock: Option<Mutex<()>>,
+
+    /// The segment files following the first one if the file is split into segments.
+    segments: Option<Segments>,
+
+    /// The durability of write operations.
+    durability: Durability,
+
+    /// The file was opened without write permission.
+    read_only: bool,
+
+    /// The error that a write operation failed with.
+    ///
+    /// Whether the data reached the device is unknown once a write operation failed, therefore
+    /// the file refuses subsequent write operations until it is reopened. It is set to
+    /// [`Error::ReadOnly`] if the file was opened without write permission.
+    write_error: OnceLock<Error>,
+
+    /// The [`FaultyFile`] injecting faults into write operations.
+    faulty_file: OnceLock<FaultyFile>,
+}
+
+/// [`Segments`] is a list of segment files following the first segment of a [`RandomAccessFile`].
+///
+/// Segment `n` covers `[n * segment_size, (n + 1) * segment_size)` of the [`RandomAccessFile`],
+/// and every segment except for the last one is exactly `segment_size` long.
+#[derive(Debug)]
+struct Segments {
+    /// The length of a full segment.
+    segment_size: u64,
+
+    /// The segment files from the second segment.
+    files: RwLock<Vec<File>>,
 }
 
+/// The alignment of offsets and lengths of direct IO operations.
+const DIRECT_IO_ALIGNMENT: u64 = 512;
+
+/// The length of the header of a frame of an encrypted file.
+const FRAME_HEADER_LEN: usize = 16;
+
+/// The number of bytes that a frame of an encrypted file adds to the encrypted data.
+pub const FRAME_OVERHEAD: u64 = (FRAME_HEADER_LEN + TAG_LEN) as u64;
+
 impl RandomAccessFile {
     /// Creates a new [`RandomAccessFile`].
     #[inline]
     pub fn from_file(path: &Path) -> Result<RandomAccessFile, Error> {
-        let file = OpenOptions::new()
-            .create(true)
-            .read(true)
-            .write(true)
-            .custom_flags(custom_flag())
-            .open(path)
-            .map_err(|e| Error::IO(e.kind()))?;
-        let metadata = file.metadata().map_err(|e| Error::IO(e.kind()))?;
+        Self::with_io_backend(path, IOBackend::Synchronous)
+    }
+
+    /// Creates a new [`RandomAccessFile`] using the specified [`IOBackend`].
+    ///
+    /// Returns [`Error::WrongParameter`] if the [`IOBackend`] is not supported on the platform.
+    #[inline]
+    pub fn with_io_backend(path: &Path, io_backend: IOBackend) -> Result<RandomAccessFile, Error> {
+        Self::with_durability(path, io_backend, Durability::Full)
+    }
+
+    /// Creates a new [`RandomAccessFile`] using the specified [`IOBackend`] and [`Durability`].
+    ///
+    /// Returns [`Error::WrongParameter`] if the [`IOBackend`] is not supported on the platform.
+    #[inline]
+    pub fn with_durability(
+        path: &Path,
+        io_backend: IOBackend,
+        durability: Durability,
+    ) -> Result<RandomAccessFile, Error> {
+        Self::open(path, io_backend, durability, false)
+    }
+
+    /// Opens an existing file without write permission using the specified [`IOBackend`].
+    ///
+    /// Returns [`Error::WrongParameter`] if the [`IOBackend`] is not supported on the platform or
+    /// it is [`IOBackend::MemoryMapped`] that requires write permission.
+    #[inline]
+    pub fn read_only(path: &Path, io_backend: IOBackend) -> Result<RandomAccessFile, Error> {
+        if io_backend == IOBackend::MemoryMapped {
+            return Err(Error::WrongParameter);
+        }
+        Self::open(path, io_backend, Durability::None, true)
+    }
+
+    /// Opens the file.
+    fn open(
+        path: &Path,
+        io_backend: IOBackend,
+        durability: Durability,
+        read_only: bool,
+    ) -> Result<RandomAccessFile, Error> {
+        #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+        if io_backend == IOBackend::Direct {
+            return Err(Error::WrongParameter);
+        }
+        let file = open_file(path, io_backend, durability, read_only)?;
+        let metadata = file.metadata()?;
+        #[cfg(target_os = "linux")]
+        let io_uring = if io_backend == IOBackend::IOUring {
+            Some(Mutex::new(IOUring::new()?))
+        } else {
+            None
+        };
+        #[cfg(not(target_os = "linux"))]
+        if io_backend == IOBackend::IOUring {
+            return Err(Error::WrongParameter);
+        }
+        let memory_map = if io_backend == IOBackend::MemoryMapped {
+            Some(RwLock::new(MemoryMap::new(&file, metadata.len())?))
+        } else {
+            None
+        };
         Ok(RandomAccessFile {
             file,
+            path: path.to_path_buf(),
             len: AtomicU64::new(metadata.len()),
+            encryption: None,
+            #[cfg(target_os = "linux")]
+            io_uring,
+            memory_map,
+            direct_io_lock: (io_backend == IOBackend::Direct).then(Mutex::default),
+            segments: None,
+            durability,
+            read_only,
+            write_error: if read_only {
+                OnceLock::from(Error::ReadOnly)
+            } else {
+                OnceLock::new()
+            },
+            faulty_file: OnceLock::new(),
         })
     }
 
+    /// Splits the file into segments of the specified size.
+    ///
+    /// Segments following the first one are stored in `{path}.1`, `{path}.2`, and so on, and
+    /// existing segments are opened.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::WrongParameter`] if the segment size is zero or the [`IOBackend`] of the
+    /// file does not support segments, [`Error::CorruptDatabase`] if the lengths of existing
+    /// segments are inconsistent, and an error if a segment could not be opened.
+    #[inline]
+    pub fn enable_segments(&mut self, segment_size: u64) -> Result<(), Error> {
+        #[cfg(target_os = "linux")]
+        let io_uring = self.io_uring.is_some();
+        #[cfg(not(target_os = "linux"))]
+        let io_uring = false;
+        if segment_size == 0 || io_uring || self.memory_map.is_some() {
+            return Err(Error::WrongParameter);
+        }
+        let mut files: Vec<File> = Vec::new();
+        let mut len = self.file.metadata()?.len();
+        loop {
+            let num_segments = files.len() as u64 + 1;
+            let segment_path = segment_path(&self.path, files.len() + 1);
+            if !segment_path.exists() {
+                if len > segment_size * num_segments {
+                    return Err(Error::CorruptDatabase);
+                }
+                break;
+            }
+            if len != segment_size * num_segments {
+                return Err(Error::CorruptDatabase);
+            }
+            let file = open_file(
+                &segment_path,
+                self.segment_io_backend(),
+                self.durability,
+                self.read_only,
+            )?;
+            len += file.metadata()?.len();
+            files.push(file);
+        }
+        self.len.store(len, Release);
+        self.segments.replace(Segments {
+            segment_size,
+            files: RwLock::new(files),
+        });
+        Ok(())
+    }
+
+    /// Returns the paths of the segments of the file.
+    ///
+    /// It only returns the path of the file if the file is not split into segments.
+    #[inline]
+    pub fn segment_paths(&self) -> Vec<PathBuf> {
+        let num_segments = self
+            .segments
+            .as_ref()
+            .and_then(|s| s.files.read().ok().map(|f| f.len()))
+            .unwrap_or(0);
+        (0..=num_segments)
+            .map(|i| segment_path(&self.path, i))
+            .collect()
+    }
+
+    /// Sets the [`Encryption`] of the file.
+    #[inline]
+    pub fn set_encryption(&mut self, encryption: Encryption) {
+        self.encryption.replace(encryption);
+    }
+
+    /// Returns a reference to the [`Encryption`] of the file.
+    #[inline]
+    pub fn encryption(&self) -> Option<&Encryption> {
+        self.encryption.as_ref()
+    }
+
+    /// Returns a reference to the [`Cipher`] of the file.
+    #[inline]
+    pub fn cipher(&self) -> Option<&dyn Cipher> {
+        self.encryption.as_ref().map(Encryption::cipher)
+    }
+
     /// Returns the current length of the file.
     #[inline]
     pub fn len(&self, order: Ordering) -> u64 {
@@ -52,27 +329,238 @@ impl RandomAccessFile {
     /// Truncates or extends the underlying file.
     #[inline]
     pub fn set_len(&self, len: u64) -> Result<(), Error> {
-        self.file.set_len(len).map_err(|e| Error::IO(e.kind()))?;
+        if self.read_only {
+            return Err(Error::ReadOnly);
+        }
+        if let Some(memory_map) = self.memory_map.as_ref() {
+            let mut memory_map = memory_map.write().map_err(|_| Error::UnexpectedState)?;
+            // The file must be unmapped before being truncated.
+            *memory_map = MemoryMap::new(&self.file, 0)?;
+            self.file.set_len(len)?;
+            *memory_map = MemoryMap::new(&self.file, len)?;
+        } else {
+            self.set_file_len(len)?;
+        }
         self.len.store(len, Release);
         Ok(())
     }
 
+    /// Deallocates the device space of `[start, end)` of the file, and the range reads as zeros
+    /// afterwards.
+    ///
+    /// The length of the file does not change, and only whole blocks of the file system are
+    /// deallocated. It has no effect if the platform or file system does not support punching
+    /// holes in files.
+    #[inline]
+    pub fn discard(&self, start: u64, end: u64) -> Result<(), Error> {
+        if let Some(error) = self.write_error.get() {
+            return Err(error.clone());
+        }
+        if self.faulty_file.get().is_some_and(FaultyFile::is_crashed) {
+            return Ok(());
+        }
+        let mut offset = start;
+        while offset < end {
+            let len = usize::try_from(end - offset).unwrap_or(usize::MAX);
+            let result = if let Some(segments) = self.segments.as_ref() {
+                let (index, segment_offset, len) = segments.locate(offset, len);
+                segments
+                    .access(&self.file, index, |f| punch_hole(f, segment_offset, len))
+                    .map(|r| r.map(|()| len))
+            } else {
+                Some(punch_hole(&self.file, offset, len).map(|()| len))
+            };
+            let Some(len) = result.transpose()? else {
+                // The segment does not exist.
+                break;
+            };
+            offset += len as u64;
+        }
+        Ok(())
+    }
+
+    /// Acquires an advisory lock on the file without blocking.
+    ///
+    /// The lock is exclusive if `exclusive` is `true`, and shared otherwise; it is released when
+    /// the file is closed. Returns [`Error::AlreadyInUse`] if another open file handle holds a
+    /// conflicting lock.
+    #[inline]
+    pub fn try_lock(&self, exclusive: bool) -> Result<(), Error> {
+        let result = if exclusive {
+            self.file.try_lock()
+        } else {
+            self.file.try_lock_shared()
+        };
+        result.map_err(|e| match e {
+            TryLockError::WouldBlock => Error::AlreadyInUse,
+            TryLockError::Error(e) => Error::from(e),
+        })
+    }
+
+    /// Returns `true` if the file was opened without write permission.
+    #[inline]
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Returns the error that a write operation failed with if the file refuses write operations.
+    #[inline]
+    pub fn write_error(&self) -> Option<&Error> {
+        self.write_error.get()
+    }
+
+    /// Sets the [`FaultyFile`] that injects faults into write operations.
+    ///
+    /// Returns `false` if a [`FaultyFile`] was already set.
+    #[inline]
+    pub fn set_faulty_file(&self, faulty_file: FaultyFile) -> bool {
+        self.faulty_file.set(faulty_file).is_ok()
+    }
+
+    /// Synchronizes the file with the device if write operations do not.
+    ///
+    /// It only takes effect if the file uses [`Durability::OsBuffered`], and the file refuses write
+    /// operations if the synchronization failed.
+    #[inline]
+    pub fn sync(&self) -> Result<(), Error> {
+        if let Some(error) = self.write_error.get() {
+            return Err(error.clone());
+        }
+        if self.durability != Durability::OsBuffered
+            || self.faulty_file.get().is_some_and(FaultyFile::is_crashed)
+        {
+            return Ok(());
+        }
+        let result = self.file.sync_all().map_err(Error::from).and_then(|()| {
+            let Some(segments) = self.segments.as_ref() else {
+                return Ok(());
+            };
+            let files = segments.files.read().map_err(|_| Error::UnexpectedState)?;
+            files
+                .iter()
+                .try_for_each(File::sync_all)
+                .map_err(Error::from)
+        });
+        if let Err(error) = result.as_ref() {
+            let _: Result<(), Error> = self.write_error.set(error.clone());
+        }
+        result
+    }
+
+    /// Advises the operating system that the file will be read sequentially.
+    ///
+    /// It is only effective if the file uses [`IOBackend::MemoryMapped`].
+    #[inline]
+    pub fn advise_sequential(&self) {
+        if let Some(Ok(memory_map)) = self.memory_map.as_ref().map(RwLock::read) {
+            memory_map.advise_sequential();
+        }
+    }
+
     /// Abstraction over random read operations.
+    ///
+    /// IO errors carry the offset of the read operation.
     #[inline]
     pub fn read(&self, buffer: &mut [u8], offset: u64) -> Result<(), Error> {
-        self.file
-            .read_exact_at(buffer, offset)
-            .map_err(|e| Error::IO(e.kind()))
+        self.dispatch_read(buffer, offset)
+            .map_err(|error| error.at(offset))
+    }
+
+    /// Reads data using the IO backend of the file.
+    fn dispatch_read(&self, buffer: &mut [u8], offset: u64) -> Result<(), Error> {
+        if let Some(memory_map) = self.memory_map.as_ref() {
+            let memory_map = memory_map.read().map_err(|_| Error::UnexpectedState)?;
+            return memory_map.read(buffer, offset);
+        }
+        if self.direct_io_lock.is_some() && !is_aligned(buffer, offset) {
+            return self.read_unaligned(buffer, offset);
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(io_uring) = self.io_uring.as_ref() {
+            let mut io_uring = io_uring.lock().map_err(|_| Error::UnexpectedState)?;
+            return io_uring.submit_and_wait(
+                self.file.as_raw_fd(),
+                &mut [Operation::Read(buffer, offset)],
+                false,
+            );
+        }
+        self.read_exact_at(buffer, offset)
     }
 
     /// Abstraction over random write operations.
     #[inline]
     pub fn write(&self, buffer: &[u8], offset: u64) -> Result<(), Error> {
-        self.file
-            .write_all_at(buffer, offset)
-            .map_err(|e| Error::IO(e.kind()))?;
+        self.write_batch(&[(buffer, offset)])
+    }
+
+    /// Writes a batch of buffers at the respective offsets.
+    ///
+    /// The data is synchronized with the device when the method returns. The file refuses write
+    /// operations after a write operation failed unless the storage device ran out of space, since
+    /// retrying a failed write or synchronization may falsely report success while the data in the
+    /// operating system cache was discarded. IO errors carry the offset of the first buffer.
+    #[inline]
+    pub fn write_batch(&self, writes: &[(&[u8], u64)]) -> Result<(), Error> {
+        if let Some(error) = self.write_error.get() {
+            return Err(error.clone());
+        }
+        let result = if let Some(faulty_file) = self.faulty_file.get() {
+            faulty_file.write_batch(writes, |writes| self.write_batch_unchecked(writes))
+        } else {
+            self.write_batch_unchecked(writes)
+        }
+        .map_err(|error| error.at(writes.first().map_or(0, |(_, offset)| *offset)));
+        if let Err(error) = result.as_ref() {
+            if *error != Error::DiskFull {
+                let _: Result<(), Error> = self.write_error.set(error.clone());
+            }
+        }
+        result
+    }
+
+    /// Writes a batch of buffers at the respective offsets without checking the write error.
+    fn write_batch_unchecked(&self, writes: &[(&[u8], u64)]) -> Result<(), Error> {
+        let new_len = writes
+            .iter()
+            .map(|(buffer, offset)| offset + buffer.len() as u64)
+            .max()
+            .unwrap_or(0);
+        if let Some(memory_map) = self.memory_map.as_ref() {
+            self.write_batch_memory_mapped(memory_map, writes, new_len)?;
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(io_uring) = self.io_uring.as_ref() {
+            let mut operations: Vec<Operation> = writes
+                .iter()
+                .map(|(buffer, offset)| Operation::Write(buffer, *offset))
+                .collect();
+            let mut io_uring = io_uring.lock().map_err(|_| Error::UnexpectedState)?;
+            io_uring.submit_and_wait(
+                self.file.as_raw_fd(),
+                &mut operations,
+                self.durability == Durability::Full,
+            )?;
+        }
+        #[cfg(target_os = "linux")]
+        let synchronous = self.io_uring.is_none() && self.memory_map.is_none();
+        #[cfg(not(target_os = "linux"))]
+        let synchronous = self.memory_map.is_none();
+        if synchronous {
+            for (buffer, offset) in writes {
+                if let Some(direct_io_lock) = self.direct_io_lock.as_ref() {
+                    if !is_aligned(buffer, *offset) {
+                        self.write_unaligned(direct_io_lock, buffer, *offset)?;
+                        continue;
+                    }
+                }
+                self.write_all_at(buffer, *offset)?;
+            }
+            #[cfg(target_os = "macos")]
+            if self.durability == Durability::Full {
+                self.full_fsync(writes)?;
+            }
+        }
         let mut current_len = self.len.load(Relaxed);
-        let new_len = offset + buffer.len() as u64;
         while current_len < new_len {
             match self
                 .len
@@ -84,13 +572,452 @@ impl RandomAccessFile {
         }
         Ok(())
     }
+
+    /// Writes a batch of buffers to the memory map, and synchronizes the written ranges.
+    fn write_batch_memory_mapped(
+        &self,
+        memory_map: &RwLock<MemoryMap>,
+        writes: &[(&[u8], u64)],
+        new_len: u64,
+    ) -> Result<(), Error> {
+        let mut memory_map = memory_map.write().map_err(|_| Error::UnexpectedState)?;
+        if new_len > memory_map.len() {
+            // The file is extended before being remapped since accessing the part of the map
+            // beyond the end of the file results in a bus error.
+            self.file.set_len(new_len)?;
+            *memory_map = MemoryMap::new(&self.file, new_len)?;
+        }
+        for (buffer, offset) in writes {
+            memory_map.write(buffer, *offset)?;
+        }
+        if self.durability == Durability::Full {
+            for (buffer, offset) in writes {
+                memory_map.sync(buffer.len(), *offset)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Flushes the drive cache for the files that were written since `O_SYNC` does not on macOS.
+    #[cfg(target_os = "macos")]
+    fn full_fsync(&self, writes: &[(&[u8], u64)]) -> Result<(), Error> {
+        let full_fsync = |file: &File| {
+            // Safety: the file descriptor is valid.
+            if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_FULLFSYNC) } == -1 {
+                return Err(Error::IO(io::Error::last_os_error().kind()));
+            }
+            Ok(())
+        };
+        let Some(segments) = self.segments.as_ref() else {
+            return full_fsync(&self.file);
+        };
+        let mut indexes: Vec<usize> = writes
+            .iter()
+            .flat_map(|(buffer, offset)| {
+                let last = offset + buffer.len().saturating_sub(1) as u64;
+                segments.locate(*offset, 1).0..=segments.locate(last, 1).0
+            })
+            .collect();
+        indexes.sort_unstable();
+        indexes.dedup();
+        for index in indexes {
+            segments
+                .access(&self.file, index, full_fsync)
+                .unwrap_or(Ok(()))?;
+        }
+        Ok(())
+    }
+
+    /// Reads data that is not aligned to [`DIRECT_IO_ALIGNMENT`] through an aligned buffer.
+    fn read_unaligned(&self, buffer: &mut [u8], offset: u64) -> Result<(), Error> {
+        let (start, block_buffer, bytes_read) = self.read_blocks(buffer.len(), offset)?;
+        let pos = usize::try_from(offset - start).map_err(|_| Error::UnexpectedState)?;
+        if bytes_read < pos + buffer.len() {
+            return Err(Error::IO(ErrorKind::UnexpectedEof));
+        }
+        buffer.copy_from_slice(&block_buffer[pos..pos + buffer.len()]);
+        Ok(())
+    }
+
+    /// Writes data that is not aligned to [`DIRECT_IO_ALIGNMENT`] by reading, modifying, and
+    /// writing the enclosing blocks.
+    fn write_unaligned(
+        &self,
+        direct_io_lock: &Mutex<()>,
+        buffer: &[u8],
+        offset: u64,
+    ) -> Result<(), Error> {
+        let _guard = direct_io_lock.lock().map_err(|_| Error::UnexpectedState)?;
+        let (start, mut block_buffer, bytes_read) = self.read_blocks(buffer.len(), offset)?;
+        let pos = usize::try_from(offset - start).map_err(|_| Error::UnexpectedState)?;
+        block_buffer[pos..pos + buffer.len()].copy_from_slice(buffer);
+        self.write_all_at(&block_buffer, start)?;
+
+        // The padding beyond the end of the data is removed if the file was extended.
+        let file_len = (pos + buffer.len()).max(bytes_read);
+        if file_len < block_buffer.len() {
+            self.set_file_len(start + file_len as u64)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the blocks enclosing the range into an aligned buffer, and returns the start offset
+    /// of the blocks, the buffer, and the number of bytes read.
+    ///
+    /// The number of bytes read is smaller than the length of the buffer only if the blocks reach
+    /// the end of the file.
+    fn read_blocks(&self, len: usize, offset: u64) -> Result<(u64, AlignedBuffer, usize), Error> {
+        let start = offset - offset % DIRECT_IO_ALIGNMENT;
+        let end = (offset + len as u64).next_multiple_of(DIRECT_IO_ALIGNMENT);
+        let mut block_buffer =
+            AlignedBuffer::new(usize::try_from(end - start).map_err(|_| Error::OutOfMemory)?)?;
+        loop {
+            match self.read_at(&mut block_buffer, start) {
+                Ok(bytes_read) => return Ok((start, block_buffer, bytes_read)),
+                Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(e) => return Err(Error::IO(e.kind())),
+            }
+        }
+    }
+
+    /// Returns the [`IOBackend`] of segments.
+    fn segment_io_backend(&self) -> IOBackend {
+        if self.direct_io_lock.is_some() {
+            IOBackend::Direct
+        } else {
+            IOBackend::Synchronous
+        }
+    }
+
+    /// Reads data at the offset across segments, and returns the number of bytes read.
+    fn read_at(&self, buffer: &mut [u8], offset: u64) -> io::Result<usize> {
+        let Some(segments) = self.segments.as_ref() else {
+            return self.file.read_at(buffer, offset);
+        };
+        let mut pos = 0;
+        while pos < buffer.len() {
+            let (index, segment_offset, len) =
+                segments.locate(offset + pos as u64, buffer.len() - pos);
+            let Some(bytes_read) = segments.access(&self.file, index, |f| {
+                f.read_at(&mut buffer[pos..pos + len], segment_offset)
+            }) else {
+                break;
+            };
+            let bytes_read = bytes_read?;
+            pos += bytes_read;
+            if bytes_read < len {
+                break;
+            }
+        }
+        Ok(pos)
+    }
+
+    /// Reads the exact number of bytes at the offset across segments.
+    fn read_exact_at(&self, buffer: &mut [u8], offset: u64) -> Result<(), Error> {
+        if self.segments.is_none() {
+            return self
+                .file
+                .read_exact_at(buffer, offset)
+                .map_err(|e| Error::IO(e.kind()));
+        }
+        loop {
+            match self.read_at(buffer, offset) {
+                Ok(bytes_read) if bytes_read == buffer.len() => return Ok(()),
+                Ok(_) => return Err(Error::IO(ErrorKind::UnexpectedEof)),
+                Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(e) => return Err(Error::IO(e.kind())),
+            }
+        }
+    }
+
+    /// Writes the whole buffer at the offset across segments, creating segments if needed.
+    fn write_all_at(&self, buffer: &[u8], offset: u64) -> Result<(), Error> {
+        let Some(segments) = self.segments.as_ref() else {
+            return self
+                .file
+                .write_all_at(buffer, offset)
+                .map_err(|e| Error::IO(e.kind()));
+        };
+        let mut pos = 0;
+        while pos < buffer.len() {
+            let (index, segment_offset, len) =
+                segments.locate(offset + pos as u64, buffer.len() - pos);
+            let result = if let Some(result) = segments.access(&self.file, index, |f| {
+                f.write_all_at(&buffer[pos..pos + len], segment_offset)
+            }) {
+                result
+            } else {
+                let mut files = segments.files.write().map_err(|_| Error::UnexpectedState)?;
+                self.create_segments(segments, &mut files, index)?;
+                files[index - 1].write_all_at(&buffer[pos..pos + len], segment_offset)
+            };
+            result?;
+            pos += len;
+        }
+        Ok(())
+    }
+
+    /// Truncates or extends the underlying segments.
+    fn set_file_len(&self, len: u64) -> Result<(), Error> {
+        let Some(segments) = self.segments.as_ref() else {
+            return self.file.set_len(len).map_err(|e| Error::IO(e.kind()));
+        };
+        let last_index = usize::try_from(len.saturating_sub(1) / segments.segment_size)
+            .map_err(|_| Error::WrongParameter)?;
+        let mut files = segments.files.write().map_err(|_| Error::UnexpectedState)?;
+        while files.len() > last_index {
+            let segment_path = segment_path(&self.path, files.len());
+            drop(files.pop());
+            remove_file(segment_path)?;
+        }
+        self.create_segments(segments, &mut files, last_index)?;
+        let last_len = len - last_index as u64 * segments.segment_size;
+        let last_file = last_index.checked_sub(1).map_or(&self.file, |i| &files[i]);
+        last_file.set_len(last_len).map_err(|e| Error::IO(e.kind()))
+    }
+
+    /// Creates segments up to the specified index, and makes preceding segments full.
+    fn create_segments(
+        &self,
+        segments: &Segments,
+        files: &mut Vec<File>,
+        index: usize,
+    ) -> Result<(), Error> {
+        while files.len() < index {
+            let last_file = files.last().unwrap_or(&self.file);
+            let last_len = last_file.metadata()?.len();
+            if last_len < segments.segment_size {
+                last_file.set_len(segments.segment_size)?;
+            }
+            let segment_path = segment_path(&self.path, files.len() + 1);
+            files.push(open_file(
+                &segment_path,
+                self.segment_io_backend(),
+                self.durability,
+                self.read_only,
+            )?);
+        }
+        Ok(())
+    }
+
+    /// Returns the length of the frame at the offset of the encrypted file.
+    ///
+    /// `0` is returned if no frame has been written at the offset.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the frame header could not be read.
+    #[inline]
+    pub fn frame_len(&self, offset: u64) -> Result<u64, Error> {
+        let mut header = [0_u8; FRAME_HEADER_LEN];
+        self.read(&mut header, offset)?;
+        if header[0..8].iter().all(|b| *b == 0) {
+            return Ok(0);
+        }
+        let data_len = u32::from_le_bytes(header[12..16].try_into().unwrap_or_default());
+        Ok(FRAME_OVERHEAD + u64::from(data_len))
+    }
+
+    /// Reads the frame of the specified length at the offset of the encrypted file, and returns
+    /// the decrypted data.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the frame could not be read, or the frame failed to be authenticated.
+    #[inline]
+    pub fn read_decrypted_frame(&self, offset: u64, frame_len: u64) -> Result<Vec<u8>, Error> {
+        let encryption = self.encryption.as_ref().ok_or(Error::UnexpectedState)?;
+        let mut frame = vec![0_u8; usize::try_from(frame_len).map_err(|_| Error::OutOfMemory)?];
+        self.read(&mut frame, offset)?;
+        let (header, rest) = frame.split_at_mut(FRAME_HEADER_LEN);
+        let (data, tag) = rest.split_at_mut(rest.len() - TAG_LEN);
+        let nonce = u64::from_le_bytes(header[0..8].try_into().unwrap_or_default());
+        let key_id = u32::from_le_bytes(header[8..12].try_into().unwrap_or_default());
+        let aad = frame_aad(offset, header);
+        let tag: [u8; TAG_LEN] = tag.try_into().unwrap_or_default();
+        encryption
+            .cipher()
+            .decrypt(key_id, nonce, &aad, data, &tag)?;
+        frame.truncate(frame.len() - TAG_LEN);
+        frame.drain(..FRAME_HEADER_LEN);
+        Ok(frame)
+    }
+
+    /// Writes a batch of contiguous buffers to the file, and returns the number of bytes written.
+    ///
+    /// If the file is encrypted, the buffers are encrypted and written as a single frame using a
+    /// new nonce; the layout of a frame is `NONCE 64-bit|KEY_ID 32-bit|LEN 32-bit|DATA|TAG`, and
+    /// the offset of the frame is authenticated along with the frame header.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a new nonce could not be issued, or the buffers could not be written.
+    #[inline]
+    pub fn write_encrypted_batch(&self, writes: &[(&[u8], u64)]) -> Result<u64, Error> {
+        let Some(encryption) = self.encryption.as_ref() else {
+            self.write_batch(writes)?;
+            return Ok(writes.iter().map(|(buffer, _)| buffer.len() as u64).sum());
+        };
+        let Some(offset) = writes.first().map(|(_, offset)| *offset) else {
+            return Ok(0);
+        };
+        debug_assert!(writes
+            .windows(2)
+            .all(|w| w[0].1 + w[0].0.len() as u64 == w[1].1));
+        let nonce = encryption.new_nonce().inspect_err(|error| {
+            // The file cannot be written until a new nonce epoch is started.
+            let _: Result<(), Error> = self.write_error.set(error.clone());
+        })?;
+        let data_len: usize = writes.iter().map(|(buffer, _)| buffer.len()).sum();
+        let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + data_len + TAG_LEN);
+        frame.extend_from_slice(&nonce.to_le_bytes());
+        frame.extend_from_slice(&encryption.cipher().key_id().to_le_bytes());
+        frame.extend_from_slice(
+            &u32::try_from(data_len)
+                .map_err(|_| Error::WrongParameter)?
+                .to_le_bytes(),
+        );
+        for (buffer, _) in writes {
+            frame.extend_from_slice(buffer);
+        }
+        let (header, data) = frame.split_at_mut(FRAME_HEADER_LEN);
+        let tag = encryption
+            .cipher()
+            .encrypt(nonce, &frame_aad(offset, header), data);
+        frame.extend_from_slice(&tag);
+        self.write_batch(&[(&frame, offset)])?;
+        Ok(frame.len() as u64)
+    }
+}
+
+/// Returns the additional authenticated data of the frame at the offset.
+fn frame_aad(offset: u64, header: &[u8]) -> [u8; 16] {
+    let mut aad = [0_u8; 16];
+    aad[0..8].copy_from_slice(&offset.to_le_bytes());
+    aad[8..16].copy_from_slice(&header[8..16]);
+    aad
+}
+
+impl Segments {
+    /// Returns the index of the segment, the offset in the segment, and the length of the range
+    /// in the segment.
+    fn locate(&self, offset: u64, len: usize) -> (usize, u64, usize) {
+        let index = usize::try_from(offset / self.segment_size).unwrap_or(usize::MAX);
+        let segment_offset = offset % self.segment_size;
+        let len = usize::try_from(self.segment_size - segment_offset)
+            .unwrap_or(usize::MAX)
+            .min(len);
+        (index, segment_offset, len)
+    }
+
+    /// Calls the function with the segment file if the segment exists.
+    fn access<R, F: FnOnce(&File) -> R>(&self, first: &File, index: usize, f: F) -> Option<R> {
+        if index == 0 {
+            return Some(f(first));
+        }
+        let files = self.files.read().ok()?;
+        files.get(index - 1).map(f)
+    }
+}
+
+/// Returns the path of the segment.
+pub(super) fn segment_path(path: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        return path.to_path_buf();
+    }
+    let mut segment_path = OsString::from(path.as_os_str());
+    segment_path.push(format!(".{index}"));
+    PathBuf::from(segment_path)
+}
+
+/// Opens the file using the specified [`IOBackend`].
+///
+/// A file opened read-only must exist.
+fn open_file(
+    path: &Path,
+    io_backend: IOBackend,
+    durability: Durability,
+    read_only: bool,
+) -> Result<File, Error> {
+    let custom_flags = match io_backend {
+        IOBackend::Synchronous => custom_flag(durability),
+        IOBackend::Direct => direct_io_flag(durability),
+        IOBackend::IOUring | IOBackend::MemoryMapped => 0,
+    };
+    let file = OpenOptions::new()
+        .create(!read_only)
+        .read(true)
+        .write(!read_only)
+        .custom_flags(custom_flags)
+        .open(path)?;
+    #[cfg(target_os = "macos")]
+    if io_backend == IOBackend::Direct {
+        // Safety: the file descriptor is valid.
+        if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_NOCACHE, 1) } == -1 {
+            return Err(Error::IO(io::Error::last_os_error().kind()));
+        }
+    }
+    Ok(file)
+}
+
+/// Deallocates the device space of the range of the file.
+#[cfg(target_os = "linux")]
+fn punch_hole(file: &File, offset: u64, len: usize) -> Result<(), Error> {
+    let (Ok(offset), Ok(len)) = (libc::off_t::try_from(offset), libc::off_t::try_from(len)) else {
+        return Err(Error::WrongParameter);
+    };
+    // Safety: the file descriptor is valid.
+    let result = unsafe {
+        libc::fallocate(
+            file.as_raw_fd(),
+            libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE,
+            offset,
+            len,
+        )
+    };
+    if result == -1 {
+        let error = io::Error::last_os_error();
+        if error.raw_os_error() != Some(libc::EOPNOTSUPP) {
+            return Err(Error::IO(error.kind()));
+        }
+    }
+    Ok(())
 }
 
-fn custom_flag() -> c_int {
+/// Deallocates the device space of the range of the file.
+#[cfg(not(target_os = "linux"))]
+#[allow(clippy::unnecessary_wraps)]
+fn punch_hole(_file: &File, _offset: u64, _len: usize) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Checks if the buffer and offset are aligned to [`DIRECT_IO_ALIGNMENT`].
+fn is_aligned(buffer: &[u8], offset: u64) -> bool {
+    [buffer.as_ptr().addr() as u64, buffer.len() as u64, offset]
+        .iter()
+        .all(|v| v.is_multiple_of(DIRECT_IO_ALIGNMENT))
+}
+
+/// Returns the flags to open a file for [`IOBackend::Direct`].
+fn direct_io_flag(durability: Durability) -> c_int {
+    #[cfg(target_os = "linux")]
+    let direct_io_flag = libc::O_DIRECT | custom_flag(durability);
+    // `F_NOCACHE` is set after the file is opened.
+    #[cfg(not(target_os = "linux"))]
+    let direct_io_flag = custom_flag(durability);
+    direct_io_flag
+}
+
+fn custom_flag(durability: Durability) -> c_int {
     // `O_DIRECT` is unavailable.
     //
-    // TODO: replace it with io_uring.
-    O_SYNC
+    // `IOBackend::IOUring` and `IOBackend::MemoryMapped` synchronize the file with the device
+    // using `fsync` and `msync` instead.
+    if durability == Durability::Full {
+        O_SYNC
+    } else {
+        0
+    }
 }
 
 #[cfg(test)]
@@ -124,10 +1051,168 @@ mod tests {
 
         assert_eq!(
             random_access_file.read(&mut read_buffer, 40),
-            Err(Error::IO(io::ErrorKind::UnexpectedEof))
+            Err(Error::IOAt(io::ErrorKind::UnexpectedEof, 40))
+        );
+
+        drop(random_access_file);
+        assert!(remove_file(FILE).is_ok());
+    }
+
+    #[test]
+    fn write_error() {
+        const FILE: &str = "random_access_file_write_error_test";
+        let random_access_file = RandomAccessFile::from_file(Path::new(FILE)).unwrap();
+        assert!(random_access_file.write(&[1; 8], 0).is_ok());
+        assert!(random_access_file.write_error().is_none());
+
+        // The offset is out of the range of `off_t`.
+        let error = random_access_file.write(&[1; 8], u64::MAX - 8).unwrap_err();
+        assert_eq!(random_access_file.write_error(), Some(&error));
+        assert_eq!(random_access_file.write(&[1; 8], 8), Err(error));
+        assert_eq!(random_access_file.len(Relaxed), 8);
+
+        drop(random_access_file);
+        assert!(remove_file(FILE).is_ok());
+    }
+
+    #[test]
+    fn memory_mapped() {
+        const FILE: &str = "random_access_file_memory_mapped_test";
+        let random_access_file =
+            RandomAccessFile::with_io_backend(Path::new(FILE), IOBackend::MemoryMapped).unwrap();
+        let mut read_buffer: [u8; 4] = [0; 4];
+        assert_eq!(
+            random_access_file.read(&mut read_buffer, 0),
+            Err(Error::IOAt(io::ErrorKind::UnexpectedEof, 0))
+        );
+        assert!(random_access_file
+            .write_batch(&[(&[1, 2], 8192), (&[3, 4], 4095)])
+            .is_ok());
+        assert_eq!(random_access_file.len(Relaxed), 8194);
+        assert!(random_access_file.read(&mut read_buffer, 4094).is_ok());
+        assert_eq!(read_buffer, [0, 3, 4, 0]);
+
+        assert!(random_access_file.set_len(4096).is_ok());
+        assert_eq!(
+            random_access_file.read(&mut read_buffer, 8192),
+            Err(Error::IOAt(io::ErrorKind::UnexpectedEof, 8192))
+        );
+        random_access_file.advise_sequential();
+        drop(random_access_file);
+
+        let random_access_file = RandomAccessFile::from_file(Path::new(FILE)).unwrap();
+        assert_eq!(random_access_file.len(Relaxed), 4096);
+        assert!(random_access_file.read(&mut read_buffer, 4092).is_ok());
+        assert_eq!(read_buffer, [0, 0, 0, 3]);
+
+        drop(random_access_file);
+        assert!(remove_file(FILE).is_ok());
+    }
+
+    #[test]
+    fn direct() {
+        const FILE: &str = "random_access_file_direct_test";
+        let random_access_file =
+            RandomAccessFile::with_io_backend(Path::new(FILE), IOBackend::Direct).unwrap();
+        let mut aligned_buffer = AlignedBuffer::new(1024).unwrap();
+        aligned_buffer.fill(1);
+        assert!(random_access_file.write(&aligned_buffer, 512).is_ok());
+        assert!(random_access_file.write(&[2, 3, 4], 1534).is_ok());
+        assert_eq!(random_access_file.len(Relaxed), 1537);
+        assert!(random_access_file.write(&[5, 6], 10).is_ok());
+        assert_eq!(random_access_file.len(Relaxed), 1537);
+
+        let mut read_buffer: [u8; 4] = [0; 4];
+        assert!(random_access_file.read(&mut read_buffer, 1533).is_ok());
+        assert_eq!(read_buffer, [1, 2, 3, 4]);
+        assert!(random_access_file.read(&mut read_buffer, 9).is_ok());
+        assert_eq!(read_buffer, [0, 5, 6, 0]);
+        assert!(random_access_file.read(&mut aligned_buffer, 0).is_ok());
+        assert!(aligned_buffer[512..].iter().all(|b| *b == 1));
+        assert_eq!(
+            random_access_file.read(&mut read_buffer, 1534),
+            Err(Error::IOAt(io::ErrorKind::UnexpectedEof, 1534))
         );
+        drop(random_access_file);
+
+        let random_access_file = RandomAccessFile::from_file(Path::new(FILE)).unwrap();
+        assert_eq!(random_access_file.len(Relaxed), 1537);
 
         drop(random_access_file);
         assert!(remove_file(FILE).is_ok());
     }
+
+    #[test]
+    fn segments() {
+        const FILE: &str = "random_access_file_segments_test";
+        let mut random_access_file = RandomAccessFile::from_file(Path::new(FILE)).unwrap();
+        assert!(random_access_file.enable_segments(16).is_ok());
+        let write_buffer: Vec<u8> = (0..40).collect();
+        assert!(random_access_file.write(&write_buffer, 4).is_ok());
+        assert_eq!(random_access_file.len(Relaxed), 44);
+        assert_eq!(random_access_file.segment_paths().len(), 3);
+        assert_eq!(Path::new(FILE).metadata().unwrap().len(), 16);
+        assert_eq!(
+            Path::new(&format!("{FILE}.2")).metadata().unwrap().len(),
+            12
+        );
+
+        let mut read_buffer: [u8; 20] = [0; 20];
+        assert!(random_access_file.read(&mut read_buffer, 10).is_ok());
+        assert!(read_buffer
+            .iter()
+            .enumerate()
+            .all(|(i, d)| *d as usize == i + 6));
+        assert_eq!(
+            random_access_file.read(&mut read_buffer, 30),
+            Err(Error::IOAt(io::ErrorKind::UnexpectedEof, 30))
+        );
+
+        assert!(random_access_file.set_len(20).is_ok());
+        assert_eq!(random_access_file.segment_paths().len(), 2);
+        assert!(!Path::new(&format!("{FILE}.2")).exists());
+        assert!(random_access_file.write(&[1], 70).is_ok());
+        assert_eq!(random_access_file.segment_paths().len(), 5);
+        drop(random_access_file);
+
+        let mut random_access_file = RandomAccessFile::from_file(Path::new(FILE)).unwrap();
+        assert!(random_access_file.enable_segments(16).is_ok());
+        assert_eq!(random_access_file.len(Relaxed), 71);
+        let mut random_access_file_wrong = RandomAccessFile::from_file(Path::new(FILE)).unwrap();
+        assert_eq!(
+            random_access_file_wrong.enable_segments(32),
+            Err(Error::CorruptDatabase)
+        );
+
+        let segment_paths = random_access_file.segment_paths();
+        drop(random_access_file_wrong);
+        drop(random_access_file);
+        for segment_path in segment_paths {
+            assert!(remove_file(segment_path).is_ok());
+        }
+    }
+
+    #[test]
+    fn discard() {
+        const FILE: &str = "random_access_file_discard_test";
+        let mut random_access_file = RandomAccessFile::from_file(Path::new(FILE)).unwrap();
+        assert!(random_access_file.enable_segments(8192).is_ok());
+        assert!(random_access_file.write(&vec![1; 32768], 0).is_ok());
+        assert!(random_access_file.discard(100, 20000).is_ok());
+        assert_eq!(random_access_file.len(Relaxed), 32768);
+
+        let mut read_buffer = vec![0_u8; 32768];
+        assert!(random_access_file.read(&mut read_buffer, 0).is_ok());
+        #[cfg(target_os = "linux")]
+        assert!(read_buffer
+            .iter()
+            .enumerate()
+            .all(|(i, d)| *d == u8::from(!(100..20000).contains(&i))));
+
+        let segment_paths = random_access_file.segment_paths();
+        drop(random_access_file);
+        for segment_path in segment_paths {
+            assert!(remove_file(segment_path).is_ok());
+        }
+    }
 }
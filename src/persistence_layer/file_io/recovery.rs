@@ -2,13 +2,24 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use super::log_record::LogRecord;
-use super::FileIOData;
+use super::container_directory::{ContainerDirectory, ContainerIndex};
+use super::log_record::{self, LogRecord};
+use super::page_manager::PageManager;
+use super::{FileIOData, FileIndex};
+use crate::catalog::CATALOG_ID;
+use crate::journal::Anchor as JournalAnchor;
 use crate::transaction::Playback;
-use crate::{Database, Error, FileIO, JournalID, Sequencer, TransactionID};
+use crate::{
+    Database, Error, FileIO, IndexType, JournalID, Sequencer, TransactionID, VersionRecord,
+};
+use scc::ebr;
+use std::mem::take;
 use std::num::NonZeroU32;
-use std::sync::atomic::Ordering::{Acquire, Relaxed};
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use std::sync::mpsc::{self, SendError, SyncSender};
+use std::sync::Arc;
 use std::task::Waker;
+use std::thread::{self, Scope};
 
 /// Collection of data for database recovery.
 #[derive(Debug)]
@@ -33,9 +44,44 @@ struct MostRecentJournal {
     journal_id: JournalID,
 }
 
+/// [`Redo`] is a database object change replayed during recovery.
+struct Redo<S: Sequencer<Instant = u64>> {
+    /// The identifier of the database object.
+    object_id: u64,
+
+    /// The [`JournalAnchor`] that owns the database object.
+    journal_anchor: ebr::Shared<JournalAnchor<S>>,
+
+    /// `true` if the database object was created, otherwise deleted.
+    created: bool,
+}
+
+/// [`RedoDispatcher`] distributes database object changes to redo workers.
+///
+/// Database object changes are partitioned by database object identifier, so that changes to a
+/// database object are replayed in the log order.
+struct RedoDispatcher<'d, S: Sequencer<Instant = u64>> {
+    /// The database to recover.
+    database: &'d Database<S, FileIO<S>>,
+
+    /// Senders of the redo workers.
+    ///
+    /// Database object changes are replayed by the current thread if there are no workers.
+    senders: Vec<SyncSender<Vec<Redo<S>>>>,
+
+    /// Database object changes to be sent to each worker.
+    batches: Vec<Vec<Redo<S>>>,
+}
+
 // No log entries are wider than 32 bytes.
 const BUFFER_SIZE: usize = 32;
 
+/// The number of database object changes sent to a redo worker at once.
+const REDO_BATCH_SIZE: usize = 256;
+
+/// The maximum number of batches waiting to be processed by a redo worker.
+const REDO_QUEUE_SIZE: usize = 4;
+
 impl<S: Sequencer<Instant = u64>> RecoveryData<S> {
     /// Creates a new [`RecoveryData`].
     pub(super) fn new(database: Database<S, FileIO<S>>, until: Option<u64>) -> Self {
@@ -63,97 +109,496 @@ impl<S: Sequencer<Instant = u64>> RecoveryData<S> {
     }
 }
 
-pub(super) fn recover_database<S: Sequencer<Instant = u64>>(file_io_data: &FileIOData<S>) {
+impl<S: Sequencer<Instant = u64>> Redo<S> {
+    /// Applies the database object change to the database.
+    fn apply(&self, database: &Database<S, FileIO<S>>) {
+        if self.created {
+            database
+                .access_controller()
+                .playback_create_sync(self.object_id, &self.journal_anchor);
+        } else {
+            database
+                .access_controller()
+                .playback_delete_sync(self.object_id, &self.journal_anchor);
+        }
+    }
+}
+
+impl<'d, S: Sequencer<Instant = u64>> RedoDispatcher<'d, S> {
+    /// Creates a new [`RedoDispatcher`] and spawns redo workers in the scope.
+    ///
+    /// No workers are spawned if `parallelism` is `1`, and fewer workers are spawned if spawning a
+    /// thread failed.
+    fn new<'s>(
+        scope: &'s Scope<'s, '_>,
+        database: &'d Database<S, FileIO<S>>,
+        parallelism: usize,
+    ) -> Self
+    where
+        'd: 's,
+    {
+        let mut senders = Vec::new();
+        if parallelism > 1 {
+            for _ in 0..parallelism {
+                let (sender, receiver) = mpsc::sync_channel::<Vec<Redo<S>>>(REDO_QUEUE_SIZE);
+                if thread::Builder::new()
+                    .spawn_scoped(scope, move || {
+                        receiver
+                            .iter()
+                            .flatten()
+                            .for_each(|redo| redo.apply(database));
+                    })
+                    .is_err()
+                {
+                    break;
+                }
+                senders.push(sender);
+            }
+        }
+        let batches = senders.iter().map(|_| Vec::new()).collect();
+        Self {
+            database,
+            senders,
+            batches,
+        }
+    }
+
+    /// Dispatches a database object change to the corresponding redo worker.
+    fn dispatch(&mut self, redo: Redo<S>) {
+        if self.senders.is_empty() {
+            redo.apply(self.database);
+            return;
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        let index = (redo.object_id % self.senders.len() as u64) as usize;
+        self.batches[index].push(redo);
+        if self.batches[index].len() == REDO_BATCH_SIZE {
+            self.send(index);
+        }
+    }
+
+    /// Sends all the pending database object changes to redo workers, and closes the channels.
+    ///
+    /// The redo workers finish once they have processed all the database object changes.
+    fn finish(mut self) {
+        for index in 0..self.batches.len() {
+            if !self.batches[index].is_empty() {
+                self.send(index);
+            }
+        }
+    }
+
+    /// Sends the pending database object changes to the specified redo worker.
+    fn send(&mut self, index: usize) {
+        let batch = take(&mut self.batches[index]);
+        if let Err(SendError(batch)) = self.senders[index].send(batch) {
+            // The worker is gone, therefore the current thread replays them.
+            for redo in batch {
+                redo.apply(self.database);
+            }
+        }
+    }
+}
+
+pub(super) fn recover_database<S: Sequencer<Instant = u64>>(file_io_data: &Arc<FileIOData<S>>) {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!("recovery").entered();
+
     let mut guard = file_io_data.recovery_data.lock().unwrap();
     let database = guard.as_mut().unwrap().database.take().unwrap();
     let playback_container: scc::HashMap<TransactionID, Playback<S, FileIO<S>>> =
         scc::HashMap::default();
     drop(guard);
 
-    let file_len = file_io_data.log.len(Acquire);
+    // Torn pages are reset before the database is reconstructed from the log unless the latest
+    // checkpoint refers to them; pages cannot be torn if the database was shut down cleanly. Torn
+    // pages of a read-only database are quarantined when read instead.
+    let crashed = !file_io_data.page_manager.is_read_only()
+        && !file_io_data.page_manager.clear_clean_shutdown_sync();
+    if crashed {
+        let page_manager = &file_io_data.page_manager;
+        #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+        let num_torn_pages =
+            page_manager.repair_torn_pages_sync(|| checkpointed_pages_sync(page_manager));
+        #[cfg(feature = "tracing")]
+        tracing::info!(num_torn_pages, "torn pages repaired");
+    }
 
-    // The variable is only updated when the journal creates or deletes a database objects.
-    let mut last_journal_anchor: Option<MostRecentJournal> = None;
+    // The catalog persisted by the latest checkpoint is played back before the log, and the log
+    // records are replayed over it; the container indexes are loaded after the log is replayed.
+    let (checkpoint_clock, log_start, container_indexes) =
+        match load_checkpoint(file_io_data, &database, crashed) {
+            Ok(loaded) => loaded,
+            Err(error) => {
+                drop(database);
+                complete(file_io_data, Err(error));
+                return;
+            }
+        };
 
-    let mut read_offset = 0;
-    let mut buffer: [u8; BUFFER_SIZE] = [0; BUFFER_SIZE];
-    while file_io_data.log.read(&mut buffer, read_offset).is_ok() {
-        if file_io_data.recovery_cancelled.load(Relaxed) {
-            // Canceled.
+    // Log records before the start offset of the checkpoint may have been reclaimed, and a log
+    // file shorter than that, e.g., a log file that was lost, is extended so that new log records
+    // are written after the offset.
+    if file_io_data.log.len(Acquire) < log_start && !file_io_data.page_manager.is_read_only() {
+        if let Err(error) = file_io_data.log.set_len(log_start) {
+            drop(database);
+            complete(file_io_data, Err(error));
             return;
         }
-        if let Some(bytes_read) = apply_to_database(
-            &buffer,
+    }
+    let file_len = file_io_data.log.len(Acquire);
+    let log_start = log_start.min(file_len);
+    file_io_data.log_head.store(log_start, Release);
+    file_io_data.log.advise_sequential();
+
+    // Redo workers are joined before open transactions are rolled back.
+    let parallelism = file_io_data.recovery_parallelism.load(Relaxed);
+    let Some(read_offset) = thread::scope(|scope| {
+        let mut redo_dispatcher = RedoDispatcher::new(scope, &database, parallelism);
+        let read_offset = replay_log(
+            file_io_data,
+            log_start,
+            file_len,
             &database,
             &playback_container,
-            &mut last_journal_anchor,
-        ) {
-            read_offset += bytes_read;
-        } else {
-            read_offset = file_len;
-            break;
+            &mut redo_dispatcher,
+        );
+        redo_dispatcher.finish();
+        read_offset
+    }) else {
+        // Canceled.
+        #[cfg(feature = "tracing")]
+        tracing::info!("recovery cancelled");
+        return;
+    };
+    #[cfg(feature = "tracing")]
+    tracing::info!(read_offset, file_len, "log replayed");
+
+    // Clock values issued without leaving log records behind must not be reused.
+    let _: Result<u64, u64> = database.sequencer().update(
+        file_io_data.page_manager.clock().max(checkpoint_clock),
+        Release,
+    );
+
+    if !playback_container.is_empty() {
+        #[cfg(feature = "tracing")]
+        tracing::info!(
+            num_open_transactions = playback_container.len(),
+            "open transactions discarded"
+        );
+        // TODO: cleanup open transactions.
+        playback_container.clear();
+    }
+    drop(playback_container);
+
+    if let Err(error) = load_container_indexes(file_io_data, &database, container_indexes) {
+        drop(database);
+        complete(file_io_data, Err(error));
+        return;
+    }
+
+    if read_offset == file_len {
+        complete(file_io_data, Ok(database));
+    } else {
+        drop(database);
+        complete(file_io_data, Err(Error::CorruptLog(read_offset)));
+    }
+}
+
+/// Returns the pages that the latest checkpoint refers to, or `None` if any of them could not be
+/// read.
+///
+/// A page that could not be read may be followed by other pages of the same container index.
+fn checkpointed_pages_sync(page_manager: &PageManager) -> Option<Vec<u64>> {
+    let (directory, mut pages) = match ContainerDirectory::read_sync(page_manager) {
+        Ok(Some(loaded)) => loaded,
+        Ok(None) => return Some(Vec::new()),
+        Err(_) => return None,
+    };
+    for index in &directory.indexes {
+        pages.extend(index.pages_sync(page_manager).ok()?);
+    }
+    Some(pages)
+}
+
+/// Plays back the catalog persisted by the latest checkpoint, and returns the clock of the
+/// checkpoint and the offset of the log from which log records are replayed along with the
+/// container indexes.
+///
+/// Pages left behind by an incomplete checkpoint are freed if the database was not shut down
+/// cleanly.
+fn load_checkpoint<S: Sequencer<Instant = u64>>(
+    file_io_data: &FileIOData<S>,
+    database: &Database<S, FileIO<S>>,
+    crashed: bool,
+) -> Result<(u64, u64, Vec<ContainerIndex>), Error> {
+    let page_manager = &file_io_data.page_manager;
+    let Some((directory, pages)) = ContainerDirectory::read_sync(page_manager)? else {
+        if crashed {
+            page_manager.reclaim_orphaned_pages_sync(&[]);
         }
+        return Ok((0, 0, Vec::new()));
+    };
+    if crashed {
+        let mut referenced = pages[..pages.len().min(1)].to_vec();
+        referenced.extend(directory.indexes.iter().map(|i| i.root));
+        #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+        let num_orphaned_pages = page_manager.reclaim_orphaned_pages_sync(&referenced);
+        #[cfg(feature = "tracing")]
+        tracing::info!(num_orphaned_pages, "orphaned pages freed");
     }
-    if read_offset < file_len && file_len - read_offset < BUFFER_SIZE as u64 {
-        // More to read in the log file.
-        #[allow(clippy::cast_possible_truncation)]
-        let buffer_piece = &mut buffer[0..(file_len - read_offset) as usize];
-        if file_io_data.log.read(buffer_piece, read_offset).is_ok() {
-            if file_io_data.recovery_cancelled.load(Relaxed) {
-                // Canceled.
-                return;
+    database.reserve_ids(directory.next_object_id, directory.next_transaction_id);
+    for record in &directory.catalog {
+        let _: Option<(u64, Arc<str>)> = database.playback_version(&VersionRecord::new(
+            CATALOG_ID,
+            record.record_id,
+            record.object_id,
+            &record.key,
+            &record.value,
+        ));
+    }
+    for object_id in &directory.invisible_objects {
+        database
+            .access_controller()
+            .playback_invisible_sync(*object_id);
+    }
+    if let Ok(mut container_directory_pages) = file_io_data.container_directory_pages.lock() {
+        *container_directory_pages = pages;
+    }
+    #[cfg(feature = "tracing")]
+    tracing::info!(
+        clock = directory.clock,
+        log_start = directory.log_start,
+        num_catalog_entries = directory.catalog.len(),
+        num_invisible_objects = directory.invisible_objects.len(),
+        num_container_indexes = directory.indexes.len(),
+        "checkpoint loaded"
+    );
+    Ok((directory.clock, directory.log_start, directory.indexes))
+}
+
+/// Installs the versions in the container indexes persisted by the latest checkpoint below the
+/// versions replayed from the log.
+///
+/// Ordered container indexes are passed to the containers, and only the versions of keys that
+/// have been replayed from the log are installed; the other versions are read from the indexes
+/// when accessed. A container of which the index has a corrupt page is quarantined instead of
+/// failing recovery.
+fn load_container_indexes<S: Sequencer<Instant = u64>>(
+    file_io_data: &Arc<FileIOData<S>>,
+    database: &Database<S, FileIO<S>>,
+    container_indexes: Vec<ContainerIndex>,
+) -> Result<(), Error> {
+    let page_manager = &file_io_data.page_manager;
+    #[cfg(feature = "tracing")]
+    let mut num_versions = 0_usize;
+    for index in &container_indexes {
+        if let Ok(pages) = index.pages_sync(page_manager) {
+            database.set_persisted_pages(index.container_id, pages.len() as u64);
+        }
+        if index.index_type == IndexType::Ordered {
+            database.playback_persisted_index(
+                index.container_id,
+                FileIndex {
+                    file_io_data: Arc::downgrade(file_io_data),
+                    container_id: index.container_id,
+                },
+            );
+        }
+        let result = index.scan_sync(page_manager, |record| {
+            #[cfg(feature = "tracing")]
+            {
+                num_versions += 1;
             }
-            if let Some(bytes_read) = apply_to_database(
-                buffer_piece,
-                &database,
-                &playback_container,
-                &mut last_journal_anchor,
-            ) {
-                read_offset += bytes_read;
-            } else {
-                read_offset = file_len;
+            database.playback_persisted_version(&VersionRecord::new(
+                index.container_id,
+                record.record_id,
+                record.object_id,
+                &record.key,
+                &record.value,
+            ));
+        });
+        match result {
+            Ok(()) => (),
+            Err(Error::CorruptPage(address)) => {
+                // The corrupt page is quarantined along with the container, and the other
+                // containers are loaded.
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    container_id = index.container_id,
+                    address,
+                    "container quarantined"
+                );
+                database.playback_quarantine(index.container_id, Error::CorruptPage(address));
             }
+            Err(error) => return Err(error),
         }
     }
-
-    if !playback_container.is_empty() {
-        // TODO: cleanup open transactions.
-        playback_container.clear();
+    #[cfg(feature = "tracing")]
+    tracing::info!(num_versions, "container indexes loaded");
+    if let Ok(mut guard) = file_io_data.container_indexes.lock() {
+        *guard = container_indexes;
     }
-    drop(playback_container);
+    Ok(())
+}
 
+/// Passes the result of recovery to the database owner unless recovery was canceled.
+fn complete<S: Sequencer<Instant = u64>>(
+    file_io_data: &FileIOData<S>,
+    result: Result<Database<S, FileIO<S>>, Error>,
+) {
     let mut guard = file_io_data.recovery_data.lock().unwrap();
     if guard.as_ref().unwrap().result.is_some() {
         // Canceled.
         return;
     }
     let recovery_data = guard.as_mut().unwrap();
-    if read_offset == file_len {
-        recovery_data.result.replace(Ok(database));
-    } else {
-        recovery_data
-            .result
-            .replace(Err(Error::IO(std::io::ErrorKind::InvalidData)));
-    }
+    recovery_data.result.replace(result);
     if let Some(waker) = recovery_data.waker.take() {
         waker.wake();
     }
 }
 
+/// Reads the log file from the start offset, and replays log records.
+///
+/// Returns the offset at which the log file was read up to, or `None` if recovery was canceled.
+fn replay_log<'d, S: Sequencer<Instant = u64>>(
+    file_io_data: &FileIOData<S>,
+    log_start: u64,
+    file_len: u64,
+    database: &'d Database<S, FileIO<S>>,
+    playback_container: &scc::HashMap<TransactionID, Playback<'d, S, FileIO<S>>>,
+    redo_dispatcher: &mut RedoDispatcher<'d, S>,
+) -> Option<u64> {
+    if file_io_data.log.encryption().is_some() {
+        return replay_encrypted_log(
+            file_io_data,
+            log_start,
+            file_len,
+            database,
+            playback_container,
+            redo_dispatcher,
+        );
+    }
+
+    // The variable is only updated when the journal creates or deletes a database objects.
+    let mut last_journal_anchor: Option<MostRecentJournal> = None;
+
+    // The buffer is enlarged when a log record is followed by a payload that does not fit.
+    let mut read_offset = log_start;
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut required_len = 0;
+    while read_offset < file_len {
+        if file_io_data.recovery_cancelled.load(Relaxed) {
+            return None;
+        }
+        let remaining = usize::try_from(file_len - read_offset).unwrap_or(usize::MAX);
+        let len = remaining.min(required_len.max(BUFFER_SIZE));
+        if len < required_len {
+            // The payload was not fully written.
+            break;
+        }
+        buffer.resize(len, 0);
+        if file_io_data.log.read(&mut buffer, read_offset).is_err() {
+            break;
+        }
+        let Some(bytes_read) = apply_to_database(
+            &buffer,
+            database,
+            playback_container,
+            redo_dispatcher,
+            &mut last_journal_anchor,
+            &mut required_len,
+        ) else {
+            return Some(file_len);
+        };
+        read_offset += bytes_read;
+        if bytes_read == 0 && required_len <= len {
+            // The log record at the end of the log file is incomplete.
+            break;
+        }
+    }
+    Some(read_offset)
+}
+
+/// Reads the frames of the encrypted log file from the start offset, and replays log records.
+///
+/// A torn or unwritten frame at the end of the log file is truncated, so that new frames directly
+/// follow the last intact frame. Returns the offset at which the log file was read up to, or
+/// `None` if recovery was canceled.
+fn replay_encrypted_log<'d, S: Sequencer<Instant = u64>>(
+    file_io_data: &FileIOData<S>,
+    log_start: u64,
+    file_len: u64,
+    database: &'d Database<S, FileIO<S>>,
+    playback_container: &scc::HashMap<TransactionID, Playback<'d, S, FileIO<S>>>,
+    redo_dispatcher: &mut RedoDispatcher<'d, S>,
+) -> Option<u64> {
+    let mut last_journal_anchor: Option<MostRecentJournal> = None;
+    let mut read_offset = log_start;
+    while read_offset < file_len {
+        if file_io_data.recovery_cancelled.load(Relaxed) {
+            return None;
+        }
+        let frame_len = file_io_data.log.frame_len(read_offset).unwrap_or(0);
+        let frame_end = read_offset + frame_len;
+        if frame_len == 0 || frame_end > file_len {
+            break;
+        }
+        let Ok(data) = file_io_data
+            .log
+            .read_decrypted_frame(read_offset, frame_len)
+        else {
+            if frame_end == file_len {
+                // The last frame was torn.
+                break;
+            }
+            return Some(read_offset);
+        };
+        // Payloads never cross frame boundaries.
+        let mut required_len = 0;
+        match apply_to_database(
+            &data,
+            database,
+            playback_container,
+            redo_dispatcher,
+            &mut last_journal_anchor,
+            &mut required_len,
+        ) {
+            Some(bytes_read) if bytes_read == data.len() as u64 => read_offset = frame_end,
+            Some(_) => return Some(read_offset),
+            None => return Some(file_len),
+        }
+    }
+    if read_offset == file_len
+        || file_io_data.page_manager.is_read_only()
+        || file_io_data.log.set_len(read_offset).is_ok()
+    {
+        Some(file_len)
+    } else {
+        Some(read_offset)
+    }
+}
+
 /// Applies log records in the buffer to the database.
 ///
-/// Returns `None` if it read the end of the log file.
+/// Returns `None` if it read the end of the log file. If a log record is followed by a payload
+/// that does not fit in the buffer, it stops before the log record, and `required_len` is set
+/// to the length of the log record including the payload.
 #[allow(clippy::too_many_lines)]
 fn apply_to_database<'d, S: Sequencer<Instant = u64>>(
     mut buffer: &[u8],
     database: &'d Database<S, FileIO<S>>,
     playback_container: &scc::HashMap<TransactionID, Playback<'d, S, FileIO<S>>>,
+    redo_dispatcher: &mut RedoDispatcher<'d, S>,
     last_journal_anchor: &mut Option<MostRecentJournal>,
+    required_len: &mut usize,
 ) -> Option<u64> {
-    // TODO: parallelize.
     let buffer_len = buffer.len();
+    *required_len = 0;
     while !buffer.is_empty() {
         if let Some((log_record, remaining)) = LogRecord::<S>::from_raw_data(buffer) {
+            let record_start = buffer;
             buffer = remaining;
             match log_record {
                 LogRecord::EndOfLog => {
@@ -166,7 +611,7 @@ fn apply_to_database<'d, S: Sequencer<Instant = u64>>(
                         .unwrap();
                     playback_entry
                         .get_mut()
-                        .submit_journal_anchor(last_journal_anchor.journal_id, submit_instant);
+                        .submit_journal(last_journal_anchor.journal_id, submit_instant);
                 }
                 LogRecord::BufferDiscarded => {
                     let last_journal_anchor = last_journal_anchor.take().unwrap();
@@ -175,18 +620,21 @@ fn apply_to_database<'d, S: Sequencer<Instant = u64>>(
                         .unwrap();
                     playback_entry
                         .get_mut()
-                        .discard_journal_anchor(last_journal_anchor.journal_id);
+                        .discard_journal(last_journal_anchor.journal_id);
                 }
                 LogRecord::JournalCreatedObjectSingle(transaction_id, journal_id, object_id) => {
                     let mut playback_entry = playback_container
                         .entry(transaction_id)
-                        .or_insert_with(|| Playback::new(database));
+                        .or_insert_with(|| Playback::with_id(database, transaction_id));
                     let journal_anchor = playback_entry
                         .get_mut()
                         .get_or_create_journal_anchor(journal_id);
-                    database
-                        .access_controller()
-                        .playback_create_sync(object_id, &journal_anchor);
+                    redo_dispatcher.dispatch(Redo {
+                        object_id,
+                        journal_anchor,
+                        created: true,
+                    });
+                    database.reserve_object_id(object_id);
                     last_journal_anchor.replace(MostRecentJournal {
                         transaction_id,
                         journal_id,
@@ -201,15 +649,18 @@ fn apply_to_database<'d, S: Sequencer<Instant = u64>>(
                 ) => {
                     let mut playback_entry = playback_container
                         .entry(transaction_id)
-                        .or_insert_with(|| Playback::new(database));
+                        .or_insert_with(|| Playback::with_id(database, transaction_id));
                     let journal_anchor = playback_entry
                         .get_mut()
                         .get_or_create_journal_anchor(journal_id);
                     (0..num_objects).for_each(|i| {
                         let object_id = start_object_id + u64::from(i) * u64::from(interval);
-                        database
-                            .access_controller()
-                            .playback_create_sync(object_id, &journal_anchor);
+                        redo_dispatcher.dispatch(Redo {
+                            object_id,
+                            journal_anchor: journal_anchor.clone(),
+                            created: true,
+                        });
+                        database.reserve_object_id(object_id);
                     });
                     last_journal_anchor.replace(MostRecentJournal {
                         transaction_id,
@@ -219,13 +670,15 @@ fn apply_to_database<'d, S: Sequencer<Instant = u64>>(
                 LogRecord::JournalDeletedObjectSingle(transaction_id, journal_id, object_id) => {
                     let mut playback_entry = playback_container
                         .entry(transaction_id)
-                        .or_insert_with(|| Playback::new(database));
+                        .or_insert_with(|| Playback::with_id(database, transaction_id));
                     let journal_anchor = playback_entry
                         .get_mut()
                         .get_or_create_journal_anchor(journal_id);
-                    database
-                        .access_controller()
-                        .playback_delete_sync(object_id, &journal_anchor);
+                    redo_dispatcher.dispatch(Redo {
+                        object_id,
+                        journal_anchor,
+                        created: false,
+                    });
                     last_journal_anchor.replace(MostRecentJournal {
                         transaction_id,
                         journal_id,
@@ -240,41 +693,64 @@ fn apply_to_database<'d, S: Sequencer<Instant = u64>>(
                 ) => {
                     let mut playback_entry = playback_container
                         .entry(transaction_id)
-                        .or_insert_with(|| Playback::new(database));
+                        .or_insert_with(|| Playback::with_id(database, transaction_id));
                     let journal_anchor = playback_entry
                         .get_mut()
                         .get_or_create_journal_anchor(journal_id);
                     (0..num_objects).for_each(|i| {
                         let object_id = start_object_id + u64::from(i) * u64::from(interval);
-                        database
-                            .access_controller()
-                            .playback_delete_sync(object_id, &journal_anchor);
+                        redo_dispatcher.dispatch(Redo {
+                            object_id,
+                            journal_anchor: journal_anchor.clone(),
+                            created: false,
+                        });
                     });
                     last_journal_anchor.replace(MostRecentJournal {
                         transaction_id,
                         journal_id,
                     });
                 }
+                LogRecord::JournalWroteVersion(transaction_id, journal_id, object_id, len) => {
+                    let len = usize::try_from(len).unwrap_or(usize::MAX);
+                    if buffer.len() < len {
+                        *required_len = log_record.size().saturating_add(len);
+                        return Some((buffer_len - record_start.len()) as u64);
+                    }
+                    let (payload, remaining) = buffer.split_at(len);
+                    buffer = remaining;
+                    let Some(version) = log_record::decode_version(object_id, payload) else {
+                        return Some((buffer_len - record_start.len()) as u64);
+                    };
+                    playback_container
+                        .entry(transaction_id)
+                        .or_insert_with(|| Playback::with_id(database, transaction_id))
+                        .get_mut()
+                        .write(&version);
+                    last_journal_anchor.replace(MostRecentJournal {
+                        transaction_id,
+                        journal_id,
+                    });
+                }
                 LogRecord::JournalSubmitted(transaction_id, journal_id, submit_instant) => {
                     let mut playback_entry = playback_container.get(&transaction_id).unwrap();
                     playback_entry
                         .get_mut()
-                        .submit_journal_anchor(journal_id, submit_instant);
+                        .submit_journal(journal_id, submit_instant);
                 }
                 LogRecord::JournalDiscarded(transaction_id, journal_id) => {
                     let mut playback_entry = playback_container.get(&transaction_id).unwrap();
-                    playback_entry.get_mut().discard_journal_anchor(journal_id);
+                    playback_entry.get_mut().discard_journal(journal_id);
                 }
                 LogRecord::TransactionPrepared(transaction_id, prepare_instant) => {
                     let mut playback_entry = playback_container
                         .entry(transaction_id)
-                        .or_insert_with(|| Playback::new(database));
+                        .or_insert_with(|| Playback::with_id(database, transaction_id));
                     playback_entry.get_mut().prepare(prepare_instant);
                 }
                 LogRecord::TransactionCommitted(transaction_id, commit_instant) => {
                     let playback_entry = playback_container
                         .entry(transaction_id)
-                        .or_insert_with(|| Playback::new(database));
+                        .or_insert_with(|| Playback::with_id(database, transaction_id));
                     playback_entry.remove().commit(commit_instant);
                 }
                 LogRecord::TransactionRolledBack(transaction_id, rollback_to) => {
@@ -302,7 +778,8 @@ fn apply_to_database<'d, S: Sequencer<Instant = u64>>(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Database;
+    use crate::{Database, MonotonicU64};
+    use std::num::NonZeroUsize;
     use std::path::Path;
     use std::sync::atomic::Ordering::Relaxed;
     use std::sync::Arc;
@@ -410,4 +887,70 @@ mod tests {
 
         assert!(remove_dir_all(path).await.is_ok());
     }
+
+    #[tokio::test]
+    async fn parallel() {
+        const DIR: &str = "recovery_parallel_test";
+        let path = Path::new(DIR);
+        let database = Arc::new(Database::with_path(path).await.unwrap());
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal
+            .create((0..4096).collect::<Vec<u64>>().as_slice(), None)
+            .await
+            .unwrap();
+        assert_eq!(Some(journal.submit()), NonZeroU32::new(1));
+        assert!(transaction.commit().await.is_ok());
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        for o in (0..4096).rev().step_by(3) {
+            journal.delete(&[o], None).await.unwrap();
+        }
+        assert_eq!(Some(journal.submit()), NonZeroU32::new(1));
+        assert!(transaction.commit().await.is_ok());
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal
+            .delete(
+                (0..4096)
+                    .filter(|o| (4095 - o) % 3 != 0)
+                    .collect::<Vec<u64>>()
+                    .as_slice(),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(Some(journal.submit()), NonZeroU32::new(1));
+        drop(transaction);
+
+        let instant = database.sequencer().now(Relaxed);
+        drop(database);
+
+        let file_io = FileIO::with_path(path).unwrap();
+        file_io.set_recovery_parallelism(NonZeroUsize::new(4).unwrap());
+        let database_recovered: Database<MonotonicU64> =
+            Database::with_persistence_layer(file_io, None, None)
+                .await
+                .unwrap();
+        assert_eq!(database_recovered.sequencer().now(Relaxed), instant);
+
+        let snapshot = database_recovered.snapshot();
+        for o in 0..4096 {
+            assert_eq!(
+                database_recovered
+                    .access_controller()
+                    .read(o, &snapshot, None)
+                    .await,
+                Ok((4095 - o) % 3 != 0),
+                "{o}"
+            );
+        }
+        drop(snapshot);
+        drop(database_recovered);
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
 }
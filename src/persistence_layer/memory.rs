@@ -0,0 +1,809 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! [`MemoryPersistence`] [`PersistenceLayer`] implementation.
+
+use super::{
+    AwaitIO, AwaitRecovery, LogBufferInterface, PersistedIndexInterface, PersistenceLayer,
+    RecoveryResult, VersionRecord,
+};
+use crate::utils::Instant;
+use crate::{Counter, Database, Error, JournalID, Playback, Sequencer, Telemetry, TransactionID};
+use std::collections::HashMap;
+use std::mem::take;
+use std::num::{NonZeroU32, NonZeroU64};
+use std::ops::Bound;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use std::sync::{Arc, Mutex};
+use std::task::Waker;
+
+/// [`MemoryPersistence`] is a [`PersistenceLayer`] that keeps the log in memory.
+///
+/// Log records are buffered in a [`MemoryStorage`] that simulates a storage device: submitted log
+/// records are only kept in its volatile part until a synchronization barrier, e.g., a
+/// transaction being committed, makes them durable. [`MemoryStorage::crash`] discards the
+/// volatile part in order to simulate a power failure, and a [`Database`] can be recovered from
+/// the durable part by creating a new [`MemoryPersistence`] with the same [`MemoryStorage`].
+///
+/// Key-value pairs are written to the log as well, and containers are reconstructed from the
+/// log when a [`Database`] is recovered, therefore no pages are kept.
+///
+/// # Examples
+///
+/// ```
+/// use sap_tsf::{Database, MemoryPersistence, MonotonicU64};
+///
+/// async {
+///     let database: Database<MonotonicU64, MemoryPersistence<MonotonicU64>> =
+///         Database::with_persistence_layer(MemoryPersistence::default(), None, None)
+///             .await
+///             .unwrap();
+///     assert!(database.transaction().commit().await.is_ok());
+/// };
+/// ```
+#[derive(Debug, Default)]
+pub struct MemoryPersistence<S: Sequencer> {
+    /// The simulated storage device.
+    storage: MemoryStorage<S>,
+
+    /// The recovered database.
+    recovery_result: Mutex<Option<Result<Database<S, Self>, Error>>>,
+
+    /// Statistics of the persistence layer.
+    telemetry: Telemetry,
+}
+
+/// [`MemoryStorage`] is a simulated storage device of [`MemoryPersistence`].
+///
+/// Cloning a [`MemoryStorage`] yields a handle to the same device.
+#[derive(Debug)]
+pub struct MemoryStorage<S: Sequencer>(Arc<StorageData<S>>);
+
+/// [`MemoryLogBuffer`] is the log buffer type for [`MemoryPersistence`].
+#[derive(Debug, Default)]
+pub struct MemoryLogBuffer<S: Sequencer> {
+    /// Log records that have yet to be passed to the storage.
+    log_records: Mutex<Vec<LogRecord<S>>>,
+
+    /// The flush epoch since when the log buffer is durable.
+    durable_flush_epoch: AtomicU64,
+}
+
+/// [`MemoryIndex`] is the persisted index type for [`MemoryPersistence`].
+///
+/// [`MemoryPersistence`] installs every version in memory when the database is recovered,
+/// therefore no instances of it exist.
+#[derive(Debug)]
+pub enum MemoryIndex {}
+
+/// [`StorageData`] is shared among [`MemoryStorage`] handles.
+#[derive(Debug, Default)]
+struct StorageData<S: Sequencer> {
+    /// The log.
+    log: Mutex<Log<S>>,
+
+    /// The number of synchronization barriers.
+    flush_epoch: AtomicU64,
+}
+
+/// [`Log`] consists of durable and volatile log records.
+#[derive(Debug, Default)]
+struct Log<S: Sequencer> {
+    /// Log records that survive [`MemoryStorage::crash`].
+    durable: Vec<LogRecord<S>>,
+
+    /// Log records that have not reached a synchronization barrier.
+    volatile: Vec<LogRecord<S>>,
+}
+
+/// [`LogRecord`] is a change to the database stored in the log.
+#[derive(Debug)]
+enum LogRecord<S: Sequencer> {
+    /// A journal created a database object.
+    JournalCreatedObject(TransactionID, JournalID, u64),
+
+    /// A journal deleted a database object.
+    JournalDeletedObject(TransactionID, JournalID, u64),
+
+    /// A journal wrote a version of a key-value pair identified as
+    /// `(container identifier, record identifier, database object identifier)`.
+    JournalWroteVersion(TransactionID, [u64; 3], Box<[u8]>, Box<[u8]>),
+
+    /// A journal was submitted.
+    JournalSubmitted(TransactionID, JournalID, NonZeroU32),
+
+    /// A journal was discarded.
+    JournalDiscarded(TransactionID, JournalID),
+
+    /// A transaction participated in a distributed transaction.
+    TransactionParticipated(TransactionID, Box<[u8]>),
+
+    /// A transaction was rewound.
+    TransactionRewound(TransactionID, Option<NonZeroU32>),
+
+    /// A transaction was prepared.
+    TransactionPrepared(TransactionID, S::Instant),
+
+    /// A transaction was committed.
+    TransactionCommitted(TransactionID, S::Instant),
+
+    /// The database was shut down at the instant.
+    CleanShutdown(S::Instant),
+}
+
+impl<S: Sequencer> MemoryPersistence<S> {
+    /// Creates a new [`MemoryPersistence`] on the specified [`MemoryStorage`].
+    ///
+    /// A [`Database`] created with the [`MemoryPersistence`] is recovered from the durable log
+    /// records in the [`MemoryStorage`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, MemoryPersistence, MemoryStorage, MonotonicU64};
+    ///
+    /// async {
+    ///     let storage = MemoryStorage::<MonotonicU64>::default();
+    ///     let database = Database::with_persistence_layer(
+    ///         MemoryPersistence::with_storage(storage.clone()), None, None).await.unwrap();
+    ///     drop(database);
+    ///
+    ///     let database = Database::with_persistence_layer(
+    ///         MemoryPersistence::with_storage(storage), None, None).await.unwrap();
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn with_storage(storage: MemoryStorage<S>) -> Self {
+        Self {
+            storage,
+            recovery_result: Mutex::default(),
+            telemetry: Telemetry::default(),
+        }
+    }
+
+    /// Returns a reference to its [`MemoryStorage`].
+    #[inline]
+    #[must_use]
+    pub fn storage(&self) -> &MemoryStorage<S> {
+        &self.storage
+    }
+
+    /// Passes the log records in the log buffer to the storage.
+    fn write(&self, log_buffer: &MemoryLogBuffer<S>, log_record: Option<LogRecord<S>>) {
+        let mut log_records = log_buffer
+            .log_records
+            .lock()
+            .map(|mut l| take(&mut *l))
+            .unwrap_or_default();
+        log_records.extend(log_record);
+        if let Ok(mut log) = self.storage.0.log.lock() {
+            log.volatile.append(&mut log_records);
+        }
+    }
+
+    /// Makes every log record in the storage durable, and sets the durable flush epoch of the log
+    /// buffer.
+    fn sync(
+        &self,
+        log_buffer: Arc<MemoryLogBuffer<S>>,
+        log_record: Option<LogRecord<S>>,
+        deadline: Option<Instant>,
+    ) -> AwaitIO<'_, S, Self> {
+        self.write(&log_buffer, log_record);
+        let Ok(mut log) = self.storage.0.log.lock() else {
+            return AwaitIO::with_error(self, Error::UnexpectedState);
+        };
+        let mut volatile = take(&mut log.volatile);
+        log.durable.append(&mut volatile);
+        let flush_epoch = self.storage.0.flush_epoch.fetch_add(1, Release) + 1;
+        drop(log);
+        self.telemetry.add(Counter::Fsyncs, 1);
+        log_buffer.set_durable_flush_epoch(flush_epoch);
+        AwaitIO::with_log_buffer(self, log_buffer, deadline)
+    }
+
+    /// Replays the durable log records.
+    fn replay(&self, database: &Database<S, Self>, until: Option<S::Instant>) {
+        let Ok(log) = self.storage.0.log.lock() else {
+            return;
+        };
+        let mut playback_container: HashMap<TransactionID, Playback<S, Self>> = HashMap::new();
+        for log_record in &log.durable {
+            match log_record {
+                LogRecord::JournalCreatedObject(transaction_id, journal_id, object_id) => {
+                    playback_container
+                        .entry(*transaction_id)
+                        .or_insert_with(|| Playback::with_id(database, *transaction_id))
+                        .create(*journal_id, *object_id);
+                }
+                LogRecord::JournalDeletedObject(transaction_id, journal_id, object_id) => {
+                    playback_container
+                        .entry(*transaction_id)
+                        .or_insert_with(|| Playback::with_id(database, *transaction_id))
+                        .delete(*journal_id, *object_id);
+                }
+                LogRecord::JournalWroteVersion(transaction_id, [c, r, o], key, value) => {
+                    playback_container
+                        .entry(*transaction_id)
+                        .or_insert_with(|| Playback::with_id(database, *transaction_id))
+                        .write(&VersionRecord::new(*c, *r, *o, key, value));
+                }
+                LogRecord::JournalSubmitted(transaction_id, journal_id, submit_instant) => {
+                    if let Some(playback) = playback_container.get_mut(transaction_id) {
+                        playback.submit_journal(*journal_id, submit_instant.get());
+                    }
+                }
+                LogRecord::JournalDiscarded(transaction_id, journal_id) => {
+                    if let Some(playback) = playback_container.get_mut(transaction_id) {
+                        playback.discard_journal(*journal_id);
+                    }
+                }
+                LogRecord::TransactionParticipated(transaction_id, xid) => {
+                    playback_container
+                        .entry(*transaction_id)
+                        .or_insert_with(|| Playback::with_id(database, *transaction_id))
+                        .participate(xid);
+                }
+                LogRecord::TransactionRewound(transaction_id, rewind_to) => {
+                    if rewind_to.is_none() {
+                        if let Some(playback) = playback_container.remove(transaction_id) {
+                            playback.rollback();
+                        }
+                    } else if let Some(playback) = playback_container.get_mut(transaction_id) {
+                        playback.rewind(*rewind_to);
+                    }
+                }
+                LogRecord::TransactionPrepared(transaction_id, prepare_instant) => {
+                    playback_container
+                        .entry(*transaction_id)
+                        .or_insert_with(|| Playback::with_id(database, *transaction_id))
+                        .prepare(*prepare_instant);
+                }
+                LogRecord::TransactionCommitted(transaction_id, commit_instant) => {
+                    if until.is_some_and(|until| *commit_instant > until) {
+                        break;
+                    }
+                    playback_container
+                        .remove(transaction_id)
+                        .unwrap_or_else(|| Playback::new(database))
+                        .commit(*commit_instant);
+                }
+                LogRecord::CleanShutdown(clock) => {
+                    // Clock values issued without leaving log records behind must not be reused.
+                    let _: Result<S::Instant, S::Instant> =
+                        database.sequencer().update(*clock, Release);
+                }
+            }
+        }
+
+        // TODO: cleanup open transactions.
+        playback_container.clear();
+    }
+}
+
+impl<S: Sequencer> MemoryStorage<S> {
+    /// Discards log records that have not reached a synchronization barrier.
+    ///
+    /// Returns the number of discarded log records.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{MemoryStorage, MonotonicU64};
+    ///
+    /// let storage = MemoryStorage::<MonotonicU64>::default();
+    /// assert_eq!(storage.crash(), 0);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn crash(&self) -> usize {
+        self.0
+            .log
+            .lock()
+            .map_or(0, |mut log| take(&mut log.volatile).len())
+    }
+
+    /// Returns the number of durable log records.
+    #[inline]
+    #[must_use]
+    pub fn durable_log_len(&self) -> usize {
+        self.0.log.lock().map_or(0, |log| log.durable.len())
+    }
+
+    /// Returns the number of synchronization barriers that the storage has gone through.
+    #[inline]
+    #[must_use]
+    pub fn flush_epoch(&self) -> u64 {
+        self.0.flush_epoch.load(Acquire)
+    }
+
+    /// Creates a new [`MemoryStorage`] that only contains the durable log records of the storage.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{MemoryStorage, MonotonicU64};
+    ///
+    /// let storage = MemoryStorage::<MonotonicU64>::default();
+    /// let snapshot = storage.snapshot();
+    /// assert_eq!(snapshot.durable_log_len(), storage.durable_log_len());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn snapshot(&self) -> Self {
+        let durable = self
+            .0
+            .log
+            .lock()
+            .map(|log| log.durable.clone())
+            .unwrap_or_default();
+        Self(Arc::new(StorageData {
+            log: Mutex::new(Log {
+                durable,
+                volatile: Vec::new(),
+            }),
+            flush_epoch: AtomicU64::new(0),
+        }))
+    }
+}
+
+impl<S: Sequencer> Clone for LogRecord<S> {
+    #[inline]
+    fn clone(&self) -> Self {
+        match self {
+            Self::JournalCreatedObject(t, j, o) => Self::JournalCreatedObject(*t, *j, *o),
+            Self::JournalDeletedObject(t, j, o) => Self::JournalDeletedObject(*t, *j, *o),
+            Self::JournalWroteVersion(t, i, k, v) => {
+                Self::JournalWroteVersion(*t, *i, k.clone(), v.clone())
+            }
+            Self::JournalSubmitted(t, j, i) => Self::JournalSubmitted(*t, *j, *i),
+            Self::JournalDiscarded(t, j) => Self::JournalDiscarded(*t, *j),
+            Self::TransactionParticipated(t, x) => Self::TransactionParticipated(*t, x.clone()),
+            Self::TransactionRewound(t, i) => Self::TransactionRewound(*t, *i),
+            Self::TransactionPrepared(t, i) => Self::TransactionPrepared(*t, *i),
+            Self::TransactionCommitted(t, i) => Self::TransactionCommitted(*t, *i),
+            Self::CleanShutdown(i) => Self::CleanShutdown(*i),
+        }
+    }
+}
+
+impl<S: Sequencer> Clone for MemoryStorage<S> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<S: Sequencer> Default for MemoryStorage<S> {
+    #[inline]
+    fn default() -> Self {
+        Self(Arc::default())
+    }
+}
+
+impl<S: Sequencer> PersistenceLayer<S> for MemoryPersistence<S> {
+    type LogBuffer = MemoryLogBuffer<S>;
+    type PersistedIndex = MemoryIndex;
+
+    #[inline]
+    fn wait_prepare_logging() -> bool {
+        true
+    }
+
+    #[inline]
+    fn recover(
+        &self,
+        database: Database<S, Self>,
+        until: Option<S::Instant>,
+        deadline: Option<Instant>,
+    ) -> Result<AwaitRecovery<'_, S, Self>, Error> {
+        self.replay(&database, until);
+        let Ok(mut recovery_result) = self.recovery_result.lock() else {
+            // Locking unexpectedly failed.
+            return Err(Error::UnexpectedState);
+        };
+        debug_assert!(recovery_result.is_none());
+        recovery_result.replace(Ok(database));
+        Ok(AwaitRecovery::new(self, deadline))
+    }
+
+    /// Backing up a [`MemoryPersistence`] to a path is not supported; use
+    /// [`MemoryStorage::snapshot`] instead.
+    #[inline]
+    fn backup(
+        &self,
+        _database: &Database<S, Self>,
+        _catalog_only: bool,
+        _path: Option<&str>,
+        _deadline: Option<Instant>,
+    ) -> AwaitIO<'_, S, Self> {
+        AwaitIO::with_error(
+            self,
+            Error::Generic("the in-memory database cannot be backed up to a path"),
+        )
+    }
+
+    #[inline]
+    fn shutdown(
+        &self,
+        database: &Database<S, Self>,
+        deadline: Option<Instant>,
+    ) -> AwaitIO<'_, S, Self> {
+        let clock = database.sequencer().now(Acquire);
+        self.sync(
+            Arc::default(),
+            Some(LogRecord::CleanShutdown(clock)),
+            deadline,
+        )
+    }
+
+    #[inline]
+    fn participate(
+        &self,
+        transaction_id: TransactionID,
+        xid: &[u8],
+        deadline: Option<Instant>,
+    ) -> AwaitIO<'_, S, Self> {
+        self.sync(
+            Arc::default(),
+            Some(LogRecord::TransactionParticipated(
+                transaction_id,
+                xid.into(),
+            )),
+            deadline,
+        )
+    }
+
+    #[inline]
+    fn create(
+        &self,
+        log_buffer: Arc<Self::LogBuffer>,
+        transaction_id: TransactionID,
+        journal_id: JournalID,
+        object_ids: &[u64],
+    ) -> Result<Arc<Self::LogBuffer>, Error> {
+        let Ok(mut log_records) = log_buffer.log_records.lock() else {
+            return Err(Error::UnexpectedState);
+        };
+        log_records.extend(
+            object_ids
+                .iter()
+                .map(|id| LogRecord::JournalCreatedObject(transaction_id, journal_id, *id)),
+        );
+        drop(log_records);
+        Ok(log_buffer)
+    }
+
+    #[inline]
+    fn write(
+        &self,
+        log_buffer: Arc<Self::LogBuffer>,
+        transaction_id: TransactionID,
+        _journal_id: JournalID,
+        version: &VersionRecord<'_>,
+    ) -> Result<Arc<Self::LogBuffer>, Error> {
+        let Ok(mut log_records) = log_buffer.log_records.lock() else {
+            return Err(Error::UnexpectedState);
+        };
+        log_records.push(LogRecord::JournalWroteVersion(
+            transaction_id,
+            [
+                version.container_id(),
+                version.record_id(),
+                version.object_id(),
+            ],
+            version.key().into(),
+            version.value().into(),
+        ));
+        drop(log_records);
+        Ok(log_buffer)
+    }
+
+    #[inline]
+    fn delete(
+        &self,
+        log_buffer: Arc<Self::LogBuffer>,
+        transaction_id: TransactionID,
+        journal_id: JournalID,
+        object_ids: &[u64],
+    ) -> Result<Arc<Self::LogBuffer>, Error> {
+        let Ok(mut log_records) = log_buffer.log_records.lock() else {
+            return Err(Error::UnexpectedState);
+        };
+        log_records.extend(
+            object_ids
+                .iter()
+                .map(|id| LogRecord::JournalDeletedObject(transaction_id, journal_id, *id)),
+        );
+        drop(log_records);
+        Ok(log_buffer)
+    }
+
+    #[inline]
+    fn submit(
+        &self,
+        log_buffer: Arc<Self::LogBuffer>,
+        transaction_id: TransactionID,
+        journal_id: JournalID,
+        transaction_instant: Option<NonZeroU32>,
+        _deadline: Option<Instant>,
+    ) {
+        let log_record = transaction_instant
+            .map(|instant| LogRecord::JournalSubmitted(transaction_id, journal_id, instant));
+        self.write(&log_buffer, log_record);
+    }
+
+    #[inline]
+    fn discard(
+        &self,
+        log_buffer: Arc<Self::LogBuffer>,
+        transaction_id: TransactionID,
+        journal_id: JournalID,
+        _deadline: Option<Instant>,
+    ) {
+        let log_record = LogRecord::JournalDiscarded(transaction_id, journal_id);
+        self.write(&log_buffer, Some(log_record));
+    }
+
+    #[inline]
+    fn rewind(
+        &self,
+        log_buffer: Arc<Self::LogBuffer>,
+        transaction_id: TransactionID,
+        transaction_instant: Option<NonZeroU32>,
+        _deadline: Option<Instant>,
+    ) {
+        let log_record = LogRecord::TransactionRewound(transaction_id, transaction_instant);
+        self.write(&log_buffer, Some(log_record));
+    }
+
+    #[inline]
+    fn prepare(
+        &self,
+        log_buffer: Arc<Self::LogBuffer>,
+        transaction_id: TransactionID,
+        prepare_instant: S::Instant,
+        deadline: Option<Instant>,
+    ) -> AwaitIO<'_, S, Self> {
+        let log_record = LogRecord::TransactionPrepared(transaction_id, prepare_instant);
+        self.sync(log_buffer, Some(log_record), deadline)
+    }
+
+    #[inline]
+    fn commit(
+        &self,
+        log_buffer: Arc<Self::LogBuffer>,
+        transaction_id: TransactionID,
+        commit_instant: S::Instant,
+        deadline: Option<Instant>,
+    ) -> AwaitIO<'_, S, Self> {
+        let log_record = LogRecord::TransactionCommitted(transaction_id, commit_instant);
+        self.sync(log_buffer, Some(log_record), deadline)
+    }
+
+    #[inline]
+    fn current_flush_epoch(&self) -> Option<NonZeroU64> {
+        NonZeroU64::new(self.storage.0.flush_epoch.load(Relaxed))
+    }
+
+    #[inline]
+    fn check_io_completion(
+        &self,
+        expected_flush_epoch: Option<NonZeroU64>,
+        _waker: &Waker,
+    ) -> Result<bool, Error> {
+        // Log buffers are synchronized with the storage before being awaited.
+        Ok(expected_flush_epoch.is_some_and(|e| self.current_flush_epoch() >= Some(e)))
+    }
+
+    #[inline]
+    fn check_recovery(&self, _waker: &Waker) -> Result<RecoveryResult<S, Self>, Error> {
+        if let Ok(mut recovery_result) = self.recovery_result.try_lock() {
+            if let Some(database) = recovery_result.take() {
+                return Ok(RecoveryResult::Recovered(database?));
+            }
+        }
+
+        // Locking failed.
+        Ok(RecoveryResult::Unknown)
+    }
+
+    #[inline]
+    fn cancel_recovery(&self) {
+        // Recovery is synchronous.
+    }
+
+    #[inline]
+    fn telemetry(&self) -> &Telemetry {
+        &self.telemetry
+    }
+}
+
+impl<S: Sequencer> LogBufferInterface for MemoryLogBuffer<S> {
+    #[inline]
+    fn set_durable_flush_epoch(&self, flush_epoch: u64) {
+        debug_assert_ne!(flush_epoch, 0);
+        let prev = self.durable_flush_epoch.swap(flush_epoch, Relaxed);
+        debug_assert_eq!(prev, 0);
+    }
+
+    #[inline]
+    fn get_durable_flush_epoch(&self) -> Option<NonZeroU64> {
+        NonZeroU64::new(self.durable_flush_epoch.load(Acquire))
+    }
+}
+
+impl PersistedIndexInterface for MemoryIndex {
+    #[inline]
+    async fn scan<F: FnMut(&VersionRecord<'_>) + Send>(
+        &self,
+        _start: Bound<&[u8]>,
+        _end: Bound<&[u8]>,
+        _limit: usize,
+        _readahead: usize,
+        _visitor: F,
+    ) -> Result<(), Error> {
+        match *self {}
+    }
+
+    #[inline]
+    fn may_contain_sync(&self, _key: &[u8]) -> bool {
+        match *self {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Metadata, MonotonicU64, ShutdownPolicy};
+
+    #[tokio::test]
+    async fn crash() {
+        let storage = MemoryStorage::<MonotonicU64>::default();
+        let database = Database::with_persistence_layer(
+            MemoryPersistence::with_storage(storage.clone()),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.create(&[1, 2], None).await.unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        let durable_log_len = storage.durable_log_len();
+        let flush_epoch = storage.flush_epoch();
+        assert_ne!(durable_log_len, 0);
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.delete(&[1], None).await.unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert_eq!(storage.flush_epoch(), flush_epoch);
+        let instant = database.sequencer().now(Relaxed);
+        drop(transaction);
+        drop(database);
+
+        // The log records of the uncommitted transaction are lost.
+        assert_ne!(storage.crash(), 0);
+        assert_eq!(storage.durable_log_len(), durable_log_len);
+
+        let database = Database::with_persistence_layer(
+            MemoryPersistence::with_storage(storage.clone()),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(database.sequencer().now(Relaxed) <= instant);
+        let snapshot = database.snapshot();
+        assert!(database
+            .access_controller()
+            .read(1, &snapshot, None)
+            .await
+            .unwrap());
+        assert!(database
+            .access_controller()
+            .read(2, &snapshot, None)
+            .await
+            .unwrap());
+        drop(snapshot);
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        journal.delete(&[1], None).await.unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+        drop(database);
+
+        let database =
+            Database::with_persistence_layer(MemoryPersistence::with_storage(storage), None, None)
+                .await
+                .unwrap();
+        let snapshot = database.snapshot();
+        assert!(!database
+            .access_controller()
+            .read(1, &snapshot, None)
+            .await
+            .unwrap());
+        assert!(database
+            .access_controller()
+            .read(2, &snapshot, None)
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn key_value() {
+        let storage = MemoryStorage::<MonotonicU64>::default();
+        let database = Database::with_persistence_layer(
+            MemoryPersistence::with_storage(storage.clone()),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("kv".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert!(container
+            .insert(b"1", b"one", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        // The update is not committed, therefore it is lost after a crash.
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .update(b"1", b"two", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        drop(transaction);
+        drop(database);
+        assert_ne!(storage.crash(), 0);
+
+        let database =
+            Database::with_persistence_layer(MemoryPersistence::with_storage(storage), None, None)
+                .await
+                .unwrap();
+        let snapshot = database.snapshot();
+        let container = database.get_container("kv", &snapshot).await.unwrap();
+        assert_eq!(
+            container.get(b"1", &snapshot, None).await,
+            Ok(Some(b"one".to_vec()))
+        );
+    }
+
+    #[tokio::test]
+    async fn shutdown() {
+        let persistence_layer = MemoryPersistence::<MonotonicU64>::default();
+        let storage = persistence_layer.storage().clone();
+        let database = Database::with_persistence_layer(persistence_layer, None, None)
+            .await
+            .unwrap();
+        let instant = database.transaction().commit().await.unwrap();
+        assert!(database.backup(false, None, None).await.is_err());
+        assert!(database.shutdown(ShutdownPolicy::Wait, None).await.is_ok());
+        assert_eq!(storage.crash(), 0);
+
+        let database = Database::with_persistence_layer(
+            MemoryPersistence::with_storage(storage.snapshot()),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(database.sequencer().now(Relaxed) >= instant);
+    }
+}
@@ -0,0 +1,416 @@
+// SPDX-FileCopyrightText: 2021 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The module implements logical replication between [`Database`] instances.
+
+use super::utils::Instant;
+use super::{
+    Change, ChangeBatch, ChangeStream, Database, Error, Journal, Metadata, PersistenceLayer,
+    Sequencer, Snapshot,
+};
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::num::NonZeroU32;
+use std::sync::{Arc, Mutex};
+
+/// [`Transport`] carries [`ChangeBatch`] instances from a [`Leader`] to a [`Follower`].
+///
+/// A [`Transport`] must deliver [`ChangeBatch`] instances in the order they were sent.
+pub trait Transport<S: Sequencer>: Debug + Send + Sync {
+    /// Sends a [`ChangeBatch`] to the [`Follower`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the [`ChangeBatch`] could not be sent.
+    fn send(&self, change_batch: &ChangeBatch<S>) -> Result<(), Error>;
+
+    /// Receives a [`ChangeBatch`] sent by the [`Leader`].
+    ///
+    /// Returns `None` if no [`ChangeBatch`] has arrived.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the [`ChangeBatch`] could not be received.
+    fn receive(&self) -> Result<Option<ChangeBatch<S>>, Error>;
+}
+
+/// [`ChannelTransport`] is an in-process [`Transport`].
+#[derive(Debug, Default)]
+pub struct ChannelTransport<S: Sequencer> {
+    /// [`ChangeBatch`] instances that have yet to be received.
+    queue: Mutex<VecDeque<ChangeBatch<S>>>,
+}
+
+/// [`Leader`] ships committed changes of a [`Database`] to a [`Follower`].
+#[derive(Debug)]
+pub struct Leader<'d, S: Sequencer, P: PersistenceLayer<S>, T: Transport<S>> {
+    /// The [`ChangeStream`] of the leader [`Database`].
+    change_stream: ChangeStream<'d, S, P>,
+
+    /// The [`Transport`] to the [`Follower`].
+    transport: T,
+
+    /// The [`ChangeBatch`] that could not be sent.
+    unsent: Option<Arc<ChangeBatch<S>>>,
+}
+
+/// [`Follower`] applies changes shipped by a [`Leader`] to a [`Database`].
+///
+/// Each [`ChangeBatch`] is applied to the follower [`Database`] in a single
+/// [`Transaction`](super::Transaction).
+#[derive(Debug)]
+pub struct Follower<'d, S: Sequencer, P: PersistenceLayer<S>, T: Transport<S>> {
+    /// The follower [`Database`].
+    database: &'d Database<S, P>,
+
+    /// The [`Transport`] from the [`Leader`].
+    transport: T,
+
+    /// The [`ChangeBatch`] that could not be applied.
+    unapplied: Option<ChangeBatch<S>>,
+
+    /// The commit instant of the last applied [`ChangeBatch`] in the leader [`Database`].
+    replicated_instant: Option<S::Instant>,
+
+    /// The [`Snapshot`] of the follower [`Database`] right after the last [`ChangeBatch`] was
+    /// applied.
+    snapshot: Snapshot<'d, 'd, 'd, S>,
+}
+
+impl<S: Sequencer> Transport<S> for ChannelTransport<S> {
+    #[inline]
+    fn send(&self, change_batch: &ChangeBatch<S>) -> Result<(), Error> {
+        self.queue
+            .lock()
+            .map_err(|_| Error::UnexpectedState)?
+            .push_back(change_batch.clone());
+        Ok(())
+    }
+
+    #[inline]
+    fn receive(&self) -> Result<Option<ChangeBatch<S>>, Error> {
+        Ok(self
+            .queue
+            .lock()
+            .map_err(|_| Error::UnexpectedState)?
+            .pop_front())
+    }
+}
+
+impl<S: Sequencer, T: Transport<S>> Transport<S> for Arc<T> {
+    #[inline]
+    fn send(&self, change_batch: &ChangeBatch<S>) -> Result<(), Error> {
+        self.as_ref().send(change_batch)
+    }
+
+    #[inline]
+    fn receive(&self) -> Result<Option<ChangeBatch<S>>, Error> {
+        self.as_ref().receive()
+    }
+}
+
+impl<'d, S: Sequencer, P: PersistenceLayer<S>, T: Transport<S>> Leader<'d, S, P, T> {
+    /// Creates a new [`Leader`].
+    ///
+    /// Changes received by the [`ChangeStream`] are shipped via the [`Transport`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{ChannelTransport, Database, Leader};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("leader")).await.unwrap();
+    ///     let leader = Leader::new(database.watch(None).unwrap(), ChannelTransport::default());
+    /// };
+    /// ```
+    #[inline]
+    pub fn new(change_stream: ChangeStream<'d, S, P>, transport: T) -> Leader<'d, S, P, T> {
+        Leader {
+            change_stream,
+            transport,
+            unsent: None,
+        }
+    }
+
+    /// Ships the next committed [`ChangeBatch`] to the [`Follower`].
+    ///
+    /// Returns the commit instant of the shipped [`ChangeBatch`]. If the [`ChangeBatch`] could not
+    /// be sent, it is sent again when the method is invoked next time.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if no changes were committed until the deadline, or the
+    /// [`Transport`] failed to send the [`ChangeBatch`].
+    #[inline]
+    pub async fn ship(&mut self, deadline: Option<Instant>) -> Result<S::Instant, Error> {
+        let change_batch = if let Some(change_batch) = self.unsent.take() {
+            change_batch
+        } else {
+            self.change_stream.next(deadline).await?
+        };
+        if let Err(error) = self.transport.send(&change_batch) {
+            self.unsent.replace(change_batch);
+            return Err(error);
+        }
+        Ok(change_batch.commit_instant)
+    }
+
+    /// Returns a reference to the [`Transport`].
+    #[inline]
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<'d, S: Sequencer, P: PersistenceLayer<S>, T: Transport<S>> Follower<'d, S, P, T> {
+    /// Creates a new [`Follower`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{ChannelTransport, Database, Follower};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("follower")).await.unwrap();
+    ///     let follower = Follower::new(&database, ChannelTransport::default());
+    /// };
+    /// ```
+    #[inline]
+    pub fn new(database: &'d Database<S, P>, transport: T) -> Follower<'d, S, P, T> {
+        Follower {
+            database,
+            transport,
+            unapplied: None,
+            replicated_instant: None,
+            snapshot: database.snapshot(),
+        }
+    }
+
+    /// Applies the next [`ChangeBatch`] received from the [`Leader`].
+    ///
+    /// Returns the commit instant of the last applied [`ChangeBatch`] in the leader [`Database`],
+    /// or `None` if no [`ChangeBatch`] has been received. [`ChangeBatch`] instances that were
+    /// already applied are ignored, and a [`ChangeBatch`] that could not be applied is retried
+    /// when the method is invoked next time.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the [`Transport`] failed to receive a [`ChangeBatch`], or the
+    /// [`ChangeBatch`] could not be applied.
+    #[inline]
+    pub async fn apply(&mut self, deadline: Option<Instant>) -> Result<Option<S::Instant>, Error> {
+        let change_batch = if let Some(change_batch) = self.unapplied.take() {
+            change_batch
+        } else if let Some(change_batch) = self.transport.receive()? {
+            change_batch
+        } else {
+            return Ok(self.replicated_instant);
+        };
+        if self
+            .replicated_instant
+            .is_some_and(|i| change_batch.commit_instant <= i)
+        {
+            return Ok(self.replicated_instant);
+        }
+        match self.apply_change_batch(&change_batch, deadline).await {
+            Ok(local_instant) => {
+                if let Some(snapshot) = self.database.snapshot_at(local_instant) {
+                    self.snapshot = snapshot;
+                }
+                self.replicated_instant.replace(change_batch.commit_instant);
+                Ok(self.replicated_instant)
+            }
+            Err(error) => {
+                self.unapplied.replace(change_batch);
+                Err(error)
+            }
+        }
+    }
+
+    /// Returns the commit instant of the last applied [`ChangeBatch`] in the leader
+    /// [`Database`].
+    #[inline]
+    pub fn replicated_instant(&self) -> Option<S::Instant> {
+        self.replicated_instant
+    }
+
+    /// Returns a [`Snapshot`] of the follower [`Database`] that reflects every [`ChangeBatch`]
+    /// applied so far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{ChannelTransport, Database, Follower};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("follower_snapshot")).await.unwrap();
+    ///     let follower = Follower::new(&database, ChannelTransport::default());
+    ///     let snapshot = follower.snapshot();
+    ///     let container = database.get_container("hello", &snapshot).await;
+    /// };
+    /// ```
+    #[inline]
+    pub fn snapshot(&self) -> Snapshot<'d, 'd, 'd, S> {
+        self.snapshot.clone()
+    }
+
+    /// Applies the [`ChangeBatch`] in a single transaction, and returns the commit instant.
+    async fn apply_change_batch(
+        &self,
+        change_batch: &ChangeBatch<S>,
+        deadline: Option<Instant>,
+    ) -> Result<S::Instant, Error> {
+        let transaction = self.database.transaction();
+        let mut journal = transaction.journal();
+        for change in &change_batch.changes {
+            self.apply_change(change, &mut journal, deadline).await?;
+        }
+        let _: NonZeroU32 = journal.submit();
+        transaction.commit().await
+    }
+
+    /// Applies a [`Change`] with the [`Journal`].
+    async fn apply_change(
+        &self,
+        change: &Change,
+        journal: &mut Journal<'d, '_, S, P>,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        let container = if let Some(container) = self
+            .database
+            .journal_container(&change.container, journal, deadline)
+            .await?
+        {
+            container
+        } else {
+            self.database
+                .create_container(
+                    change.container.to_string(),
+                    Metadata::default(),
+                    journal,
+                    deadline,
+                )
+                .await?
+        };
+        match (change.old_value.as_ref(), change.new_value.as_ref()) {
+            (None, Some(new_value)) => {
+                container
+                    .insert(&change.key, new_value, journal, deadline)
+                    .await
+            }
+            (Some(_), Some(new_value)) => {
+                container
+                    .update(&change.key, new_value, journal, deadline)
+                    .await
+            }
+            (Some(_), None) => container.delete(&change.key, journal, deadline).await,
+            (None, None) => container.truncate(journal, deadline).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::Path;
+    use tokio::fs::remove_dir_all;
+
+    #[tokio::test]
+    async fn replicate() {
+        const DIR_LEADER: &str = "replication_replicate_test_leader";
+        const DIR_FOLLOWER: &str = "replication_replicate_test_follower";
+        let path_leader = Path::new(DIR_LEADER);
+        let path_follower = Path::new(DIR_FOLLOWER);
+        let leader_database = Database::with_path(path_leader).await.unwrap();
+        let follower_database = Database::with_path(path_follower).await.unwrap();
+
+        let transport = Arc::new(ChannelTransport::default());
+        let mut leader = Leader::new(leader_database.watch(None).unwrap(), transport.clone());
+        let mut follower = Follower::new(&follower_database, transport.clone());
+        assert_eq!(follower.apply(None).await, Ok(None));
+
+        let transaction = leader_database.transaction();
+        let mut journal = transaction.journal();
+        let container = leader_database
+            .create_container(
+                "replicated".to_string(),
+                Metadata::default(),
+                &mut journal,
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(container
+            .insert(b"1", b"1", &mut journal, None)
+            .await
+            .is_ok());
+        assert!(container
+            .insert(b"2", b"2", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        let first_instant = transaction.commit().await.unwrap();
+
+        let transaction = leader_database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .update(b"1", b"3", &mut journal, None)
+            .await
+            .is_ok());
+        assert!(container.delete(b"2", &mut journal, None).await.is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        let second_instant = transaction.commit().await.unwrap();
+
+        assert_eq!(leader.ship(None).await, Ok(first_instant));
+        assert_eq!(follower.apply(None).await, Ok(Some(first_instant)));
+        let first_snapshot = follower.snapshot();
+        assert_eq!(leader.ship(None).await, Ok(second_instant));
+        assert_eq!(follower.apply(None).await, Ok(Some(second_instant)));
+        assert_eq!(follower.replicated_instant(), Some(second_instant));
+
+        // Change batches that were already applied are ignored.
+        let duplicate = ChangeBatch {
+            commit_instant: first_instant,
+            changes: Vec::new(),
+        };
+        assert!(transport.send(&duplicate).is_ok());
+        assert_eq!(follower.apply(None).await, Ok(Some(second_instant)));
+
+        let second_snapshot = follower.snapshot();
+        let follower_container = follower_database
+            .get_container("replicated", &second_snapshot)
+            .await
+            .unwrap();
+        assert_eq!(
+            follower_container.get(b"1", &first_snapshot, None).await,
+            Ok(Some(b"1".to_vec()))
+        );
+        assert_eq!(
+            follower_container.get(b"2", &first_snapshot, None).await,
+            Ok(Some(b"2".to_vec()))
+        );
+        assert_eq!(
+            follower_container.get(b"1", &second_snapshot, None).await,
+            Ok(Some(b"3".to_vec()))
+        );
+        assert_eq!(
+            follower_container.get(b"2", &second_snapshot, None).await,
+            Ok(None)
+        );
+
+        drop(first_snapshot);
+        drop(second_snapshot);
+        drop(container);
+        drop(follower);
+        drop(leader);
+        drop(follower_database);
+        drop(leader_database);
+        assert!(remove_dir_all(path_leader).await.is_ok());
+        assert!(remove_dir_all(path_follower).await.is_ok());
+    }
+}
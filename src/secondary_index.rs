@@ -0,0 +1,114 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! [`SecondaryIndex`] maps index keys derived from key-value pairs to their keys.
+
+use super::journal::Anchor as JournalAnchor;
+use super::{Container, PersistenceLayer, Sequencer};
+use scc::ebr;
+use std::fmt::{self, Debug};
+
+/// [`SecondaryIndex`] is a [`Container`] mapping index keys to keys of another [`Container`].
+///
+/// Each index entry is a key-value pair in the index [`Container`] with an empty value, and its
+/// key is `INDEX KEY|KEY|INDEX KEY LENGTH 32-bit` so that keys sharing the same index key are
+/// adjacent and ordered by the key.
+pub(super) struct SecondaryIndex<S: Sequencer, P: PersistenceLayer<S>> {
+    /// The [`Container`] storing index entries.
+    index: ebr::Shared<Container<S, P>>,
+
+    /// The function deriving the index key from a key-value pair.
+    extractor: Box<Extractor>,
+
+    /// The anchor of the journal that created the [`SecondaryIndex`].
+    creator: ebr::Shared<JournalAnchor<S>>,
+}
+
+/// The type of functions deriving index keys from key-value pairs.
+type Extractor = dyn Fn(&[u8], &[u8]) -> Option<Vec<u8>> + Send + Sync;
+
+/// The length of the index key length suffix of an index entry key.
+const INDEX_KEY_LEN_LEN: usize = 4;
+
+impl<S: Sequencer, P: PersistenceLayer<S>> SecondaryIndex<S, P> {
+    /// Creates a new [`SecondaryIndex`].
+    pub(super) fn new(
+        index: ebr::Shared<Container<S, P>>,
+        extractor: Box<Extractor>,
+        creator: ebr::Shared<JournalAnchor<S>>,
+    ) -> SecondaryIndex<S, P> {
+        SecondaryIndex {
+            index,
+            extractor,
+            creator,
+        }
+    }
+
+    /// Returns the [`Container`] storing index entries.
+    pub(super) fn index(&self) -> &Container<S, P> {
+        &self.index
+    }
+
+    /// Returns the index key of the key-value pair.
+    pub(super) fn index_key(&self, key: &[u8], value: &[u8]) -> Option<Vec<u8>> {
+        (self.extractor)(key, value)
+    }
+
+    /// Returns `true` if the journal or transaction that created the [`SecondaryIndex`] was
+    /// rolled back.
+    pub(super) fn is_discarded(&self) -> bool {
+        self.creator.is_rolled_back() || self.creator.eot_instant() == Some(S::Instant::default())
+    }
+
+    /// Returns the key of the index entry of the key associated with the index key.
+    pub(super) fn entry_key(index_key: &[u8], key: &[u8]) -> Vec<u8> {
+        let index_key_len = u32::try_from(index_key.len()).unwrap_or(u32::MAX);
+        let mut entry_key = Vec::with_capacity(index_key.len() + key.len() + INDEX_KEY_LEN_LEN);
+        entry_key.extend_from_slice(index_key);
+        entry_key.extend_from_slice(key);
+        entry_key.extend_from_slice(&index_key_len.to_be_bytes());
+        entry_key
+    }
+
+    /// Returns the key in the index entry key if the index entry belongs to the index key.
+    pub(super) fn key<'k>(entry_key: &'k [u8], index_key: &[u8]) -> Option<&'k [u8]> {
+        let (prefixed_key, index_key_len) =
+            entry_key.split_at_checked(entry_key.len().checked_sub(INDEX_KEY_LEN_LEN)?)?;
+        let index_key_len = u32::from_be_bytes(index_key_len.try_into().ok()?);
+        if usize::try_from(index_key_len).ok()? != index_key.len() {
+            return None;
+        }
+        prefixed_key.strip_prefix(index_key)
+    }
+}
+
+impl<S: Sequencer, P: PersistenceLayer<S>> Debug for SecondaryIndex<S, P> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecondaryIndex")
+            .field("index", &self.index)
+            .field("creator", &self.creator)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FileIO, MonotonicU64};
+
+    type Index = SecondaryIndex<MonotonicU64, FileIO<MonotonicU64>>;
+
+    #[test]
+    fn entry_key() {
+        let entry_key = Index::entry_key(b"ab", b"c");
+        assert_eq!(Index::key(&entry_key, b"ab"), Some(b"c".as_slice()));
+        assert!(Index::key(&entry_key, b"a").is_none());
+        assert_eq!(
+            Index::key(&Index::entry_key(b"", b""), b""),
+            Some(b"".as_slice())
+        );
+        assert!(Index::key(b"ab", b"").is_none());
+    }
+}
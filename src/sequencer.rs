@@ -10,6 +10,12 @@
 mod monotonic_u64;
 pub use monotonic_u64::MonotonicU64;
 
+mod remote;
+pub use remote::{RemoteSequencer, RemoteTracker, TimestampOracle};
+
+mod sharded_u64;
+pub use sharded_u64::{ShardedTracker, ShardedU64};
+
 use std::fmt::Debug;
 use std::panic::UnwindSafe;
 use std::sync::atomic::Ordering;
@@ -57,6 +63,18 @@ pub trait Sequencer: 'static + Debug + Default + Send + Sync + Unpin + UnwindSaf
     /// visible to all the current and future readers.
     fn min(&self, order: Ordering) -> Self::Instant;
 
+    /// Returns the latest [`Instant`](Sequencer::Instant) that [`min`](Sequencer::min) has
+    /// computed, or a lower bound of it.
+    ///
+    /// The value may lag behind [`min`](Sequencer::min), however it is always safe to discard
+    /// database object versions that are only visible to instants older than it. Implementations
+    /// should return it without scanning tracked instants; the default implementation calls
+    /// [`min`](Sequencer::min).
+    #[inline]
+    fn cached_min(&self, order: Ordering) -> Self::Instant {
+        self.min(order)
+    }
+
     /// Gets the current [`Instant`](Sequencer::Instant).
     fn now(&self, order: Ordering) -> Self::Instant;
 
@@ -64,6 +82,19 @@ pub trait Sequencer: 'static + Debug + Default + Send + Sync + Unpin + UnwindSaf
     /// [`Tracker`](Sequencer::Tracker).
     fn track(&self, order: Ordering) -> Self::Tracker<'_>;
 
+    /// Tracks the specified past [`Instant`](Sequencer::Instant) value by wrapping it in a
+    /// [`Tracker`](Sequencer::Tracker).
+    ///
+    /// Returns `None` if the [`Instant`](Sequencer::Instant) is newer than the current one, or
+    /// older than any [`Instant`](Sequencer::Instant) that has been returned by
+    /// [`min`](Sequencer::min), since database objects that are only visible to the
+    /// [`Instant`](Sequencer::Instant) may have been garbage collected. The default
+    /// implementation does not support tracking past instants, and always returns `None`.
+    #[inline]
+    fn track_at(&self, _instant: Self::Instant, _order: Ordering) -> Option<Self::Tracker<'_>> {
+        None
+    }
+
     /// Updates the current logical [`Instant`](Sequencer::Instant) value.
     ///
     /// It tries to replace the current [`Instant`](Sequencer::Instant) value with the given one,
@@ -5,11 +5,12 @@
 //! [`MonotonicU64`] [`Sequencer`] implementation.
 
 use super::{Sequencer, ToInstant};
-use crate::utils;
-use scc::Queue;
+use crate::{sync, utils};
+use scc::{ebr::Guard, Queue};
 use std::mem::transmute;
 use std::sync::atomic::AtomicU64;
 use std::sync::atomic::Ordering::{self, Acquire, Relaxed};
+use std::sync::{Mutex, TryLockError};
 
 /// [`MonotonicU64`] implements [`Sequencer`] on top of a single `u64` atomic counter.
 ///
@@ -18,13 +19,36 @@ use std::sync::atomic::Ordering::{self, Acquire, Relaxed};
 #[derive(Debug)]
 pub struct MonotonicU64 {
     /// The current logical clock value.
-    clock: AtomicU64,
+    clock: sync::AtomicU64,
 
+    /// The instants in use.
+    registry: InstantRegistry,
+}
+
+/// [`InstantRegistry`] keeps track of `u64` instants in use.
+#[derive(Debug)]
+pub(super) struct InstantRegistry {
     /// The list of tracked entries spread over thread-local queues.
     ///
     /// A single [`EntryContainer`] can be shared among multiple threads because of hash conflicts
     /// or too many threads having been spawned.
     sharded_entry_list: Vec<EntryContainer>,
+
+    /// The list of tracked entries of past instants.
+    ///
+    /// Entries are not sorted by the instant.
+    past_entry_list: EntryContainer,
+
+    /// The largest instant ever returned by [`Sequencer::min`].
+    ///
+    /// The [`Mutex`] serializes [`Sequencer::min`] and [`Sequencer::track_at`] in order for a
+    /// past instant not to be tracked after [`Sequencer::min`] has returned a newer instant.
+    min_watermark: Mutex<u64>,
+
+    /// A copy of the value in `min_watermark` that can be read without acquiring the lock.
+    ///
+    /// Every tracked instant, including past instants, is equal to or greater than it.
+    cached_min: AtomicU64,
 }
 
 /// [`U64Tracker`] has a reference to a tracking entry.
@@ -54,26 +78,101 @@ impl Sequencer for MonotonicU64 {
 
     #[inline]
     fn min(&self, _order: Ordering) -> u64 {
-        let mut min = self.now(Acquire);
+        self.registry.min(self.now(Acquire))
+    }
+
+    #[inline]
+    fn cached_min(&self, _order: Ordering) -> u64 {
+        self.registry.cached_min()
+    }
+
+    #[inline]
+    fn now(&self, order: Ordering) -> Self::Instant {
+        self.clock.load(order)
+    }
+
+    #[inline]
+    fn track(&self, order: Ordering) -> Self::Tracker<'_> {
+        self.registry.track(|| self.now(order))
+    }
+
+    #[inline]
+    fn track_at(&self, instant: u64, order: Ordering) -> Option<Self::Tracker<'_>> {
+        self.registry.track_at(instant, self.now(order))
+    }
+
+    #[inline]
+    fn update(
+        &self,
+        new_value: Self::Instant,
+        order: Ordering,
+    ) -> Result<Self::Instant, Self::Instant> {
+        let mut current = self.clock.load(Relaxed);
+        loop {
+            if current >= new_value {
+                return Err(current);
+            }
+            match self
+                .clock
+                .compare_exchange(current, new_value, order, Relaxed)
+            {
+                Ok(_) => return Ok(new_value),
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    #[inline]
+    fn advance(&self, order: Ordering) -> Self::Instant {
+        self.clock.fetch_add(1, order) + 1
+    }
+}
+
+impl InstantRegistry {
+    /// Returns the oldest instant in use, or `now` if no instants are in use.
+    pub(super) fn min(&self, now: u64) -> u64 {
+        let mut min = now;
         for entry_list in &self.sharded_entry_list {
             while let Ok(Some(_)) = entry_list.0.pop_if(|e| e.ref_cnt.load(Relaxed) == 0) {}
             min = entry_list
                 .0
                 .peek_with(|e| e.map_or(min, |t| t.instant.min(min)));
         }
+        let mut min_watermark = match self.min_watermark.try_lock() {
+            Ok(min_watermark) => min_watermark,
+            Err(TryLockError::WouldBlock) => {
+                // Another thread is scanning past entries; past entries are never older than the
+                // cached watermark.
+                return self.cached_min.load(Relaxed).min(min);
+            }
+            Err(TryLockError::Poisoned(_)) => return min,
+        };
+        while let Ok(Some(_)) = self
+            .past_entry_list
+            .0
+            .pop_if(|e| e.ref_cnt.load(Relaxed) == 0)
+        {}
+        min = self
+            .past_entry_list
+            .0
+            .iter(&Guard::new())
+            .filter(|e| e.ref_cnt.load(Relaxed) != 0)
+            .fold(min, |min, e| e.instant.min(min));
+        *min_watermark = min.max(*min_watermark);
+        self.cached_min.store(*min_watermark, Relaxed);
         min
     }
 
-    #[inline]
-    fn now(&self, order: Ordering) -> Self::Instant {
-        self.clock.load(order)
+    /// Returns the instant that the last [`InstantRegistry::min`] call has computed.
+    pub(super) fn cached_min(&self) -> u64 {
+        self.cached_min.load(Acquire)
     }
 
-    #[inline]
-    fn track(&self, order: Ordering) -> Self::Tracker<'_> {
+    /// Tracks the current instant derived from `now`.
+    pub(super) fn track<'s, F: Fn() -> u64>(&'s self, now: F) -> U64Tracker<'s> {
         let shard_id = utils::shard_id() % self.sharded_entry_list.len();
         loop {
-            let candidate = self.now(order);
+            let candidate = now();
             let mut reuse: Option<&Entry> = None;
             match self.sharded_entry_list[shard_id].0.push_if(
                 Entry {
@@ -94,7 +193,8 @@ impl Sequencer for MonotonicU64 {
                                 .is_ok()
                             {
                                 // Safety: the entry is ref-counted.
-                                let prolonged_entry_ref = unsafe { transmute::<&Entry, _>(&**e) };
+                                let prolonged_entry_ref =
+                                    unsafe { transmute::<&Entry, &'s Entry>(&**e) };
                                 reuse.replace(prolonged_entry_ref);
                                 return false;
                             }
@@ -108,7 +208,8 @@ impl Sequencer for MonotonicU64 {
                 Ok(new_entry) => {
                     debug_assert!(reuse.is_none());
                     // Safety: the entry is ref-counted.
-                    let prolonged_entry_ref = unsafe { transmute::<&Entry, _>(&**new_entry) };
+                    let prolonged_entry_ref =
+                        unsafe { transmute::<&Entry, &'s Entry>(&**new_entry) };
                     return U64Tracker {
                         entry: prolonged_entry_ref,
                     };
@@ -122,54 +223,62 @@ impl Sequencer for MonotonicU64 {
         }
     }
 
-    #[inline]
-    fn update(
-        &self,
-        new_value: Self::Instant,
-        order: Ordering,
-    ) -> Result<Self::Instant, Self::Instant> {
-        let current = self.clock.load(Relaxed);
-        loop {
-            if current >= new_value {
-                return Err(current);
-            }
-            if self
-                .clock
-                .compare_exchange(current, new_value, order, Relaxed)
-                .is_ok()
-            {
-                return Ok(new_value);
-            }
+    /// Tracks the specified past instant if it is not newer than `now`.
+    pub(super) fn track_at<'s>(&'s self, instant: u64, now: u64) -> Option<U64Tracker<'s>> {
+        if instant > now {
+            return None;
+        }
+        let min_watermark = self.min_watermark.lock().ok()?;
+        if instant < *min_watermark {
+            return None;
         }
+        let new_entry = self.past_entry_list.0.push(Entry {
+            instant,
+            ref_cnt: AtomicU64::new(1),
+        });
+        drop(min_watermark);
+
+        // Safety: the entry is ref-counted.
+        let prolonged_entry_ref = unsafe { transmute::<&Entry, &'s Entry>(&**new_entry) };
+        Some(U64Tracker {
+            entry: prolonged_entry_ref,
+        })
     }
+}
 
+impl Default for MonotonicU64 {
     #[inline]
-    fn advance(&self, order: Ordering) -> Self::Instant {
-        self.clock.fetch_add(1, order) + 1
+    fn default() -> Self {
+        MonotonicU64 {
+            // Starts from `1` in order to avoid using `0`.
+            clock: sync::AtomicU64::new(1),
+            registry: InstantRegistry::default(),
+        }
     }
 }
 
-impl Default for MonotonicU64 {
+impl Default for InstantRegistry {
     #[inline]
     fn default() -> Self {
         let num_shards = utils::advise_num_shards();
         let mut sharded_entry_list = Vec::with_capacity(num_shards);
         sharded_entry_list.resize_with(num_shards, EntryContainer::default);
-        MonotonicU64 {
-            // Starts from `1` in order to avoid using `0`.
-            clock: AtomicU64::new(1),
+        InstantRegistry {
             sharded_entry_list,
+            past_entry_list: EntryContainer::default(),
+            min_watermark: Mutex::new(0),
+            cached_min: AtomicU64::new(0),
         }
     }
 }
 
-impl<'s> U64Tracker<'s> {
+impl U64Tracker<'_> {
     fn entry(&self) -> &Entry {
         self.entry
     }
 }
 
-impl<'s> Clone for U64Tracker<'s> {
+impl Clone for U64Tracker<'_> {
     #[inline]
     fn clone(&self) -> Self {
         let prev = self.entry().ref_cnt.fetch_add(1, Relaxed);
@@ -178,7 +287,7 @@ impl<'s> Clone for U64Tracker<'s> {
     }
 }
 
-impl<'s> Drop for U64Tracker<'s> {
+impl Drop for U64Tracker<'_> {
     #[inline]
     fn drop(&mut self) {
         let prev = self.entry().ref_cnt.fetch_sub(1, Relaxed);
@@ -186,7 +295,7 @@ impl<'s> Drop for U64Tracker<'s> {
     }
 }
 
-impl<'s> ToInstant<MonotonicU64> for U64Tracker<'s> {
+impl ToInstant<MonotonicU64> for U64Tracker<'_> {
     #[inline]
     fn to_instant(&self) -> u64 {
         self.entry().instant
@@ -235,4 +344,43 @@ mod tests {
         }
         assert_eq!(atomic_counter.min(Acquire), atomic_counter.now(Acquire));
     }
+
+    #[test]
+    fn track_at() {
+        let atomic_counter = MonotonicU64::default();
+        let first = atomic_counter.advance(Release);
+        let second = atomic_counter.advance(Release);
+        assert!(atomic_counter.track_at(second + 1, Acquire).is_none());
+
+        let tracker = atomic_counter.track_at(first, Acquire).unwrap();
+        assert_eq!(tracker.to_instant(), first);
+        assert_eq!(atomic_counter.min(Acquire), first);
+        let tracker_clone = tracker.clone();
+        drop(tracker);
+        assert_eq!(atomic_counter.min(Acquire), first);
+        drop(tracker_clone);
+
+        assert_eq!(atomic_counter.min(Acquire), second);
+        assert!(atomic_counter.track_at(first, Acquire).is_none());
+        assert!(atomic_counter.track_at(second, Acquire).is_some());
+    }
+
+    #[test]
+    fn cached_min() {
+        let atomic_counter = MonotonicU64::default();
+        assert_eq!(atomic_counter.cached_min(Acquire), 0);
+        let first = atomic_counter.advance(Release);
+        let tracker = atomic_counter.track_at(first, Acquire).unwrap();
+        atomic_counter.advance(Release);
+        assert_eq!(atomic_counter.min(Acquire), first);
+        assert_eq!(atomic_counter.cached_min(Acquire), first);
+
+        drop(tracker);
+        assert_eq!(atomic_counter.cached_min(Acquire), first);
+        assert_eq!(atomic_counter.min(Acquire), atomic_counter.now(Acquire));
+        assert_eq!(
+            atomic_counter.cached_min(Acquire),
+            atomic_counter.now(Acquire)
+        );
+    }
 }
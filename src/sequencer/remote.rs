@@ -0,0 +1,235 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! [`RemoteSequencer`] [`Sequencer`] implementation.
+
+use super::monotonic_u64::U64Tracker;
+use super::{MonotonicU64, Sequencer, ToInstant};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::panic::UnwindSafe;
+use std::sync::atomic::Ordering::{self, Relaxed};
+use std::sync::Mutex;
+
+/// [`TimestampOracle`] is an external source of globally ordered timestamps, e.g., a timestamp
+/// oracle service shared by database nodes.
+///
+/// The [`Sequencer`] trait requires [`Default`], therefore the oracle is constructed by
+/// [`Default::default`] when the [`Database`](crate::Database) is created, and it should connect
+/// to the service on its own.
+pub trait TimestampOracle: 'static + Debug + Default + Send + Sync + Unpin + UnwindSafe {
+    /// Reserves `count` consecutive timestamps, and returns the first one.
+    ///
+    /// The returned range must not overlap any range that has been reserved before, and it must
+    /// be greater than any timestamp passed to [`TimestampOracle::observe`].
+    fn reserve(&self, count: u64) -> u64;
+
+    /// Notifies the oracle of a timestamp that the database has observed, e.g., the clock of
+    /// replicated or recovered transactions.
+    ///
+    /// Timestamps reserved afterwards must be greater than `timestamp`.
+    fn observe(&self, timestamp: u64);
+
+    /// Returns the number of timestamps reserved at once.
+    ///
+    /// Reserved timestamps are cached and handed out locally, therefore a larger batch reduces
+    /// round trips to the oracle at the cost of the commit clocks of different nodes being
+    /// ordered only at the granularity of batches. The default value is `1`.
+    #[inline]
+    fn batch_size(&self) -> u64 {
+        1
+    }
+}
+
+/// [`RemoteTracker`] tracks an instant of a [`RemoteSequencer`].
+#[derive(Debug)]
+pub struct RemoteTracker<'s, O: TimestampOracle>(U64Tracker<'s>, PhantomData<fn() -> O>);
+
+/// [`RemoteSequencer`] implements [`Sequencer`] on top of a [`TimestampOracle`].
+///
+/// New instants generated by [`Sequencer::advance`] are taken from timestamp ranges reserved from
+/// the oracle, and the local clock is maintained by a [`MonotonicU64`] that also tracks the
+/// instants in use. Advancing the clock is serialized on a [`Mutex`] protecting the cached
+/// timestamp range.
+#[derive(Debug, Default)]
+pub struct RemoteSequencer<O: TimestampOracle> {
+    /// The local clock that tracks instants in use.
+    local: MonotonicU64,
+
+    /// The oracle.
+    oracle: O,
+
+    /// The start and end of the reserved timestamp range that have not been handed out.
+    reserved: Mutex<(u64, u64)>,
+}
+
+impl<O: TimestampOracle> RemoteSequencer<O> {
+    /// Returns a reference to the [`TimestampOracle`].
+    #[inline]
+    pub fn oracle(&self) -> &O {
+        &self.oracle
+    }
+
+    /// Takes the next timestamp greater than `current` from the reserved range, or reserves a new
+    /// range.
+    fn next_timestamp(&self, current: u64) -> u64 {
+        let Ok(mut reserved) = self.reserved.lock() else {
+            return self.oracle.reserve(1);
+        };
+        if reserved.0 <= current {
+            reserved.0 = current + 1;
+        }
+        if reserved.0 >= reserved.1 {
+            let batch_size = self.oracle.batch_size().max(1);
+            let start = self.oracle.reserve(batch_size);
+            *reserved = (start.max(current + 1), start.saturating_add(batch_size));
+            if reserved.0 >= reserved.1 {
+                // The oracle is lagging behind, e.g., it was never notified of the clock of the
+                // recovered database.
+                self.oracle.observe(current);
+                let start = self.oracle.reserve(batch_size);
+                *reserved = (start, start.saturating_add(batch_size));
+            }
+        }
+        let timestamp = reserved.0;
+        reserved.0 += 1;
+        timestamp
+    }
+}
+
+impl<O: TimestampOracle> Sequencer for RemoteSequencer<O> {
+    type Instant = u64;
+    type Tracker<'s> = RemoteTracker<'s, O>;
+
+    #[inline]
+    fn min(&self, order: Ordering) -> u64 {
+        self.local.min(order)
+    }
+
+    #[inline]
+    fn cached_min(&self, order: Ordering) -> u64 {
+        self.local.cached_min(order)
+    }
+
+    #[inline]
+    fn now(&self, order: Ordering) -> u64 {
+        self.local.now(order)
+    }
+
+    #[inline]
+    fn track(&self, order: Ordering) -> Self::Tracker<'_> {
+        RemoteTracker(self.local.track(order), PhantomData)
+    }
+
+    #[inline]
+    fn track_at(&self, instant: u64, order: Ordering) -> Option<Self::Tracker<'_>> {
+        self.local
+            .track_at(instant, order)
+            .map(|t| RemoteTracker(t, PhantomData))
+    }
+
+    #[inline]
+    fn update(&self, new_sequence: u64, order: Ordering) -> Result<u64, u64> {
+        let result = self.local.update(new_sequence, order);
+        if result.is_ok() {
+            self.oracle.observe(new_sequence);
+        }
+        result
+    }
+
+    #[inline]
+    fn advance(&self, order: Ordering) -> u64 {
+        let mut current = self.local.now(Relaxed);
+        loop {
+            match self.local.update(self.next_timestamp(current), order) {
+                Ok(instant) => return instant,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+}
+
+impl<O: TimestampOracle> Clone for RemoteTracker<'_, O> {
+    #[inline]
+    fn clone(&self) -> Self {
+        RemoteTracker(self.0.clone(), PhantomData)
+    }
+}
+
+impl<O: TimestampOracle> ToInstant<RemoteSequencer<O>> for RemoteTracker<'_, O> {
+    #[inline]
+    fn to_instant(&self) -> u64 {
+        self.0.to_instant()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Database, FileIO};
+    use std::path::Path;
+    use std::sync::atomic::AtomicU64;
+    use std::sync::atomic::Ordering::{Acquire, Release};
+    use tokio::fs::remove_dir_all;
+
+    #[derive(Debug, Default)]
+    struct TestOracle {
+        next: AtomicU64,
+        num_reservations: AtomicU64,
+    }
+
+    impl TimestampOracle for TestOracle {
+        fn reserve(&self, count: u64) -> u64 {
+            self.num_reservations.fetch_add(1, Relaxed);
+            self.next.fetch_add(count, Relaxed) + 1
+        }
+
+        fn observe(&self, timestamp: u64) {
+            self.next.fetch_max(timestamp, Relaxed);
+        }
+
+        fn batch_size(&self) -> u64 {
+            4
+        }
+    }
+
+    #[test]
+    fn advance() {
+        let sequencer = RemoteSequencer::<TestOracle>::default();
+        let mut prev = sequencer.now(Acquire);
+        // The local clock starts from `1`, therefore `1` in the first batch is discarded.
+        for _ in 0..7 {
+            let instant = sequencer.advance(Release);
+            assert!(instant > prev);
+            assert_eq!(sequencer.now(Acquire), instant);
+            prev = instant;
+        }
+        assert_eq!(sequencer.oracle().num_reservations.load(Relaxed), 2);
+
+        assert_eq!(sequencer.update(prev + 10, Release), Ok(prev + 10));
+        assert_eq!(sequencer.update(prev, Release), Err(prev + 10));
+        assert!(sequencer.oracle().next.load(Relaxed) >= prev + 10);
+        assert_eq!(sequencer.advance(Release), prev + 11);
+
+        let tracker = sequencer.track(Acquire);
+        assert_eq!(tracker.to_instant(), prev + 11);
+        assert_eq!(sequencer.min(Acquire), prev + 11);
+    }
+
+    #[tokio::test]
+    async fn database() {
+        const DIR: &str = "remote_sequencer_database_test";
+        let path = Path::new(DIR);
+        let file_io = FileIO::<RemoteSequencer<TestOracle>>::with_path(path).unwrap();
+        let database = Database::with_persistence_layer(file_io, None, None)
+            .await
+            .unwrap();
+        let commit_instant_1 = database.transaction().commit().await.unwrap();
+        let commit_instant_2 = database.transaction().commit().await.unwrap();
+        assert!(commit_instant_1 < commit_instant_2);
+        drop(database);
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+}
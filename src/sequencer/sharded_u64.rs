@@ -0,0 +1,296 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! [`ShardedU64`] [`Sequencer`] implementation.
+
+use super::monotonic_u64::{InstantRegistry, U64Tracker};
+use super::{Sequencer, ToInstant};
+use crate::utils;
+use std::sync::atomic::Ordering::{self, Relaxed, SeqCst};
+use std::sync::atomic::{fence, AtomicU64};
+
+/// [`ShardedU64`] implements [`Sequencer`] on top of `u64` clocks spread over shards.
+///
+/// Each shard owns the instants that are congruent to the shard index modulo the number of
+/// shards, and [`Sequencer::advance`] only modifies the clock of the shard that the current
+/// thread is mapped to, so that threads committing transactions at a high rate do not contend
+/// for a single cache line as they do with [`MonotonicU64`](super::MonotonicU64).
+///
+/// The current instant is the largest instant among the shards, and a new instant is greater
+/// than that; therefore instants are unique and monotonically increasing, and transactions
+/// committed before a [`Snapshot`](crate::Snapshot) is taken are visible to it in the same way as
+/// with [`MonotonicU64`](super::MonotonicU64). The tradeoff is that reading the current instant,
+/// e.g., taking a [`Snapshot`](crate::Snapshot), and advancing the clock read the clocks of all
+/// the shards, and issue a memory fence; in addition, the clock may skip up to the number of
+/// shards instants at once.
+///
+/// # Examples
+///
+/// ```
+/// use sap_tsf::{Sequencer, ShardedU64};
+/// use std::sync::atomic::Ordering::{Acquire, Release};
+///
+/// let sequencer = ShardedU64::default();
+/// let first = sequencer.advance(Release);
+/// let second = sequencer.advance(Release);
+/// assert!(first < second);
+/// assert_eq!(sequencer.now(Acquire), second);
+/// ```
+#[derive(Debug)]
+pub struct ShardedU64 {
+    /// The clocks of the shards.
+    shards: Vec<Shard>,
+
+    /// The instants in use.
+    registry: InstantRegistry,
+}
+
+/// [`ShardedTracker`] tracks an instant of a [`ShardedU64`].
+#[derive(Debug)]
+pub struct ShardedTracker<'s>(U64Tracker<'s>);
+
+/// [`Shard`] is aligned to a typical size of cache lines.
+#[repr(align(64))]
+#[derive(Debug, Default)]
+struct Shard(AtomicU64);
+
+impl ShardedU64 {
+    /// Returns the smallest instant owned by the shard that is greater than `current`.
+    fn next_instant(&self, shard_id: usize, current: u64) -> u64 {
+        let num_shards = self.num_shards();
+        let remainder = current % num_shards;
+        let shard_id = shard_id as u64;
+        if remainder < shard_id {
+            current - remainder + shard_id
+        } else {
+            current - remainder + num_shards + shard_id
+        }
+    }
+
+    /// Returns the number of shards.
+    fn num_shards(&self) -> u64 {
+        self.shards.len() as u64
+    }
+
+    /// Returns the largest instant among the shards.
+    fn load_max(&self) -> u64 {
+        self.shards
+            .iter()
+            .fold(0, |max, shard| shard.0.load(Relaxed).max(max))
+    }
+}
+
+impl Sequencer for ShardedU64 {
+    type Instant = u64;
+    type Tracker<'s> = ShardedTracker<'s>;
+
+    #[inline]
+    fn min(&self, order: Ordering) -> u64 {
+        self.registry.min(self.now(order))
+    }
+
+    #[inline]
+    fn cached_min(&self, _order: Ordering) -> u64 {
+        self.registry.cached_min()
+    }
+
+    #[inline]
+    fn now(&self, _order: Ordering) -> u64 {
+        let now = self.load_max();
+
+        // Pairs with the fence in `advance`: a transaction that starts to commit after the
+        // instant was read will be given a greater instant.
+        fence(SeqCst);
+        now
+    }
+
+    #[inline]
+    fn track(&self, order: Ordering) -> Self::Tracker<'_> {
+        ShardedTracker(self.registry.track(|| self.now(order)))
+    }
+
+    #[inline]
+    fn track_at(&self, instant: u64, order: Ordering) -> Option<Self::Tracker<'_>> {
+        self.registry
+            .track_at(instant, self.now(order))
+            .map(ShardedTracker)
+    }
+
+    #[inline]
+    fn update(&self, new_value: u64, _order: Ordering) -> Result<u64, u64> {
+        let shard = &self.shards[utils::shard_id() % self.shards.len()];
+        loop {
+            fence(SeqCst);
+            let current = self.load_max();
+            if current >= new_value {
+                return Err(current);
+            }
+            let own = shard.0.load(Relaxed);
+            if shard
+                .0
+                .compare_exchange(own, new_value, SeqCst, Relaxed)
+                .is_ok()
+            {
+                return Ok(new_value);
+            }
+        }
+    }
+
+    #[inline]
+    fn advance(&self, _order: Ordering) -> u64 {
+        let shard_id = utils::shard_id() % self.shards.len();
+        let shard = &self.shards[shard_id];
+        loop {
+            // Pairs with the fence in `now`: the new instant is greater than any instant that was
+            // read before the caller started to commit the transaction.
+            fence(SeqCst);
+            let own = shard.0.load(Relaxed);
+            let instant = self.next_instant(shard_id, self.load_max());
+            if shard
+                .0
+                .compare_exchange(own, instant, SeqCst, Relaxed)
+                .is_ok()
+            {
+                return instant;
+            }
+        }
+    }
+}
+
+impl Default for ShardedU64 {
+    #[inline]
+    fn default() -> Self {
+        let num_shards = utils::advise_num_shards();
+        let mut shards = Vec::with_capacity(num_shards);
+        shards.resize_with(num_shards, Shard::default);
+
+        // Starts from `1` in order to avoid using `0`.
+        shards[0].0.store(1, Relaxed);
+        ShardedU64 {
+            shards,
+            registry: InstantRegistry::default(),
+        }
+    }
+}
+
+impl Clone for ShardedTracker<'_> {
+    #[inline]
+    fn clone(&self) -> Self {
+        ShardedTracker(self.0.clone())
+    }
+}
+
+impl ToInstant<ShardedU64> for ShardedTracker<'_> {
+    #[inline]
+    fn to_instant(&self) -> u64 {
+        self.0.to_instant()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Database, MemoryPersistence};
+    use std::collections::BTreeSet;
+    use std::sync::atomic::Ordering::{Acquire, Release};
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::Barrier;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 16)]
+    async fn sharded_counter() {
+        let sequencer = Arc::new(ShardedU64::default());
+        let instants = Arc::new(Mutex::new(BTreeSet::new()));
+        let num_tasks = 16;
+        let num_operations = 4096;
+        let mut task_handles = Vec::with_capacity(num_tasks);
+        let barrier = Arc::new(Barrier::new(num_tasks));
+        for _ in 0..num_tasks {
+            let sequencer_clone = sequencer.clone();
+            let instants_clone = instants.clone();
+            let barrier_clone = barrier.clone();
+            task_handles.push(tokio::spawn(async move {
+                barrier_clone.wait().await;
+                let mut advanced_instants = Vec::with_capacity(num_operations);
+                for _ in 0..num_operations {
+                    let current = sequencer_clone.now(Acquire);
+                    let advanced = sequencer_clone.advance(Release);
+                    assert!(current < advanced);
+                    assert!(advanced <= sequencer_clone.now(Acquire));
+                    advanced_instants.push(advanced);
+
+                    let tracker = sequencer_clone.track(Acquire);
+                    assert!(advanced <= tracker.to_instant());
+                    assert!(sequencer_clone.min(Relaxed) <= tracker.to_instant());
+                }
+                instants_clone.lock().unwrap().extend(advanced_instants);
+            }));
+        }
+        for r in futures::future::join_all(task_handles).await {
+            assert!(r.is_ok());
+        }
+        assert_eq!(instants.lock().unwrap().len(), num_tasks * num_operations);
+        assert_eq!(sequencer.min(Acquire), sequencer.now(Acquire));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn database() {
+        let database: Arc<Database<ShardedU64, MemoryPersistence<ShardedU64>>> = Arc::new(
+            Database::with_persistence_layer(MemoryPersistence::default(), None, None)
+                .await
+                .unwrap(),
+        );
+        let num_tasks = 4;
+        let mut task_handles = Vec::with_capacity(num_tasks);
+        for task_id in 0..num_tasks {
+            let database_clone = database.clone();
+            task_handles.push(tokio::spawn(async move {
+                for i in 0..64 {
+                    let object_id = (task_id * 64 + i) as u64;
+                    let transaction = database_clone.transaction();
+                    let mut journal = transaction.journal();
+                    assert!(journal.create(&[object_id], None).await.is_ok());
+                    assert_eq!(journal.submit().get(), 1);
+                    let commit_instant = transaction.commit().await.unwrap();
+                    assert!(commit_instant <= database_clone.sequencer().now(Acquire));
+
+                    // The transaction is visible to snapshots taken after it was committed.
+                    let snapshot = database_clone.snapshot();
+                    assert_eq!(
+                        database_clone
+                            .access_controller()
+                            .read(object_id, &snapshot, None)
+                            .await,
+                        Ok(true)
+                    );
+                }
+            }));
+        }
+        for r in futures::future::join_all(task_handles).await {
+            assert!(r.is_ok());
+        }
+    }
+
+    #[test]
+    fn update() {
+        let sequencer = ShardedU64::default();
+        let first = sequencer.advance(Release);
+        assert_eq!(sequencer.update(first, Release), Err(first));
+        assert_eq!(sequencer.update(first + 64, Release), Ok(first + 64));
+        assert_eq!(sequencer.now(Acquire), first + 64);
+        assert!(sequencer.advance(Release) > first + 64);
+    }
+
+    #[test]
+    fn next_instant() {
+        let sequencer = ShardedU64 {
+            shards: (0..4).map(|_| Shard::default()).collect(),
+            registry: InstantRegistry::default(),
+        };
+        assert_eq!(sequencer.next_instant(0, 1), 4);
+        assert_eq!(sequencer.next_instant(1, 1), 5);
+        assert_eq!(sequencer.next_instant(2, 1), 2);
+        assert_eq!(sequencer.next_instant(3, 7), 11);
+        assert_eq!(sequencer.next_instant(3, 6), 7);
+    }
+}
@@ -0,0 +1,204 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! [`Session`] provides read-your-writes consistency across transactions.
+
+use super::task_processor::Task;
+use super::utils::Instant;
+use super::{Database, Error, PersistenceLayer, Sequencer, Snapshot, Transaction};
+use std::future::poll_fn;
+use std::sync::atomic::Ordering::Acquire;
+use std::sync::Mutex;
+use std::task::Poll;
+use std::time::Duration;
+
+/// [`Session`] remembers the latest commit instant of the transactions committed in it, and
+/// hands out [`Snapshot`] instances that see the changes made by those transactions.
+///
+/// Commit instants learned elsewhere, e.g., from another [`Database`] sharing a
+/// [`RemoteSequencer`](super::RemoteSequencer), can be passed to [`Session::observe`]; the
+/// [`Session`] then waits for the clock of the [`Database`] to catch up before taking a
+/// [`Snapshot`].
+///
+/// # Examples
+///
+/// ```
+/// use sap_tsf::Database;
+/// use std::path::Path;
+///
+/// async {
+///     let database = Database::with_path(Path::new("session")).await.unwrap();
+///     let session = database.session();
+///     let commit_instant = session.commit(database.transaction()).await.unwrap();
+///     assert_eq!(session.last_commit_instant(), commit_instant);
+///     let snapshot = session.snapshot(None).await.unwrap();
+/// };
+/// ```
+#[derive(Debug)]
+pub struct Session<'d, S: Sequencer, P: PersistenceLayer<S>> {
+    /// The [`Database`] that the [`Session`] belongs to.
+    database: &'d Database<S, P>,
+
+    /// The latest commit instant that the [`Session`] has observed.
+    last_commit_instant: Mutex<S::Instant>,
+}
+
+/// The interval of checking the clock of the [`Database`] while waiting for it to catch up.
+const CLOCK_RECHECK_INTERVAL: Duration = Duration::from_millis(1);
+
+impl<'d, S: Sequencer, P: PersistenceLayer<S>> Session<'d, S, P> {
+    /// Commits the [`Transaction`], and remembers the commit instant.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the [`Transaction`] could not be committed.
+    #[inline]
+    pub async fn commit(&self, transaction: Transaction<'d, S, P>) -> Result<S::Instant, Error> {
+        let commit_instant = transaction.commit().await?;
+        self.observe(commit_instant);
+        Ok(commit_instant)
+    }
+
+    /// Remembers the commit instant so that [`Snapshot`] instances taken later see the changes
+    /// committed at the instant.
+    ///
+    /// Instants that are not newer than the latest one are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("session_observe")).await.unwrap();
+    ///     let session = database.session();
+    ///     let transaction = database.transaction();
+    ///     let commit_handle = transaction.commit_handle();
+    ///     let _: Result<_, _> = transaction.commit().await;
+    ///     if let Some(commit_instant) = commit_handle.commit_instant() {
+    ///         session.observe(commit_instant);
+    ///     }
+    /// };
+    /// ```
+    #[inline]
+    pub fn observe(&self, commit_instant: S::Instant) {
+        if let Ok(mut last_commit_instant) = self.last_commit_instant.lock() {
+            if *last_commit_instant < commit_instant {
+                *last_commit_instant = commit_instant;
+            }
+        }
+    }
+
+    /// Returns the latest commit instant that the [`Session`] has observed.
+    #[inline]
+    #[must_use]
+    pub fn last_commit_instant(&self) -> S::Instant {
+        self.last_commit_instant
+            .lock()
+            .map_or_else(|_| S::Instant::default(), |i| *i)
+    }
+
+    /// Returns a [`Snapshot`] that sees the changes committed at or before the latest commit
+    /// instant that the [`Session`] has observed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Timeout`] if the clock of the [`Database`] did not reach the commit
+    /// instant until the deadline.
+    #[inline]
+    pub async fn snapshot(
+        &self,
+        deadline: Option<Instant>,
+    ) -> Result<Snapshot<'d, 'd, 'd, S>, Error> {
+        let last_commit_instant = self.last_commit_instant();
+        poll_fn(|cx| {
+            if self.database.sequencer().now(Acquire) >= last_commit_instant {
+                return Poll::Ready(Ok(()));
+            }
+            let recheck = Instant::now() + CLOCK_RECHECK_INTERVAL;
+            if deadline.is_some_and(|d| d < Instant::now()) {
+                return Poll::Ready(Err(Error::Timeout));
+            } else if !self.database.task_processor().send_task(Task::WakeUp(
+                deadline.map_or(recheck, |d| d.min(recheck)),
+                cx.waker().clone(),
+            )) {
+                // The message channel is congested.
+                cx.waker().wake_by_ref();
+            }
+            Poll::Pending
+        })
+        .await?;
+        Ok(self.database.snapshot())
+    }
+
+    /// Creates a new [`Session`].
+    pub(super) fn new(database: &'d Database<S, P>) -> Session<'d, S, P> {
+        Session {
+            database,
+            last_commit_instant: Mutex::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Database, Error, Metadata, Sequencer};
+    use std::path::Path;
+    use std::sync::atomic::Ordering::Relaxed;
+    use std::time::{Duration, Instant};
+    use tokio::fs::remove_dir_all;
+
+    #[tokio::test]
+    async fn read_your_writes() {
+        const DIR: &str = "session_read_your_writes_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let session = database.session();
+        assert_eq!(session.last_commit_instant(), 0);
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("rw".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert!(container
+            .insert(b"1", b"1", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        let commit_instant = session.commit(transaction).await.unwrap();
+        assert_eq!(session.last_commit_instant(), commit_instant);
+        let snapshot = session.snapshot(None).await.unwrap();
+        assert_eq!(
+            container.get(b"1", &snapshot, None).await,
+            Ok(Some(b"1".to_vec()))
+        );
+        drop(snapshot);
+
+        // Older instants are ignored, and the session waits for the clock to catch up.
+        session.observe(commit_instant - 1);
+        assert_eq!(session.last_commit_instant(), commit_instant);
+        let future_instant = database.sequencer().now(Relaxed) + 2;
+        session.observe(future_instant);
+        let deadline = Instant::now() + Duration::from_millis(16);
+        assert_eq!(
+            session.snapshot(Some(deadline)).await.map(|_| ()),
+            Err(Error::Timeout)
+        );
+        let (snapshot, ()) = tokio::join!(session.snapshot(None), async {
+            for _ in 0..2 {
+                assert!(database.transaction().commit().await.is_ok());
+            }
+        });
+        assert!(snapshot.is_ok());
+        assert!(database.sequencer().now(Relaxed) >= future_instant);
+        drop(snapshot);
+
+        drop(container);
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+}
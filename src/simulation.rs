@@ -0,0 +1,449 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Deterministic simulation.
+//!
+//! [`Simulator`] is a single-threaded executor that polls tasks in an order derived from a seed,
+//! and advances a virtual clock whenever no tasks are runnable, so that interleavings of
+//! transactions, lock waits, and timeouts are reproducible from the seed. Log flushes are
+//! deterministic if the [`Database`](crate::Database) uses
+//! [`MemoryPersistence`](crate::MemoryPersistence).
+//!
+//! Lock waiters are woken up by the background task processor of the
+//! [`Database`](crate::Database), therefore [`Simulator::add_idle_check`] should be given
+//! [`Database::is_idle`](crate::Database::is_idle) so that the [`Simulator`] waits for background
+//! tasks before choosing the next task to run. Deadlines passed to the
+//! [`Database`](crate::Database) are still measured in real time, and
+//! [`SimulationContext::timeout`] should be used to time out in virtual time.
+
+use crate::Error;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Debug};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::yield_now;
+use std::time::Duration;
+
+/// [`Simulator`] drives tasks deterministically.
+///
+/// # Examples
+///
+/// ```
+/// use sap_tsf::simulation::Simulator;
+/// use std::time::Duration;
+///
+/// let mut simulator = Simulator::with_seed(7);
+/// let context = simulator.context();
+/// simulator.spawn(async move {
+///     context.sleep(Duration::from_secs(60)).await;
+/// });
+/// assert_eq!(simulator.run(), 0);
+/// assert_eq!(simulator.context().now(), Duration::from_secs(60));
+/// ```
+pub struct Simulator {
+    /// The seed.
+    seed: u64,
+
+    /// Tasks indexed by task identifiers.
+    tasks: Vec<Option<Task>>,
+
+    /// The state shared with tasks.
+    context: SimulationContext,
+
+    /// Identifiers of polled tasks in the order of being polled.
+    trace: Vec<usize>,
+
+    /// Functions that return `true` if no background work is pending.
+    idle_checks: Vec<Box<dyn Fn() -> bool>>,
+}
+
+/// [`SimulationContext`] provides tasks of a [`Simulator`] with the virtual clock and random
+/// numbers.
+#[derive(Clone, Debug)]
+pub struct SimulationContext(Arc<SharedState>);
+
+/// [`Task`] is a future spawned in a [`Simulator`].
+type Task = Pin<Box<dyn Future<Output = ()>>>;
+
+/// [`SharedState`] is shared among the [`Simulator`] and [`SimulationContext`] handles.
+#[derive(Debug)]
+struct SharedState {
+    /// Identifiers of runnable tasks.
+    runnable: Mutex<BTreeSet<usize>>,
+
+    /// Wakers of sleeping tasks indexed by the wake-up time and the registration order.
+    timers: Mutex<BTreeMap<(Duration, u64), Waker>>,
+
+    /// The virtual clock.
+    now: Mutex<Duration>,
+
+    /// The state of the pseudo-random number generator.
+    random_state: AtomicU64,
+
+    /// The number of registered timers.
+    num_timers: AtomicU64,
+}
+
+/// [`TaskWaker`] makes the task runnable when woken up.
+struct TaskWaker {
+    /// The identifier of the task.
+    task_id: usize,
+
+    /// The state shared with the [`Simulator`].
+    shared_state: Arc<SharedState>,
+}
+
+/// [`Sleep`] is a future that completes at a virtual time point.
+#[derive(Debug)]
+pub struct Sleep {
+    /// The [`SimulationContext`].
+    context: SimulationContext,
+
+    /// The virtual time point when the future completes.
+    wake_up_at: Duration,
+}
+
+/// [`Timeout`] is a future that fails with [`Error::Timeout`] unless the wrapped future completes
+/// before the virtual deadline.
+#[derive(Debug)]
+pub struct Timeout<F: Future> {
+    /// The wrapped future.
+    future: Pin<Box<F>>,
+
+    /// The deadline.
+    sleep: Sleep,
+}
+
+impl Simulator {
+    /// Creates a new [`Simulator`] with the specified seed.
+    #[inline]
+    #[must_use]
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            tasks: Vec::new(),
+            context: SimulationContext(Arc::new(SharedState {
+                runnable: Mutex::default(),
+                timers: Mutex::default(),
+                now: Mutex::default(),
+                // `0` is not a valid state of the generator.
+                random_state: AtomicU64::new(seed | 1 << 63),
+                num_timers: AtomicU64::new(0),
+            })),
+            trace: Vec::new(),
+            idle_checks: Vec::new(),
+        }
+    }
+
+    /// Returns the seed.
+    #[inline]
+    #[must_use]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns a [`SimulationContext`] of the [`Simulator`].
+    #[inline]
+    #[must_use]
+    pub fn context(&self) -> SimulationContext {
+        self.context.clone()
+    }
+
+    /// Spawns a task, and returns its identifier.
+    #[inline]
+    pub fn spawn<F: Future<Output = ()> + 'static>(&mut self, future: F) -> usize {
+        let task_id = self.tasks.len();
+        self.tasks.push(Some(Box::pin(future)));
+        if let Ok(mut runnable) = self.context.0.runnable.lock() {
+            runnable.insert(task_id);
+        }
+        task_id
+    }
+
+    /// Adds a function that returns `true` if no background work that may wake up tasks is
+    /// pending.
+    ///
+    /// The [`Simulator`] waits for every function to return `true` before choosing the next task
+    /// to run or advancing the virtual clock.
+    #[inline]
+    pub fn add_idle_check<F: Fn() -> bool + 'static>(&mut self, is_idle: F) {
+        self.idle_checks.push(Box::new(is_idle));
+    }
+
+    /// Runs tasks until no tasks are runnable or sleeping.
+    ///
+    /// A runnable task is chosen pseudo-randomly from the seed at each step, and the virtual clock
+    /// is advanced to the earliest wake-up time if no tasks are runnable. Returns the number of
+    /// unfinished tasks, e.g., tasks waiting for each other.
+    #[inline]
+    pub fn run(&mut self) -> usize {
+        while let Some(task_id) = self.next_task() {
+            let Some(task) = self.tasks.get_mut(task_id).and_then(Option::as_mut) else {
+                continue;
+            };
+            self.trace.push(task_id);
+            let waker = Waker::from(Arc::new(TaskWaker {
+                task_id,
+                shared_state: self.context.0.clone(),
+            }));
+            if task
+                .as_mut()
+                .poll(&mut Context::from_waker(&waker))
+                .is_ready()
+            {
+                self.tasks[task_id].take();
+            }
+        }
+        self.tasks.iter().filter(|t| t.is_some()).count()
+    }
+
+    /// Returns identifiers of polled tasks in the order of being polled.
+    ///
+    /// Two runs of the same tasks with the same seed produce the same trace.
+    #[inline]
+    #[must_use]
+    pub fn trace(&self) -> &[usize] {
+        &self.trace
+    }
+
+    /// Chooses the next task to poll.
+    fn next_task(&self) -> Option<usize> {
+        loop {
+            while !self.idle_checks.iter().all(|is_idle| is_idle()) {
+                yield_now();
+            }
+            if let Ok(mut runnable) = self.context.0.runnable.lock() {
+                if !runnable.is_empty() {
+                    #[allow(clippy::cast_possible_truncation)]
+                    let index = (self.context.random() % runnable.len() as u64) as usize;
+                    let task_id = runnable.iter().nth(index).copied();
+                    if let Some(task_id) = task_id {
+                        runnable.remove(&task_id);
+                    }
+                    return task_id;
+                }
+            }
+            if !self.context.advance() {
+                return None;
+            }
+        }
+    }
+}
+
+impl Debug for Simulator {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Simulator")
+            .field("seed", &self.seed)
+            .field("num_tasks", &self.tasks.len())
+            .field("context", &self.context)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SimulationContext {
+    /// Returns the virtual time elapsed since the [`Simulator`] was created.
+    #[inline]
+    #[must_use]
+    pub fn now(&self) -> Duration {
+        self.0.now.lock().map_or(Duration::ZERO, |now| *now)
+    }
+
+    /// Returns a [`Sleep`] that completes after the specified virtual duration.
+    #[inline]
+    #[must_use]
+    pub fn sleep(&self, duration: Duration) -> Sleep {
+        Sleep {
+            context: self.clone(),
+            wake_up_at: self.now().saturating_add(duration),
+        }
+    }
+
+    /// Returns a [`Timeout`] that fails with [`Error::Timeout`] unless the future completes
+    /// within the specified virtual duration.
+    #[inline]
+    #[must_use]
+    pub fn timeout<F: Future>(&self, duration: Duration, future: F) -> Timeout<F> {
+        Timeout {
+            future: Box::pin(future),
+            sleep: self.sleep(duration),
+        }
+    }
+
+    /// Returns a pseudo-random number derived from the seed.
+    #[inline]
+    #[must_use]
+    pub fn random(&self) -> u64 {
+        // `xorshift64*`.
+        let mut state = self.0.random_state.load(Relaxed);
+        state ^= state >> 12;
+        state ^= state << 25;
+        state ^= state >> 27;
+        self.0.random_state.store(state, Relaxed);
+        state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Advances the virtual clock to the earliest wake-up time, and wakes up the sleeping tasks.
+    ///
+    /// Returns `false` if no tasks are sleeping.
+    fn advance(&self) -> bool {
+        let Ok(mut timers) = self.0.timers.lock() else {
+            return false;
+        };
+        let Some(((wake_up_at, _), waker)) = timers.pop_first() else {
+            return false;
+        };
+        drop(timers);
+        if let Ok(mut now) = self.0.now.lock() {
+            *now = (*now).max(wake_up_at);
+        }
+        waker.wake();
+        true
+    }
+}
+
+impl Wake for TaskWaker {
+    #[inline]
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    #[inline]
+    fn wake_by_ref(self: &Arc<Self>) {
+        if let Ok(mut runnable) = self.shared_state.runnable.lock() {
+            runnable.insert(self.task_id);
+        }
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.context.now() >= self.wake_up_at {
+            return Poll::Ready(());
+        }
+        let timer_id = self.context.0.num_timers.fetch_add(1, Relaxed);
+        if let Ok(mut timers) = self.context.0.timers.lock() {
+            timers.insert((self.wake_up_at, timer_id), cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+impl<F: Future> Future for Timeout<F> {
+    type Output = Result<F::Output, Error>;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Poll::Ready(output) = self.future.as_mut().poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+        if Pin::new(&mut self.sleep).poll(cx).is_ready() {
+            return Poll::Ready(Err(Error::Timeout));
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Database, MemoryPersistence, MonotonicU64};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::Instant;
+
+    /// Runs a workload of tasks that sleep for random durations.
+    fn run_workload(seed: u64) -> (Vec<usize>, Vec<usize>) {
+        let mut simulator = Simulator::with_seed(seed);
+        let completion_order = Rc::new(RefCell::new(Vec::new()));
+        for task_id in 0..8 {
+            let context = simulator.context();
+            let completion_order = completion_order.clone();
+            simulator.spawn(async move {
+                for _ in 0..4 {
+                    let duration = Duration::from_millis(context.random() % 4);
+                    context.sleep(duration).await;
+                }
+                completion_order.borrow_mut().push(task_id);
+            });
+        }
+        assert_eq!(simulator.run(), 0);
+        let completion_order = completion_order.borrow().clone();
+        (simulator.trace().to_vec(), completion_order)
+    }
+
+    #[test]
+    fn replay() {
+        let (trace, completion_order) = run_workload(11);
+        assert_eq!(completion_order.len(), 8);
+        assert_eq!(run_workload(11), (trace.clone(), completion_order));
+        assert!((12..16).any(|seed| run_workload(seed).0 != trace));
+    }
+
+    #[test]
+    fn lock_wait() {
+        let database: Arc<Database<MonotonicU64, MemoryPersistence<MonotonicU64>>> = Arc::new(
+            futures::executor::block_on(Database::with_persistence_layer(
+                MemoryPersistence::default(),
+                None,
+                None,
+            ))
+            .unwrap(),
+        );
+        let mut simulator = Simulator::with_seed(3);
+        let database_clone = database.clone();
+        simulator.add_idle_check(move || database_clone.is_idle());
+        let results = Rc::new(RefCell::new(Vec::new()));
+
+        // The owner of the database object commits the transaction after a while.
+        let context = simulator.context();
+        let database_clone = database.clone();
+        simulator.spawn(async move {
+            let transaction = database_clone.transaction();
+            let mut journal = transaction.journal();
+            journal.create(&[1], None).await.unwrap();
+            assert_eq!(journal.submit().get(), 1);
+            context.sleep(Duration::from_millis(10)).await;
+            assert!(transaction.commit().await.is_ok());
+        });
+
+        // The waiter times out before the database object is released, and the other one waits.
+        for timeout in [5, 20] {
+            let context = simulator.context();
+            let database_clone = database.clone();
+            let results = results.clone();
+            simulator.spawn(async move {
+                context.sleep(Duration::from_millis(1)).await;
+                let transaction = database_clone.transaction();
+                let mut journal = transaction.journal();
+                let result = context
+                    .timeout(
+                        Duration::from_millis(timeout),
+                        journal.delete(&[1], Some(Instant::now() + Duration::from_mins(1))),
+                    )
+                    .await;
+                results.borrow_mut().push((
+                    timeout,
+                    context.now(),
+                    result.is_ok_and(|r| r.is_ok()),
+                ));
+            });
+        }
+        assert_eq!(simulator.run(), 0);
+        assert_eq!(
+            *results.borrow(),
+            [
+                (5, Duration::from_millis(6), false),
+                (20, Duration::from_millis(10), true)
+            ]
+        );
+    }
+}
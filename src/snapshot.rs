@@ -4,7 +4,7 @@
 
 use super::sequencer::ToInstant;
 use super::task_processor::TaskProcessor;
-use super::{Database, JournalID, PersistenceLayer, Sequencer, TransactionID};
+use super::{Database, Error, JournalID, PersistenceLayer, Sequencer, TransactionID};
 use std::cmp;
 use std::marker::PhantomData;
 use std::num::NonZeroU32;
@@ -23,7 +23,8 @@ use std::sync::atomic::Ordering::Acquire;
 ///
 /// Two or more types of [`Snapshot`] can be combined into a single [`Snapshot`] via
 /// [`Snapshot::combine`] as long as they belong to the same database.
-#[derive(Clone, Debug)]
+#[allow(clippy::struct_field_names)]
+#[derive(Debug)]
 pub struct Snapshot<'d, 't, 'j, S: Sequencer> {
     /// The logical instant of the database system being tracked by [`Database`].
     ///
@@ -48,6 +49,12 @@ pub struct Snapshot<'d, 't, 'j, S: Sequencer> {
     task_processor: &'d TaskProcessor,
 }
 
+/// The version of the format of exported snapshots.
+const EXPORT_FORMAT_VERSION: u8 = 1;
+
+/// The length of an exported snapshot: `VERSION 8-bit|CLOCK 64-bit`.
+const EXPORTED_LEN: usize = 9;
+
 /// Data representing the current state of the [`Transaction`](super::Transaction).
 #[derive(Clone, Debug, PartialEq)]
 pub(super) struct TransactionSnapshot<'t> {
@@ -135,6 +142,20 @@ impl<'d, 't, 'j, S: Sequencer> Snapshot<'d, 't, 'j, S> {
         }
     }
 
+    /// Creates a new [`Snapshot`] of a past instant from a [`Database`]
+    pub(super) fn from_database_at<P: PersistenceLayer<S>>(
+        database: &'d Database<S, P>,
+        instant: S::Instant,
+    ) -> Option<Snapshot<'d, 't, 'j, S>> {
+        let tracker = database.sequencer().track_at(instant, Acquire)?;
+        Some(Snapshot {
+            tracker: Some(tracker),
+            transaction_snapshot: None,
+            journal_snapshot: None,
+            task_processor: database.task_processor(),
+        })
+    }
+
     /// Creates a new [`Snapshot`] from a [`TransactionSnapshot`]
     pub(super) fn from_journal<P: PersistenceLayer<S>>(
         database: &'d Database<S, P>,
@@ -186,14 +207,69 @@ impl<'d, 't, 'j, S: Sequencer> Snapshot<'d, 't, 'j, S> {
     }
 }
 
-impl<'d, 't, 'j, S: Sequencer> PartialEq<S::Instant> for Snapshot<'d, 't, 'j, S> {
+impl<S: Sequencer<Instant = u64>> Snapshot<'_, '_, '_, S> {
+    /// Exports the clock value of the database snapshot.
+    ///
+    /// The exported bytes can be handed to other processes reading replicas of the same database,
+    /// and imported through [`Database::snapshot_from`](super::Database::snapshot_from) in order
+    /// for them to read the database at the same clock value. Changes made by the transaction or
+    /// the journal of the [`Snapshot`] are not exported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("snapshot_export")).await.unwrap();
+    ///     let instant = database.transaction().commit().await.unwrap();
+    ///     let exported = database.snapshot().export();
+    ///     let snapshot = database.snapshot_from(&exported).unwrap();
+    ///     assert!(snapshot >= instant);
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn export(&self) -> Vec<u8> {
+        let mut exported = Vec::with_capacity(EXPORTED_LEN);
+        exported.push(EXPORT_FORMAT_VERSION);
+        exported.extend_from_slice(&self.database_snapshot().to_le_bytes());
+        exported
+    }
+
+    /// Decodes the clock value exported by [`Snapshot::export`].
+    ///
+    /// Returns [`Error::WrongParameter`] if the bytes were not exported by [`Snapshot::export`].
+    pub(super) fn import(exported: &[u8]) -> Result<u64, Error> {
+        let Some((&EXPORT_FORMAT_VERSION, clock)) = exported.split_first() else {
+            return Err(Error::WrongParameter);
+        };
+        let clock = <[u8; 8]>::try_from(clock).map_err(|_| Error::WrongParameter)?;
+        Ok(u64::from_le_bytes(clock))
+    }
+}
+
+impl<S: Sequencer> Clone for Snapshot<'_, '_, '_, S> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            tracker: self.tracker.clone(),
+            transaction_snapshot: self.transaction_snapshot.clone(),
+            journal_snapshot: self.journal_snapshot.clone(),
+            task_processor: self.task_processor,
+        }
+    }
+}
+
+impl<S: Sequencer> PartialEq<S::Instant> for Snapshot<'_, '_, '_, S> {
     #[inline]
     fn eq(&self, other: &S::Instant) -> bool {
         self.database_snapshot().eq(other)
     }
 }
 
-impl<'d, 't, 'j, S: Sequencer> PartialOrd<S::Instant> for Snapshot<'d, 't, 'j, S> {
+impl<S: Sequencer> PartialOrd<S::Instant> for Snapshot<'_, '_, '_, S> {
     #[inline]
     fn partial_cmp(&self, other: &S::Instant) -> Option<cmp::Ordering> {
         self.database_snapshot().partial_cmp(other)
@@ -209,9 +285,14 @@ impl<'t> TransactionSnapshot<'t> {
             _phantom: PhantomData,
         }
     }
+
+    /// Returns the identifier of the transaction.
+    pub(super) fn id(&self) -> TransactionID {
+        self.id
+    }
 }
 
-impl<'t> PartialOrd for TransactionSnapshot<'t> {
+impl PartialOrd for TransactionSnapshot<'_> {
     #[inline]
     fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
         if self.id != other.id {
@@ -237,7 +318,7 @@ mod test {
 
     use tokio::fs::remove_dir_all;
 
-    use crate::Database;
+    use crate::{Database, Metadata};
 
     #[tokio::test]
     async fn combine() {
@@ -306,4 +387,53 @@ mod test {
         assert!(remove_dir_all(path).await.is_ok());
         assert!(remove_dir_all(path_other).await.is_ok());
     }
+
+    #[tokio::test]
+    async fn snapshot_at() {
+        const DIR: &str = "snapshot_snapshot_at_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("time".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert!(container
+            .insert(b"1", b"1", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        let first_instant = transaction.commit().await.unwrap();
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .update(b"1", b"2", &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        let second_instant = transaction.commit().await.unwrap();
+
+        let first_snapshot = database.snapshot_at(first_instant).unwrap();
+        let second_snapshot = database.snapshot_at(second_instant).unwrap();
+        assert!(database.snapshot_at(second_instant + 1).is_none());
+        assert_eq!(
+            container.get(b"1", &first_snapshot, None).await,
+            Ok(Some(b"1".to_vec()))
+        );
+        assert_eq!(
+            container.get(b"1", &second_snapshot, None).await,
+            Ok(Some(b"2".to_vec()))
+        );
+        assert!(first_snapshot.database_snapshot() < second_snapshot.database_snapshot());
+
+        drop(first_snapshot);
+        drop(second_snapshot);
+        drop(container);
+        drop(database);
+
+        assert!(remove_dir_all(path).await.is_ok());
+    }
 }
@@ -0,0 +1,14 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Atomic types that the commit protocol is built on.
+//!
+//! They are replaced with their [`loom`](https://docs.rs/loom) counterparts when the crate is
+//! compiled with `--cfg sap_tsf_loom` in order for the visibility protocol to be model-checked.
+
+#[cfg(sap_tsf_loom)]
+pub(crate) use loom::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize};
+
+#[cfg(not(sap_tsf_loom))]
+pub(crate) use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize};
@@ -4,29 +4,50 @@
 
 use super::database::Kernel;
 use super::utils;
-use super::{PersistenceLayer, Sequencer};
+use super::utils::Instant;
+use super::{Counter, PersistenceLayer, Sequencer, TransactionID};
 use scc::ebr;
 use std::collections::{BTreeMap, BTreeSet};
 use std::mem::take;
+#[cfg(feature = "simulation")]
+use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering::Acquire;
+#[cfg(feature = "simulation")]
+use std::sync::atomic::Ordering::{AcqRel, Release};
 use std::sync::mpsc::{self, Receiver, SyncSender};
 use std::sync::Arc;
+#[cfg(target_arch = "wasm32")]
+use std::sync::Mutex;
 use std::task::Waker;
+#[cfg(not(target_arch = "wasm32"))]
 use std::thread::{self, JoinHandle};
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 /// [`TaskProcessor`] receives tasks from database system workers, and processes them in the
 /// background.
 ///
 /// [`TaskProcessor`] is the only one that is allowed to execute blocking code as the code is run
 /// in a separate background thread.
+///
+/// No threads can be spawned on `wasm32`, therefore received tasks are processed only when the
+/// host calls [`Database::process_tasks`](super::Database::process_tasks).
 #[derive(Debug)]
 pub struct TaskProcessor {
     /// The task processor thread.
+    #[cfg(not(target_arch = "wasm32"))]
     processor: Option<JoinHandle<()>>,
 
+    /// The task processor that processes received tasks, and returns the duration until it has
+    /// to process tasks again.
+    #[cfg(target_arch = "wasm32")]
+    processor: Mutex<PendingTaskProcessor>,
+
     /// The task sender.
     sender: SyncSender<Task>,
+
+    /// The number of tasks that have been sent and not fully processed.
+    #[cfg(feature = "simulation")]
+    num_pending_tasks: Arc<AtomicUsize>,
 }
 
 /// [`Task`] is sent to a [`TaskProcessor`] by database workers, and the [`TaskProcessor`] makes
@@ -34,6 +55,7 @@ pub struct TaskProcessor {
 #[derive(Debug)]
 pub enum Task {
     /// The [`Database`](super::Database) is shutting down.
+    #[cfg(not(target_arch = "wasm32"))]
     Shutdown,
 
     /// The [`Waker`] should be invoked at around the specified instant.
@@ -41,15 +63,15 @@ pub enum Task {
     /// [`TaskProcessor`] makes its best to invoke the [`Waker`] at the specified instant, however
     /// there are cases where [`TaskProcessor`] fails to fulfill the requirement.
     /// * The [`Waker`] may be called before the instant if memory allocation failed in the
-    /// [`TaskProcessor`].
+    ///   [`TaskProcessor`].
     /// * The [`Waker`] may be called after the instant if the [`TaskProcessor`] is overloaded.
     WakeUp(Instant, Waker),
 
     /// The [`TaskProcessor`] should monitor the database container.
     ///
-    /// TODO: implement it.
-    #[allow(dead_code)]
-    MonitorContainer(String),
+    /// [`TaskProcessor`] periodically reclaims versions of key-value pairs in the container that
+    /// are no longer visible to any readers until no versions are left to be reclaimed.
+    MonitorContainer(u64),
 
     /// The [`TaskProcessor`] should monitor the database object.
     ///
@@ -67,12 +89,20 @@ pub enum Task {
 }
 
 /// The default interval that a [`TaskProcessor`] wakes up and checks the status of the database.
-const DEFAULT_CHECK_INTERAL: Duration = Duration::from_secs(60);
+pub(super) const DEFAULT_CHECK_INTERAL: Duration = Duration::from_mins(1);
+
+/// The minimum interval that a [`TaskProcessor`] checks active transactions for the
+/// [`Watchdog`](super::Watchdog).
+const MIN_WATCH_INTERVAL: Duration = Duration::from_millis(10);
 
 /// [`TaskProcessor`] processes time critical tasks on every `CONTEXT_SWITCH_THRESHOLD` operations
 /// in a long task.
 const CONTEXT_SWITCH_THRESHOLD: usize = 256;
 
+/// [`PendingTaskProcessor`] processes tasks received by a [`TaskProcessor`] on `wasm32`.
+#[cfg(target_arch = "wasm32")]
+struct PendingTaskProcessor(Box<dyn FnMut() -> Duration + Send>);
+
 /// [`ThreadLocalData`] is privately used by [`TaskProcessor`].
 #[derive(Debug)]
 struct ThreadLocalData<S: Sequencer, P: PersistenceLayer<S>> {
@@ -82,14 +112,29 @@ struct ThreadLocalData<S: Sequencer, P: PersistenceLayer<S>> {
     /// The [`Waker`] container.
     waker_queue: BTreeMap<Instant, Waker>,
 
-    /// A set containing the names of monitored containers.
-    monitored_containers: BTreeSet<String>,
+    /// A set containing the identifiers of monitored containers.
+    monitored_containers: BTreeSet<u64>,
 
     /// A set containing the object identifiers of monitored database objects.
     monitored_object_ids: BTreeSet<u64>,
 
     /// Wait duration to receive a new [`Task`].
     wait_duration: Duration,
+
+    /// The time when active transactions are checked by the [`Watchdog`](super::Watchdog) next
+    /// time.
+    next_watch: Option<Instant>,
+
+    /// Identifiers of the transactions that the [`Watchdog`](super::Watchdog) has reported.
+    reported_transactions: BTreeSet<TransactionID>,
+
+    /// The number of tasks that have been sent and not fully processed.
+    #[cfg(feature = "simulation")]
+    num_pending_tasks: Arc<AtomicUsize>,
+
+    /// The number of tasks received since time critical tasks were last processed.
+    #[cfg(feature = "simulation")]
+    num_received_tasks: usize,
 }
 
 impl TaskProcessor {
@@ -99,21 +144,44 @@ impl TaskProcessor {
         kernel: Arc<Kernel<S, P>>,
     ) -> TaskProcessor {
         let (sender, receiver) = mpsc::sync_channel::<Task>(utils::advise_num_shards() * 4);
+        #[cfg(feature = "simulation")]
+        let num_pending_tasks = Arc::new(AtomicUsize::new(0));
+        #[cfg(feature = "simulation")]
+        let num_pending_tasks_clone = num_pending_tasks.clone();
+        let mut thread_local_data = ThreadLocalData {
+            kernel,
+            waker_queue: BTreeMap::default(),
+            monitored_containers: BTreeSet::default(),
+            monitored_object_ids: BTreeSet::default(),
+            wait_duration: DEFAULT_CHECK_INTERAL,
+            next_watch: None,
+            reported_transactions: BTreeSet::default(),
+            #[cfg(feature = "simulation")]
+            num_pending_tasks: num_pending_tasks_clone,
+            #[cfg(feature = "simulation")]
+            num_received_tasks: 0,
+        };
         TaskProcessor {
+            #[cfg(not(target_arch = "wasm32"))]
             processor: Some(thread::spawn(move || {
-                let mut thread_local_data = ThreadLocalData {
-                    kernel,
-                    waker_queue: BTreeMap::default(),
-                    monitored_containers: BTreeSet::default(),
-                    monitored_object_ids: BTreeSet::default(),
-                    wait_duration: DEFAULT_CHECK_INTERAL,
-                };
                 Self::process(&receiver, &mut thread_local_data);
             })),
+            #[cfg(target_arch = "wasm32")]
+            processor: Mutex::new(PendingTaskProcessor(Box::new(move || {
+                Self::process_pending(&receiver, &mut thread_local_data)
+            }))),
             sender,
+            #[cfg(feature = "simulation")]
+            num_pending_tasks,
         }
     }
 
+    /// Returns `true` if every task sent to the [`TaskProcessor`] has been processed.
+    #[cfg(feature = "simulation")]
+    pub(super) fn is_idle(&self) -> bool {
+        self.num_pending_tasks.load(Acquire) == 0
+    }
+
     /// Tries to send a [`Task`] to the [`TaskProcessor`].
     ///
     /// Returns `false` if the [`Task`] could not be sent. It is usually not a problem since it
@@ -122,56 +190,117 @@ impl TaskProcessor {
     /// very specific action, e.g., [`Task::MonitorObject`], will need to be sent to the
     /// [`TaskProcessor`] eventually by the caller.
     pub(super) fn send_task(&self, task: Task) -> bool {
-        self.sender.try_send(task).is_ok()
+        #[cfg(feature = "simulation")]
+        self.num_pending_tasks.fetch_add(1, AcqRel);
+        let sent = self.sender.try_send(task).is_ok();
+        #[cfg(feature = "simulation")]
+        if !sent {
+            self.num_pending_tasks.fetch_sub(1, AcqRel);
+        }
+        sent
+    }
+
+    /// Processes all the received tasks, and returns the duration until tasks have to be processed
+    /// again.
+    ///
+    /// Returns `None` if the [`TaskProcessor`] is being processed.
+    #[cfg(target_arch = "wasm32")]
+    pub(super) fn process_pending_tasks(&self) -> Option<Duration> {
+        let mut processor = self.processor.try_lock().ok()?;
+        Some((processor.0)())
     }
 
     /// Processes tasks.
+    #[cfg(not(target_arch = "wasm32"))]
     fn process<S: Sequencer, P: PersistenceLayer<S>>(
         receiver: &Receiver<Task>,
         thread_local_data: &mut ThreadLocalData<S, P>,
     ) {
-        while Self::receive_task(receiver, thread_local_data, false) {
-            // The monitored database objects and deadlines have to be always checked on every
-            // iteration as sending `ScanAccessController` tasks to `Overseer` may fail when the
-            // send buffer is full.
-            Self::process_time_critical_tasks(thread_local_data);
-
-            // Perform MVCC garbage collection.
-            let mut operation_count = 0;
-            let mut monitored_containers = take(&mut thread_local_data.monitored_containers);
-            monitored_containers.retain(|name| {
-                if let Some(container) = thread_local_data
-                    .kernel
-                    .container(name.as_str(), &ebr::Guard::new())
-                {
-                    let oldest = thread_local_data.kernel.sequencer().min(Acquire);
-                    let versioned_record_iter = container.iter_versioned_records();
-                    let mut num_versioned_records = 0;
-                    for object_id in versioned_record_iter {
-                        if !thread_local_data
-                            .kernel
-                            .access_controller()
-                            .try_remove_access_data_sync(object_id, &|i| *i <= oldest, &mut |_| ())
-                        {
-                            num_versioned_records += 1;
-                        }
+        while Self::receive_task(receiver, thread_local_data, false)
+            && Self::process_round(receiver, thread_local_data)
+        {}
+    }
+
+    /// Processes the received tasks without blocking the thread, and returns the duration until
+    /// tasks have to be processed again.
+    #[cfg(target_arch = "wasm32")]
+    fn process_pending<S: Sequencer, P: PersistenceLayer<S>>(
+        receiver: &Receiver<Task>,
+        thread_local_data: &mut ThreadLocalData<S, P>,
+    ) -> Duration {
+        while let Ok(task) = receiver.try_recv() {
+            Self::handle_task(task, thread_local_data);
+        }
+        Self::process_round(receiver, thread_local_data);
+        thread_local_data.wait_duration
+    }
+
+    /// Processes time critical tasks, and then reclaims versions and database objects that are no
+    /// longer visible.
+    ///
+    /// Returns `false` if the sender is shutting down.
+    fn process_round<S: Sequencer, P: PersistenceLayer<S>>(
+        receiver: &Receiver<Task>,
+        thread_local_data: &mut ThreadLocalData<S, P>,
+    ) -> bool {
+        // The monitored database objects and deadlines have to be always checked on every
+        // iteration as sending `ScanAccessController` tasks to `Overseer` may fail when the
+        // send buffer is full.
+        Self::process_time_critical_tasks(thread_local_data);
+
+        // Report long-running transactions.
+        Self::watch_transactions(thread_local_data);
+
+        // Discard committed changes that no change streams need; it also refreshes the cached
+        // minimum instant of the sequencer.
+        let kernel = &thread_local_data.kernel;
+        kernel.change_log().prune(kernel.sequencer().min(Acquire));
+
+        // Perform MVCC garbage collection.
+        let mut operation_count = 0;
+        let mut shutting_down = false;
+        let mut monitored_containers = take(&mut thread_local_data.monitored_containers);
+        monitored_containers.retain(|container_id| {
+            if let Some(container) = thread_local_data
+                .kernel
+                .container(*container_id, &ebr::Guard::new())
+            {
+                let oldest = thread_local_data.kernel.sequencer().cached_min(Acquire);
+                let (num_reclaimed, revisit) =
+                    container.reclaim_versions_sync(&|i| *i <= oldest, || {
                         if operation_count == CONTEXT_SWITCH_THRESHOLD {
                             // Process time critical tasks periodically.
-                            Self::receive_task(receiver, thread_local_data, true);
+                            shutting_down |= !Self::receive_task(receiver, thread_local_data, true);
                             Self::process_time_critical_tasks(thread_local_data);
                             operation_count = 0;
                         } else {
                             operation_count += 1;
                         }
-                    }
-                    return num_versioned_records != 0;
-                }
-                false
-            });
-            thread_local_data
-                .monitored_containers
-                .append(&mut monitored_containers);
-        }
+                    });
+                thread_local_data
+                    .kernel
+                    .telemetry()
+                    .add(Counter::VersionsReclaimed, num_reclaimed);
+                return revisit;
+            }
+            false
+        });
+        thread_local_data
+            .monitored_containers
+            .append(&mut monitored_containers);
+
+        // Consolidate database objects of transactions committed before the oldest snapshot.
+        let kernel = &thread_local_data.kernel;
+        let oldest = kernel.sequencer().cached_min(Acquire);
+        let num_consolidated = kernel
+            .access_controller()
+            .consolidate_sync(&|i| *i <= oldest);
+        kernel
+            .telemetry()
+            .add(Counter::ObjectsConsolidated, num_consolidated);
+
+        // The shutdown request may have been received during garbage collection.
+        !shutting_down
     }
 
     /// Tries to receive a task.
@@ -188,34 +317,50 @@ impl TaskProcessor {
             receiver.recv_timeout(thread_local_data.wait_duration).ok()
         };
         if let Some(task) = receive_result {
-            match task {
-                Task::Shutdown => return false,
-                Task::WakeUp(deadline, mut waker) => {
-                    let now = Instant::now();
-                    if deadline < now {
-                        waker.wake();
-                    } else {
-                        // Try `4` times, and give up inserting the `Waker`.
-                        for offset in 0..4 {
-                            match thread_local_data
-                                .waker_queue
-                                .insert(deadline + Duration::from_nanos(offset), waker)
-                            {
-                                Some(other_waker) => waker = other_waker,
-                                None => break,
-                            }
+            return Self::handle_task(task, thread_local_data);
+        }
+        true
+    }
+
+    /// Handles a received task.
+    ///
+    /// Returns `false` if the sender is shutting down.
+    fn handle_task<S: Sequencer, P: PersistenceLayer<S>>(
+        task: Task,
+        thread_local_data: &mut ThreadLocalData<S, P>,
+    ) -> bool {
+        #[cfg(feature = "simulation")]
+        {
+            thread_local_data.num_received_tasks += 1;
+        }
+        match task {
+            #[cfg(not(target_arch = "wasm32"))]
+            Task::Shutdown => return false,
+            Task::WakeUp(deadline, mut waker) => {
+                let now = Instant::now();
+                if deadline < now {
+                    waker.wake();
+                } else {
+                    // Try `4` times, and give up inserting the `Waker`.
+                    for offset in 0..4 {
+                        match thread_local_data
+                            .waker_queue
+                            .insert(deadline + Duration::from_nanos(offset), waker)
+                        {
+                            Some(other_waker) => waker = other_waker,
+                            None => break,
                         }
                     }
                 }
-                Task::MonitorContainer(name) => {
-                    thread_local_data.monitored_containers.insert(name);
-                }
-                Task::MonitorObject(object_id) => {
-                    thread_local_data.monitored_object_ids.insert(object_id);
-                }
-                Task::ScanAccessController => {
-                    // Do nothing.
-                }
+            }
+            Task::MonitorContainer(container_id) => {
+                thread_local_data.monitored_containers.insert(container_id);
+            }
+            Task::MonitorObject(object_id) => {
+                thread_local_data.monitored_object_ids.insert(object_id);
+            }
+            Task::ScanAccessController => {
+                // Do nothing.
             }
         }
         true
@@ -232,8 +377,20 @@ impl TaskProcessor {
         thread_local_data
             .monitored_object_ids
             .retain(|object_id| access_controller.transfer_ownership_sync(*object_id));
+        let deadlocks =
+            access_controller.resolve_deadlocks_sync(&thread_local_data.monitored_object_ids);
+        if !deadlocks.is_empty() {
+            thread_local_data
+                .kernel
+                .telemetry()
+                .add(Counter::DeadlocksDetected, deadlocks.len() as u64);
+            #[cfg(feature = "tracing")]
+            for report in &deadlocks {
+                tracing::warn!(victim = report.victim, "deadlock detected: {report}");
+            }
+        }
 
-        let mut new_wait_duration = DEFAULT_CHECK_INTERAL;
+        let mut new_wait_duration = thread_local_data.kernel.gc_interval();
         let now = Instant::now();
         while let Some(entry) = thread_local_data.waker_queue.first_entry() {
             if *entry.key() >= now {
@@ -242,10 +399,71 @@ impl TaskProcessor {
             }
             entry.remove().wake();
         }
+        if let Some(next_watch) = thread_local_data.next_watch {
+            new_wait_duration = new_wait_duration.min(next_watch.saturating_duration_since(now));
+        }
         thread_local_data.wait_duration = new_wait_duration;
+
+        #[cfg(feature = "simulation")]
+        thread_local_data
+            .num_pending_tasks
+            .fetch_sub(take(&mut thread_local_data.num_received_tasks), Release);
+    }
+
+    /// Reports active transactions that the [`Watchdog`](super::Watchdog) considers overdue.
+    ///
+    /// Active transactions are checked at an interval of a quarter of the age threshold, and each
+    /// transaction is reported only once.
+    fn watch_transactions<S: Sequencer, P: PersistenceLayer<S>>(
+        thread_local_data: &mut ThreadLocalData<S, P>,
+    ) {
+        let Some(watchdog) = thread_local_data.kernel.watchdog() else {
+            thread_local_data.next_watch = None;
+            thread_local_data.reported_transactions.clear();
+            return;
+        };
+        let now = Instant::now();
+        if thread_local_data.next_watch.is_some_and(|n| now < n) {
+            return;
+        }
+        let interval =
+            (watchdog.age_threshold() / 4).clamp(MIN_WATCH_INTERVAL, DEFAULT_CHECK_INTERAL);
+        thread_local_data.next_watch = Some(now + interval);
+        thread_local_data.wait_duration = thread_local_data.wait_duration.min(interval);
+
+        let mut active_transactions = BTreeSet::new();
+        let mut reports = Vec::new();
+        let reported_transactions = &thread_local_data.reported_transactions;
+        thread_local_data
+            .kernel
+            .scan_active_transactions(|id, anchor| {
+                active_transactions.insert(id);
+                if !reported_transactions.contains(&id) {
+                    let report = anchor.report(id, now);
+                    if watchdog.is_overdue(report.age, report.memory_usage) {
+                        reports.push(report);
+                    }
+                }
+            });
+        thread_local_data
+            .reported_transactions
+            .retain(|id| active_transactions.contains(id));
+        for report in reports {
+            thread_local_data.reported_transactions.insert(report.id);
+            watchdog.report(&report);
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl std::fmt::Debug for PendingTaskProcessor {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("PendingTaskProcessor").finish()
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl Drop for TaskProcessor {
     #[inline]
     fn drop(&mut self) {
@@ -258,17 +476,18 @@ impl Drop for TaskProcessor {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::Database;
+    use crate::{Database, Watchdog};
     use std::future::Future;
     use std::path::Path;
     use std::pin::Pin;
+    use std::sync::Mutex;
     use std::task::{Context, Poll};
     use std::time::Instant;
     use tokio::fs::remove_dir_all;
 
     struct AfterNSecs<'d>(Instant, u64, &'d TaskProcessor);
 
-    impl<'d> Future for AfterNSecs<'d> {
+    impl Future for AfterNSecs<'_> {
         type Output = ();
         fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
             if self.0 + Duration::from_secs(self.1) < Instant::now() {
@@ -299,4 +518,42 @@ mod test {
         assert!(now.elapsed() > Duration::from_millis(256));
         assert!(remove_dir_all(path).await.is_ok());
     }
+
+    #[tokio::test]
+    async fn watchdog() {
+        const DIR: &str = "task_processor_watchdog_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let reports_clone = reports.clone();
+        database.set_watchdog(Some(
+            Watchdog::new(Duration::from_millis(64)).with_reporter(move |report| {
+                reports_clone.lock().unwrap().push(report.clone());
+            }),
+        ));
+
+        let mut transaction = database.transaction();
+        assert!(transaction.set_tag("slow").is_ok());
+        assert_eq!(transaction.journal().submit().get(), 1);
+        assert!(database.transaction().commit().await.is_ok());
+        for _ in 0..1024 {
+            if !reports.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(8)).await;
+        }
+
+        // The transaction is reported only once.
+        tokio::time::sleep(Duration::from_millis(128)).await;
+        let reports = take(&mut *reports.lock().unwrap());
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].id, transaction.id());
+        assert_eq!(reports[0].tag.as_deref(), Some("slow"));
+        assert_eq!(reports[0].num_submitted_journals, 1);
+        assert!(reports[0].age >= Duration::from_millis(64));
+        transaction.rollback();
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
 }
@@ -2,7 +2,336 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+use scc::HashMap;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering::Relaxed;
+
 /// [`Telemetry`] provides statistics data and aggregated views on the database system internals.
-#[derive(Debug)]
-#[allow(dead_code)]
-pub struct Telemetry;
+///
+/// A [`Telemetry`] is owned by the [`PersistenceLayer`](super::PersistenceLayer) of a
+/// [`Database`](super::Database), so that the database and its persistence layer update the same
+/// set of counters.
+///
+/// Counters that are attributable to a transaction are also aggregated for each tag attached to
+/// transactions through [`Transaction::set_tag`](super::Transaction::set_tag).
+#[derive(Debug, Default)]
+pub struct Telemetry {
+    /// Counters indexed by [`Counter`].
+    counters: [AtomicU64; Counter::LEN],
+
+    /// Counters of tagged transactions indexed by tags.
+    tagged_counters: HashMap<String, [AtomicU64; Counter::LEN]>,
+}
+
+/// [`Counter`] identifies a statistics counter in [`Telemetry`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum Counter {
+    /// Transactions started.
+    TransactionsStarted,
+
+    /// Transactions committed.
+    TransactionsCommitted,
+
+    /// Transactions rolled back.
+    TransactionsRolledBack,
+
+    /// Requests to access database objects that had to wait for other transactions.
+    LockWaits,
+
+    /// Requests to access database objects that timed out while waiting for other transactions.
+    LockTimeouts,
+
+    /// Record locks of a journal escalated to an exclusive lock on the whole container.
+    LockEscalations,
+
+    /// Pages read from the database file.
+    PagesRead,
+
+    /// Pages written to the database file.
+    PagesWritten,
+
+    /// Synchronizations of the log file with the device.
+    Fsyncs,
+
+    /// Bytes written to the log file.
+    LogBytesWritten,
+
+    /// Versions of key-value pairs removed by garbage collection.
+    VersionsReclaimed,
+
+    /// Page accesses served by the page cache.
+    PageCacheHits,
+
+    /// Page accesses that had to read the page from the database file.
+    PageCacheMisses,
+
+    /// Runtime configuration changes applied to the database.
+    Reconfigurations,
+
+    /// Buffers of journals taken from the thread-local pool instead of being allocated.
+    JournalBufferHits,
+
+    /// Buffers of journals that had to be allocated since the thread-local pool was empty.
+    JournalBufferMisses,
+
+    /// Deadlocks detected and resolved by aborting a waiting request of the victim.
+    DeadlocksDetected,
+
+    /// Database objects of committed transactions consolidated in the background.
+    ObjectsConsolidated,
+}
+
+/// [`Statistics`] is a point-in-time copy of the counters in [`Telemetry`].
+///
+/// Counters are updated independently of each other, therefore the values may be slightly
+/// inconsistent with each other when the database is being used.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Statistics {
+    /// Transactions started.
+    pub transactions_started: u64,
+
+    /// Transactions committed.
+    pub transactions_committed: u64,
+
+    /// Transactions rolled back.
+    pub transactions_rolled_back: u64,
+
+    /// Requests to access database objects that had to wait for other transactions.
+    pub lock_waits: u64,
+
+    /// Requests to access database objects that timed out while waiting for other transactions.
+    pub lock_timeouts: u64,
+
+    /// Record locks of a journal escalated to an exclusive lock on the whole container.
+    pub lock_escalations: u64,
+
+    /// Pages read from the database file.
+    pub pages_read: u64,
+
+    /// Pages written to the database file.
+    pub pages_written: u64,
+
+    /// Synchronizations of the log file with the device.
+    pub fsyncs: u64,
+
+    /// Bytes written to the log file.
+    pub log_bytes_written: u64,
+
+    /// Versions of key-value pairs removed by garbage collection.
+    pub versions_reclaimed: u64,
+
+    /// Page accesses served by the page cache.
+    pub page_cache_hits: u64,
+
+    /// Page accesses that had to read the page from the database file.
+    pub page_cache_misses: u64,
+
+    /// Runtime configuration changes applied to the database.
+    pub reconfigurations: u64,
+
+    /// Buffers of journals taken from the thread-local pool instead of being allocated.
+    pub journal_buffer_hits: u64,
+
+    /// Buffers of journals that had to be allocated since the thread-local pool was empty.
+    pub journal_buffer_misses: u64,
+
+    /// Deadlocks detected and resolved by aborting a waiting request of the victim.
+    pub deadlocks_detected: u64,
+
+    /// Database objects of committed transactions consolidated in the background.
+    pub objects_consolidated: u64,
+}
+
+impl Telemetry {
+    /// Adds `value` to the counter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Counter, Telemetry};
+    ///
+    /// let telemetry = Telemetry::default();
+    /// telemetry.add(Counter::PagesRead, 2);
+    /// assert_eq!(telemetry.statistics().pages_read, 2);
+    /// ```
+    #[inline]
+    pub fn add(&self, counter: Counter, value: u64) {
+        self.counters[counter as usize].fetch_add(value, Relaxed);
+    }
+
+    /// Adds `value` to the counter and to the counter of the tag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Counter, Telemetry};
+    ///
+    /// let telemetry = Telemetry::default();
+    /// telemetry.add_tagged(Counter::LockWaits, 1, Some("batch"));
+    /// telemetry.add_tagged(Counter::LockWaits, 1, None);
+    /// assert_eq!(telemetry.statistics().lock_waits, 2);
+    /// assert_eq!(telemetry.tagged_statistics()["batch"].lock_waits, 1);
+    /// ```
+    #[inline]
+    pub fn add_tagged(&self, counter: Counter, value: u64, tag: Option<&str>) {
+        self.add(counter, value);
+        if let Some(tag) = tag {
+            self.add_to_tag(counter, value, tag);
+        }
+    }
+
+    /// Returns the current values of the counters.
+    #[inline]
+    #[must_use]
+    pub fn statistics(&self) -> Statistics {
+        Statistics::from_counters(&self.counters)
+    }
+
+    /// Returns the current values of the counters of each tag.
+    ///
+    /// Only counters attributable to transactions, e.g., [`Counter::TransactionsCommitted`] and
+    /// [`Counter::LockWaits`], are aggregated for tags.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Counter, Telemetry};
+    ///
+    /// let telemetry = Telemetry::default();
+    /// telemetry.add_tagged(Counter::TransactionsCommitted, 1, Some("tenant-a"));
+    /// telemetry.add_tagged(Counter::TransactionsRolledBack, 1, Some("tenant-b"));
+    /// let tagged_statistics = telemetry.tagged_statistics();
+    /// assert_eq!(tagged_statistics.len(), 2);
+    /// assert_eq!(tagged_statistics["tenant-a"].transactions_committed, 1);
+    /// assert_eq!(tagged_statistics["tenant-b"].transactions_rolled_back, 1);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn tagged_statistics(&self) -> BTreeMap<String, Statistics> {
+        let mut tagged_statistics = BTreeMap::new();
+        self.tagged_counters.scan(|tag, counters| {
+            tagged_statistics.insert(tag.clone(), Statistics::from_counters(counters));
+        });
+        tagged_statistics
+    }
+
+    /// Resets all the counters to zero, and forgets the tags.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Counter, Statistics, Telemetry};
+    ///
+    /// let telemetry = Telemetry::default();
+    /// telemetry.add(Counter::Fsyncs, 1);
+    /// telemetry.reset();
+    /// assert_eq!(telemetry.statistics(), Statistics::default());
+    /// ```
+    #[inline]
+    pub fn reset(&self) {
+        for counter in &self.counters {
+            counter.store(0, Relaxed);
+        }
+        self.tagged_counters.clear();
+    }
+
+    /// Adds `value` to the counter of the tag.
+    pub(super) fn add_to_tag(&self, counter: Counter, value: u64, tag: &str) {
+        if self
+            .tagged_counters
+            .read(tag, |_, c| c[counter as usize].fetch_add(value, Relaxed))
+            .is_none()
+        {
+            self.tagged_counters
+                .entry(tag.to_string())
+                .or_default()
+                .get()[counter as usize]
+                .fetch_add(value, Relaxed);
+        }
+    }
+}
+
+impl Counter {
+    /// The number of counters.
+    const LEN: usize = Counter::ObjectsConsolidated as usize + 1;
+}
+
+impl Statistics {
+    /// Creates a new [`Statistics`] from counters indexed by [`Counter`].
+    fn from_counters(counters: &[AtomicU64; Counter::LEN]) -> Statistics {
+        let value = |counter: Counter| counters[counter as usize].load(Relaxed);
+        Statistics {
+            transactions_started: value(Counter::TransactionsStarted),
+            transactions_committed: value(Counter::TransactionsCommitted),
+            transactions_rolled_back: value(Counter::TransactionsRolledBack),
+            lock_waits: value(Counter::LockWaits),
+            lock_timeouts: value(Counter::LockTimeouts),
+            lock_escalations: value(Counter::LockEscalations),
+            pages_read: value(Counter::PagesRead),
+            pages_written: value(Counter::PagesWritten),
+            fsyncs: value(Counter::Fsyncs),
+            log_bytes_written: value(Counter::LogBytesWritten),
+            versions_reclaimed: value(Counter::VersionsReclaimed),
+            page_cache_hits: value(Counter::PageCacheHits),
+            page_cache_misses: value(Counter::PageCacheMisses),
+            reconfigurations: value(Counter::Reconfigurations),
+            journal_buffer_hits: value(Counter::JournalBufferHits),
+            journal_buffer_misses: value(Counter::JournalBufferMisses),
+            deadlocks_detected: value(Counter::DeadlocksDetected),
+            objects_consolidated: value(Counter::ObjectsConsolidated),
+        }
+    }
+
+    /// Returns the ratio of page accesses served by the page cache.
+    ///
+    /// Returns `None` if no pages have been accessed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Statistics;
+    ///
+    /// let statistics = Statistics {
+    ///     page_cache_hits: 3,
+    ///     page_cache_misses: 1,
+    ///     ..Statistics::default()
+    /// };
+    /// assert_eq!(statistics.page_cache_hit_ratio(), Some(0.75));
+    /// ```
+    #[allow(clippy::cast_precision_loss)]
+    #[inline]
+    #[must_use]
+    pub fn page_cache_hit_ratio(&self) -> Option<f64> {
+        let accesses = self.page_cache_hits + self.page_cache_misses;
+        (accesses != 0).then(|| self.page_cache_hits as f64 / accesses as f64)
+    }
+
+    /// Returns the ratio of journal buffers taken from the thread-local pool.
+    ///
+    /// Returns `None` if no journals have needed buffers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Statistics;
+    ///
+    /// let statistics = Statistics {
+    ///     journal_buffer_hits: 1,
+    ///     journal_buffer_misses: 1,
+    ///     ..Statistics::default()
+    /// };
+    /// assert_eq!(statistics.journal_buffer_hit_ratio(), Some(0.5));
+    /// ```
+    #[allow(clippy::cast_precision_loss)]
+    #[inline]
+    #[must_use]
+    pub fn journal_buffer_hit_ratio(&self) -> Option<f64> {
+        let requests = self.journal_buffer_hits + self.journal_buffer_misses;
+        (requests != 0).then(|| self.journal_buffer_hits as f64 / requests as f64)
+    }
+}
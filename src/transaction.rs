@@ -2,20 +2,36 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+use super::cancellation_token::Cancellable;
+use super::container::OptimisticAccess;
+#[cfg(feature = "diagnostics")]
+use super::diagnostics::AnchorTracker;
 use super::journal::Anchor as JournalAnchor;
+use super::journal::ObjectRead;
+use super::journal::RenamedContainer;
+use super::journal_pool;
 use super::snapshot::TransactionSnapshot;
-use super::{AwaitIO, Database, Error, Journal, PersistenceLayer, Sequencer, Snapshot};
+use super::sync;
+use super::task_processor::Task;
+use super::utils::Instant;
+use super::{
+    AwaitIO, CancellationToken, Change, Container, Counter, Database, Error, Journal, JournalID,
+    PersistenceLayer, Sequencer, Snapshot, TransactionReport, VersionRecord,
+};
 use scc::ebr;
 use scc::Bag;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::collections::hash_map;
 use std::collections::{BTreeMap, HashMap};
-use std::future::Future;
+use std::future::{poll_fn, Future};
+use std::mem::take;
 use std::num::{NonZeroU32, NonZeroU64};
 use std::pin::Pin;
-use std::ptr::addr_of;
-use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
-use std::sync::atomic::{AtomicU64, AtomicUsize};
-use std::sync::Arc;
+use std::ptr;
+use std::sync::atomic::Ordering::{AcqRel, Acquire, Relaxed, Release, SeqCst};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicU8, AtomicUsize};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::task::Waker;
 use std::task::{Context, Poll};
 
@@ -24,6 +40,9 @@ use std::task::{Context, Poll};
 /// A single strand of [`Journal`] constitutes a [`Transaction`], and an on-going transaction can
 /// be rewound to a certain instant by rolling back submitted [`Journal`] instances in reverse
 /// order.
+///
+/// An optimistic [`Transaction`] buffers its accesses to key-value pairs in [`Container`]
+/// instances without acquiring locks, and validates them when it is prepared for commit.
 #[derive(Debug)]
 pub struct Transaction<'d, S: Sequencer, P: PersistenceLayer<S>> {
     /// The transaction refers to the corresponding [`Database`] to persist pending changes at
@@ -44,6 +63,52 @@ pub struct Transaction<'d, S: Sequencer, P: PersistenceLayer<S>> {
     /// order.
     journal_strand: ebr::AtomicShared<JournalAnchor<S>>,
 
+    /// Changes to key-value pairs in submitted [`Journal`] instances along with their submit
+    /// instants.
+    ///
+    /// The bag is unordered in order for [`Journal`] instances to be submitted concurrently
+    /// without blocking, and changes are sorted by submit instant when the transaction is
+    /// committed.
+    submitted_changes: Bag<(NonZeroU32, Vec<Change>)>,
+
+    /// Changes to key-value pairs to be published when the transaction is committed along with
+    /// the commit instant.
+    committing_changes: Option<(S::Instant, Vec<Change>)>,
+
+    /// The commit instant of the transaction being committed that holds the durable and visible
+    /// clocks of the [`Database`] back until the transaction is ended.
+    committing_instant: Option<S::Instant>,
+
+    /// The transaction validates its accesses to key-value pairs at commit instead of acquiring
+    /// locks.
+    optimistic: bool,
+
+    /// Accesses to key-value pairs in submitted [`Journal`] instances of an optimistic
+    /// transaction along with their submit instants.
+    submitted_optimistic_accesses: Mutex<Vec<(NonZeroU32, Vec<OptimisticAccess>)>>,
+
+    /// Database objects read by submitted [`Journal`] instances along with their visibility and
+    /// the submit instants.
+    submitted_reads: Mutex<Vec<(NonZeroU32, Vec<ObjectRead>)>>,
+
+    /// Memory retained by submitted [`Journal`] instances in bytes along with their submit
+    /// instants.
+    submitted_memory_usage: Mutex<Vec<(NonZeroU32, usize)>>,
+
+    /// Database objects created or deleted by submitted [`Journal`] instances along with their
+    /// submit instants.
+    submitted_owned_objects: Mutex<Vec<(NonZeroU32, Vec<u64>)>>,
+
+    /// Containers renamed by submitted [`Journal`] instances along with their new names and the
+    /// submit instants.
+    submitted_renamed_containers: Mutex<Vec<(NonZeroU32, Vec<RenamedContainer>)>>,
+
+    /// The memory budget of the transaction in bytes.
+    memory_limit: Option<usize>,
+
+    /// Read-write dependencies of the transaction if the transaction is serializable.
+    serialization_anchor: Option<ebr::Shared<SerializationAnchor>>,
+
     /// The identifier of the [`Transaction`] as part of a distributed transaction.
     ///
     /// It is `None` if the transaction is not part of a distributed transaction.
@@ -57,14 +122,16 @@ pub struct Transaction<'d, S: Sequencer, P: PersistenceLayer<S>> {
 
 /// The type of transaction identifiers.
 ///
-/// The identifier of a transaction is only valid during the lifetime of the transaction. The same
-/// identifier can be used by an unrelated transaction afterwards.
+/// Identifiers are issued by the [`Database`] in increasing order, and they are not reused by the
+/// [`Database`]; identifiers of transactions recovered from the persistence layer are retained,
+/// and new transactions are given greater identifiers than them.
 ///
 /// The lower three bits are always zero.
 pub type ID = u64;
 
 /// Possible [`Transaction`] states.
 #[derive(Clone, Copy, Eq, Debug, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub enum State {
     /// The transaction is active.
     Active,
@@ -82,6 +149,95 @@ pub enum State {
     RolledBack,
 }
 
+/// The visibility of changes made by a transaction at an instant.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum Visibility {
+    /// The changes are visible unless they were rolled back.
+    Visible,
+
+    /// The changes are invisible.
+    Invisible,
+
+    /// The transaction is being committed, and the reader has to wait for it to end.
+    Undetermined,
+}
+
+/// The state of a [`Transaction`] observable from any thread.
+///
+/// The state can be queried through [`Transaction::state`], [`Journal::transaction_state`], and
+/// [`AccessController::owners`](super::AccessController::owners).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum TransactionState<I> {
+    /// The transaction is active.
+    Active,
+
+    /// The transaction is being committed.
+    Committing {
+        /// The instant when the transaction started to commit.
+        ///
+        /// The commit instant of the transaction is greater than the value.
+        prepare_instant: I,
+    },
+
+    /// The transaction is committed.
+    Committed {
+        /// The commit instant of the transaction.
+        commit_instant: I,
+    },
+
+    /// The transaction is being rolled back, or has been rolled back.
+    RolledBack,
+}
+
+/// [`IsolationLevel`] determines the degree to which a [`Transaction`] is isolated from others.
+///
+/// The isolation level only affects accesses to key-value pairs in
+/// [`Container`](super::Container) instances.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum IsolationLevel {
+    /// Every read sees the latest committed data, and writers lock the data they modify.
+    #[default]
+    Snapshot,
+
+    /// Serializable snapshot isolation.
+    ///
+    /// Read-write dependencies between concurrent serializable transactions are tracked, and a
+    /// transaction is rolled back with [`Error::SerializationFailure`] when it would form a
+    /// dangerous structure of two consecutive read-write dependencies; the execution of
+    /// serializable transactions is therefore equivalent to a serial execution. Only values read
+    /// through [`Container::read`](super::Container::read) are tracked, and false positives are
+    /// possible.
+    Serializable,
+}
+
+/// [`SerializationAnchor`] records read-write dependencies of a serializable [`Transaction`].
+///
+/// A read-write dependency from a reader to a writer is formed when the reader fails to see a
+/// value written by the concurrent writer. Values of the clock fields come from the logical clock
+/// of the [`AccessController`](super::AccessController).
+#[derive(Debug)]
+pub(super) struct SerializationAnchor {
+    /// The logical clock value when the transaction started.
+    start_clock: u64,
+
+    /// The logical clock value when the transaction was committed; `u64::MAX` until committed.
+    end_clock: AtomicU64,
+
+    /// The transaction has been certified to be committed.
+    prepared: AtomicBool,
+
+    /// The transaction was rolled back, and its dependencies are void.
+    rolled_back: AtomicBool,
+
+    /// A concurrent transaction depends on this transaction.
+    in_conflict: AtomicBool,
+
+    /// This transaction depends on a concurrent transaction.
+    out_conflict: AtomicBool,
+}
+
 /// [`Committable`] gives one last chance of rolling back the transaction.
 ///
 /// The transaction is bound to be rolled back if no actions are taken before dropping the
@@ -95,19 +251,37 @@ pub struct Committable<'d, S: Sequencer, P: PersistenceLayer<S>> {
     commit_log_io: Option<(AwaitIO<'d, S, P>, S::Instant)>,
 }
 
+/// [`CommitHandle`] reports the outcome of a [`Transaction`] after the [`Transaction`] is gone.
+///
+/// A transaction is only committed by awaiting its [`Committable`], and dropping either of them
+/// rolls the transaction back; a [`CommitHandle`] taken before the [`Transaction`] is handed over
+/// to code that does not return the commit instant lets the owner of the handle learn the commit
+/// instant, e.g., in order to take a [`Snapshot`] that sees the changes made by the transaction.
+#[derive(Debug)]
+pub struct CommitHandle<S: Sequencer> {
+    /// The anchor of the transaction.
+    anchor: ebr::Shared<Anchor<S>>,
+}
+
 /// `u32::MAX - 1` is the last clock value that a transaction clock can reach.
 ///
 /// [`Transaction`] cannot generate a clock value that is greater than [`MAX_TRANSACTION_INSTANT`],
 /// and changes made after the instant can never be visible to any other jobs in the same
 /// transaction.
 #[allow(clippy::undocumented_unsafe_blocks)]
-pub const MAX_TRANSACTION_INSTANT: NonZeroU32 = unsafe { NonZeroU32::new_unchecked(u32::MAX - 1) };
+pub const MAX_TRANSACTION_INSTANT: NonZeroU32 = NonZeroU32::new(u32::MAX - 1).unwrap();
 
 /// [`Playback`] is a type of transaction during [`Database`] recovery.
 ///
 /// [`Playback`] is almost the same with [`Transaction`] except that it never generates log
 /// records, and the sole purpose of the type is to apply the database change history stored in the
 /// log file to the [`Database`].
+///
+/// A [`PersistenceLayer`] replays each logged transaction through a [`Playback`] in
+/// [`PersistenceLayer::recover`]: the changes made by journals are played back with
+/// [`Playback::create`], [`Playback::delete`], and [`Playback::write`], followed by
+/// [`Playback::submit_journal`] or [`Playback::discard_journal`], and the transaction is ended
+/// with [`Playback::commit`] or [`Playback::rollback`].
 #[allow(dead_code)]
 #[derive(Debug)]
 pub struct Playback<'d, S: Sequencer, P: PersistenceLayer<S>> {
@@ -129,16 +303,34 @@ pub struct Playback<'d, S: Sequencer, P: PersistenceLayer<S>> {
     /// It is `None` if the transaction is not part of a distributed transaction.
     xid: Option<Box<[u8]>>,
 
+    /// Containers renamed by the transaction along with their new names and the instants at which
+    /// the journals renaming them were submitted.
+    ///
+    /// The instant is `None` until the journal writing the new catalog entry is submitted.
+    renamed_containers: Vec<(Option<u32>, u64, Arc<str>)>,
+
     /// A piece of data that is shared between [`Journal`] and [`Transaction`].
     ///
     /// It outlives the [`Transaction`], and it is dropped when no database objects refer to it.
     anchor: ebr::Shared<Anchor<S>>,
 }
 
+/// The transaction can be aborted.
+const ABORTABLE: usize = 0;
+
+/// The transaction was aborted.
+const ABORTED: usize = 1;
+
+/// The transaction began being prepared, and it can no longer be aborted.
+const SEALED: usize = 2;
+
 /// [Anchor] contains data that is required to outlive the [Transaction] instance.
 #[derive(Debug)]
 #[repr(align(16))]
 pub(super) struct Anchor<S: Sequencer> {
+    /// The identifier of the transaction.
+    id: ID,
+
     /// The transaction state.
     ///
     /// An integer represents a transaction state.
@@ -147,23 +339,82 @@ pub(super) struct Anchor<S: Sequencer> {
     ///  * 2: committed.
     ///  * 3: abort started.
     ///  * 4: aborted.
-    state: AtomicUsize,
+    state: sync::AtomicUsize,
 
     /// The instant when the commit has begun.
-    prepare_instant: S::Instant,
+    ///
+    /// It is set before the state becomes [`State::Committing`], and readers are allowed to read
+    /// it once they observe the state.
+    prepare_instant: OnceLock<S::Instant>,
 
     /// The instant when the commit is completed.
-    commit_instant: S::Instant,
+    ///
+    /// It is set before the state becomes [`State::Committed`], and readers are allowed to read it
+    /// once they observe the state.
+    commit_instant: OnceLock<S::Instant>,
+
+    /// The commit instant of the transaction that released its locks before its commit log
+    /// record was persisted.
+    release_instant: OnceLock<S::Instant>,
+
+    /// Transactions that released the locks that the transaction took over.
+    ///
+    /// The transaction cannot be committed until they are committed.
+    commit_dependencies: Bag<ebr::Shared<Anchor<S>>>,
+
+    /// Transactions that took over the locks that the transaction released.
+    ///
+    /// They are allowed to see the changes made by the transaction before the transaction is
+    /// committed.
+    dependents: Mutex<Vec<ebr::Shared<Anchor<S>>>>,
+
+    /// The logical clock value when the transaction started.
+    ///
+    /// The value is used by [`ConflictPolicy`](super::ConflictPolicy) to determine which
+    /// transaction is older.
+    start_clock: u64,
+
+    /// The transaction was wounded by an older transaction, and it has to be rolled back.
+    wounded: sync::AtomicBool,
+
+    /// Whether the transaction can be aborted by another thread.
+    ///
+    ///  * 0: the transaction can be aborted.
+    ///  * 1: the transaction was aborted, and it has to be rolled back.
+    ///  * 2: the transaction began being prepared, and it cannot be aborted.
+    abort_state: sync::AtomicUsize,
 
-    /// An unordered bag of [`Waker`] for readers.
+    /// An unordered bag of [`Waker`] for readers and wounded journals.
     waiting_readers: Bag<Waker, 4>,
+
+    /// The [`CancellationToken`] attached to the transaction.
+    cancellation_token: OnceLock<CancellationToken>,
+
+    /// The application label attached to the transaction.
+    tag: OnceLock<Box<str>>,
+
+    /// The time when the transaction started.
+    start_time: Instant,
+
+    /// The number of submitted [`Journal`] instances.
+    num_submitted_journals: AtomicU32,
+
+    /// Memory retained by submitted [`Journal`] instances in bytes.
+    memory_usage: AtomicUsize,
+
+    /// The priority of the transaction when waiting for database objects.
+    priority: AtomicU8,
+
+    /// The [`AnchorTracker`] removing the trace of the anchor from the registry when dropped.
+    #[cfg(feature = "diagnostics")]
+    tracker: OnceLock<AnchorTracker>,
 }
 
 impl<'d, S: Sequencer, P: PersistenceLayer<S>> Transaction<'d, S, P> {
     /// The transaction identifier.
     ///
-    /// The identifier is unique in the process, however the same identifier can be used after the
-    /// transaction is committed or rolled back by an unrelated database transaction.
+    /// The identifier is recorded in the log records of the transaction, and it is not reused by
+    /// the [`Database`].
     ///
     /// # Examples
     ///
@@ -179,8 +430,32 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Transaction<'d, S, P> {
     /// ```
     #[inline]
     pub fn id(&self) -> ID {
-        debug_assert_eq!((self.anchor.as_ptr() as ID) & 0b111, 0);
-        self.anchor.as_ptr() as ID
+        self.anchor.id()
+    }
+
+    /// Returns a [`CommitHandle`] of the [`Transaction`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("commit_handle")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let commit_handle = transaction.commit_handle();
+    ///     let _: Result<_, _> = transaction.commit().await;
+    ///     let commit_instant = commit_handle.commit_instant().unwrap();
+    ///     assert!(database.snapshot_at(commit_instant).is_some());
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn commit_handle(&self) -> CommitHandle<S> {
+        CommitHandle {
+            anchor: self.anchor.clone(),
+        }
     }
 
     /// Creates a new [`Journal`].
@@ -225,7 +500,7 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Transaction<'d, S, P> {
     /// };
     /// ```
     #[inline]
-    pub fn snapshot<'t>(&'t self) -> Snapshot<'d, 't, '_, S> {
+    pub fn snapshot<'t>(&'t self) -> Snapshot<'d, 't, 't, S> {
         Snapshot::from_transaction(self.database, self.transaction_snapshot(self.now()))
     }
 
@@ -288,6 +563,280 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Transaction<'d, S, P> {
             .and_then(|j| j.submit_instant().map(|i| i.min(MAX_TRANSACTION_INSTANT)))
     }
 
+    /// Returns the current state of the [`Transaction`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, TransactionState};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("state")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     assert_eq!(transaction.state(), TransactionState::Active);
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn state(&self) -> TransactionState<S::Instant> {
+        self.anchor.transaction_state()
+    }
+
+    /// Attaches a [`CancellationToken`] to the [`Transaction`].
+    ///
+    /// Once the token is cancelled, any [`Journal`] of the transaction waiting for a database
+    /// object and the transaction waiting for its commit log record to be persisted return
+    /// [`Error::Cancelled`]. A transaction that failed to be committed due to cancellation is
+    /// rolled back.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnexpectedState`] if a [`CancellationToken`] is already attached.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{CancellationToken, Database};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("set_cancellation_token")).await.unwrap();
+    ///     let mut transaction = database.transaction();
+    ///     let token = CancellationToken::default();
+    ///     assert!(transaction.set_cancellation_token(token.clone()).is_ok());
+    ///     assert!(transaction.set_cancellation_token(token).is_err());
+    /// };
+    /// ```
+    #[inline]
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) -> Result<(), Error> {
+        self.anchor
+            .cancellation_token
+            .set(token)
+            .map_err(|_| Error::UnexpectedState)
+    }
+
+    /// Attaches an application label to the [`Transaction`].
+    ///
+    /// Counters attributable to the transaction, e.g., commits, rollbacks, and lock waits, are
+    /// aggregated for each tag in [`Database::tagged_statistics`], so that contention can be
+    /// attributed to workloads sharing the same [`Database`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnexpectedState`] if a tag is already attached.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("set_tag")).await.unwrap();
+    ///     let mut transaction = database.transaction();
+    ///     assert!(transaction.set_tag("tenant-a").is_ok());
+    ///     assert!(transaction.set_tag("tenant-b").is_err());
+    ///     assert_eq!(transaction.tag(), Some("tenant-a"));
+    ///     assert!(transaction.commit().await.is_ok());
+    ///     assert_eq!(database.tagged_statistics()["tenant-a"].transactions_committed, 1);
+    /// };
+    /// ```
+    #[inline]
+    pub fn set_tag(&mut self, tag: &str) -> Result<(), Error> {
+        self.anchor
+            .tag
+            .set(tag.into())
+            .map_err(|_| Error::UnexpectedState)?;
+        self.database
+            .telemetry()
+            .add_to_tag(Counter::TransactionsStarted, 1, tag);
+        Ok(())
+    }
+
+    /// Returns the tag attached to the [`Transaction`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("tag")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     assert!(transaction.tag().is_none());
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn tag(&self) -> Option<&str> {
+        self.anchor.tag()
+    }
+
+    /// Sets the priority of the [`Transaction`].
+    ///
+    /// The priority is used to order transactions waiting for database objects if
+    /// [`WaitPolicy::Priority`](super::WaitPolicy::Priority) is set for the
+    /// [`AccessController`](super::AccessController); a transaction of higher priority gains access
+    /// first. The default priority is `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("set_priority")).await.unwrap();
+    ///     let mut transaction = database.transaction();
+    ///     assert_eq!(transaction.priority(), 0);
+    ///     transaction.set_priority(8);
+    ///     assert_eq!(transaction.priority(), 8);
+    /// };
+    /// ```
+    #[inline]
+    pub fn set_priority(&mut self, priority: u8) {
+        self.anchor.priority.store(priority, Relaxed);
+    }
+
+    /// Returns the priority of the [`Transaction`].
+    #[inline]
+    #[must_use]
+    pub fn priority(&self) -> u8 {
+        self.anchor.priority()
+    }
+
+    /// Sets the memory budget of the [`Transaction`] in bytes.
+    ///
+    /// Changes to key-value pairs retained by the transaction until it is committed are accounted
+    /// for, and further changes fail with [`Error::OutOfMemoryBudget`] once the budget would be
+    /// exceeded; the changes retained by a [`Journal`] are released when the [`Journal`] is
+    /// rolled back or rewound. `None` means no limit, and the default budget is set by
+    /// [`Database::set_transaction_memory_limit`](super::Database::set_transaction_memory_limit).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Error, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("set_memory_limit")).await.unwrap();
+    ///     let mut transaction = database.transaction();
+    ///     transaction.set_memory_limit(Some(4));
+    ///     let mut journal = transaction.journal();
+    ///     let container = database
+    ///         .create_container("hello".to_string(), Metadata::default(), &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     assert_eq!(
+    ///         container.insert(b"1", b"one", &mut journal, None).await,
+    ///         Err(Error::OutOfMemoryBudget)
+    ///     );
+    /// };
+    /// ```
+    #[inline]
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.memory_limit = limit;
+    }
+
+    /// Returns the memory budget of the [`Transaction`] in bytes.
+    #[inline]
+    #[must_use]
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
+
+    /// Returns the memory retained by submitted [`Journal`] instances of the [`Transaction`] in
+    /// bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, Metadata};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("memory_usage")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let mut journal = transaction.journal();
+    ///     let container = database
+    ///         .create_container("hello".to_string(), Metadata::default(), &mut journal, None)
+    ///         .await
+    ///         .unwrap();
+    ///     assert!(container.insert(b"1", b"one", &mut journal, None).await.is_ok());
+    ///     assert_eq!(transaction.memory_usage(), 0);
+    ///     journal.submit();
+    ///     assert!(transaction.memory_usage() > 0);
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn memory_usage(&self) -> usize {
+        self.submitted_memory_usage
+            .lock()
+            .map_or(0, |m| m.iter().map(|(_, u)| u).sum())
+    }
+
+    /// Sets the [`IsolationLevel`] of the [`Transaction`].
+    ///
+    /// The isolation level should be set before the transaction accesses any key-value pairs;
+    /// accesses made by the transaction before it becomes serializable are not tracked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, IsolationLevel};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("set_isolation_level")).await.unwrap();
+    ///     let mut transaction = database.transaction();
+    ///     transaction.set_isolation_level(IsolationLevel::Serializable);
+    ///     assert_eq!(transaction.isolation_level(), IsolationLevel::Serializable);
+    /// };
+    /// ```
+    #[inline]
+    pub fn set_isolation_level(&mut self, isolation_level: IsolationLevel) {
+        match (isolation_level, self.serialization_anchor.is_some()) {
+            (IsolationLevel::Serializable, false) => {
+                let start_clock = self.anchor.start_clock();
+                self.database
+                    .access_controller()
+                    .begin_serializable(start_clock);
+                self.serialization_anchor
+                    .replace(ebr::Shared::new(SerializationAnchor::new(start_clock)));
+            }
+            (IsolationLevel::Snapshot, true) => self.end_serializable(false),
+            _ => (),
+        }
+    }
+
+    /// Returns the [`IsolationLevel`] of the [`Transaction`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::{Database, IsolationLevel};
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("isolation_level")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     assert_eq!(transaction.isolation_level(), IsolationLevel::Snapshot);
+    /// };
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn isolation_level(&self) -> IsolationLevel {
+        if self.serialization_anchor.is_some() {
+            IsolationLevel::Serializable
+        } else {
+            IsolationLevel::Snapshot
+        }
+    }
+
     /// Rewinds the [`Transaction`] to the given point of time.
     ///
     /// All the changes made after the specified instant are rolled back and returns the updated
@@ -321,17 +870,67 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Transaction<'d, S, P> {
     /// ```
     #[inline]
     pub fn rewind(&mut self, instant: Option<NonZeroU32>) -> Result<Option<NonZeroU32>, Error> {
+        let mut wake_up_others = false;
         let mut current = self.journal_strand.swap((None, ebr::Tag::None), Acquire).0;
         while let Some(record) = current {
             if record.submit_instant() <= instant {
                 current = Some(record);
                 break;
             }
-            record.rollback(self.database.task_processor());
+            wake_up_others |= record.revert();
             current = record.set_next(None, Relaxed).0;
         }
+        if wake_up_others {
+            // Transactions waiting for resources acquired by any of the rolled back journals are
+            // woken up at once; the result can be ignored since messages pending in the queue
+            // mean that the access controller will be scanned in the future.
+            self.database
+                .task_processor()
+                .send_task(Task::ScanAccessController);
+        }
+
         let new_instant = current.as_ref().and_then(|r| r.submit_instant());
         self.journal_strand.swap((current, ebr::Tag::None), Relaxed);
+        let mut discarded_changes = Vec::new();
+        self.submitted_changes =
+            self.submitted_changes
+                .pop_all(Bag::default(), |retained, (i, changes)| {
+                    if Some(i) <= new_instant {
+                        retained.push((i, changes));
+                    } else {
+                        discarded_changes.push(changes);
+                    }
+                    retained
+                });
+        let mut discarded_accesses = Vec::new();
+        if let Ok(accesses) = self.submitted_optimistic_accesses.get_mut() {
+            (*accesses, discarded_accesses) = take(accesses)
+                .into_iter()
+                .partition(|(i, _)| Some(*i) <= new_instant);
+        }
+        if !discarded_changes.is_empty() || !discarded_accesses.is_empty() {
+            // Releasing the memory retained by the rolled back journals is deferred until the
+            // epoch advances in order not to make the latency depend on the size of the changes.
+            ebr::Guard::new().defer_execute(move || drop((discarded_changes, discarded_accesses)));
+        }
+        if let Ok(reads) = self.submitted_reads.get_mut() {
+            reads.retain(|(i, _)| Some(*i) <= new_instant);
+        }
+        if let Ok(owned_objects) = self.submitted_owned_objects.get_mut() {
+            owned_objects.retain(|(i, _)| Some(*i) <= new_instant);
+        }
+        if let Ok(renamed_containers) = self.submitted_renamed_containers.get_mut() {
+            renamed_containers.retain(|(i, _)| Some(*i) <= new_instant);
+        }
+        if let Ok(memory_usage) = self.submitted_memory_usage.get_mut() {
+            memory_usage.retain(|(i, _)| Some(*i) <= new_instant);
+            self.anchor
+                .memory_usage
+                .store(memory_usage.iter().map(|(_, u)| u).sum(), Relaxed);
+        }
+        self.anchor
+            .num_submitted_journals
+            .store(new_instant.map_or(0, NonZeroU32::get), Relaxed);
 
         if let Some(eot_log_buffer) = self.eot_log_buffer.take() {
             self.database
@@ -353,6 +952,18 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Transaction<'d, S, P> {
     /// # Errors
     ///
     /// If the transaction could not be prepared for commit, an [`Error`] is returned.
+    /// [`Error::Deadlock`] is returned if the transaction was wounded by an older transaction
+    /// under [`ConflictPolicy::WoundWait`](super::ConflictPolicy::WoundWait), and the transaction
+    /// is rolled back. [`Error::ShutDown`] is returned if the transaction was started after the
+    /// database began shutting down or was aborted by [`Database::shutdown`], and the transaction
+    /// is rolled back. [`Error::Conflict`] is returned if the transaction is optimistic and any
+    /// key-value pairs it accessed were modified by other transactions, or the visibility of any
+    /// database objects read with [`Journal::read`] was changed by other transactions, and the
+    /// transaction is rolled back. [`Error::SerializationFailure`] is returned if the transaction is
+    /// serializable and both depends on and is depended on by concurrent transactions, and the
+    /// transaction is rolled back. [`Error::Aborted`] is returned if the transaction was aborted
+    /// by [`Database::abort_transaction`](super::Database::abort_transaction), and the transaction
+    /// is rolled back.
     ///
     /// # Examples
     ///
@@ -368,21 +979,47 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Transaction<'d, S, P> {
     ///     };
     /// };
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(transaction_id = self.id()))
+    )]
     #[inline]
     pub async fn prepare(self) -> Result<Committable<'d, S, P>, Error> {
         debug_assert_eq!(self.anchor.state.load(Relaxed), State::Active.into());
 
-        let prepare_instant = self.sequencer().now(Relaxed);
+        if !self.anchor.seal() {
+            // The transaction was aborted by another thread before anything was written for
+            // commit, and it is rolled back when dropped.
+            return Err(Error::Aborted);
+        }
+        if self.anchor.is_wounded() {
+            // The transaction was wounded by an older transaction or rejected by a database being
+            // shut down, and it is rolled back when dropped.
+            if self.database.is_shut_down() {
+                return Err(Error::ShutDown);
+            }
+            return Err(Error::Deadlock);
+        }
+
+        if self.optimistic {
+            self.install_optimistic_accesses().await?;
+        }
+
+        // The transaction is rolled back when dropped.
+        self.validate_reads().await?;
 
-        // Safety: it is the sole writer of its own `anchor`.
-        unsafe {
-            let anchor_mut_ref = &mut *(addr_of!(*self.anchor).cast_mut());
-            anchor_mut_ref.prepare_instant = prepare_instant;
-            anchor_mut_ref
-                .state
-                .store(State::Committing.into(), Release);
+        // The transaction is rolled back when dropped.
+        self.await_commit_dependencies().await?;
+
+        if let Some(serialization_anchor) = self.serialization_anchor.as_ref() {
+            // The transaction is rolled back when dropped.
+            serialization_anchor.certify()?;
         }
 
+        let prepare_instant = self.sequencer().now(Relaxed);
+
+        self.anchor.prepare(prepare_instant);
+
         let io_completion = self.database.persistence_layer().prepare(
             Arc::default(),
             self.id(),
@@ -390,7 +1027,7 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Transaction<'d, S, P> {
             None,
         );
         if self.determine_need_for_io_completion(false) {
-            io_completion.await?;
+            Cancellable::new(io_completion, self.anchor.cancellation_token()).await?;
         }
 
         Ok(Committable {
@@ -417,6 +1054,10 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Transaction<'d, S, P> {
     ///     assert!(transaction.commit().await.is_ok());
     /// };
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(transaction_id = self.id()))
+    )]
     #[inline]
     pub async fn commit(self) -> Result<S::Instant, Error> {
         let indoubt_transaction = self.prepare().await?;
@@ -449,15 +1090,44 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Transaction<'d, S, P> {
     }
 
     /// Creates a new [`Transaction`].
-    pub(crate) fn new(database: &'d Database<S, P>) -> Transaction<'d, S, P> {
-        Transaction {
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub(crate) fn new(database: &'d Database<S, P>, optimistic: bool) -> Transaction<'d, S, P> {
+        database.telemetry().add(Counter::TransactionsStarted, 1);
+        let transaction = Transaction {
             database,
             durable_flush_epoch: AtomicU64::new(0),
             eot_log_buffer: Some(Arc::default()),
             journal_strand: ebr::AtomicShared::null(),
+            submitted_changes: Bag::default(),
+            committing_changes: None,
+            committing_instant: None,
+            optimistic,
+            submitted_optimistic_accesses: Mutex::default(),
+            submitted_reads: Mutex::default(),
+            submitted_memory_usage: Mutex::default(),
+            submitted_owned_objects: Mutex::default(),
+            submitted_renamed_containers: Mutex::default(),
+            memory_limit: database.transaction_memory_limit(),
+            serialization_anchor: None,
             xid: None,
-            anchor: ebr::Shared::new(Anchor::new()),
+            anchor: ebr::Shared::new(Anchor::new(
+                database.new_transaction_id(),
+                database.access_controller().next_start_clock(),
+            )),
+        };
+        #[cfg(feature = "diagnostics")]
+        let _: Result<(), _> = transaction.anchor.tracker.set(
+            database
+                .anchor_registry()
+                .track(transaction.id(), std::panic::Location::caller()),
+        );
+        if !database.register_transaction(transaction.id(), &transaction.anchor) {
+            // The database is shut down, therefore the transaction cannot be committed.
+            transaction.anchor.wound();
         }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(transaction_id = transaction.id(), "transaction started");
+        transaction
     }
 
     /// Returns a reference to its associated [`Sequencer`].
@@ -470,11 +1140,41 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Transaction<'d, S, P> {
         self.database
     }
 
-    /// Submits a [`Journal`].
-    pub(super) fn submit_journal(
+    /// Tracks database objects created or deleted by a submitted [`Journal`] in order to
+    /// consolidate them after the transaction is committed.
+    pub(super) fn submit_owned_objects(&self, submit_instant: NonZeroU32, object_ids: Vec<u64>) {
+        if object_ids.is_empty() {
+            return;
+        }
+        if let Ok(mut owned_objects) = self.submitted_owned_objects.lock() {
+            owned_objects.push((submit_instant, object_ids));
+        }
+    }
+
+    /// Tracks containers renamed by a submitted [`Journal`] in order to change their names after
+    /// the transaction is committed.
+    pub(super) fn submit_renamed_containers(
+        &self,
+        submit_instant: NonZeroU32,
+        renamed_containers: Vec<RenamedContainer>,
+    ) {
+        if renamed_containers.is_empty() {
+            return;
+        }
+        if let Ok(mut submitted) = self.submitted_renamed_containers.lock() {
+            submitted.push((submit_instant, renamed_containers));
+        }
+    }
+
+    /// Submits a [`Journal`].
+    pub(super) fn submit_journal(
         &self,
         anchor: &ebr::Shared<JournalAnchor<S>>,
         log_buffer: Option<Arc<P::LogBuffer>>,
+        changes: Vec<Change>,
+        optimistic_accesses: Vec<OptimisticAccess>,
+        reads: Vec<ObjectRead>,
+        memory_usage: usize,
     ) -> NonZeroU32 {
         let barrier = ebr::Guard::new();
         let mut current = self.journal_strand.load(Relaxed, &barrier);
@@ -488,6 +1188,29 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Transaction<'d, S, P> {
                 &barrier,
             ) {
                 Ok(_) => {
+                    if !changes.is_empty() {
+                        self.submitted_changes.push((submit_instant, changes));
+                    }
+                    if !optimistic_accesses.is_empty() {
+                        if let Ok(mut accesses) = self.submitted_optimistic_accesses.lock() {
+                            accesses.push((submit_instant, optimistic_accesses));
+                        }
+                    }
+                    if !reads.is_empty() {
+                        if let Ok(mut submitted_reads) = self.submitted_reads.lock() {
+                            submitted_reads.push((submit_instant, reads));
+                        }
+                    }
+                    if memory_usage != 0 {
+                        if let Ok(mut submitted) = self.submitted_memory_usage.lock() {
+                            submitted.push((submit_instant, memory_usage));
+                        }
+                        self.anchor.memory_usage.fetch_add(memory_usage, Relaxed);
+                    }
+                    self.anchor
+                        .num_submitted_journals
+                        .fetch_max(submit_instant.get(), Relaxed);
+
                     // Pass the log buffer to the persistence layer.
                     if let Some(log_buffer) = log_buffer {
                         self.database.persistence_layer().submit(
@@ -524,6 +1247,13 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Transaction<'d, S, P> {
                     // Write access to any changes made in the journal can be granted after the
                     // anchor is marked `submitted`.
                     anchor.submit(self.database().task_processor());
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(
+                        transaction_id = self.id(),
+                        journal_id = anchor.id(),
+                        submit_instant,
+                        "journal submitted"
+                    );
                     return submit_instant;
                 }
                 Err((_, actual)) => current = actual,
@@ -531,12 +1261,131 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Transaction<'d, S, P> {
         }
     }
 
+    /// Returns the [`SerializationAnchor`] if the transaction is serializable.
+    pub(super) fn serialization_anchor(&self) -> Option<&ebr::Shared<SerializationAnchor>> {
+        self.serialization_anchor.as_ref()
+    }
+
+    /// Returns `true` if the transaction is optimistic.
+    pub(super) fn is_optimistic(&self) -> bool {
+        self.optimistic
+    }
+
+    /// Finds the latest access to a key-value pair in submitted [`Journal`] instances with the
+    /// supplied function.
+    pub(super) fn find_optimistic_access<R, F: FnMut(&OptimisticAccess) -> Option<R>>(
+        &self,
+        mut f: F,
+    ) -> Option<R> {
+        let accesses = self.submitted_optimistic_accesses.lock().ok()?;
+        let mut latest: Option<(NonZeroU32, R)> = None;
+        for (i, accesses) in accesses.iter() {
+            if latest.as_ref().is_some_and(|(l, _)| l > i) {
+                continue;
+            }
+            if let Some(r) = accesses.iter().rev().find_map(&mut f) {
+                latest.replace((*i, r));
+            }
+        }
+        latest.map(|(_, r)| r)
+    }
+
+    /// Validates and installs the accesses to key-value pairs made by the optimistic transaction.
+    async fn install_optimistic_accesses(&self) -> Result<(), Error> {
+        let mut accesses = self
+            .submitted_optimistic_accesses
+            .lock()
+            .map_or_else(|_| Vec::new(), |mut a| take(&mut *a));
+        if accesses.is_empty() {
+            return Ok(());
+        }
+        accesses.sort_by_key(|(i, _)| *i);
+        let mut journal = self.journal();
+        Container::<S, P>::install_optimistic_accesses(
+            accesses.iter().flat_map(|(_, a)| a.iter()),
+            &mut journal,
+        )
+        .await?;
+        let _: NonZeroU32 = journal.submit();
+        for (_, accesses) in accesses {
+            journal_pool::recycle(accesses);
+        }
+        Ok(())
+    }
+
+    /// Validates that the database objects read by submitted [`Journal`] instances are equally
+    /// visible to the latest committed state of the database.
+    async fn validate_reads(&self) -> Result<(), Error> {
+        let reads = self
+            .submitted_reads
+            .lock()
+            .map_or_else(|_| Vec::new(), |mut r| take(&mut *r));
+        if reads.is_empty() {
+            return Ok(());
+        }
+        let mut reads = journal_pool::concat(reads.into_iter().map(|(_, r)| r));
+        reads.sort_unstable();
+        reads.dedup();
+        let access_controller = self.database.access_controller();
+        let snapshot = self.database.snapshot();
+        for &(object_id, visible) in &reads {
+            let owners = access_controller.owners(object_id).await;
+            if owners.iter().any(|(id, _)| *id == self.id()) {
+                // Changes made by the transaction itself do not conflict.
+                continue;
+            }
+            if access_controller.read(object_id, &snapshot, None).await? != visible {
+                let owner = owners.first().map(|(id, _)| *id);
+                return Err(Error::conflict(object_id, owner));
+            }
+        }
+        drop(snapshot);
+        journal_pool::recycle(reads);
+        Ok(())
+    }
+
     /// Returns the memory address of its [`Anchor`].
-    pub(super) fn transaction_snapshot(&self, instant: Option<NonZeroU32>) -> TransactionSnapshot {
+    pub(super) fn transaction_snapshot(
+        &self,
+        instant: Option<NonZeroU32>,
+    ) -> TransactionSnapshot<'_> {
         debug_assert!(instant <= self.now());
         TransactionSnapshot::new(self.id(), instant)
     }
 
+    /// Waits for the transactions that released the locks taken over by the transaction to be
+    /// committed.
+    ///
+    /// Returns [`Error::SerializationFailure`] if any of them was rolled back.
+    async fn await_commit_dependencies(&self) -> Result<(), Error> {
+        while let Some(dependency) = self.anchor.commit_dependencies.pop() {
+            let eot_instant = poll_fn(|cx| {
+                dependency
+                    .wait_eot(cx.waker().clone())
+                    .map_or(Poll::Pending, Poll::Ready)
+            })
+            .await;
+            if eot_instant == S::Instant::default() {
+                // The changes that the transaction is based on were rolled back.
+                return Err(Error::SerializationFailure);
+            }
+        }
+        Ok(())
+    }
+
+    /// Releases the locks of the transaction before its commit log record is persisted if early
+    /// lock release is enabled.
+    fn release_locks(&self, commit_instant: S::Instant) {
+        if self.database.access_controller().early_lock_release() {
+            self.anchor.release_locks(commit_instant);
+
+            // Waiting transactions can take over the locks from now on.
+            self.database
+                .task_processor()
+                .send_task(Task::ScanAccessController);
+        }
+    }
+
     /// Returns `true` if the transaction needs to wait for an IO completion.
     fn determine_need_for_io_completion(&self, commit_log_record: bool) -> bool {
         debug_assert_eq!(self.anchor.state.load(Relaxed), State::Committing.into());
@@ -544,7 +1393,7 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Transaction<'d, S, P> {
         // The transaction is a part of a distributed transaction, or the transaction has modified
         // the database and the modification log has yet to be persisted.
         (!commit_log_record && self.xid.is_some())
-            || NonZeroU64::new(self.durable_flush_epoch.load(Relaxed)).map_or(false, |f| {
+            || NonZeroU64::new(self.durable_flush_epoch.load(Relaxed)).is_some_and(|f| {
                 // A commit log record should always be completed before the transaction is closed
                 // if the transaction has modified the database.
                 commit_log_record
@@ -556,7 +1405,25 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Transaction<'d, S, P> {
     /// Generates a commit log record.
     fn generate_commit_log_record(&mut self) -> Result<(AwaitIO<'d, S, P>, S::Instant), Error> {
         if let Some(eot_log_buffer) = self.eot_log_buffer.take() {
-            let commit_instant = self.sequencer().advance(Release);
+            let mut submitted_changes: Vec<(NonZeroU32, Vec<Change>)> =
+                take(&mut self.submitted_changes).into_iter().collect();
+            submitted_changes.sort_by_key(|(i, _)| *i);
+            let changes = journal_pool::concat(submitted_changes.into_iter().map(|(_, c)| c));
+            let commit_watermark = self.database.commit_watermark();
+            let commit_instant = if changes.is_empty() {
+                commit_watermark.reserve(self.sequencer(), || self.sequencer().advance(Release))
+            } else {
+                // The commit instant is generated while the change log is locked in order for
+                // changes to be published in commit order.
+                let commit_instant = commit_watermark.reserve(self.sequencer(), || {
+                    self.database
+                        .change_log()
+                        .reserve(|| self.sequencer().advance(Release))
+                });
+                self.committing_changes.replace((commit_instant, changes));
+                commit_instant
+            };
+            self.committing_instant.replace(commit_instant);
             let io_completion = self.database.persistence_layer().commit(
                 eot_log_buffer,
                 self.id(),
@@ -576,17 +1443,62 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Transaction<'d, S, P> {
         debug_assert_ne!(commit_instant, S::Instant::default());
         debug_assert_eq!(self.anchor.state.load(Relaxed), State::Committing.into());
 
-        // Safety: it is the sole writer of its own `anchor`.
-        let anchor_mut_ref = unsafe { &mut *(addr_of!(*self.anchor).cast_mut()) };
-        anchor_mut_ref.commit_instant = commit_instant;
-        anchor_mut_ref.state.store(State::Committed.into(), Release);
-        self.anchor.wake_up();
+        self.anchor.commit(commit_instant);
+        self.database.deregister_transaction(self.id());
+        self.database
+            .telemetry()
+            .add_tagged(Counter::TransactionsCommitted, 1, self.anchor.tag());
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            transaction_id = self.id(),
+            ?commit_instant,
+            "transaction committed"
+        );
+
+        if let Some((instant, changes)) = self.committing_changes.take() {
+            debug_assert_eq!(instant, commit_instant);
+            self.database.change_log().publish(commit_instant, changes);
+        }
+        if let Some(instant) = self.committing_instant.take() {
+            debug_assert_eq!(instant, commit_instant);
+            self.database.commit_watermark().complete(commit_instant);
+        }
 
         let mut current = self.journal_strand.swap((None, ebr::Tag::None), Acquire).0;
         while let Some(record) = current {
             record.commit(self.database.task_processor());
             current = record.set_next(None, Relaxed).0;
         }
+        if let Ok(owned_objects) = self.submitted_owned_objects.get_mut() {
+            let object_ids = take(owned_objects)
+                .into_iter()
+                .flat_map(|(_, o)| o)
+                .collect();
+            self.database
+                .access_controller()
+                .schedule_consolidation(commit_instant, object_ids);
+        }
+        if let Ok(renamed_containers) = self.submitted_renamed_containers.get_mut() {
+            let mut renamed_containers = take(renamed_containers);
+            renamed_containers.sort_by_key(|(i, _)| *i);
+            self.database
+                .rename_containers(renamed_containers.into_iter().flat_map(|(_, r)| r));
+        }
+
+        self.end_serializable(true);
+    }
+
+    /// Stops tracking read-write dependencies of the transaction.
+    fn end_serializable(&mut self, committed: bool) {
+        if let Some(serialization_anchor) = self.serialization_anchor.take() {
+            let access_controller = self.database.access_controller();
+            if committed {
+                serialization_anchor.end(access_controller.next_start_clock());
+            } else {
+                serialization_anchor.rolled_back.store(true, SeqCst);
+            }
+            access_controller.end_serializable(serialization_anchor.start_clock);
+        }
     }
 
     /// Rolls back all the changes.
@@ -601,11 +1513,30 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Transaction<'d, S, P> {
         let result = self.rewind(None);
         debug_assert_eq!(result, Ok(None));
 
+        if let Some((instant, _)) = self.committing_changes.take() {
+            self.database.change_log().withdraw(instant);
+        }
+        if let Some(instant) = self.committing_instant.take() {
+            self.database.commit_watermark().complete(instant);
+        }
+
+        while self.anchor.commit_dependencies.pop().is_some() {}
+
+        self.end_serializable(false);
+
         self.anchor.state.store(State::RolledBack.into(), Release);
+        self.anchor.clear_dependents();
+        self.anchor.wake_up();
+        self.database.deregister_transaction(self.id());
+        self.database
+            .telemetry()
+            .add_tagged(Counter::TransactionsRolledBack, 1, self.anchor.tag());
+        #[cfg(feature = "tracing")]
+        tracing::debug!(transaction_id = self.id(), "transaction rolled back");
     }
 }
 
-impl<'d, S: Sequencer, P: PersistenceLayer<S>> Drop for Transaction<'d, S, P> {
+impl<S: Sequencer, P: PersistenceLayer<S>> Drop for Transaction<'_, S, P> {
     #[inline]
     fn drop(&mut self) {
         let state = self.anchor.state.load(Relaxed);
@@ -615,16 +1546,24 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Drop for Transaction<'d, S, P> {
     }
 }
 
-impl<'d, S: Sequencer, P: PersistenceLayer<S>> Future for Committable<'d, S, P> {
+impl<S: Sequencer, P: PersistenceLayer<S>> Future for Committable<'_, S, P> {
     type Output = Result<S::Instant, Error>;
 
     #[inline]
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         if let Some(mut transaction) = self.transaction.take() {
+            if transaction.anchor.wait_cancel(cx.waker()) {
+                // The transaction is rolled back when dropped.
+                return Poll::Ready(Err(Error::Cancelled));
+            }
             if let Some((mut io_completion, commit_instant)) = self.commit_log_io.take() {
                 match Pin::new(&mut io_completion).poll(cx) {
-                    Poll::Ready(Ok(_)) => {
+                    Poll::Ready(Ok(())) => {
                         // All done, returning the commit instant after post-processing.
+                        transaction
+                            .database
+                            .commit_watermark()
+                            .mark_durable(commit_instant);
                         transaction.post_commit(commit_instant);
                         return Poll::Ready(Ok(commit_instant));
                     }
@@ -647,6 +1586,7 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Future for Committable<'d, S, P>
                         transaction.post_commit(commit_log_io.1);
                         return Poll::Ready(Ok(commit_log_io.1));
                     }
+                    transaction.release_locks(commit_log_io.1);
                     self.transaction.replace(transaction);
                     self.commit_log_io.replace(commit_log_io);
                     cx.waker().wake_by_ref();
@@ -664,6 +1604,63 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Future for Committable<'d, S, P>
     }
 }
 
+impl<S: Sequencer> CommitHandle<S> {
+    /// Returns the current state of the transaction.
+    #[inline]
+    #[must_use]
+    pub fn state(&self) -> TransactionState<S::Instant> {
+        self.anchor.transaction_state()
+    }
+
+    /// Returns the commit instant of the transaction if the transaction has been committed.
+    #[inline]
+    #[must_use]
+    pub fn commit_instant(&self) -> Option<S::Instant> {
+        match self.state() {
+            TransactionState::Committed { commit_instant } => Some(commit_instant),
+            _ => None,
+        }
+    }
+
+    /// Waits for the transaction to be committed or rolled back, and returns the commit instant
+    /// of the transaction if it was committed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Database;
+    /// use std::path::Path;
+    ///
+    /// async {
+    ///     let database = Database::with_path(Path::new("commit_handle_wait")).await.unwrap();
+    ///     let transaction = database.transaction();
+    ///     let commit_handle = transaction.commit_handle();
+    ///     transaction.rollback();
+    ///     assert!(commit_handle.wait().await.is_none());
+    /// };
+    /// ```
+    #[inline]
+    pub async fn wait(&self) -> Option<S::Instant> {
+        poll_fn(|cx| {
+            if self.anchor.wait_eot(cx.waker().clone()).is_some() {
+                Poll::Ready(self.commit_instant())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+impl<S: Sequencer> Clone for CommitHandle<S> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            anchor: self.anchor.clone(),
+        }
+    }
+}
+
 impl From<State> for usize {
     #[inline]
     fn from(v: State) -> usize {
@@ -679,17 +1676,37 @@ impl From<State> for usize {
 
 impl<'d, S: Sequencer, P: PersistenceLayer<S>> Playback<'d, S, P> {
     /// Creates a new [`Playback`].
-    pub(crate) fn new(database: &'d Database<S, P>) -> Playback<'d, S, P> {
+    #[inline]
+    #[must_use]
+    pub fn new(database: &'d Database<S, P>) -> Playback<'d, S, P> {
+        Self::with_id(database, database.new_transaction_id())
+    }
+
+    /// Creates a new [`Playback`] of the transaction identified by the log records.
+    ///
+    /// New transactions of the [`Database`] are given greater identifiers than it.
+    #[inline]
+    #[must_use]
+    pub fn with_id(database: &'d Database<S, P>, id: ID) -> Playback<'d, S, P> {
+        database.reserve_transaction_id(id);
         Self {
             database,
             journal_anchor_map: HashMap::default(),
             submitted_journal_anchors: BTreeMap::default(),
             submitted_unbounded_journal_anchors: Vec::default(),
             xid: None,
-            anchor: ebr::Shared::new(Anchor::new()),
+            renamed_containers: Vec::default(),
+            anchor: ebr::Shared::new(Anchor::new(id, 0)),
         }
     }
 
+    /// Returns the identifier of the transaction.
+    #[inline]
+    #[must_use]
+    pub fn id(&self) -> ID {
+        self.anchor.id()
+    }
+
     /// Gets or create a [`JournalAnchor`] associated with the specified identifier.
     pub(crate) fn get_or_create_journal_anchor(
         &mut self,
@@ -717,19 +1734,54 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Playback<'d, S, P> {
         }
     }
 
+    /// Plays back the creation of the database object by the journal.
+    #[inline]
+    pub fn create(&mut self, journal_id: JournalID, object_id: u64) {
+        let journal_anchor = self.get_or_create_journal_anchor(journal_id);
+        self.database
+            .access_controller()
+            .playback_create_sync(object_id, &journal_anchor);
+        self.database.reserve_object_id(object_id);
+    }
+
+    /// Plays back the deletion of the database object by the journal.
+    #[inline]
+    pub fn delete(&mut self, journal_id: JournalID, object_id: u64) {
+        let journal_anchor = self.get_or_create_journal_anchor(journal_id);
+        self.database
+            .access_controller()
+            .playback_delete_sync(object_id, &journal_anchor);
+    }
+
+    /// Plays back a new version of a key-value pair.
+    ///
+    /// The database object of the version has to be played back by [`Playback::create`] in
+    /// advance, and the version becomes visible when the database object is.
+    #[inline]
+    pub fn write(&mut self, version: &VersionRecord<'_>) {
+        if let Some((container_id, name)) = self.database.playback_version(version) {
+            self.renamed_containers.push((None, container_id, name));
+        }
+    }
+
     /// Participates in a distributed transaction.
-    #[allow(dead_code)]
-    pub(crate) fn participate(&mut self, xid: &[u8]) {
+    #[inline]
+    pub fn participate(&mut self, xid: &[u8]) {
         debug_assert!(self.xid.is_none());
         self.xid.replace(xid.into());
     }
 
-    /// Submits a [`JournalAnchor`].
-    pub(super) fn submit_journal_anchor(&mut self, id: u64, transaction_instant: u32) {
+    /// Submits the journal at the transaction instant.
+    #[inline]
+    pub fn submit_journal(&mut self, id: JournalID, transaction_instant: u32) {
         debug_assert_ne!(transaction_instant, 0);
         if let Some(journal_anchor) = self.journal_anchor_map.remove(&id) {
             journal_anchor.set_submit_instant(transaction_instant);
             journal_anchor.submit(self.database.task_processor());
+            self.renamed_containers
+                .iter_mut()
+                .filter(|(i, _, _)| i.is_none())
+                .for_each(|(i, _, _)| *i = Some(transaction_instant));
             if transaction_instant == u32::MAX {
                 self.submitted_unbounded_journal_anchors
                     .push(journal_anchor);
@@ -742,14 +1794,16 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Playback<'d, S, P> {
         }
     }
 
-    /// Discards a [`JournalAnchor`].
-    pub(super) fn discard_journal_anchor(&mut self, id: u64) {
+    /// Discards the journal.
+    #[inline]
+    pub fn discard_journal(&mut self, id: JournalID) {
         let result = self.journal_anchor_map.remove(&id);
         debug_assert!(result.is_some());
     }
 
     /// Rewinds the [`Playback`] to the given point of time.
-    pub(crate) fn rewind(&mut self, instant: Option<NonZeroU32>) {
+    #[inline]
+    pub fn rewind(&mut self, instant: Option<NonZeroU32>) {
         debug_assert!(self.journal_anchor_map.is_empty());
 
         let rewind_to = instant.map_or(0, NonZeroU32::get);
@@ -770,28 +1824,25 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Playback<'d, S, P> {
             }
             o.remove().rollback(self.database.task_processor());
         }
+        self.renamed_containers
+            .retain(|(i, _, _)| i.is_some_and(|i| i <= rewind_to));
     }
 
     /// Prepares the [`Playback`] for commit.
-    pub(crate) fn prepare(&mut self, prepare_instant: S::Instant) {
+    #[inline]
+    pub fn prepare(&mut self, prepare_instant: S::Instant) {
         debug_assert!(self.journal_anchor_map.is_empty());
         debug_assert_eq!(self.anchor.state.load(Relaxed), State::Active.into());
 
         let _: Result<S::Instant, S::Instant> =
             self.database.sequencer().update(prepare_instant, Release);
 
-        // Safety: it is the sole writer of its own `anchor`.
-        unsafe {
-            let anchor_mut_ref = &mut *(addr_of!(*self.anchor).cast_mut());
-            anchor_mut_ref.prepare_instant = prepare_instant;
-            anchor_mut_ref
-                .state
-                .store(State::Committing.into(), Release);
-        }
+        self.anchor.prepare(prepare_instant);
     }
 
     /// Commits the [`Playback`].
-    pub(crate) fn commit(self, commit_instant: S::Instant) {
+    #[inline]
+    pub fn commit(self, commit_instant: S::Instant) {
         debug_assert!(
             self.journal_anchor_map.is_empty(),
             "{:?}",
@@ -806,11 +1857,7 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Playback<'d, S, P> {
         let _: Result<S::Instant, S::Instant> =
             self.database.sequencer().update(commit_instant, Release);
 
-        // Safety: it is the sole writer of its own `anchor`.
-        let anchor_mut_ref = unsafe { &mut *(addr_of!(*self.anchor).cast_mut()) };
-        anchor_mut_ref.commit_instant = commit_instant;
-        anchor_mut_ref.state.store(State::Committed.into(), Release);
-        self.anchor.wake_up();
+        self.anchor.commit(commit_instant);
 
         // Commit journals.
         self.submitted_journal_anchors
@@ -819,10 +1866,16 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Playback<'d, S, P> {
         self.submitted_unbounded_journal_anchors
             .into_iter()
             .for_each(|j| j.commit(self.database.task_processor()));
+        self.database.rename_containers(
+            self.renamed_containers
+                .into_iter()
+                .filter_map(|(i, container_id, name)| i.map(|_| (container_id, name))),
+        );
     }
 
     /// Rolls back the changes made by the [`Playback`].
-    pub(crate) fn rollback(mut self) {
+    #[inline]
+    pub fn rollback(mut self) {
         debug_assert!(self.journal_anchor_map.is_empty());
         debug_assert_ne!(self.anchor.state.load(Relaxed), State::Committed.into());
         debug_assert_ne!(self.anchor.state.load(Relaxed), State::RollingBack.into());
@@ -838,15 +1891,158 @@ impl<'d, S: Sequencer, P: PersistenceLayer<S>> Playback<'d, S, P> {
 }
 
 impl<S: Sequencer> Anchor<S> {
-    fn new() -> Anchor<S> {
+    fn new(id: ID, start_clock: u64) -> Anchor<S> {
+        debug_assert_eq!(id & 0b111, 0);
         Anchor {
-            state: AtomicUsize::new(0),
-            prepare_instant: S::Instant::default(),
-            commit_instant: S::Instant::default(),
+            id,
+            state: sync::AtomicUsize::new(0),
+            prepare_instant: OnceLock::new(),
+            commit_instant: OnceLock::new(),
+            release_instant: OnceLock::new(),
+            commit_dependencies: Bag::new(),
+            dependents: Mutex::default(),
+            start_clock,
+            wounded: sync::AtomicBool::new(false),
+            abort_state: sync::AtomicUsize::new(ABORTABLE),
             waiting_readers: Bag::new(),
+            cancellation_token: OnceLock::new(),
+            tag: OnceLock::new(),
+            start_time: Instant::now(),
+            num_submitted_journals: AtomicU32::new(0),
+            memory_usage: AtomicUsize::new(0),
+            priority: AtomicU8::new(0),
+            #[cfg(feature = "diagnostics")]
+            tracker: OnceLock::new(),
+        }
+    }
+
+    /// Returns the identifier of the transaction.
+    pub(super) fn id(&self) -> ID {
+        self.id
+    }
+
+    /// Returns the tag attached to the transaction.
+    pub(super) fn tag(&self) -> Option<&str> {
+        self.tag.get().map(AsRef::as_ref)
+    }
+
+    /// Records that the transaction was committed or rolled back in the anchor registry.
+    #[cfg(feature = "diagnostics")]
+    pub(super) fn record_end(&self) {
+        if let Some(tracker) = self.tracker.get() {
+            tracker.end(self.tag());
+        }
+    }
+
+    /// Creates a [`TransactionReport`] of the transaction.
+    pub(super) fn report(&self, id: ID, now: Instant) -> TransactionReport {
+        TransactionReport {
+            id,
+            tag: self.tag().map(ToString::to_string),
+            start_clock: self.start_clock,
+            age: now.saturating_duration_since(self.start_time),
+            num_submitted_journals: self.num_submitted_journals.load(Relaxed),
+            memory_usage: self.memory_usage.load(Relaxed),
+        }
+    }
+
+    /// Returns the number of submitted journals and the memory retained by the uncommitted
+    /// changes of the transaction.
+    pub(super) fn work(&self) -> (u32, usize) {
+        (
+            self.num_submitted_journals.load(Relaxed),
+            self.memory_usage.load(Relaxed),
+        )
+    }
+
+    /// Returns the priority of the transaction.
+    pub(super) fn priority(&self) -> u8 {
+        self.priority.load(Relaxed)
+    }
+
+    /// Returns the logical clock value when the transaction started.
+    pub(super) fn start_clock(&self) -> u64 {
+        self.start_clock
+    }
+
+    /// Wounds the transaction if it is active.
+    ///
+    /// The wounded transaction fails to gain access to database objects, and it cannot be
+    /// committed.
+    pub(super) fn wound(&self) {
+        if self.state.load(Acquire) == State::Active.into() && !self.wounded.swap(true, AcqRel) {
+            self.wake_up();
+        }
+    }
+
+    /// Returns `true` if the transaction was wounded.
+    pub(super) fn is_wounded(&self) -> bool {
+        self.wounded.load(Acquire)
+    }
+
+    /// Pushes a [`Waker`] to be woken up when the transaction is wounded.
+    ///
+    /// Returns `true` if the transaction is already wounded.
+    pub(super) fn wait_wound(&self, waker: Waker) -> bool {
+        self.waiting_readers.push(waker);
+        if self.is_wounded() {
+            self.wake_up();
+            true
+        } else {
+            false
         }
     }
 
+    /// Aborts the transaction if it is active.
+    ///
+    /// The aborted transaction fails to gain access to database objects, and it cannot be
+    /// committed. Returns `false` if the transaction began being prepared, in which case the
+    /// transaction may or may not be committed.
+    pub(super) fn abort(&self) -> bool {
+        if self.state.load(Acquire) != State::Active.into() {
+            return false;
+        }
+
+        // Pairs with `seal`: the transaction is either aborted before `prepare` writes anything,
+        // or it is left untouched.
+        match self
+            .abort_state
+            .compare_exchange(ABORTABLE, ABORTED, AcqRel, Acquire)
+        {
+            Ok(_) | Err(ABORTED) => {
+                self.wake_up();
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Returns `true` if the transaction was aborted.
+    pub(super) fn is_aborted(&self) -> bool {
+        self.abort_state.load(Acquire) == ABORTED
+    }
+
+    /// Makes the transaction no longer abortable before it is prepared.
+    ///
+    /// Returns `false` if the transaction was aborted.
+    fn seal(&self) -> bool {
+        self.abort_state
+            .compare_exchange(ABORTABLE, SEALED, AcqRel, Acquire)
+            .is_ok()
+    }
+
+    /// Returns the [`CancellationToken`] attached to the transaction.
+    pub(super) fn cancellation_token(&self) -> Option<CancellationToken> {
+        self.cancellation_token.get().cloned()
+    }
+
+    /// Registers the [`Waker`] to be woken up when the transaction is cancelled.
+    ///
+    /// Returns `true` if the transaction is already cancelled.
+    pub(super) fn wait_cancel(&self, waker: &Waker) -> bool {
+        self.cancellation_token.get().is_some_and(|t| t.wait(waker))
+    }
+
     /// Returns the instant when the transaction was being prepared for commit.
     pub(super) fn prepare_instant(&self) -> Option<S::Instant> {
         let state = self.state.load(Acquire);
@@ -855,7 +2051,7 @@ impl<S: Sequencer> Anchor<S> {
             || state == State::RollingBack.into()
             || state == State::RolledBack.into()
         {
-            Some(self.prepare_instant)
+            Some(self.prepare_instant.get().copied().unwrap_or_default())
         } else {
             None
         }
@@ -865,12 +2061,105 @@ impl<S: Sequencer> Anchor<S> {
     pub(super) fn eot_instant(&self) -> Option<S::Instant> {
         let state = self.state.load(Acquire);
         if state == State::Committed.into() || state == State::RolledBack.into() {
-            Some(self.commit_instant)
+            Some(self.commit_instant.get().copied().unwrap_or_default())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the [`Visibility`] of the changes made by the transaction at the instant.
+    ///
+    /// The transaction state is loaded only once, and the caller has to check whether its changes
+    /// were rolled back after this returns [`Visibility::Visible`].
+    pub(super) fn visibility(&self, instant: S::Instant) -> Visibility {
+        if let Some(eot_instant) = self.eot_instant() {
+            if eot_instant != S::Instant::default() && eot_instant <= instant {
+                return Visibility::Visible;
+            }
+        } else if let Some(prepare_instant) = self.prepare_instant() {
+            if prepare_instant == S::Instant::default() || prepare_instant < instant {
+                return Visibility::Undetermined;
+            }
+        }
+        Visibility::Invisible
+    }
+
+    /// Returns the commit instant if the transaction released its locks before its commit log
+    /// record was persisted, and the transaction is still being committed.
+    pub(super) fn release_instant(&self) -> Option<S::Instant> {
+        if self.state.load(Acquire) == State::Committing.into() {
+            self.release_instant.get().copied()
         } else {
             None
         }
     }
 
+    /// Returns `true` if the changes made by the transaction are visible at the instant to the
+    /// transaction that took over a lock released by the transaction.
+    pub(super) fn is_visible_to_dependent(&self, id: ID, instant: S::Instant) -> bool {
+        self.release_instant().is_some_and(|i| i <= instant)
+            && self
+                .dependents
+                .lock()
+                .is_ok_and(|d| d.iter().any(|a| a.id() == id))
+    }
+
+    /// Makes the dependent transaction depend on the commit of the other transaction.
+    pub(super) fn depend_on_commit(dependent: &ebr::Shared<Self>, other: &ebr::Shared<Self>) {
+        if let Ok(mut dependents) = other.dependents.lock() {
+            dependents.push(dependent.clone());
+        }
+        dependent.commit_dependencies.push(other.clone());
+    }
+
+    /// Forgets the transactions that took over the locks released by the transaction.
+    fn clear_dependents(&self) {
+        if let Ok(mut dependents) = self.dependents.lock() {
+            dependents.clear();
+        }
+    }
+
+    /// Sets the commit instant of the transaction releasing its locks.
+    fn release_locks(&self, commit_instant: S::Instant) {
+        let result = self.release_instant.set(commit_instant);
+        debug_assert!(result.is_ok());
+    }
+
+    /// Sets the prepare instant, and makes the transaction enter the committing state.
+    fn prepare(&self, prepare_instant: S::Instant) {
+        let result = self.prepare_instant.set(prepare_instant);
+        debug_assert!(result.is_ok());
+        self.state.store(State::Committing.into(), Release);
+    }
+
+    /// Sets the commit instant, makes the transaction enter the committed state, and wakes up
+    /// waiting readers.
+    fn commit(&self, commit_instant: S::Instant) {
+        let result = self.commit_instant.set(commit_instant);
+        debug_assert!(result.is_ok());
+        self.state.store(State::Committed.into(), Release);
+        self.clear_dependents();
+        self.wake_up();
+    }
+
+    /// Returns the current state of the transaction.
+    pub(super) fn transaction_state(&self) -> TransactionState<S::Instant> {
+        let state = self.state.load(Acquire);
+        if state == State::Active.into() {
+            TransactionState::Active
+        } else if state == State::Committing.into() {
+            TransactionState::Committing {
+                prepare_instant: self.prepare_instant.get().copied().unwrap_or_default(),
+            }
+        } else if state == State::Committed.into() {
+            TransactionState::Committed {
+                commit_instant: self.commit_instant.get().copied().unwrap_or_default(),
+            }
+        } else {
+            TransactionState::RolledBack
+        }
+    }
+
     /// Waiting for the transaction to be committed or rolled back.
     pub(super) fn wait_eot(&self, waker: Waker) -> Option<S::Instant> {
         self.waiting_readers.push(waker);
@@ -890,9 +2179,93 @@ impl<S: Sequencer> Anchor<S> {
     }
 }
 
+impl SerializationAnchor {
+    /// Records a read-write dependency from the reader to the writer.
+    ///
+    /// `by_reader` denotes whether the dependency was found by the reader.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SerializationFailure`] if the other transaction has been certified, and
+    /// the dependency makes it a pivot of a dangerous structure.
+    pub(super) fn depend(
+        reader: &SerializationAnchor,
+        writer: &SerializationAnchor,
+        by_reader: bool,
+    ) -> Result<(), Error> {
+        if ptr::eq(reader, writer)
+            || reader.rolled_back.load(SeqCst)
+            || writer.rolled_back.load(SeqCst)
+            || !reader.is_concurrent(writer)
+        {
+            return Ok(());
+        }
+        reader.out_conflict.store(true, SeqCst);
+        writer.in_conflict.store(true, SeqCst);
+
+        // The other transaction may have been certified before the dependency is recorded.
+        let (other, other_conflict) = if by_reader {
+            (writer, &writer.out_conflict)
+        } else {
+            (reader, &reader.in_conflict)
+        };
+        if other.prepared.load(SeqCst)
+            && other_conflict.load(SeqCst)
+            && !other.rolled_back.load(SeqCst)
+        {
+            return Err(Error::SerializationFailure);
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the transaction can be safely forgotten.
+    ///
+    /// `oldest` is the start clock of the oldest active serializable transaction.
+    pub(super) fn is_obsolete(&self, oldest: Option<u64>) -> bool {
+        if self.rolled_back.load(SeqCst) {
+            return true;
+        }
+        let end_clock = self.end_clock.load(SeqCst);
+        end_clock != u64::MAX && oldest.is_none_or(|o| end_clock <= o)
+    }
+
+    /// Creates a new [`SerializationAnchor`].
+    fn new(start_clock: u64) -> SerializationAnchor {
+        SerializationAnchor {
+            start_clock,
+            end_clock: AtomicU64::new(u64::MAX),
+            prepared: AtomicBool::new(false),
+            rolled_back: AtomicBool::new(false),
+            in_conflict: AtomicBool::new(false),
+            out_conflict: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns `true` if the lifetimes of the transactions overlap.
+    fn is_concurrent(&self, other: &SerializationAnchor) -> bool {
+        self.end_clock.load(SeqCst) > other.start_clock
+            && other.end_clock.load(SeqCst) > self.start_clock
+    }
+
+    /// Certifies that the transaction is not a pivot of a dangerous structure.
+    fn certify(&self) -> Result<(), Error> {
+        self.prepared.store(true, SeqCst);
+        if self.in_conflict.load(SeqCst) && self.out_conflict.load(SeqCst) {
+            return Err(Error::SerializationFailure);
+        }
+        Ok(())
+    }
+
+    /// Sets the end clock value.
+    fn end(&self, end_clock: u64) {
+        self.end_clock.store(end_clock, SeqCst);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Metadata;
     use static_assertions::assert_eq_size;
     use std::{path::Path, sync::Arc};
     use tokio::{fs::remove_dir_all, sync::Barrier};
@@ -963,4 +2336,445 @@ mod tests {
         drop(database);
         assert!(remove_dir_all(path).await.is_ok());
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn rewind_wake_up() {
+        const DIR: &str = "transaction_rewind_wake_up_test";
+        let path = Path::new(DIR);
+        let database = Arc::new(Database::with_path(path).await.unwrap());
+        let mut transaction = database.transaction();
+        for object_id in 0..4 {
+            let mut journal = transaction.journal();
+            assert_eq!(
+                database
+                    .access_controller()
+                    .lock(object_id, &mut journal, None)
+                    .await,
+                Ok(true)
+            );
+            assert!(journal.submit().get() > 0);
+        }
+
+        let mut task_handles = Vec::new();
+        for object_id in 1..4 {
+            let database_clone = database.clone();
+            task_handles.push(tokio::spawn(async move {
+                let transaction = database_clone.transaction();
+                let mut journal = transaction.journal();
+                let deadline = Some(Instant::now() + std::time::Duration::from_mins(1));
+                let result = database_clone
+                    .access_controller()
+                    .lock(object_id, &mut journal, deadline)
+                    .await;
+                drop(journal);
+                transaction.rollback();
+                result
+            }));
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+
+        // All the waiters are woken up when the journals are rolled back at once.
+        assert_eq!(
+            transaction.rewind(NonZeroU32::new(1)),
+            Ok(NonZeroU32::new(1))
+        );
+        for r in futures::future::join_all(task_handles).await {
+            assert_eq!(r.unwrap(), Ok(true));
+        }
+        assert_eq!(transaction.memory_usage(), 0);
+        assert!(transaction.commit().await.is_ok());
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 16)]
+    async fn submit_changes() {
+        const DIR: &str = "transaction_submit_changes_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("c".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert_eq!(Some(journal.submit()), NonZeroU32::new(1));
+        assert!(transaction.commit().await.is_ok());
+
+        let num_tasks = 16_u64;
+        let barrier = Arc::new(Barrier::new(usize::try_from(num_tasks).unwrap()));
+        let transaction = Arc::new(prolong_transaction(database.transaction()));
+        let mut task_handles = Vec::with_capacity(usize::try_from(num_tasks).unwrap());
+        for k in 0..num_tasks {
+            let barrier_clone = barrier.clone();
+            let container_clone = container.clone();
+            let transaction_clone = transaction.clone();
+            task_handles.push(tokio::spawn(async move {
+                barrier_clone.wait().await;
+                let mut journal = transaction_clone.journal();
+                let key = k.to_le_bytes();
+                assert!(container_clone
+                    .insert(&key, &key, &mut journal, None)
+                    .await
+                    .is_ok());
+                (journal.submit(), k)
+            }));
+        }
+        let mut submitted = Vec::with_capacity(task_handles.len());
+        for r in futures::future::join_all(task_handles).await {
+            submitted.push(r.unwrap());
+        }
+        submitted.sort_unstable();
+
+        let mut transaction = Arc::into_inner(transaction).unwrap();
+        let rewind_to = NonZeroU32::new(12);
+        assert_eq!(transaction.rewind(rewind_to), Ok(rewind_to));
+        let mut change_stream = database.watch(None).unwrap();
+        let commit_instant = transaction.commit().await.unwrap();
+
+        let change_batch = change_stream.next(None).await.unwrap();
+        assert_eq!(change_batch.commit_instant, commit_instant);
+        let keys: Vec<Box<[u8]>> = change_batch.changes.iter().map(|c| c.key.clone()).collect();
+        let expected: Vec<Box<[u8]>> = submitted
+            .iter()
+            .filter(|(i, _)| Some(*i) <= rewind_to)
+            .map(|(_, k)| k.to_le_bytes().into())
+            .collect();
+        assert_eq!(keys, expected);
+
+        drop(change_stream);
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn observe_commit() {
+        const DIR: &str = "transaction_observe_commit_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let transaction = database.transaction();
+        let anchor = transaction.anchor.clone();
+        let observers: Vec<_> = (0..4)
+            .map(|_| {
+                let anchor = anchor.clone();
+                std::thread::spawn(move || loop {
+                    let eot_instant = anchor.eot_instant();
+                    let prepare_instant = anchor.prepare_instant();
+                    if let Some(eot_instant) = eot_instant {
+                        assert!(prepare_instant.is_some_and(|i| i <= eot_instant));
+                        return eot_instant;
+                    }
+                })
+            })
+            .collect();
+        let committable = transaction.prepare().await.unwrap();
+        let commit_instant = committable.await.unwrap();
+        for observer in observers {
+            assert_eq!(observer.join().unwrap(), commit_instant);
+        }
+        assert_eq!(
+            anchor.prepare_instant().map(|i| i < commit_instant),
+            Some(true)
+        );
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn commit_handle() {
+        const DIR: &str = "transaction_commit_handle_test";
+        let path = Path::new(DIR);
+        let database = Arc::new(Database::with_path(path).await.unwrap());
+
+        // The commit instant is learned after the transaction is committed by another task
+        // discarding the result.
+        let transaction = prolong_transaction(database.transaction());
+        let commit_handle = transaction.commit_handle();
+        assert_eq!(commit_handle.state(), TransactionState::Active);
+        assert!(commit_handle.commit_instant().is_none());
+        let mut journal = transaction.journal();
+        assert!(database
+            .access_controller()
+            .create(1, &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(Some(journal.submit()), NonZeroU32::new(1));
+        let task = tokio::spawn(async move {
+            let _: Result<_, _> = transaction.commit().await;
+        });
+        let commit_instant = commit_handle.wait().await.unwrap();
+        assert!(task.await.is_ok());
+        assert_eq!(commit_handle.clone().commit_instant(), Some(commit_instant));
+        let snapshot = database.snapshot_at(commit_instant).unwrap();
+        assert_eq!(
+            database.access_controller().read(1, &snapshot, None).await,
+            Ok(true)
+        );
+        drop(snapshot);
+
+        // Dropping a transaction rolls it back.
+        let commit_handle = database.transaction().commit_handle();
+        assert_eq!(commit_handle.state(), TransactionState::RolledBack);
+        assert!(commit_handle.wait().await.is_none());
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn state() {
+        const DIR: &str = "transaction_state_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let access_controller = database.access_controller();
+
+        let transaction = database.transaction();
+        let id = transaction.id();
+        let mut journal = transaction.journal();
+        assert!(access_controller
+            .create(1, &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.transaction_state(), TransactionState::Active);
+        assert_eq!(Some(journal.submit()), NonZeroU32::new(1));
+        assert_eq!(transaction.state(), TransactionState::Active);
+        let prepare_instant = database.sequencer().now(Relaxed);
+        let committable = transaction.prepare().await.unwrap();
+        assert_eq!(
+            access_controller.owners(1).await,
+            vec![(id, TransactionState::Committing { prepare_instant })]
+        );
+        let commit_instant = committable.await.unwrap();
+        assert!(access_controller
+            .owners(1)
+            .await
+            .iter()
+            .all(|o| *o == (id, TransactionState::Committed { commit_instant })));
+
+        let transaction = database.transaction();
+        let id = transaction.id();
+        let mut journal = transaction.journal();
+        assert!(access_controller
+            .create(2, &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(Some(journal.submit()), NonZeroU32::new(1));
+        transaction.rollback();
+        assert!(access_controller
+            .owners(2)
+            .await
+            .iter()
+            .all(|o| *o == (id, TransactionState::RolledBack)));
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn cancel() {
+        const DIR: &str = "transaction_cancel_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let access_controller = database.access_controller();
+
+        let transaction_1 = database.transaction();
+        let mut journal_1 = transaction_1.journal();
+        assert!(journal_1.create(&[1], None).await.is_ok());
+
+        // The waiting journal observes the cancellation.
+        let token = CancellationToken::default();
+        let mut transaction_2 = database.transaction();
+        assert!(transaction_2.set_cancellation_token(token.clone()).is_ok());
+        let mut journal_2 = transaction_2.journal();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        let (result, ()) = tokio::join!(
+            access_controller.lock(1, &mut journal_2, Some(deadline)),
+            async {
+                tokio::task::yield_now().await;
+                token.cancel();
+            }
+        );
+        assert_eq!(result, Err(Error::Cancelled));
+        drop(journal_2);
+
+        // The cancelled transaction cannot be committed.
+        assert_eq!(transaction_2.commit().await, Err(Error::Cancelled));
+
+        assert_eq!(journal_1.submit().get(), 1);
+        assert!(transaction_1.commit().await.is_ok());
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn wait_on_single_thread() {
+        const DIR: &str = "transaction_wait_on_single_thread_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let access_controller = database.access_controller();
+
+        let transaction_1 = database.transaction();
+        let mut journal_1 = transaction_1.journal();
+        assert!(journal_1.create(&[1], None).await.is_ok());
+        assert_eq!(journal_1.submit().get(), 1);
+
+        // The waiting journal must yield the only worker thread for the owner to be committed.
+        let transaction_2 = database.transaction();
+        let mut journal_2 = transaction_2.journal();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        let (result, commit_result) = tokio::join!(
+            access_controller.lock(1, &mut journal_2, Some(deadline)),
+            transaction_1.commit()
+        );
+        assert_eq!(result, Ok(true));
+        assert!(commit_result.is_ok());
+        assert_eq!(journal_2.submit().get(), 1);
+        assert!(transaction_2.commit().await.is_ok());
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn tag() {
+        const DIR: &str = "transaction_tag_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+        let access_controller = database.access_controller();
+
+        let mut transaction_1 = database.transaction();
+        assert!(transaction_1.set_tag("writer").is_ok());
+        let mut journal_1 = transaction_1.journal();
+        assert!(journal_1.create(&[1], None).await.is_ok());
+        assert_eq!(journal_1.submit().get(), 1);
+
+        // The lock wait is attributed to the tag of the waiting transaction.
+        let mut transaction_2 = database.transaction();
+        assert!(transaction_2.set_tag("reader").is_ok());
+        let mut journal_2 = transaction_2.journal();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        let (result, commit_result) = tokio::join!(
+            access_controller.lock(1, &mut journal_2, Some(deadline)),
+            transaction_1.commit()
+        );
+        assert_eq!(result, Ok(true));
+        assert!(commit_result.is_ok());
+        drop(journal_2);
+        transaction_2.rollback();
+        database.transaction().rollback();
+
+        let statistics = database.statistics();
+        assert_eq!(statistics.transactions_started, 3);
+        assert_eq!(statistics.transactions_rolled_back, 2);
+        let tagged_statistics = database.tagged_statistics();
+        assert_eq!(tagged_statistics.len(), 2);
+        let writer = tagged_statistics["writer"];
+        assert_eq!(writer.transactions_started, 1);
+        assert_eq!(writer.transactions_committed, 1);
+        assert_eq!(writer.lock_waits, 0);
+        let reader = tagged_statistics["reader"];
+        assert_eq!(reader.transactions_started, 1);
+        assert_eq!(reader.transactions_rolled_back, 1);
+        assert_eq!(reader.lock_waits, 1);
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn memory_limit() {
+        const DIR: &str = "transaction_memory_limit_test";
+        let path = Path::new(DIR);
+        let database = Database::with_path(path).await.unwrap();
+
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        let container = database
+            .create_container("test".to_string(), Metadata::default(), &mut journal, None)
+            .await
+            .unwrap();
+        assert_eq!(journal.submit().get(), 1);
+        assert!(transaction.commit().await.is_ok());
+
+        let mut transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert!(container
+            .insert(b"1", &[0; 64], &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(journal.submit().get(), 1);
+        let usage = transaction.memory_usage();
+        assert!(usage > 128);
+
+        transaction.set_memory_limit(Some(usage * 2));
+        let mut journal = transaction.journal();
+        assert!(container
+            .insert(b"2", &[0; 64], &mut journal, None)
+            .await
+            .is_ok());
+        assert_eq!(
+            container.insert(b"3", &[0; 64], &mut journal, None).await,
+            Err(Error::OutOfMemoryBudget)
+        );
+        drop(journal);
+        assert_eq!(transaction.memory_usage(), usage);
+
+        // Rewinding the transaction releases the memory.
+        assert_eq!(transaction.rewind(None), Ok(None));
+        assert_eq!(transaction.memory_usage(), 0);
+        transaction.rollback();
+
+        // The default budget of the database applies to new transactions.
+        database.set_transaction_memory_limit(Some(usage / 2));
+        let transaction = database.transaction();
+        let mut journal = transaction.journal();
+        assert_eq!(
+            container.insert(b"4", &[0; 64], &mut journal, None).await,
+            Err(Error::OutOfMemoryBudget)
+        );
+        drop(journal);
+        transaction.rollback();
+
+        drop(database);
+        assert!(remove_dir_all(path).await.is_ok());
+    }
+
+    #[cfg(sap_tsf_loom)]
+    #[test]
+    fn visibility_protocol() {
+        use crate::MonotonicU64;
+
+        loom::model(|| {
+            let sequencer = Arc::new(MonotonicU64::default());
+            let anchor = Arc::new(Anchor::<MonotonicU64>::new(0));
+            let committer = {
+                let (sequencer, anchor) = (sequencer.clone(), anchor.clone());
+                loom::thread::spawn(move || {
+                    anchor.prepare(sequencer.now(Relaxed));
+                    let commit_instant = sequencer.advance(Release);
+                    anchor.commit(commit_instant);
+                    commit_instant
+                })
+            };
+
+            // `Snapshot` reads the clock in the same way when tracking the instant.
+            let snapshot = sequencer.now(Acquire);
+            let visibility = anchor.visibility(snapshot);
+            let commit_instant = committer.join().unwrap();
+            if snapshot >= commit_instant {
+                assert_ne!(visibility, Visibility::Invisible);
+            } else {
+                assert_ne!(visibility, Visibility::Visible);
+            }
+            assert_eq!(anchor.visibility(snapshot), {
+                if snapshot >= commit_instant {
+                    Visibility::Visible
+                } else {
+                    Visibility::Invisible
+                }
+            });
+        });
+    }
 }
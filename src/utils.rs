@@ -11,6 +11,15 @@ use std::ops::Deref;
 use std::sync::{Condvar, Mutex};
 use std::thread::{available_parallelism, current, ThreadId};
 
+/// [`Instant`] is a measurement of a monotonically nondecreasing clock.
+///
+/// It is [`std::time::Instant`] except on `wasm32` where the standard library has no clock, and
+/// the clock of the host, e.g., `performance.now()` of the browser, is used.
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+pub use web_time::Instant;
+
 /// A non-cryptographic [`Hasher`] for integer values.
 #[derive(Clone, Copy, Debug)]
 pub struct IntHasher(ArrayOrU64);
@@ -0,0 +1,163 @@
+// SPDX-FileCopyrightText: 2023 Changgyoo Park <wvwwvwwv@me.com>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! [`Watchdog`] reports long-running transactions.
+
+use super::TransactionID;
+use std::fmt::{self, Debug};
+use std::time::Duration;
+
+/// [`Watchdog`] reports transactions that have been active for too long or that retain too many
+/// uncommitted changes.
+///
+/// Long-running transactions prevent versions of key-value pairs from being reclaimed, and they
+/// are the most common cause of degraded performance of multi-version databases. A [`Watchdog`]
+/// is installed through [`Database::set_watchdog`](super::Database::set_watchdog), and the
+/// background thread of the [`Database`](super::Database) periodically checks active
+/// transactions against it; each offending transaction is reported only once.
+///
+/// Reports are passed to the reporter, and emitted as `tracing` events if the `tracing` feature
+/// is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use sap_tsf::Watchdog;
+/// use std::time::Duration;
+///
+/// let watchdog = Watchdog::new(Duration::from_secs(10))
+///     .with_memory_threshold(1 << 20)
+///     .with_reporter(|report| println!("{report:?}"));
+/// assert_eq!(watchdog.age_threshold(), Duration::from_secs(10));
+/// assert_eq!(watchdog.memory_threshold(), Some(1 << 20));
+/// ```
+pub struct Watchdog {
+    /// Transactions active longer than this are reported.
+    age_threshold: Duration,
+
+    /// Transactions retaining more memory than this in bytes are reported.
+    memory_threshold: Option<usize>,
+
+    /// The function to call with each report.
+    reporter: Option<Box<Reporter>>,
+}
+
+/// The type of functions receiving [`TransactionReport`] instances.
+type Reporter = dyn Fn(&TransactionReport) + Send + Sync;
+
+/// [`TransactionReport`] describes a transaction reported by a [`Watchdog`] or
+/// [`Database::active_transactions`](super::Database::active_transactions).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransactionReport {
+    /// The identifier of the transaction.
+    pub id: TransactionID,
+
+    /// The tag attached to the transaction.
+    pub tag: Option<String>,
+
+    /// The logical clock value when the transaction started.
+    pub start_clock: u64,
+
+    /// The time elapsed since the transaction started.
+    pub age: Duration,
+
+    /// The number of [`Journal`](super::Journal) instances submitted to the transaction.
+    pub num_submitted_journals: u32,
+
+    /// Memory retained by the uncommitted changes of the transaction in bytes.
+    pub memory_usage: usize,
+}
+
+impl Watchdog {
+    /// Creates a new [`Watchdog`] that reports transactions active longer than `age_threshold`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sap_tsf::Watchdog;
+    /// use std::time::Duration;
+    ///
+    /// let watchdog = Watchdog::new(Duration::from_mins(1));
+    /// assert!(watchdog.memory_threshold().is_none());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn new(age_threshold: Duration) -> Watchdog {
+        Watchdog {
+            age_threshold,
+            memory_threshold: None,
+            reporter: None,
+        }
+    }
+
+    /// Additionally reports transactions retaining more than `memory_threshold` bytes of
+    /// uncommitted changes.
+    #[inline]
+    #[must_use]
+    pub fn with_memory_threshold(mut self, memory_threshold: usize) -> Watchdog {
+        self.memory_threshold.replace(memory_threshold);
+        self
+    }
+
+    /// Sets the function to call with each [`TransactionReport`].
+    ///
+    /// The function is called by the background thread of the database, therefore it must not
+    /// block.
+    #[inline]
+    #[must_use]
+    pub fn with_reporter<F: Fn(&TransactionReport) + Send + Sync + 'static>(
+        mut self,
+        reporter: F,
+    ) -> Watchdog {
+        self.reporter.replace(Box::new(reporter));
+        self
+    }
+
+    /// Returns the age threshold.
+    #[inline]
+    #[must_use]
+    pub fn age_threshold(&self) -> Duration {
+        self.age_threshold
+    }
+
+    /// Returns the memory threshold.
+    #[inline]
+    #[must_use]
+    pub fn memory_threshold(&self) -> Option<usize> {
+        self.memory_threshold
+    }
+
+    /// Returns `true` if the transaction has to be reported.
+    pub(super) fn is_overdue(&self, age: Duration, memory_usage: usize) -> bool {
+        age >= self.age_threshold || self.memory_threshold.is_some_and(|m| memory_usage > m)
+    }
+
+    /// Reports the transaction.
+    pub(super) fn report(&self, report: &TransactionReport) {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(
+            transaction_id = report.id,
+            tag = report.tag,
+            start_clock = report.start_clock,
+            ?report.age,
+            num_submitted_journals = report.num_submitted_journals,
+            memory_usage = report.memory_usage,
+            "long-running transaction"
+        );
+        if let Some(reporter) = self.reporter.as_ref() {
+            reporter(report);
+        }
+    }
+}
+
+impl Debug for Watchdog {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watchdog")
+            .field("age_threshold", &self.age_threshold)
+            .field("memory_threshold", &self.memory_threshold)
+            .field("reporter", &self.reporter.is_some())
+            .finish()
+    }
+}